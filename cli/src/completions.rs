@@ -0,0 +1,211 @@
+use std::io::{self, Write};
+
+use clap::{Arg, Command, ValueHint};
+
+pub(crate) const SHELLS: [&str; 3] = ["bash", "fish", "zsh"];
+
+/// Write a completion script for the given shell to the given writer. The
+/// script completes subcommands, their options, and the values of options
+/// that have possible values or expect a directory.
+pub(crate) fn generate(shell: &str, command: &Command, writer: &mut impl Write) -> io::Result<()> {
+    match shell {
+        "bash" => generate_bash(command, writer),
+        "fish" => generate_fish(command, writer),
+        "zsh" => {
+            // zsh can run bash completion functions using bashcompinit.
+            writeln!(writer, "autoload -U +X bashcompinit && bashcompinit")?;
+            generate_bash(command, writer)
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Unsupported shell \"{shell}\""),
+        )),
+    }
+}
+
+fn long_options(command: &Command) -> impl Iterator<Item = (&str, &Arg)> {
+    command
+        .get_arguments()
+        .filter_map(|arg| arg.get_long().map(|long| (long, arg)))
+        .filter(|(long, _)| *long != "help" && *long != "version")
+}
+
+fn possible_values(arg: &Arg) -> Vec<String> {
+    arg.get_possible_values()
+        .iter()
+        .map(|value| value.get_name().to_owned())
+        .collect()
+}
+
+fn generate_bash(command: &Command, writer: &mut impl Write) -> io::Result<()> {
+    let name = command.get_name();
+    let function = format!("_{}", name.replace('-', "_"));
+    let subcommands: Vec<&str> = command.get_subcommands().map(Command::get_name).collect();
+
+    writeln!(writer, "{function}() {{")?;
+    writeln!(writer, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"")?;
+    writeln!(writer, "    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"")?;
+    writeln!(writer, "    if [[ $COMP_CWORD -eq 1 ]]; then")?;
+    writeln!(
+        writer,
+        "        COMPREPLY=($(compgen -W \"{} --help --version\" -- \"$cur\"))",
+        subcommands.join(" ")
+    )?;
+    writeln!(writer, "        return")?;
+    writeln!(writer, "    fi")?;
+
+    writeln!(writer, "    case \"${{COMP_WORDS[1]}}:$prev\" in")?;
+    for subcommand in command.get_subcommands() {
+        for (long, arg) in long_options(subcommand) {
+            let values = possible_values(arg);
+            let completion = if !values.is_empty() {
+                format!("-W \"{}\"", values.join(" "))
+            } else if arg.get_value_hint() == ValueHint::DirPath {
+                "-d".to_owned()
+            } else {
+                continue;
+            };
+
+            writeln!(writer, "        {}:--{long})", subcommand.get_name())?;
+            writeln!(
+                writer,
+                "            COMPREPLY=($(compgen {completion} -- \"$cur\"))"
+            )?;
+            writeln!(writer, "            return;;")?;
+        }
+    }
+    writeln!(writer, "    esac")?;
+
+    writeln!(writer, "    case \"${{COMP_WORDS[1]}}\" in")?;
+    for subcommand in command.get_subcommands() {
+        let options: Vec<String> = long_options(subcommand)
+            .map(|(long, _)| format!("--{long}"))
+            .collect();
+        writeln!(writer, "        {})", subcommand.get_name())?;
+        writeln!(
+            writer,
+            "            COMPREPLY=($(compgen -W \"{} --help\" -- \"$cur\"));;",
+            options.join(" ")
+        )?;
+    }
+    writeln!(writer, "    esac")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer, "complete -F {function} {name}")
+}
+
+fn generate_fish(command: &Command, writer: &mut impl Write) -> io::Result<()> {
+    let name = command.get_name();
+
+    for subcommand in command.get_subcommands() {
+        writeln!(
+            writer,
+            "complete -c {name} -f -n \"__fish_use_subcommand\" -a \"{}\" -d \"{}\"",
+            subcommand.get_name(),
+            fish_escape(
+                &subcommand
+                    .get_about()
+                    .map(ToString::to_string)
+                    .unwrap_or_default()
+            )
+        )?;
+    }
+
+    for subcommand in command.get_subcommands() {
+        let condition = format!("__fish_seen_subcommand_from {}", subcommand.get_name());
+        for (long, arg) in long_options(subcommand) {
+            let values = possible_values(arg);
+            let arguments = if !values.is_empty() {
+                format!(" -r -f -a \"{}\"", values.join(" "))
+            } else if arg.get_value_hint() == ValueHint::DirPath {
+                " -r -f -a \"(__fish_complete_directories)\"".to_owned()
+            } else if arg.get_action().takes_values() {
+                " -r".to_owned()
+            } else {
+                String::new()
+            };
+
+            writeln!(
+                writer,
+                "complete -c {name} -n \"{condition}\" -l {long}{arguments} -d \"{}\"",
+                fish_escape(&arg.get_help().map(ToString::to_string).unwrap_or_default())
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> Command {
+        Command::new("tool")
+            .subcommand(
+                Command::new("run")
+                    .about("Run \"it\"")
+                    .arg(
+                        Arg::new("mode")
+                            .long("mode")
+                            .value_parser(["fast", "slow"])
+                            .help("The mode"),
+                    )
+                    .arg(
+                        Arg::new("dir")
+                            .long("dir")
+                            .value_hint(ValueHint::DirPath)
+                            .help("A directory"),
+                    ),
+            )
+            .subcommand(Command::new("stop"))
+    }
+
+    fn generate_string(shell: &str) -> String {
+        let mut output = Vec::new();
+        generate(shell, &command(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn generate_bash_should_complete_subcommands_options_and_values() {
+        let script = generate_string("bash");
+
+        assert!(script.contains("compgen -W \"run stop --help --version\""));
+        assert!(script.contains("run:--mode)\n            COMPREPLY=($(compgen -W \"fast slow\""));
+        assert!(script.contains("run:--dir)\n            COMPREPLY=($(compgen -d"));
+        assert!(script.contains("compgen -W \"--mode --dir --help\""));
+        assert!(script.ends_with("complete -F _tool tool\n"));
+    }
+
+    #[test]
+    fn generate_zsh_should_wrap_the_bash_script() {
+        let script = generate_string("zsh");
+
+        assert!(script.starts_with("autoload -U +X bashcompinit && bashcompinit\n"));
+        assert!(script.ends_with(&generate_string("bash")));
+    }
+
+    #[test]
+    fn generate_fish_should_complete_subcommands_options_and_values() {
+        let script = generate_string("fish");
+
+        assert!(script.contains(
+            "complete -c tool -f -n \"__fish_use_subcommand\" -a \"run\" -d \"Run \\\"it\\\"\"\n"
+        ));
+        assert!(script.contains(
+            "complete -c tool -n \"__fish_seen_subcommand_from run\" -l mode -r -f -a \"fast slow\" -d \"The mode\"\n"
+        ));
+        assert!(script.contains(
+            "complete -c tool -n \"__fish_seen_subcommand_from run\" -l dir -r -f -a \"(__fish_complete_directories)\" -d \"A directory\"\n"
+        ));
+    }
+
+    #[test]
+    fn generate_should_error_for_an_unsupported_shell() {
+        assert!(generate("powershell", &command(), &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,397 @@
+// Deny some rustc lints that are allow-by-default.
+#![deny(
+    ambiguous_negative_literals,
+    impl_trait_overcaptures,
+    let_underscore_drop,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    non_ascii_idents,
+    redundant_imports,
+    redundant_lifetimes,
+    trivial_casts,
+    trivial_numeric_casts,
+    unit_bindings,
+    unsafe_code
+)]
+#![deny(clippy::pedantic)]
+// Selectively deny clippy restriction lints.
+#![deny(
+    clippy::allow_attributes,
+    clippy::as_conversions,
+    clippy::dbg_macro,
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::panic,
+    clippy::str_to_string,
+    clippy::string_slice,
+    clippy::unwrap_used
+)]
+#![cfg_attr(
+    test,
+    allow(
+        clippy::assertions_on_result_states,
+        clippy::indexing_slicing,
+        clippy::missing_asserts_for_indexing,
+        clippy::panic,
+        clippy::unwrap_used,
+    )
+)]
+
+mod completions;
+mod repl;
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::builder::{EnumValueParser, PossibleValue};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command, ValueEnum, ValueHint};
+use loot_condition_interpreter::{DataSnapshot, Expression, GameType, Server, State};
+
+use crate::repl::Repl;
+
+#[derive(Clone, Copy, Debug)]
+struct Game(GameType);
+
+impl ValueEnum for Game {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Game(GameType::OpenMW),
+            Game(GameType::Morrowind),
+            Game(GameType::Oblivion),
+            Game(GameType::Skyrim),
+            Game(GameType::SkyrimSE),
+            Game(GameType::SkyrimVR),
+            Game(GameType::Fallout3),
+            Game(GameType::FalloutNV),
+            Game(GameType::Fallout4),
+            Game(GameType::Fallout4VR),
+            Game(GameType::Starfield),
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let name = match self.0 {
+            GameType::OpenMW => "openmw",
+            GameType::Morrowind => "morrowind",
+            GameType::Oblivion => "oblivion",
+            GameType::Skyrim => "skyrim",
+            GameType::SkyrimSE => "skyrimse",
+            GameType::SkyrimVR => "skyrimvr",
+            GameType::Fallout3 => "fallout3",
+            GameType::FalloutNV => "falloutnv",
+            GameType::Fallout4 => "fallout4",
+            GameType::Fallout4VR => "fallout4vr",
+            GameType::Starfield => "starfield",
+            _ => return None,
+        };
+
+        Some(PossibleValue::new(name))
+    }
+}
+
+fn state_args() -> [Arg; 5] {
+    [
+        Arg::new("game")
+            .long("game")
+            .required(true)
+            .ignore_case(true)
+            .value_parser(EnumValueParser::<Game>::new())
+            .help("The game that conditions are evaluated for"),
+        Arg::new("data-path")
+            .long("data-path")
+            .required_unless_present("data-snapshot")
+            .value_parser(value_parser!(PathBuf))
+            .value_hint(ValueHint::DirPath)
+            .help("The game's Data folder"),
+        Arg::new("data-snapshot")
+            .long("data-snapshot")
+            .conflicts_with("data-path")
+            .value_parser(value_parser!(PathBuf))
+            .value_hint(ValueHint::FilePath)
+            .help("A zip or 7z archive of the game's Data folder to use instead of the folder"),
+        Arg::new("additional-data-path")
+            .long("additional-data-path")
+            .action(ArgAction::Append)
+            .value_parser(value_parser!(PathBuf))
+            .value_hint(ValueHint::DirPath)
+            .help("Another folder to look for game files in, before the Data folder"),
+        Arg::new("active-plugin")
+            .long("active-plugin")
+            .action(ArgAction::Append)
+            .help("The filename of an active plugin"),
+    ]
+}
+
+fn condition_arg() -> Arg {
+    Arg::new("condition")
+        .required(true)
+        .help("The condition string")
+}
+
+fn cli() -> Command {
+    Command::new("lci")
+        .about("Parse and evaluate LOOT metadata condition strings.")
+        .version(env!("CARGO_PKG_VERSION"))
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("parse")
+                .about("Check that a condition is valid and print its normalised form")
+                .arg(condition_arg()),
+        )
+        .subcommand(
+            Command::new("eval")
+                .about("Evaluate a condition and print its result")
+                .args(state_args())
+                .arg(condition_arg()),
+        )
+        .subcommand(
+            Command::new("serve")
+                .about("Answer JSON-RPC requests read line by line from stdin")
+                .args(state_args()),
+        )
+        .subcommand(
+            Command::new("repl")
+                .about("Interactively evaluate conditions typed on stdin")
+                .args(state_args()),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Print a shell completion script")
+                .arg(
+                    Arg::new("shell")
+                        .required(true)
+                        .value_parser(completions::SHELLS)
+                        .help("The shell to generate the script for"),
+                ),
+        )
+}
+
+/// Get the game's data path. If a data snapshot was given, it's extracted and
+/// also returned, as it must be kept until the state is no longer used.
+fn data_path(matches: &ArgMatches) -> Result<(PathBuf, Option<DataSnapshot>), String> {
+    if let Some(archive_path) = matches.get_one::<PathBuf>("data-snapshot") {
+        let snapshot = DataSnapshot::extract(archive_path).map_err(|e| e.to_string())?;
+        Ok((snapshot.data_path().to_path_buf(), Some(snapshot)))
+    } else {
+        let data_path = matches
+            .get_one::<PathBuf>("data-path")
+            .ok_or("No data path given")?;
+        Ok((data_path.clone(), None))
+    }
+}
+
+/// Get the directories that the state looks for files in, in the order that
+/// it looks in them.
+fn search_paths(matches: &ArgMatches, data_path: &Path) -> Vec<PathBuf> {
+    matches
+        .get_many::<PathBuf>("additional-data-path")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .chain([data_path.to_path_buf()])
+        .collect()
+}
+
+fn state(matches: &ArgMatches, data_path: &Path) -> Result<State, String> {
+    let Game(game_type) = *matches.get_one::<Game>("game").ok_or("No game given")?;
+
+    let mut state = State::new(game_type, data_path.to_path_buf());
+
+    if let Some(paths) = matches.get_many::<PathBuf>("additional-data-path") {
+        state.set_additional_data_paths(paths.cloned().collect());
+    }
+
+    if let Some(plugins) = matches.get_many::<String>("active-plugin") {
+        let plugins: Vec<&String> = plugins.collect();
+        state.set_active_plugins(&plugins);
+    }
+
+    Ok(state)
+}
+
+fn parse_condition(matches: &ArgMatches) -> Result<Expression, String> {
+    matches
+        .get_one::<String>("condition")
+        .ok_or_else(|| "No condition given".to_owned())?
+        .parse()
+        .map_err(|e: loot_condition_interpreter::Error| e.to_string())
+}
+
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    match matches.subcommand() {
+        Some(("parse", matches)) => {
+            println!("{}", parse_condition(matches)?);
+        }
+        Some(("eval", matches)) => {
+            let (data_path, _snapshot) = data_path(matches)?;
+            let state = state(matches, &data_path)?;
+            let expression = parse_condition(matches)?;
+            println!("{}", expression.eval(&state).map_err(|e| e.to_string())?);
+        }
+        Some(("serve", matches)) => {
+            let (data_path, _snapshot) = data_path(matches)?;
+            let mut server = Server::new(state(matches, &data_path)?);
+            server
+                .serve(io::stdin().lock(), io::stdout().lock())
+                .map_err(|e| e.to_string())?;
+        }
+        Some(("repl", matches)) => {
+            let (data_path, _snapshot) = data_path(matches)?;
+            let mut repl = Repl::new(
+                state(matches, &data_path)?,
+                search_paths(matches, &data_path),
+            );
+            repl.run(io::stdin().lock(), io::stdout().lock())
+                .map_err(|e| e.to_string())?;
+        }
+        Some(("completions", matches)) => {
+            let shell = matches.get_one::<String>("shell").ok_or("No shell given")?;
+            completions::generate(shell, &cli(), &mut io::stdout().lock())
+                .map_err(|e| e.to_string())?;
+        }
+        _ => return Err("Unrecognised command".to_owned()),
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let matches = cli().get_matches();
+
+    match run(&matches) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_should_be_valid() {
+        cli().debug_assert();
+    }
+
+    fn game_arg(game: &str) -> Result<GameType, clap::Error> {
+        let matches =
+            cli().try_get_matches_from(["lci", "serve", "--game", game, "--data-path", "."])?;
+        let (_, matches) = matches.subcommand().unwrap();
+
+        Ok(matches.get_one::<Game>("game").unwrap().0)
+    }
+
+    #[test]
+    fn game_arg_should_ignore_case() {
+        assert_eq!(GameType::SkyrimSE, game_arg("SkyrimSE").unwrap());
+        assert_eq!(GameType::OpenMW, game_arg("openmw").unwrap());
+    }
+
+    #[test]
+    fn game_arg_should_error_for_an_unknown_game() {
+        assert!(game_arg("daggerfall").is_err());
+    }
+
+    #[test]
+    fn search_paths_should_put_additional_data_paths_first() {
+        let matches = cli()
+            .try_get_matches_from([
+                "lci",
+                "repl",
+                "--game",
+                "oblivion",
+                "--data-path",
+                "Data",
+                "--additional-data-path",
+                "a",
+                "--additional-data-path",
+                "b",
+            ])
+            .unwrap();
+        let (_, matches) = matches.subcommand().unwrap();
+
+        assert_eq!(
+            vec![
+                PathBuf::from("a"),
+                PathBuf::from("b"),
+                PathBuf::from("Data")
+            ],
+            search_paths(matches, Path::new("Data"))
+        );
+    }
+
+    #[test]
+    fn data_path_should_be_required_unless_a_data_snapshot_is_given() {
+        let args = ["lci", "eval", "--game", "oblivion", "true"];
+        assert!(cli().try_get_matches_from(args).is_err());
+
+        let args = [
+            "lci",
+            "eval",
+            "--game",
+            "oblivion",
+            "--data-path",
+            "Data",
+            "--data-snapshot",
+            "Data.zip",
+            "file(\"Blank.esp\")",
+        ];
+        assert!(cli().try_get_matches_from(args).is_err());
+    }
+
+    #[test]
+    fn data_path_should_be_the_extracted_data_snapshot() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let archive_path = tmp_dir.path().join("Data.zip");
+        std::fs::write(
+            &archive_path,
+            b"PK\x05\x06\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        )
+        .unwrap();
+
+        let matches = cli()
+            .try_get_matches_from([
+                "lci".as_ref(),
+                "eval".as_ref(),
+                "--game".as_ref(),
+                "oblivion".as_ref(),
+                "--data-snapshot".as_ref(),
+                archive_path.as_os_str(),
+                "file(\"Blank.esp\")".as_ref(),
+            ])
+            .unwrap();
+        let (_, matches) = matches.subcommand().unwrap();
+
+        let (data_path, snapshot) = data_path(matches).unwrap();
+
+        assert_eq!(snapshot.unwrap().data_path(), data_path);
+    }
+
+    #[test]
+    fn state_should_use_the_given_args() {
+        let matches = cli()
+            .try_get_matches_from([
+                "lci",
+                "eval",
+                "--game",
+                "oblivion",
+                "--data-path",
+                ".",
+                "--active-plugin",
+                "Blank.esp",
+                "active(\"Blank.esp\")",
+            ])
+            .unwrap();
+        let (_, matches) = matches.subcommand().unwrap();
+
+        let (data_path, _) = data_path(matches).unwrap();
+        let state = state(matches, &data_path).unwrap();
+        let expression = parse_condition(matches).unwrap();
+
+        assert!(expression.eval(&state).unwrap());
+    }
+}
@@ -0,0 +1,295 @@
+use std::fs::read_dir;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use loot_condition_interpreter::{Expression, State};
+
+const FUNCTION_NAMES: &[&str] = &[
+    "active",
+    "active_count",
+    "archive_contains",
+    "archive_regex",
+    "archive_version",
+    "author_contains",
+    "checksum",
+    "checksum_manifest",
+    "checksum_sha256",
+    "count",
+    "description_contains",
+    "description_contains_text",
+    "description_regex",
+    "file",
+    "file_content_contains",
+    "file_glob",
+    "file_mtime",
+    "file_size",
+    "filename_version",
+    "header_version",
+    "ini_value",
+    "is_executable",
+    "is_light_plugin",
+    "is_master",
+    "is_medium_plugin",
+    "is_newer_than",
+    "is_valid_plugin",
+    "is_x64",
+    "is_x86",
+    "many",
+    "many_active",
+    "masters_contain",
+    "product_version",
+    "readable",
+    "record_count",
+    "registry_value",
+    "version",
+    "version_cmp",
+    "writable",
+];
+
+/// Functions that take no arguments, so are completed with their parentheses.
+const NULLARY_FUNCTION_NAMES: &[&str] = &["is_linux", "is_macos", "is_windows", "is_wine"];
+
+const KEYWORDS: &[&str] = &["and", "not", "or"];
+
+const HELP: &str = "Enter a condition to evaluate it against the loaded state.
+End a line with a tab to list completions for it instead of evaluating it.
+
+Commands:
+  :clear  Clear the condition and CRC caches
+  :help   Show this help
+  :quit   Exit the REPL";
+
+/// An interactive loop that evaluates each line of input as a condition.
+///
+/// Input is read a line at a time, so the terminal's own line editing is
+/// used. A tab can't trigger completion until the line is submitted, so a
+/// line that ends in a tab lists completions for the text before it.
+#[derive(Debug)]
+pub(crate) struct Repl {
+    state: State,
+    /// The directories that file paths are completed from, in the order that
+    /// they're searched when evaluating conditions.
+    search_paths: Vec<PathBuf>,
+}
+
+impl Repl {
+    pub(crate) fn new(state: State, search_paths: Vec<PathBuf>) -> Self {
+        Repl {
+            state,
+            search_paths,
+        }
+    }
+
+    pub(crate) fn run<R: BufRead, W: Write>(
+        &mut self,
+        mut reader: R,
+        mut writer: W,
+    ) -> io::Result<()> {
+        let mut line = String::new();
+        loop {
+            write!(writer, "> ")?;
+            writer.flush()?;
+
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                writeln!(writer)?;
+                return Ok(());
+            }
+
+            let line = line.trim_end_matches(['\r', '\n']);
+
+            if let Some(partial) = line.strip_suffix('\t') {
+                for candidate in self.complete(partial) {
+                    writeln!(writer, "{candidate}")?;
+                }
+                continue;
+            }
+
+            match line.trim() {
+                "" => {}
+                ":quit" | ":q" => return Ok(()),
+                ":help" => writeln!(writer, "{HELP}")?,
+                ":clear" => {
+                    if let Err(e) = self.state.clear_condition_cache() {
+                        writeln!(writer, "Error: {e}")?;
+                    }
+                }
+                condition => match condition
+                    .parse::<Expression>()
+                    .and_then(|e| e.eval(&self.state))
+                {
+                    Ok(result) => writeln!(writer, "{result}")?,
+                    Err(e) => writeln!(writer, "Error: {e}")?,
+                },
+            }
+        }
+    }
+
+    /// Get the possible completions of the given partial input, each as the
+    /// whole input with its last token completed.
+    pub(crate) fn complete(&self, input: &str) -> Vec<String> {
+        // An odd number of quotes means that the input ends inside a string,
+        // which is assumed to be a path.
+        if input.matches('"').count() % 2 == 1 {
+            let (head, partial_path) = input.rsplit_once('"').unwrap_or(("", input));
+            return self
+                .complete_path(partial_path)
+                .into_iter()
+                .map(|path| format!("{head}\"{path}"))
+                .collect();
+        }
+
+        let word_start = input
+            .rfind(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .map_or(0, |i| i + 1);
+        let (head, partial_word) = input.split_at(word_start);
+
+        let functions = FUNCTION_NAMES
+            .iter()
+            .filter(|name| name.starts_with(partial_word))
+            .map(|name| format!("{head}{name}(\""));
+        let nullary_functions = NULLARY_FUNCTION_NAMES
+            .iter()
+            .filter(|name| name.starts_with(partial_word))
+            .map(|name| format!("{head}{name}()"));
+        let keywords = KEYWORDS
+            .iter()
+            .filter(|keyword| keyword.starts_with(partial_word))
+            .map(|keyword| format!("{head}{keyword} "));
+
+        keywords.chain(functions).chain(nullary_functions).collect()
+    }
+
+    fn complete_path(&self, partial_path: &str) -> Vec<String> {
+        let (parent, partial_name) = partial_path.rsplit_once('/').unwrap_or(("", partial_path));
+        let partial_name = partial_name.to_lowercase();
+
+        let mut candidates: Vec<String> = self
+            .search_paths
+            .iter()
+            .flat_map(|search_path| entry_names(&search_path.join(parent)))
+            .filter(|name| name.to_lowercase().starts_with(&partial_name))
+            .map(|name| {
+                if parent.is_empty() {
+                    name
+                } else {
+                    format!("{parent}/{name}")
+                }
+            })
+            .collect();
+
+        candidates.sort_unstable_by_key(|candidate| candidate.to_lowercase());
+        candidates.dedup_by_key(|candidate| candidate.to_lowercase());
+
+        candidates
+    }
+}
+
+/// Get the names of the entries in the given directory, with a trailing slash
+/// on the names of subdirectories. Unreadable directories and entries with
+/// non-UTF-8 names are ignored.
+fn entry_names(directory: &Path) -> Vec<String> {
+    let Ok(entries) = read_dir(directory) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if entry.path().is_dir() {
+                Some(format!("{name}/"))
+            } else {
+                Some(name)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir, File};
+
+    use loot_condition_interpreter::GameType;
+
+    use super::*;
+
+    fn repl(search_paths: Vec<PathBuf>) -> Repl {
+        Repl::new(
+            State::new(GameType::Oblivion, PathBuf::from(".")),
+            search_paths,
+        )
+    }
+
+    #[test]
+    fn complete_should_complete_function_names_and_keywords() {
+        let repl = repl(Vec::new());
+
+        assert_eq!(
+            vec![
+                "file(\"a\") and ",
+                "file(\"a\") active(\"",
+                "file(\"a\") active_count(\"",
+                "file(\"a\") archive_contains(\"",
+                "file(\"a\") archive_regex(\"",
+                "file(\"a\") archive_version(\"",
+                "file(\"a\") author_contains(\""
+            ],
+            repl.complete("file(\"a\") a")
+        );
+        assert_eq!(vec!["many(\"", "many_active(\""], repl.complete("man"));
+    }
+
+    #[test]
+    fn complete_should_complete_functions_without_arguments_with_their_parentheses() {
+        let repl = repl(Vec::new());
+
+        assert_eq!(
+            vec!["not is_windows()", "not is_wine()"],
+            repl.complete("not is_w")
+        );
+    }
+
+    #[test]
+    fn complete_should_complete_paths_from_all_search_paths() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let other_path = tmp_dir.path().join("Other");
+        create_dir(&data_path).unwrap();
+        create_dir(&other_path).unwrap();
+        create_dir(data_path.join("Meshes")).unwrap();
+        File::create(data_path.join("Blank.esm")).unwrap();
+        File::create(data_path.join("Blank.esp")).unwrap();
+        File::create(other_path.join("blank.esp")).unwrap();
+        File::create(data_path.join("Meshes").join("a.nif")).unwrap();
+
+        let repl = repl(vec![other_path, data_path]);
+
+        assert_eq!(
+            vec!["file(\"Blank.esm", "file(\"blank.esp"],
+            repl.complete("file(\"bl")
+        );
+        assert_eq!(vec!["file(\"Meshes/"], repl.complete("file(\"m"));
+        assert_eq!(vec!["file(\"Meshes/a.nif"], repl.complete("file(\"Meshes/"));
+    }
+
+    #[test]
+    fn run_should_evaluate_each_line_and_list_completions_for_lines_ending_in_a_tab() {
+        let mut repl = repl(Vec::new());
+        let input = "file(\"Cargo.toml\")\n\nfoo\nversio\t\n:quit\nfile(\"missing\")\n";
+        let mut output = Vec::new();
+
+        repl.run(input.as_bytes(), &mut output).unwrap();
+
+        assert_eq!(
+            concat!(
+                "> true\n",
+                "> ",
+                "> Error: The parser did not consume the following input: \"foo\"\n",
+                "> version(\"\nversion_cmp(\"\n",
+                "> ",
+            ),
+            String::from_utf8(output).unwrap()
+        );
+    }
+}
@@ -41,6 +41,38 @@ pub static LCI_ERROR_TEXT_ENCODE_FAIL: c_int = -7;
 #[no_mangle]
 pub static LCI_ERROR_INTERNAL_LOGIC_ERROR: c_int = -8;
 
+/// A condition referenced a path outside of the game's data paths that was not allowed.
+#[no_mangle]
+pub static LCI_ERROR_PATH_TRAVERSAL: c_int = -9;
+
+/// A function examined the maximum number of directory entries allowed.
+#[no_mangle]
+pub static LCI_ERROR_DIRECTORY_ENTRY_LIMIT: c_int = -10;
+
+/// A function needed to read from the filesystem, but filesystem access was disabled.
+#[no_mangle]
+pub static LCI_ERROR_FILESYSTEM_ACCESS_DISABLED: c_int = -11;
+
+/// A condition referenced a variable that has no value.
+#[no_mangle]
+pub static LCI_ERROR_UNBOUND_VARIABLE: c_int = -12;
+
+/// A filesystem operation kept failing with transient errors until it ran out of retries.
+#[no_mangle]
+pub static LCI_ERROR_RETRIES_EXHAUSTED: c_int = -13;
+
+/// Evaluation was cancelled.
+#[no_mangle]
+pub static LCI_ERROR_CANCELLED: c_int = -14;
+
+/// Evaluation did not finish before its deadline.
+#[no_mangle]
+pub static LCI_ERROR_DEADLINE_EXCEEDED: c_int = -15;
+
+/// A condition referenced a path starting with a drive letter, but no Wine path mapper was set to translate it.
+#[no_mangle]
+pub static LCI_ERROR_UNMAPPED_DRIVE_PATH: c_int = -16;
+
 /// Game code for The Elder Scrolls III: Morrowind.
 #[no_mangle]
 pub static LCI_GAME_MORROWIND: c_int = 8;
@@ -7,10 +7,13 @@ use loot_condition_interpreter::{Error, GameType};
 
 use super::ERROR_MESSAGE;
 use crate::constants::{
-    LCI_ERROR_INTERNAL_LOGIC_ERROR, LCI_ERROR_INVALID_ARGS, LCI_ERROR_IO_ERROR,
-    LCI_ERROR_PARSING_ERROR, LCI_ERROR_PE_PARSING_ERROR, LCI_GAME_FALLOUT_3, LCI_GAME_FALLOUT_4,
-    LCI_GAME_FALLOUT_4_VR, LCI_GAME_FALLOUT_NV, LCI_GAME_MORROWIND, LCI_GAME_OBLIVION,
-    LCI_GAME_OPENMW, LCI_GAME_SKYRIM, LCI_GAME_SKYRIM_SE, LCI_GAME_SKYRIM_VR, LCI_GAME_STARFIELD,
+    LCI_ERROR_CANCELLED, LCI_ERROR_DEADLINE_EXCEEDED, LCI_ERROR_DIRECTORY_ENTRY_LIMIT,
+    LCI_ERROR_FILESYSTEM_ACCESS_DISABLED, LCI_ERROR_INTERNAL_LOGIC_ERROR, LCI_ERROR_INVALID_ARGS,
+    LCI_ERROR_IO_ERROR, LCI_ERROR_PARSING_ERROR, LCI_ERROR_PATH_TRAVERSAL,
+    LCI_ERROR_PE_PARSING_ERROR, LCI_ERROR_RETRIES_EXHAUSTED, LCI_ERROR_UNBOUND_VARIABLE,
+    LCI_ERROR_UNMAPPED_DRIVE_PATH, LCI_GAME_FALLOUT_3, LCI_GAME_FALLOUT_4, LCI_GAME_FALLOUT_4_VR,
+    LCI_GAME_FALLOUT_NV, LCI_GAME_MORROWIND, LCI_GAME_OBLIVION, LCI_GAME_OPENMW, LCI_GAME_SKYRIM,
+    LCI_GAME_SKYRIM_SE, LCI_GAME_SKYRIM_VR, LCI_GAME_STARFIELD,
 };
 use crate::state::{plugin_crc, plugin_version};
 
@@ -35,6 +38,14 @@ fn map_error(err: &Error) -> c_int {
         }
         Error::PeParsingError(_, _) => LCI_ERROR_PE_PARSING_ERROR,
         Error::IoError(_, _) => LCI_ERROR_IO_ERROR,
+        Error::PathTraversal(_) => LCI_ERROR_PATH_TRAVERSAL,
+        Error::UnmappedDrivePath(_) => LCI_ERROR_UNMAPPED_DRIVE_PATH,
+        Error::DirectoryEntryLimitExceeded(_, _) => LCI_ERROR_DIRECTORY_ENTRY_LIMIT,
+        Error::FilesystemAccessDisabled(_) => LCI_ERROR_FILESYSTEM_ACCESS_DISABLED,
+        Error::UnboundVariable(_) => LCI_ERROR_UNBOUND_VARIABLE,
+        Error::RetriesExhausted(_, _, _) => LCI_ERROR_RETRIES_EXHAUSTED,
+        Error::Cancelled => LCI_ERROR_CANCELLED,
+        Error::DeadlineExceeded => LCI_ERROR_DEADLINE_EXCEEDED,
         _ => LCI_ERROR_INTERNAL_LOGIC_ERROR,
     }
 }
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use crate::error::ParsingErrorKind;
+use crate::{Error, Expression, ParseOptions};
+
+/// Named conditions that can be referenced in other conditions as `@name`,
+/// e.g. `@sse_engine_ok and file("Plugin.esp")`. References are expanded when
+/// parsing, so a parsed expression behaves as if the alias's condition had
+/// been written in parentheses in its place.
+///
+/// Aliases are given to the parser using
+/// [ParseOptions::with_aliases](crate::ParseOptions::with_aliases). A
+/// reference to an undefined alias is a parsing error.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConditionAliases(HashMap<String, Expression>);
+
+impl ConditionAliases {
+    pub fn new() -> Self {
+        ConditionAliases::default()
+    }
+
+    /// Parse the given condition and define it as an alias with the given
+    /// name, replacing any existing alias with that name. The name may only
+    /// contain ASCII letters, digits and underscores. The condition may
+    /// reference aliases that have already been defined.
+    pub fn define(
+        &mut self,
+        name: &str,
+        condition: &str,
+        options: &ParseOptions,
+    ) -> Result<(), Error> {
+        if !is_valid_name(name) {
+            return Err(Error::ParsingError(
+                name.to_owned(),
+                ParsingErrorKind::InvalidAliasDefinition,
+            ));
+        }
+
+        let options = options.clone().with_aliases(self.clone());
+        let mut expression = Expression::parse_with_options(condition, &options)?;
+
+        // The spans refer to the alias's condition, not the conditions that
+        // will reference it.
+        expression.set_spans(&(0..0));
+
+        self.0.insert(name.to_owned(), expression);
+        Ok(())
+    }
+
+    /// Define aliases from the given text, which has one definition per line
+    /// in the form `@name := condition`. Blank lines are ignored. Definitions
+    /// are processed in order, so each may reference aliases defined on
+    /// earlier lines. If a line is invalid, the aliases defined before it are
+    /// kept.
+    pub fn define_all(&mut self, definitions: &str, options: &ParseOptions) -> Result<(), Error> {
+        for line in definitions.lines().map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some((name, condition)) = line
+                .strip_prefix('@')
+                .and_then(|line| line.split_once(":="))
+            else {
+                return Err(Error::ParsingError(
+                    line.to_owned(),
+                    ParsingErrorKind::InvalidAliasDefinition,
+                ));
+            };
+
+            self.define(name.trim(), condition.trim(), options)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Expression> {
+        self.0.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+pub(crate) fn is_alias_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(is_alias_name_char)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn define_should_parse_the_condition() {
+        let mut aliases = ConditionAliases::new();
+
+        aliases
+            .define("a", "file(\"Cargo.toml\")", &ParseOptions::default())
+            .unwrap();
+
+        assert_eq!(
+            Some(&Expression::from_str("file(\"Cargo.toml\")").unwrap()),
+            aliases.get("a")
+        );
+    }
+
+    #[test]
+    fn define_should_error_if_the_name_is_invalid() {
+        let mut aliases = ConditionAliases::new();
+
+        for name in ["", "a b", "a-b", "@a"] {
+            assert!(matches!(
+                aliases.define(name, "file(\"a\")", &ParseOptions::default()),
+                Err(Error::ParsingError(
+                    _,
+                    ParsingErrorKind::InvalidAliasDefinition
+                ))
+            ));
+        }
+        assert!(aliases.is_empty());
+    }
+
+    #[test]
+    fn define_all_should_define_each_line_in_order() {
+        let mut aliases = ConditionAliases::new();
+
+        aliases
+            .define_all(
+                "@a := file(\"a\")\n\n  @b_2:=not @a or active(\"b.esp\")\n",
+                &ParseOptions::default(),
+            )
+            .unwrap();
+
+        assert_eq!(2, aliases.len());
+        assert_eq!(
+            "not (file(\"a\")) or active(\"b.esp\")",
+            aliases.get("b_2").unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn define_all_should_error_if_a_line_is_not_a_definition() {
+        let mut aliases = ConditionAliases::new();
+
+        let result = aliases.define_all(
+            "@a := file(\"a\")\nb := file(\"b\")",
+            &ParseOptions::default(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(Error::ParsingError(
+                _,
+                ParsingErrorKind::InvalidAliasDefinition
+            ))
+        ));
+        assert!(aliases.get("a").is_some());
+    }
+
+    #[test]
+    fn define_all_should_error_if_an_alias_is_used_before_it_is_defined() {
+        let mut aliases = ConditionAliases::new();
+
+        let result = aliases.define_all("@a := @b\n@b := file(\"b\")", &ParseOptions::default());
+
+        assert!(matches!(
+            result,
+            Err(Error::ParsingError(_, ParsingErrorKind::UnknownAlias(name))) if name == "b"
+        ));
+    }
+}
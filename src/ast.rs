@@ -0,0 +1,331 @@
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::function::Function;
+use crate::{CompoundCondition, Condition, Expression};
+
+/// One of the conditions joined by `and` in a [CompoundCondition]: either a
+/// function or a parenthesised expression, which may be negated using `not`.
+#[derive(Clone, Copy, Debug)]
+pub enum Clause<'a> {
+    Function(FunctionCall<'a>),
+    NotFunction(FunctionCall<'a>),
+    Expression(&'a Expression),
+    NotExpression(&'a Expression),
+}
+
+impl<'a> Clause<'a> {
+    pub(crate) fn new(condition: &'a Condition) -> Self {
+        match condition {
+            Condition::Function(function, span) => {
+                Clause::Function(FunctionCall { function, span })
+            }
+            Condition::InvertedFunction(function, span) => {
+                Clause::NotFunction(FunctionCall { function, span })
+            }
+            Condition::Expression(expression) => Clause::Expression(expression),
+            Condition::InvertedExpression(expression) => Clause::NotExpression(expression),
+        }
+    }
+
+    /// Check if the clause's result is negated using `not`.
+    pub fn is_negated(&self) -> bool {
+        matches!(self, Clause::NotFunction(_) | Clause::NotExpression(_))
+    }
+}
+
+impl fmt::Display for Clause<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Clause::Function(function) => write!(f, "{function}"),
+            Clause::NotFunction(function) => write!(f, "not {function}"),
+            Clause::Expression(expression) => write!(f, "({expression})"),
+            Clause::NotExpression(expression) => write!(f, "not ({expression})"),
+        }
+    }
+}
+
+/// A function in a parsed expression, along with where it was written in the
+/// condition string that the expression was parsed from.
+#[derive(Clone, Copy, Debug)]
+pub struct FunctionCall<'a> {
+    function: &'a Function,
+    span: &'a Range<usize>,
+}
+
+impl FunctionCall<'_> {
+    /// The name that the function is called by, e.g. `file` or
+    /// `is_master`. Different forms of the same function have the same name,
+    /// e.g. `file("a.esp")` and `file("a/.*\.esp")` are both called `file`.
+    pub fn name(&self) -> &'static str {
+        self.function.name()
+    }
+
+    /// The byte range of the function in the condition string that the
+    /// expression was parsed from.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The filesystem paths that the function checks, as they were written.
+    /// If the function matches filenames against a regex, the path is the
+    /// directory containing the files that are matched, or `.` if no
+    /// directory was given. Plugins that are only checked for being active
+    /// are not included, as they're only compared against plugin names.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.function.paths()
+    }
+}
+
+impl fmt::Display for FunctionCall<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.function)
+    }
+}
+
+/// A filesystem path that an expression may read, as it was written in the
+/// condition string. Relative paths are relative to the game's data paths,
+/// and any variables in them have not been substituted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReferencedPath<'a> {
+    /// A path that is read directly.
+    Literal(&'a Path),
+    /// The entries in a directory with names that match a regex, which is
+    /// matched case-insensitively against the whole name. The depth is how
+    /// many levels of subdirectories below the directory are also searched,
+    /// and is 0 if only the directory itself is searched.
+    Pattern {
+        directory: &'a Path,
+        regex: &'a str,
+        depth: u32,
+    },
+}
+
+/// Traverses a parsed expression. Each method is called when its part of the
+/// expression is reached, and by default visits that part's children in the
+/// order they were written using the corresponding `walk_*` function, so
+/// implementations only need to override the methods for the parts they're
+/// interested in. An overriding method can call the `walk_*` function itself
+/// to continue into the part's children, or not call it to skip them.
+pub trait Visitor {
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+
+    fn visit_compound_condition(&mut self, compound_condition: &CompoundCondition) {
+        walk_compound_condition(self, compound_condition);
+    }
+
+    fn visit_clause(&mut self, clause: Clause<'_>) {
+        walk_clause(self, clause);
+    }
+
+    fn visit_function(&mut self, _function: FunctionCall<'_>) {}
+}
+
+/// Visit each of the expression's compound conditions.
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    for compound_condition in expression.compound_conditions() {
+        visitor.visit_compound_condition(compound_condition);
+    }
+}
+
+/// Visit each of the compound condition's clauses.
+pub fn walk_compound_condition<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    compound_condition: &CompoundCondition,
+) {
+    for clause in compound_condition.clauses() {
+        visitor.visit_clause(clause);
+    }
+}
+
+/// Visit the clause's function or expression.
+pub fn walk_clause<V: Visitor + ?Sized>(visitor: &mut V, clause: Clause<'_>) {
+    match clause {
+        Clause::Function(function) | Clause::NotFunction(function) => {
+            visitor.visit_function(function);
+        }
+        Clause::Expression(expression) | Clause::NotExpression(expression) => {
+            visitor.visit_expression(expression);
+        }
+    }
+}
+
+pub(crate) fn referenced_paths(expression: &Expression) -> Vec<ReferencedPath<'_>> {
+    let mut paths = Vec::new();
+    collect_referenced_paths(expression, &mut paths);
+    paths
+}
+
+fn collect_referenced_paths<'a>(expression: &'a Expression, paths: &mut Vec<ReferencedPath<'a>>) {
+    for clause in expression
+        .compound_conditions()
+        .iter()
+        .flat_map(CompoundCondition::clauses)
+    {
+        match clause {
+            Clause::Function(call) | Clause::NotFunction(call) => {
+                for path in call.function.referenced_paths() {
+                    if !paths.contains(&path) {
+                        paths.push(path);
+                    }
+                }
+            }
+            Clause::Expression(expression) | Clause::NotExpression(expression) => {
+                collect_referenced_paths(expression, paths);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+    }
+
+    impl Visitor for Recorder {
+        fn visit_expression(&mut self, expression: &Expression) {
+            self.events.push(format!("expression {expression}"));
+            walk_expression(self, expression);
+        }
+
+        fn visit_clause(&mut self, clause: Clause<'_>) {
+            if clause.is_negated() {
+                self.events.push("not".into());
+            }
+            walk_clause(self, clause);
+        }
+
+        fn visit_function(&mut self, function: FunctionCall<'_>) {
+            self.events
+                .push(format!("{} {:?}", function.name(), function.span()));
+        }
+    }
+
+    #[test]
+    fn walk_expression_should_visit_everything_in_the_order_it_was_written() {
+        let expression =
+            Expression::from_str("file(\"a\") or not (active(\"b\") and not is_master(\"c\"))")
+                .unwrap();
+
+        let mut recorder = Recorder::default();
+        recorder.visit_expression(&expression);
+
+        assert_eq!(
+            vec![
+                "expression file(\"a\") or not (active(\"b\") and not is_master(\"c\"))",
+                "file 0..9",
+                "not",
+                "expression active(\"b\") and not is_master(\"c\")",
+                "active 18..29",
+                "not",
+                "is_master 38..52",
+            ],
+            recorder.events
+        );
+    }
+
+    #[test]
+    fn visitor_should_be_able_to_collect_paths() {
+        struct Paths(Vec<String>);
+
+        impl Visitor for Paths {
+            fn visit_function(&mut self, function: FunctionCall<'_>) {
+                self.0
+                    .extend(function.paths().map(|p| p.display().to_string()));
+            }
+        }
+
+        let expression = Expression::from_str(
+            "(file(\"a.esp\") or many(\"meshes/.*\\.nif\")) and is_newer_than(\"b\", \"c\") and active(\"d.esp\")",
+        )
+        .unwrap();
+
+        let mut paths = Paths(Vec::new());
+        paths.visit_expression(&expression);
+
+        assert_eq!(vec!["a.esp", "meshes", "b", "c"], paths.0);
+    }
+
+    #[test]
+    fn referenced_paths_should_distinguish_literal_paths_from_patterns() {
+        let expression = Expression::from_str(
+            "file(\"a.esp\") or (many(\"meshes/.*\\.nif\") and not file(\"textures/.*\\.dds\", 2)) or file_glob(\"b\", \"*.esp\")",
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                ReferencedPath::Literal(Path::new("a.esp")),
+                ReferencedPath::Pattern {
+                    directory: Path::new("meshes"),
+                    regex: ".*\\.nif",
+                    depth: 0,
+                },
+                ReferencedPath::Pattern {
+                    directory: Path::new("textures"),
+                    regex: ".*\\.dds",
+                    depth: 2,
+                },
+                ReferencedPath::Pattern {
+                    directory: Path::new("b"),
+                    regex: ".*\\.esp",
+                    depth: 0,
+                },
+            ],
+            expression.referenced_paths()
+        );
+    }
+
+    #[test]
+    fn referenced_paths_should_not_include_duplicates_or_active_plugins() {
+        let expression = Expression::from_str(
+            "is_newer_than(\"a.esp\", \"b.esp\") and active(\"c.esp\") or checksum(\"a.esp\", DEADBEEF)",
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                ReferencedPath::Literal(Path::new("a.esp")),
+                ReferencedPath::Literal(Path::new("b.esp")),
+            ],
+            expression.referenced_paths()
+        );
+    }
+
+    #[test]
+    fn clause_display_should_match_how_it_was_written() {
+        let expression = Expression::from_str("not file(\"a\") and not (file(\"b\"))").unwrap();
+
+        let clauses: Vec<_> = expression.compound_conditions()[0]
+            .clauses()
+            .map(|c| c.to_string())
+            .collect();
+
+        assert_eq!(vec!["not file(\"a\")", "not (file(\"b\"))"], clauses);
+    }
+
+    #[test]
+    fn function_call_name_should_be_the_same_for_all_forms_of_a_function() {
+        let expression =
+            Expression::from_str("file(\"a\") and file(\"b/.*\") and file(\"c/.*\", 2)").unwrap();
+
+        let names: Vec<_> = expression.compound_conditions()[0]
+            .clauses()
+            .filter_map(|c| match c {
+                Clause::Function(f) => Some(f.name()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(vec!["file", "file", "file"], names);
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::prefetch::collect_functions;
+use crate::{Error, Expression, State};
+
+/// The outcome of evaluating one expression in a batch.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BatchOutcome {
+    Evaluated(bool),
+    Failed(Error),
+    /// The batch's time budget ran out before the expression was evaluated.
+    NotEvaluated,
+}
+
+impl BatchOutcome {
+    pub fn is_evaluated(&self) -> bool {
+        !matches!(self, BatchOutcome::NotEvaluated)
+    }
+}
+
+/// Evaluate the given expressions in order, stopping once the given time
+/// budget has been used up. The outcomes are returned in the same order as the
+/// expressions, and any expressions that were not evaluated in time have an
+/// outcome of [BatchOutcome::NotEvaluated].
+///
+/// The budget is checked before each expression is evaluated, so an
+/// expression that is already being evaluated when the budget runs out is
+/// allowed to finish.
+pub fn eval_batch<'a>(
+    expressions: impl IntoIterator<Item = &'a Expression>,
+    state: &State,
+    budget: Duration,
+) -> Vec<BatchOutcome> {
+    let start = Instant::now();
+
+    expressions
+        .into_iter()
+        .map(|expression| {
+            if start.elapsed() >= budget {
+                BatchOutcome::NotEvaluated
+            } else {
+                match expression.eval(state) {
+                    Ok(result) => BatchOutcome::Evaluated(result),
+                    Err(e) => BatchOutcome::Failed(e),
+                }
+            }
+        })
+        .collect()
+}
+
+/// Evaluate all the given expressions, first reading the metadata of every
+/// distinct path that they reference and the entries of every directory that
+/// they read, so that each is read once and the reads are done together
+/// instead of being interleaved with evaluation. The results are returned in
+/// the same order as the expressions.
+///
+/// Errors encountered while reading are ignored, as they'll be encountered
+/// again while evaluating the expressions that need the data. As with
+/// [prefetch](crate::prefetch), data is read for every function, including
+/// those that evaluation then skips because an earlier function already
+/// decided the result.
+// Functions are hashed using their regexes' strings, which can't change.
+#[cfg_attr(feature = "regex", expect(clippy::mutable_key_type))]
+pub fn evaluate_all(expressions: &[Expression], state: &State) -> Vec<Result<bool, Error>> {
+    let mut seen = HashSet::new();
+    let mut functions = Vec::new();
+    for expression in expressions {
+        collect_functions(expression, &mut seen, &mut functions);
+    }
+
+    for function in functions {
+        // Any error will be returned when the function is evaluated.
+        let _result = function.cache_file_system_data(state);
+    }
+
+    expressions.iter().map(|e| e.eval(state)).collect()
+}
+
+/// Evaluate the given expressions in parallel on rayon's thread pool,
+/// stopping once the given time budget has been used up. The outcomes are
+/// returned in the same order as the expressions, as for [eval_batch], but as
+/// expressions aren't evaluated in order, which expressions are not evaluated
+/// once the budget runs out isn't predictable.
+#[cfg(feature = "parallel")]
+pub fn eval_batch_parallel(
+    expressions: &[Expression],
+    state: &State,
+    budget: Duration,
+) -> Vec<BatchOutcome> {
+    use rayon::prelude::*;
+
+    let start = Instant::now();
+
+    expressions
+        .par_iter()
+        .map(|expression| {
+            if start.elapsed() >= budget {
+                BatchOutcome::NotEvaluated
+            } else {
+                match expression.eval(state) {
+                    Ok(result) => BatchOutcome::Evaluated(result),
+                    Err(e) => BatchOutcome::Failed(e),
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+
+    use crate::{DirEntry, FileSystem, GameType, Metadata, ReadSeek, StdFileSystem};
+
+    use super::*;
+
+    fn expressions() -> Vec<Expression> {
+        ["file(\"Cargo.toml\")", "file(\"missing\")"]
+            .iter()
+            .map(|s| Expression::from_str(s).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn eval_batch_should_evaluate_all_expressions_within_the_budget() {
+        let state = State::new(GameType::Oblivion, PathBuf::from("."));
+
+        let outcomes = eval_batch(&expressions(), &state, Duration::from_mins(1));
+
+        assert!(matches!(
+            outcomes.as_slice(),
+            [
+                BatchOutcome::Evaluated(true),
+                BatchOutcome::Evaluated(false)
+            ]
+        ));
+    }
+
+    #[test]
+    fn eval_batch_should_not_evaluate_expressions_once_the_budget_is_used_up() {
+        let state = State::new(GameType::Oblivion, PathBuf::from("."));
+
+        let outcomes = eval_batch(&expressions(), &state, Duration::ZERO);
+
+        assert_eq!(2, outcomes.len());
+        assert!(outcomes.iter().all(|o| !o.is_evaluated()));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn eval_batch_parallel_should_return_outcomes_in_the_order_of_the_expressions() {
+        let state = State::new(GameType::Oblivion, PathBuf::from("."));
+
+        let outcomes = eval_batch_parallel(&expressions(), &state, Duration::from_mins(1));
+
+        assert!(matches!(
+            outcomes.as_slice(),
+            [
+                BatchOutcome::Evaluated(true),
+                BatchOutcome::Evaluated(false)
+            ]
+        ));
+    }
+
+    #[derive(Debug, Default)]
+    struct OperationRecorder(Mutex<Vec<(&'static str, PathBuf)>>);
+
+    impl OperationRecorder {
+        fn record(&self, operation: &'static str, path: &Path) {
+            self.0.lock().unwrap().push((operation, path.to_path_buf()));
+        }
+    }
+
+    impl FileSystem for OperationRecorder {
+        fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+            self.record("metadata", path);
+            StdFileSystem.metadata(path)
+        }
+
+        fn read_dir(
+            &self,
+            path: &Path,
+        ) -> io::Result<Box<dyn Iterator<Item = io::Result<DirEntry>> + '_>> {
+            self.record("read_dir", path);
+            StdFileSystem.read_dir(path)
+        }
+
+        fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek>> {
+            self.record("open", path);
+            StdFileSystem.open(path)
+        }
+    }
+
+    #[test]
+    fn evaluate_all_should_evaluate_every_expression_reading_each_path_once() {
+        let recorder = Arc::new(OperationRecorder::default());
+        let mut state = State::new(GameType::Oblivion, PathBuf::from("."));
+        state.set_file_system(Arc::<OperationRecorder>::clone(&recorder));
+        let expressions: Vec<_> = [
+            "file(\"Cargo.toml\") or file(\"missing\")",
+            "file(\"Cargo.toml\") and file(\"src/.*\\.rs\")",
+            "not file(\"missing\") and file(\"src/.*\\.rs\")",
+            "file(\"missing\")",
+        ]
+        .iter()
+        .map(|s| Expression::from_str(s).unwrap())
+        .collect();
+
+        let results: Vec<_> = evaluate_all(&expressions, &state)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(vec![true, true, true, false], results);
+
+        let operations = recorder.0.lock().unwrap();
+        let distinct: HashSet<_> = operations.iter().collect();
+        assert_eq!(distinct.len(), operations.len(), "{operations:?}");
+    }
+
+    #[test]
+    fn eval_batch_should_record_evaluation_errors() {
+        let mut state = State::new(GameType::Oblivion, PathBuf::from("."));
+        state.set_path_traversal_allowlist(Some(Vec::new()));
+        let expression = Expression::from_str("file(\"../Cargo.toml\")").unwrap();
+
+        let outcomes = eval_batch([&expression], &state, Duration::from_mins(1));
+
+        assert!(matches!(
+            outcomes.as_slice(),
+            [BatchOutcome::Failed(Error::PathTraversal(_))]
+        ));
+    }
+}
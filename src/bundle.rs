@@ -0,0 +1,552 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::str;
+
+use crate::function::Function;
+use crate::{CompoundCondition, Condition, Error, Expression, ParseOptions};
+
+const MAGIC: &[u8; 4] = b"LCIB";
+const FORMAT_VERSION: u32 = 1;
+
+/// The deepest nesting of parenthesised expressions that will be read from a
+/// bundle, to avoid exhausting the stack when reading corrupt data.
+const MAX_NESTING_DEPTH: usize = 100;
+
+const FUNCTION_TAG: u8 = 0;
+const INVERTED_FUNCTION_TAG: u8 = 1;
+const EXPRESSION_TAG: u8 = 2;
+const INVERTED_EXPRESSION_TAG: u8 = 3;
+
+/// A set of parsed condition strings that can be serialized into a compact
+/// bundle and loaded again, e.g. so that a masterlist's conditions can be
+/// compiled once and loaded at startup without parsing every string.
+///
+/// In the serialized form, functions and expressions that appear in more than
+/// one condition are only stored once, so each distinct function (including
+/// its regex) is only parsed once when the bundle is loaded. Compiled regex
+/// programs can't be serialized, so regexes are compiled again when loading.
+/// Each condition also has a cost annotation, which is the
+/// [total](crate::EvaluationCost::total) of its estimated evaluation cost.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConditionBundle {
+    conditions: BTreeMap<String, BundledCondition>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BundledCondition {
+    expression: Expression,
+    cost: u32,
+}
+
+impl ConditionBundle {
+    /// Parse the given condition strings using the given options and bundle
+    /// the results. Duplicate strings are only parsed once.
+    pub fn compile<T: AsRef<str>>(conditions: &[T], options: &ParseOptions) -> Result<Self, Error> {
+        let mut bundle = ConditionBundle::default();
+
+        for condition in conditions {
+            let condition = condition.as_ref();
+            if bundle.conditions.contains_key(condition) {
+                continue;
+            }
+
+            let expression = Expression::parse_with_options(condition, options)?;
+            let cost = expression.estimate_cost().total();
+
+            bundle
+                .conditions
+                .insert(condition.to_owned(), BundledCondition { expression, cost });
+        }
+
+        Ok(bundle)
+    }
+
+    /// Load a bundle that was serialized using [ConditionBundle::to_bytes].
+    /// The bundle's functions are parsed using the given options, so the same
+    /// limits apply as when compiling a bundle. Expressions nested more than
+    /// 100 levels deep are treated as invalid.
+    pub fn from_bytes(bytes: &[u8], options: &ParseOptions) -> Result<Self, Error> {
+        let mut reader = Reader { bytes };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid("unrecognised signature"));
+        }
+
+        let version = reader.u32()?;
+        if version != FORMAT_VERSION {
+            return Err(Error::InvalidBundle(format!(
+                "unsupported format version {version}"
+            )));
+        }
+
+        let function_count = reader.usize()?;
+        let mut functions = Vec::new();
+        for _ in 0..function_count {
+            functions.push(parse_function(reader.str()?, options)?);
+        }
+
+        let expression_count = reader.usize()?;
+        let mut expressions = Vec::new();
+        for _ in 0..expression_count {
+            let cost = reader.u32()?;
+            let length = reader.usize()?;
+            let mut tree = Reader {
+                bytes: reader.take(length)?,
+            };
+
+            let expression = tree.expression(&functions, 0)?;
+            if !tree.bytes.is_empty() {
+                return Err(invalid("trailing data after an expression"));
+            }
+
+            expressions.push(BundledCondition { expression, cost });
+        }
+
+        let condition_count = reader.usize()?;
+        let mut bundle = ConditionBundle::default();
+        for _ in 0..condition_count {
+            let condition = reader.str()?;
+            let mut bundled = expressions
+                .get(reader.usize()?)
+                .ok_or_else(|| invalid("a condition refers to an unknown expression"))?
+                .clone();
+
+            let span_count = reader.usize()?;
+            let mut spans = Vec::new();
+            for _ in 0..span_count {
+                spans.push(reader.usize()?..reader.usize()?);
+            }
+
+            let mut spans = spans.into_iter();
+            if !set_function_spans(&mut bundled.expression, &mut spans) || spans.next().is_some() {
+                return Err(invalid(
+                    "a condition's spans do not match its expression's functions",
+                ));
+            }
+
+            bundle.conditions.insert(condition.to_owned(), bundled);
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(invalid("trailing data at the end"));
+        }
+
+        Ok(bundle)
+    }
+
+    /// Serialize the bundle. The output is the same for bundles that contain
+    /// the same conditions, whatever order they were compiled in.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut functions = FunctionTable::default();
+        let mut expression_indices: HashMap<Vec<u8>, usize> = HashMap::new();
+        let mut expressions: Vec<(Vec<u8>, u32)> = Vec::new();
+        let mut conditions = Vec::new();
+
+        for (condition, bundled) in &self.conditions {
+            let mut tree = Vec::new();
+            let mut spans = Vec::new();
+            write_expression(&mut tree, &bundled.expression, &mut functions, &mut spans);
+
+            let index = *expression_indices.entry(tree.clone()).or_insert_with(|| {
+                expressions.push((tree, bundled.cost));
+                expressions.len().saturating_sub(1)
+            });
+
+            conditions.push((condition, index, spans));
+        }
+
+        let mut bytes = MAGIC.to_vec();
+        write_u32(&mut bytes, FORMAT_VERSION);
+
+        write_usize(&mut bytes, functions.functions.len());
+        for function in functions.functions {
+            write_str(&mut bytes, &function.to_string());
+        }
+
+        write_usize(&mut bytes, expressions.len());
+        for (tree, cost) in expressions {
+            write_u32(&mut bytes, cost);
+            write_usize(&mut bytes, tree.len());
+            bytes.extend(tree);
+        }
+
+        write_usize(&mut bytes, conditions.len());
+        for (condition, index, spans) in conditions {
+            write_str(&mut bytes, condition);
+            write_usize(&mut bytes, index);
+            write_usize(&mut bytes, spans.len());
+            for span in spans {
+                write_usize(&mut bytes, span.start);
+                write_usize(&mut bytes, span.end);
+            }
+        }
+
+        bytes
+    }
+
+    /// Get the parsed expression for the given condition string, if it is in
+    /// the bundle. The string must match exactly.
+    pub fn get(&self, condition: &str) -> Option<&Expression> {
+        self.conditions
+            .get(condition)
+            .map(|bundled| &bundled.expression)
+    }
+
+    /// Get the estimated cost of evaluating the given condition string, if it
+    /// is in the bundle. Costs are only meaningful relative to each other.
+    pub fn cost(&self, condition: &str) -> Option<u32> {
+        self.conditions.get(condition).map(|bundled| bundled.cost)
+    }
+
+    /// Iterate over the bundle's condition strings and their parsed
+    /// expressions, in the strings' lexicographical order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Expression)> {
+        self.conditions
+            .iter()
+            .map(|(condition, bundled)| (condition.as_str(), &bundled.expression))
+    }
+
+    pub fn len(&self) -> usize {
+        self.conditions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidBundle(reason.to_owned())
+}
+
+fn parse_function(input: &str, options: &ParseOptions) -> Result<Function, Error> {
+    let (remaining_input, function) = Function::parse(input, options)?;
+
+    if remaining_input.is_empty() {
+        Ok(function)
+    } else {
+        Err(Error::UnconsumedInput(remaining_input.to_owned()))
+    }
+}
+
+/// Set the spans of the expression's functions, in the order that they appear
+/// in the expression. Returns false if there are fewer spans than functions.
+fn set_function_spans(
+    expression: &mut Expression,
+    spans: &mut impl Iterator<Item = Range<usize>>,
+) -> bool {
+    for condition in expression.0.iter_mut().flat_map(|c| &mut c.0) {
+        match condition {
+            Condition::Function(_, span) | Condition::InvertedFunction(_, span) => {
+                match spans.next() {
+                    Some(new_span) => *span = new_span,
+                    None => return false,
+                }
+            }
+            Condition::Expression(e) | Condition::InvertedExpression(e) => {
+                if !set_function_spans(e, spans) {
+                    return false;
+                }
+            }
+        }
+    }
+
+    true
+}
+
+#[derive(Default)]
+struct FunctionTable<'a> {
+    indices: HashMap<&'a Function, usize>,
+    functions: Vec<&'a Function>,
+}
+
+impl<'a> FunctionTable<'a> {
+    fn index(&mut self, function: &'a Function) -> usize {
+        *self.indices.entry(function).or_insert_with(|| {
+            self.functions.push(function);
+            self.functions.len().saturating_sub(1)
+        })
+    }
+}
+
+/// Write the expression's structure, with its functions replaced by their
+/// indices in the function table. The functions' spans are collected
+/// separately so that expressions that differ only in their spans are
+/// written identically.
+fn write_expression<'a>(
+    bytes: &mut Vec<u8>,
+    expression: &'a Expression,
+    functions: &mut FunctionTable<'a>,
+    spans: &mut Vec<Range<usize>>,
+) {
+    write_usize(bytes, expression.0.len());
+    for compound_condition in &expression.0 {
+        write_usize(bytes, compound_condition.0.len());
+        for condition in &compound_condition.0 {
+            match condition {
+                Condition::Function(f, span) | Condition::InvertedFunction(f, span) => {
+                    bytes.push(if matches!(condition, Condition::Function(_, _)) {
+                        FUNCTION_TAG
+                    } else {
+                        INVERTED_FUNCTION_TAG
+                    });
+                    write_usize(bytes, functions.index(f));
+                    spans.push(span.clone());
+                }
+                Condition::Expression(e) => {
+                    bytes.push(EXPRESSION_TAG);
+                    write_expression(bytes, e, functions, spans);
+                }
+                Condition::InvertedExpression(e) => {
+                    bytes.push(INVERTED_EXPRESSION_TAG);
+                    write_expression(bytes, e, functions, spans);
+                }
+            }
+        }
+    }
+}
+
+fn write_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_usize(bytes: &mut Vec<u8>, value: usize) {
+    // usize is no wider than 64 bits on any supported platform.
+    let value = u64::try_from(value).unwrap_or(u64::MAX);
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_str(bytes: &mut Vec<u8>, value: &str) {
+    write_usize(bytes, value.len());
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        let (taken, remaining) = self
+            .bytes
+            .split_at_checked(length)
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+
+        self.bytes = remaining;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        let (first, remaining) = self
+            .bytes
+            .split_first()
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+
+        self.bytes = remaining;
+        Ok(*first)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let (bytes, remaining) = self
+            .bytes
+            .split_first_chunk()
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+
+        self.bytes = remaining;
+        Ok(u32::from_le_bytes(*bytes))
+    }
+
+    fn usize(&mut self) -> Result<usize, Error> {
+        let (bytes, remaining) = self
+            .bytes
+            .split_first_chunk()
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+
+        self.bytes = remaining;
+        usize::try_from(u64::from_le_bytes(*bytes))
+            .map_err(|e| Error::InvalidBundle(format!("a length or index is too large: {e}")))
+    }
+
+    fn str(&mut self) -> Result<&'a str, Error> {
+        let length = self.usize()?;
+        str::from_utf8(self.take(length)?)
+            .map_err(|e| Error::InvalidBundle(format!("a string is not valid UTF-8: {e}")))
+    }
+
+    fn expression(&mut self, functions: &[Function], depth: usize) -> Result<Expression, Error> {
+        if depth > MAX_NESTING_DEPTH {
+            return Err(invalid("an expression is nested too deeply"));
+        }
+
+        let compound_condition_count = self.usize()?;
+        let mut compound_conditions = Vec::new();
+        for _ in 0..compound_condition_count {
+            let condition_count = self.usize()?;
+            let mut conditions = Vec::new();
+            for _ in 0..condition_count {
+                conditions.push(self.condition(functions, depth)?);
+            }
+            compound_conditions.push(CompoundCondition(conditions));
+        }
+
+        Ok(Expression(compound_conditions))
+    }
+
+    fn function(&mut self, functions: &[Function]) -> Result<Function, Error> {
+        functions
+            .get(self.usize()?)
+            .cloned()
+            .ok_or_else(|| invalid("an expression refers to an unknown function"))
+    }
+
+    fn condition(&mut self, functions: &[Function], depth: usize) -> Result<Condition, Error> {
+        match self.u8()? {
+            FUNCTION_TAG => Ok(Condition::Function(self.function(functions)?, 0..0)),
+            INVERTED_FUNCTION_TAG => {
+                Ok(Condition::InvertedFunction(self.function(functions)?, 0..0))
+            }
+            EXPRESSION_TAG => Ok(Condition::Expression(
+                self.expression(functions, depth.saturating_add(1))?,
+            )),
+            INVERTED_EXPRESSION_TAG => Ok(Condition::InvertedExpression(
+                self.expression(functions, depth.saturating_add(1))?,
+            )),
+            _ => Err(invalid("an expression contains an unknown condition type")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "eval")]
+    use std::path::PathBuf;
+
+    #[cfg(feature = "eval")]
+    use crate::{GameType, State};
+
+    use super::*;
+
+    const CONDITIONS: &[&str] = &[
+        "file(\"Cargo.toml\") and not many(\"src/.*\\.rs\")",
+        "active(\"Blank.esp\") or (file(\"Cargo.toml\")  and not (checksum(\"a\", DEADBEEF)))",
+        "active(\"Blank.esp\")  or (file(\"Cargo.toml\") and not (checksum(\"a\", DEADBEEF)))",
+        "file(\"Cargo.toml\") and not many(\"src/.*\\.rs\")",
+    ];
+
+    #[test]
+    fn compile_should_error_if_a_condition_is_invalid() {
+        let result = ConditionBundle::compile(&["file(\"a\")", "foo"], &ParseOptions::default());
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn compile_should_parse_each_distinct_condition_and_estimate_its_cost() {
+        let bundle = ConditionBundle::compile(CONDITIONS, &ParseOptions::default()).unwrap();
+
+        assert_eq!(3, bundle.len());
+        assert_eq!(Some(6), bundle.cost(CONDITIONS[0]));
+        assert_eq!(Some(13), bundle.cost(CONDITIONS[1]));
+        assert_eq!(None, bundle.cost("file(\"Cargo.toml\")"));
+        assert_eq!(
+            &CONDITIONS[1].parse::<Expression>().unwrap(),
+            bundle.get(CONDITIONS[1]).unwrap()
+        );
+    }
+
+    #[test]
+    fn to_bytes_should_store_shared_functions_and_expressions_once() {
+        let bundle = ConditionBundle::compile(CONDITIONS, &ParseOptions::default()).unwrap();
+        let bytes = bundle.to_bytes();
+
+        let mut reader = Reader { bytes: &bytes };
+        reader.take(8).unwrap();
+        let function_count = reader.usize().unwrap();
+        for _ in 0..function_count {
+            reader.str().unwrap();
+        }
+        assert_eq!(4, function_count);
+        assert_eq!(2, reader.usize().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn from_bytes_should_load_the_bundled_conditions_with_their_spans() {
+        let bundle = ConditionBundle::compile(CONDITIONS, &ParseOptions::default()).unwrap();
+
+        let loaded =
+            ConditionBundle::from_bytes(&bundle.to_bytes(), &ParseOptions::default()).unwrap();
+
+        assert_eq!(bundle, loaded);
+        assert_eq!(Some(13), loaded.cost(CONDITIONS[2]));
+
+        let state = State::new(GameType::Oblivion, PathBuf::from("."));
+        for (condition, expression) in loaded.iter() {
+            let report = expression.eval_with_report(&state).unwrap();
+            let function = report.function_evaluations().first().unwrap();
+
+            assert_eq!(function.function(), condition.get(function.span()).unwrap());
+        }
+    }
+
+    #[test]
+    fn from_bytes_should_apply_the_given_options() {
+        let bundle = ConditionBundle::compile(CONDITIONS, &ParseOptions::default()).unwrap();
+        let options = ParseOptions::new().with_max_regex_length(Some(2));
+
+        let result = ConditionBundle::from_bytes(&bundle.to_bytes(), &options);
+
+        assert!(matches!(result, Err(Error::ParsingError(_, _))));
+    }
+
+    #[test]
+    fn from_bytes_should_error_if_the_data_is_invalid() {
+        let bytes = ConditionBundle::compile(CONDITIONS, &ParseOptions::default())
+            .unwrap()
+            .to_bytes();
+
+        let mut wrong_signature = bytes.clone();
+        wrong_signature[0] = b'X';
+        let mut trailing_data = bytes.clone();
+        trailing_data.push(0);
+
+        for bytes in [
+            &wrong_signature[..],
+            &bytes[..bytes.len() - 1],
+            &trailing_data[..],
+            &[],
+        ] {
+            assert!(matches!(
+                ConditionBundle::from_bytes(bytes, &ParseOptions::default()),
+                Err(Error::InvalidBundle(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn from_bytes_should_error_if_an_expression_is_nested_too_deeply() {
+        let mut bundle = ConditionBundle::default();
+        let condition = format!(
+            "{}file(\"a\"){}",
+            "(".repeat(MAX_NESTING_DEPTH + 1),
+            ")".repeat(MAX_NESTING_DEPTH + 1)
+        );
+        let mut expression = "file(\"a\")".parse::<Expression>().unwrap();
+        for _ in 0..=MAX_NESTING_DEPTH {
+            expression = Expression(vec![CompoundCondition(vec![Condition::Expression(
+                expression,
+            )])]);
+        }
+        bundle.conditions.insert(
+            condition,
+            BundledCondition {
+                expression,
+                cost: 0,
+            },
+        );
+
+        let result = ConditionBundle::from_bytes(&bundle.to_bytes(), &ParseOptions::default());
+
+        assert!(matches!(result, Err(Error::InvalidBundle(_))));
+    }
+}
@@ -0,0 +1,758 @@
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use arc_swap::ArcSwap;
+
+use crate::function::Function;
+use crate::ParseOptions;
+
+/// A change to one of a [State](crate::State)'s caches.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CacheEvent {
+    /// The result of a function was cached. The function is given as it
+    /// would be written in a condition string.
+    ConditionCached { function: String, result: bool },
+    /// The cached result of a function was invalidated.
+    ConditionEvicted { function: String },
+    /// All cached function results were invalidated.
+    ConditionCacheCleared,
+    /// The CRC of a file was cached. The filename is lowercased.
+    CrcCached { file: String, crc: u32 },
+    /// The cached CRC of a file was invalidated.
+    CrcEvicted { file: String },
+    /// All cached CRCs were invalidated.
+    CrcCacheCleared,
+}
+
+/// Receives notifications of changes to a state's caches. Notifications are
+/// sent after the change has been made, from whichever thread made it.
+pub trait CacheObserver: Send + Sync {
+    fn cache_changed(&self, event: &CacheEvent);
+}
+
+impl<F: Fn(&CacheEvent) + Send + Sync> CacheObserver for F {
+    fn cache_changed(&self, event: &CacheEvent) {
+        self(event);
+    }
+}
+
+/// Storage for a cache's values, keyed by strings. Caches keep their values
+/// in memory by default, but implementations of this can be given to a
+/// [State](crate::State) to make its caches durable or share them between
+/// processes. Cached values depend on the state's game and data paths, so
+/// storage that is shared should be scoped accordingly.
+///
+/// Storage must not panic, and may drop entries at any time, e.g. to limit
+/// its size.
+pub trait CacheStorage<V>: fmt::Debug + Send + Sync {
+    fn get(&self, key: &str) -> Option<V>;
+
+    fn insert(&self, key: String, value: V);
+
+    /// Remove all entries for which the given predicate returns false.
+    fn retain(&self, predicate: &mut dyn FnMut(&str, &V) -> bool);
+
+    fn clear(&self);
+}
+
+/// The number of shards that a [ShardedMap] splits its entries between.
+const SHARD_COUNT: usize = 16;
+
+/// A [CacheStorage] that keeps its entries in memory, e.g. so that they can
+/// be shared between states in the same process.
+#[derive(Debug)]
+pub struct MemoryCacheStorage<V> {
+    map: ShardedMap<String, V>,
+}
+
+impl<V> MemoryCacheStorage<V> {
+    pub fn new() -> Self {
+        MemoryCacheStorage {
+            map: ShardedMap::new(),
+        }
+    }
+}
+
+impl<V> Default for MemoryCacheStorage<V> {
+    fn default() -> Self {
+        MemoryCacheStorage::new()
+    }
+}
+
+impl<V: Clone + fmt::Debug + Send + Sync> CacheStorage<V> for MemoryCacheStorage<V> {
+    fn get(&self, key: &str) -> Option<V> {
+        self.map.get(key)
+    }
+
+    fn insert(&self, key: String, value: V) {
+        self.map.insert(key, value);
+    }
+
+    fn retain(&self, predicate: &mut dyn FnMut(&str, &V) -> bool) {
+        self.map.retain(&mut |key, value| predicate(key, value));
+    }
+
+    fn clear(&self) {
+        self.map.clear();
+    }
+}
+
+/// A map that can be shared between threads, which is optimised for reads.
+///
+/// Entries are split between a fixed number of shards by a hash of their
+/// keys, and each shard is guarded separately, so threads that insert entries
+/// into different shards don't wait for each other.
+///
+/// Each shard's entries are read from an immutable snapshot that is replaced
+/// atomically, so reading an entry that is in a snapshot never waits for a
+/// lock. New entries are first added to the shard's map of pending entries
+/// that is guarded by a lock, and are copied into a new snapshot in batches so
+/// that the whole shard isn't copied for every insertion. Reading an entry
+/// that is still pending takes the lock. Replacing or removing entries creates
+/// a new snapshot immediately.
+///
+/// If a shard's lock is poisoned, the shard is recreated in an empty
+/// non-poisoned state, as it only holds values that can be recalculated.
+#[derive(Debug)]
+struct ShardedMap<K, V> {
+    shards: Vec<Shard<K, V>>,
+}
+
+impl<K, V> ShardedMap<K, V> {
+    fn new() -> Self {
+        ShardedMap {
+            shards: std::iter::repeat_with(Shard::new)
+                .take(SHARD_COUNT)
+                .collect(),
+        }
+    }
+
+    /// Get the shard that holds the entry with the given key. The key is
+    /// hashed with fixed keys so that entries are always split in the same
+    /// way.
+    fn shard<Q: Hash + ?Sized>(&self, key: &Q) -> Option<&Shard<K, V>> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+
+        let shard_count = u64::try_from(self.shards.len()).ok()?;
+        let index = usize::try_from(hasher.finish().checked_rem(shard_count)?).ok()?;
+
+        self.shards.get(index)
+    }
+
+    fn clear(&self) {
+        for shard in &self.shards {
+            shard.clear();
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> ShardedMap<K, V> {
+    fn get<Q: Eq + Hash + ?Sized>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        self.shard(key)?.get(key)
+    }
+
+    fn insert(&self, key: K, value: V) {
+        if let Some(shard) = self.shard(&key) {
+            shard.insert(key, value);
+        }
+    }
+
+    fn retain(&self, predicate: &mut dyn FnMut(&K, &V) -> bool) {
+        for shard in &self.shards {
+            shard.retain(predicate);
+        }
+    }
+}
+
+/// One of the shards of a [ShardedMap].
+#[derive(Debug)]
+struct Shard<K, V> {
+    snapshot: ArcSwap<HashMap<K, V>>,
+    pending: Mutex<HashMap<K, V>>,
+}
+
+impl<K, V> Shard<K, V> {
+    fn new() -> Self {
+        Shard {
+            snapshot: ArcSwap::default(),
+            pending: Mutex::default(),
+        }
+    }
+
+    fn lock_pending(&self) -> MutexGuard<'_, HashMap<K, V>> {
+        self.pending.lock().unwrap_or_else(|mut e| {
+            **e.get_mut() = HashMap::new();
+            self.snapshot.store(Arc::default());
+            self.pending.clear_poison();
+            e.into_inner()
+        })
+    }
+
+    /// Whether there are enough pending entries to be worth copying the
+    /// snapshot to publish them. Publishing once the pending entries are a
+    /// quarter of the snapshot's size keeps the amount of copying
+    /// proportional to the number of entries inserted.
+    fn should_publish(&self, pending: &HashMap<K, V>) -> bool {
+        pending.len().saturating_mul(4) >= self.snapshot.load().len()
+    }
+
+    fn clear(&self) {
+        let mut pending = self.lock_pending();
+        pending.clear();
+        self.snapshot.store(Arc::default());
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> Shard<K, V> {
+    /// Replace the snapshot with one that also has the pending entries, which
+    /// should be locked while this is done.
+    fn publish(&self, pending: &mut HashMap<K, V>) {
+        let mut snapshot = HashMap::clone(&self.snapshot.load());
+        snapshot.extend(pending.drain());
+        self.snapshot.store(Arc::new(snapshot));
+    }
+
+    fn get<Q: Eq + Hash + ?Sized>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        if let Some(value) = self.snapshot.load().get(key) {
+            return Some(value.clone());
+        }
+
+        let mut pending = self.lock_pending();
+        if let Some(value) = pending.get(key).cloned() {
+            if self.should_publish(&pending) {
+                self.publish(&mut pending);
+            }
+            return Some(value);
+        }
+
+        // The entry may have been published while waiting for the lock.
+        self.snapshot.load().get(key).cloned()
+    }
+
+    fn insert(&self, key: K, value: V) {
+        let mut pending = self.lock_pending();
+        // A pending value would be hidden by the snapshot's value for the
+        // same key, so replacing a value must be published immediately.
+        let replaces_value = self.snapshot.load().contains_key(&key);
+
+        pending.insert(key, value);
+
+        if replaces_value || self.should_publish(&pending) {
+            self.publish(&mut pending);
+        }
+    }
+
+    fn retain(&self, predicate: &mut dyn FnMut(&K, &V) -> bool) {
+        let mut pending = self.lock_pending();
+        let mut snapshot = HashMap::clone(&self.snapshot.load());
+        snapshot.extend(pending.drain());
+        snapshot.retain(|key, value| predicate(key, value));
+        self.snapshot.store(Arc::new(snapshot));
+    }
+}
+
+impl<V, T: CacheStorage<V> + ?Sized> CacheStorage<V> for Arc<T> {
+    fn get(&self, key: &str) -> Option<V> {
+        (**self).get(key)
+    }
+
+    fn insert(&self, key: String, value: V) {
+        (**self).insert(key, value);
+    }
+
+    fn retain(&self, predicate: &mut dyn FnMut(&str, &V) -> bool) {
+        (**self).retain(predicate);
+    }
+
+    fn clear(&self) {
+        (**self).clear();
+    }
+}
+
+/// A value that can be used to look up cached values in a [CacheStorage].
+pub(crate) trait EncodeKey {
+    /// Encode the value as a string, or return None if it can't be encoded
+    /// without losing information.
+    fn encode_key(&self) -> Option<Cow<'_, str>>;
+}
+
+/// A value that can be used as a cache's key, and recovered from a
+/// [CacheStorage].
+pub(crate) trait CacheKey: EncodeKey + Clone + Eq + Hash + Sized {
+    fn decode_key(key: &str) -> Option<Self>;
+}
+
+impl EncodeKey for str {
+    fn encode_key(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl EncodeKey for String {
+    fn encode_key(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl CacheKey for String {
+    fn decode_key(key: &str) -> Option<Self> {
+        Some(key.to_owned())
+    }
+}
+
+// Resolved paths are absolute paths on the host, which may not be valid
+// UTF-8. Converting them lossily could give different paths the same key, so
+// they can't be encoded.
+impl EncodeKey for Path {
+    fn encode_key(&self) -> Option<Cow<'_, str>> {
+        self.to_str().map(Cow::Borrowed)
+    }
+}
+
+impl EncodeKey for PathBuf {
+    fn encode_key(&self) -> Option<Cow<'_, str>> {
+        self.as_path().encode_key()
+    }
+}
+
+impl CacheKey for PathBuf {
+    fn decode_key(key: &str) -> Option<Self> {
+        Some(PathBuf::from(key))
+    }
+}
+
+impl EncodeKey for Function {
+    fn encode_key(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(self.to_string()))
+    }
+}
+
+impl CacheKey for Function {
+    fn decode_key(key: &str) -> Option<Self> {
+        match Function::parse(key, &ParseOptions::default()) {
+            Ok(("", function)) => Some(function),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) enum CacheChange<'a, K, V> {
+    Inserted(&'a K, &'a V),
+    Evicted(&'a K),
+    Cleared,
+}
+
+type Listener<K, V> = Arc<dyn Fn(CacheChange<'_, K, V>) + Send + Sync>;
+
+/// Where a cache keeps its values.
+enum Storage<K, V> {
+    /// The default storage, which is keyed by the cache's keys so that they
+    /// don't need to be encoded to look up values.
+    Memory(ShardedMap<K, V>),
+    /// Storage that was given to the cache, which is keyed by encoded keys.
+    /// Values with keys that can't be encoded are not stored.
+    External(Box<dyn CacheStorage<V>>),
+}
+
+impl<K, V> fmt::Debug for Storage<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Storage::Memory(map) => f.debug_tuple("Memory").field(&map.shards.len()).finish(),
+            Storage::External(storage) => f.debug_tuple("External").field(storage).finish(),
+        }
+    }
+}
+
+/// A cache that can be shared between threads, which stores its values in a
+/// replaceable storage backend and notifies listeners of changes.
+pub(crate) struct Cache<K, V> {
+    storage: Storage<K, V>,
+    listeners: Vec<Listener<K, V>>,
+}
+
+impl<K, V> Default for Cache<K, V> {
+    fn default() -> Self {
+        Cache {
+            storage: Storage::Memory(ShardedMap::new()),
+            listeners: Vec::new(),
+        }
+    }
+}
+
+impl<K, V> fmt::Debug for Cache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Cache")
+            .field("storage", &self.storage)
+            .field("listeners", &self.listeners.len())
+            .finish()
+    }
+}
+
+impl<K: CacheKey, V: Clone> Cache<K, V> {
+    pub(crate) fn get<Q: EncodeKey + Eq + Hash + ?Sized>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        match &self.storage {
+            Storage::Memory(map) => map.get(key),
+            Storage::External(storage) => storage.get(&key.encode_key()?),
+        }
+    }
+
+    /// Replace the cache's storage. Values in the previous storage are not
+    /// copied to the new storage.
+    pub(crate) fn set_storage(&mut self, storage: Box<dyn CacheStorage<V>>) {
+        self.storage = Storage::External(storage);
+    }
+
+    /// Register a function to be called after each change to the cache.
+    pub(crate) fn add_listener(&mut self, listener: Listener<K, V>) {
+        self.listeners.push(listener);
+    }
+
+    pub(crate) fn insert(&self, key: &K, value: V) {
+        if self.listeners.is_empty() {
+            self.insert_into_storage(key, value);
+        } else {
+            self.insert_into_storage(key, value.clone());
+            self.notify(&CacheChange::Inserted(key, &value));
+        }
+    }
+
+    fn insert_into_storage(&self, key: &K, value: V) {
+        match &self.storage {
+            Storage::Memory(map) => map.insert(key.clone(), value),
+            Storage::External(storage) => {
+                if let Some(key) = key.encode_key() {
+                    storage.insert(key.into_owned(), value);
+                }
+            }
+        }
+    }
+
+    /// Replace all cached values with the given entries.
+    pub(crate) fn replace(&self, entries: impl IntoIterator<Item = (K, V)>) {
+        self.clear();
+        for (key, value) in entries {
+            self.insert(&key, value);
+        }
+    }
+
+    /// Remove all cached values for which the given predicate returns false.
+    /// Values in external storage with keys that can't be decoded are also
+    /// removed. Listeners are notified of evictions in the order of the
+    /// evicted values' encoded keys, so that the order doesn't depend on how
+    /// the storage iterates.
+    pub(crate) fn retain(&self, mut predicate: impl FnMut(&K, &V) -> bool) {
+        let mut evicted = Vec::new();
+        let mut retain_key = |key: &K, value: &V| {
+            let keep = predicate(key, value);
+            if !keep && !self.listeners.is_empty() {
+                evicted.push(key.clone());
+            }
+            keep
+        };
+
+        match &self.storage {
+            Storage::Memory(map) => map.retain(&mut retain_key),
+            Storage::External(storage) => storage.retain(&mut |key, value| {
+                K::decode_key(key).is_some_and(|key| retain_key(&key, value))
+            }),
+        }
+
+        evicted.sort_by_cached_key(|key| key.encode_key().map(Cow::into_owned));
+        for key in &evicted {
+            self.notify(&CacheChange::Evicted(key));
+        }
+    }
+
+    /// Get copies of the cached entries, in the order of their encoded keys.
+    /// Values in external storage with keys that can't be decoded are
+    /// removed, as by [Cache::retain].
+    pub(crate) fn entries(&self) -> Vec<(K, V)> {
+        let mut entries = Vec::new();
+        self.retain(|key, value| {
+            entries.push((key.clone(), value.clone()));
+            true
+        });
+
+        entries.sort_by_cached_key(|(key, _)| key.encode_key().map(Cow::into_owned));
+        entries
+    }
+
+    pub(crate) fn clear(&self) {
+        match &self.storage {
+            Storage::Memory(map) => map.clear(),
+            Storage::External(storage) => storage.clear(),
+        }
+        self.notify(&CacheChange::Cleared);
+    }
+
+    fn notify(&self, change: &CacheChange<'_, K, V>) {
+        for listener in &self.listeners {
+            listener(match change {
+                CacheChange::Inserted(key, value) => CacheChange::Inserted(key, value),
+                CacheChange::Evicted(key) => CacheChange::Evicted(key),
+                CacheChange::Cleared => CacheChange::Cleared,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_should_return_a_clone_of_an_inserted_value() {
+        let cache = Cache::default();
+        cache.insert(&"key".to_owned(), 1_u32);
+
+        assert_eq!(Some(1_u32), cache.get("key"));
+        assert_eq!(None, cache.get("other"));
+    }
+
+    #[test]
+    fn clear_should_remove_all_values() {
+        let cache = Cache::default();
+        cache.insert(&"key".to_owned(), 1_u32);
+        cache.clear();
+
+        assert_eq!(None, cache.get("key"));
+    }
+
+    #[test]
+    fn listeners_should_be_notified_of_each_change() {
+        let changes = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let changes_clone = Arc::clone(&changes);
+        let mut cache = Cache::default();
+        cache.add_listener(Arc::new(move |change| {
+            let description = match change {
+                CacheChange::Inserted(key, value) => format!("inserted {key}={value}"),
+                CacheChange::Evicted(key) => format!("evicted {key}"),
+                CacheChange::Cleared => "cleared".to_owned(),
+            };
+            changes_clone.lock().unwrap().push(description);
+        }));
+
+        cache.insert(&"a".to_owned(), 1_u32);
+        cache.replace([("b".to_owned(), 2_u32), ("c".to_owned(), 3_u32)]);
+        cache.retain(|key, _| key == "c");
+        cache.clear();
+
+        assert_eq!(
+            vec![
+                "inserted a=1",
+                "cleared",
+                "inserted b=2",
+                "inserted c=3",
+                "evicted b",
+                "cleared",
+            ],
+            *changes.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn retain_should_notify_listeners_of_evictions_in_key_order() {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let evicted_clone = Arc::clone(&evicted);
+        let mut cache: Cache<String, u32> = Cache::default();
+        cache.add_listener(Arc::new(move |change| {
+            if let CacheChange::Evicted(key) = change {
+                evicted_clone.lock().unwrap().push(key.clone());
+            }
+        }));
+
+        let keys = ["d", "b", "e", "a", "c", "f"].map(str::to_owned);
+        cache.replace(keys.iter().map(|key| (key.clone(), 0_u32)));
+        cache.retain(|key, _| key == "c");
+
+        assert_eq!(vec!["a", "b", "d", "e", "f"], *evicted.lock().unwrap());
+    }
+
+    #[test]
+    fn retain_should_remove_values_with_keys_that_cannot_be_decoded() {
+        let storage = Arc::new(MemoryCacheStorage::new());
+        storage.insert("file(\"a\")".to_owned(), 1_u32);
+        storage.insert("invalid".to_owned(), 2_u32);
+        let mut cache: Cache<Function, u32> = Cache::default();
+        cache.set_storage(Box::new(Arc::clone(&storage)));
+
+        cache.retain(|_, _| true);
+
+        assert_eq!(Some(1_u32), storage.get("file(\"a\")"));
+        assert_eq!(None, storage.get("invalid"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn memory_storage_should_not_confuse_paths_that_are_not_valid_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path1 = PathBuf::from(OsStr::from_bytes(b"/a/\xFF.esp"));
+        let path2 = PathBuf::from(OsStr::from_bytes(b"/a/\xFE.esp"));
+        let cache = Cache::default();
+        cache.insert(&path1, 1_u32);
+        cache.insert(&path2, 2_u32);
+
+        assert_eq!(Some(1_u32), cache.get(path1.as_path()));
+        assert_eq!(Some(2_u32), cache.get(path2.as_path()));
+        assert_eq!(2, cache.entries().len());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn external_storage_should_not_store_values_with_keys_that_cannot_be_encoded() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let storage = Arc::new(MemoryCacheStorage::new());
+        let mut cache: Cache<PathBuf, u32> = Cache::default();
+        cache.set_storage(Box::new(Arc::clone(&storage)));
+
+        let path = PathBuf::from(OsStr::from_bytes(b"/a/\xFF.esp"));
+        cache.insert(&path, 1_u32);
+        cache.insert(&PathBuf::from("/a/b.esp"), 2_u32);
+
+        assert_eq!(None, cache.get(path.as_path()));
+        assert_eq!(None, storage.get(&path.to_string_lossy()));
+        assert_eq!(Some(2_u32), storage.get("/a/b.esp"));
+    }
+
+    #[test]
+    fn function_keys_should_round_trip() {
+        let functions = [
+            "file(\"a/b.esp\")",
+            "many(\"a/.*\\.esp\")",
+            "checksum(\"a.esp\", DEADBEEF)",
+            "version(\"a.esp\", \"1.0\", >=)",
+            "product_version(\"a.dll\", \"1.0 - 2.0\")",
+        ];
+
+        for string in functions {
+            let (_, function) = Function::parse(string, &ParseOptions::default()).unwrap();
+
+            assert_eq!(Some(string), function.encode_key().as_deref());
+            assert_eq!(Some(function), Function::decode_key(string));
+        }
+    }
+
+    #[test]
+    fn memory_storage_should_split_entries_inserted_from_multiple_threads_between_shards() {
+        let storage = MemoryCacheStorage::new();
+
+        std::thread::scope(|s| {
+            for thread in 0..4_u32 {
+                let storage = &storage;
+                s.spawn(move || {
+                    for i in 0..100_u32 {
+                        storage.insert(format!("{thread}-{i}"), i);
+                    }
+                });
+            }
+        });
+
+        for thread in 0..4_u32 {
+            for i in 0..100_u32 {
+                assert_eq!(Some(i), storage.get(&format!("{thread}-{i}")));
+            }
+        }
+        assert!(storage
+            .map
+            .shards
+            .iter()
+            .all(|shard| shard.snapshot.load().len() + shard.lock_pending().len() > 0));
+
+        storage.retain(&mut |_, value| *value < 50);
+
+        assert_eq!(Some(49_u32), storage.get("3-49"));
+        assert_eq!(None, storage.get("0-50"));
+    }
+
+    #[test]
+    fn shard_should_publish_pending_entries_in_batches() {
+        let storage = Shard::new();
+
+        for i in 0..100_u32 {
+            storage.insert(i.to_string(), i);
+
+            let published = storage.snapshot.load().len();
+            let pending = storage.lock_pending().len();
+            assert_eq!(usize::try_from(i).unwrap() + 1, published + pending);
+            assert!(pending * 4 < published);
+        }
+
+        for i in 0..100_u32 {
+            assert_eq!(Some(i), storage.get(&i.to_string()));
+        }
+        assert_eq!(None, storage.get("100"));
+    }
+
+    #[test]
+    fn memory_storage_insert_should_replace_a_published_value() {
+        let storage = MemoryCacheStorage::new();
+        storage.insert("key".to_owned(), 1_u32);
+        for i in 0..8_u32 {
+            storage.insert(i.to_string(), i);
+        }
+
+        storage.insert("key".to_owned(), 2_u32);
+
+        assert_eq!(Some(2_u32), storage.get("key"));
+    }
+
+    #[test]
+    fn shard_retain_and_clear_should_apply_to_pending_and_published_entries() {
+        let storage = Shard::new();
+        for i in 0..10_u32 {
+            storage.insert(i.to_string(), i);
+        }
+        assert!(!storage.lock_pending().is_empty());
+
+        storage.retain(&mut |_, value| *value < 5);
+
+        assert_eq!(Some(4_u32), storage.get("4"));
+        assert_eq!(None, storage.get("9"));
+        assert_eq!(5, storage.snapshot.load().len());
+
+        storage.insert("10".to_owned(), 10_u32);
+        storage.clear();
+
+        assert_eq!(None, storage.get("4"));
+        assert_eq!(None, storage.get("10"));
+    }
+
+    #[test]
+    fn shard_insert_should_recover_from_a_poisoned_lock() {
+        let storage = Shard::new();
+        storage.insert("key".to_owned(), 1_u32);
+
+        let result = std::thread::scope(|s| {
+            s.spawn(|| {
+                let _guard = storage.pending.lock();
+                panic!("Poisoning the lock");
+            })
+            .join()
+        });
+        assert!(result.is_err());
+
+        storage.insert("other".to_owned(), 2_u32);
+
+        assert_eq!(None, storage.get("key"));
+        assert_eq!(Some(2_u32), storage.get("other"));
+    }
+}
@@ -0,0 +1,194 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
+
+/// Calculates the CRC-32 of the data written to it, in the same way as the
+/// CRCs that `checksum()` conditions compare against, so that hosts can
+/// calculate CRCs to give to
+/// [State::set_cached_crcs](crate::State::set_cached_crcs). Data can be given
+/// to it in pieces, either by calling [Crc32Writer::update] or through its
+/// [Write] implementation, e.g. using [io::copy].
+#[derive(Clone, Debug, Default)]
+pub struct Crc32Writer(crc32fast::Hasher);
+
+impl Crc32Writer {
+    pub fn new() -> Self {
+        Crc32Writer::default()
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    /// The CRC-32 of all the data given so far.
+    pub fn crc(&self) -> u32 {
+        self.0.clone().finalize()
+    }
+}
+
+impl Write for Crc32Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A CRC from a state's CRC cache, with the size and modification time of its
+/// file when the CRC was exported, so that it can be saved by the host and
+/// given to [State::import_crc_cache](crate::State::import_crc_cache) in a
+/// later run. Its path is the lowercased path that conditions use to refer
+/// to the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CachedCrc {
+    path: String,
+    size: u64,
+    modified: SystemTime,
+    crc: u32,
+}
+
+impl CachedCrc {
+    pub(crate) fn new(path: String, size: u64, modified: SystemTime, crc: u32) -> Self {
+        CachedCrc {
+            path,
+            size,
+            modified,
+            crc,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The file's size in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn modified(&self) -> SystemTime {
+        self.modified
+    }
+
+    pub fn crc(&self) -> u32 {
+        self.crc
+    }
+}
+
+/// Calculate the CRC-32 of all the data that can be read from the given
+/// reader, which is read in buffered chunks.
+pub fn crc32_from_reader<R: Read>(reader: R) -> io::Result<u32> {
+    let mut reader = BufReader::new(reader);
+    let mut writer = Crc32Writer::new();
+
+    let mut buffer = reader.fill_buf()?;
+    while !buffer.is_empty() {
+        writer.update(buffer);
+        let length = buffer.len();
+        reader.consume(length);
+
+        buffer = reader.fill_buf()?;
+    }
+
+    Ok(writer.crc())
+}
+
+/// Calculate the CRC-32 of the given bytes by hashing chunks of them on
+/// rayon's thread pool and combining the chunks' CRCs in order. The given
+/// function is called before each chunk is hashed, and if it returns an error
+/// no more chunks are hashed.
+#[cfg(feature = "parallel")]
+pub(crate) fn crc32_from_chunks_parallel<E: Send>(
+    bytes: &[u8],
+    chunk_size: usize,
+    before_chunk: impl Fn() -> Result<(), E> + Sync,
+) -> Result<u32, E> {
+    use rayon::prelude::*;
+
+    let hashers = bytes
+        .par_chunks(chunk_size)
+        .map(|chunk| {
+            before_chunk()?;
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(chunk);
+            Ok(hasher)
+        })
+        .collect::<Result<Vec<_>, E>>()?;
+
+    let mut combined = crc32fast::Hasher::new();
+    for hasher in &hashers {
+        combined.combine(hasher);
+    }
+
+    Ok(combined.finalize())
+}
+
+/// Calculate the SHA-256 digest of all the data that can be read from the
+/// given reader.
+pub(crate) fn sha256_from_reader<R: Read>(mut reader: R) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut reader, &mut hasher)?;
+
+    Ok(hasher.finalize().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &[u8] = b"The quick brown fox jumps over the lazy dog";
+    const DATA_CRC: u32 = 0x414F_A339;
+
+    #[test]
+    fn crc32_writer_should_give_the_same_crc_however_the_data_is_split() {
+        let mut writer = Crc32Writer::new();
+        assert_eq!(0, writer.crc());
+
+        let (first, second) = DATA.split_at(10);
+        writer.update(first);
+        writer.write_all(second).unwrap();
+
+        assert_eq!(DATA_CRC, writer.crc());
+    }
+
+    #[test]
+    fn crc32_from_reader_should_read_all_the_data() {
+        assert_eq!(DATA_CRC, crc32_from_reader(DATA).unwrap());
+        assert_eq!(0, crc32_from_reader(io::empty()).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn crc32_from_chunks_parallel_should_combine_the_chunks_crcs_in_order() {
+        for chunk_size in [1, 5, DATA.len(), 100] {
+            let crc = crc32_from_chunks_parallel(DATA, chunk_size, || Ok::<_, ()>(())).unwrap();
+
+            assert_eq!(DATA_CRC, crc);
+        }
+
+        assert_eq!(Err(()), crc32_from_chunks_parallel(DATA, 5, || Err(())));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn cached_crc_should_round_trip_through_serde() {
+        let cached_crc = CachedCrc::new("blank.esp".into(), 3, SystemTime::UNIX_EPOCH, DATA_CRC);
+
+        let json = serde_json::to_string(&cached_crc).unwrap();
+
+        assert_eq!(cached_crc, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn sha256_from_reader_should_read_all_the_data() {
+        let digest = sha256_from_reader(DATA).unwrap();
+
+        assert!(digest.starts_with(&[0xd7, 0xa8, 0xfb, 0xb3, 0x07, 0xd7, 0x80, 0x94]));
+        assert!(digest.ends_with(&[0x2d, 0x02, 0xd0, 0xbf, 0x37, 0xc9, 0xe5, 0x92]));
+    }
+}
@@ -0,0 +1,246 @@
+use crate::function::Function;
+use crate::{Condition, Expression};
+
+/// An estimate of the work needed to evaluate an expression, counted by the
+/// kind of I/O involved. The estimate is calculated without accessing the
+/// filesystem, and assumes that every function in the expression is
+/// evaluated and that none of their results are cached.
+///
+/// The counts are estimates: for example, `checksum_manifest()` counts as a
+/// single hash, as the number of files that its manifest lists isn't known
+/// without reading it, recursive directory searches count one scan per level
+/// of subdirectories, and version comparisons count as executable reads even
+/// though the versions of plugins may have been given to the state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct EvaluationCost {
+    active_plugin_checks: u32,
+    file_checks: u32,
+    directory_scans: u32,
+    plugin_parses: u32,
+    executable_reads: u32,
+    archive_reads: u32,
+    hashes: u32,
+}
+
+impl EvaluationCost {
+    pub(crate) fn of(expression: &Expression) -> Self {
+        expression
+            .0
+            .iter()
+            .flat_map(|compound_condition| &compound_condition.0)
+            .map(|condition| match condition {
+                Condition::Function(f, _) | Condition::InvertedFunction(f, _) => function_cost(f),
+                Condition::Expression(e) | Condition::InvertedExpression(e) => {
+                    EvaluationCost::of(e)
+                }
+            })
+            .fold(EvaluationCost::default(), EvaluationCost::saturating_add)
+    }
+
+    /// The number of checks against the state's list of active plugins, which
+    /// don't involve any I/O.
+    pub fn active_plugin_checks(&self) -> u32 {
+        self.active_plugin_checks
+    }
+
+    /// The number of times a file's metadata is read.
+    pub fn file_checks(&self) -> u32 {
+        self.file_checks
+    }
+
+    /// The number of directories that are read to find entries whose names
+    /// match a regex.
+    pub fn directory_scans(&self) -> u32 {
+        self.directory_scans
+    }
+
+    /// The number of plugins whose headers are parsed.
+    pub fn plugin_parses(&self) -> u32 {
+        self.plugin_parses
+    }
+
+    /// The number of executables whose version information is read.
+    pub fn executable_reads(&self) -> u32 {
+        self.executable_reads
+    }
+
+    /// The number of archives whose indexes of files are read.
+    pub fn archive_reads(&self) -> u32 {
+        self.archive_reads
+    }
+
+    /// The number of files whose CRCs are calculated.
+    pub fn hashes(&self) -> u32 {
+        self.hashes
+    }
+
+    /// A single number that combines the counts, weighting each by how
+    /// expensive that kind of work typically is. Totals are only meaningful
+    /// relative to each other.
+    pub fn total(&self) -> u32 {
+        [
+            (self.active_plugin_checks, 1),
+            (self.file_checks, 2),
+            (self.directory_scans, 4),
+            (self.plugin_parses, 5),
+            (self.executable_reads, 6),
+            (self.archive_reads, 6),
+            (self.hashes, 10),
+        ]
+        .into_iter()
+        .map(|(count, weight)| count.saturating_mul(weight))
+        .fold(0, u32::saturating_add)
+    }
+
+    fn saturating_add(self, other: Self) -> Self {
+        EvaluationCost {
+            active_plugin_checks: self
+                .active_plugin_checks
+                .saturating_add(other.active_plugin_checks),
+            file_checks: self.file_checks.saturating_add(other.file_checks),
+            directory_scans: self.directory_scans.saturating_add(other.directory_scans),
+            plugin_parses: self.plugin_parses.saturating_add(other.plugin_parses),
+            executable_reads: self.executable_reads.saturating_add(other.executable_reads),
+            archive_reads: self.archive_reads.saturating_add(other.archive_reads),
+            hashes: self.hashes.saturating_add(other.hashes),
+        }
+    }
+}
+
+/// Check if evaluating the function involves more than checking file metadata
+/// or the state's active plugins.
+pub(crate) fn is_expensive(function: &Function) -> bool {
+    let cost = function_cost(function);
+
+    EvaluationCost {
+        active_plugin_checks: 0,
+        file_checks: 0,
+        ..cost
+    } != EvaluationCost::default()
+}
+
+fn function_cost(function: &Function) -> EvaluationCost {
+    let none = EvaluationCost::default();
+
+    match function {
+        Function::IsPlatform(_) | Function::IsWine => none,
+        Function::ActivePath(_)
+        | Function::ActiveRegex(_)
+        | Function::ManyActive(_)
+        | Function::ActiveCount(_, _, _) => EvaluationCost {
+            active_plugin_checks: 1,
+            ..none
+        },
+        Function::FilePath(_)
+        | Function::Readable(_)
+        | Function::Writable(_)
+        | Function::RegistryValue(_, _, _)
+        | Function::FileSize(_, _, _)
+        | Function::FileModifiedTime(_, _, _)
+        | Function::FileContentContains(_, _)
+        | Function::IniValue(_, _, _, _) => EvaluationCost {
+            file_checks: 1,
+            ..none
+        },
+        Function::IsNewerThan(_, _) => EvaluationCost {
+            file_checks: 2,
+            ..none
+        },
+        Function::DescriptionRegex(_, _, _) => EvaluationCost {
+            directory_scans: 1,
+            plugin_parses: 1,
+            ..none
+        },
+        Function::FileRegex(_, _)
+        | Function::FileGlob(_, _, _)
+        | Function::Many(_, _)
+        | Function::Count(_, _, _, _)
+        | Function::FilenameVersion(_, _, _, _) => EvaluationCost {
+            directory_scans: 1,
+            ..none
+        },
+        Function::RecursiveFileRegex(_, _, depth) | Function::RecursiveMany(_, _, depth) => {
+            EvaluationCost {
+                directory_scans: depth.saturating_add(1),
+                ..none
+            }
+        }
+        Function::IsMaster(_)
+        | Function::IsLightPlugin(_)
+        | Function::IsMediumPlugin(_)
+        | Function::HeaderVersion(_, _, _)
+        | Function::RecordCount(_, _, _)
+        | Function::MastersContain(_, _)
+        | Function::IsValidPlugin(_)
+        | Function::DescriptionContains(_, _)
+        | Function::DescriptionContainsText(_, _)
+        | Function::AuthorContains(_, _) => EvaluationCost {
+            plugin_parses: 1,
+            ..none
+        },
+        Function::IsExecutable(_)
+        | Function::IsArchitecture(_, _)
+        | Function::Version(_, _, _)
+        | Function::ProductVersion(_, _, _)
+        | Function::VersionInRange(_, _, _)
+        | Function::ProductVersionInRange(_, _, _) => EvaluationCost {
+            executable_reads: 1,
+            ..none
+        },
+        Function::VersionCmp(_, _, _) => EvaluationCost {
+            executable_reads: 2,
+            ..none
+        },
+        Function::ArchiveContains(_, _)
+        | Function::ArchiveRegex(_, _)
+        | Function::ArchiveVersion(_, _, _) => EvaluationCost {
+            archive_reads: 1,
+            ..none
+        },
+        Function::Checksum(_, _)
+        | Function::ChecksumSha256(_, _)
+        | Function::ChecksumManifest(_) => EvaluationCost { hashes: 1, ..none },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn cost(expression: &str) -> EvaluationCost {
+        Expression::from_str(expression).unwrap().estimate_cost()
+    }
+
+    #[test]
+    fn estimate_cost_should_count_each_kind_of_work_across_nested_expressions() {
+        let cost = cost(
+            "active(\"a.esp\") or (file(\"b\") and not (checksum(\"c\", DEADBEEF) or is_master(\"d.esp\"))) and many(\"e/.*\") and version_cmp(\"f.exe\", \"g.exe\", ==)",
+        );
+
+        assert_eq!(1, cost.active_plugin_checks());
+        assert_eq!(1, cost.file_checks());
+        assert_eq!(1, cost.directory_scans());
+        assert_eq!(1, cost.plugin_parses());
+        assert_eq!(2, cost.executable_reads());
+        assert_eq!(1, cost.hashes());
+    }
+
+    #[test]
+    fn estimate_cost_should_count_repeated_functions_each_time() {
+        assert_eq!(2, cost("file(\"a\") or not file(\"a\")").file_checks());
+    }
+
+    #[test]
+    fn total_should_weight_the_counts() {
+        assert_eq!(0, EvaluationCost::default().total());
+        assert_eq!(4, cost("is_newer_than(\"a\", \"b\")").total());
+        assert_eq!(6, cost("archive_contains(\"a.bsa\", \"b\")").total());
+        assert_eq!(
+            28,
+            cost("file(\"a\") and many(\"b/.*\") and is_master(\"c.esp\") and version(\"d\", \"1\", ==) and checksum(\"e\", 1) and active(\"f\")")
+                .total()
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::function::eval::is_directory_cached;
+use crate::function::path::{data_paths_in_search_order, plan_path_resolution};
+use crate::function::Function;
+use crate::{Condition, Error, Expression, State};
+
+/// A filesystem operation that evaluating an expression would perform.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FileSystemOperation {
+    /// Read the metadata of the path, e.g. to check if it exists, get its
+    /// size, or check if it can be read or written.
+    Stat(PathBuf),
+    /// List the entries in the directory at the path.
+    ReadDir(PathBuf),
+    /// Read part of the file at the path, e.g. a plugin's header, an
+    /// executable's version information or an archive's index of files.
+    ReadPart(PathBuf),
+    /// Read the whole of the file at the path, e.g. to calculate its CRC.
+    ReadAll(PathBuf),
+}
+
+impl FileSystemOperation {
+    pub fn path(&self) -> &Path {
+        match self {
+            FileSystemOperation::Stat(p)
+            | FileSystemOperation::ReadDir(p)
+            | FileSystemOperation::ReadPart(p)
+            | FileSystemOperation::ReadAll(p) => p,
+        }
+    }
+}
+
+/// Plans the operations that evaluating expressions would perform, keeping
+/// track of what earlier evaluations would have cached.
+struct Planner<'a> {
+    state: &'a State,
+    operations: Vec<FileSystemOperation>,
+    resolved_paths: HashMap<PathBuf, PathBuf>,
+    planned_functions: HashSet<Function>,
+}
+
+pub(crate) fn dry_run(
+    expression: &Expression,
+    state: &State,
+) -> Result<Vec<FileSystemOperation>, Error> {
+    let mut planner = Planner {
+        state,
+        operations: Vec::new(),
+        resolved_paths: HashMap::new(),
+        planned_functions: HashSet::new(),
+    };
+
+    planner.plan_expression(expression)?;
+
+    Ok(planner.operations)
+}
+
+impl Planner<'_> {
+    fn plan_expression(&mut self, expression: &Expression) -> Result<(), Error> {
+        for condition in expression.0.iter().flat_map(|c| &c.0) {
+            match condition {
+                Condition::Function(f, _) | Condition::InvertedFunction(f, _) => {
+                    self.plan_function(f)?;
+                }
+                Condition::Expression(e) | Condition::InvertedExpression(e) => {
+                    self.plan_expression(e)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn plan_function(&mut self, function: &Function) -> Result<(), Error> {
+        let state = self.state;
+        let function = function.bind_variables(state)?.into_owned();
+
+        // Evaluating a function caches its result or the data it read, so
+        // evaluating it again doesn't access the filesystem.
+        if function.has_cached_data(state)? || self.planned_functions.contains(&function) {
+            return Ok(());
+        }
+
+        match operation_kind(&function) {
+            Some(OperationKind::ReadDir) => {
+                for path in function.paths() {
+                    for data_path in data_paths_in_search_order(state) {
+                        let directory = data_path.join(path);
+                        if !is_directory_cached(state, &directory) {
+                            self.operations
+                                .push(FileSystemOperation::ReadDir(directory));
+                        }
+                    }
+                }
+            }
+            Some(kind) => {
+                for path in function.paths() {
+                    let resolved_path = self.plan_path_resolution(path);
+                    if let Some(operation) = kind.operation(resolved_path) {
+                        self.operations.push(operation);
+                    }
+                }
+            }
+            None => {}
+        }
+
+        self.planned_functions.insert(function);
+
+        Ok(())
+    }
+
+    fn plan_path_resolution(&mut self, path: &Path) -> PathBuf {
+        if let Some(resolved_path) = self.resolved_paths.get(path) {
+            return resolved_path.clone();
+        }
+
+        let (checked_paths, resolved_path) = plan_path_resolution(self.state, path);
+        self.operations
+            .extend(checked_paths.into_iter().map(FileSystemOperation::Stat));
+        self.resolved_paths
+            .insert(path.to_path_buf(), resolved_path.clone());
+
+        resolved_path
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum OperationKind {
+    /// Resolving the path is enough to check if it exists.
+    Resolve,
+    Stat,
+    ReadDir,
+    ReadPart,
+    ReadAll,
+}
+
+impl OperationKind {
+    fn operation(self, resolved_path: PathBuf) -> Option<FileSystemOperation> {
+        match self {
+            OperationKind::Resolve => None,
+            OperationKind::Stat => Some(FileSystemOperation::Stat(resolved_path)),
+            OperationKind::ReadDir => Some(FileSystemOperation::ReadDir(resolved_path)),
+            OperationKind::ReadPart => Some(FileSystemOperation::ReadPart(resolved_path)),
+            OperationKind::ReadAll => Some(FileSystemOperation::ReadAll(resolved_path)),
+        }
+    }
+}
+
+/// Get the kind of operation that the function performs on each of its paths,
+/// if it accesses the filesystem.
+pub(crate) fn operation_kind(function: &Function) -> Option<OperationKind> {
+    match function {
+        Function::IsPlatform(_)
+        | Function::IsWine
+        | Function::RegistryValue(_, _, _)
+        | Function::ActivePath(_)
+        | Function::ActiveRegex(_)
+        | Function::ManyActive(_)
+        | Function::ActiveCount(_, _, _) => None,
+        Function::FilePath(_) => Some(OperationKind::Resolve),
+        Function::FileSize(_, _, _)
+        | Function::FileModifiedTime(_, _, _)
+        | Function::Readable(_)
+        | Function::Writable(_)
+        | Function::IsNewerThan(_, _) => Some(OperationKind::Stat),
+        Function::FileRegex(_, _)
+        | Function::FileGlob(_, _, _)
+        | Function::RecursiveFileRegex(_, _, _)
+        | Function::Many(_, _)
+        | Function::RecursiveMany(_, _, _)
+        | Function::Count(_, _, _, _)
+        | Function::FilenameVersion(_, _, _, _)
+        | Function::DescriptionRegex(_, _, _) => Some(OperationKind::ReadDir),
+        Function::IsExecutable(_)
+        | Function::IsArchitecture(_, _)
+        | Function::IsMaster(_)
+        | Function::IsLightPlugin(_)
+        | Function::IsMediumPlugin(_)
+        | Function::HeaderVersion(_, _, _)
+        | Function::RecordCount(_, _, _)
+        | Function::MastersContain(_, _)
+        | Function::IsValidPlugin(_)
+        | Function::DescriptionContains(_, _)
+        | Function::DescriptionContainsText(_, _)
+        | Function::AuthorContains(_, _)
+        | Function::Version(_, _, _)
+        | Function::ProductVersion(_, _, _)
+        | Function::VersionInRange(_, _, _)
+        | Function::ProductVersionInRange(_, _, _)
+        | Function::VersionCmp(_, _, _)
+        | Function::ArchiveContains(_, _)
+        | Function::ArchiveRegex(_, _)
+        | Function::ArchiveVersion(_, _, _) => Some(OperationKind::ReadPart),
+        Function::Checksum(_, _)
+        | Function::ChecksumSha256(_, _)
+        | Function::ChecksumManifest(_)
+        | Function::FileContentContains(_, _)
+        | Function::IniValue(_, _, _, _) => Some(OperationKind::ReadAll),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::GameType;
+
+    use super::*;
+
+    fn state() -> State {
+        State::new(GameType::Oblivion, PathBuf::from("data")).with_active_plugins(&["Blank.esp"])
+    }
+
+    fn dry_run(expression: &str, state: &State) -> Vec<FileSystemOperation> {
+        Expression::from_str(expression)
+            .unwrap()
+            .dry_run(state)
+            .unwrap()
+    }
+
+    #[test]
+    fn dry_run_should_list_the_operations_of_every_function_in_order() {
+        let operations = dry_run(
+            "active(\"Blank.esp\") or (file(\"a\") and not checksum(\"b\", DEADBEEF)) or many(\"c/.*\") or is_master(\"d.esp\")",
+            &state(),
+        );
+
+        assert_eq!(
+            vec![
+                FileSystemOperation::Stat(PathBuf::from("data/a")),
+                FileSystemOperation::Stat(PathBuf::from("data/b")),
+                FileSystemOperation::ReadAll(PathBuf::from("data/b")),
+                FileSystemOperation::ReadDir(PathBuf::from("data/c")),
+                FileSystemOperation::Stat(PathBuf::from("data/d.esp")),
+                FileSystemOperation::Stat(PathBuf::from("data/d.esp.ghost")),
+                FileSystemOperation::ReadPart(PathBuf::from("data/d.esp")),
+            ],
+            operations
+        );
+    }
+
+    #[test]
+    fn dry_run_should_check_each_data_path_in_search_order() {
+        let mut state = state();
+        state.set_additional_data_paths(vec![PathBuf::from("extra")]);
+
+        let operations = dry_run("file_size(\"a\", 1, ==) and many(\"b/.*\")", &state);
+
+        assert_eq!(
+            vec![
+                FileSystemOperation::Stat(PathBuf::from("extra/a")),
+                FileSystemOperation::Stat(PathBuf::from("data/a")),
+                FileSystemOperation::Stat(PathBuf::from("data/a")),
+                FileSystemOperation::ReadDir(PathBuf::from("extra/b")),
+                FileSystemOperation::ReadDir(PathBuf::from("data/b")),
+            ],
+            operations
+        );
+    }
+
+    #[test]
+    fn dry_run_should_only_resolve_each_path_and_evaluate_each_function_once() {
+        let operations = dry_run(
+            "checksum(\"a\", DEADBEEF) or not checksum(\"a\", DEADBEEF) or readable(\"a\")",
+            &state(),
+        );
+
+        assert_eq!(
+            vec![
+                FileSystemOperation::Stat(PathBuf::from("data/a")),
+                FileSystemOperation::ReadAll(PathBuf::from("data/a")),
+                FileSystemOperation::Stat(PathBuf::from("data/a")),
+            ],
+            operations
+        );
+    }
+
+    #[test]
+    fn dry_run_should_skip_functions_with_cached_results() {
+        let state = State::new(GameType::Oblivion, PathBuf::from("."));
+        let expression = Expression::from_str("readable(\"Cargo.toml\")").unwrap();
+        expression.eval(&state).unwrap();
+
+        assert!(expression.dry_run(&state).unwrap().is_empty());
+    }
+
+    #[test]
+    fn dry_run_should_error_if_a_path_is_not_allowed() {
+        let mut state = state();
+        state.set_path_traversal_allowlist(Some(Vec::new()));
+        let expression = Expression::from_str("file(\"../a\")").unwrap();
+
+        assert!(expression.dry_run(&state).is_err());
+    }
+}
@@ -1,8 +1,10 @@
+use std::cmp::Ordering;
 use std::error;
 use std::fmt;
 use std::io;
 use std::num::NonZeroUsize;
 use std::num::ParseIntError;
+use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
 use std::slice::EscapeAscii;
@@ -10,6 +12,8 @@ use std::slice::EscapeAscii;
 use nom::error::ErrorKind;
 use nom::Err;
 
+use crate::RegexFeature;
+
 #[expect(clippy::error_impl_error)]
 #[derive(Debug)]
 #[non_exhaustive]
@@ -21,6 +25,40 @@ pub enum Error {
     ParsingError(String, ParsingErrorKind),
     PeParsingError(PathBuf, Box<dyn error::Error + Send + Sync + 'static>),
     IoError(PathBuf, io::Error),
+    /// The path could refer to something outside of the game's data paths,
+    /// and is not allowed by the state's path traversal allowlist.
+    PathTraversal(PathBuf),
+    /// The path starts with a Windows drive letter, but the state has no
+    /// Wine path mapper to translate it into a path on the host.
+    UnmappedDrivePath(PathBuf),
+    /// The byte range of an edit to a condition string was out of bounds or
+    /// did not lie on character boundaries.
+    InvalidEdit(Range<usize>),
+    /// A directory had more entries than the state allows a function to
+    /// examine. The number is the limit.
+    DirectoryEntryLimitExceeded(PathBuf, usize),
+    /// Serialized condition bundle data could not be read. The string
+    /// describes what was wrong with it.
+    InvalidBundle(String),
+    /// A function needed to read the path, but the state does not allow
+    /// filesystem access.
+    FilesystemAccessDisabled(PathBuf),
+    /// More functions had unknown results than a truth table was allowed to
+    /// cover. The number is the limit.
+    TooManyUnknowns(usize),
+    /// The string is the name of a variable that a condition references but
+    /// that has no value.
+    UnboundVariable(String),
+    /// A filesystem operation on the path kept failing with a transient
+    /// error, and was given up on after the number of attempts that the
+    /// state's retry policy allowed. The error is from the last attempt.
+    RetriesExhausted(PathBuf, u32, io::Error),
+    /// The file could not be read as a zip or 7z archive of a data directory.
+    InvalidArchive(PathBuf, Box<dyn error::Error + Send + Sync + 'static>),
+    /// Evaluation was cancelled using the state's cancellation token.
+    Cancelled,
+    /// Evaluation gave up because the state's deadline passed.
+    DeadlineExceeded,
 }
 
 fn escape<I: fmt::Display>(input: I) -> String {
@@ -71,6 +109,55 @@ impl fmt::Display for Error {
                 escape_ascii(p),
                 e
             ),
+            Error::PathTraversal(p) => write!(
+                f,
+                "The path \"{}\" is not allowed as it could refer to something outside of the game's data paths",
+                escape_ascii(p)
+            ),
+            Error::UnmappedDrivePath(p) => write!(
+                f,
+                "The path \"{}\" is not allowed as it has a drive letter and there is no Wine path mapper to translate it",
+                escape_ascii(p)
+            ),
+            Error::InvalidEdit(r) => write!(
+                f,
+                "The edit range {}..{} is out of bounds or does not lie on character boundaries",
+                r.start, r.end
+            ),
+            Error::DirectoryEntryLimitExceeded(p, limit) => write!(
+                f,
+                "Gave up reading the directory \"{}\" after examining the maximum of {limit} entries",
+                escape_ascii(p)
+            ),
+            Error::InvalidBundle(reason) => {
+                write!(f, "The condition bundle could not be read: {reason}")
+            }
+            Error::FilesystemAccessDisabled(p) => write!(
+                f,
+                "The path \"{}\" could not be read as filesystem access is disabled",
+                escape_ascii(p)
+            ),
+            Error::TooManyUnknowns(limit) => write!(
+                f,
+                "The condition has more than the maximum of {limit} functions with unknown results"
+            ),
+            Error::UnboundVariable(name) => {
+                write!(f, "The variable \"${{{name}}}\" has no value")
+            }
+            Error::RetriesExhausted(p, attempts, e) => write!(
+                f,
+                "Gave up accessing the path \"{}\" after {attempts} attempts: {e}",
+                escape_ascii(p)
+            ),
+            Error::InvalidArchive(p, e) => write!(
+                f,
+                "The archive \"{}\" could not be extracted: {e}",
+                escape_ascii(p)
+            ),
+            Error::Cancelled => write!(f, "Evaluation was cancelled"),
+            Error::DeadlineExceeded => {
+                write!(f, "Evaluation gave up as it did not finish before its deadline")
+            }
         }
     }
 }
@@ -79,8 +166,8 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             Error::ParsingError(_, e) => Some(e),
-            Error::PeParsingError(_, e) => Some(e.as_ref()),
-            Error::IoError(_, e) => Some(e),
+            Error::PeParsingError(_, e) | Error::InvalidArchive(_, e) => Some(e.as_ref()),
+            Error::IoError(_, e) | Error::RetriesExhausted(_, _, e) => Some(e),
             _ => None,
         }
     }
@@ -94,20 +181,35 @@ pub enum MoreDataNeeded {
     Size(NonZeroUsize),
 }
 
+/// The expected tokens are those that were tried and not found at the input,
+/// and are only recorded for errors that could be recovered from by trying
+/// another alternative.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ParsingError<I: fmt::Debug + fmt::Display> {
     input: I,
     kind: ParsingErrorKind,
+    expected: Vec<&'static str>,
 }
 
-impl<I: fmt::Debug + fmt::Display> From<(I, ErrorKind)> for ParsingError<I> {
+impl<I: fmt::Debug + fmt::Display> ParsingError<I> {
+    /// An error for input that doesn't start with the given token.
+    pub(crate) fn missing(input: I, token: &'static str) -> Self {
+        ParsingError {
+            input,
+            kind: ParsingErrorKind::GenericParserError(ErrorKind::Tag.description().to_owned()),
+            expected: vec![token],
+        }
+    }
+}
+
+impl<I: fmt::Debug + fmt::Display + nom::Input> From<(I, ErrorKind)> for ParsingError<I> {
     fn from((input, kind): (I, ErrorKind)) -> Self {
         use nom::error::ParseError;
         ParsingError::from_error_kind(input, kind)
     }
 }
 
-impl<I: fmt::Debug + fmt::Display> From<nom::error::Error<I>> for ParsingError<I> {
+impl<I: fmt::Debug + fmt::Display + nom::Input> From<nom::error::Error<I>> for ParsingError<I> {
     fn from(error: nom::error::Error<I>) -> Self {
         use nom::error::ParseError;
         ParsingError::from_error_kind(error.input, error.code)
@@ -130,17 +232,133 @@ impl<I: fmt::Debug + fmt::Display> error::Error for ParsingError<I> {
     }
 }
 
-impl<I: fmt::Debug + fmt::Display> nom::error::ParseError<I> for ParsingError<I> {
+impl<I: fmt::Debug + fmt::Display + nom::Input> nom::error::ParseError<I> for ParsingError<I> {
     fn from_error_kind(input: I, kind: ErrorKind) -> Self {
         ParsingError {
             input,
             kind: ParsingErrorKind::GenericParserError(kind.description().to_owned()),
+            expected: Vec::new(),
         }
     }
 
     fn append(_: I, _: ErrorKind, other: Self) -> Self {
         other
     }
+
+    /// Keep the error from the alternative that got furthest through the
+    /// input, as it's most likely to be the one that was intended, and combine
+    /// the expected tokens of alternatives that got equally far.
+    fn or(mut self, other: Self) -> Self {
+        match self.input.input_len().cmp(&other.input.input_len()) {
+            Ordering::Less => self,
+            Ordering::Greater => other,
+            Ordering::Equal => {
+                for token in other.expected {
+                    if !self.expected.contains(&token) {
+                        self.expected.push(token);
+                    }
+                }
+                self
+            }
+        }
+    }
+}
+
+/// A description of where and why a condition string could not be parsed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    span: Range<usize>,
+    fragment: String,
+    expected: Vec<&'static str>,
+    kind: ParsingErrorKind,
+}
+
+impl ParseError {
+    /// Locate the error within the input that was being parsed. Errors for
+    /// unexpected input only cover the text up to the next whitespace
+    /// character, while errors for invalid input cover all of it.
+    pub(crate) fn new(input: &str, error: ParsingError<&str>) -> Self {
+        let offset = error
+            .input
+            .as_ptr()
+            .addr()
+            .checked_sub(input.as_ptr().addr())
+            .filter(|offset| offset.saturating_add(error.input.len()) <= input.len())
+            .or_else(|| input.find(error.input))
+            .unwrap_or(input.len());
+
+        let fragment = if offset.saturating_add(error.input.len()) == input.len() {
+            error
+                .input
+                .split(char::is_whitespace)
+                .next()
+                .unwrap_or_default()
+        } else {
+            error.input
+        };
+
+        ParseError {
+            span: offset..offset.saturating_add(fragment.len()),
+            fragment: fragment.to_owned(),
+            expected: error.expected,
+            kind: error.kind,
+        }
+    }
+
+    /// The byte range of the fragment in the condition string.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The text at which parsing failed, which is empty if the condition
+    /// string ended too soon.
+    pub fn fragment(&self) -> &str {
+        &self.fragment
+    }
+
+    /// The tokens that would have been accepted at the start of the fragment,
+    /// which is empty if the fragment could be parsed but was invalid.
+    pub fn expected(&self) -> &[&'static str] {
+        &self.expected
+    }
+
+    pub fn kind(&self) -> &ParsingErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Range { start, end } = self.span;
+
+        if self.expected.is_empty() {
+            write!(
+                f,
+                "\"{}\" at bytes {start}..{end} is invalid: {}",
+                escape(&self.fragment),
+                self.kind
+            )
+        } else {
+            let expected: Vec<String> = self
+                .expected
+                .iter()
+                .map(|token| format!("\"{}\"", escape(token)))
+                .collect();
+
+            write!(
+                f,
+                "Unexpected \"{}\" at bytes {start}..{end}, expected one of {}",
+                escape(&self.fragment),
+                expected.join(", ")
+            )
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.kind)
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -151,18 +369,55 @@ pub enum ParsingErrorKind {
     PathEndsInADirectorySeparator(PathBuf),
     PathIsNotInGameDirectory(PathBuf),
     GenericParserError(String),
+    /// The usize is the maximum regex length in bytes.
+    RegexTooLong(usize),
+    /// The usize is the maximum compiled regex size in bytes.
+    RegexTooLarge(usize),
+    DisallowedRegexFeature(RegexFeature),
+    /// The string is the alias name, without its `@` prefix.
+    UnknownAlias(String),
+    InvalidAliasDefinition,
+    /// The string is the text from the start of the invalid `${NAME}`
+    /// reference.
+    InvalidVariableReference(String),
+    /// The string is the variable name.
+    UndefinedVariable(String),
+    /// The string is the digest that is not 64 hexadecimal digits.
+    InvalidSha256(String),
+    /// The string is the timestamp that is not in a supported ISO-8601 form.
+    InvalidTimestamp(String),
+    /// The string is an `ini_value()` section name, key or value that no
+    /// line of an INI file could match.
+    InvalidIniArgument(String),
+    /// The string is a `registry_value()` key that doesn't start with a known
+    /// root key followed by a subkey path.
+    InvalidRegistryKey(String),
+    /// The string is a `file_glob()` pattern that contains a directory
+    /// separator.
+    InvalidGlob(String),
+    /// The usize is the maximum condition string length in bytes.
+    ExpressionTooLong(usize),
+    /// The usize is the maximum number of parenthesised expressions that may
+    /// be nested inside each other.
+    ExpressionTooDeep(usize),
 }
 
 impl ParsingErrorKind {
     pub fn at<I: fmt::Debug + fmt::Display>(self, input: I) -> ParsingError<I> {
-        ParsingError { input, kind: self }
+        ParsingError {
+            input,
+            kind: self,
+            expected: Vec::new(),
+        }
     }
 }
 
+#[cfg(feature = "regex")]
 impl From<regex::Error> for ParsingErrorKind {
     fn from(error: regex::Error) -> Self {
         match error {
             regex::Error::Syntax(s) => ParsingErrorKind::InvalidRegexSyntax(s),
+            regex::Error::CompiledTooBig(limit) => ParsingErrorKind::RegexTooLarge(limit),
             _ => ParsingErrorKind::InvalidRegexUnknown,
         }
     }
@@ -196,6 +451,55 @@ impl fmt::Display for ParsingErrorKind {
                 write!(f, "\"{}\" is not in the game directory", escape_ascii(p))
             }
             ParsingErrorKind::GenericParserError(e) => write!(f, "Error in parser: {e}"),
+            ParsingErrorKind::RegexTooLong(l) => {
+                write!(f, "The regex is longer than the limit of {l} bytes")
+            }
+            ParsingErrorKind::RegexTooLarge(l) => {
+                write!(f, "The compiled regex exceeds the size limit of {l} bytes")
+            }
+            ParsingErrorKind::DisallowedRegexFeature(feature) => {
+                write!(f, "The regex uses {feature}, which are not allowed")
+            }
+            ParsingErrorKind::UnknownAlias(name) => {
+                write!(f, "The alias \"@{name}\" is not defined")
+            }
+            ParsingErrorKind::InvalidAliasDefinition => write!(
+                f,
+                "Alias definitions must be in the form \"@name := condition\", where the name only contains ASCII letters, digits and underscores"
+            ),
+            ParsingErrorKind::InvalidVariableReference(s) => write!(
+                f,
+                "\"{s}\" does not start with a valid variable reference, which must be in the form \"${{NAME}}\", where the name only contains ASCII letters, digits and underscores"
+            ),
+            ParsingErrorKind::UndefinedVariable(name) => {
+                write!(f, "The variable \"${{{name}}}\" is not defined")
+            }
+            ParsingErrorKind::InvalidSha256(s) => {
+                write!(f, "\"{s}\" is not a SHA-256 digest of 64 hexadecimal digits")
+            }
+            ParsingErrorKind::InvalidTimestamp(s) => write!(
+                f,
+                "\"{s}\" is not an ISO-8601 timestamp in the form \"YYYY-MM-DD\" or \"YYYY-MM-DDTHH:MM:SS\", optionally followed by \"Z\" or an offset from UTC"
+            ),
+            ParsingErrorKind::InvalidIniArgument(s) => write!(
+                f,
+                "\"{s}\" can't match an INI section name, key or value, as it has leading or trailing whitespace, spans more than one line or contains a reserved character"
+            ),
+            ParsingErrorKind::InvalidRegistryKey(s) => write!(
+                f,
+                "\"{s}\" is not a registry key path that starts with HKCR, HKCU, HKLM or HKU followed by a backslash and a subkey path"
+            ),
+            ParsingErrorKind::InvalidGlob(s) => write!(
+                f,
+                "\"{s}\" is not a valid glob, as it contains a directory separator"
+            ),
+            ParsingErrorKind::ExpressionTooLong(l) => {
+                write!(f, "The condition is longer than the limit of {l} bytes")
+            }
+            ParsingErrorKind::ExpressionTooDeep(d) => write!(
+                f,
+                "The condition nests parenthesised expressions deeper than the limit of {d}"
+            ),
         }
     }
 }
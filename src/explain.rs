@@ -0,0 +1,306 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::function::{unanchored, ComparisonOperator, Function, Regex};
+use crate::{ClauseTrace, CompoundConditionTrace, ExpressionTrace, FileSystem, FunctionTrace};
+
+/// Write an explanation of a traced evaluation as a Markdown list, with the
+/// expression's result followed by the result of each of its parts and, where
+/// possible, the reason for each function's result.
+pub(crate) fn explain(trace: &ExpressionTrace, file_system: &dyn FileSystem) -> String {
+    let mut text = format!("`{}` is {}.\n", trace.expression(), outcome(trace.result()));
+
+    if !trace.compound_conditions().is_empty() {
+        text.push('\n');
+        write_expression(&mut text, trace, file_system, 0);
+    }
+
+    text
+}
+
+fn outcome(result: Option<bool>) -> &'static str {
+    match result {
+        Some(true) => "true",
+        Some(false) => "false",
+        None => "not evaluated",
+    }
+}
+
+fn write_line(text: &mut String, depth: usize, line: &str) {
+    text.push_str(&"  ".repeat(depth));
+    text.push_str("- ");
+    text.push_str(line);
+    text.push('\n');
+}
+
+fn write_expression(
+    text: &mut String,
+    trace: &ExpressionTrace,
+    file_system: &dyn FileSystem,
+    depth: usize,
+) {
+    for compound_condition in trace.compound_conditions() {
+        write_compound_condition(text, compound_condition, file_system, depth);
+    }
+}
+
+fn write_compound_condition(
+    text: &mut String,
+    trace: &CompoundConditionTrace,
+    file_system: &dyn FileSystem,
+    depth: usize,
+) {
+    if let [clause] = trace.clauses() {
+        write_clause(text, clause, file_system, depth);
+        return;
+    }
+
+    let summary = match trace.result() {
+        Some(true) => "All of these are true:",
+        Some(false) => "Not all of these are true:",
+        None => "These were not evaluated, as the result was already known:",
+    };
+    write_line(text, depth, summary);
+
+    let depth = depth.saturating_add(1);
+    for clause in trace.clauses() {
+        write_clause(text, clause, file_system, depth);
+    }
+}
+
+fn write_clause(
+    text: &mut String,
+    clause: &ClauseTrace,
+    file_system: &dyn FileSystem,
+    depth: usize,
+) {
+    let not = if clause.is_negated() { "not " } else { "" };
+    match clause {
+        ClauseTrace::Function(f) | ClauseTrace::NotFunction(f) => {
+            let mut line = format!("`{not}{}` ", f.function());
+            match f.result() {
+                None => line.push_str("was not evaluated, as the result was already known."),
+                Some(result) => {
+                    line.push_str("is ");
+                    line.push_str(outcome(clause.result()));
+                    if let Some(reason) = describe(f, file_system, result) {
+                        line.push_str(": ");
+                        line.push_str(&reason);
+                    }
+                    line.push('.');
+                }
+            }
+            write_line(text, depth, &line);
+        }
+        ClauseTrace::Expression(e) | ClauseTrace::NotExpression(e) => {
+            let line = format!(
+                "`{not}({})` is {}:",
+                e.expression(),
+                outcome(clause.result())
+            );
+            write_line(text, depth, &line);
+            write_expression(text, e, file_system, depth.saturating_add(1));
+        }
+    }
+}
+
+/// Describe why a function that was evaluated had the given result, if its
+/// result can be explained more clearly than by restating it.
+fn describe(trace: &FunctionTrace, file_system: &dyn FileSystem, result: bool) -> Option<String> {
+    let function = trace.bound_function();
+
+    if !result {
+        if let Some(missing) = trace
+            .resolved_paths()
+            .find(|path| !file_system.exists(path).unwrap_or(false))
+        {
+            let path = function.paths().next().unwrap_or(missing);
+            return Some(format!("{} was not found in any data path", quoted(path)));
+        }
+    }
+
+    let is = if result { "is" } else { "is not" };
+    let description = match function {
+        Function::FilePath(p) => {
+            let resolved = trace.resolved_paths().next().unwrap_or(p);
+            format!("{} was found at {}", quoted(p), quoted(resolved))
+        }
+        Function::FileRegex(p, r) | Function::RecursiveFileRegex(p, r, _) => {
+            let entries = if result { "an entry" } else { "no entry" };
+            format!("{entries} in {} matches {}", directory(p), quoted_regex(r))
+        }
+        Function::Many(p, r) | Function::RecursiveMany(p, r, _) => {
+            let entries = if result {
+                "more than one entry"
+            } else {
+                "fewer than two entries"
+            };
+            format!("{entries} in {} match {}", directory(p), quoted_regex(r))
+        }
+        Function::Count(p, r, comparator, count) => format!(
+            "the number of entries in {} that match {} {}",
+            directory(p),
+            quoted_regex(r),
+            comparison(result, *comparator, count)
+        ),
+        Function::Readable(p) => format!("{} {is} readable", quoted(p)),
+        Function::Writable(p) => format!("{} {is} writable", quoted(p)),
+        Function::IsExecutable(p) => format!("{} {is} an executable", quoted(p)),
+        Function::ActivePath(p) => format!("{} {is} an active plugin", quoted(p)),
+        Function::ActiveRegex(r) => {
+            let plugins = if result {
+                "an active plugin matches"
+            } else {
+                "no active plugin matches"
+            };
+            format!("{plugins} {}", quoted_regex(r))
+        }
+        Function::ManyActive(r) => {
+            let plugins = if result {
+                "more than one active plugin matches"
+            } else {
+                "fewer than two active plugins match"
+            };
+            format!("{plugins} {}", quoted_regex(r))
+        }
+        Function::ActiveCount(r, comparator, count) => format!(
+            "the number of active plugins that match {} {}",
+            quoted_regex(r),
+            comparison(result, *comparator, count)
+        ),
+        Function::IsMaster(p) => format!("{} {is} a master plugin", quoted(p)),
+        Function::IsLightPlugin(p) => format!("{} {is} a light plugin", quoted(p)),
+        Function::IsMediumPlugin(p) => format!("{} {is} a medium plugin", quoted(p)),
+        Function::IsValidPlugin(p) => format!("{} {is} a valid plugin", quoted(p)),
+        Function::Checksum(p, crcs) => {
+            let crcs = crcs
+                .iter()
+                .map(|crc| format!("{crc:08X}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let matches = if result { "matches" } else { "doesn't match" };
+            format!("the CRC of {} {matches} {crcs}", quoted(p))
+        }
+        Function::FileSize(p, size, comparator) => format!(
+            "the size of {} {}",
+            quoted(p),
+            comparison(result, *comparator, size)
+        ),
+        Function::RecordCount(p, comparator, count) => format!(
+            "the number of records in {} {}",
+            quoted(p),
+            comparison(result, *comparator, count)
+        ),
+        Function::HeaderVersion(p, comparator, version) => format!(
+            "the header version of {} {}",
+            quoted(p),
+            comparison(result, *comparator, version)
+        ),
+        Function::Version(p, version, comparator) => format!(
+            "the version of {} {}",
+            quoted(p),
+            comparison(result, *comparator, version)
+        ),
+        Function::ProductVersion(p, version, comparator) => format!(
+            "the product version of {} {}",
+            quoted(p),
+            comparison(result, *comparator, version)
+        ),
+        _ => return None,
+    };
+
+    Some(description)
+}
+
+fn quoted(path: &Path) -> String {
+    format!("\"{}\"", path.display())
+}
+
+fn quoted_regex(regex: &Regex) -> String {
+    format!("\"{}\"", unanchored(regex))
+}
+
+fn directory(path: &Path) -> String {
+    if path.as_os_str().is_empty() {
+        "the data path".to_owned()
+    } else {
+        quoted(path)
+    }
+}
+
+fn comparison(result: bool, comparator: ComparisonOperator, value: &dyn fmt::Display) -> String {
+    let satisfies = if result {
+        "satisfies"
+    } else {
+        "doesn't satisfy"
+    };
+    format!("{satisfies} {comparator} {value}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use crate::{Expression, GameType, State};
+
+    fn state() -> State {
+        State::new(GameType::Oblivion, PathBuf::from(".")).with_active_plugins(&["Blank.esp"])
+    }
+
+    fn explain(expression: &str) -> String {
+        Expression::from_str(expression)
+            .unwrap()
+            .explain(&state())
+            .unwrap()
+    }
+
+    #[test]
+    fn explain_should_give_the_reason_for_a_missing_file() {
+        assert_eq!(
+            "`file(\"x.esp\")` is false.\n\n- `file(\"x.esp\")` is false: \"x.esp\" was not found in any data path.\n",
+            explain("file(\"x.esp\")")
+        );
+    }
+
+    #[test]
+    fn explain_should_list_the_results_of_each_part_of_the_expression() {
+        assert_eq!(
+            "`active(\"Blank.esp\") and not file(\"Cargo.toml\") or (many_active(\"Blank\\.esp\"))` is false.
+
+- Not all of these are true:
+  - `active(\"Blank.esp\")` is true: \"Blank.esp\" is an active plugin.
+  - `not file(\"Cargo.toml\")` is false: \"Cargo.toml\" was found at \"./Cargo.toml\".
+- `(many_active(\"Blank\\.esp\"))` is false:
+  - `many_active(\"Blank\\.esp\")` is false: fewer than two active plugins match \"Blank\\.esp\".
+",
+            explain(
+                "active(\"Blank.esp\") and not file(\"Cargo.toml\") or (many_active(\"Blank\\.esp\"))"
+            )
+        );
+    }
+
+    #[test]
+    fn explain_should_say_which_functions_were_not_evaluated() {
+        assert_eq!(
+            "`active(\"Blank.esp\") or checksum(\"Cargo.toml\", DEADBEEF)` is true.
+
+- `active(\"Blank.esp\")` is true: \"Blank.esp\" is an active plugin.
+- `checksum(\"Cargo.toml\", DEADBEEF)` was not evaluated, as the result was already known.
+",
+            explain("active(\"Blank.esp\") or checksum(\"Cargo.toml\", DEADBEEF)")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn explain_should_restate_results_that_it_cannot_describe() {
+        assert_eq!(
+            "`file_content_contains(\"Cargo.toml\", \"xyz\\d{20}\")` is false.
+
+- `file_content_contains(\"Cargo.toml\", \"xyz\\d{20}\")` is false.
+",
+            explain("file_content_contains(\"Cargo.toml\", \"xyz\\d{20}\")")
+        );
+    }
+}
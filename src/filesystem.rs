@@ -0,0 +1,445 @@
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+/// A reader that can also seek, as needed to read data from part of a file.
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// The filesystem that functions read from, which can be given to a
+/// [State](crate::State) using
+/// [State::set_file_system](crate::State::set_file_system) so that conditions
+/// are evaluated against a virtual view of the game's files, e.g. one that
+/// overlays mod directories, instead of what is on disk.
+///
+/// Paths are given as they were resolved against the state's data paths. Errors
+/// that are likely to be transient are retried according to the state's
+/// [RetryPolicy](crate::RetryPolicy), and other errors are treated as the path
+/// being missing or unreadable.
+pub trait FileSystem: fmt::Debug + Send + Sync {
+    /// Get the metadata of the file or directory at the given path, following
+    /// symbolic links.
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// List the entries in the directory at the given path, in any order.
+    fn read_dir(
+        &self,
+        path: &Path,
+    ) -> io::Result<Box<dyn Iterator<Item = io::Result<DirEntry>> + '_>>;
+
+    /// Open the file at the given path for reading.
+    fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek>>;
+
+    /// Check if there is a file or directory at the given path, giving an
+    /// error if that can't be determined.
+    fn exists(&self, path: &Path) -> io::Result<bool> {
+        match self.metadata(path) {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Read the whole of the file at the given path.
+    fn read(&self, path: &Path) -> io::Result<Box<dyn AsRef<[u8]>>> {
+        let mut bytes = Vec::new();
+        self.open(path)?.read_to_end(&mut bytes)?;
+        Ok(Box::new(bytes))
+    }
+
+    /// Memory-map the file at the given path, if the filesystem supports it,
+    /// so that a large file can be hashed without copying it into a buffer.
+    /// By default, files aren't memory-mapped and `None` is returned.
+    fn memory_map(&self, _path: &Path) -> io::Result<Option<Box<dyn AsRef<[u8]>>>> {
+        Ok(None)
+    }
+
+    /// Check that the file at the given path can be opened for writing, or
+    /// that files can be created in the directory at the given path. By
+    /// default, this checks that the path isn't read-only.
+    fn check_writable(&self, path: &Path) -> io::Result<()> {
+        if self.metadata(path)?.is_readonly() {
+            Err(io::ErrorKind::PermissionDenied.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The metadata of a file or directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    is_dir: bool,
+    len: u64,
+    modified: Option<SystemTime>,
+    is_readonly: bool,
+}
+
+impl Metadata {
+    /// The metadata of a file with the given size in bytes.
+    pub fn file(len: u64) -> Self {
+        Metadata {
+            is_dir: false,
+            len,
+            modified: None,
+            is_readonly: false,
+        }
+    }
+
+    pub fn directory() -> Self {
+        Metadata {
+            is_dir: true,
+            len: 0,
+            modified: None,
+            is_readonly: false,
+        }
+    }
+
+    #[must_use]
+    pub fn with_modified(mut self, modified: SystemTime) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+
+    #[must_use]
+    pub fn with_readonly(mut self, is_readonly: bool) -> Self {
+        self.is_readonly = is_readonly;
+        self
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    pub fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+
+    /// The file's size in bytes.
+    #[expect(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// When the file or directory was last modified, if that's known.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    pub fn is_readonly(&self) -> bool {
+        self.is_readonly
+    }
+}
+
+impl From<std::fs::Metadata> for Metadata {
+    fn from(metadata: std::fs::Metadata) -> Self {
+        Metadata {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            is_readonly: metadata.permissions().readonly(),
+        }
+    }
+}
+
+/// An entry in a directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    file_name: OsString,
+    is_dir: bool,
+}
+
+impl DirEntry {
+    /// An entry with the given filename, which is a directory if `is_dir` is
+    /// true. Symbolic links to directories are not directories, so that
+    /// recursive searches don't follow them.
+    pub fn new<T: Into<OsString>>(file_name: T, is_dir: bool) -> Self {
+        DirEntry {
+            file_name: file_name.into(),
+            is_dir,
+        }
+    }
+
+    pub fn file_name(&self) -> &OsStr {
+        &self.file_name
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+}
+
+/// The filesystem on disk, which states use by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StdFileSystem;
+
+impl FileSystem for StdFileSystem {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        std::fs::metadata(path).map(Metadata::from)
+    }
+
+    fn read_dir(
+        &self,
+        path: &Path,
+    ) -> io::Result<Box<dyn Iterator<Item = io::Result<DirEntry>> + '_>> {
+        let entries = std::fs::read_dir(path)?.map(|entry| {
+            entry.map(|e| {
+                let is_dir = e.file_type().is_ok_and(|t| t.is_dir());
+                DirEntry::new(e.file_name(), is_dir)
+            })
+        });
+
+        Ok(Box::new(entries))
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek>> {
+        Ok(Box::new(File::open(path)?))
+    }
+
+    fn exists(&self, path: &Path) -> io::Result<bool> {
+        path.try_exists()
+    }
+
+    /// Files are memory-mapped where that's supported, as executables can be
+    /// large and only their resources are read.
+    fn read(&self, path: &Path) -> io::Result<Box<dyn AsRef<[u8]>>> {
+        #[cfg(any(windows, unix))]
+        return Ok(Box::new(pelite::FileMap::open(path)?));
+
+        #[cfg(not(any(windows, unix)))]
+        return Ok(Box::new(std::fs::read(path)?));
+    }
+
+    fn memory_map(&self, path: &Path) -> io::Result<Option<Box<dyn AsRef<[u8]>>>> {
+        #[cfg(any(windows, unix))]
+        return Ok(Some(Box::new(MappedFile::open(path)?)));
+
+        #[cfg(not(any(windows, unix)))]
+        return Ok(None);
+    }
+
+    /// Files are checked by opening them for writing without changing them.
+    /// Directory permissions don't reliably say whether files can be created
+    /// in a directory, so a directory is writable if an empty file can be
+    /// created in it and then removed.
+    fn check_writable(&self, path: &Path) -> io::Result<()> {
+        if path.is_dir() {
+            let probe_path = writable_probe_path(path);
+            File::options()
+                .write(true)
+                .create_new(true)
+                .open(&probe_path)?;
+
+            std::fs::remove_file(&probe_path)
+        } else {
+            File::options().write(true).open(path).map(|_| ())
+        }
+    }
+}
+
+/// A memory-mapped file. Files are mapped in whole pages, so the mapping is
+/// limited to the file's length when it's read.
+#[cfg(any(windows, unix))]
+struct MappedFile {
+    map: pelite::FileMap,
+    len: usize,
+}
+
+#[cfg(any(windows, unix))]
+impl MappedFile {
+    fn open(path: &Path) -> io::Result<Self> {
+        let len = usize::try_from(std::fs::metadata(path)?.len()).map_err(io::Error::other)?;
+        let map = pelite::FileMap::open(path)?;
+
+        Ok(MappedFile { map, len })
+    }
+}
+
+#[cfg(any(windows, unix))]
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        let bytes = self.map.as_ref();
+        bytes.get(..self.len).unwrap_or(bytes)
+    }
+}
+
+/// Get a path in the given directory that no other writable() check will use,
+/// even in another thread or process.
+fn writable_probe_path(directory: &Path) -> PathBuf {
+    static PROBE_COUNT: AtomicU64 = AtomicU64::new(0);
+
+    let count = PROBE_COUNT.fetch_add(1, Ordering::Relaxed);
+
+    directory.join(format!(
+        ".loot-condition-interpreter-{}-{count}.tmp",
+        std::process::id()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::io::Cursor;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use crate::{Expression, GameType, State};
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct MemoryFileSystem {
+        files: BTreeMap<PathBuf, Vec<u8>>,
+    }
+
+    impl MemoryFileSystem {
+        fn with_file(mut self, path: &str, contents: &[u8]) -> Self {
+            self.files.insert(PathBuf::from(path), contents.to_vec());
+            self
+        }
+
+        fn is_dir(&self, path: &Path) -> bool {
+            self.files.keys().any(|p| p != path && p.starts_with(path))
+        }
+    }
+
+    impl FileSystem for MemoryFileSystem {
+        fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+            if let Some(contents) = self.files.get(path) {
+                let len = contents.len().try_into().map_err(io::Error::other)?;
+                Ok(Metadata::file(len))
+            } else if self.is_dir(path) {
+                Ok(Metadata::directory())
+            } else {
+                Err(io::ErrorKind::NotFound.into())
+            }
+        }
+
+        fn read_dir(
+            &self,
+            path: &Path,
+        ) -> io::Result<Box<dyn Iterator<Item = io::Result<DirEntry>> + '_>> {
+            if !self.is_dir(path) {
+                return Err(io::ErrorKind::NotFound.into());
+            }
+
+            let mut entries = Vec::new();
+            for file_path in self.files.keys() {
+                if let Ok(relative) = file_path.strip_prefix(path) {
+                    let mut components = relative.components();
+                    if let Some(name) = components.next() {
+                        let entry = DirEntry::new(name.as_os_str(), components.next().is_some());
+                        if !entries.contains(&entry) {
+                            entries.push(entry);
+                        }
+                    }
+                }
+            }
+
+            Ok(Box::new(entries.into_iter().map(Ok)))
+        }
+
+        fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek>> {
+            match self.files.get(path) {
+                Some(contents) => Ok(Box::new(Cursor::new(contents.clone()))),
+                None => Err(io::ErrorKind::NotFound.into()),
+            }
+        }
+    }
+
+    fn state(file_system: MemoryFileSystem) -> State {
+        let mut state = State::new(GameType::Oblivion, PathBuf::from("data"));
+        state.set_file_system(Arc::new(file_system));
+        state
+    }
+
+    fn eval(expression: &str, state: &State) -> bool {
+        Expression::from_str(expression)
+            .unwrap()
+            .eval(state)
+            .unwrap()
+    }
+
+    #[test]
+    fn functions_should_read_from_the_state_file_system() {
+        let state = state(
+            MemoryFileSystem::default()
+                .with_file("data/a.txt", b"hello")
+                .with_file("data/textures/b.dds", b"")
+                .with_file("data/textures/c.dds", b""),
+        );
+
+        assert!(eval("file(\"a.txt\")", &state));
+        assert!(eval("file(\"A.TXT\")", &state));
+        assert!(!eval("file(\"d.txt\")", &state));
+        assert!(eval("file_size(\"a.txt\", 5, ==)", &state));
+        assert!(eval("checksum(\"a.txt\", 3610A686)", &state));
+        assert!(eval("many(\"textures/.*\\.dds\")", &state));
+        assert!(eval("readable(\"textures\")", &state));
+    }
+
+    #[test]
+    fn set_file_system_should_clear_cached_results() {
+        let mut state = state(MemoryFileSystem::default().with_file("data/a.txt", b""));
+        assert!(eval("file(\"a.txt\")", &state));
+
+        state.set_file_system(Arc::new(MemoryFileSystem::default()));
+
+        assert!(!eval("file(\"a.txt\")", &state));
+    }
+
+    #[test]
+    fn default_check_writable_should_error_if_the_path_is_read_only() {
+        #[derive(Debug)]
+        struct ReadOnlyFileSystem;
+
+        impl FileSystem for ReadOnlyFileSystem {
+            fn metadata(&self, _: &Path) -> io::Result<Metadata> {
+                Ok(Metadata::file(0).with_readonly(true))
+            }
+
+            fn read_dir(
+                &self,
+                _: &Path,
+            ) -> io::Result<Box<dyn Iterator<Item = io::Result<DirEntry>> + '_>> {
+                Ok(Box::new(std::iter::empty()))
+            }
+
+            fn open(&self, _: &Path) -> io::Result<Box<dyn ReadSeek>> {
+                Err(io::ErrorKind::Unsupported.into())
+            }
+        }
+
+        assert!(ReadOnlyFileSystem.check_writable(Path::new("a")).is_err());
+        assert!(MemoryFileSystem::default()
+            .with_file("a", b"")
+            .check_writable(Path::new("a"))
+            .is_ok());
+    }
+
+    #[test]
+    fn std_file_system_metadata_should_describe_files_and_directories() {
+        let file = StdFileSystem.metadata(Path::new("Cargo.toml")).unwrap();
+        let directory = StdFileSystem.metadata(Path::new("src")).unwrap();
+
+        assert!(file.is_file());
+        assert!(file.len() > 0u64);
+        assert!(file.modified().is_some());
+        assert!(directory.is_dir());
+        assert!(!StdFileSystem.exists(Path::new("missing")).unwrap());
+    }
+
+    #[test]
+    #[cfg(any(windows, unix))]
+    fn std_file_system_memory_map_should_map_only_the_file_contents() {
+        let path = Path::new("Cargo.toml");
+        let map = StdFileSystem.memory_map(path).unwrap().unwrap();
+
+        assert_eq!(std::fs::read(path).unwrap(), (*map).as_ref());
+    }
+}
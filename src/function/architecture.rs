@@ -0,0 +1,281 @@
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::Architecture;
+use crate::FileSystem;
+
+const PE_MAGIC: &[u8] = b"MZ";
+const PE_SIGNATURE: &[u8] = b"PE\0\0";
+const PE_HEADER_OFFSET_OFFSET: u64 = 0x3C;
+const IMAGE_FILE_MACHINE_I386: u16 = 0x014C;
+const IMAGE_FILE_MACHINE_AMD64: u16 = 0x8664;
+
+const ELF_MAGIC: &[u8] = b"\x7FELF";
+const ELF_DATA_BIG_ENDIAN: u8 = 2;
+const ELF_MACHINE_OFFSET: u64 = 18;
+const EM_386: u16 = 3;
+const EM_X86_64: u16 = 62;
+
+const MACH_O_MAGICS: [u32; 2] = [0xFEED_FACE, 0xFEED_FACF];
+const FAT_MAGIC: u32 = 0xCAFE_BABE;
+const FAT_MAGIC_64: u32 = 0xCAFE_BABF;
+const FAT_ARCH_SIZE: u64 = 20;
+const FAT_ARCH_64_SIZE: u64 = 32;
+/// Java class files share the fat Mach-O magic number, but where a fat binary
+/// has its number of architectures, they have their version number, which is
+/// always larger than this.
+const MAX_FAT_ARCHITECTURES: u32 = 32;
+const CPU_TYPE_X86: u32 = 7;
+const CPU_TYPE_X86_64: u32 = 0x0100_0007;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Endianness {
+    Little,
+    Big,
+}
+
+/// Read the architectures that the executable at the given path is built for
+/// from its header. PE, ELF and Mach-O executables are supported, including
+/// universal Mach-O binaries, which can be built for more than one
+/// architecture. Files in other formats or for other architectures have no
+/// architectures.
+pub(super) fn read_architectures(
+    file_system: &dyn FileSystem,
+    path: &Path,
+) -> io::Result<Vec<Architecture>> {
+    let mut file = file_system.open(path)?;
+
+    let Some(magic) = read_at(&mut file, 0, 4)? else {
+        return Ok(Vec::new());
+    };
+
+    if magic.starts_with(PE_MAGIC) {
+        read_pe_architecture(&mut file).map(|a| a.into_iter().collect())
+    } else if magic == ELF_MAGIC {
+        read_elf_architecture(&mut file).map(|a| a.into_iter().collect())
+    } else {
+        read_mach_o_architectures(&mut file, &magic)
+    }
+}
+
+fn read_pe_architecture<R: Read + Seek>(file: &mut R) -> io::Result<Option<Architecture>> {
+    let Some(header_offset) = read_u32_at(file, PE_HEADER_OFFSET_OFFSET, Endianness::Little)?
+    else {
+        return Ok(None);
+    };
+
+    let Some(header) = read_at(file, header_offset.into(), 6)? else {
+        return Ok(None);
+    };
+
+    let machine = header
+        .strip_prefix(PE_SIGNATURE)
+        .and_then(|m| m.try_into().ok())
+        .map(u16::from_le_bytes);
+
+    Ok(match machine {
+        Some(IMAGE_FILE_MACHINE_I386) => Some(Architecture::X86),
+        Some(IMAGE_FILE_MACHINE_AMD64) => Some(Architecture::X64),
+        _ => None,
+    })
+}
+
+fn read_elf_architecture<R: Read + Seek>(file: &mut R) -> io::Result<Option<Architecture>> {
+    let endianness = match read_at(file, 5, 1)?.as_deref() {
+        Some([ELF_DATA_BIG_ENDIAN]) => Endianness::Big,
+        _ => Endianness::Little,
+    };
+
+    let Some(machine) = read_at(file, ELF_MACHINE_OFFSET, 2)? else {
+        return Ok(None);
+    };
+    let Ok(machine) = machine.as_slice().try_into() else {
+        return Ok(None);
+    };
+    let machine = match endianness {
+        Endianness::Little => u16::from_le_bytes(machine),
+        Endianness::Big => u16::from_le_bytes(machine).swap_bytes(),
+    };
+
+    Ok(match machine {
+        EM_386 => Some(Architecture::X86),
+        EM_X86_64 => Some(Architecture::X64),
+        _ => None,
+    })
+}
+
+fn read_mach_o_architectures<R: Read + Seek>(
+    file: &mut R,
+    magic: &[u8],
+) -> io::Result<Vec<Architecture>> {
+    let Ok(magic) = magic.try_into() else {
+        return Ok(Vec::new());
+    };
+
+    if MACH_O_MAGICS.contains(&u32::from_le_bytes(magic)) {
+        let cpu_type = read_u32_at(file, 4, Endianness::Little)?;
+        return Ok(cpu_type.and_then(mach_o_architecture).into_iter().collect());
+    }
+
+    let arch_size = match u32::from_le_bytes(magic).swap_bytes() {
+        magic if MACH_O_MAGICS.contains(&magic) => {
+            let cpu_type = read_u32_at(file, 4, Endianness::Big)?;
+            return Ok(cpu_type.and_then(mach_o_architecture).into_iter().collect());
+        }
+        FAT_MAGIC => FAT_ARCH_SIZE,
+        FAT_MAGIC_64 => FAT_ARCH_64_SIZE,
+        _ => return Ok(Vec::new()),
+    };
+
+    let arch_count = match read_u32_at(file, 4, Endianness::Big)? {
+        Some(count) if count <= MAX_FAT_ARCHITECTURES => count,
+        _ => return Ok(Vec::new()),
+    };
+
+    let mut architectures = Vec::new();
+    for index in 0..u64::from(arch_count) {
+        let offset = index.saturating_mul(arch_size).saturating_add(8);
+        if let Some(architecture) =
+            read_u32_at(file, offset, Endianness::Big)?.and_then(mach_o_architecture)
+        {
+            architectures.push(architecture);
+        }
+    }
+
+    Ok(architectures)
+}
+
+fn mach_o_architecture(cpu_type: u32) -> Option<Architecture> {
+    match cpu_type {
+        CPU_TYPE_X86 => Some(Architecture::X86),
+        CPU_TYPE_X86_64 => Some(Architecture::X64),
+        _ => None,
+    }
+}
+
+fn read_u32_at<R: Read + Seek>(
+    file: &mut R,
+    offset: u64,
+    endianness: Endianness,
+) -> io::Result<Option<u32>> {
+    let bytes = read_at(file, offset, 4)?.and_then(|b| b.as_slice().try_into().ok());
+
+    Ok(bytes.map(|b| match endianness {
+        Endianness::Little => u32::from_le_bytes(b),
+        Endianness::Big => u32::from_le_bytes(b).swap_bytes(),
+    }))
+}
+
+/// Read the given number of bytes starting at the given offset, or None if the
+/// file ends before then.
+fn read_at<R: Read + Seek>(
+    file: &mut R,
+    offset: u64,
+    length: usize,
+) -> io::Result<Option<Vec<u8>>> {
+    file.seek(SeekFrom::Start(offset))?;
+
+    let mut bytes = vec![0; length];
+    match file.read_exact(&mut bytes) {
+        Ok(()) => Ok(Some(bytes)),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::StdFileSystem;
+
+    use super::*;
+
+    fn architectures_of(bytes: &[u8]) -> Vec<Architecture> {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("executable");
+        std::fs::write(&path, bytes).unwrap();
+
+        read_architectures(&StdFileSystem, &path).unwrap()
+    }
+
+    #[test]
+    fn read_architectures_should_read_pe_machine_types() {
+        assert_eq!(
+            vec![Architecture::X86],
+            read_architectures(&StdFileSystem, Path::new("tests/libloot_win32/loot.dll")).unwrap()
+        );
+        assert_eq!(
+            vec![Architecture::X64],
+            read_architectures(&StdFileSystem, Path::new("tests/libloot_win64/loot.dll")).unwrap()
+        );
+    }
+
+    #[test]
+    fn read_architectures_should_read_the_machine_type_after_the_pe_signature() {
+        let mut pe = b"MZ".to_vec();
+        pe.resize(0x3C, 0);
+        pe.extend(0x40_u32.to_le_bytes());
+        pe.extend(PE_SIGNATURE);
+        pe.extend(IMAGE_FILE_MACHINE_AMD64.to_le_bytes());
+        assert_eq!(vec![Architecture::X64], architectures_of(&pe));
+
+        if let Some(signature) = pe.get_mut(0x40) {
+            *signature = b'N';
+        }
+        assert!(architectures_of(&pe).is_empty());
+    }
+
+    #[test]
+    fn read_architectures_should_read_elf_machine_types() {
+        let mut header = b"\x7FELF\x02\x01".to_vec();
+        header.resize(18, 0);
+
+        let mut x64 = header.clone();
+        x64.extend(EM_X86_64.to_le_bytes());
+        assert_eq!(vec![Architecture::X64], architectures_of(&x64));
+
+        let mut x86 = header;
+        if let Some(data) = x86.get_mut(5) {
+            *data = ELF_DATA_BIG_ENDIAN;
+        }
+        x86.extend(EM_386.swap_bytes().to_le_bytes());
+        assert_eq!(vec![Architecture::X86], architectures_of(&x86));
+    }
+
+    #[test]
+    fn read_architectures_should_read_mach_o_cpu_types() {
+        let mut x64 = 0xFEED_FACF_u32.to_le_bytes().to_vec();
+        x64.extend(CPU_TYPE_X86_64.to_le_bytes());
+        assert_eq!(vec![Architecture::X64], architectures_of(&x64));
+
+        let mut fat = FAT_MAGIC.swap_bytes().to_le_bytes().to_vec();
+        fat.extend(3_u32.swap_bytes().to_le_bytes());
+        for cpu_type in [CPU_TYPE_X86, 0x0100_000C, CPU_TYPE_X86_64] {
+            fat.extend(cpu_type.swap_bytes().to_le_bytes());
+            fat.extend([0; 16]);
+        }
+        assert_eq!(
+            vec![Architecture::X86, Architecture::X64],
+            architectures_of(&fat)
+        );
+    }
+
+    #[test]
+    fn read_architectures_should_return_nothing_for_other_files() {
+        assert!(read_architectures(&StdFileSystem, Path::new("Cargo.toml"))
+            .unwrap()
+            .is_empty());
+        assert!(architectures_of(b"MZ").is_empty());
+        assert!(architectures_of(&[]).is_empty());
+
+        let mut java_class = FAT_MAGIC.swap_bytes().to_le_bytes().to_vec();
+        java_class.extend([0, 0, 0, 52]);
+        assert!(architectures_of(&java_class).is_empty());
+    }
+
+    #[test]
+    fn read_architectures_should_error_if_the_file_does_not_exist() {
+        assert!(read_architectures(&StdFileSystem, Path::new("missing")).is_err());
+    }
+}
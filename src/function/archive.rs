@@ -0,0 +1,489 @@
+use std::collections::HashSet;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::FileSystem;
+
+const MORROWIND_BSA_MAGIC: &[u8] = b"\x00\x01\x00\x00";
+const BSA_MAGIC: &[u8] = b"BSA\0";
+const BA2_MAGIC: &[u8] = b"BTDX";
+
+const BSA_VERSION_SKYRIM_SE: u32 = 105;
+const BSA_FOLDER_RECORD_SIZE: u64 = 16;
+const BSA_SKYRIM_SE_FOLDER_RECORD_SIZE: u64 = 24;
+const BSA_FILE_RECORD_SIZE: u64 = 16;
+const BSA_INCLUDE_DIRECTORY_NAMES: u32 = 0x1;
+const BSA_INCLUDE_FILE_NAMES: u32 = 0x2;
+
+const MORROWIND_BSA_HEADER_SIZE: u64 = 12;
+const MORROWIND_BSA_FILE_RECORD_SIZE: u64 = 8;
+
+/// The paths of the files in a BSA or BA2 archive, normalised by
+/// [normalise_entry_path].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ArchiveIndex(HashSet<String>);
+
+impl ArchiveIndex {
+    pub(crate) fn contains(&self, path: &str) -> bool {
+        self.0.contains(&normalise_entry_path(path))
+    }
+
+    /// Iterate over the normalised paths of the files in the archive, in no
+    /// particular order.
+    pub(crate) fn entries(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+impl FromIterator<String> for ArchiveIndex {
+    fn from_iter<T: IntoIterator<Item = String>>(iter: T) -> Self {
+        ArchiveIndex(iter.into_iter().map(|p| normalise_entry_path(&p)).collect())
+    }
+}
+
+/// Archives store paths with backslashes as separators and are read without
+/// regard to case, so paths are compared lowercased and with forward slashes.
+pub(crate) fn normalise_entry_path(path: &str) -> String {
+    path.replace('\\', "/").to_lowercase()
+}
+
+/// Read the paths of the files in the archive at the given path from its
+/// file name tables, without reading any file data. Morrowind BSAs, the BSAs
+/// used by later games up to Skyrim Special Edition, and the BA2s used by
+/// Fallout 4, Fallout 76 and Starfield are supported. Files in other formats
+/// have no index, and BSAs that don't store their folder and file names have
+/// an empty index.
+pub(super) fn read_archive_index(
+    file_system: &dyn FileSystem,
+    path: &Path,
+) -> io::Result<Option<ArchiveIndex>> {
+    let mut reader = BufReader::new(file_system.open(path)?);
+
+    let mut magic = [0; 4];
+    let result = reader
+        .read_exact(&mut magic)
+        .and_then(|()| match magic.as_slice() {
+            MORROWIND_BSA_MAGIC => read_morrowind_bsa_index(&mut reader).map(Some),
+            BSA_MAGIC => read_bsa_index(&mut reader).map(Some),
+            BA2_MAGIC => read_ba2_index(&mut reader).map(Some),
+            _ => Ok(None),
+        });
+
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        result => result,
+    }
+}
+
+/// Read the format version from the header of the archive at the given path.
+/// Morrowind BSAs have version 256 (0x100), later BSAs have versions 103 to
+/// 105, and BA2s have versions 1 to 3 and 7 to 8. Files in other formats have
+/// no version.
+pub(super) fn read_archive_version(
+    file_system: &dyn FileSystem,
+    path: &Path,
+) -> io::Result<Option<u32>> {
+    let mut reader = file_system.open(path)?;
+
+    let mut header = [0; 8];
+    match reader.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let [m1, m2, m3, m4, v1, v2, v3, v4] = header;
+    let magic = [m1, m2, m3, m4];
+
+    Ok(match magic.as_slice() {
+        MORROWIND_BSA_MAGIC => Some(u32::from_le_bytes(magic)),
+        BSA_MAGIC | BA2_MAGIC => Some(u32::from_le_bytes([v1, v2, v3, v4])),
+        _ => None,
+    })
+}
+
+/// Morrowind BSAs store the offset of each file's name in a block of
+/// null-terminated names that ends where the table of name hashes begins.
+fn read_morrowind_bsa_index<R: Read + Seek>(reader: &mut R) -> io::Result<ArchiveIndex> {
+    let hash_table_offset = u64::from(read_u32(reader)?);
+    let file_count = u64::from(read_u32(reader)?);
+
+    let name_offsets_start = MORROWIND_BSA_HEADER_SIZE
+        .saturating_add(file_count.saturating_mul(MORROWIND_BSA_FILE_RECORD_SIZE));
+    let names_start = name_offsets_start.saturating_add(file_count.saturating_mul(4));
+    let names_end = MORROWIND_BSA_HEADER_SIZE.saturating_add(hash_table_offset);
+
+    reader.seek(SeekFrom::Start(name_offsets_start))?;
+    let mut name_offsets = Vec::new();
+    for _ in 0..file_count {
+        name_offsets.push(read_u32(reader)?);
+    }
+
+    let names = read_bytes(reader, names_end.saturating_sub(names_start))?;
+
+    name_offsets
+        .into_iter()
+        .map(|offset| {
+            usize::try_from(offset)
+                .ok()
+                .and_then(|offset| names.get(offset..))
+                .and_then(|name| name.split(|b| *b == 0).next())
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .ok_or_else(|| io::ErrorKind::UnexpectedEof.into())
+        })
+        .collect()
+}
+
+/// Later BSAs store each folder's name before the records of its files, and
+/// then the names of all the files in a single block, in the same order.
+fn read_bsa_index<R: Read + Seek>(reader: &mut R) -> io::Result<ArchiveIndex> {
+    let version = read_u32(reader)?;
+    let folder_records_offset = u64::from(read_u32(reader)?);
+    let archive_flags = read_u32(reader)?;
+    let folder_count = u64::from(read_u32(reader)?);
+    let file_count = read_u32(reader)?;
+    let _total_folder_name_length = read_u32(reader)?;
+    let total_file_name_length = u64::from(read_u32(reader)?);
+
+    let required_flags = BSA_INCLUDE_DIRECTORY_NAMES | BSA_INCLUDE_FILE_NAMES;
+    if archive_flags & required_flags != required_flags {
+        return Ok(ArchiveIndex::default());
+    }
+
+    let folder_record_size = if version >= BSA_VERSION_SKYRIM_SE {
+        BSA_SKYRIM_SE_FOLDER_RECORD_SIZE
+    } else {
+        BSA_FOLDER_RECORD_SIZE
+    };
+
+    reader.seek(SeekFrom::Start(folder_records_offset))?;
+
+    // Each folder record starts with the folder's name hash and file count.
+    let mut folder_file_counts = Vec::new();
+    for _ in 0..folder_count {
+        let _hash = read_u64(reader)?;
+        folder_file_counts.push(read_u32(reader)?);
+        skip(reader, folder_record_size.saturating_sub(12))?;
+    }
+
+    let mut folders = Vec::new();
+    for file_count in folder_file_counts {
+        let name_length = read_u8(reader)?;
+        let name = read_bytes(reader, u64::from(name_length))?;
+        let name = name.split(|b| *b == 0).next().unwrap_or_default();
+        folders.push((String::from_utf8_lossy(name).into_owned(), file_count));
+
+        skip(
+            reader,
+            u64::from(file_count).saturating_mul(BSA_FILE_RECORD_SIZE),
+        )?;
+    }
+
+    let file_names = read_bytes(reader, total_file_name_length)?;
+    let mut file_names = file_names.split(|b| *b == 0);
+
+    let mut paths = Vec::with_capacity(usize::try_from(file_count).unwrap_or_default());
+    for (folder, count) in folders {
+        for _ in 0..count {
+            let file_name = file_names.next().ok_or(io::ErrorKind::UnexpectedEof)?;
+            let file_name = String::from_utf8_lossy(file_name);
+
+            if folder.is_empty() || folder == "." {
+                paths.push(file_name.into_owned());
+            } else {
+                paths.push(format!("{folder}\\{file_name}"));
+            }
+        }
+    }
+
+    Ok(paths.into_iter().collect())
+}
+
+/// BA2s store a table of length-prefixed file paths at an offset given in
+/// their header, after all the file data.
+fn read_ba2_index<R: Read + Seek>(reader: &mut R) -> io::Result<ArchiveIndex> {
+    let _version = read_u32(reader)?;
+    let _archive_type = read_u32(reader)?;
+    let file_count = read_u32(reader)?;
+    let name_table_offset = read_u64(reader)?;
+
+    if name_table_offset == 0 {
+        return Ok(ArchiveIndex::default());
+    }
+
+    reader.seek(SeekFrom::Start(name_table_offset))?;
+
+    let mut paths = Vec::new();
+    for _ in 0..file_count {
+        let length = read_u16(reader)?;
+        let name = read_bytes(reader, u64::from(length))?;
+        paths.push(String::from_utf8_lossy(&name).into_owned());
+    }
+
+    Ok(paths.into_iter().collect())
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    reader.read_exact(&mut bytes)?;
+    Ok(u8::from_le_bytes(bytes))
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Read the given number of bytes, which are only allocated as they're read
+/// so that a corrupt length can't exhaust memory.
+fn read_bytes<R: Read>(reader: &mut R, length: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.by_ref().take(length).read_to_end(&mut bytes)?;
+
+    if u64::try_from(bytes.len()).is_ok_and(|l| l == length) {
+        Ok(bytes)
+    } else {
+        Err(io::ErrorKind::UnexpectedEof.into())
+    }
+}
+
+/// Skip over the given number of bytes without seeking, which would discard
+/// a buffered reader's buffer.
+fn skip<R: Read>(reader: &mut R, length: u64) -> io::Result<()> {
+    let skipped = io::copy(&mut reader.by_ref().take(length), &mut io::sink())?;
+
+    if skipped == length {
+        Ok(())
+    } else {
+        Err(io::ErrorKind::UnexpectedEof.into())
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use tempfile::tempdir;
+
+    use crate::StdFileSystem;
+
+    use super::*;
+
+    /// Build a BSA in the format used from Oblivion to Skyrim Special
+    /// Edition, with the given folders and the names of the files in them.
+    pub(in crate::function) fn bsa(version: u32, folders: &[(&str, &[&str])]) -> Vec<u8> {
+        let folder_record_size = if version >= BSA_VERSION_SKYRIM_SE {
+            BSA_SKYRIM_SE_FOLDER_RECORD_SIZE
+        } else {
+            BSA_FOLDER_RECORD_SIZE
+        };
+        let file_names: Vec<&str> = folders
+            .iter()
+            .flat_map(|(_, f)| f.iter().copied())
+            .collect();
+        let file_names_length: usize = file_names.iter().map(|n| n.len() + 1).sum();
+
+        let mut bytes = BSA_MAGIC.to_vec();
+        for value in [
+            version,
+            36,
+            BSA_INCLUDE_DIRECTORY_NAMES | BSA_INCLUDE_FILE_NAMES,
+            u32::try_from(folders.len()).unwrap(),
+            u32::try_from(file_names.len()).unwrap(),
+            0,
+            u32::try_from(file_names_length).unwrap(),
+            0,
+        ] {
+            bytes.extend(value.to_le_bytes());
+        }
+
+        for (_, files) in folders {
+            let mut record = vec![0; usize::try_from(folder_record_size).unwrap()];
+            record[8..12].copy_from_slice(&u32::try_from(files.len()).unwrap().to_le_bytes());
+            bytes.extend(record);
+        }
+
+        for (folder, files) in folders {
+            bytes.push(u8::try_from(folder.len() + 1).unwrap());
+            bytes.extend(folder.as_bytes());
+            bytes.push(0);
+            bytes.extend(vec![0; files.len() * 16]);
+        }
+
+        for name in file_names {
+            bytes.extend(name.as_bytes());
+            bytes.push(0);
+        }
+
+        bytes
+    }
+
+    /// Build a BA2 containing files with the given paths.
+    pub(in crate::function) fn ba2(paths: &[&str]) -> Vec<u8> {
+        let mut bytes = BA2_MAGIC.to_vec();
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend(b"GNRL");
+        bytes.extend(u32::try_from(paths.len()).unwrap().to_le_bytes());
+        bytes.extend(24u64.to_le_bytes());
+
+        for path in paths {
+            bytes.extend(u16::try_from(path.len()).unwrap().to_le_bytes());
+            bytes.extend(path.as_bytes());
+        }
+
+        bytes
+    }
+
+    fn morrowind_bsa(paths: &[&str]) -> Vec<u8> {
+        let file_count = paths.len();
+        let names_length: usize = paths.iter().map(|p| p.len() + 1).sum();
+
+        let mut bytes = MORROWIND_BSA_MAGIC.to_vec();
+        bytes.extend(
+            u32::try_from(file_count * 12 + names_length)
+                .unwrap()
+                .to_le_bytes(),
+        );
+        bytes.extend(u32::try_from(file_count).unwrap().to_le_bytes());
+        bytes.extend(vec![0; file_count * 8]);
+
+        let mut offset = 0;
+        for path in paths {
+            bytes.extend(u32::try_from(offset).unwrap().to_le_bytes());
+            offset += path.len() + 1;
+        }
+
+        for path in paths {
+            bytes.extend(path.as_bytes());
+            bytes.push(0);
+        }
+
+        bytes.extend(vec![0; file_count * 8]);
+
+        bytes
+    }
+
+    fn index_of(bytes: &[u8]) -> Option<ArchiveIndex> {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("archive");
+        std::fs::write(&path, bytes).unwrap();
+
+        read_archive_index(&StdFileSystem, &path).unwrap()
+    }
+
+    fn index(paths: &[&str]) -> ArchiveIndex {
+        paths.iter().map(|p| (*p).to_owned()).collect()
+    }
+
+    #[test]
+    fn read_archive_index_should_read_the_file_paths_in_a_morrowind_bsa() {
+        let bytes = morrowind_bsa(&["meshes\\a.nif", "textures\\b.dds"]);
+
+        assert_eq!(
+            Some(index(&["meshes/a.nif", "textures/b.dds"])),
+            index_of(&bytes)
+        );
+    }
+
+    #[test]
+    fn read_archive_index_should_read_the_file_paths_in_a_bsa() {
+        for version in [103, 104, 105] {
+            let bytes = bsa(
+                version,
+                &[
+                    ("meshes\\armor", &["a.nif", "b.nif"]),
+                    ("textures", &["c.dds"]),
+                ],
+            );
+
+            assert_eq!(
+                Some(index(&[
+                    "meshes/armor/a.nif",
+                    "meshes/armor/b.nif",
+                    "textures/c.dds"
+                ])),
+                index_of(&bytes),
+                "{version}"
+            );
+        }
+    }
+
+    #[test]
+    fn read_archive_index_should_read_an_empty_index_if_a_bsa_does_not_store_names() {
+        let mut bytes = bsa(104, &[("meshes", &["a.nif"])]);
+        bytes[12..16].copy_from_slice(&0u32.to_le_bytes());
+
+        assert_eq!(Some(ArchiveIndex::default()), index_of(&bytes));
+    }
+
+    #[test]
+    fn read_archive_index_should_read_the_file_paths_in_a_ba2() {
+        let bytes = ba2(&["Meshes\\Armor\\a.nif", "Textures\\b.dds"]);
+
+        assert_eq!(
+            Some(index(&["meshes/armor/a.nif", "textures/b.dds"])),
+            index_of(&bytes)
+        );
+    }
+
+    #[test]
+    fn read_archive_index_should_not_read_an_index_from_other_files() {
+        assert_eq!(None, index_of(b""));
+        assert_eq!(None, index_of(b"not an archive"));
+    }
+
+    #[test]
+    fn read_archive_index_should_not_read_an_index_from_a_truncated_archive() {
+        let bytes = bsa(104, &[("meshes", &["a.nif"])]);
+        assert_eq!(None, index_of(&bytes[..bytes.len() - 2]));
+
+        let bytes = ba2(&["meshes\\a.nif"]);
+        assert_eq!(None, index_of(&bytes[..bytes.len() - 2]));
+
+        let bytes = morrowind_bsa(&["meshes\\a.nif"]);
+        assert_eq!(None, index_of(&bytes[..20]));
+    }
+
+    fn version_of(bytes: &[u8]) -> Option<u32> {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("archive");
+        std::fs::write(&path, bytes).unwrap();
+
+        read_archive_version(&StdFileSystem, &path).unwrap()
+    }
+
+    #[test]
+    fn read_archive_version_should_read_the_version_from_the_header() {
+        assert_eq!(Some(0x100), version_of(&morrowind_bsa(&["a.nif"])));
+        assert_eq!(Some(104), version_of(&bsa(104, &[])));
+        assert_eq!(Some(105), version_of(&bsa(105, &[])));
+        assert_eq!(Some(1), version_of(&ba2(&[])));
+    }
+
+    #[test]
+    fn read_archive_version_should_not_read_a_version_from_other_files() {
+        assert_eq!(None, version_of(b""));
+        assert_eq!(None, version_of(b"BTDX"));
+        assert_eq!(None, version_of(b"not an archive"));
+    }
+
+    #[test]
+    fn archive_index_contains_should_ignore_case_and_separators() {
+        let index = index(&["Meshes\\Armor\\a.nif"]);
+
+        assert!(index.contains("meshes/armor/a.nif"));
+        assert!(index.contains("MESHES\\ARMOR\\A.NIF"));
+        assert!(!index.contains("meshes/armor"));
+    }
+}
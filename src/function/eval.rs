@@ -1,20 +1,131 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
 use std::ffi::OsStr;
-use std::fs::{read_dir, DirEntry, File};
-use std::hash::Hasher;
-use std::io::{BufRead, BufReader};
+use std::io::{self, Read};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 
 use esplugin::ParseOptions;
-use regex::Regex;
 
-use super::path::{has_plugin_file_extension, normalise_file_name, resolve_path};
+use super::architecture::read_architectures;
+use super::archive::{read_archive_index, read_archive_version, ArchiveIndex};
+use super::ini;
+use super::path::{
+    check_path_is_allowed, data_paths_in_search_order, fold_case, has_plugin_file_extension,
+    is_path_traversal, is_plugin_path, missing_since, normalise_file_name, resolve_path,
+};
+use super::plugin_header::read_author;
+use super::registry;
 use super::version::Version;
-use super::{ComparisonOperator, Function};
-use crate::{Error, GameType, State};
+use super::wine::is_wine;
+use super::{Architecture, ComparisonOperator, Function, Regex, Timestamp};
+use crate::cache::Cache;
+use crate::checksum::{crc32_from_reader, sha256_from_reader, CachedCrc, Crc32Writer};
+use crate::dry_run::{operation_kind, OperationKind};
+use crate::report::EvalObserver;
+use crate::retry::with_retries;
+use crate::variables::{self, VariableError};
+use crate::{
+    CancellationToken, DirEntry, Error, GameType, Metadata, ParsedPlugin, ResultSource, State,
+};
+
+/// The largest text file, in bytes, that functions will read.
+const MAX_FILE_CONTENT_SIZE: u64 = 1024 * 1024;
+
+/// The size of the chunks that memory-mapped files are hashed in, so that
+/// cancellation can be checked while hashing very large files, and so that
+/// chunks can be hashed on different threads.
+const MEMORY_MAP_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+fn evaluate_file_path(state: &State, file_path: &Path) -> Result<(bool, ResultSource), Error> {
+    if state.virtual_files.contains(file_path) {
+        return Ok((true, ResultSource::Preloaded));
+    }
+
+    let path = resolve_path(state, file_path)?;
+
+    let (metadata, source) = read_metadata(state, file_path, &path)?;
+
+    Ok((metadata.is_some(), source))
+}
+
+/// Get the metadata of the file or directory at the given resolved path, or
+/// `None` if there's nothing there, using the state's metadata cache. The
+/// given unresolved path is used in errors.
+fn read_metadata(
+    state: &State,
+    path: &Path,
+    resolved_path: &Path,
+) -> Result<(Option<Metadata>, ResultSource), Error> {
+    if let Some(metadata) = state.metadata_cache.get(resolved_path) {
+        return Ok((metadata, ResultSource::MetadataCache));
+    }
+
+    let metadata = with_io(state, path, || state.file_system.metadata(resolved_path))?.ok();
+
+    state
+        .metadata_cache
+        .insert(&resolved_path.to_path_buf(), metadata);
+
+    Ok((metadata, ResultSource::FreshIo))
+}
+
+/// Check if evaluation has been cancelled or has passed its deadline.
+pub(crate) fn check_cancellation(state: &State) -> Result<(), Error> {
+    if state
+        .cancellation_token
+        .as_ref()
+        .is_some_and(CancellationToken::is_cancelled)
+    {
+        Err(Error::Cancelled)
+    } else if state
+        .deadline
+        .is_some_and(|deadline| Instant::now() >= deadline)
+    {
+        Err(Error::DeadlineExceeded)
+    } else {
+        Ok(())
+    }
+}
 
-fn evaluate_file_path(state: &State, file_path: &Path) -> bool {
-    resolve_path(state, file_path).exists()
+/// Check if an error means that a path is missing or that its contents aren't
+/// in the format that a function expected, rather than that something went
+/// wrong while accessing it.
+fn is_expected_io_error(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::NotFound
+            | io::ErrorKind::NotADirectory
+            | io::ErrorKind::InvalidData
+            | io::ErrorKind::UnexpectedEof
+    )
+}
+
+/// Errors are usually treated like the path being missing or unreadable, but
+/// in strict mode, unexpected errors are returned so that they're noticed.
+fn check_io_result<T>(
+    state: &State,
+    path: &Path,
+    result: io::Result<T>,
+    is_expected: fn(&io::Error) -> bool,
+) -> Result<io::Result<T>, Error> {
+    match result {
+        Err(e) if state.strict && !is_expected(&e) => Err(Error::IoError(path.to_path_buf(), e)),
+        result => Ok(result),
+    }
+}
+
+/// Run the operation on the given path, retrying it according to the state's
+/// retry policy, then check its result.
+fn with_io<T>(
+    state: &State,
+    path: &Path,
+    operation: impl FnMut() -> io::Result<T>,
+) -> Result<io::Result<T>, Error> {
+    let result = with_retries(state, path, operation)?;
+
+    check_io_result(state, path, result, is_expected_io_error)
 }
 
 fn is_match(game_type: GameType, regex: &Regex, file_name: &OsStr) -> bool {
@@ -23,21 +134,87 @@ fn is_match(game_type: GameType, regex: &Regex, file_name: &OsStr) -> bool {
         .is_some_and(|s| regex.is_match(s))
 }
 
+/// Read the entries in the given directory, or get them from the state's
+/// directory cache if it has already been read, giving `None` if it can't be
+/// read. Only entries up to just past the given limit are read, and
+/// directories with more entries than that aren't cached.
+pub(super) fn read_dir_entries(
+    state: &State,
+    directory: &Path,
+    max_entries: Option<usize>,
+) -> Result<Option<Arc<[DirEntry]>>, Error> {
+    if let Some(entries) = state.directory_cache.get(directory) {
+        return Ok(Some(entries));
+    }
+
+    let Ok(dir_iterator) = with_io(state, directory, || state.file_system.read_dir(directory))?
+    else {
+        return Ok(None);
+    };
+
+    let mut entries = Vec::new();
+    for entry in dir_iterator {
+        if max_entries.is_some_and(|max_entries| entries.len() > max_entries) {
+            return Ok(Some(entries.into()));
+        }
+        check_cancellation(state)?;
+
+        entries.push(entry.map_err(|e| Error::IoError(directory.to_path_buf(), e))?);
+    }
+
+    let entries: Arc<[DirEntry]> = entries.into();
+    state
+        .directory_cache
+        .insert(&directory.to_path_buf(), Arc::clone(&entries));
+
+    Ok(Some(entries))
+}
+
+/// Subdirectories are searched down to the given max depth, where a depth of
+/// zero only searches the parent path itself. Symbolic links to directories
+/// aren't followed.
 fn evaluate_dir_entries_from_base_paths<'a>(
+    state: &State,
     base_path_iter: impl Iterator<Item = &'a PathBuf>,
     parent_path: &Path,
-    mut evaluator: impl FnMut(DirEntry) -> bool,
+    max_depth: u32,
+    mut evaluator: impl FnMut(&DirEntry) -> bool,
 ) -> Result<bool, Error> {
+    // The limit applies to the total across all base paths and subdirectories.
+    let mut entry_count: usize = 0;
+
     for base_path in base_path_iter {
         let parent_path = base_path.join(parent_path);
-        let Ok(dir_iterator) = read_dir(&parent_path) else {
-            return Ok(false);
-        };
+        let mut directories = vec![(parent_path.clone(), 0)];
+
+        while let Some((directory, depth)) = directories.pop() {
+            let remaining_entries = state
+                .max_directory_entries
+                .map(|max_entries| max_entries.saturating_sub(entry_count));
+            let Some(entries) = read_dir_entries(state, &directory, remaining_entries)? else {
+                // A subdirectory may have been removed since it was found.
+                if depth == 0 {
+                    return Ok(false);
+                }
+                continue;
+            };
+
+            for entry in entries.iter() {
+                if let Some(max_entries) = state.max_directory_entries {
+                    if entry_count >= max_entries {
+                        return Err(Error::DirectoryEntryLimitExceeded(parent_path, max_entries));
+                    }
+                }
+                entry_count = entry_count.saturating_add(1);
+                check_cancellation(state)?;
+
+                if depth < max_depth && entry.is_dir() {
+                    directories.push((directory.join(entry.file_name()), depth.saturating_add(1)));
+                }
 
-        for entry in dir_iterator {
-            let entry = entry.map_err(|e| Error::IoError(parent_path.clone(), e))?;
-            if evaluator(entry) {
-                return Ok(true);
+                if evaluator(entry) {
+                    return Ok(true);
+                }
             }
         }
     }
@@ -45,86 +222,348 @@ fn evaluate_dir_entries_from_base_paths<'a>(
     Ok(false)
 }
 
+/// Check if the entries in the given directory are in the state's directory
+/// cache.
+pub(crate) fn is_directory_cached(state: &State, directory: &Path) -> bool {
+    state.directory_cache.get(directory).is_some()
+}
+
 fn evaluate_dir_entries(
     state: &State,
     parent_path: &Path,
-    evaluator: impl FnMut(DirEntry) -> bool,
+    evaluator: impl FnMut(&DirEntry) -> bool,
+) -> Result<bool, Error> {
+    evaluate_dir_entries_to_depth(state, parent_path, 0, evaluator)
+}
+
+fn evaluate_dir_entries_to_depth(
+    state: &State,
+    parent_path: &Path,
+    max_depth: u32,
+    evaluator: impl FnMut(&DirEntry) -> bool,
 ) -> Result<bool, Error> {
     match state.game_type {
         GameType::OpenMW => evaluate_dir_entries_from_base_paths(
+            state,
             state
                 .additional_data_paths
                 .iter()
                 .rev()
                 .chain(std::iter::once(&state.data_path)),
             parent_path,
+            max_depth,
             evaluator,
         ),
         _ => evaluate_dir_entries_from_base_paths(
+            state,
             state
                 .additional_data_paths
                 .iter()
                 .chain(std::iter::once(&state.data_path)),
             parent_path,
+            max_depth,
             evaluator,
         ),
     }
 }
 
-fn evaluate_file_regex(state: &State, parent_path: &Path, regex: &Regex) -> Result<bool, Error> {
-    let evaluator = |entry: DirEntry| is_match(state.game_type, regex, &entry.file_name());
+/// Get the virtual files in the given parent path and its subdirectories down
+/// to the given max depth that have filenames matching the given regex.
+fn matching_virtual_files<'a>(
+    state: &'a State,
+    parent_path: &Path,
+    regex: &'a Regex,
+    max_depth: u32,
+) -> impl Iterator<Item = &'a Path> + use<'a> {
+    state
+        .virtual_files
+        .files_in(parent_path, max_depth)
+        .filter(|p| {
+            p.file_name()
+                .is_some_and(|n| is_match(state.game_type, regex, n))
+        })
+}
 
-    evaluate_dir_entries(state, parent_path, evaluator)
+fn evaluate_file_regex(
+    state: &State,
+    parent_path: &Path,
+    regex: &Regex,
+    max_depth: u32,
+) -> Result<(bool, ResultSource), Error> {
+    if matching_virtual_files(state, parent_path, regex, max_depth)
+        .next()
+        .is_some()
+    {
+        return Ok((true, ResultSource::Preloaded));
+    }
+
+    let evaluator = |entry: &DirEntry| is_match(state.game_type, regex, entry.file_name());
+
+    let result = evaluate_dir_entries_to_depth(state, parent_path, max_depth, evaluator)?;
+
+    Ok((result, ResultSource::FreshIo))
+}
+
+/// A missing file has no size, so it fails every comparison. Neither does a
+/// virtual file, as its size isn't known.
+fn evaluate_file_size(
+    state: &State,
+    path: &Path,
+    size: u64,
+    comparator: ComparisonOperator,
+) -> Result<(bool, ResultSource), Error> {
+    if state.virtual_files.contains(path) {
+        return Ok((false, ResultSource::Preloaded));
+    }
+
+    let resolved_path = resolve_path(state, path)?;
+
+    let (metadata, source) = read_metadata(state, path, &resolved_path)?;
+    let result = metadata.is_some_and(|m| compare(&m.len(), comparator, &size));
+
+    Ok((result, source))
+}
+
+/// File modification times are compared to the second, and a missing file
+/// fails every comparison.
+fn evaluate_file_modified_time(
+    state: &State,
+    path: &Path,
+    comparator: ComparisonOperator,
+    timestamp: Timestamp,
+) -> Result<bool, Error> {
+    let resolved_path = resolve_path(state, path)?;
+
+    Ok(
+        with_io(state, path, || state.file_system.metadata(&resolved_path))?
+            .ok()
+            .and_then(|m| m.modified())
+            .is_some_and(|time| compare(&Timestamp::from(time), comparator, &timestamp)),
+    )
+}
+
+/// Read a text file, giving `None` if it's missing, unreadable or larger than
+/// [MAX_FILE_CONTENT_SIZE]. Contents that aren't valid UTF-8 are read lossily,
+/// so that text in other encodings can still be matched where it's ASCII.
+fn read_text_file(state: &State, path: &Path) -> Result<Option<String>, Error> {
+    let resolved_path = resolve_path(state, path)?;
+
+    let contents = with_io(state, path, || {
+        let mut bytes = Vec::new();
+        state
+            .file_system
+            .open(&resolved_path)?
+            .take(MAX_FILE_CONTENT_SIZE.saturating_add(1))
+            .read_to_end(&mut bytes)?;
+        Ok(bytes)
+    })?;
+
+    Ok(contents
+        .ok()
+        .filter(|bytes| u64::try_from(bytes.len()).is_ok_and(|len| len <= MAX_FILE_CONTENT_SIZE))
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+fn evaluate_file_content_contains(
+    state: &State,
+    path: &Path,
+    regex: &Regex,
+) -> Result<bool, Error> {
+    Ok(read_text_file(state, path)?.is_some_and(|contents| regex.is_match(&contents)))
+}
+
+/// Values are compared case-insensitively, and a key that is missing from the
+/// file or section has no value.
+fn evaluate_ini_value(
+    state: &State,
+    path: &Path,
+    section: &str,
+    key: &str,
+    value: &str,
+) -> Result<bool, Error> {
+    Ok(read_text_file(state, path)?.is_some_and(|contents| {
+        ini::find_value(&contents, section, key).is_some_and(|v| unicase::eq(v, value))
+    }))
 }
 
-fn evaluate_file_size(state: &State, path: &Path, size: u64) -> Result<bool, Error> {
-    std::fs::metadata(resolve_path(state, path))
-        .map(|m| m.len() == size)
-        .or(Ok(false))
+/// Permission errors are what `readable()` and `writable()` check for, so
+/// they're expected even in strict mode.
+fn is_expected_permission_error(error: &io::Error) -> bool {
+    is_expected_io_error(error)
+        || matches!(
+            error.kind(),
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem
+        )
 }
 
-fn evaluate_readable(state: &State, path: &Path) -> bool {
-    if path.is_dir() {
-        read_dir(resolve_path(state, path)).is_ok()
+fn evaluate_readable(state: &State, path: &Path) -> Result<bool, Error> {
+    let resolved_path = resolve_path(state, path)?;
+
+    let file_system = state.file_system.as_ref();
+
+    let metadata = check_io_result(
+        state,
+        path,
+        file_system.metadata(&resolved_path),
+        is_expected_permission_error,
+    )?;
+
+    let result = if metadata.is_ok_and(|m| m.is_dir()) {
+        with_retries(state, path, || {
+            file_system.read_dir(&resolved_path).map(|_| ())
+        })?
     } else {
-        File::open(resolve_path(state, path)).is_ok()
-    }
+        with_retries(state, path, || file_system.open(&resolved_path).map(|_| ()))?
+    };
+
+    Ok(check_io_result(state, path, result, is_expected_permission_error)?.is_ok())
+}
+
+fn evaluate_writable(state: &State, path: &Path) -> Result<bool, Error> {
+    let resolved_path = resolve_path(state, path)?;
+
+    let result = with_retries(state, path, || {
+        state.file_system.check_writable(&resolved_path)
+    })?;
+
+    Ok(check_io_result(state, path, result, is_expected_permission_error)?.is_ok())
 }
 
-fn evaluate_is_executable(state: &State, path: &Path) -> bool {
-    Version::is_readable(&resolve_path(state, path))
+fn is_file(state: &State, path: &Path) -> Result<bool, Error> {
+    let (metadata, _) = read_metadata(state, path, path)?;
+
+    Ok(metadata.is_some_and(|m| m.is_file()))
 }
 
-fn evaluate_many(state: &State, parent_path: &Path, regex: &Regex) -> Result<bool, Error> {
-    // Share the found_one state across all data paths because they're all
-    // treated as if they were merged into one directory.
-    let mut found_one = false;
-    let evaluator = |entry: DirEntry| {
-        if is_match(state.game_type, regex, &entry.file_name()) {
-            if found_one {
-                true
-            } else {
-                found_one = true;
-                false
-            }
+/// Values that can't be read, including all values on platforms other than
+/// Windows, don't match.
+fn evaluate_registry_value(key: &str, value_name: &str, regex: &Regex) -> bool {
+    registry::read_value(key, value_name).is_some_and(|value| regex.is_match(&value))
+}
+
+fn evaluate_is_executable(state: &State, path: &Path) -> Result<bool, Error> {
+    Version::is_readable(state, &resolve_path(state, path)?)
+}
+
+/// Files that don't exist, can't be read or aren't executables built for any
+/// known architecture are not built for the given architecture.
+fn evaluate_is_architecture(
+    state: &State,
+    path: &Path,
+    architecture: Architecture,
+) -> Result<bool, Error> {
+    let resolved_path = resolve_path(state, path)?;
+
+    Ok(with_io(state, path, || {
+        read_architectures(state.file_system.as_ref(), &resolved_path)
+    })?
+    .is_ok_and(|a| a.contains(&architecture)))
+}
+
+fn evaluate_many(
+    state: &State,
+    parent_path: &Path,
+    regex: &Regex,
+    max_depth: u32,
+) -> Result<bool, Error> {
+    evaluate_count(
+        state,
+        parent_path,
+        regex,
+        max_depth,
+        ComparisonOperator::GreaterThan,
+        1,
+    )
+}
+
+/// Directory entries stop being read once a greater than comparison is known
+/// to be true, but every entry must be counted for other comparisons. Virtual
+/// files are counted unless they're also on disk, so that they're not counted
+/// twice.
+fn evaluate_count(
+    state: &State,
+    parent_path: &Path,
+    regex: &Regex,
+    max_depth: u32,
+    comparator: ComparisonOperator,
+    count: u64,
+) -> Result<bool, Error> {
+    // Share the count across all data paths because they're all treated as if
+    // they were merged into one directory.
+    let mut match_count: u64 = 0;
+    for path in matching_virtual_files(state, parent_path, regex, max_depth) {
+        let resolved_path = resolve_path(state, path)?;
+        if !state.file_system.exists(&resolved_path).unwrap_or(false) {
+            match_count = match_count.saturating_add(1);
+        }
+    }
+
+    let evaluator = |entry: &DirEntry| {
+        if is_match(state.game_type, regex, entry.file_name()) {
+            match_count = match_count.saturating_add(1);
+
+            matches!(
+                comparator,
+                ComparisonOperator::GreaterThan | ComparisonOperator::GreaterThanOrEqual
+            ) && compare(&match_count, comparator, &count)
         } else {
             false
         }
     };
 
-    evaluate_dir_entries(state, parent_path, evaluator)
+    evaluate_dir_entries_to_depth(state, parent_path, max_depth, evaluator)?;
+
+    Ok(compare(&match_count, comparator, &count))
 }
 
 fn evaluate_active_path(state: &State, path: &Path) -> bool {
     path.to_str()
-        .is_some_and(|s| state.active_plugins.contains(&s.to_lowercase()))
+        .is_some_and(|s| state.active_plugins.contains_key(&fold_case(s)))
 }
 
+/// Regexes are matched against active plugin filenames as they were given, as
+/// the regexes are already case-insensitive.
 fn evaluate_active_regex(state: &State, regex: &Regex) -> bool {
-    state.active_plugins.iter().any(|p| regex.is_match(p))
+    state
+        .active_plugins
+        .iter()
+        .any(|(folded_name, name)| is_active_plugin_match(regex, folded_name, name))
+}
+
+/// Regexes are matched against active plugins' names both as they were given
+/// and case-folded, so that a regex can match a name that differs from it only
+/// in case even if that changes the name's length, e.g. `strasse\.esp` matches
+/// `Straße.esp`.
+fn is_active_plugin_match(regex: &Regex, folded_name: &str, name: &str) -> bool {
+    regex.is_match(name) || regex.is_match(folded_name)
 }
 
-fn parse_plugin(state: &State, file_path: &Path) -> Option<esplugin::Plugin> {
+/// Get the parsed header of the plugin at the given path, parsing it if it
+/// isn't already in the plugin cache. Plugins that can't be parsed are cached
+/// too, so that each plugin is read at most once.
+fn parse_plugin(state: &State, file_path: &Path) -> (Option<Arc<esplugin::Plugin>>, ResultSource) {
+    let key = lowercase(file_path);
+
+    if let Some(plugin) = key.as_ref().and_then(|k| state.plugin_cache.get(k)) {
+        return (plugin, ResultSource::PluginCache);
+    }
+
+    let plugin = parse_plugin_header(state, file_path).map(Arc::new);
+
+    if let Some(key) = key {
+        state
+            .plugin_cache
+            .insert(&key, plugin.as_ref().map(Arc::clone));
+    }
+
+    (plugin, ResultSource::FreshIo)
+}
+
+fn is_plugin_cached(state: &State, file_path: &Path) -> bool {
+    lowercase(file_path).is_some_and(|key| state.plugin_cache.get(&key).is_some())
+}
+
+fn parse_plugin_header(state: &State, file_path: &Path) -> Option<esplugin::Plugin> {
     use esplugin::GameId;
 
     let game_id = match state.game_type {
@@ -138,478 +577,2961 @@ fn parse_plugin(state: &State, file_path: &Path) -> Option<esplugin::Plugin> {
         GameType::Starfield => GameId::Starfield,
     };
 
-    let path = resolve_path(state, file_path);
+    let path = resolve_path(state, file_path).ok()?;
 
+    let reader = state.file_system.open(&path).ok()?;
     let mut plugin = esplugin::Plugin::new(game_id, &path);
 
     plugin
-        .parse_file(ParseOptions::header_only())
+        .parse_reader(reader, ParseOptions::header_only())
         .is_ok()
         .then_some(plugin)
 }
 
-fn evaluate_is_master(state: &State, file_path: &Path) -> bool {
-    if state.game_type == GameType::OpenMW {
-        false
+/// Read data from the plugin at the given path, using the host's parsed plugin
+/// with the same filename if there is one, and otherwise parsing the plugin's
+/// header.
+fn read_plugin<T>(
+    state: &State,
+    file_path: &Path,
+    reader: impl FnOnce(&dyn ParsedPlugin) -> T,
+) -> Option<(T, ResultSource)> {
+    let parsed_plugin =
+        lowercase_filename(file_path).and_then(|key| state.parsed_plugins.get(&key));
+
+    if let Some(plugin) = parsed_plugin {
+        Some((reader(plugin.as_ref()), ResultSource::Preloaded))
     } else {
-        parse_plugin(state, file_path).is_some_and(|plugin| plugin.is_master_file())
+        let (plugin, source) = parse_plugin(state, file_path);
+        plugin.map(|plugin| (reader(plugin.as_ref()), source))
     }
 }
 
-#[expect(clippy::iter_over_hash_type)]
-fn evaluate_many_active(state: &State, regex: &Regex) -> bool {
-    let mut found_one = false;
-    for active_plugin in &state.active_plugins {
-        if regex.is_match(active_plugin) {
-            if found_one {
-                return true;
-            }
-            found_one = true;
-        }
+fn evaluate_is_master(state: &State, file_path: &Path) -> (bool, ResultSource) {
+    if state.game_type == GameType::OpenMW {
+        (false, ResultSource::FreshIo)
+    } else {
+        read_plugin(state, file_path, |plugin| plugin.is_master_file())
+            .unwrap_or((false, ResultSource::FreshIo))
     }
+}
 
-    false
+/// Only games that support light plugins can have them, and a plugin that
+/// can't be read is not a light plugin.
+fn evaluate_is_light_plugin(state: &State, file_path: &Path) -> (bool, ResultSource) {
+    read_plugin(state, file_path, |plugin| plugin.is_light_plugin())
+        .unwrap_or((false, ResultSource::FreshIo))
 }
 
-fn lowercase(path: &Path) -> Option<String> {
-    path.to_str().map(str::to_lowercase)
+/// Only Starfield supports medium plugins, and a plugin that is also light is
+/// not a medium plugin.
+fn evaluate_is_medium_plugin(state: &State, file_path: &Path) -> (bool, ResultSource) {
+    read_plugin(state, file_path, |plugin| plugin.is_medium_plugin())
+        .unwrap_or((false, ResultSource::FreshIo))
 }
 
-fn evaluate_checksum(state: &State, file_path: &Path, crc: u32) -> Result<bool, Error> {
-    if let Ok(reader) = state.crc_cache.read() {
-        if let Some(key) = lowercase(file_path) {
-            if let Some(cached_crc) = reader.get(&key) {
-                return Ok(*cached_crc == crc);
+/// Plugins that can't be read or that have no header version fail every
+/// comparison.
+fn evaluate_header_version(
+    state: &State,
+    file_path: &Path,
+    comparator: ComparisonOperator,
+    version: f32,
+) -> (bool, ResultSource) {
+    read_plugin(state, file_path, |plugin| {
+        plugin
+            .header_version()
+            .is_some_and(|v| compare(&v, comparator, &version))
+    })
+    .unwrap_or((false, ResultSource::FreshIo))
+}
+
+/// Record counts are read from plugin headers and cached by the paths given in
+/// conditions, unless the plugin was given to the state by the host. Plugins
+/// that can't be read fail every comparison.
+fn evaluate_record_count(
+    state: &State,
+    file_path: &Path,
+    comparator: ComparisonOperator,
+    count: u64,
+) -> (bool, ResultSource, SystemTime) {
+    let key = lowercase(file_path);
+
+    if let Some((cached_count, cached_at)) =
+        key.as_ref().and_then(|k| state.record_count_cache.get(k))
+    {
+        return (
+            compare(&u64::from(cached_count), comparator, &count),
+            ResultSource::RecordCountCache,
+            cached_at,
+        );
+    }
+
+    let computed_at = SystemTime::now();
+
+    match read_plugin(state, file_path, |plugin| plugin.record_count()) {
+        Some((Some(record_count), source)) => {
+            if let (Some(key), ResultSource::FreshIo) = (key, source) {
+                state
+                    .record_count_cache
+                    .insert(&key, (record_count, computed_at));
             }
+
+            (
+                compare(&u64::from(record_count), comparator, &count),
+                source,
+                computed_at,
+            )
         }
+        Some((None, source)) => (false, source, computed_at),
+        None => (false, ResultSource::FreshIo, computed_at),
     }
+}
 
-    let path = resolve_path(state, file_path);
+/// Get the index of the files in an archive, using the state's cache if the
+/// archive has been read before. Archives that are missing or in unsupported
+/// formats have no index, which isn't cached.
+fn archive_index(
+    state: &State,
+    file_path: &Path,
+) -> Result<(Option<Arc<ArchiveIndex>>, ResultSource, SystemTime), Error> {
+    let key = lowercase(file_path);
 
-    if !path.is_file() {
-        return Ok(false);
+    if let Some((index, cached_at)) = key.as_ref().and_then(|k| state.archive_index_cache.get(k)) {
+        return Ok((Some(index), ResultSource::ArchiveIndexCache, cached_at));
     }
 
-    let io_error_mapper = |e| Error::IoError(file_path.to_path_buf(), e);
-    let file = File::open(path).map_err(io_error_mapper)?;
-    let mut reader = BufReader::new(file);
-    let mut hasher = crc32fast::Hasher::new();
+    let computed_at = SystemTime::now();
+    let resolved_path = resolve_path(state, file_path)?;
 
-    let mut buffer = reader.fill_buf().map_err(io_error_mapper)?;
-    while !buffer.is_empty() {
-        hasher.write(buffer);
-        let length = buffer.len();
-        reader.consume(length);
+    let index = with_io(state, file_path, || {
+        read_archive_index(state.file_system.as_ref(), &resolved_path)
+    })?
+    .ok()
+    .flatten()
+    .map(Arc::new);
 
-        buffer = reader.fill_buf().map_err(io_error_mapper)?;
+    if let (Some(key), Some(index)) = (key, &index) {
+        state
+            .archive_index_cache
+            .insert(&key, (Arc::clone(index), computed_at));
     }
 
-    let calculated_crc = hasher.finalize();
-    let mut writer = state.crc_cache.write().unwrap_or_else(|mut e| {
-        **e.get_mut() = HashMap::new();
-        state.crc_cache.clear_poison();
-        e.into_inner()
-    });
+    Ok((index, ResultSource::FreshIo, computed_at))
+}
 
-    if let Some(key) = lowercase(file_path) {
-        writer.insert(key, calculated_crc);
-    }
+fn evaluate_archive_contains(
+    state: &State,
+    file_path: &Path,
+    entry_path: &str,
+) -> Result<(bool, ResultSource, SystemTime), Error> {
+    let (index, source, computed_at) = archive_index(state, file_path)?;
+
+    Ok((
+        index.is_some_and(|i| i.contains(entry_path)),
+        source,
+        computed_at,
+    ))
+}
 
-    Ok(calculated_crc == crc)
+fn evaluate_archive_regex(
+    state: &State,
+    file_path: &Path,
+    regex: &Regex,
+) -> Result<(bool, ResultSource, SystemTime), Error> {
+    let (index, source, computed_at) = archive_index(state, file_path)?;
+
+    Ok((
+        index.is_some_and(|i| i.entries().any(|e| regex.is_match(e))),
+        source,
+        computed_at,
+    ))
 }
 
-fn lowercase_filename(path: &Path) -> Option<String> {
-    path.file_name()
-        .and_then(OsStr::to_str)
-        .map(str::to_lowercase)
+/// Files that are missing or aren't archives fail every comparison.
+fn evaluate_archive_version(
+    state: &State,
+    file_path: &Path,
+    comparator: ComparisonOperator,
+    version: u64,
+) -> Result<bool, Error> {
+    let resolved_path = resolve_path(state, file_path)?;
+
+    Ok(with_io(state, file_path, || {
+        read_archive_version(state.file_system.as_ref(), &resolved_path)
+    })?
+    .ok()
+    .flatten()
+    .is_some_and(|v| compare(&u64::from(v), comparator, &version)))
 }
 
-fn get_version(state: &State, file_path: &Path) -> Result<Option<Version>, Error> {
-    if !file_path.is_file() {
-        return Ok(None);
-    }
+fn evaluate_masters_contain(state: &State, file_path: &Path, master: &str) -> (bool, ResultSource) {
+    read_plugin(state, file_path, |plugin| {
+        plugin
+            .masters()
+            .iter()
+            .any(|m| unicase::eq(m.as_str(), master))
+    })
+    .unwrap_or((false, ResultSource::FreshIo))
+}
 
-    if let Some(key) = lowercase_filename(file_path) {
-        if let Some(version) = state.plugin_versions.get(&key) {
-            return Ok(Some(Version::from(version.as_str())));
-        }
+/// A plugin is valid if it has a plugin file extension for the current game and
+/// its header can be parsed for the current game. Skyrim Special Edition
+/// plugins (with a header version of 1.7) are also invalid for Skyrim, as they
+/// may use record formats that Skyrim can't load.
+fn evaluate_is_valid_plugin(state: &State, file_path: &Path) -> (bool, ResultSource) {
+    if !has_plugin_file_extension(state.game_type, file_path) {
+        return (false, ResultSource::FreshIo);
     }
 
-    if has_plugin_file_extension(state.game_type, file_path) {
-        Ok(None)
-    } else {
-        Version::read_file_version(file_path)
-    }
+    let (plugin, source) = parse_plugin(state, file_path);
+    let is_valid = plugin.is_some_and(|plugin| {
+        state.game_type != GameType::Skyrim
+            || plugin.header_version().is_some_and(|version| version < 1.0)
+    });
+
+    (is_valid, source)
 }
 
-fn get_product_version(file_path: &Path) -> Result<Option<Version>, Error> {
-    if file_path.is_file() {
-        Version::read_product_version(file_path)
-    } else {
-        Ok(None)
-    }
+fn evaluate_many_active(state: &State, regex: &Regex) -> bool {
+    evaluate_active_count(state, regex, ComparisonOperator::GreaterThan, 1)
 }
 
-fn compare_versions(
-    actual_version: &Version,
+fn evaluate_active_count(
+    state: &State,
+    regex: &Regex,
     comparator: ComparisonOperator,
-    given_version: &str,
+    count: u64,
 ) -> bool {
-    let given_version = &Version::from(given_version);
+    let match_count = state
+        .active_plugins
+        .iter()
+        .filter(|(folded_name, name)| is_active_plugin_match(regex, folded_name, name))
+        .fold(0_u64, |acc, _| acc.saturating_add(1));
 
-    match comparator {
-        ComparisonOperator::Equal => actual_version == given_version,
-        ComparisonOperator::NotEqual => actual_version != given_version,
-        ComparisonOperator::LessThan => actual_version < given_version,
-        ComparisonOperator::GreaterThan => actual_version > given_version,
-        ComparisonOperator::LessThanOrEqual => actual_version <= given_version,
-        ComparisonOperator::GreaterThanOrEqual => actual_version >= given_version,
+    compare(&match_count, comparator, &count)
+}
+
+fn lowercase(path: &Path) -> Option<String> {
+    path.to_str().map(str::to_lowercase)
+}
+
+/// Calculate the CRC of the file at the given resolved path, using the given
+/// unresolved path in errors. Files that are larger than the state's memory
+/// map threshold are memory-mapped if the filesystem supports it.
+fn read_crc(state: &State, file_path: &Path, resolved_path: &Path) -> Result<u32, Error> {
+    if let Some(bytes) = memory_map_large_file(state, file_path, resolved_path)? {
+        let bytes = (*bytes).as_ref();
+
+        #[cfg(feature = "parallel")]
+        if state
+            .parallel_crc_threshold
+            .is_some_and(|threshold| u64::try_from(bytes.len()).is_ok_and(|len| len > threshold))
+        {
+            return crate::checksum::crc32_from_chunks_parallel(
+                bytes,
+                MEMORY_MAP_CHUNK_SIZE,
+                || check_cancellation(state),
+            );
+        }
+
+        let mut writer = Crc32Writer::new();
+        for chunk in bytes.chunks(MEMORY_MAP_CHUNK_SIZE) {
+            check_cancellation(state)?;
+            writer.update(chunk);
+        }
+
+        return Ok(writer.crc());
     }
+
+    with_retries(state, file_path, || {
+        state
+            .file_system
+            .open(resolved_path)
+            .and_then(crc32_from_reader)
+    })?
+    .map_err(|e| Error::IoError(file_path.to_path_buf(), e))
 }
 
-fn evaluate_version<F>(
+/// Memory-map the file at the given resolved path if it's larger than the
+/// state's memory map threshold and the filesystem supports memory mapping.
+fn memory_map_large_file(
     state: &State,
     file_path: &Path,
-    given_version: &str,
-    comparator: ComparisonOperator,
-    read_version: F,
-) -> Result<bool, Error>
-where
-    F: Fn(&State, &Path) -> Result<Option<Version>, Error>,
-{
-    let file_path = resolve_path(state, file_path);
-    let Some(actual_version) = read_version(state, &file_path)? else {
-        return Ok(comparator == ComparisonOperator::NotEqual
-            || comparator == ComparisonOperator::LessThan
-            || comparator == ComparisonOperator::LessThanOrEqual);
+    resolved_path: &Path,
+) -> Result<Option<Box<dyn AsRef<[u8]>>>, Error> {
+    let Some(threshold) = state.memory_map_threshold else {
+        return Ok(None);
     };
 
-    Ok(compare_versions(&actual_version, comparator, given_version))
+    let (metadata, _) = read_metadata(state, file_path, resolved_path)?;
+    if metadata.is_none_or(|m| m.len() <= threshold) {
+        return Ok(None);
+    }
+
+    with_retries(state, file_path, || {
+        state.file_system.memory_map(resolved_path)
+    })?
+    .map_err(|e| Error::IoError(file_path.to_path_buf(), e))
 }
 
-fn evaluate_filename_version(
-    state: &State,
-    parent_path: &Path,
-    regex: &Regex,
-    version: &str,
-    comparator: ComparisonOperator,
-) -> Result<bool, Error> {
-    let evaluator = |entry: DirEntry| {
-        normalise_file_name(state.game_type, &entry.file_name())
-            .to_str()
-            .and_then(|s| regex.captures(s))
-            .and_then(|c| c.get(1))
-            .map(|m| Version::from(m.as_str()))
-            .is_some_and(|v| compare_versions(&v, comparator, version))
-    };
+/// Calculate the CRC of the file at the given path, which is resolved in the
+/// same way as paths in conditions, without using or updating the state's CRC
+/// caches. Returns None if there is no file at the path.
+pub(crate) fn calculate_crc(state: &State, file_path: &Path) -> Result<Option<u32>, Error> {
+    check_path_is_allowed(state, file_path)?;
 
-    evaluate_dir_entries(state, parent_path, evaluator)
+    let path = resolve_path(state, file_path)?;
+    if is_file(state, &path)? {
+        read_crc(state, file_path, &path).map(Some)
+    } else {
+        Ok(None)
+    }
 }
 
-fn evaluate_description_contains(state: &State, file_path: &Path, regex: &Regex) -> bool {
-    parse_plugin(state, file_path)
-        .and_then(|plugin| plugin.description().unwrap_or(None))
-        .is_some_and(|description| regex.is_match(&description))
+/// Get the metadata of the file that the given path in a condition resolves
+/// to, if there is one, bypassing the metadata cache as the file may have
+/// changed since it was cached.
+fn read_file_metadata(state: &State, file_path: &Path) -> Option<Metadata> {
+    let path = resolve_path(state, file_path).ok()?;
+
+    state
+        .file_system
+        .metadata(&path)
+        .ok()
+        .filter(Metadata::is_file)
 }
 
-impl Function {
-    pub fn eval(&self, state: &State) -> Result<bool, Error> {
-        if self.is_slow() {
-            if let Ok(reader) = state.condition_cache.read() {
-                if let Some(cached_result) = reader.get(self) {
-                    return Ok(*cached_result);
-                }
-            }
+/// Get the entries in the state's CRC cache whose files haven't been modified
+/// since their CRCs were cached. Entries for files with unknown modification
+/// times can't be validated when they're imported, so they're skipped.
+pub(crate) fn export_crc_cache(state: &State) -> Vec<CachedCrc> {
+    state
+        .crc_cache
+        .entries()
+        .into_iter()
+        .filter_map(|(path, (crc, cached_at))| {
+            let metadata = read_file_metadata(state, Path::new(&path))?;
+            let modified = metadata.modified().filter(|m| *m <= cached_at)?;
+
+            Some(CachedCrc::new(path, metadata.len(), modified, crc))
+        })
+        .collect()
+}
+
+/// Add the given CRCs to the state's CRC cache, skipping those whose files
+/// are missing or have a different size or modification time. Returns the
+/// number of CRCs that were added.
+pub(crate) fn import_crc_cache(state: &State, cached_crcs: &[CachedCrc]) -> usize {
+    let cached_at = SystemTime::now();
+    let mut count = 0_usize;
+
+    for cached_crc in cached_crcs {
+        let path = Path::new(cached_crc.path());
+        if is_path_traversal(path) {
+            continue;
         }
 
-        let result = match self {
-            Function::FilePath(f) => Ok(evaluate_file_path(state, f)),
-            Function::FileRegex(p, r) => evaluate_file_regex(state, p, r),
-            Function::FileSize(p, s) => evaluate_file_size(state, p, *s),
-            Function::Readable(p) => Ok(evaluate_readable(state, p)),
-            Function::IsExecutable(p) => Ok(evaluate_is_executable(state, p)),
-            Function::ActivePath(p) => Ok(evaluate_active_path(state, p)),
-            Function::ActiveRegex(r) => Ok(evaluate_active_regex(state, r)),
-            Function::IsMaster(p) => Ok(evaluate_is_master(state, p)),
-            Function::Many(p, r) => evaluate_many(state, p, r),
-            Function::ManyActive(r) => Ok(evaluate_many_active(state, r)),
-            Function::Checksum(path, crc) => evaluate_checksum(state, path, *crc),
-            Function::Version(p, v, c) => evaluate_version(state, p, v, *c, get_version),
-            Function::ProductVersion(p, v, c) => {
-                evaluate_version(state, p, v, *c, |_, p| get_product_version(p))
-            }
-            Function::FilenameVersion(p, r, v, c) => evaluate_filename_version(state, p, r, v, *c),
-            Function::DescriptionContains(p, r) => Ok(evaluate_description_contains(state, p, r)),
-        };
+        let is_unchanged = read_file_metadata(state, path).is_some_and(|m| {
+            m.len() == cached_crc.size() && m.modified() == Some(cached_crc.modified())
+        });
 
-        if self.is_slow() {
-            if let Ok(function_result) = result {
-                let mut writer = state.condition_cache.write().unwrap_or_else(|mut e| {
-                    **e.get_mut() = HashMap::new();
-                    state.condition_cache.clear_poison();
-                    e.into_inner()
-                });
-
-                writer.insert(self.clone(), function_result);
-            }
+        if is_unchanged {
+            state.crc_cache.insert(
+                &cached_crc.path().to_lowercase(),
+                (cached_crc.crc(), cached_at),
+            );
+            count = count.saturating_add(1);
         }
-
-        result
     }
 
-    /// Some functions are faster to evaluate than to look their result up in
-    /// the cache, as the data they operate on are already cached separately and
-    /// the operation is simple.
-    fn is_slow(&self) -> bool {
-        !matches!(
-            self,
-            Self::ActivePath(_) | Self::ActiveRegex(_) | Self::ManyActive(_) | Self::Checksum(_, _)
+    count
+}
+
+/// The file's CRC is calculated at most once, however many CRCs it is compared
+/// against.
+fn evaluate_checksum(
+    state: &State,
+    file_path: &Path,
+    crcs: &[u32],
+) -> Result<(bool, ResultSource, SystemTime), Error> {
+    // The CRCs of files outside the data paths are cached by their resolved
+    // paths, so that they can be shared with states for other games.
+    let is_outside_data_paths = is_path_traversal(file_path);
+
+    if !is_outside_data_paths {
+        if let Some(key) = lowercase(file_path) {
+            if let Some((cached_crc, cached_at)) = state.crc_cache.get(&key) {
+                return Ok((
+                    crcs.contains(&cached_crc),
+                    ResultSource::CrcCache,
+                    cached_at,
+                ));
+            }
+        }
+    }
+
+    let path = resolve_path(state, file_path)?;
+
+    if is_outside_data_paths {
+        if let Some((cached_crc, cached_at)) = state.shared_caches.crcs.get(&path) {
+            return Ok((
+                crcs.contains(&cached_crc),
+                ResultSource::CrcCache,
+                cached_at,
+            ));
+        }
+    }
+
+    let computed_at = SystemTime::now();
+
+    if !is_file(state, &path)? {
+        return Ok((false, ResultSource::FreshIo, computed_at));
+    }
+
+    let calculated_crc = read_crc(state, file_path, &path)?;
+
+    if is_outside_data_paths {
+        state
+            .shared_caches
+            .crcs
+            .insert(&path, (calculated_crc, computed_at));
+    } else if let Some(key) = lowercase(file_path) {
+        state.crc_cache.insert(&key, (calculated_crc, computed_at));
+    }
+
+    Ok((
+        crcs.contains(&calculated_crc),
+        ResultSource::FreshIo,
+        computed_at,
+    ))
+}
+
+/// Unlike CRCs, SHA-256 digests are always cached by the paths given in
+/// conditions, as they're not shared between states.
+fn evaluate_checksum_sha256(
+    state: &State,
+    file_path: &Path,
+    digest: &[u8; 32],
+) -> Result<(bool, ResultSource, SystemTime), Error> {
+    let key = lowercase(file_path);
+
+    if let Some((cached_digest, cached_at)) = key.as_ref().and_then(|k| state.sha256_cache.get(k)) {
+        return Ok((
+            cached_digest == *digest,
+            ResultSource::Sha256Cache,
+            cached_at,
+        ));
+    }
+
+    let computed_at = SystemTime::now();
+
+    let path = resolve_path(state, file_path)?;
+    if !is_file(state, &path)? {
+        return Ok((false, ResultSource::FreshIo, computed_at));
+    }
+
+    let calculated_digest = with_retries(state, file_path, || {
+        state.file_system.open(&path).and_then(sha256_from_reader)
+    })?
+    .map_err(|e| Error::IoError(file_path.to_path_buf(), e))?;
+
+    if let Some(key) = key {
+        state
+            .sha256_cache
+            .insert(&key, (calculated_digest, computed_at));
+    }
+
+    Ok((
+        calculated_digest == *digest,
+        ResultSource::FreshIo,
+        computed_at,
+    ))
+}
+
+/// Parse an SFV-style manifest, in which each line is a file path followed by
+/// whitespace and the file's CRC-32 in hexadecimal. Blank lines and lines
+/// starting with a semicolon are ignored. Returns None if any other line is
+/// malformed.
+fn parse_checksum_manifest(content: &str) -> Option<Vec<(PathBuf, u32)>> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(';'))
+        .map(|line| {
+            let (path, crc) = line.rsplit_once(char::is_whitespace)?;
+            let crc = u32::from_str_radix(crc, 16).ok()?;
+            let path = path.trim_end().replace('\\', "/");
+
+            (!path.is_empty()).then(|| (PathBuf::from(path), crc))
+        })
+        .collect()
+}
+
+/// Check that every file listed in the given manifest exists and has the CRC
+/// given for it. Paths in the manifest are relative to the manifest's parent
+/// directory. A manifest that is missing, malformed or empty fails the check.
+fn evaluate_checksum_manifest(state: &State, manifest_path: &Path) -> Result<bool, Error> {
+    let resolved_path = resolve_path(state, manifest_path)?;
+    if !is_file(state, &resolved_path)? {
+        return Ok(false);
+    }
+
+    // Manifests larger than the limit for other text files don't match, to
+    // avoid reading a huge file into memory.
+    let content = with_retries(state, manifest_path, || {
+        let mut content = String::new();
+        state
+            .file_system
+            .open(&resolved_path)?
+            .take(MAX_FILE_CONTENT_SIZE.saturating_add(1))
+            .read_to_string(&mut content)?;
+        Ok(content)
+    })?
+    .map_err(|e| Error::IoError(manifest_path.to_path_buf(), e))?;
+
+    if !u64::try_from(content.len()).is_ok_and(|len| len <= MAX_FILE_CONTENT_SIZE) {
+        return Ok(false);
+    }
+
+    let Some(entries) = parse_checksum_manifest(&content) else {
+        return Ok(false);
+    };
+
+    if entries.is_empty() {
+        return Ok(false);
+    }
+
+    let parent_path = manifest_path.parent().unwrap_or_else(|| Path::new(""));
+    for (path, crc) in entries {
+        let path = parent_path.join(path);
+        check_path_is_allowed(state, &path)?;
+
+        if !evaluate_checksum(state, &path, &[crc])?.0 {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+fn lowercase_filename(path: &Path) -> Option<String> {
+    path.file_name()
+        .and_then(OsStr::to_str)
+        .map(str::to_lowercase)
+}
+
+fn get_version(state: &State, file_path: &Path) -> Result<Option<(Version, ResultSource)>, Error> {
+    if !is_file(state, file_path)? {
+        return Ok(None);
+    }
+
+    if let Some(key) = lowercase_filename(file_path) {
+        if let Some(version) = state.plugin_versions.get(&key) {
+            return Ok(Some((
+                Version::from(version.as_str()),
+                ResultSource::Preloaded,
+            )));
+        }
+    }
+
+    if has_plugin_file_extension(state.game_type, file_path) {
+        Ok(None)
+    } else {
+        read_cached_version(
+            state,
+            &state.shared_caches.file_versions,
+            file_path,
+            Version::read_file_version,
         )
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn get_product_version(
+    state: &State,
+    file_path: &Path,
+) -> Result<Option<(Version, ResultSource)>, Error> {
+    if is_file(state, file_path)? {
+        read_cached_version(
+            state,
+            &state.shared_caches.product_versions,
+            file_path,
+            Version::read_product_version,
+        )
+    } else {
+        Ok(None)
+    }
+}
 
-    const LOWERCASE_NON_ASCII: &str = "\u{20ac}\u{192}.";
+type VersionReader = fn(&State, &Path) -> Result<Option<Version>, Error>;
+
+/// Read a version from the given file, or get it from the given cache if it
+/// has already been read.
+fn read_cached_version(
+    state: &State,
+    cache: &Cache<PathBuf, Option<Version>>,
+    file_path: &Path,
+    read_version: VersionReader,
+) -> Result<Option<(Version, ResultSource)>, Error> {
+    if let Some(version) = cache.get(file_path) {
+        return Ok(version.map(|v| (v, ResultSource::VersionCache)));
+    }
+
+    let version = read_version(state, file_path)?;
+    cache.insert(&file_path.to_path_buf(), version.clone());
+
+    Ok(version.map(|v| (v, ResultSource::FreshIo)))
+}
+
+fn compare_versions(
+    actual_version: &Version,
+    comparator: ComparisonOperator,
+    given_version: &str,
+) -> bool {
+    compare(actual_version, comparator, &Version::from(given_version))
+}
+
+fn compare<T: PartialOrd>(actual: &T, comparator: ComparisonOperator, given: &T) -> bool {
+    match comparator {
+        ComparisonOperator::Equal => actual == given,
+        ComparisonOperator::NotEqual => actual != given,
+        ComparisonOperator::LessThan => actual < given,
+        ComparisonOperator::GreaterThan => actual > given,
+        ComparisonOperator::LessThanOrEqual => actual <= given,
+        ComparisonOperator::GreaterThanOrEqual => actual >= given,
+    }
+}
+
+fn evaluate_version<F>(
+    state: &State,
+    file_path: &Path,
+    given_version: &str,
+    comparator: ComparisonOperator,
+    read_version: F,
+) -> Result<(bool, ResultSource), Error>
+where
+    F: Fn(&State, &Path) -> Result<Option<(Version, ResultSource)>, Error>,
+{
+    let file_path = resolve_path(state, file_path)?;
+    let Some((actual_version, source)) = read_version(state, &file_path)? else {
+        let result = comparator == ComparisonOperator::NotEqual
+            || comparator == ComparisonOperator::LessThan
+            || comparator == ComparisonOperator::LessThanOrEqual;
+        return Ok((result, ResultSource::FreshIo));
+    };
+
+    Ok((
+        compare_versions(&actual_version, comparator, given_version),
+        source,
+    ))
+}
+
+/// Compare the versions of two files, which may be plugins with cached
+/// versions. If either version can't be found the result is false.
+fn evaluate_version_cmp(
+    state: &State,
+    file_path: &Path,
+    other_file_path: &Path,
+    comparator: ComparisonOperator,
+) -> Result<(bool, ResultSource), Error> {
+    let version = get_version(state, &resolve_path(state, file_path)?)?;
+    let other_version = get_version(state, &resolve_path(state, other_file_path)?)?;
+
+    let (Some((version, source)), Some((other_version, other_source))) = (version, other_version)
+    else {
+        return Ok((false, ResultSource::FreshIo));
+    };
+
+    let source = if source == ResultSource::Preloaded && other_source == ResultSource::Preloaded {
+        ResultSource::Preloaded
+    } else {
+        ResultSource::FreshIo
+    };
+
+    Ok((compare(&version, comparator, &other_version), source))
+}
+
+fn evaluate_version_range<F>(
+    state: &State,
+    file_path: &Path,
+    lower_version: &str,
+    upper_version: &str,
+    read_version: F,
+) -> Result<(bool, ResultSource), Error>
+where
+    F: Fn(&State, &Path) -> Result<Option<(Version, ResultSource)>, Error>,
+{
+    let file_path = resolve_path(state, file_path)?;
+    let Some((actual_version, source)) = read_version(state, &file_path)? else {
+        return Ok((false, ResultSource::FreshIo));
+    };
+
+    Ok((
+        actual_version >= Version::from(lower_version)
+            && actual_version <= Version::from(upper_version),
+        source,
+    ))
+}
+
+#[cfg(feature = "regex")]
+fn captured_version<'a>(regex: &Regex, file_name: &'a str) -> Option<&'a str> {
+    regex
+        .captures(file_name)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str())
+}
+
+/// Wildcard patterns can't contain capture groups, so `filename_version()`
+/// functions fail to parse when regex support is disabled.
+#[cfg(not(feature = "regex"))]
+fn captured_version<'a>(_regex: &Regex, _file_name: &'a str) -> Option<&'a str> {
+    None
+}
+
+fn evaluate_filename_version(
+    state: &State,
+    parent_path: &Path,
+    regex: &Regex,
+    version: &str,
+    comparator: ComparisonOperator,
+) -> Result<bool, Error> {
+    let evaluator = |entry: &DirEntry| {
+        normalise_file_name(state.game_type, entry.file_name())
+            .to_str()
+            .and_then(|s| captured_version(regex, s))
+            .map(Version::from)
+            .is_some_and(|v| compare_versions(&v, comparator, version))
+    };
+
+    evaluate_dir_entries(state, parent_path, evaluator)
+}
+
+fn evaluate_description_contains(
+    state: &State,
+    file_path: &Path,
+    regex: &Regex,
+) -> (bool, ResultSource) {
+    read_plugin(state, file_path, |plugin| {
+        plugin
+            .description()
+            .is_some_and(|description| regex.is_match(&description))
+    })
+    .unwrap_or((false, ResultSource::FreshIo))
+}
+
+/// Plugins are read using their unghosted filenames, so that the host's parsed
+/// plugins are used for ghosted plugins too.
+fn evaluate_description_regex(
+    state: &State,
+    parent_path: &Path,
+    regex: &Regex,
+    description_regex: &Regex,
+) -> Result<bool, Error> {
+    let evaluator = |entry: &DirEntry| {
+        let file_name = entry.file_name();
+        if !is_match(state.game_type, regex, file_name) {
+            return false;
+        }
+
+        let file_path = parent_path.join(normalise_file_name(state.game_type, file_name));
+        read_plugin(state, &file_path, |plugin| {
+            plugin
+                .description()
+                .is_some_and(|description| description_regex.is_match(&description))
+        })
+        .is_some_and(|(matches, _)| matches)
+    };
+
+    evaluate_dir_entries(state, parent_path, evaluator)
+}
+
+/// The author is always read from the plugin file, as [ParsedPlugin] doesn't
+/// provide it. Plugins that can't be read or that have no author don't match.
+fn evaluate_author_contains(state: &State, file_path: &Path, regex: &Regex) -> Result<bool, Error> {
+    let resolved_path = resolve_path(state, file_path)?;
+
+    Ok(with_io(state, file_path, || {
+        read_author(state.game_type, state.file_system.as_ref(), &resolved_path)
+    })?
+    .ok()
+    .flatten()
+    .is_some_and(|author| regex.is_match(&author)))
+}
+
+fn modified_time(state: &State, path: &Path) -> Result<Option<SystemTime>, Error> {
+    let resolved_path = resolve_path(state, path)?;
+
+    Ok(
+        with_io(state, path, || state.file_system.metadata(&resolved_path))?
+            .ok()
+            .and_then(|m| m.modified()),
+    )
+}
+
+fn evaluate_is_newer_than(state: &State, path: &Path, other_path: &Path) -> Result<bool, Error> {
+    match (
+        modified_time(state, path)?,
+        modified_time(state, other_path)?,
+    ) {
+        (Some(time), Some(other_time)) => Ok(time > other_time),
+        _ => Ok(false),
+    }
+}
+
+fn evaluate_description_contains_text(
+    state: &State,
+    file_path: &Path,
+    text: &str,
+) -> (bool, ResultSource) {
+    let text = text.to_lowercase();
+
+    read_plugin(state, file_path, |plugin| {
+        plugin
+            .description()
+            .is_some_and(|description| description.to_lowercase().contains(&text))
+    })
+    .unwrap_or((false, ResultSource::FreshIo))
+}
+
+impl Function {
+    #[cfg(test)]
+    pub(crate) fn eval(&self, state: &State) -> Result<bool, Error> {
+        self.eval_with_observer(state, &(0..0), &mut ())
+    }
+
+    /// Evaluate the function and pass its result and where that came from to
+    /// the given observer, along with the function's span in the condition
+    /// string that it was parsed from.
+    pub(crate) fn eval_with_observer(
+        &self,
+        state: &State,
+        span: &Range<usize>,
+        observer: &mut dyn EvalObserver,
+    ) -> Result<bool, Error> {
+        let (result, source, computed_at) = self.eval_with_source(state)?;
+
+        observer.function_evaluated(self, span.clone(), result, source, computed_at);
+
+        Ok(result)
+    }
+
+    /// Functions are evaluated and cached with their variable references
+    /// replaced by the variables' current values.
+    pub(crate) fn eval_with_source(
+        &self,
+        state: &State,
+    ) -> Result<(bool, ResultSource, SystemTime), Error> {
+        self.bind_variables(state)?.eval_bound(state)
+    }
+
+    /// Get the paths that the function reads from, resolved against the
+    /// state's data paths in the same way as when it is evaluated.
+    pub(crate) fn resolved_paths(&self, state: &State) -> Result<Vec<PathBuf>, Error> {
+        self.paths().map(|path| resolve_path(state, path)).collect()
+    }
+
+    /// Check if evaluating the function would get its result without accessing
+    /// the filesystem, because its result or the data it needs is cached, or
+    /// one of its paths is already known to be missing. Errors if any of its
+    /// paths aren't allowed. Variables must already be bound.
+    pub(crate) fn has_cached_data(&self, state: &State) -> Result<bool, Error> {
+        for path in self.paths() {
+            check_path_is_allowed(state, path)?;
+        }
+
+        Ok(self.is_slow() && state.condition_cache.get(self).is_some()
+            || self.has_state_data(state)
+            || self.result_if_missing(state).is_some())
+    }
+
+    /// Get a copy of the function with its variable references replaced by
+    /// the variables' values, or the function itself if it doesn't reference
+    /// any variables.
+    pub(crate) fn bind_variables(&self, state: &State) -> Result<Cow<'_, Function>, Error> {
+        if !self
+            .variable_strings()
+            .iter()
+            .any(|s| variables::has_references(s))
+        {
+            return Ok(Cow::Borrowed(self));
+        }
+
+        let string = |s: &str| {
+            variables::substitute(s, &state.variables)
+                .map(Cow::into_owned)
+                .map_err(|e| match e {
+                    VariableError::Invalid(kind) => Error::ParsingError(s.to_owned(), kind),
+                    VariableError::Unbound(name) => Error::UnboundVariable(name),
+                })
+        };
+        // Substituting into a lossily converted path could give a different
+        // path, so paths that aren't valid UTF-8 are an error.
+        let path = |p: &Path| {
+            let Some(s) = p.to_str() else {
+                return Err(Error::IoError(
+                    p.to_path_buf(),
+                    io::Error::new(io::ErrorKind::InvalidData, "the path is not valid UTF-8"),
+                ));
+            };
+            string(s).map(PathBuf::from)
+        };
+
+        let function = match self {
+            Function::FilePath(p) => Function::FilePath(path(p)?),
+            Function::FileRegex(p, r) => Function::FileRegex(path(p)?, r.clone()),
+            Function::FileGlob(p, g, r) => Function::FileGlob(path(p)?, g.clone(), r.clone()),
+            Function::RecursiveFileRegex(p, r, d) => {
+                Function::RecursiveFileRegex(path(p)?, r.clone(), *d)
+            }
+            Function::FileSize(p, s, c) => Function::FileSize(path(p)?, *s, *c),
+            Function::FileModifiedTime(p, c, t) => Function::FileModifiedTime(path(p)?, *c, *t),
+            Function::Readable(p) => Function::Readable(path(p)?),
+            Function::Writable(p) => Function::Writable(path(p)?),
+            Function::RegistryValue(k, n, r) => {
+                Function::RegistryValue(string(k)?, string(n)?, r.clone())
+            }
+            Function::IsPlatform(p) => Function::IsPlatform(*p),
+            Function::IsWine => Function::IsWine,
+            Function::IsExecutable(p) => Function::IsExecutable(path(p)?),
+            Function::IsArchitecture(p, a) => Function::IsArchitecture(path(p)?, *a),
+            Function::ActivePath(p) => Function::ActivePath(path(p)?),
+            Function::ActiveRegex(r) => Function::ActiveRegex(r.clone()),
+            Function::IsMaster(p) => Function::IsMaster(path(p)?),
+            Function::IsLightPlugin(p) => Function::IsLightPlugin(path(p)?),
+            Function::IsMediumPlugin(p) => Function::IsMediumPlugin(path(p)?),
+            Function::HeaderVersion(p, c, v) => Function::HeaderVersion(path(p)?, *c, *v),
+            Function::RecordCount(p, c, n) => Function::RecordCount(path(p)?, *c, *n),
+            Function::MastersContain(p, m) => Function::MastersContain(path(p)?, string(m)?),
+            Function::IsValidPlugin(p) => Function::IsValidPlugin(path(p)?),
+            Function::FileContentContains(p, r) => {
+                Function::FileContentContains(path(p)?, r.clone())
+            }
+            Function::IniValue(p, s, k, v) => {
+                Function::IniValue(path(p)?, string(s)?, string(k)?, string(v)?)
+            }
+            Function::ArchiveContains(p, e) => Function::ArchiveContains(path(p)?, string(e)?),
+            Function::ArchiveRegex(p, r) => Function::ArchiveRegex(path(p)?, r.clone()),
+            Function::ArchiveVersion(p, c, v) => Function::ArchiveVersion(path(p)?, *c, *v),
+            Function::Many(p, r) => Function::Many(path(p)?, r.clone()),
+            Function::RecursiveMany(p, r, d) => Function::RecursiveMany(path(p)?, r.clone(), *d),
+            Function::Count(p, r, c, n) => Function::Count(path(p)?, r.clone(), *c, *n),
+            Function::ManyActive(r) => Function::ManyActive(r.clone()),
+            Function::ActiveCount(r, c, n) => Function::ActiveCount(r.clone(), *c, *n),
+            Function::Checksum(p, c) => Function::Checksum(path(p)?, c.clone()),
+            Function::ChecksumSha256(p, d) => Function::ChecksumSha256(path(p)?, *d),
+            Function::Version(p, v, c) => Function::Version(path(p)?, string(v)?, *c),
+            Function::ProductVersion(p, v, c) => Function::ProductVersion(path(p)?, string(v)?, *c),
+            Function::FilenameVersion(p, r, v, c) => {
+                Function::FilenameVersion(path(p)?, r.clone(), string(v)?, *c)
+            }
+            Function::DescriptionContains(p, r) => {
+                Function::DescriptionContains(path(p)?, r.clone())
+            }
+            Function::DescriptionRegex(p, r, d) => {
+                Function::DescriptionRegex(path(p)?, r.clone(), d.clone())
+            }
+            Function::AuthorContains(p, r) => Function::AuthorContains(path(p)?, r.clone()),
+            Function::IsNewerThan(p1, p2) => Function::IsNewerThan(path(p1)?, path(p2)?),
+            Function::ChecksumManifest(p) => Function::ChecksumManifest(path(p)?),
+            Function::DescriptionContainsText(p, t) => {
+                Function::DescriptionContainsText(path(p)?, string(t)?)
+            }
+            Function::VersionCmp(p1, p2, c) => Function::VersionCmp(path(p1)?, path(p2)?, *c),
+            Function::VersionInRange(p, l, u) => {
+                Function::VersionInRange(path(p)?, string(l)?, string(u)?)
+            }
+            Function::ProductVersionInRange(p, l, u) => {
+                Function::ProductVersionInRange(path(p)?, string(l)?, string(u)?)
+            }
+        };
+
+        Ok(Cow::Owned(function))
+    }
+
+    #[expect(clippy::too_many_lines)]
+    fn eval_bound(&self, state: &State) -> Result<(bool, ResultSource, SystemTime), Error> {
+        check_cancellation(state)?;
+
+        for path in self.paths() {
+            check_path_is_allowed(state, path)?;
+        }
+
+        if self.is_slow() {
+            if let Some((cached_result, cached_at)) = state.condition_cache.get(self) {
+                return Ok((cached_result, ResultSource::ConditionCache, cached_at));
+            }
+        }
+
+        if let Some((result, missing_since)) = self.result_if_missing(state) {
+            return Ok((result, ResultSource::MissingFileCache, missing_since));
+        }
+
+        if !state.filesystem_access && !self.has_state_data(state) {
+            if let Some(path) = self.paths().next() {
+                return Err(Error::FilesystemAccessDisabled(path.to_path_buf()));
+            }
+        }
+
+        let computed_at = SystemTime::now();
+        let fresh = |result| (result, ResultSource::FreshIo, computed_at);
+        let preloaded = |result| (result, ResultSource::Preloaded, computed_at);
+        let constant = |result| (result, ResultSource::Constant, computed_at);
+        let with_source = |(result, source)| (result, source, computed_at);
+
+        let evaluation = match self {
+            Function::FilePath(f) => with_source(evaluate_file_path(state, f)?),
+            Function::FileRegex(p, r) | Function::FileGlob(p, _, r) => {
+                with_source(evaluate_file_regex(state, p, r, 0)?)
+            }
+            Function::RecursiveFileRegex(p, r, d) => {
+                with_source(evaluate_file_regex(state, p, r, *d)?)
+            }
+            Function::FileSize(p, s, c) => with_source(evaluate_file_size(state, p, *s, *c)?),
+            Function::FileModifiedTime(p, c, t) => {
+                fresh(evaluate_file_modified_time(state, p, *c, *t)?)
+            }
+            Function::Readable(p) => fresh(evaluate_readable(state, p)?),
+            Function::Writable(p) => fresh(evaluate_writable(state, p)?),
+            Function::RegistryValue(k, n, r) => fresh(evaluate_registry_value(k, n, r)),
+            Function::IsPlatform(p) => constant(p.is_current()),
+            Function::IsWine => constant(is_wine()),
+            Function::IsExecutable(p) => fresh(evaluate_is_executable(state, p)?),
+            Function::IsArchitecture(p, a) => fresh(evaluate_is_architecture(state, p, *a)?),
+            Function::ActivePath(p) => preloaded(evaluate_active_path(state, p)),
+            Function::ActiveRegex(r) => preloaded(evaluate_active_regex(state, r)),
+            Function::IsMaster(p) => with_source(evaluate_is_master(state, p)),
+            Function::IsLightPlugin(p) => with_source(evaluate_is_light_plugin(state, p)),
+            Function::IsMediumPlugin(p) => with_source(evaluate_is_medium_plugin(state, p)),
+            Function::HeaderVersion(p, c, v) => {
+                with_source(evaluate_header_version(state, p, *c, *v))
+            }
+            Function::RecordCount(p, c, n) => evaluate_record_count(state, p, *c, *n),
+            Function::MastersContain(p, m) => with_source(evaluate_masters_contain(state, p, m)),
+            Function::IsValidPlugin(p) => with_source(evaluate_is_valid_plugin(state, p)),
+            Function::FileContentContains(p, r) => {
+                fresh(evaluate_file_content_contains(state, p, r)?)
+            }
+            Function::IniValue(p, s, k, v) => fresh(evaluate_ini_value(state, p, s, k, v)?),
+            Function::ArchiveContains(p, e) => evaluate_archive_contains(state, p, e)?,
+            Function::ArchiveRegex(p, r) => evaluate_archive_regex(state, p, r)?,
+            Function::ArchiveVersion(p, c, v) => fresh(evaluate_archive_version(state, p, *c, *v)?),
+            Function::Many(p, r) => fresh(evaluate_many(state, p, r, 0)?),
+            Function::RecursiveMany(p, r, d) => fresh(evaluate_many(state, p, r, *d)?),
+            Function::Count(p, r, c, n) => fresh(evaluate_count(state, p, r, 0, *c, *n)?),
+            Function::ManyActive(r) => preloaded(evaluate_many_active(state, r)),
+            Function::ActiveCount(r, c, n) => preloaded(evaluate_active_count(state, r, *c, *n)),
+            Function::Checksum(path, crcs) => evaluate_checksum(state, path, crcs)?,
+            Function::ChecksumSha256(path, digest) => {
+                evaluate_checksum_sha256(state, path, digest)?
+            }
+            Function::Version(p, v, c) => {
+                with_source(evaluate_version(state, p, v, *c, get_version)?)
+            }
+            Function::ProductVersion(p, v, c) => {
+                with_source(evaluate_version(state, p, v, *c, get_product_version)?)
+            }
+            Function::FilenameVersion(p, r, v, c) => {
+                fresh(evaluate_filename_version(state, p, r, v, *c)?)
+            }
+            Function::DescriptionContains(p, r) => {
+                with_source(evaluate_description_contains(state, p, r))
+            }
+            Function::DescriptionRegex(p, r, d) => {
+                fresh(evaluate_description_regex(state, p, r, d)?)
+            }
+            Function::AuthorContains(p, r) => fresh(evaluate_author_contains(state, p, r)?),
+            Function::IsNewerThan(p1, p2) => fresh(evaluate_is_newer_than(state, p1, p2)?),
+            Function::ChecksumManifest(p) => fresh(evaluate_checksum_manifest(state, p)?),
+            Function::DescriptionContainsText(p, t) => {
+                with_source(evaluate_description_contains_text(state, p, t))
+            }
+            Function::VersionCmp(p1, p2, c) => {
+                with_source(evaluate_version_cmp(state, p1, p2, *c)?)
+            }
+            Function::VersionInRange(p, l, u) => {
+                with_source(evaluate_version_range(state, p, l, u, get_version)?)
+            }
+            Function::ProductVersionInRange(p, l, u) => {
+                with_source(evaluate_version_range(state, p, l, u, get_product_version)?)
+            }
+        };
+
+        if self.is_slow() {
+            state
+                .condition_cache
+                .insert(self, (evaluation.0, computed_at));
+        }
+
+        Ok(evaluation)
+    }
+
+    /// Check if the function's result could depend on the plugin with the
+    /// given filename, either because the plugin is named in the function or
+    /// because it's matched by the function's regex.
+    pub(crate) fn references_plugin(&self, game_type: GameType, plugin_name: &str) -> bool {
+        match self {
+            Self::FileRegex(_, r)
+            | Self::FileGlob(_, _, r)
+            | Self::ActiveRegex(r)
+            | Self::Many(_, r)
+            | Self::RecursiveFileRegex(_, r, _)
+            | Self::RecursiveMany(_, r, _)
+            | Self::Count(_, r, _, _)
+            | Self::ManyActive(r)
+            | Self::ActiveCount(r, _, _)
+            | Self::FilenameVersion(_, r, _, _)
+            | Self::DescriptionRegex(_, r, _) => r.is_match(plugin_name),
+            Self::ActivePath(p) => is_plugin_path(game_type, p, plugin_name),
+            _ => self
+                .paths()
+                .any(|p| is_plugin_path(game_type, p, plugin_name)),
+        }
+    }
+
+    /// Read the metadata of the files that the function reads, or the entries
+    /// of the directories that it reads, into the state's caches, so that
+    /// evaluating it doesn't need to access the filesystem for them. Nothing
+    /// is read if the function's result or data is already cached.
+    pub(crate) fn cache_file_system_data(&self, state: &State) -> Result<(), Error> {
+        let function = self.bind_variables(state)?;
+
+        if !state.filesystem_access || function.has_cached_data(state)? {
+            return Ok(());
+        }
+
+        match operation_kind(&function) {
+            Some(OperationKind::ReadDir) => {
+                for path in function.paths() {
+                    for data_path in data_paths_in_search_order(state) {
+                        let directory = data_path.join(path);
+                        read_dir_entries(state, &directory, state.max_directory_entries)?;
+                    }
+                }
+            }
+            Some(_) => {
+                for path in function.paths() {
+                    // Read the metadata of the paths that resolution checks
+                    // first, so that resolution doesn't check them again.
+                    for data_path in data_paths_in_search_order(state) {
+                        let (metadata, _) = read_metadata(state, path, &data_path.join(path))?;
+                        if metadata.is_some() {
+                            break;
+                        }
+                    }
+                    read_metadata(state, path, &resolve_path(state, path)?)?;
+                }
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+
+    /// Check if the function's result could depend on the version given to
+    /// the state for the plugin with the given filename.
+    pub(crate) fn references_plugin_version(&self, plugin_name: &str) -> bool {
+        let is_plugin = |path: &Path| {
+            lowercase_filename(path).is_some_and(|name| name == plugin_name.to_lowercase())
+        };
+
+        match self {
+            Self::Version(p, _, _) | Self::VersionInRange(p, _, _) => is_plugin(p),
+            Self::VersionCmp(p1, p2, _) => is_plugin(p1) || is_plugin(p2),
+            _ => false,
+        }
+    }
+
+    /// If a path that the function reads from is in the missing path cache,
+    /// get the function's result for a missing path and when the path was
+    /// found to be missing. Functions that could use data given to the state
+    /// for the path are left to be evaluated, as are functions that read from
+    /// directories, which aren't resolved like other paths. In strict mode,
+    /// all functions are evaluated, as resolving a path treats errors like it
+    /// being missing.
+    fn result_if_missing(&self, state: &State) -> Option<(bool, SystemTime)> {
+        if state.strict || self.has_state_data(state) {
+            return None;
+        }
+
+        let result = match self {
+            Self::FilePath(_)
+            | Self::FileSize(_, _, _)
+            | Self::FileModifiedTime(_, _, _)
+            | Self::Readable(_)
+            | Self::Writable(_)
+            | Self::IsExecutable(_)
+            | Self::IsArchitecture(_, _)
+            | Self::IsValidPlugin(_)
+            | Self::FileContentContains(_, _)
+            | Self::IniValue(_, _, _, _)
+            | Self::ArchiveContains(_, _)
+            | Self::ArchiveRegex(_, _)
+            | Self::ArchiveVersion(_, _, _)
+            | Self::ChecksumManifest(_)
+            | Self::IsNewerThan(_, _)
+            | Self::VersionCmp(_, _, _)
+            | Self::VersionInRange(_, _, _)
+            | Self::ProductVersionInRange(_, _, _)
+            | Self::IsMaster(_)
+            | Self::IsLightPlugin(_)
+            | Self::IsMediumPlugin(_)
+            | Self::HeaderVersion(_, _, _)
+            | Self::RecordCount(_, _, _)
+            | Self::MastersContain(_, _)
+            | Self::DescriptionContains(_, _)
+            | Self::DescriptionContainsText(_, _)
+            | Self::AuthorContains(_, _)
+            | Self::Checksum(_, _)
+            | Self::ChecksumSha256(_, _) => false,
+            Self::Version(_, _, comparator) | Self::ProductVersion(_, _, comparator) => matches!(
+                comparator,
+                ComparisonOperator::NotEqual
+                    | ComparisonOperator::LessThan
+                    | ComparisonOperator::LessThanOrEqual
+            ),
+            Self::FileRegex(_, _)
+            | Self::FileGlob(_, _, _)
+            | Self::Many(_, _)
+            | Self::RecursiveFileRegex(_, _, _)
+            | Self::RecursiveMany(_, _, _)
+            | Self::Count(_, _, _, _)
+            | Self::FilenameVersion(_, _, _, _)
+            | Self::DescriptionRegex(_, _, _)
+            | Self::ActivePath(_)
+            | Self::ActiveRegex(_)
+            | Self::ManyActive(_)
+            | Self::ActiveCount(_, _, _)
+            | Self::RegistryValue(_, _, _)
+            | Self::IsPlatform(_)
+            | Self::IsWine => return None,
+        };
+
+        self.paths()
+            .find_map(|path| missing_since(state, path))
+            .map(|missing_since| (result, missing_since))
+    }
+
+    /// Check if the function can be evaluated using data that was given to the
+    /// state for its path (a parsed plugin or a CRC), instead of reading the
+    /// path.
+    fn has_state_data(&self, state: &State) -> bool {
+        match self {
+            Self::IsMaster(p)
+            | Self::IsLightPlugin(p)
+            | Self::IsMediumPlugin(p)
+            | Self::HeaderVersion(p, _, _)
+            | Self::MastersContain(p, _)
+            | Self::DescriptionContains(p, _)
+            | Self::DescriptionContainsText(p, _) => {
+                lowercase_filename(p).is_some_and(|key| state.parsed_plugins.contains_key(&key))
+                    || is_plugin_cached(state, p)
+            }
+            Self::IsValidPlugin(p) => {
+                has_plugin_file_extension(state.game_type, p) && is_plugin_cached(state, p)
+            }
+            Self::Checksum(p, _) => {
+                lowercase(p).is_some_and(|key| state.crc_cache.get(&key).is_some())
+            }
+            Self::ChecksumSha256(p, _) => {
+                lowercase(p).is_some_and(|key| state.sha256_cache.get(&key).is_some())
+            }
+            Self::ArchiveContains(p, _) | Self::ArchiveRegex(p, _) => {
+                lowercase(p).is_some_and(|key| state.archive_index_cache.get(&key).is_some())
+            }
+            Self::RecordCount(p, _, _) => {
+                lowercase(p).is_some_and(|key| state.record_count_cache.get(&key).is_some())
+                    || lowercase_filename(p)
+                        .is_some_and(|key| state.parsed_plugins.contains_key(&key))
+            }
+            Self::FilePath(p) | Self::FileSize(p, _, _) => state.virtual_files.contains(p),
+            _ => false,
+        }
+    }
+
+    /// Some functions are faster to evaluate than to look their result up in
+    /// the cache, as the data they operate on are already cached separately and
+    /// the operation is simple.
+    fn is_slow(&self) -> bool {
+        !matches!(
+            self,
+            Self::ActivePath(_)
+                | Self::ActiveRegex(_)
+                | Self::ManyActive(_)
+                | Self::ActiveCount(_, _, _)
+                | Self::IsPlatform(_)
+                | Self::IsWine
+                | Self::Checksum(_, _)
+                | Self::ChecksumSha256(_, _)
+                | Self::RecordCount(_, _, _)
+                | Self::ArchiveContains(_, _)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOWERCASE_NON_ASCII: &str = "\u{20ac}\u{192}.";
+
+    use std::collections::HashMap;
+    use std::fs::{copy, create_dir_all, read_dir, remove_file, File};
+    use std::sync::Mutex;
+
+    use tempfile::tempdir;
+
+    use crate::function::archive::tests::{ba2, bsa};
+    use crate::function::parse::compile_regex;
+    use crate::function::Platform;
+    use crate::FileSystem;
+
+    fn state<T: Into<PathBuf>>(data_path: T) -> State {
+        state_with_active_plugins(data_path, &[])
+    }
+
+    fn state_with_active_plugins<T: Into<PathBuf>>(data_path: T, active_plugins: &[&str]) -> State {
+        state_with_data(data_path, Vec::default(), active_plugins, &[])
+    }
+
+    fn state_with_versions<T: Into<PathBuf>>(
+        data_path: T,
+        plugin_versions: &[(&str, &str)],
+    ) -> State {
+        state_with_data(data_path, Vec::default(), &[], plugin_versions)
+    }
+
+    fn state_with_data<T: Into<PathBuf>>(
+        data_path: T,
+        additional_data_paths: Vec<T>,
+        active_plugins: &[&str],
+        plugin_versions: &[(&str, &str)],
+    ) -> State {
+        let data_path = data_path.into();
+        if !data_path.exists() {
+            create_dir_all(&data_path).unwrap();
+        }
+
+        let additional_data_paths = additional_data_paths
+            .into_iter()
+            .map(|data_path| {
+                let data_path: PathBuf = data_path.into();
+                if !data_path.exists() {
+                    create_dir_all(&data_path).unwrap();
+                }
+                data_path
+            })
+            .collect();
+
+        State {
+            game_type: GameType::Oblivion,
+            data_path,
+            additional_data_paths,
+            active_plugins: active_plugins
+                .iter()
+                .map(|s| (fold_case(s), (*s).to_owned()))
+                .collect(),
+            crc_cache: Cache::default(),
+            sha256_cache: Cache::default(),
+            record_count_cache: Cache::default(),
+            plugin_cache: Cache::default(),
+            archive_index_cache: Cache::default(),
+            plugin_versions: plugin_versions
+                .iter()
+                .map(|(p, v)| (p.to_lowercase(), (*v).to_owned()))
+                .collect(),
+            condition_cache: Cache::default(),
+            wine_path_mapper: None,
+            resolved_path_cache: Cache::default(),
+            directory_cache: Cache::default(),
+            metadata_cache: Cache::default(),
+            missing_path_cache: Cache::default(),
+            path_traversal_allowlist: None,
+            max_directory_entries: None,
+            memory_map_threshold: Some(crate::DEFAULT_MEMORY_MAP_THRESHOLD),
+            #[cfg(feature = "parallel")]
+            parallel_crc_threshold: Some(crate::DEFAULT_PARALLEL_CRC_THRESHOLD),
+            deterministic_ordering: false,
+            filesystem_access: true,
+            parsed_plugins: HashMap::default(),
+            shared_caches: Arc::default(),
+            variables: HashMap::default(),
+            retry_policy: None,
+            file_system: Arc::new(crate::StdFileSystem),
+            virtual_files: crate::VirtualFiles::default(),
+            strict: false,
+            cancellation_token: None,
+            deadline: None,
+        }
+    }
+
+    fn regex(string: &str) -> Regex {
+        compile_regex(string, &crate::ParseOptions::default()).unwrap()
+    }
+
+    #[cfg(not(windows))]
+    fn make_path_unreadable(path: &Path) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut permissions = std::fs::metadata(path).unwrap().permissions();
+        permissions.set_mode(0o200);
+        std::fs::set_permissions(path, permissions).unwrap();
+    }
+
+    /// Records the directories that are read, in order.
+    #[derive(Debug, Default)]
+    struct DirectoryRecorder(Mutex<Vec<PathBuf>>);
+
+    impl DirectoryRecorder {
+        fn directories(&self) -> Vec<PathBuf> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl FileSystem for DirectoryRecorder {
+        fn metadata(&self, path: &Path) -> std::io::Result<crate::Metadata> {
+            crate::StdFileSystem.metadata(path)
+        }
+
+        fn read_dir(
+            &self,
+            path: &Path,
+        ) -> std::io::Result<Box<dyn Iterator<Item = std::io::Result<DirEntry>> + '_>> {
+            if let Ok(mut directories) = self.0.lock() {
+                directories.push(path.to_path_buf());
+            }
+            crate::StdFileSystem.read_dir(path)
+        }
+
+        fn open(&self, path: &Path) -> std::io::Result<Box<dyn crate::ReadSeek>> {
+            crate::StdFileSystem.open(path)
+        }
+    }
+
+    /// Fails every operation with the given kind of error.
+    #[derive(Debug)]
+    struct FailingFileSystem(std::io::ErrorKind);
+
+    impl FileSystem for FailingFileSystem {
+        fn metadata(&self, _: &Path) -> std::io::Result<crate::Metadata> {
+            Err(self.0.into())
+        }
+
+        fn read_dir(
+            &self,
+            _: &Path,
+        ) -> std::io::Result<Box<dyn Iterator<Item = std::io::Result<DirEntry>> + '_>> {
+            Err(self.0.into())
+        }
+
+        fn open(&self, _: &Path) -> std::io::Result<Box<dyn crate::ReadSeek>> {
+            Err(self.0.into())
+        }
+    }
+
+    fn failing_state(kind: std::io::ErrorKind) -> State {
+        let mut state = state(".");
+        state.set_file_system(Arc::new(FailingFileSystem(kind)));
+        state
+    }
+
+    #[test]
+    fn evaluate_dir_entries_should_check_additional_paths_in_order_then_data_path() {
+        let mut state = state_with_data(
+            "./tests/testing-plugins/SkyrimSE",
+            vec![
+                "./tests/testing-plugins/Oblivion",
+                "./tests/testing-plugins/Skyrim",
+            ],
+            &[],
+            &[],
+        );
+
+        let file_system = Arc::new(DirectoryRecorder::default());
+        state.file_system = Arc::<DirectoryRecorder>::clone(&file_system);
+
+        let result = evaluate_dir_entries(&state, Path::new("Data"), |_| false).unwrap();
+
+        assert!(!result);
+        let paths: Vec<_> = file_system
+            .directories()
+            .iter()
+            .map(|p| p.parent().unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            vec![
+                state.additional_data_paths[0].clone(),
+                state.additional_data_paths[1].clone(),
+                state.data_path,
+            ],
+            paths
+        );
+    }
+
+    #[test]
+    fn evaluate_dir_entries_should_check_additional_paths_in_reverse_order_then_data_path_for_openmw(
+    ) {
+        let mut state = state_with_data(
+            "./tests/testing-plugins/SkyrimSE",
+            vec![
+                "./tests/testing-plugins/Oblivion",
+                "./tests/testing-plugins/Skyrim",
+            ],
+            &[],
+            &[],
+        );
+        state.game_type = GameType::OpenMW;
+
+        let file_system = Arc::new(DirectoryRecorder::default());
+        state.file_system = Arc::<DirectoryRecorder>::clone(&file_system);
+
+        let result = evaluate_dir_entries(&state, Path::new("Data"), |_| false).unwrap();
+
+        assert!(!result);
+        let paths: Vec<_> = file_system
+            .directories()
+            .iter()
+            .map(|p| p.parent().unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            vec![
+                state.additional_data_paths[1].clone(),
+                state.additional_data_paths[0].clone(),
+                state.data_path,
+            ],
+            paths
+        );
+    }
+
+    #[test]
+    fn evaluate_dir_entries_should_error_if_more_entries_than_the_limit_are_examined() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let other_path = tmp_dir.path().join("Other");
+        for (path, names) in [
+            (&data_path, ["a", "b", "c"].as_slice()),
+            (&other_path, &["d", "e"]),
+        ] {
+            create_dir_all(path).unwrap();
+            for name in names {
+                File::create(path.join(name)).unwrap();
+            }
+        }
+
+        let mut state = state_with_data(data_path, vec![other_path], &[], &[]);
+
+        state.set_max_directory_entries(Some(5));
+        assert!(!evaluate_dir_entries(&state, Path::new(""), |_| false).unwrap());
+
+        state.set_max_directory_entries(Some(4));
+        assert!(matches!(
+            evaluate_dir_entries(&state, Path::new(""), |_| false),
+            Err(Error::DirectoryEntryLimitExceeded(_, 4))
+        ));
+
+        state.set_max_directory_entries(Some(1));
+        assert!(evaluate_dir_entries(&state, Path::new(""), |_| true).unwrap());
+    }
+
+    #[test]
+    fn function_file_regex_eval_should_error_if_the_directory_entry_limit_is_exceeded() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        create_dir_all(&data_path).unwrap();
+        File::create(data_path.join("a.esp")).unwrap();
+        File::create(data_path.join("b.esp")).unwrap();
+
+        let mut state = state(data_path);
+        state.set_max_directory_entries(Some(1));
+
+        let function = Function::FileRegex(PathBuf::from("."), regex("c\\.esp"));
+
+        assert!(matches!(
+            function.eval(&state),
+            Err(Error::DirectoryEntryLimitExceeded(_, 1))
+        ));
+    }
+
+    #[test]
+    fn function_eval_should_use_the_missing_path_cache_for_other_functions_with_the_same_path() {
+        let state = state(".");
+        let path = PathBuf::from("missing.esp");
+
+        let (result, source, _) = Function::FilePath(path.clone())
+            .eval_with_source(&state)
+            .unwrap();
+        assert!(!result);
+        assert_eq!(ResultSource::FreshIo, source);
+
+        for (function, expected_result) in [
+            (Function::Readable(path.clone()), false),
+            (Function::Writable(path.clone()), false),
+            (Function::Checksum(path.clone(), vec![0xDEAD_BEEF]), false),
+            (Function::IsMaster(path.clone()), false),
+            (
+                Function::IsNewerThan(PathBuf::from("Cargo.toml"), path.clone()),
+                false,
+            ),
+            (
+                Function::Version(path.clone(), "1".into(), ComparisonOperator::LessThan),
+                true,
+            ),
+            (
+                Function::Version(path.clone(), "1".into(), ComparisonOperator::Equal),
+                false,
+            ),
+        ] {
+            let (result, source, _) = function.eval_with_source(&state).unwrap();
+
+            assert_eq!(expected_result, result, "{function}");
+            assert_eq!(ResultSource::MissingFileCache, source, "{function}");
+        }
+    }
+
+    #[test]
+    fn function_eval_should_not_use_the_missing_path_cache_if_the_state_has_data_for_the_path() {
+        let mut state = state(".");
+        state
+            .set_cached_crcs(&[("missing.esp", 0xDEAD_BEEF)])
+            .unwrap();
+        let path = PathBuf::from("missing.esp");
+
+        assert!(!Function::FilePath(path.clone()).eval(&state).unwrap());
+        let (result, source, _) = Function::Checksum(path, vec![0xDEAD_BEEF])
+            .eval_with_source(&state)
+            .unwrap();
+
+        assert!(result);
+        assert_eq!(ResultSource::CrcCache, source);
+    }
+
+    #[test]
+    fn invalidate_missing_path_should_make_functions_check_the_filesystem_again() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        create_dir_all(&data_path).unwrap();
+        let mut state = state(&data_path);
+
+        assert!(!Function::FilePath(PathBuf::from("a.txt"))
+            .eval(&state)
+            .unwrap());
+
+        File::create(data_path.join("a.txt")).unwrap();
+        assert!(!Function::Readable(PathBuf::from("a.txt"))
+            .eval(&state)
+            .unwrap());
+
+        state.invalidate_missing_path("A.txt");
+
+        assert!(Function::FilePath(PathBuf::from("a.txt"))
+            .eval(&state)
+            .unwrap());
+        assert!(Function::Readable(PathBuf::from("a.txt"))
+            .eval(&state)
+            .unwrap());
+    }
+
+    #[test]
+    fn file_functions_should_share_cached_metadata() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        std::fs::write(data_path.join("a.txt"), "abc").unwrap();
+        let state = state(data_path);
+
+        let function = Function::FilePath(PathBuf::from("a.txt"));
+        assert_eq!(
+            (true, ResultSource::FreshIo),
+            eval_source(&function, &state)
+        );
+
+        remove_file(data_path.join("a.txt")).unwrap();
+
+        let function = Function::FileSize(PathBuf::from("a.txt"), 3, ComparisonOperator::Equal);
+        assert_eq!(
+            (true, ResultSource::MetadataCache),
+            eval_source(&function, &state)
+        );
+    }
+
+    #[test]
+    fn invalidate_path_should_forget_cached_metadata_for_the_path() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        std::fs::write(data_path.join("a.txt"), "abc").unwrap();
+        std::fs::write(data_path.join("b.txt"), "abc").unwrap();
+        let mut state = state(data_path);
+
+        for path in ["a.txt", "b.txt"] {
+            assert!(Function::FilePath(PathBuf::from(path))
+                .eval(&state)
+                .unwrap());
+        }
+
+        remove_file(data_path.join("a.txt")).unwrap();
+        remove_file(data_path.join("b.txt")).unwrap();
+        state.invalidate_path("A.txt");
+
+        let eval = |path: &str| {
+            let function = Function::FileSize(PathBuf::from(path), 3, ComparisonOperator::Equal);
+            eval_source(&function, &state)
+        };
+        assert_eq!((false, ResultSource::FreshIo), eval("a.txt"));
+        assert_eq!((true, ResultSource::MetadataCache), eval("b.txt"));
+    }
+
+    #[test]
+    fn parse_plugin_should_parse_openmw_plugins() {
+        let mut state = state(Path::new("./tests/testing-plugins/Morrowind/Data Files"));
+        state.game_type = GameType::OpenMW;
+
+        let (plugin, _) = parse_plugin(&state, Path::new("Blank.esp"));
+
+        assert!(plugin.is_some());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn function_bind_variables_should_error_if_a_path_is_not_valid_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = PathBuf::from(OsStr::from_bytes(b"${PROFILE}/\xFF.esp"));
+        let function = Function::FilePath(path.clone());
+        let mut state = state(".");
+        state.set_variables(&[("PROFILE", "src")]);
+
+        assert!(matches!(
+            function.bind_variables(&state),
+            Err(Error::IoError(p, e)) if p == path && e.kind() == io::ErrorKind::InvalidData
+        ));
+    }
+
+    #[test]
+    fn function_file_path_eval_should_return_true_if_the_file_exists_relative_to_the_data_path() {
+        let function = Function::FilePath(PathBuf::from("Cargo.toml"));
+        let state = state(".");
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_path_eval_should_error_if_the_path_is_a_traversal_that_is_not_allowlisted() {
+        let function = Function::FilePath(PathBuf::from("../Cargo.toml"));
+        let mut state = state("src");
+        state.set_path_traversal_allowlist(Some(vec![PathBuf::from("../README.md")]));
+
+        assert!(matches!(
+            function.eval(&state),
+            Err(Error::PathTraversal(p)) if p == Path::new("../Cargo.toml")
+        ));
+    }
+
+    #[test]
+    fn function_file_path_eval_should_error_if_the_path_has_a_drive_letter_and_there_is_no_wine_path_mapper(
+    ) {
+        let function = Function::FilePath(PathBuf::from("C:\\Windows\\notepad.exe"));
+        let state = state(".");
+
+        assert!(matches!(
+            function.eval(&state),
+            Err(Error::UnmappedDrivePath(p)) if p == Path::new("C:\\Windows\\notepad.exe")
+        ));
+    }
+
+    #[test]
+    fn function_file_path_eval_should_allow_a_traversal_that_is_allowlisted() {
+        let function = Function::FilePath(PathBuf::from("../Cargo.toml"));
+        let mut state = state("src");
+        state.set_path_traversal_allowlist(Some(vec![PathBuf::from("../cargo.toml")]));
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_active_path_eval_should_not_check_the_path_traversal_allowlist() {
+        let function = Function::ActivePath(PathBuf::from("../Blank.esp"));
+        let mut state = state_with_active_plugins(".", &["../Blank.esp"]);
+        state.set_path_traversal_allowlist(Some(Vec::new()));
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_path_eval_should_return_true_if_given_a_plugin_that_is_ghosted() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        copy(
+            Path::new("tests/testing-plugins/Oblivion/Data/Blank.esp"),
+            state.data_path.join("Blank.esp.ghost"),
+        )
+        .unwrap();
+
+        let function = Function::FilePath(PathBuf::from("Blank.esp"));
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_path_eval_should_not_check_for_ghosted_non_plugin_file() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        copy(
+            Path::new("Cargo.toml"),
+            state.data_path.join("Cargo.toml.ghost"),
+        )
+        .unwrap();
+
+        let function = Function::FilePath(PathBuf::from("Cargo.toml"));
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_path_eval_should_return_false_if_the_file_does_not_exist() {
+        let function = Function::FilePath(PathBuf::from("missing"));
+        let state = state(".");
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_path_eval_should_return_true_for_a_virtual_file() {
+        let function = Function::FilePath(PathBuf::from("Meshes/Missing.nif"));
+        let mut state = state(".");
+        state.set_virtual_files(vec![PathBuf::from("meshes\\missing.nif")]);
+        state.set_filesystem_access(false);
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_size_eval_should_return_false_for_a_virtual_file() {
+        let function = Function::FileSize(
+            PathBuf::from("Cargo.toml"),
+            0,
+            ComparisonOperator::GreaterThanOrEqual,
+        );
+        let mut state = state(".");
+        state.set_virtual_files(vec![PathBuf::from("Cargo.toml")]);
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_regex_eval_should_be_true_if_a_virtual_file_matches() {
+        let function = Function::FileRegex(PathBuf::from("meshes"), regex("missing\\.nif"));
+        let mut state = state(".");
+        state.set_virtual_files(vec![PathBuf::from("Meshes/Missing.nif")]);
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_many_eval_should_count_virtual_files_that_are_not_on_disk() {
+        let function = Function::Many(PathBuf::from("."), regex("Cargo\\.t.*"));
+        let mut state = state(".");
+        state.set_virtual_files(vec![PathBuf::from("Cargo.toml")]);
+
+        assert!(!function.eval(&state).unwrap());
+
+        state.set_virtual_files(vec![
+            PathBuf::from("Cargo.toml"),
+            PathBuf::from("Cargo.txt"),
+        ]);
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn evaluate_dir_entries_should_read_each_directory_once() {
+        let file_system = Arc::new(DirectoryRecorder::default());
+        let mut state = state("tests");
+        state.file_system = Arc::<DirectoryRecorder>::clone(&file_system);
+
+        Function::FileRegex(PathBuf::from("."), regex("a"))
+            .eval(&state)
+            .unwrap();
+        Function::Many(PathBuf::from("."), regex("b"))
+            .eval(&state)
+            .unwrap();
+
+        assert_eq!(vec![PathBuf::from("tests/.")], file_system.directories());
+    }
+
+    #[test]
+    fn invalidate_directory_should_forget_cached_entries_for_the_directory() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        create_dir_all(data_path.join("meshes/sub")).unwrap();
+        let mut state = state(data_path);
+        let function = Function::RecursiveFileRegex(PathBuf::from("meshes"), regex("a\\.nif"), 1);
+
+        assert!(!function.eval(&state).unwrap());
+
+        File::create(data_path.join("meshes/sub/a.nif")).unwrap();
+        state.condition_cache.clear();
+
+        assert!(!function.eval(&state).unwrap());
+
+        state.invalidate_directory("Meshes");
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_eval_should_error_if_cancelled() {
+        let token = CancellationToken::new();
+        let mut state = state(".");
+        state.set_cancellation_token(Some(token.clone()));
+        let function = Function::FilePath(PathBuf::from("Cargo.toml"));
+
+        assert!(function.eval(&state).unwrap());
+
+        token.cancel();
+
+        assert!(matches!(function.eval(&state), Err(Error::Cancelled)));
+    }
+
+    #[test]
+    fn function_eval_should_error_if_the_deadline_has_passed() {
+        let mut state = state(".");
+        state.set_deadline(Some(Instant::now()));
+        let function = Function::FilePath(PathBuf::from("Cargo.toml"));
+
+        assert!(matches!(
+            function.eval(&state),
+            Err(Error::DeadlineExceeded)
+        ));
+
+        state.set_deadline(None);
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn evaluate_dir_entries_should_stop_reading_entries_once_cancelled() {
+        let token = CancellationToken::new();
+        let mut state = state(".");
+        state.set_cancellation_token(Some(token.clone()));
+
+        let mut entry_count = 0_u32;
+        let result = evaluate_dir_entries(&state, Path::new("."), |_| {
+            entry_count = entry_count.saturating_add(1);
+            token.cancel();
+            false
+        });
+
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert_eq!(1, entry_count);
+    }
+
+    #[test]
+    fn function_eval_should_treat_io_errors_as_missing_paths_if_not_strict() {
+        let state = failing_state(std::io::ErrorKind::Other);
+
+        assert!(!Function::FilePath(PathBuf::from("a")).eval(&state).unwrap());
+        assert!(!Function::FileSize(
+            PathBuf::from("a"),
+            0,
+            ComparisonOperator::GreaterThanOrEqual
+        )
+        .eval(&state)
+        .unwrap());
+        assert!(!Function::Readable(PathBuf::from("a")).eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_eval_should_error_on_unexpected_io_errors_if_strict() {
+        let mut state = failing_state(std::io::ErrorKind::Other);
+        state.set_strict(true);
+
+        for function in [
+            Function::FilePath(PathBuf::from("a")),
+            Function::FileSize(
+                PathBuf::from("a"),
+                0,
+                ComparisonOperator::GreaterThanOrEqual,
+            ),
+            Function::Readable(PathBuf::from("a")),
+            Function::Checksum(PathBuf::from("a"), vec![0xDEAD_BEEF]),
+            Function::Many(PathBuf::from("."), regex("a")),
+        ] {
+            match function.eval(&state) {
+                Err(Error::IoError(_, e)) => assert_eq!(std::io::ErrorKind::Other, e.kind()),
+                result => panic!("unexpected result for {function}: {result:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn function_eval_should_not_error_on_expected_io_errors_if_strict() {
+        let mut state = failing_state(std::io::ErrorKind::NotFound);
+        state.set_strict(true);
+
+        assert!(!Function::FilePath(PathBuf::from("a")).eval(&state).unwrap());
+        assert!(!Function::Many(PathBuf::from("."), regex("a"))
+            .eval(&state)
+            .unwrap());
+
+        let mut state = failing_state(std::io::ErrorKind::PermissionDenied);
+        state.set_strict(true);
+
+        assert!(!Function::Readable(PathBuf::from("a")).eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_regex_eval_should_be_false_if_no_directory_entries_match() {
+        let function = Function::FileRegex(PathBuf::from("."), regex("missing"));
+        let state = state(".");
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_regex_eval_should_be_false_if_the_parent_path_part_is_not_a_directory() {
+        let function = Function::FileRegex(PathBuf::from("missing"), regex("Cargo.*"));
+        let state = state(".");
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_regex_eval_should_be_true_if_a_directory_entry_matches() {
+        let function = Function::FileRegex(
+            PathBuf::from("tests/testing-plugins/Oblivion/Data"),
+            regex("Blank\\.esp"),
+        );
+        let state = state(".");
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_regex_eval_should_trim_ghost_plugin_extension_before_matching_against_regex() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        copy(
+            Path::new("tests/testing-plugins/Oblivion/Data/Blank.esm"),
+            state.data_path.join("Blank.esm.ghost"),
+        )
+        .unwrap();
+
+        let function = Function::FileRegex(PathBuf::from("."), regex("^Blank\\.esm$"));
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_regex_eval_should_check_all_configured_data_paths() {
+        let function = Function::FileRegex(PathBuf::from("Data"), regex("Blank\\.esp"));
+        let state = state_with_data("./src", vec!["./tests/testing-plugins/Oblivion"], &[], &[]);
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_glob_eval_should_match_filenames_in_the_directory_against_the_glob() {
+        let tmp_dir = tempdir().unwrap();
+        let state = state(tmp_dir.path());
+        create_dir_all(state.data_path.join("Data")).unwrap();
+        std::fs::write(state.data_path.join("Data/Blank - Different.esp"), "").unwrap();
+
+        let file_glob = |glob: &str, pattern: &str| {
+            Function::FileGlob(PathBuf::from("Data"), glob.into(), regex(pattern))
+        };
+
+        assert!(file_glob("blank*.es?", "^blank.*\\.es.$")
+            .eval(&state)
+            .unwrap());
+        assert!(!file_glob("Blank.es?", "^Blank\\.es.$")
+            .eval(&state)
+            .unwrap());
+    }
+
+    #[test]
+    fn function_recursive_file_regex_eval_should_search_subdirectories_down_to_the_depth() {
+        let tmp_dir = tempdir().unwrap();
+        let state = state(tmp_dir.path());
+        create_dir_all(state.data_path.join("meshes/a/b")).unwrap();
+        std::fs::write(state.data_path.join("meshes/a/b/test.nif"), "").unwrap();
+
+        let function = |depth| {
+            Function::RecursiveFileRegex(PathBuf::from("meshes"), regex("^test\\.nif$"), depth)
+        };
+
+        assert!(!function(0).eval(&state).unwrap());
+        assert!(!function(1).eval(&state).unwrap());
+        assert!(function(2).eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_recursive_many_eval_should_count_matches_in_all_searched_directories() {
+        let tmp_dir = tempdir().unwrap();
+        let state = state(tmp_dir.path());
+        create_dir_all(state.data_path.join("meshes/a")).unwrap();
+        std::fs::write(state.data_path.join("meshes/test.nif"), "").unwrap();
+        std::fs::write(state.data_path.join("meshes/a/test.nif"), "").unwrap();
+
+        let function =
+            |depth| Function::RecursiveMany(PathBuf::from("meshes"), regex("^test\\.nif$"), depth);
+
+        assert!(!function(0).eval(&state).unwrap());
+        assert!(function(1).eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_recursive_file_regex_eval_should_count_subdirectory_entries_towards_the_limit() {
+        let tmp_dir = tempdir().unwrap();
+        let mut state = state(tmp_dir.path());
+        create_dir_all(state.data_path.join("meshes/a")).unwrap();
+        std::fs::write(state.data_path.join("meshes/a/1.nif"), "").unwrap();
+        std::fs::write(state.data_path.join("meshes/a/2.nif"), "").unwrap();
+        state.set_max_directory_entries(Some(2));
+
+        let function = Function::RecursiveFileRegex(PathBuf::from("meshes"), regex("^3\\.nif$"), 1);
+
+        assert!(matches!(
+            function.eval(&state),
+            Err(Error::DirectoryEntryLimitExceeded(_, 2))
+        ));
+    }
+
+    #[test]
+    fn function_file_size_eval_should_return_false_if_file_does_not_exist() {
+        let function = Function::FileSize("missing.esp".into(), 55, ComparisonOperator::Equal);
+        let state = state_with_data(
+            "./src",
+            vec!["./tests/testing-plugins/Oblivion/Data"],
+            &[],
+            &[],
+        );
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_size_eval_should_return_false_if_file_size_is_different() {
+        let function = Function::FileSize("Blank.esp".into(), 10, ComparisonOperator::Equal);
+        let state = state_with_data(
+            "./src",
+            vec!["./tests/testing-plugins/Oblivion/Data"],
+            &[],
+            &[],
+        );
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_size_eval_should_return_true_if_file_size_is_equal() {
+        let function = Function::FileSize("Blank.esp".into(), 55, ComparisonOperator::Equal);
+        let state = state_with_data(
+            "./src",
+            vec!["./tests/testing-plugins/Oblivion/Data"],
+            &[],
+            &[],
+        );
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_size_eval_should_compare_the_file_size_using_the_given_comparator() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        create_dir_all(&state.data_path).unwrap();
+        std::fs::write(state.data_path.join("Blank.esp"), [0; 55]).unwrap();
+
+        for (size, comparator, expected) in [
+            (55, ComparisonOperator::NotEqual, false),
+            (10, ComparisonOperator::NotEqual, true),
+            (56, ComparisonOperator::LessThan, true),
+            (55, ComparisonOperator::LessThan, false),
+            (54, ComparisonOperator::GreaterThan, true),
+            (55, ComparisonOperator::GreaterThan, false),
+            (55, ComparisonOperator::LessThanOrEqual, true),
+            (54, ComparisonOperator::LessThanOrEqual, false),
+            (55, ComparisonOperator::GreaterThanOrEqual, true),
+            (56, ComparisonOperator::GreaterThanOrEqual, false),
+        ] {
+            let function = Function::FileSize("Blank.esp".into(), size, comparator);
+
+            assert_eq!(expected, function.eval(&state).unwrap(), "{function}");
+        }
+    }
+
+    #[test]
+    fn function_file_size_eval_should_return_false_for_any_comparator_if_file_does_not_exist() {
+        let function = Function::FileSize("missing.esp".into(), 55, ComparisonOperator::NotEqual);
+        let state = state_with_data(
+            "./src",
+            vec!["./tests/testing-plugins/Oblivion/Data"],
+            &[],
+            &[],
+        );
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn function_file_content_contains_eval_should_match_the_regex_against_the_file_contents() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        create_dir_all(state.data_path.join("SKSE/Plugins")).unwrap();
+        std::fs::write(
+            state.data_path.join("SKSE/Plugins/foo.ini"),
+            "[General]\r\nbPatch=1\r\nbOther=0\r\n",
+        )
+        .unwrap();
+
+        for (pattern, expected) in [
+            ("bpatch=1", true),
+            ("(?m)^bOther=0\r?$", true),
+            ("bPatch=0", false),
+            ("^bPatch=1", false),
+        ] {
+            let function =
+                Function::FileContentContains("SKSE/Plugins/foo.ini".into(), regex(pattern));
+
+            assert_eq!(expected, function.eval(&state).unwrap(), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn function_file_content_contains_eval_should_read_invalid_utf8_lossily() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        create_dir_all(&state.data_path).unwrap();
+        std::fs::write(
+            state.data_path.join("foo.ini"),
+            b"sName=Caf\xe9\nbPatch=1\n",
+        )
+        .unwrap();
+
+        let function = Function::FileContentContains("foo.ini".into(), regex("bPatch=1"));
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_content_contains_eval_should_return_false_if_the_file_is_too_large() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let mut state = state(data_path);
+
+        create_dir_all(&state.data_path).unwrap();
+        let mut contents = b"bPatch=1\n".to_vec();
+        contents.resize(usize::try_from(MAX_FILE_CONTENT_SIZE).unwrap() + 1, b' ');
+        std::fs::write(state.data_path.join("foo.ini"), &contents).unwrap();
+
+        let function = Function::FileContentContains("foo.ini".into(), regex("bPatch=1"));
+
+        assert!(!function.eval(&state).unwrap());
+
+        contents.pop();
+        std::fs::write(state.data_path.join("foo.ini"), &contents).unwrap();
+        state.clear_condition_cache().unwrap();
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_content_contains_eval_should_return_false_if_the_file_does_not_exist() {
+        let function = Function::FileContentContains("missing.ini".into(), regex(".*"));
+        let state = state(".");
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_ini_value_eval_should_compare_the_value_of_the_key_in_the_section() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        create_dir_all(&state.data_path).unwrap();
+        std::fs::write(
+            state.data_path.join("MyMod.ini"),
+            "[General]\r\nbEnabled=1\r\nsMode = Fast\r\n[Other]\r\nbPatch=1\r\n",
+        )
+        .unwrap();
+
+        for (section, key, value, expected) in [
+            ("General", "bEnabled", "1", true),
+            ("general", "BENABLED", "1", true),
+            ("General", "sMode", "fast", true),
+            ("General", "bEnabled", "0", false),
+            ("General", "bPatch", "1", false),
+            ("Missing", "bEnabled", "1", false),
+        ] {
+            let function =
+                Function::IniValue("MyMod.ini".into(), section.into(), key.into(), value.into());
+
+            assert_eq!(expected, function.eval(&state).unwrap(), "{function}");
+        }
+    }
+
+    #[cfg(not(all(windows, feature = "windows-registry")))]
+    #[test]
+    fn function_registry_value_eval_should_be_false_if_the_registry_cannot_be_read() {
+        let function = Function::RegistryValue(
+            "HKLM\\SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion".into(),
+            "ProductName".into(),
+            regex(".*"),
+        );
+
+        assert!(!function.eval(&state(".")).unwrap());
+    }
+
+    #[test]
+    fn function_is_platform_eval_should_be_true_only_for_the_current_platform() {
+        let mut state = state(".");
+        state.set_filesystem_access(false);
+
+        for (platform, expected) in [
+            (Platform::Windows, cfg!(windows)),
+            (Platform::Linux, cfg!(target_os = "linux")),
+            (Platform::MacOs, cfg!(target_os = "macos")),
+        ] {
+            assert_eq!(
+                (expected, ResultSource::Constant),
+                eval_source(&Function::IsPlatform(platform), &state)
+            );
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn function_is_wine_eval_should_be_false() {
+        let mut state = state(".");
+        state.set_filesystem_access(false);
+
+        assert_eq!(
+            (false, ResultSource::Constant),
+            eval_source(&Function::IsWine, &state)
+        );
+    }
+
+    #[test]
+    fn function_ini_value_eval_should_return_false_if_the_file_does_not_exist() {
+        let function = Function::IniValue(
+            "missing.ini".into(),
+            "General".into(),
+            "bEnabled".into(),
+            "1".into(),
+        );
+        let state = state(".");
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_size_eval_should_return_true_if_given_a_plugin_that_is_ghosted() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        copy(
+            Path::new("tests/testing-plugins/Oblivion/Data/Blank.esp"),
+            state.data_path.join("Blank.esp.ghost"),
+        )
+        .unwrap();
+
+        let function =
+            Function::FileSize(PathBuf::from("Blank.esp"), 55, ComparisonOperator::Equal);
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_file_size_eval_should_not_check_for_ghosted_non_plugin_file() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        copy(
+            Path::new("tests/testing-plugins/Oblivion/Data/Blank.bsa"),
+            state.data_path.join("Blank.bsa.ghost"),
+        )
+        .unwrap();
+
+        let function =
+            Function::FileSize(PathBuf::from("Blank.bsa"), 736, ComparisonOperator::Equal);
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_readable_eval_should_be_true_for_a_file_that_can_be_opened_as_read_only() {
+        let function = Function::Readable(PathBuf::from("Cargo.toml"));
+        let state = state(".");
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_readable_eval_should_be_true_for_a_folder_that_can_be_read() {
+        let function = Function::Readable(PathBuf::from("tests"));
+        let state = state(".");
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_readable_eval_should_be_false_for_a_file_that_does_not_exist() {
+        let function = Function::Readable(PathBuf::from("missing"));
+        let state = state(".");
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn function_readable_eval_should_be_false_for_a_file_that_is_not_readable() {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        let relative_path = "unreadable";
+        let file_path = state.data_path.join(relative_path);
+
+        // Create a file and open it with exclusive access so that the readable
+        // function eval isn't able to open the file in read-only mode.
+        let _file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .share_mode(0)
+            .open(&file_path);
+
+        assert!(file_path.exists());
+
+        let function = Function::Readable(PathBuf::from(relative_path));
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn function_readable_eval_should_be_false_for_a_file_that_is_not_readable() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        let relative_path = "unreadable";
+        let file_path = state.data_path.join(relative_path);
+
+        std::fs::write(&file_path, "").unwrap();
+        make_path_unreadable(&file_path);
+
+        assert!(file_path.exists());
+
+        let function = Function::Readable(PathBuf::from(relative_path));
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn function_readable_eval_should_be_false_for_a_folder_that_is_not_readable() {
+        let data_path = Path::new(r"C:\Program Files");
+        let state = state(data_path);
+
+        let relative_path = "WindowsApps";
+
+        // The WindowsApps directory is so locked down that trying to read its
+        // metadata fails, but its existence can still be observed by iterating
+        // over its parent directory's entries.
+        let entry_exists = state
+            .data_path
+            .read_dir()
+            .unwrap()
+            .flat_map(|res| res.map(|e| e.file_name()).into_iter())
+            .any(|name| name == relative_path);
+
+        assert!(entry_exists);
+
+        let function = Function::Readable(PathBuf::from(relative_path));
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn function_readable_eval_should_be_false_for_a_folder_that_is_not_readable() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        let relative_path = "unreadable";
+        let folder_path = state.data_path.join(relative_path);
+
+        create_dir_all(&folder_path).unwrap();
+        make_path_unreadable(&folder_path);
+
+        assert!(folder_path.exists());
+
+        let function = Function::Readable(PathBuf::from(relative_path));
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_writable_eval_should_be_true_for_a_file_that_can_be_opened_for_writing() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        std::fs::write(data_path.join("Blank.esp"), "content").unwrap();
+        let state = state(data_path);
+
+        assert!(Function::Writable(PathBuf::from("Blank.esp"))
+            .eval(&state)
+            .unwrap());
+        assert_eq!(
+            "content",
+            std::fs::read_to_string(data_path.join("Blank.esp")).unwrap()
+        );
+    }
+
+    #[test]
+    fn function_writable_eval_should_be_true_for_a_folder_that_files_can_be_created_in() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        create_dir_all(data_path.join("subdir")).unwrap();
+        let state = state(data_path);
+
+        assert!(Function::Writable(PathBuf::from("subdir"))
+            .eval(&state)
+            .unwrap());
+        assert_eq!(0, read_dir(data_path.join("subdir")).unwrap().count());
+    }
+
+    #[test]
+    fn function_writable_eval_should_be_false_for_a_path_that_does_not_exist() {
+        let tmp_dir = tempdir().unwrap();
+        let state = state(tmp_dir.path());
+
+        assert!(!Function::Writable(PathBuf::from("missing"))
+            .eval(&state)
+            .unwrap());
+        assert!(!tmp_dir.path().join("missing").exists());
+    }
+
+    #[test]
+    fn function_is_executable_should_be_false_for_a_path_that_does_not_exist() {
+        let state = state(".");
+        let function = Function::IsExecutable("missing".into());
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_is_executable_should_be_false_for_a_directory() {
+        let state = state(".");
+        let function = Function::IsExecutable("tests".into());
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn function_is_executable_should_be_false_for_a_file_that_cannot_be_read() {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        let relative_path = "unreadable";
+        let file_path = state.data_path.join(relative_path);
+
+        // Create a file and open it with exclusive access so that the readable
+        // function eval isn't able to open the file in read-only mode.
+        let _file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .share_mode(0)
+            .open(&file_path);
+
+        assert!(file_path.exists());
+
+        let function = Function::IsExecutable(PathBuf::from(relative_path));
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn function_is_executable_should_be_false_for_a_file_that_cannot_be_read() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
+
+        let relative_path = "unreadable";
+        let file_path = state.data_path.join(relative_path);
+
+        std::fs::write(&file_path, "").unwrap();
+        make_path_unreadable(&file_path);
+
+        assert!(file_path.exists());
+
+        let function = Function::IsExecutable(PathBuf::from(relative_path));
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_is_executable_should_be_false_for_a_file_that_is_not_an_executable() {
+        let state = state(".");
+        let function = Function::IsExecutable("Cargo.toml".into());
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_is_executable_should_be_true_for_a_file_that_is_an_executable() {
+        let state = state(".");
+        let function = Function::IsExecutable("tests/libloot_win32/loot.dll".into());
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_is_architecture_should_be_true_if_the_executable_is_built_for_the_architecture() {
+        let state = state(".");
+        let eval = |path: &str, architecture| {
+            Function::IsArchitecture(path.into(), architecture)
+                .eval(&state)
+                .unwrap()
+        };
+
+        assert!(eval("tests/libloot_win32/loot.dll", Architecture::X86));
+        assert!(!eval("tests/libloot_win32/loot.dll", Architecture::X64));
+        assert!(eval("tests/libloot_win64/loot.dll", Architecture::X64));
+        assert!(!eval("tests/libloot_win64/loot.dll", Architecture::X86));
+    }
+
+    #[test]
+    fn function_is_architecture_should_be_false_for_a_file_that_is_missing_or_not_an_executable() {
+        let state = state(".");
+
+        for path in ["missing.dll", "Cargo.toml"] {
+            for architecture in [Architecture::X86, Architecture::X64] {
+                let function = Function::IsArchitecture(path.into(), architecture);
+
+                assert!(!function.eval(&state).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn function_active_path_eval_should_be_true_if_the_path_is_an_active_plugin() {
+        let function = Function::ActivePath(PathBuf::from("Blank.esp"));
+        let state = state_with_active_plugins(".", &["Blank.esp"]);
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_active_path_eval_should_be_case_insensitive() {
+        let function = Function::ActivePath(PathBuf::from("Blank.esp"));
+        let state = state_with_active_plugins(".", &["blank.esp"]);
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_active_path_eval_should_use_unicode_case_folding() {
+        let state =
+            state_with_active_plugins(".", &["Stra\u{df}e.esp", "\u{3c3}\u{3b1}\u{3c3}.esp"]);
+
+        for name in ["STRASSE.esp", "\u{3a3}\u{391}\u{3a3}.esp"] {
+            let function = Function::ActivePath(PathBuf::from(name));
+            assert!(function.eval(&state).unwrap(), "{name}");
+        }
+    }
+
+    #[test]
+    fn function_active_path_eval_should_be_false_if_the_path_is_not_an_active_plugin() {
+        let function = Function::ActivePath(PathBuf::from("inactive.esp"));
+        let state = state_with_active_plugins(".", &["Blank.esp"]);
 
-    use std::fs::{copy, create_dir_all, remove_file};
-    use std::sync::RwLock;
+        assert!(!function.eval(&state).unwrap());
+    }
 
-    use regex::RegexBuilder;
-    use tempfile::tempdir;
+    #[test]
+    fn function_active_regex_eval_should_be_true_if_the_regex_matches_an_active_plugin() {
+        let function = Function::ActiveRegex(regex("Blank\\.esp"));
+        let state = state_with_active_plugins(".", &["Blank.esp"]);
 
-    fn state<T: Into<PathBuf>>(data_path: T) -> State {
-        state_with_active_plugins(data_path, &[])
+        assert!(function.eval(&state).unwrap());
     }
 
-    fn state_with_active_plugins<T: Into<PathBuf>>(data_path: T, active_plugins: &[&str]) -> State {
-        state_with_data(data_path, Vec::default(), active_plugins, &[])
-    }
+    #[test]
+    fn function_active_regex_eval_should_match_active_plugins_that_differ_only_in_case() {
+        let function = Function::ActiveRegex(regex("^strasse\\.esp$"));
+        let state = state_with_active_plugins(".", &["Stra\u{df}e.esp"]);
 
-    fn state_with_versions<T: Into<PathBuf>>(
-        data_path: T,
-        plugin_versions: &[(&str, &str)],
-    ) -> State {
-        state_with_data(data_path, Vec::default(), &[], plugin_versions)
+        assert!(function.eval(&state).unwrap());
     }
 
-    fn state_with_data<T: Into<PathBuf>>(
-        data_path: T,
-        additional_data_paths: Vec<T>,
-        active_plugins: &[&str],
-        plugin_versions: &[(&str, &str)],
-    ) -> State {
-        let data_path = data_path.into();
-        if !data_path.exists() {
-            create_dir_all(&data_path).unwrap();
-        }
-
-        let additional_data_paths = additional_data_paths
-            .into_iter()
-            .map(|data_path| {
-                let data_path: PathBuf = data_path.into();
-                if !data_path.exists() {
-                    create_dir_all(&data_path).unwrap();
-                }
-                data_path
-            })
-            .collect();
+    #[test]
+    fn function_active_regex_eval_should_be_false_if_the_regex_does_not_match_an_active_plugin() {
+        let function = Function::ActiveRegex(regex("inactive\\.esp"));
+        let state = state_with_active_plugins(".", &["Blank.esp"]);
 
-        State {
-            game_type: GameType::Oblivion,
-            data_path,
-            additional_data_paths,
-            active_plugins: active_plugins.iter().map(|s| s.to_lowercase()).collect(),
-            crc_cache: RwLock::default(),
-            plugin_versions: plugin_versions
-                .iter()
-                .map(|(p, v)| (p.to_lowercase(), (*v).to_owned()))
-                .collect(),
-            condition_cache: RwLock::default(),
-        }
+        assert!(!function.eval(&state).unwrap());
     }
 
-    fn regex(string: &str) -> Regex {
-        RegexBuilder::new(string)
-            .case_insensitive(true)
-            .build()
-            .unwrap()
+    #[test]
+    fn function_is_master_eval_should_be_true_if_the_path_is_a_master_plugin() {
+        let function = Function::IsMaster(PathBuf::from("Blank.esm"));
+        let state = state("tests/testing-plugins/Oblivion/Data");
+
+        assert!(function.eval(&state).unwrap());
     }
 
-    #[cfg(not(windows))]
-    fn make_path_unreadable(path: &Path) {
-        use std::os::unix::fs::PermissionsExt;
+    #[test]
+    fn function_is_master_eval_should_be_false_if_the_path_is_an_openmw_master_flagged_plugin() {
+        let function = Function::IsMaster(PathBuf::from("Blank.esm"));
+        let mut state = state("tests/testing-plugins/Morrowind/Data Files");
+        state.game_type = GameType::OpenMW;
 
-        let mut permissions = std::fs::metadata(path).unwrap().permissions();
-        permissions.set_mode(0o200);
-        std::fs::set_permissions(path, permissions).unwrap();
+        assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn evaluate_dir_entries_should_check_additional_paths_in_order_then_data_path() {
-        let state = state_with_data(
-            "./tests/testing-plugins/SkyrimSE",
-            vec![
-                "./tests/testing-plugins/Oblivion",
-                "./tests/testing-plugins/Skyrim",
-            ],
-            &[],
-            &[],
-        );
+    fn function_is_master_eval_should_be_false_if_the_path_does_not_exist() {
+        let function = Function::IsMaster(PathBuf::from("missing.esp"));
+        let state = state("tests/testing-plugins/Oblivion/Data");
 
-        let mut paths = Vec::new();
-        let evaluator = |entry: DirEntry| {
-            if entry.file_name() == "Blank.esp" {
-                paths.push(
-                    entry
-                        .path()
-                        .parent()
-                        .unwrap()
-                        .parent()
-                        .unwrap()
-                        .to_path_buf(),
-                );
-            }
-            false
-        };
-        let result = evaluate_dir_entries(&state, Path::new("Data"), evaluator).unwrap();
+        assert!(!function.eval(&state).unwrap());
+    }
 
-        assert!(!result);
-        assert_eq!(
-            vec![
-                state.additional_data_paths[0].clone(),
-                state.additional_data_paths[1].clone(),
-                state.data_path,
-            ],
-            paths
-        );
+    #[test]
+    fn function_is_master_eval_should_be_false_if_the_path_is_not_a_plugin() {
+        let function = Function::IsMaster(PathBuf::from("Cargo.toml"));
+        let state = state(".");
+
+        assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn evaluate_dir_entries_should_check_additional_paths_in_reverse_order_then_data_path_for_openmw(
-    ) {
-        let mut state = state_with_data(
-            "./tests/testing-plugins/SkyrimSE",
-            vec![
-                "./tests/testing-plugins/Oblivion",
-                "./tests/testing-plugins/Skyrim",
-            ],
-            &[],
-            &[],
-        );
-        state.game_type = GameType::OpenMW;
+    fn function_is_master_eval_should_be_false_if_the_path_is_a_non_master_plugin() {
+        let function = Function::IsMaster(PathBuf::from("Blank.esp"));
+        let state = state("tests/testing-plugins/Oblivion/Data");
 
-        let mut paths = Vec::new();
-        let evaluator = |entry: DirEntry| {
-            if entry.file_name() == "Blank.esp" {
-                paths.push(
-                    entry
-                        .path()
-                        .parent()
-                        .unwrap()
-                        .parent()
-                        .unwrap()
-                        .to_path_buf(),
-                );
-            }
-            false
-        };
-        let result = evaluate_dir_entries(&state, Path::new("Data"), evaluator).unwrap();
+        assert!(!function.eval(&state).unwrap());
+    }
 
-        assert!(!result);
-        assert_eq!(
-            vec![
-                state.additional_data_paths[1].clone(),
-                state.additional_data_paths[0].clone(),
-                state.data_path,
-            ],
-            paths
-        );
+    #[test]
+    fn function_many_eval_should_be_false_if_no_directory_entries_match() {
+        let function = Function::Many(PathBuf::from("."), regex("missing"));
+        let state = state(".");
+
+        assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn parse_plugin_should_parse_openmw_plugins() {
-        let mut state = state(Path::new("./tests/testing-plugins/Morrowind/Data Files"));
-        state.game_type = GameType::OpenMW;
+    fn function_many_eval_should_be_false_if_the_parent_path_part_is_not_a_directory() {
+        let function = Function::Many(PathBuf::from("missing"), regex("Cargo.*"));
+        let state = state(".");
 
-        let plugin = parse_plugin(&state, Path::new("Blank.esp"));
+        assert!(!function.eval(&state).unwrap());
+    }
 
-        assert!(plugin.is_some());
+    #[test]
+    fn function_many_eval_should_be_false_if_one_directory_entry_matches() {
+        let function = Function::Many(
+            PathBuf::from("tests/testing-plugins/Oblivion/Data"),
+            regex("Blank\\.esp"),
+        );
+        let state = state(".");
+
+        assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_file_path_eval_should_return_true_if_the_file_exists_relative_to_the_data_path() {
-        let function = Function::FilePath(PathBuf::from("Cargo.toml"));
+    fn function_many_eval_should_be_true_if_more_than_one_directory_entry_matches() {
+        let function = Function::Many(
+            PathBuf::from("tests/testing-plugins/Oblivion/Data"),
+            regex("Blank.*"),
+        );
         let state = state(".");
 
         assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_file_path_eval_should_return_true_if_given_a_plugin_that_is_ghosted() {
+    fn function_many_eval_should_trim_ghost_plugin_extension_before_matching_against_regex() {
         let tmp_dir = tempdir().unwrap();
         let data_path = tmp_dir.path().join("Data");
         let state = state(data_path);
 
+        copy(
+            Path::new("tests/testing-plugins/Oblivion/Data/Blank.esm"),
+            state.data_path.join("Blank.esm.ghost"),
+        )
+        .unwrap();
         copy(
             Path::new("tests/testing-plugins/Oblivion/Data/Blank.esp"),
             state.data_path.join("Blank.esp.ghost"),
         )
         .unwrap();
 
-        let function = Function::FilePath(PathBuf::from("Blank.esp"));
+        let function = Function::Many(PathBuf::from("."), regex("^Blank\\.es(m|p)$"));
 
         assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_file_path_eval_should_not_check_for_ghosted_non_plugin_file() {
+    fn function_many_eval_should_check_across_all_configured_data_paths() {
+        let function = Function::Many(PathBuf::from("Data"), regex("Blank\\.esp"));
+        let state = state_with_data(
+            "./tests/testing-plugins/Skyrim",
+            vec!["./tests/testing-plugins/Oblivion"],
+            &[],
+            &[],
+        );
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_count_eval_should_compare_the_number_of_matching_directory_entries() {
         let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
+        let data_path = tmp_dir.path();
+        for name in [
+            "A Patch.esp",
+            "B Patch.esp.ghost",
+            "C Patch.esp",
+            "Other.esp",
+        ] {
+            File::create(data_path.join(name)).unwrap();
+        }
         let state = state(data_path);
 
-        copy(
-            Path::new("Cargo.toml"),
-            state.data_path.join("Cargo.toml.ghost"),
+        let eval = |comparator: ComparisonOperator, count: u64| {
+            Function::Count(
+                PathBuf::from("."),
+                regex(".* Patch\\.esp"),
+                comparator,
+                count,
+            )
+            .eval(&state)
+            .unwrap()
+        };
+
+        assert!(eval(ComparisonOperator::Equal, 3));
+        assert!(eval(ComparisonOperator::GreaterThanOrEqual, 3));
+        assert!(eval(ComparisonOperator::GreaterThan, 2));
+        assert!(eval(ComparisonOperator::LessThan, 4));
+        assert!(!eval(ComparisonOperator::GreaterThan, 3));
+        assert!(!eval(ComparisonOperator::LessThanOrEqual, 2));
+        assert!(!eval(ComparisonOperator::NotEqual, 3));
+    }
+
+    #[test]
+    fn function_count_eval_should_count_zero_entries_if_the_directory_does_not_exist() {
+        let state = state(".");
+
+        assert!(Function::Count(
+            PathBuf::from("missing"),
+            regex(".*"),
+            ComparisonOperator::Equal,
+            0
         )
-        .unwrap();
+        .eval(&state)
+        .unwrap());
+    }
 
-        let function = Function::FilePath(PathBuf::from("Cargo.toml"));
+    #[test]
+    fn function_many_active_eval_should_be_true_if_the_regex_matches_more_than_one_active_plugin() {
+        let function = Function::ManyActive(regex("Blank.*"));
+        let state = state_with_active_plugins(".", &["Blank.esp", "Blank.esm"]);
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_many_active_eval_should_match_active_plugins_that_differ_only_in_case() {
+        let function = Function::ManyActive(regex("^strasse\\.es[mp]$"));
+        let state = state_with_active_plugins(".", &["Stra\u{df}e.esp", "STRASSE.esm"]);
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_many_active_eval_should_be_false_if_one_active_plugin_matches() {
+        let function = Function::ManyActive(regex("Blank\\.esp"));
+        let state = state_with_active_plugins(".", &["Blank.esp", "Blank.esm"]);
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_file_path_eval_should_return_false_if_the_file_does_not_exist() {
-        let function = Function::FilePath(PathBuf::from("missing"));
-        let state = state(".");
+    fn function_active_count_eval_should_compare_the_number_of_matching_active_plugins() {
+        let state = state_with_active_plugins(".", &["Blank.esp", "Blank.esm", "Other.esp"]);
+
+        let eval = |comparator: ComparisonOperator, count: u64| {
+            Function::ActiveCount(regex("Blank.*"), comparator, count)
+                .eval(&state)
+                .unwrap()
+        };
+
+        assert!(eval(ComparisonOperator::Equal, 2));
+        assert!(eval(ComparisonOperator::GreaterThan, 1));
+        assert!(eval(ComparisonOperator::LessThanOrEqual, 2));
+        assert!(!eval(ComparisonOperator::GreaterThan, 2));
+        assert!(!eval(ComparisonOperator::LessThan, 2));
+    }
+
+    #[test]
+    fn function_many_active_eval_should_be_false_if_the_regex_does_not_match_an_active_plugin() {
+        let function = Function::ManyActive(regex("inactive\\.esp"));
+        let state = state_with_active_plugins(".", &["Blank.esp", "Blank.esm"]);
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_file_regex_eval_should_be_false_if_no_directory_entries_match() {
-        let function = Function::FileRegex(PathBuf::from("."), regex("missing"));
+    fn function_checksum_eval_should_be_false_if_the_file_does_not_exist() {
+        let function = Function::Checksum(PathBuf::from("missing"), vec![0x374E_2A6F]);
         let state = state(".");
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_file_regex_eval_should_be_false_if_the_parent_path_part_is_not_a_directory() {
-        let function = Function::FileRegex(PathBuf::from("missing"), regex("Cargo.*"));
+    fn function_checksum_eval_should_be_false_if_the_file_checksum_does_not_equal_the_given_checksum(
+    ) {
+        let function = Function::Checksum(
+            PathBuf::from("tests/testing-plugins/Oblivion/Data/Blank.esm"),
+            vec![0xDEAD_BEEF],
+        );
         let state = state(".");
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_file_regex_eval_should_be_true_if_a_directory_entry_matches() {
-        let function = Function::FileRegex(
-            PathBuf::from("tests/testing-plugins/Oblivion/Data"),
-            regex("Blank\\.esp"),
+    fn function_checksum_eval_should_be_true_if_the_file_checksum_equals_the_given_checksum() {
+        let function = Function::Checksum(
+            PathBuf::from("tests/testing-plugins/Oblivion/Data/Blank.esm"),
+            vec![0x374E_2A6F],
         );
         let state = state(".");
 
@@ -617,7 +3539,7 @@ mod tests {
     }
 
     #[test]
-    fn function_file_regex_eval_should_trim_ghost_plugin_extension_before_matching_against_regex() {
+    fn function_checksum_eval_should_support_checking_the_crc_of_a_ghosted_plugin() {
         let tmp_dir = tempdir().unwrap();
         let data_path = tmp_dir.path().join("Data");
         let state = state(data_path);
@@ -628,1136 +3550,1883 @@ mod tests {
         )
         .unwrap();
 
-        let function = Function::FileRegex(PathBuf::from("."), regex("^Blank\\.esm$"));
+        let function = Function::Checksum(PathBuf::from("Blank.esm"), vec![0x374E_2A6F]);
 
         assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_file_regex_eval_should_check_all_configured_data_paths() {
-        let function = Function::FileRegex(PathBuf::from("Data"), regex("Blank\\.esp"));
-        let state = state_with_data("./src", vec!["./tests/testing-plugins/Oblivion"], &[], &[]);
+    fn function_checksum_eval_should_not_check_for_ghosted_non_plugin_file() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
 
-        assert!(function.eval(&state).unwrap());
-    }
+        copy(
+            Path::new("tests/testing-plugins/Oblivion/Data/Blank.bsa"),
+            state.data_path.join("Blank.bsa.ghost"),
+        )
+        .unwrap();
 
-    #[test]
-    fn function_file_size_eval_should_return_false_if_file_does_not_exist() {
-        let function = Function::FileSize("missing.esp".into(), 55);
-        let state = state_with_data(
-            "./src",
-            vec!["./tests/testing-plugins/Oblivion/Data"],
-            &[],
-            &[],
-        );
+        let function = Function::Checksum(PathBuf::from("Blank.bsa"), vec![0x22AB_79D9]);
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_file_size_eval_should_return_false_if_file_size_is_different() {
-        let function = Function::FileSize("Blank.esp".into(), 10);
-        let state = state_with_data(
-            "./src",
-            vec!["./tests/testing-plugins/Oblivion/Data"],
-            &[],
-            &[],
-        );
+    fn function_checksum_eval_should_be_false_if_given_a_directory_path() {
+        // The given CRC is the CRC-32 of the directory as calculated by 7-zip.
+        let function =
+            Function::Checksum(PathBuf::from("tests/testing-plugins"), vec![0xC9CD_16C3]);
+        let state = state(".");
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_file_size_eval_should_return_true_if_file_size_is_equal() {
-        let function = Function::FileSize("Blank.esp".into(), 55);
-        let state = state_with_data(
-            "./src",
-            vec!["./tests/testing-plugins/Oblivion/Data"],
-            &[],
-            &[],
+    fn function_checksum_eval_should_be_true_if_the_file_checksum_equals_any_given_checksum() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        std::fs::write(
+            data_path.join("fox.txt"),
+            "The quick brown fox jumps over the lazy dog",
+        )
+        .unwrap();
+        let state = state(data_path);
+
+        let function = Function::Checksum("fox.txt".into(), vec![0xDEAD_BEEF, 0x414F_A339]);
+        assert_eq!(
+            (true, ResultSource::FreshIo),
+            eval_source(&function, &state)
         );
 
-        assert!(function.eval(&state).unwrap());
+        let function = Function::Checksum("fox.txt".into(), vec![0xDEAD_BEEF, 0x1122_3344]);
+        assert_eq!(
+            (false, ResultSource::CrcCache),
+            eval_source(&function, &state)
+        );
     }
 
     #[test]
-    fn function_file_size_eval_should_return_true_if_given_a_plugin_that_is_ghosted() {
+    fn function_checksum_eval_should_cache_and_use_cached_crcs() {
         let tmp_dir = tempdir().unwrap();
         let data_path = tmp_dir.path().join("Data");
         let state = state(data_path);
 
         copy(
-            Path::new("tests/testing-plugins/Oblivion/Data/Blank.esp"),
-            state.data_path.join("Blank.esp.ghost"),
+            Path::new("tests/testing-plugins/Oblivion/Data/Blank.esm"),
+            state.data_path.join("Blank.esm"),
         )
         .unwrap();
 
-        let function = Function::FileSize(PathBuf::from("Blank.esp"), 55);
+        let function = Function::Checksum(PathBuf::from("Blank.esm"), vec![0x374E_2A6F]);
 
         assert!(function.eval(&state).unwrap());
-    }
-
-    #[test]
-    fn function_file_size_eval_should_not_check_for_ghosted_non_plugin_file() {
-        let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
-        let state = state(data_path);
 
+        // Change the CRC of the file to test that the cached value is used.
         copy(
             Path::new("tests/testing-plugins/Oblivion/Data/Blank.bsa"),
-            state.data_path.join("Blank.bsa.ghost"),
+            state.data_path.join("Blank.esm"),
         )
         .unwrap();
 
-        let function = Function::FileSize(PathBuf::from("Blank.bsa"), 736);
+        let function = Function::Checksum(PathBuf::from("Blank.esm"), vec![0x374E_2A6F]);
 
-        assert!(!function.eval(&state).unwrap());
+        assert!(function.eval(&state).unwrap());
     }
 
-    #[test]
-    fn function_readable_eval_should_be_true_for_a_file_that_can_be_opened_as_read_only() {
-        let function = Function::Readable(PathBuf::from("Cargo.toml"));
-        let state = state(".");
+    /// Memory-maps files but can't open them for reading.
+    #[derive(Debug)]
+    struct MemoryMapOnly;
 
-        assert!(function.eval(&state).unwrap());
-    }
+    impl FileSystem for MemoryMapOnly {
+        fn metadata(&self, path: &Path) -> std::io::Result<crate::Metadata> {
+            crate::StdFileSystem.metadata(path)
+        }
 
-    #[test]
-    fn function_readable_eval_should_be_true_for_a_folder_that_can_be_read() {
-        let function = Function::Readable(PathBuf::from("tests"));
-        let state = state(".");
+        fn read_dir(
+            &self,
+            path: &Path,
+        ) -> std::io::Result<Box<dyn Iterator<Item = std::io::Result<DirEntry>> + '_>> {
+            crate::StdFileSystem.read_dir(path)
+        }
 
-        assert!(function.eval(&state).unwrap());
+        fn open(&self, _path: &Path) -> std::io::Result<Box<dyn crate::ReadSeek>> {
+            Err(std::io::ErrorKind::PermissionDenied.into())
+        }
+
+        fn memory_map(&self, path: &Path) -> std::io::Result<Option<Box<dyn AsRef<[u8]>>>> {
+            crate::StdFileSystem.memory_map(path)
+        }
     }
 
     #[test]
-    fn function_readable_eval_should_be_false_for_a_file_that_does_not_exist() {
-        let function = Function::Readable(PathBuf::from("missing"));
-        let state = state(".");
+    #[cfg(any(windows, unix))]
+    fn function_checksum_eval_should_memory_map_files_larger_than_the_threshold() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        std::fs::write(
+            data_path.join("fox.txt"),
+            "The quick brown fox jumps over the lazy dog",
+        )
+        .unwrap();
 
-        assert!(!function.eval(&state).unwrap());
+        let eval = |threshold| {
+            let mut state = state(data_path);
+            state.set_file_system(Arc::new(MemoryMapOnly));
+            state.set_memory_map_threshold(threshold);
+
+            Function::Checksum(PathBuf::from("fox.txt"), vec![0x414F_A339]).eval(&state)
+        };
+
+        assert!(eval(Some(42)).unwrap());
+        assert!(eval(Some(43)).is_err());
+        assert!(eval(None).is_err());
     }
 
-    #[cfg(windows)]
     #[test]
-    fn function_readable_eval_should_be_false_for_a_file_that_is_not_readable() {
-        use std::os::windows::fs::OpenOptionsExt;
-
+    #[cfg(all(feature = "parallel", any(windows, unix)))]
+    fn function_checksum_eval_should_hash_files_larger_than_the_parallel_threshold_in_parallel() {
         let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
-        let state = state(data_path);
+        let data_path = tmp_dir.path();
+        std::fs::write(
+            data_path.join("fox.txt"),
+            "The quick brown fox jumps over the lazy dog",
+        )
+        .unwrap();
 
-        let relative_path = "unreadable";
-        let file_path = state.data_path.join(relative_path);
+        for threshold in [Some(0), Some(43), None] {
+            let mut state = state(data_path);
+            state.set_memory_map_threshold(Some(0));
+            state.set_parallel_crc_threshold(threshold);
 
-        // Create a file and open it with exclusive access so that the readable
-        // function eval isn't able to open the file in read-only mode.
-        let _file = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .share_mode(0)
-            .open(&file_path);
+            let function = Function::Checksum(PathBuf::from("fox.txt"), vec![0x414F_A339]);
+            assert!(function.eval(&state).unwrap());
+        }
+    }
 
-        assert!(file_path.exists());
+    /// The SHA-256 digest of "The quick brown fox jumps over the lazy dog".
+    const FOX_SHA256: [u8; 32] = [
+        0xd7, 0xa8, 0xfb, 0xb3, 0x07, 0xd7, 0x80, 0x94, 0x69, 0xca, 0x9a, 0xbc, 0xb0, 0x08, 0x2e,
+        0x4f, 0x8d, 0x56, 0x51, 0xe4, 0x6d, 0x3c, 0xdb, 0x76, 0x2d, 0x02, 0xd0, 0xbf, 0x37, 0xc9,
+        0xe5, 0x92,
+    ];
 
-        let function = Function::Readable(PathBuf::from(relative_path));
+    #[test]
+    fn function_checksum_sha256_eval_should_compare_the_file_digest_with_the_given_digest() {
+        let tmp_dir = tempdir().unwrap();
+        let state = state(tmp_dir.path());
+        std::fs::write(
+            state.data_path.join("fox.txt"),
+            "The quick brown fox jumps over the lazy dog",
+        )
+        .unwrap();
 
-        assert!(!function.eval(&state).unwrap());
+        let eval = |path: &str, digest| {
+            Function::ChecksumSha256(PathBuf::from(path), digest)
+                .eval(&state)
+                .unwrap()
+        };
+
+        assert!(eval("fox.txt", FOX_SHA256));
+        assert!(!eval("fox.txt", [0; 32]));
+        assert!(!eval("missing.txt", FOX_SHA256));
+        assert!(!eval(".", FOX_SHA256));
     }
 
-    #[cfg(not(windows))]
     #[test]
-    fn function_readable_eval_should_be_false_for_a_file_that_is_not_readable() {
+    fn function_checksum_sha256_eval_should_cache_and_use_cached_digests() {
         let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
-        let state = state(data_path);
+        let state = state(tmp_dir.path());
+        let file_path = state.data_path.join("fox.txt");
+        std::fs::write(&file_path, "The quick brown fox jumps over the lazy dog").unwrap();
 
-        let relative_path = "unreadable";
-        let file_path = state.data_path.join(relative_path);
+        let function = Function::ChecksumSha256(PathBuf::from("Fox.txt"), FOX_SHA256);
 
-        std::fs::write(&file_path, "").unwrap();
-        make_path_unreadable(&file_path);
+        let (result, source, _) = function.eval_with_source(&state).unwrap();
+        assert!(result);
+        assert_eq!(ResultSource::FreshIo, source);
 
-        assert!(file_path.exists());
+        // Change the file's digest to test that the cached value is used.
+        std::fs::write(&file_path, "Something else").unwrap();
 
-        let function = Function::Readable(PathBuf::from(relative_path));
+        let function = Function::ChecksumSha256(PathBuf::from("fox.txt"), FOX_SHA256);
 
-        assert!(!function.eval(&state).unwrap());
+        let (result, source, _) = function.eval_with_source(&state).unwrap();
+        assert!(result);
+        assert_eq!(ResultSource::Sha256Cache, source);
     }
 
-    #[cfg(windows)]
     #[test]
-    fn function_readable_eval_should_be_false_for_a_folder_that_is_not_readable() {
-        let data_path = Path::new(r"C:\Program Files");
+    fn function_eval_should_cache_results_and_use_cached_results() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
         let state = state(data_path);
 
-        let relative_path = "WindowsApps";
+        copy(Path::new("Cargo.toml"), state.data_path.join("Cargo.toml")).unwrap();
 
-        // The WindowsApps directory is so locked down that trying to read its
-        // metadata fails, but its existence can still be observed by iterating
-        // over its parent directory's entries.
-        let entry_exists = state
-            .data_path
-            .read_dir()
-            .unwrap()
-            .flat_map(|res| res.map(|e| e.file_name()).into_iter())
-            .any(|name| name == relative_path);
+        let function = Function::FilePath(PathBuf::from("Cargo.toml"));
 
-        assert!(entry_exists);
+        assert!(function.eval(&state).unwrap());
 
-        let function = Function::Readable(PathBuf::from(relative_path));
+        remove_file(state.data_path.join("Cargo.toml")).unwrap();
 
-        assert!(!function.eval(&state).unwrap());
+        assert!(function.eval(&state).unwrap());
     }
 
-    #[cfg(not(windows))]
     #[test]
-    fn function_readable_eval_should_be_false_for_a_folder_that_is_not_readable() {
-        let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
-        let state = state(data_path);
+    fn function_version_eval_should_be_true_if_the_path_does_not_exist_and_comparator_is_ne() {
+        let function =
+            Function::Version("missing".into(), "1.0".into(), ComparisonOperator::NotEqual);
+        let state = state(".");
 
-        let relative_path = "unreadable";
-        let folder_path = state.data_path.join(relative_path);
+        assert!(function.eval(&state).unwrap());
+    }
 
-        create_dir_all(&folder_path).unwrap();
-        make_path_unreadable(&folder_path);
+    #[test]
+    fn function_version_eval_should_be_true_if_the_path_does_not_exist_and_comparator_is_lt() {
+        let function =
+            Function::Version("missing".into(), "1.0".into(), ComparisonOperator::LessThan);
+        let state = state(".");
 
-        assert!(folder_path.exists());
+        assert!(function.eval(&state).unwrap());
+    }
 
-        let function = Function::Readable(PathBuf::from(relative_path));
+    #[test]
+    fn function_version_eval_should_be_true_if_the_path_does_not_exist_and_comparator_is_lteq() {
+        let function = Function::Version(
+            "missing".into(),
+            "1.0".into(),
+            ComparisonOperator::LessThanOrEqual,
+        );
+        let state = state(".");
 
-        assert!(!function.eval(&state).unwrap());
+        assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_is_executable_should_be_false_for_a_path_that_does_not_exist() {
+    fn function_version_eval_should_be_false_if_the_path_does_not_exist_and_comparator_is_eq() {
+        let function = Function::Version("missing".into(), "1.0".into(), ComparisonOperator::Equal);
         let state = state(".");
-        let function = Function::IsExecutable("missing".into());
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_is_executable_should_be_false_for_a_directory() {
+    fn function_version_eval_should_be_false_if_the_path_does_not_exist_and_comparator_is_gt() {
+        let function = Function::Version(
+            "missing".into(),
+            "1.0".into(),
+            ComparisonOperator::GreaterThan,
+        );
         let state = state(".");
-        let function = Function::IsExecutable("tests".into());
 
         assert!(!function.eval(&state).unwrap());
     }
 
-    #[cfg(windows)]
     #[test]
-    fn function_is_executable_should_be_false_for_a_file_that_cannot_be_read() {
-        use std::os::windows::fs::OpenOptionsExt;
+    fn function_version_eval_should_be_false_if_the_path_does_not_exist_and_comparator_is_gteq() {
+        let function = Function::Version(
+            "missing".into(),
+            "1.0".into(),
+            ComparisonOperator::GreaterThanOrEqual,
+        );
+        let state = state(".");
 
-        let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
-        let state = state(data_path);
+        assert!(!function.eval(&state).unwrap());
+    }
 
-        let relative_path = "unreadable";
-        let file_path = state.data_path.join(relative_path);
+    #[test]
+    fn function_version_eval_should_be_true_if_the_path_is_not_a_file_and_comparator_is_ne() {
+        let function =
+            Function::Version("tests".into(), "1.0".into(), ComparisonOperator::NotEqual);
+        let state = state(".");
 
-        // Create a file and open it with exclusive access so that the readable
-        // function eval isn't able to open the file in read-only mode.
-        let _file = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .share_mode(0)
-            .open(&file_path);
+        assert!(function.eval(&state).unwrap());
+    }
 
-        assert!(file_path.exists());
+    #[test]
+    fn function_version_eval_should_be_true_if_the_path_is_not_a_file_and_comparator_is_lt() {
+        let function =
+            Function::Version("tests".into(), "1.0".into(), ComparisonOperator::LessThan);
+        let state = state(".");
 
-        let function = Function::IsExecutable(PathBuf::from(relative_path));
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_version_eval_should_be_true_if_the_path_is_not_a_file_and_comparator_is_lteq() {
+        let function = Function::Version(
+            "tests".into(),
+            "1.0".into(),
+            ComparisonOperator::LessThanOrEqual,
+        );
+        let state = state(".");
 
-        assert!(!function.eval(&state).unwrap());
+        assert!(function.eval(&state).unwrap());
     }
 
-    #[cfg(not(windows))]
     #[test]
-    fn function_is_executable_should_be_false_for_a_file_that_cannot_be_read() {
-        let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
-        let state = state(data_path);
-
-        let relative_path = "unreadable";
-        let file_path = state.data_path.join(relative_path);
-
-        std::fs::write(&file_path, "").unwrap();
-        make_path_unreadable(&file_path);
+    fn function_version_eval_should_be_false_if_the_path_is_not_a_file_and_comparator_is_eq() {
+        let function = Function::Version("tests".into(), "1.0".into(), ComparisonOperator::Equal);
+        let state = state(".");
 
-        assert!(file_path.exists());
+        assert!(!function.eval(&state).unwrap());
+    }
 
-        let function = Function::IsExecutable(PathBuf::from(relative_path));
+    #[test]
+    fn function_version_eval_should_be_false_if_the_path_is_not_a_file_and_comparator_is_gt() {
+        let function = Function::Version(
+            "tests".into(),
+            "1.0".into(),
+            ComparisonOperator::GreaterThan,
+        );
+        let state = state(".");
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_is_executable_should_be_false_for_a_file_that_is_not_an_executable() {
+    fn function_version_eval_should_be_false_if_the_path_is_not_a_file_and_comparator_is_gteq() {
+        let function = Function::Version(
+            "tests".into(),
+            "1.0".into(),
+            ComparisonOperator::GreaterThanOrEqual,
+        );
         let state = state(".");
-        let function = Function::IsExecutable("Cargo.toml".into());
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_is_executable_should_be_true_for_a_file_that_is_an_executable() {
-        let state = state(".");
-        let function = Function::IsExecutable("tests/libloot_win32/loot.dll".into());
+    fn function_version_eval_should_treat_a_plugin_with_no_cached_version_as_if_it_did_not_exist() {
+        use self::ComparisonOperator::*;
+
+        let plugin = PathBuf::from("Blank.esm");
+        let version = String::from("1.0");
+        let state = state("tests/testing-plugins/Oblivion/Data");
 
+        let function = Function::Version(plugin.clone(), version.clone(), NotEqual);
+        assert!(function.eval(&state).unwrap());
+        let function = Function::Version(plugin.clone(), version.clone(), LessThan);
         assert!(function.eval(&state).unwrap());
+        let function = Function::Version(plugin.clone(), version.clone(), LessThanOrEqual);
+        assert!(function.eval(&state).unwrap());
+        let function = Function::Version(plugin.clone(), version.clone(), Equal);
+        assert!(!function.eval(&state).unwrap());
+        let function = Function::Version(plugin.clone(), version.clone(), GreaterThan);
+        assert!(!function.eval(&state).unwrap());
+        let function = Function::Version(plugin.clone(), version.clone(), GreaterThanOrEqual);
+        assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_active_path_eval_should_be_true_if_the_path_is_an_active_plugin() {
-        let function = Function::ActivePath(PathBuf::from("Blank.esp"));
-        let state = state_with_active_plugins(".", &["Blank.esp"]);
+    fn function_version_eval_should_be_false_if_versions_are_not_equal_and_comparator_is_eq() {
+        let function = Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::Equal);
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "1")]);
 
-        assert!(function.eval(&state).unwrap());
+        assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_active_path_eval_should_be_case_insensitive() {
-        let function = Function::ActivePath(PathBuf::from("Blank.esp"));
-        let state = state_with_active_plugins(".", &["blank.esp"]);
+    fn function_version_eval_should_be_true_if_versions_are_equal_and_comparator_is_eq() {
+        let function = Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::Equal);
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "5")]);
 
         assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_active_path_eval_should_be_false_if_the_path_is_not_an_active_plugin() {
-        let function = Function::ActivePath(PathBuf::from("inactive.esp"));
-        let state = state_with_active_plugins(".", &["Blank.esp"]);
+    fn function_version_eval_should_be_false_if_versions_are_equal_and_comparator_is_ne() {
+        let function =
+            Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::NotEqual);
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "5")]);
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_active_regex_eval_should_be_true_if_the_regex_matches_an_active_plugin() {
-        let function = Function::ActiveRegex(regex("Blank\\.esp"));
-        let state = state_with_active_plugins(".", &["Blank.esp"]);
+    fn function_version_eval_should_be_true_if_versions_are_not_equal_and_comparator_is_ne() {
+        let function =
+            Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::NotEqual);
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "1")]);
 
         assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_active_regex_eval_should_be_false_if_the_regex_does_not_match_an_active_plugin() {
-        let function = Function::ActiveRegex(regex("inactive\\.esp"));
-        let state = state_with_active_plugins(".", &["Blank.esp"]);
+    fn function_version_eval_should_be_false_if_actual_version_is_eq_and_comparator_is_lt() {
+        let function =
+            Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::LessThan);
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "5")]);
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_is_master_eval_should_be_true_if_the_path_is_a_master_plugin() {
-        let function = Function::IsMaster(PathBuf::from("Blank.esm"));
-        let state = state("tests/testing-plugins/Oblivion/Data");
+    fn function_version_eval_should_be_false_if_actual_version_is_gt_and_comparator_is_lt() {
+        let function =
+            Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::LessThan);
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "6")]);
+
+        assert!(!function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_version_eval_should_be_true_if_actual_version_is_lt_and_comparator_is_lt() {
+        let function =
+            Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::NotEqual);
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "1")]);
 
         assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_is_master_eval_should_be_false_if_the_path_is_an_openmw_master_flagged_plugin() {
-        let function = Function::IsMaster(PathBuf::from("Blank.esm"));
-        let mut state = state("tests/testing-plugins/Morrowind/Data Files");
-        state.game_type = GameType::OpenMW;
+    fn function_version_eval_should_be_false_if_actual_version_is_eq_and_comparator_is_gt() {
+        let function = Function::Version(
+            "Blank.esm".into(),
+            "5".into(),
+            ComparisonOperator::GreaterThan,
+        );
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "5")]);
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_is_master_eval_should_be_false_if_the_path_does_not_exist() {
-        let function = Function::IsMaster(PathBuf::from("missing.esp"));
-        let state = state("tests/testing-plugins/Oblivion/Data");
+    fn function_version_eval_should_be_false_if_actual_version_is_lt_and_comparator_is_gt() {
+        let function = Function::Version(
+            "Blank.esm".into(),
+            "5".into(),
+            ComparisonOperator::GreaterThan,
+        );
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "4")]);
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_is_master_eval_should_be_false_if_the_path_is_not_a_plugin() {
-        let function = Function::IsMaster(PathBuf::from("Cargo.toml"));
-        let state = state(".");
+    fn function_version_eval_should_be_true_if_actual_version_is_gt_and_comparator_is_gt() {
+        let function = Function::Version(
+            "Blank.esm".into(),
+            "5".into(),
+            ComparisonOperator::GreaterThan,
+        );
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "6")]);
 
-        assert!(!function.eval(&state).unwrap());
+        assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_is_master_eval_should_be_false_if_the_path_is_a_non_master_plugin() {
-        let function = Function::IsMaster(PathBuf::from("Blank.esp"));
-        let state = state("tests/testing-plugins/Oblivion/Data");
+    fn function_version_eval_should_be_false_if_actual_version_is_gt_and_comparator_is_lteq() {
+        let function = Function::Version(
+            "Blank.esm".into(),
+            "5".into(),
+            ComparisonOperator::LessThanOrEqual,
+        );
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "6")]);
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_many_eval_should_be_false_if_no_directory_entries_match() {
-        let function = Function::Many(PathBuf::from("."), regex("missing"));
-        let state = state(".");
+    fn function_version_eval_should_be_true_if_actual_version_is_eq_and_comparator_is_lteq() {
+        let function = Function::Version(
+            "Blank.esm".into(),
+            "5".into(),
+            ComparisonOperator::LessThanOrEqual,
+        );
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "5")]);
 
-        assert!(!function.eval(&state).unwrap());
+        assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_many_eval_should_be_false_if_the_parent_path_part_is_not_a_directory() {
-        let function = Function::Many(PathBuf::from("missing"), regex("Cargo.*"));
-        let state = state(".");
+    fn function_version_eval_should_be_true_if_actual_version_is_lt_and_comparator_is_lteq() {
+        let function = Function::Version(
+            "Blank.esm".into(),
+            "5".into(),
+            ComparisonOperator::LessThanOrEqual,
+        );
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "4")]);
 
-        assert!(!function.eval(&state).unwrap());
+        assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_many_eval_should_be_false_if_one_directory_entry_matches() {
-        let function = Function::Many(
-            PathBuf::from("tests/testing-plugins/Oblivion/Data"),
-            regex("Blank\\.esp"),
+    fn function_version_eval_should_be_false_if_actual_version_is_lt_and_comparator_is_gteq() {
+        let function = Function::Version(
+            "Blank.esm".into(),
+            "5".into(),
+            ComparisonOperator::GreaterThanOrEqual,
         );
-        let state = state(".");
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "4")]);
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_many_eval_should_be_true_if_more_than_one_directory_entry_matches() {
-        let function = Function::Many(
-            PathBuf::from("tests/testing-plugins/Oblivion/Data"),
-            regex("Blank.*"),
+    fn function_version_eval_should_be_true_if_actual_version_is_eq_and_comparator_is_gteq() {
+        let function = Function::Version(
+            "Blank.esm".into(),
+            "5".into(),
+            ComparisonOperator::GreaterThanOrEqual,
         );
-        let state = state(".");
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "5")]);
 
         assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_many_eval_should_trim_ghost_plugin_extension_before_matching_against_regex() {
-        let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
-        let state = state(data_path);
+    fn function_version_eval_should_be_true_if_actual_version_is_gt_and_comparator_is_gteq() {
+        let function = Function::Version(
+            "Blank.esm".into(),
+            "5".into(),
+            ComparisonOperator::GreaterThanOrEqual,
+        );
+        let state =
+            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "6")]);
 
-        copy(
-            Path::new("tests/testing-plugins/Oblivion/Data/Blank.esm"),
-            state.data_path.join("Blank.esm.ghost"),
-        )
-        .unwrap();
-        copy(
-            Path::new("tests/testing-plugins/Oblivion/Data/Blank.esp"),
-            state.data_path.join("Blank.esp.ghost"),
-        )
-        .unwrap();
+        assert!(function.eval(&state).unwrap());
+    }
 
-        let function = Function::Many(PathBuf::from("."), regex("^Blank\\.es(m|p)$"));
+    #[test]
+    fn function_version_eval_should_read_executable_file_version() {
+        let function = Function::Version(
+            "loot.dll".into(),
+            "0.18.2.0".into(),
+            ComparisonOperator::Equal,
+        );
+        let state = state("tests/libloot_win32");
+
+        assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_product_version_eval_should_read_executable_product_version() {
+        let function = Function::ProductVersion(
+            "loot.dll".into(),
+            "0.18.2".into(),
+            ComparisonOperator::Equal,
+        );
+        let state = state("tests/libloot_win32");
 
         assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_many_eval_should_check_across_all_configured_data_paths() {
-        let function = Function::Many(PathBuf::from("Data"), regex("Blank\\.esp"));
-        let state = state_with_data(
-            "./tests/testing-plugins/Skyrim",
-            vec!["./tests/testing-plugins/Oblivion"],
-            &[],
-            &[],
-        );
+    fn function_version_in_range_eval_should_be_true_if_the_version_is_within_inclusive_bounds() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        File::create(data_path.join("Blank.esm")).unwrap();
+        let state = state_with_versions(data_path, &[("Blank.esm", "0.18.2")]);
+        let in_range = |lower: &str, upper: &str| {
+            Function::VersionInRange("Blank.esm".into(), lower.into(), upper.into())
+                .eval(&state)
+                .unwrap()
+        };
 
-        assert!(function.eval(&state).unwrap());
+        assert!(in_range("0.18", "0.19"));
+        assert!(in_range("0.18.2", "0.18.2"));
+        assert!(!in_range("0.18.3", "0.19"));
+        assert!(!in_range("0.17", "0.18.1"));
     }
 
     #[test]
-    fn function_many_active_eval_should_be_true_if_the_regex_matches_more_than_one_active_plugin() {
-        let function = Function::ManyActive(regex("Blank.*"));
-        let state = state_with_active_plugins(".", &["Blank.esp", "Blank.esm"]);
+    fn function_version_in_range_eval_should_be_false_if_the_file_does_not_exist() {
+        let function = Function::VersionInRange("missing".into(), "0".into(), "999".into());
+        let state = state(".");
 
-        assert!(function.eval(&state).unwrap());
+        assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_many_active_eval_should_be_false_if_one_active_plugin_matches() {
-        let function = Function::ManyActive(regex("Blank\\.esp"));
-        let state = state_with_active_plugins(".", &["Blank.esp", "Blank.esm"]);
+    fn function_product_version_in_range_eval_should_read_executable_product_version() {
+        let function =
+            Function::ProductVersionInRange("loot.dll".into(), "0.18".into(), "0.18.2".into());
+        let state = state("tests/libloot_win32");
 
-        assert!(!function.eval(&state).unwrap());
+        assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_many_active_eval_should_be_false_if_the_regex_does_not_match_an_active_plugin() {
-        let function = Function::ManyActive(regex("inactive\\.esp"));
-        let state = state_with_active_plugins(".", &["Blank.esp", "Blank.esm"]);
+    fn get_product_version_should_return_ok_none_if_the_path_does_not_exist() {
+        assert!(get_product_version(&state("."), Path::new("missing"))
+            .unwrap()
+            .is_none());
+    }
 
-        assert!(!function.eval(&state).unwrap());
+    #[test]
+    fn get_product_version_should_return_ok_none_if_the_path_is_not_a_file() {
+        assert!(get_product_version(&state("."), Path::new("tests"))
+            .unwrap()
+            .is_none());
     }
 
     #[test]
-    fn function_checksum_eval_should_be_false_if_the_file_does_not_exist() {
-        let function = Function::Checksum(PathBuf::from("missing"), 0x374E_2A6F);
-        let state = state(".");
+    fn get_product_version_should_return_ok_some_if_the_path_is_an_executable() {
+        let version = get_product_version(&state("."), Path::new("tests/libloot_win32/loot.dll"))
+            .unwrap()
+            .unwrap();
 
-        assert!(!function.eval(&state).unwrap());
+        assert_eq!((Version::from("0.18.2"), ResultSource::FreshIo), version);
     }
 
     #[test]
-    fn function_checksum_eval_should_be_false_if_the_file_checksum_does_not_equal_the_given_checksum(
-    ) {
-        let function = Function::Checksum(
-            PathBuf::from("tests/testing-plugins/Oblivion/Data/Blank.esm"),
-            0xDEAD_BEEF,
+    fn get_product_version_should_error_if_the_path_is_not_an_executable() {
+        assert!(get_product_version(&state("."), Path::new("Cargo.toml")).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn function_filename_version_eval_should_be_false_if_no_matching_filenames_exist() {
+        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
+
+        let function = Function::FilenameVersion(
+            "".into(),
+            regex("Blank (A+).esm"),
+            "5".into(),
+            ComparisonOperator::Equal,
         );
-        let state = state(".");
 
         assert!(!function.eval(&state).unwrap());
-    }
 
-    #[test]
-    fn function_checksum_eval_should_be_true_if_the_file_checksum_equals_the_given_checksum() {
-        let function = Function::Checksum(
-            PathBuf::from("tests/testing-plugins/Oblivion/Data/Blank.esm"),
-            0x374E_2A6F,
+        let function = Function::FilenameVersion(
+            "".into(),
+            regex("Blank (A+).esm"),
+            "5".into(),
+            ComparisonOperator::NotEqual,
         );
-        let state = state(".");
 
-        assert!(function.eval(&state).unwrap());
-    }
+        assert!(!function.eval(&state).unwrap());
 
-    #[test]
-    fn function_checksum_eval_should_support_checking_the_crc_of_a_ghosted_plugin() {
-        let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
-        let state = state(data_path);
+        let function = Function::FilenameVersion(
+            "".into(),
+            regex("Blank (A+).esm"),
+            "5".into(),
+            ComparisonOperator::LessThan,
+        );
 
-        copy(
-            Path::new("tests/testing-plugins/Oblivion/Data/Blank.esm"),
-            state.data_path.join("Blank.esm.ghost"),
-        )
-        .unwrap();
+        assert!(!function.eval(&state).unwrap());
 
-        let function = Function::Checksum(PathBuf::from("Blank.esm"), 0x374E_2A6F);
+        let function = Function::FilenameVersion(
+            "".into(),
+            regex("Blank (A+).esm"),
+            "5".into(),
+            ComparisonOperator::GreaterThan,
+        );
 
-        assert!(function.eval(&state).unwrap());
-    }
+        assert!(!function.eval(&state).unwrap());
 
-    #[test]
-    fn function_checksum_eval_should_not_check_for_ghosted_non_plugin_file() {
-        let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
-        let state = state(data_path);
+        let function = Function::FilenameVersion(
+            "".into(),
+            regex("Blank (A+).esm"),
+            "5".into(),
+            ComparisonOperator::LessThanOrEqual,
+        );
 
-        copy(
-            Path::new("tests/testing-plugins/Oblivion/Data/Blank.bsa"),
-            state.data_path.join("Blank.bsa.ghost"),
-        )
-        .unwrap();
+        assert!(!function.eval(&state).unwrap());
 
-        let function = Function::Checksum(PathBuf::from("Blank.bsa"), 0x22AB_79D9);
+        let function = Function::FilenameVersion(
+            "".into(),
+            regex("Blank (A+).esm"),
+            "5".into(),
+            ComparisonOperator::GreaterThanOrEqual,
+        );
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_checksum_eval_should_be_false_if_given_a_directory_path() {
-        // The given CRC is the CRC-32 of the directory as calculated by 7-zip.
-        let function = Function::Checksum(PathBuf::from("tests/testing-plugins"), 0xC9CD_16C3);
-        let state = state(".");
+    #[cfg(feature = "regex")]
+    fn function_filename_version_eval_should_be_false_if_filenames_matched_but_no_version_was_captured(
+    ) {
+        // This shouldn't happen in practice because parsing validates that there is one explicit capturing group in the regex.
+        let function = Function::FilenameVersion(
+            "".into(),
+            regex("Blank .+.esm"),
+            "5".into(),
+            ComparisonOperator::GreaterThanOrEqual,
+        );
+        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
 
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_checksum_eval_should_cache_and_use_cached_crcs() {
+    fn function_filename_version_eval_should_be_true_if_the_captured_version_is_eq_and_operator_is_eq(
+    ) {
         let tmp_dir = tempdir().unwrap();
         let data_path = tmp_dir.path().join("Data");
         let state = state(data_path);
 
         copy(
             Path::new("tests/testing-plugins/Oblivion/Data/Blank.esm"),
-            state.data_path.join("Blank.esm"),
+            state.data_path.join("Blank 5.esm"),
         )
         .unwrap();
 
-        let function = Function::Checksum(PathBuf::from("Blank.esm"), 0x374E_2A6F);
+        let function = Function::FilenameVersion(
+            "".into(),
+            regex("Blank (\\d).esm"),
+            "5".into(),
+            ComparisonOperator::Equal,
+        );
 
         assert!(function.eval(&state).unwrap());
+    }
+
+    #[test]
+    fn function_filename_version_eval_should_be_true_if_the_captured_version_is_not_equal_and_operator_is_not_equal(
+    ) {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        let state = state(data_path);
 
-        // Change the CRC of the file to test that the cached value is used.
         copy(
-            Path::new("tests/testing-plugins/Oblivion/Data/Blank.bsa"),
-            state.data_path.join("Blank.esm"),
+            Path::new("tests/testing-plugins/Oblivion/Data/Blank.esm"),
+            state.data_path.join("Blank 4.esm"),
         )
         .unwrap();
 
-        let function = Function::Checksum(PathBuf::from("Blank.esm"), 0x374E_2A6F);
+        let function = Function::FilenameVersion(
+            "".into(),
+            regex("Blank (\\d).esm"),
+            "5".into(),
+            ComparisonOperator::NotEqual,
+        );
 
         assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_eval_should_cache_results_and_use_cached_results() {
-        let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
-        let state = state(data_path);
+    fn function_description_contains_eval_should_return_false_if_the_file_does_not_exist() {
+        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
 
-        copy(Path::new("Cargo.toml"), state.data_path.join("Cargo.toml")).unwrap();
+        let function =
+            Function::DescriptionContains("missing.esp".into(), regex(LOWERCASE_NON_ASCII));
 
-        let function = Function::FilePath(PathBuf::from("Cargo.toml"));
+        assert!(!function.eval(&state).unwrap());
+    }
 
-        assert!(function.eval(&state).unwrap());
+    #[test]
+    fn function_description_contains_eval_should_return_false_if_the_file_is_not_a_plugin() {
+        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
 
-        remove_file(state.data_path.join("Cargo.toml")).unwrap();
+        let function =
+            Function::DescriptionContains("Blank.bsa".into(), regex(LOWERCASE_NON_ASCII));
 
-        assert!(function.eval(&state).unwrap());
+        assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_the_path_does_not_exist_and_comparator_is_ne() {
-        let function =
-            Function::Version("missing".into(), "1.0".into(), ComparisonOperator::NotEqual);
-        let state = state(".");
+    fn function_description_contains_eval_should_return_false_if_the_plugin_has_no_description() {
+        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
 
-        assert!(function.eval(&state).unwrap());
+        let function = Function::DescriptionContains(
+            "Blank - Different.esm".into(),
+            regex(LOWERCASE_NON_ASCII),
+        );
+
+        assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_the_path_does_not_exist_and_comparator_is_lt() {
+    fn function_description_contains_eval_should_return_false_if_the_plugin_description_does_not_match(
+    ) {
+        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
+
         let function =
-            Function::Version("missing".into(), "1.0".into(), ComparisonOperator::LessThan);
-        let state = state(".");
+            Function::DescriptionContains("Blank.esm".into(), regex(LOWERCASE_NON_ASCII));
 
-        assert!(function.eval(&state).unwrap());
+        assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_the_path_does_not_exist_and_comparator_is_lteq() {
-        let function = Function::Version(
-            "missing".into(),
-            "1.0".into(),
-            ComparisonOperator::LessThanOrEqual,
-        );
-        let state = state(".");
+    fn function_description_contains_eval_should_return_true_if_the_plugin_description_contains_a_match(
+    ) {
+        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
+
+        let function =
+            Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII));
 
         assert!(function.eval(&state).unwrap());
     }
 
-    #[test]
-    fn function_version_eval_should_be_false_if_the_path_does_not_exist_and_comparator_is_eq() {
-        let function = Function::Version("missing".into(), "1.0".into(), ComparisonOperator::Equal);
-        let state = state(".");
+    #[derive(Debug)]
+    struct HostPlugin {
+        is_master: bool,
+        description: Option<String>,
+    }
 
-        assert!(!function.eval(&state).unwrap());
+    impl ParsedPlugin for HostPlugin {
+        fn is_master_file(&self) -> bool {
+            self.is_master
+        }
+
+        /// Host plugins are light and medium if they're masters, so that all
+        /// the functions can be tested using the same plugin.
+        fn is_light_plugin(&self) -> bool {
+            self.is_master
+        }
+
+        fn is_medium_plugin(&self) -> bool {
+            self.is_master
+        }
+
+        fn header_version(&self) -> Option<f32> {
+            Some(1.71)
+        }
+
+        fn record_count(&self) -> Option<u32> {
+            Some(3)
+        }
+
+        fn masters(&self) -> Vec<String> {
+            vec!["Skyrim.esm".to_owned()]
+        }
+
+        fn description(&self) -> Option<String> {
+            self.description.clone()
+        }
+    }
+
+    fn state_with_host_plugin(is_master: bool, description: Option<&str>) -> State {
+        let mut state = state(".");
+        let plugin: Arc<dyn ParsedPlugin> = Arc::new(HostPlugin {
+            is_master,
+            description: description.map(str::to_owned),
+        });
+        state.set_parsed_plugins(&[("Host.esp", plugin)]);
+        state
+    }
+
+    fn eval_source(function: &Function, state: &State) -> (bool, ResultSource) {
+        let mut evaluations = Vec::new();
+        let result = function
+            .eval_with_observer(state, &(0..0), &mut evaluations)
+            .unwrap();
+
+        (result, evaluations[0].source())
     }
 
     #[test]
-    fn function_version_eval_should_be_false_if_the_path_does_not_exist_and_comparator_is_gt() {
-        let function = Function::Version(
-            "missing".into(),
-            "1.0".into(),
-            ComparisonOperator::GreaterThan,
-        );
-        let state = state(".");
+    fn function_is_master_eval_should_use_a_parsed_plugin_with_the_same_filename() {
+        let function = Function::IsMaster(PathBuf::from("host.ESP"));
 
-        assert!(!function.eval(&state).unwrap());
+        assert_eq!(
+            (true, ResultSource::Preloaded),
+            eval_source(&function, &state_with_host_plugin(true, None))
+        );
+        assert_eq!(
+            (false, ResultSource::Preloaded),
+            eval_source(&function, &state_with_host_plugin(false, None))
+        );
     }
 
     #[test]
-    fn function_version_eval_should_be_false_if_the_path_does_not_exist_and_comparator_is_gteq() {
-        let function = Function::Version(
-            "missing".into(),
-            "1.0".into(),
-            ComparisonOperator::GreaterThanOrEqual,
-        );
-        let state = state(".");
+    fn function_is_light_plugin_eval_should_use_a_parsed_plugin_with_the_same_filename() {
+        let function = Function::IsLightPlugin(PathBuf::from("host.ESP"));
 
-        assert!(!function.eval(&state).unwrap());
+        assert_eq!(
+            (true, ResultSource::Preloaded),
+            eval_source(&function, &state_with_host_plugin(true, None))
+        );
+        assert_eq!(
+            (false, ResultSource::Preloaded),
+            eval_source(&function, &state_with_host_plugin(false, None))
+        );
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_the_path_is_not_a_file_and_comparator_is_ne() {
-        let function =
-            Function::Version("tests".into(), "1.0".into(), ComparisonOperator::NotEqual);
-        let state = state(".");
+    fn function_is_medium_plugin_eval_should_use_a_parsed_plugin_with_the_same_filename() {
+        let function = Function::IsMediumPlugin(PathBuf::from("host.ESP"));
 
-        assert!(function.eval(&state).unwrap());
+        assert_eq!(
+            (true, ResultSource::Preloaded),
+            eval_source(&function, &state_with_host_plugin(true, None))
+        );
+        assert_eq!(
+            (false, ResultSource::Preloaded),
+            eval_source(&function, &state_with_host_plugin(false, None))
+        );
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_the_path_is_not_a_file_and_comparator_is_lt() {
+    fn function_header_version_eval_should_use_a_parsed_plugin_with_the_same_filename() {
         let function =
-            Function::Version("tests".into(), "1.0".into(), ComparisonOperator::LessThan);
-        let state = state(".");
+            Function::HeaderVersion(PathBuf::from("host.ESP"), ComparisonOperator::Equal, 1.71);
 
-        assert!(function.eval(&state).unwrap());
+        assert_eq!(
+            (true, ResultSource::Preloaded),
+            eval_source(&function, &state_with_host_plugin(false, None))
+        );
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_the_path_is_not_a_file_and_comparator_is_lteq() {
-        let function = Function::Version(
-            "tests".into(),
-            "1.0".into(),
-            ComparisonOperator::LessThanOrEqual,
-        );
-        let state = state(".");
+    fn function_record_count_eval_should_use_a_parsed_plugin_with_the_same_filename() {
+        let function =
+            Function::RecordCount(PathBuf::from("host.ESP"), ComparisonOperator::Equal, 3);
+        let state = state_with_host_plugin(false, None);
 
-        assert!(function.eval(&state).unwrap());
+        assert_eq!(
+            (true, ResultSource::Preloaded),
+            eval_source(&function, &state)
+        );
+        assert_eq!(
+            (true, ResultSource::Preloaded),
+            eval_source(&function, &state)
+        );
     }
 
     #[test]
-    fn function_version_eval_should_be_false_if_the_path_is_not_a_file_and_comparator_is_eq() {
-        let function = Function::Version("tests".into(), "1.0".into(), ComparisonOperator::Equal);
-        let state = state(".");
+    fn function_masters_contain_eval_should_use_a_parsed_plugin_with_the_same_filename() {
+        let state = state_with_host_plugin(false, None);
 
-        assert!(!function.eval(&state).unwrap());
+        assert_eq!(
+            (true, ResultSource::Preloaded),
+            eval_source(
+                &Function::MastersContain("host.ESP".into(), "skyrim.ESM".into()),
+                &state
+            )
+        );
+        assert_eq!(
+            (false, ResultSource::Preloaded),
+            eval_source(
+                &Function::MastersContain("host.ESP".into(), "Update.esm".into()),
+                &state
+            )
+        );
     }
 
     #[test]
-    fn function_version_eval_should_be_false_if_the_path_is_not_a_file_and_comparator_is_gt() {
-        let function = Function::Version(
-            "tests".into(),
-            "1.0".into(),
-            ComparisonOperator::GreaterThan,
-        );
-        let state = state(".");
+    fn function_description_contains_eval_should_use_a_parsed_plugin_with_the_same_filename() {
+        let state = state_with_host_plugin(false, Some("A plugin [Requires Patch]"));
 
-        assert!(!function.eval(&state).unwrap());
+        assert_eq!(
+            (true, ResultSource::Preloaded),
+            eval_source(
+                &Function::DescriptionContains("Host.esp".into(), regex("requires")),
+                &state
+            )
+        );
+        assert_eq!(
+            (true, ResultSource::Preloaded),
+            eval_source(
+                &Function::DescriptionContainsText("Host.esp".into(), "[requires patch]".into()),
+                &state
+            )
+        );
+        assert_eq!(
+            (false, ResultSource::Preloaded),
+            eval_source(
+                &Function::DescriptionContains("Host.esp".into(), regex("missing")),
+                &state_with_host_plugin(false, None)
+            )
+        );
     }
 
-    #[test]
-    fn function_version_eval_should_be_false_if_the_path_is_not_a_file_and_comparator_is_gteq() {
-        let function = Function::Version(
-            "tests".into(),
-            "1.0".into(),
-            ComparisonOperator::GreaterThanOrEqual,
-        );
-        let state = state(".");
+    /// Write a minimal Oblivion plugin that only has a header record with the
+    /// given description.
+    fn write_oblivion_plugin(path: &Path, description: &str) {
+        write_oblivion_plugin_with_author(path, description, "");
+    }
 
-        assert!(!function.eval(&state).unwrap());
+    /// Write a minimal Oblivion plugin that only has a header record with the
+    /// given description and author.
+    fn write_oblivion_plugin_with_author(path: &Path, description: &str, author: &str) {
+        let mut description = description.as_bytes().to_vec();
+        description.push(0);
+        let mut author = author.as_bytes().to_vec();
+        author.push(0);
+
+        let mut subrecords = Vec::new();
+        subrecords.extend_from_slice(b"HEDR");
+        subrecords.extend_from_slice(&12u16.to_le_bytes());
+        subrecords.extend_from_slice(&0.8f32.to_le_bytes());
+        subrecords.extend_from_slice(&0u32.to_le_bytes());
+        subrecords.extend_from_slice(&0u32.to_le_bytes());
+        subrecords.extend_from_slice(b"CNAM");
+        subrecords.extend_from_slice(&u16::try_from(author.len()).unwrap().to_le_bytes());
+        subrecords.extend_from_slice(&author);
+        subrecords.extend_from_slice(b"SNAM");
+        subrecords.extend_from_slice(&u16::try_from(description.len()).unwrap().to_le_bytes());
+        subrecords.extend_from_slice(&description);
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"TES4");
+        bytes.extend_from_slice(&u32::try_from(subrecords.len()).unwrap().to_le_bytes());
+        bytes.extend_from_slice(&[0; 12]);
+        bytes.extend_from_slice(&subrecords);
+
+        std::fs::write(path, bytes).unwrap();
     }
 
-    #[test]
-    fn function_version_eval_should_treat_a_plugin_with_no_cached_version_as_if_it_did_not_exist() {
-        use self::ComparisonOperator::*;
+    /// Write a minimal Skyrim plugin that only has a header record with the
+    /// given header version, record flags, count of records and groups, and
+    /// masters.
+    fn write_skyrim_plugin_header(
+        path: &Path,
+        header_version: f32,
+        flags: u32,
+        record_count: u32,
+        masters: &[&str],
+    ) {
+        let mut subrecords = Vec::new();
+        subrecords.extend_from_slice(b"HEDR");
+        subrecords.extend_from_slice(&12u16.to_le_bytes());
+        subrecords.extend_from_slice(&header_version.to_le_bytes());
+        subrecords.extend_from_slice(&record_count.to_le_bytes());
+        subrecords.extend_from_slice(&0u32.to_le_bytes());
+
+        for master in masters {
+            let mut master = master.as_bytes().to_vec();
+            master.push(0);
+
+            subrecords.extend_from_slice(b"MAST");
+            subrecords.extend_from_slice(&u16::try_from(master.len()).unwrap().to_le_bytes());
+            subrecords.extend_from_slice(&master);
+            subrecords.extend_from_slice(b"DATA");
+            subrecords.extend_from_slice(&8u16.to_le_bytes());
+            subrecords.extend_from_slice(&0u64.to_le_bytes());
+        }
 
-        let plugin = PathBuf::from("Blank.esm");
-        let version = String::from("1.0");
-        let state = state("tests/testing-plugins/Oblivion/Data");
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"TES4");
+        bytes.extend_from_slice(&u32::try_from(subrecords.len()).unwrap().to_le_bytes());
+        bytes.extend_from_slice(&flags.to_le_bytes());
+        bytes.extend_from_slice(&[0; 12]);
+        bytes.extend_from_slice(&subrecords);
 
-        let function = Function::Version(plugin.clone(), version.clone(), NotEqual);
-        assert!(function.eval(&state).unwrap());
-        let function = Function::Version(plugin.clone(), version.clone(), LessThan);
-        assert!(function.eval(&state).unwrap());
-        let function = Function::Version(plugin.clone(), version.clone(), LessThanOrEqual);
-        assert!(function.eval(&state).unwrap());
-        let function = Function::Version(plugin.clone(), version.clone(), Equal);
-        assert!(!function.eval(&state).unwrap());
-        let function = Function::Version(plugin.clone(), version.clone(), GreaterThan);
-        assert!(!function.eval(&state).unwrap());
-        let function = Function::Version(plugin.clone(), version.clone(), GreaterThanOrEqual);
-        assert!(!function.eval(&state).unwrap());
+        std::fs::write(path, bytes).unwrap();
     }
 
     #[test]
-    fn function_version_eval_should_be_false_if_versions_are_not_equal_and_comparator_is_eq() {
-        let function = Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::Equal);
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "1")]);
-
-        assert!(!function.eval(&state).unwrap());
+    fn function_is_light_plugin_eval_should_be_true_if_the_plugin_is_light_flagged_or_an_esl() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        write_skyrim_plugin_header(&data_path.join("Flagged.esp"), 1.7, 0x200, 0, &[]);
+        write_skyrim_plugin_header(&data_path.join("Unflagged.esl"), 1.7, 0, 0, &[]);
+        write_skyrim_plugin_header(&data_path.join("Unflagged.esp"), 1.7, 0, 0, &[]);
+        let mut state = state(data_path);
+        state.game_type = GameType::SkyrimSE;
+
+        for (plugin, expected) in [
+            ("Flagged.esp", true),
+            ("Unflagged.esl", true),
+            ("Unflagged.esp", false),
+        ] {
+            let function = Function::IsLightPlugin(plugin.into());
+
+            assert_eq!(expected, function.eval(&state).unwrap(), "{plugin}");
+        }
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_versions_are_equal_and_comparator_is_eq() {
-        let function = Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::Equal);
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "5")]);
-
-        assert!(function.eval(&state).unwrap());
+    fn function_is_light_plugin_eval_should_be_false_if_the_game_does_not_support_light_plugins() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        write_skyrim_plugin_header(&data_path.join("Flagged.esp"), 0.94, 0x200, 0, &[]);
+        let mut state = state(data_path);
+        state.game_type = GameType::Skyrim;
+
+        assert!(!Function::IsLightPlugin("Flagged.esp".into())
+            .eval(&state)
+            .unwrap());
     }
 
     #[test]
-    fn function_version_eval_should_be_false_if_versions_are_equal_and_comparator_is_ne() {
-        let function =
-            Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::NotEqual);
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "5")]);
-
-        assert!(!function.eval(&state).unwrap());
+    fn function_is_light_plugin_eval_should_be_false_if_the_path_is_not_a_plugin() {
+        let mut state = state(".");
+        state.game_type = GameType::SkyrimSE;
+
+        assert!(!Function::IsLightPlugin("Cargo.toml".into())
+            .eval(&state)
+            .unwrap());
+        assert!(!Function::IsLightPlugin("missing.esl".into())
+            .eval(&state)
+            .unwrap());
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_versions_are_not_equal_and_comparator_is_ne() {
-        let function =
-            Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::NotEqual);
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "1")]);
-
-        assert!(function.eval(&state).unwrap());
+    fn function_is_medium_plugin_eval_should_be_true_if_the_plugin_is_medium_flagged() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        write_skyrim_plugin_header(&data_path.join("Medium.esm"), 0.96, 0x401, 0, &[]);
+        write_skyrim_plugin_header(&data_path.join("Light.esm"), 0.96, 0x501, 0, &[]);
+        write_skyrim_plugin_header(&data_path.join("Full.esm"), 0.96, 0x1, 0, &[]);
+        let mut state = state(data_path);
+        state.game_type = GameType::Starfield;
+
+        for (plugin, expected) in [
+            ("Medium.esm", true),
+            ("Light.esm", false),
+            ("Full.esm", false),
+        ] {
+            let function = Function::IsMediumPlugin(plugin.into());
+
+            assert_eq!(expected, function.eval(&state).unwrap(), "{plugin}");
+        }
     }
 
     #[test]
-    fn function_version_eval_should_be_false_if_actual_version_is_eq_and_comparator_is_lt() {
-        let function =
-            Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::LessThan);
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "5")]);
+    fn function_is_medium_plugin_eval_should_be_false_if_the_game_does_not_support_medium_plugins()
+    {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        write_skyrim_plugin_header(&data_path.join("Medium.esm"), 1.7, 0x401, 0, &[]);
+        let mut state = state(data_path);
+        state.game_type = GameType::SkyrimSE;
+
+        assert!(!Function::IsMediumPlugin("Medium.esm".into())
+            .eval(&state)
+            .unwrap());
+    }
 
-        assert!(!function.eval(&state).unwrap());
+    #[test]
+    fn function_header_version_eval_should_compare_the_header_version_using_the_given_comparator() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        write_skyrim_plugin_header(&data_path.join("Blank.esp"), 1.71, 0, 0, &[]);
+        let mut state = state(data_path);
+        state.game_type = GameType::SkyrimSE;
+
+        for (version, comparator, expected) in [
+            (1.71, ComparisonOperator::Equal, true),
+            (1.7, ComparisonOperator::Equal, false),
+            (1.7, ComparisonOperator::NotEqual, true),
+            (1.7, ComparisonOperator::GreaterThan, true),
+            (1.71, ComparisonOperator::GreaterThan, false),
+            (1.71, ComparisonOperator::GreaterThanOrEqual, true),
+            (1.8, ComparisonOperator::LessThan, true),
+            (1.71, ComparisonOperator::LessThan, false),
+            (1.71, ComparisonOperator::LessThanOrEqual, true),
+            (1.7, ComparisonOperator::LessThanOrEqual, false),
+        ] {
+            let function = Function::HeaderVersion("Blank.esp".into(), comparator, version);
+
+            assert_eq!(expected, function.eval(&state).unwrap(), "{function}");
+        }
     }
 
     #[test]
-    fn function_version_eval_should_be_false_if_actual_version_is_gt_and_comparator_is_lt() {
-        let function =
-            Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::LessThan);
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "6")]);
+    fn function_header_version_eval_should_be_false_if_the_path_is_not_a_plugin() {
+        let mut state = state(".");
+        state.game_type = GameType::SkyrimSE;
 
-        assert!(!function.eval(&state).unwrap());
+        for path in ["Cargo.toml", "missing.esp"] {
+            let function = Function::HeaderVersion(path.into(), ComparisonOperator::NotEqual, 1.7);
+
+            assert!(!function.eval(&state).unwrap(), "{path}");
+        }
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_actual_version_is_lt_and_comparator_is_lt() {
-        let function =
-            Function::Version("Blank.esm".into(), "5".into(), ComparisonOperator::NotEqual);
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "1")]);
-
-        assert!(function.eval(&state).unwrap());
+    fn function_record_count_eval_should_compare_the_record_count_using_the_given_comparator() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        write_skyrim_plugin_header(&data_path.join("Blank.esp"), 1.7, 0, 5, &[]);
+        let mut state = state(data_path);
+        state.game_type = GameType::SkyrimSE;
+
+        for (count, comparator, expected) in [
+            (5, ComparisonOperator::Equal, true),
+            (4, ComparisonOperator::Equal, false),
+            (4, ComparisonOperator::NotEqual, true),
+            (4, ComparisonOperator::GreaterThan, true),
+            (5, ComparisonOperator::GreaterThan, false),
+            (5, ComparisonOperator::GreaterThanOrEqual, true),
+            (6, ComparisonOperator::LessThan, true),
+            (5, ComparisonOperator::LessThan, false),
+            (5, ComparisonOperator::LessThanOrEqual, true),
+            (4, ComparisonOperator::LessThanOrEqual, false),
+        ] {
+            let function = Function::RecordCount("Blank.esp".into(), comparator, count);
+
+            assert_eq!(expected, function.eval(&state).unwrap(), "{function}");
+        }
     }
 
     #[test]
-    fn function_version_eval_should_be_false_if_actual_version_is_eq_and_comparator_is_gt() {
-        let function = Function::Version(
-            "Blank.esm".into(),
-            "5".into(),
-            ComparisonOperator::GreaterThan,
+    fn function_record_count_eval_should_cache_the_record_count_of_each_plugin() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        let plugin_path = data_path.join("Blank.esp");
+        write_skyrim_plugin_header(&plugin_path, 1.7, 0, 0, &[]);
+        let mut state = state(data_path);
+        state.game_type = GameType::SkyrimSE;
+
+        let function = Function::RecordCount("Blank.esp".into(), ComparisonOperator::Equal, 0);
+        assert_eq!(
+            (true, ResultSource::FreshIo),
+            eval_source(&function, &state)
         );
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "5")]);
 
-        assert!(!function.eval(&state).unwrap());
+        remove_file(&plugin_path).unwrap();
+
+        let function =
+            Function::RecordCount("blank.ESP".into(), ComparisonOperator::GreaterThan, 0);
+        assert_eq!(
+            (false, ResultSource::RecordCountCache),
+            eval_source(&function, &state)
+        );
     }
 
     #[test]
-    fn function_version_eval_should_be_false_if_actual_version_is_lt_and_comparator_is_gt() {
-        let function = Function::Version(
-            "Blank.esm".into(),
-            "5".into(),
-            ComparisonOperator::GreaterThan,
+    fn plugin_functions_should_share_cached_plugin_headers() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        let plugin_path = data_path.join("Blank.esp");
+        write_skyrim_plugin_header(&plugin_path, 1.7, 1, 0, &["Skyrim.esm"]);
+        let mut state = state(data_path);
+        state.game_type = GameType::SkyrimSE;
+
+        assert_eq!(
+            (true, ResultSource::FreshIo),
+            eval_source(&Function::IsMaster("Blank.esp".into()), &state)
         );
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "4")]);
 
-        assert!(!function.eval(&state).unwrap());
+        remove_file(&plugin_path).unwrap();
+
+        let function = Function::MastersContain("BLANK.esp".into(), "skyrim.esm".into());
+        assert_eq!(
+            (true, ResultSource::PluginCache),
+            eval_source(&function, &state)
+        );
+        assert_eq!(
+            (true, ResultSource::PluginCache),
+            eval_source(&Function::IsValidPlugin("blank.esp".into()), &state)
+        );
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_actual_version_is_gt_and_comparator_is_gt() {
-        let function = Function::Version(
-            "Blank.esm".into(),
-            "5".into(),
-            ComparisonOperator::GreaterThan,
+    fn plugin_functions_should_cache_plugins_that_could_not_be_parsed() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        let mut state = state(data_path);
+        state.game_type = GameType::SkyrimSE;
+
+        let function = Function::IsMaster("Blank.esp".into());
+        assert_eq!(
+            (false, ResultSource::FreshIo),
+            eval_source(&function, &state)
         );
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "6")]);
 
-        assert!(function.eval(&state).unwrap());
+        write_skyrim_plugin_header(&data_path.join("Blank.esp"), 1.7, 1, 0, &[]);
+
+        let function = Function::IsValidPlugin("Blank.esp".into());
+        assert_eq!(
+            (false, ResultSource::PluginCache),
+            eval_source(&function, &state)
+        );
+
+        state.invalidate_missing_path("blank.esp");
+
+        assert_eq!(
+            (true, ResultSource::FreshIo),
+            eval_source(&function, &state)
+        );
     }
 
     #[test]
-    fn function_version_eval_should_be_false_if_actual_version_is_gt_and_comparator_is_lteq() {
-        let function = Function::Version(
-            "Blank.esm".into(),
-            "5".into(),
-            ComparisonOperator::LessThanOrEqual,
-        );
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "6")]);
+    fn function_record_count_eval_should_be_false_if_the_path_is_not_a_plugin() {
+        let mut state = state(".");
+        state.game_type = GameType::SkyrimSE;
 
-        assert!(!function.eval(&state).unwrap());
+        for path in ["Cargo.toml", "missing.esp"] {
+            let function = Function::RecordCount(path.into(), ComparisonOperator::NotEqual, 1);
+
+            assert!(!function.eval(&state).unwrap(), "{path}");
+        }
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_actual_version_is_eq_and_comparator_is_lteq() {
-        let function = Function::Version(
-            "Blank.esm".into(),
-            "5".into(),
-            ComparisonOperator::LessThanOrEqual,
-        );
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "5")]);
+    fn function_archive_contains_eval_should_check_for_the_path_in_a_bsa_or_ba2() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        std::fs::write(
+            data_path.join("Textures.bsa"),
+            bsa(105, &[("textures\\armor", &["iron.dds"])]),
+        )
+        .unwrap();
+        std::fs::write(
+            data_path.join("Meshes - Main.ba2"),
+            ba2(&["Meshes\\Armor\\Iron.nif"]),
+        )
+        .unwrap();
+        let state = state(data_path);
 
-        assert!(function.eval(&state).unwrap());
+        for (archive, entry, expected) in [
+            ("Textures.bsa", "textures/armor/iron.dds", true),
+            ("textures.BSA", "Textures\\Armor\\Iron.dds", true),
+            ("Textures.bsa", "textures/armor/steel.dds", false),
+            ("Meshes - Main.ba2", "meshes/armor/iron.nif", true),
+            ("Meshes - Main.ba2", "meshes/armor", false),
+        ] {
+            let function = Function::ArchiveContains(archive.into(), entry.into());
+
+            assert_eq!(expected, function.eval(&state).unwrap(), "{function}");
+        }
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_actual_version_is_lt_and_comparator_is_lteq() {
-        let function = Function::Version(
-            "Blank.esm".into(),
-            "5".into(),
-            ComparisonOperator::LessThanOrEqual,
-        );
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "4")]);
+    fn function_archive_regex_eval_should_match_the_whole_path_of_a_file_in_the_archive() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        std::fs::write(
+            data_path.join("Meshes - Main.ba2"),
+            ba2(&["Meshes\\Armor\\Iron.nif", "Textures\\Iron.dds"]),
+        )
+        .unwrap();
+        let state = state(data_path);
 
-        assert!(function.eval(&state).unwrap());
+        for (pattern, expected) in [
+            ("^meshes/armor/.*\\.nif$", true),
+            ("^MESHES/.*$", true),
+            ("^textures/.*\\.nif$", false),
+            ("^armor/.*$", false),
+        ] {
+            let function = Function::ArchiveRegex("Meshes - Main.ba2".into(), regex(pattern));
+
+            assert_eq!(expected, function.eval(&state).unwrap(), "{function}");
+        }
     }
 
     #[test]
-    fn function_version_eval_should_be_false_if_actual_version_is_lt_and_comparator_is_gteq() {
-        let function = Function::Version(
-            "Blank.esm".into(),
-            "5".into(),
-            ComparisonOperator::GreaterThanOrEqual,
+    fn function_archive_regex_eval_should_use_the_cached_index_of_the_archive() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        let archive_path = data_path.join("Main.ba2");
+        std::fs::write(&archive_path, ba2(&["meshes\\a.nif"])).unwrap();
+        let state = state(data_path);
+
+        let function = Function::ArchiveContains("Main.ba2".into(), "meshes/a.nif".into());
+        assert_eq!(
+            (true, ResultSource::FreshIo),
+            eval_source(&function, &state)
         );
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "4")]);
 
-        assert!(!function.eval(&state).unwrap());
+        remove_file(&archive_path).unwrap();
+
+        let function = Function::ArchiveRegex("Main.ba2".into(), regex("^meshes/.*$"));
+        assert_eq!(
+            (true, ResultSource::ArchiveIndexCache),
+            eval_source(&function, &state)
+        );
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_actual_version_is_eq_and_comparator_is_gteq() {
-        let function = Function::Version(
-            "Blank.esm".into(),
-            "5".into(),
-            ComparisonOperator::GreaterThanOrEqual,
-        );
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "5")]);
+    fn function_archive_version_eval_should_compare_the_archive_header_version() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        std::fs::write(data_path.join("Skyrim.bsa"), bsa(104, &[])).unwrap();
+        std::fs::write(data_path.join("Fallout4.ba2"), ba2(&[])).unwrap();
+        let state = state(data_path);
 
-        assert!(function.eval(&state).unwrap());
+        for (path, comparator, version, expected) in [
+            ("Skyrim.bsa", ComparisonOperator::Equal, 104, true),
+            ("Skyrim.bsa", ComparisonOperator::Equal, 105, false),
+            ("Skyrim.bsa", ComparisonOperator::LessThan, 105, true),
+            ("Fallout4.ba2", ComparisonOperator::Equal, 1, true),
+            (
+                "Fallout4.ba2",
+                ComparisonOperator::GreaterThanOrEqual,
+                2,
+                false,
+            ),
+        ] {
+            let function = Function::ArchiveVersion(path.into(), comparator, version);
+
+            assert_eq!(expected, function.eval(&state).unwrap(), "{function}");
+        }
     }
 
     #[test]
-    fn function_version_eval_should_be_true_if_actual_version_is_gt_and_comparator_is_gteq() {
-        let function = Function::Version(
-            "Blank.esm".into(),
-            "5".into(),
-            ComparisonOperator::GreaterThanOrEqual,
-        );
-        let state =
-            state_with_versions("tests/testing-plugins/Oblivion/Data", &[("Blank.esm", "6")]);
+    fn function_archive_version_eval_should_be_false_if_the_path_is_not_an_archive() {
+        let state = state(".");
 
-        assert!(function.eval(&state).unwrap());
+        for path in ["Cargo.toml", "missing.ba2"] {
+            let function = Function::ArchiveVersion(path.into(), ComparisonOperator::NotEqual, 1);
+
+            assert!(!function.eval(&state).unwrap(), "{path}");
+        }
     }
 
     #[test]
-    fn function_version_eval_should_read_executable_file_version() {
-        let function = Function::Version(
-            "loot.dll".into(),
-            "0.18.2.0".into(),
-            ComparisonOperator::Equal,
-        );
-        let state = state("tests/libloot_win32");
+    fn function_archive_contains_eval_should_be_false_if_the_path_is_not_an_archive() {
+        let state = state(".");
 
-        assert!(function.eval(&state).unwrap());
+        for path in ["Cargo.toml", "missing.bsa"] {
+            let function = Function::ArchiveContains(path.into(), "Cargo.toml".into());
+
+            assert!(!function.eval(&state).unwrap(), "{path}");
+        }
     }
 
     #[test]
-    fn function_product_version_eval_should_read_executable_product_version() {
-        let function = Function::ProductVersion(
-            "loot.dll".into(),
-            "0.18.2".into(),
-            ComparisonOperator::Equal,
+    fn function_archive_contains_eval_should_cache_the_index_of_each_archive() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        let archive_path = data_path.join("Textures.bsa");
+        std::fs::write(&archive_path, bsa(104, &[("textures", &["a.dds"])])).unwrap();
+        let state = state(data_path);
+
+        let function = Function::ArchiveContains("Textures.bsa".into(), "textures/a.dds".into());
+        assert_eq!(
+            (true, ResultSource::FreshIo),
+            eval_source(&function, &state)
         );
-        let state = state("tests/libloot_win32");
 
-        assert!(function.eval(&state).unwrap());
+        remove_file(&archive_path).unwrap();
+
+        let function = Function::ArchiveContains("textures.BSA".into(), "textures/b.dds".into());
+        assert_eq!(
+            (false, ResultSource::ArchiveIndexCache),
+            eval_source(&function, &state)
+        );
     }
 
     #[test]
-    fn get_product_version_should_return_ok_none_if_the_path_does_not_exist() {
-        assert!(get_product_version(Path::new("missing")).unwrap().is_none());
+    fn function_masters_contain_eval_should_check_the_plugins_masters_case_insensitively() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        write_skyrim_plugin_header(
+            &data_path.join("Child.esp"),
+            1.7,
+            0,
+            0,
+            &["Skyrim.esm", "Update.esm"],
+        );
+        let mut state = state(data_path);
+        state.game_type = GameType::SkyrimSE;
+
+        for (master, expected) in [
+            ("Skyrim.esm", true),
+            ("UPDATE.ESM", true),
+            ("Dawnguard.esm", false),
+            ("Skyrim", false),
+        ] {
+            let function = Function::MastersContain("Child.esp".into(), master.into());
+
+            assert_eq!(expected, function.eval(&state).unwrap(), "{master}");
+        }
     }
 
     #[test]
-    fn get_product_version_should_return_ok_none_if_the_path_is_not_a_file() {
-        assert!(get_product_version(Path::new("tests")).unwrap().is_none());
+    fn function_masters_contain_eval_should_be_false_if_the_path_is_not_a_plugin() {
+        let mut state = state(".");
+        state.game_type = GameType::SkyrimSE;
+
+        for path in ["Cargo.toml", "missing.esp"] {
+            let function = Function::MastersContain(path.into(), "Skyrim.esm".into());
+
+            assert!(!function.eval(&state).unwrap(), "{path}");
+        }
     }
 
     #[test]
-    fn get_product_version_should_return_ok_some_if_the_path_is_an_executable() {
-        let version = get_product_version(Path::new("tests/libloot_win32/loot.dll"))
-            .unwrap()
-            .unwrap();
+    fn function_is_valid_plugin_eval_should_be_true_if_the_path_is_a_plugin_for_the_game() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        write_oblivion_plugin(&data_path.join("Blank.esp"), "");
+        let state = state(data_path);
 
-        assert_eq!(Version::from("0.18.2"), version);
+        assert!(Function::IsValidPlugin("Blank.esp".into())
+            .eval(&state)
+            .unwrap());
     }
 
     #[test]
-    fn get_product_version_should_error_if_the_path_is_not_an_executable() {
-        assert!(get_product_version(Path::new("Cargo.toml")).is_err());
+    fn function_is_valid_plugin_eval_should_be_false_if_the_path_is_not_a_valid_plugin() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        write_oblivion_plugin(&data_path.join("Blank.txt"), "");
+        copy("Cargo.toml", data_path.join("Cargo.esp")).unwrap();
+        let state = state(data_path);
+
+        let eval = |path: &str| Function::IsValidPlugin(path.into()).eval(&state).unwrap();
+
+        assert!(!eval("Blank.txt"));
+        assert!(!eval("Cargo.esp"));
+        assert!(!eval("missing.esp"));
     }
 
     #[test]
-    fn function_filename_version_eval_should_be_false_if_no_matching_filenames_exist() {
-        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
+    fn function_is_valid_plugin_eval_should_be_false_for_a_skyrim_se_plugin_in_skyrim() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        write_skyrim_plugin_header(&data_path.join("LE.esp"), 0.94, 0, 0, &[]);
+        write_skyrim_plugin_header(&data_path.join("SE.esp"), 1.7, 0, 0, &[]);
+        let mut state = state(data_path);
+
+        let eval =
+            |state: &State, path: &str| Function::IsValidPlugin(path.into()).eval(state).unwrap();
+
+        state.game_type = GameType::Skyrim;
+        assert!(eval(&state, "LE.esp"));
+        assert!(!eval(&state, "SE.esp"));
+
+        state.game_type = GameType::SkyrimSE;
+        state.clear_condition_cache().unwrap();
+        assert!(eval(&state, "LE.esp"));
+        assert!(eval(&state, "SE.esp"));
+    }
 
-        let function = Function::FilenameVersion(
-            "".into(),
-            regex("Blank (A+).esm"),
-            "5".into(),
-            ComparisonOperator::Equal,
+    #[test]
+    fn function_description_contains_text_eval_should_match_a_case_insensitive_substring() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        write_oblivion_plugin(
+            &data_path.join("Blank.esp"),
+            "A plugin [Requires Patch] (*)",
         );
+        let state = state(data_path);
 
-        assert!(!function.eval(&state).unwrap());
+        let eval = |text: &str| {
+            Function::DescriptionContainsText("Blank.esp".into(), text.into())
+                .eval(&state)
+                .unwrap()
+        };
 
-        let function = Function::FilenameVersion(
-            "".into(),
-            regex("Blank (A+).esm"),
-            "5".into(),
-            ComparisonOperator::NotEqual,
-        );
+        assert!(eval("[requires patch] (*)"));
+        assert!(!eval("[Requires Other]"));
+    }
 
-        assert!(!function.eval(&state).unwrap());
+    #[test]
+    fn function_description_regex_eval_should_check_the_descriptions_of_plugins_matching_the_regex()
+    {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        create_dir_all(data_path.join("Patches")).unwrap();
+        write_oblivion_plugin(&data_path.join("Patches/A.esp"), "Requires nothing");
+        write_oblivion_plugin(&data_path.join("Patches/B.esp.ghost"), "Requires Cobl");
+        write_oblivion_plugin(&data_path.join("Patches/C.txt"), "Requires OOO");
+        let state = state(data_path);
 
-        let function = Function::FilenameVersion(
-            "".into(),
-            regex("Blank (A+).esm"),
-            "5".into(),
-            ComparisonOperator::LessThan,
-        );
+        let eval = |description: &str| {
+            Function::DescriptionRegex("Patches".into(), regex(".*\\.esp"), regex(description))
+                .eval(&state)
+                .unwrap()
+        };
 
-        assert!(!function.eval(&state).unwrap());
+        assert!(eval("requires nothing"));
+        assert!(eval("requires cobl"));
+        assert!(!eval("requires ooo"));
+    }
 
-        let function = Function::FilenameVersion(
-            "".into(),
-            regex("Blank (A+).esm"),
-            "5".into(),
-            ComparisonOperator::GreaterThan,
-        );
+    #[test]
+    fn function_description_regex_eval_should_be_false_if_the_directory_does_not_exist() {
+        let function = Function::DescriptionRegex("missing".into(), regex(".*"), regex(".*"));
+        let state = state(".");
 
         assert!(!function.eval(&state).unwrap());
+    }
 
-        let function = Function::FilenameVersion(
-            "".into(),
-            regex("Blank (A+).esm"),
-            "5".into(),
-            ComparisonOperator::LessThanOrEqual,
+    #[test]
+    fn function_author_contains_eval_should_match_the_plugin_author() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        write_oblivion_plugin_with_author(
+            &data_path.join("Blank.esp"),
+            "A description",
+            "Original Author",
         );
+        write_oblivion_plugin(&data_path.join("NoAuthor.esp"), "A description");
+        let state = state(data_path);
 
-        assert!(!function.eval(&state).unwrap());
-
-        let function = Function::FilenameVersion(
-            "".into(),
-            regex("Blank (A+).esm"),
-            "5".into(),
-            ComparisonOperator::GreaterThanOrEqual,
-        );
+        let eval = |path: &str, author: &str| {
+            Function::AuthorContains(path.into(), regex(author))
+                .eval(&state)
+                .unwrap()
+        };
 
-        assert!(!function.eval(&state).unwrap());
+        assert!(eval("Blank.esp", "original"));
+        assert!(!eval("Blank.esp", "description"));
+        assert!(!eval("NoAuthor.esp", ".*"));
+        assert!(!eval("missing.esp", ".*"));
     }
 
     #[test]
-    fn function_filename_version_eval_should_be_false_if_filenames_matched_but_no_version_was_captured(
-    ) {
-        // This shouldn't happen in practice because parsing validates that there is one explicit capturing group in the regex.
-        let function = Function::FilenameVersion(
-            "".into(),
-            regex("Blank .+.esm"),
-            "5".into(),
-            ComparisonOperator::GreaterThanOrEqual,
-        );
-        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
+    fn function_description_contains_text_eval_should_be_false_if_the_plugin_does_not_exist() {
+        let function = Function::DescriptionContainsText("missing.esp".into(), "text".into());
+        let state = state(".");
 
         assert!(!function.eval(&state).unwrap());
     }
 
+    fn create_file_modified_at(path: &Path, seconds_since_epoch: u64) {
+        let file = File::create(path).unwrap();
+        file.set_modified(
+            std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds_since_epoch),
+        )
+        .unwrap();
+    }
+
     #[test]
-    fn function_filename_version_eval_should_be_true_if_the_captured_version_is_eq_and_operator_is_eq(
-    ) {
+    fn function_file_mtime_eval_should_compare_the_modification_time_using_the_given_comparator() {
         let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
+        let data_path = tmp_dir.path();
+        create_file_modified_at(&data_path.join("Bashed Patch, 0.esp"), 1_000_000);
         let state = state(data_path);
 
-        copy(
-            Path::new("tests/testing-plugins/Oblivion/Data/Blank.esm"),
-            state.data_path.join("Blank 5.esm"),
-        )
-        .unwrap();
+        for (timestamp, comparator, expected) in [
+            ("1970-01-12T13:46:40Z", ComparisonOperator::Equal, true),
+            ("1970-01-12T14:46:40+01:00", ComparisonOperator::Equal, true),
+            ("1970-01-12T13:46:41Z", ComparisonOperator::Equal, false),
+            ("1970-01-12T13:46:41Z", ComparisonOperator::NotEqual, true),
+            ("1970-01-12T13:46:41Z", ComparisonOperator::LessThan, true),
+            ("1970-01-12T13:46:40Z", ComparisonOperator::LessThan, false),
+            ("1970-01-12", ComparisonOperator::GreaterThan, true),
+            (
+                "1970-01-12T13:46:40Z",
+                ComparisonOperator::GreaterThan,
+                false,
+            ),
+            (
+                "1970-01-12T13:46:40Z",
+                ComparisonOperator::LessThanOrEqual,
+                true,
+            ),
+            (
+                "1970-01-12T13:46:39Z",
+                ComparisonOperator::LessThanOrEqual,
+                false,
+            ),
+            (
+                "1970-01-12T13:46:40Z",
+                ComparisonOperator::GreaterThanOrEqual,
+                true,
+            ),
+            ("1970-01-13", ComparisonOperator::GreaterThanOrEqual, false),
+        ] {
+            let function = Function::FileModifiedTime(
+                "Bashed Patch, 0.esp".into(),
+                comparator,
+                Timestamp::parse(timestamp).unwrap(),
+            );
+
+            assert_eq!(expected, function.eval(&state).unwrap(), "{function}");
+        }
+    }
 
-        let function = Function::FilenameVersion(
-            "".into(),
-            regex("Blank (\\d).esm"),
-            "5".into(),
+    #[test]
+    fn function_file_mtime_eval_should_ignore_fractions_of_a_second() {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("Bashed Patch, 0.esp");
+        File::create(&path)
+            .unwrap()
+            .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_millis(1_000_000_999))
+            .unwrap();
+        let state = state(tmp_dir.path());
+
+        let function = Function::FileModifiedTime(
+            "Bashed Patch, 0.esp".into(),
             ComparisonOperator::Equal,
+            Timestamp::parse("1970-01-12T13:46:40Z").unwrap(),
         );
 
         assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_filename_version_eval_should_be_true_if_the_captured_version_is_not_equal_and_operator_is_not_equal(
-    ) {
+    fn function_file_mtime_eval_should_be_false_if_the_file_does_not_exist() {
+        let state = state(".");
+
+        for comparator in [ComparisonOperator::NotEqual, ComparisonOperator::LessThan] {
+            let function = Function::FileModifiedTime(
+                "missing.esp".into(),
+                comparator,
+                Timestamp::parse("2024-01-01").unwrap(),
+            );
+
+            assert!(!function.eval(&state).unwrap());
+        }
+    }
+
+    #[test]
+    fn function_is_newer_than_eval_should_compare_modification_times() {
         let tmp_dir = tempdir().unwrap();
-        let data_path = tmp_dir.path().join("Data");
+        let data_path = tmp_dir.path();
+        create_file_modified_at(&data_path.join("Patch.esp"), 2_000_000);
+        create_file_modified_at(&data_path.join("Blank.esp"), 1_000_000);
         let state = state(data_path);
 
-        copy(
-            Path::new("tests/testing-plugins/Oblivion/Data/Blank.esm"),
-            state.data_path.join("Blank 4.esm"),
-        )
-        .unwrap();
+        let function = Function::IsNewerThan("Patch.esp".into(), "Blank.esp".into());
+        assert!(function.eval(&state).unwrap());
 
-        let function = Function::FilenameVersion(
-            "".into(),
-            regex("Blank (\\d).esm"),
-            "5".into(),
-            ComparisonOperator::NotEqual,
-        );
+        let function = Function::IsNewerThan("Blank.esp".into(), "Patch.esp".into());
+        assert!(!function.eval(&state).unwrap());
 
-        assert!(function.eval(&state).unwrap());
+        let function = Function::IsNewerThan("Blank.esp".into(), "Blank.esp".into());
+        assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_description_contains_eval_should_return_false_if_the_file_does_not_exist() {
-        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
+    fn function_is_newer_than_eval_should_be_false_if_either_file_does_not_exist() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        create_file_modified_at(&data_path.join("Patch.esp"), 2_000_000);
+        let state = state(data_path);
 
-        let function =
-            Function::DescriptionContains("missing.esp".into(), regex(LOWERCASE_NON_ASCII));
+        let function = Function::IsNewerThan("Patch.esp".into(), "missing.esp".into());
+        assert!(!function.eval(&state).unwrap());
 
+        let function = Function::IsNewerThan("missing.esp".into(), "Patch.esp".into());
         assert!(!function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_description_contains_eval_should_return_false_if_the_file_is_not_a_plugin() {
-        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
+    fn function_is_newer_than_eval_should_check_both_paths_against_the_traversal_allowlist() {
+        let mut state = state(".");
+        state.set_path_traversal_allowlist(Some(Vec::new()));
+
+        let function = Function::IsNewerThan("Cargo.toml".into(), "../Cargo.toml".into());
+        assert!(matches!(
+            function.eval(&state),
+            Err(Error::PathTraversal(_))
+        ));
+    }
+    #[test]
+    fn parse_checksum_manifest_should_skip_comments_and_blank_lines() {
+        let content = "; generated\n\nBlank.esp 374E2A6F\r\nsub dir\\Other File.esp\tdeadbeef\n";
 
-        let function =
-            Function::DescriptionContains("Blank.bsa".into(), regex(LOWERCASE_NON_ASCII));
+        assert_eq!(
+            Some(vec![
+                (PathBuf::from("Blank.esp"), 0x374E_2A6F),
+                (PathBuf::from("sub dir/Other File.esp"), 0xDEAD_BEEF),
+            ]),
+            parse_checksum_manifest(content)
+        );
+    }
 
-        assert!(!function.eval(&state).unwrap());
+    #[test]
+    fn parse_checksum_manifest_should_return_none_if_a_line_is_malformed() {
+        assert_eq!(None, parse_checksum_manifest("Blank.esp\n"));
+        assert_eq!(None, parse_checksum_manifest("Blank.esp nothex\n"));
     }
 
     #[test]
-    fn function_description_contains_eval_should_return_false_if_the_plugin_has_no_description() {
-        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
+    fn function_checksum_manifest_eval_should_check_files_relative_to_the_manifest() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        create_dir_all(data_path.join("mod/sub")).unwrap();
+        std::fs::write(data_path.join("mod/sub/file.txt"), "abc").unwrap();
+        std::fs::write(
+            data_path.join("mod/checksums.sfv"),
+            "; abc\nsub\\file.txt 352441C2\n",
+        )
+        .unwrap();
+        std::fs::write(data_path.join("mod/bad.sfv"), "sub/file.txt DEADBEEF\n").unwrap();
+        std::fs::write(data_path.join("mod/missing.sfv"), "missing.txt 352441C2\n").unwrap();
+        std::fs::write(data_path.join("mod/empty.sfv"), "; nothing\n").unwrap();
+        let state = state(data_path);
 
-        let function = Function::DescriptionContains(
-            "Blank - Different.esm".into(),
-            regex(LOWERCASE_NON_ASCII),
-        );
+        let eval = |manifest: &str| {
+            Function::ChecksumManifest(manifest.into())
+                .eval(&state)
+                .unwrap()
+        };
 
-        assert!(!function.eval(&state).unwrap());
+        assert!(eval("mod/checksums.sfv"));
+        assert!(!eval("mod/bad.sfv"));
+        assert!(!eval("mod/missing.sfv"));
+        assert!(!eval("mod/empty.sfv"));
+        assert!(!eval("mod/absent.sfv"));
     }
 
     #[test]
-    fn function_description_contains_eval_should_return_false_if_the_plugin_description_does_not_match(
-    ) {
-        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
+    fn function_checksum_manifest_eval_should_check_listed_paths_against_the_traversal_allowlist() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        std::fs::write(data_path.join("checksums.sfv"), "../file.txt 352441C2\n").unwrap();
+        let mut state = state(data_path);
+        state.set_path_traversal_allowlist(Some(Vec::new()));
+
+        let function = Function::ChecksumManifest("checksums.sfv".into());
+        assert!(matches!(
+            function.eval(&state),
+            Err(Error::PathTraversal(_))
+        ));
+    }
 
-        let function =
-            Function::DescriptionContains("Blank.esm".into(), regex(LOWERCASE_NON_ASCII));
+    #[test]
+    fn function_checksum_manifest_eval_should_be_false_if_the_manifest_is_too_large() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        std::fs::write(data_path.join("file.txt"), "abc").unwrap();
+        let mut contents = b"file.txt 352441C2\n".to_vec();
+        contents.resize(usize::try_from(MAX_FILE_CONTENT_SIZE).unwrap() + 1, b'\n');
+        std::fs::write(data_path.join("checksums.sfv"), &contents).unwrap();
+        let mut state = state(data_path);
+
+        let function = Function::ChecksumManifest("checksums.sfv".into());
 
         assert!(!function.eval(&state).unwrap());
+
+        contents.pop();
+        std::fs::write(data_path.join("checksums.sfv"), &contents).unwrap();
+        state.clear_condition_cache().unwrap();
+
+        assert!(function.eval(&state).unwrap());
     }
 
     #[test]
-    fn function_description_contains_eval_should_return_true_if_the_plugin_description_contains_a_match(
-    ) {
-        let state = state_with_versions("tests/testing-plugins/Oblivion/Data", &[]);
+    fn function_version_cmp_eval_should_compare_the_versions_of_two_files() {
+        use self::ComparisonOperator::*;
 
-        let function =
-            Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII));
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        File::create(data_path.join("Blank.esm")).unwrap();
+        File::create(data_path.join("Blank.esp")).unwrap();
+        let state = state_with_versions(data_path, &[("Blank.esm", "1.2"), ("Blank.esp", "1.10")]);
+
+        let eval = |comparator| {
+            Function::VersionCmp("Blank.esm".into(), "Blank.esp".into(), comparator)
+                .eval(&state)
+                .unwrap()
+        };
 
-        assert!(function.eval(&state).unwrap());
+        assert!(eval(LessThan));
+        assert!(eval(LessThanOrEqual));
+        assert!(eval(NotEqual));
+        assert!(!eval(Equal));
+        assert!(!eval(GreaterThan));
+        assert!(!eval(GreaterThanOrEqual));
+    }
+
+    #[test]
+    fn function_version_cmp_eval_should_be_false_if_either_version_is_missing() {
+        let tmp_dir = tempdir().unwrap();
+        let data_path = tmp_dir.path();
+        File::create(data_path.join("Blank.esm")).unwrap();
+        let state = state_with_versions(data_path, &[("Blank.esm", "1.2")]);
+
+        let function = Function::VersionCmp(
+            "Blank.esm".into(),
+            "missing.esp".into(),
+            ComparisonOperator::NotEqual,
+        );
+        assert!(!function.eval(&state).unwrap());
+
+        let function = Function::VersionCmp(
+            "missing.esp".into(),
+            "Blank.esm".into(),
+            ComparisonOperator::NotEqual,
+        );
+        assert!(!function.eval(&state).unwrap());
     }
 }
@@ -0,0 +1,139 @@
+/// Check if the string could be a section name that [find_value] matches.
+/// Section names are trimmed when read, and end at the first `]`.
+pub(crate) fn is_valid_section(section: &str) -> bool {
+    is_valid_value(section) && !section.contains(']')
+}
+
+/// Check if the string could be a key that [find_value] matches. Keys are
+/// trimmed when read, end at the first `=`, and lines that start with `;` or
+/// `#` are comments.
+pub(crate) fn is_valid_key(key: &str) -> bool {
+    is_valid_value(key) && !key.contains('=') && !key.starts_with([';', '#', '['])
+}
+
+/// Check if the string could be a value that [find_value] returns, i.e. it
+/// is all on one line and has no leading or trailing whitespace.
+pub(crate) fn is_valid_value(value: &str) -> bool {
+    value.trim() == value && !value.contains(['\r', '\n'])
+}
+
+/// Find the value of a key in a section of the given INI file contents.
+///
+/// Section names and keys are compared case-insensitively. Whitespace around
+/// names, keys and values is ignored, as is one pair of double quotes around
+/// a value. Keys that appear before the first section header are not in any
+/// section, and if a key appears more than once in a section, its first value
+/// is used, as Windows' own INI functions do.
+#[cfg(feature = "eval")]
+pub(crate) fn find_value<'a>(contents: &'a str, section: &str, key: &str) -> Option<&'a str> {
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    let mut in_section = false;
+
+    for line in contents.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('[') {
+            in_section = header
+                .split_once(']')
+                .is_some_and(|(name, _)| unicase::eq(name.trim(), section));
+        } else if in_section && !line.starts_with([';', '#']) {
+            if let Some((line_key, value)) = line.split_once('=') {
+                if unicase::eq(line_key.trim(), key) {
+                    return Some(unquote(value.trim()));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(feature = "eval")]
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_valid_section_should_reject_padding_line_breaks_and_closing_brackets() {
+        assert!(is_valid_section("General"));
+        assert!(is_valid_section("Display Settings"));
+
+        assert!(!is_valid_section(" General"));
+        assert!(!is_valid_section("General\n"));
+        assert!(!is_valid_section("Gen]eral"));
+    }
+
+    #[test]
+    fn is_valid_key_should_reject_padding_equals_signs_and_comment_characters() {
+        assert!(is_valid_key("bEnabled"));
+        assert!(is_valid_key("b;Enabled"));
+
+        assert!(!is_valid_key("bEnabled "));
+        assert!(!is_valid_key("b=Enabled"));
+        assert!(!is_valid_key(";bEnabled"));
+        assert!(!is_valid_key("#bEnabled"));
+        assert!(!is_valid_key("[bEnabled"));
+    }
+
+    #[test]
+    fn is_valid_value_should_reject_padding_and_line_breaks() {
+        assert!(is_valid_value("1"));
+        assert!(is_valid_value("a = b"));
+
+        assert!(!is_valid_value(" 1"));
+        assert!(!is_valid_value("1\r\n2"));
+    }
+
+    #[cfg(feature = "eval")]
+    mod find_value {
+        use super::*;
+
+        const CONTENTS: &str = "\u{feff}; A comment\r
+bOutside=1\r
+[General]\r
+  bEnabled = 1  \r
+sName=\"Quoted Name\"\r
+#bCommented=1\r
+bEnabled=0\r
+\r
+[ Display ]\r
+iSize=1024\r
+[General]\r
+sLater=yes\r
+";
+
+        #[test]
+        fn should_find_a_key_in_a_section_case_insensitively() {
+            assert_eq!(Some("1"), find_value(CONTENTS, "General", "bEnabled"));
+            assert_eq!(Some("1"), find_value(CONTENTS, "general", "BENABLED"));
+            assert_eq!(Some("1024"), find_value(CONTENTS, "Display", "iSize"));
+        }
+
+        #[test]
+        fn should_strip_quotes_from_values() {
+            assert_eq!(
+                Some("Quoted Name"),
+                find_value(CONTENTS, "General", "sName")
+            );
+        }
+
+        #[test]
+        fn should_read_sections_that_appear_more_than_once() {
+            assert_eq!(Some("yes"), find_value(CONTENTS, "General", "sLater"));
+        }
+
+        #[test]
+        fn should_not_find_keys_in_other_sections_or_comments() {
+            assert_eq!(None, find_value(CONTENTS, "Display", "bEnabled"));
+            assert_eq!(None, find_value(CONTENTS, "General", "iSize"));
+            assert_eq!(None, find_value(CONTENTS, "General", "bCommented"));
+            assert_eq!(None, find_value(CONTENTS, "General", "bOutside"));
+            assert_eq!(None, find_value(CONTENTS, "Missing", "bEnabled"));
+        }
+    }
+}
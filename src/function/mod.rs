@@ -1,16 +1,44 @@
-use std::fmt;
+use std::borrow::Cow;
+use std::fmt::{self, Write};
 use std::hash::{Hash, Hasher};
 use std::mem::discriminant;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use regex::Regex;
+use crate::ast::ReferencedPath;
+#[cfg(feature = "regex")]
+pub(crate) use regex::Regex;
+use timestamp::Timestamp;
 use unicase::eq;
-
+#[cfg(not(feature = "regex"))]
+pub(crate) use wildcard::WildcardPattern as Regex;
+
+#[cfg(feature = "eval")]
+mod architecture;
+#[cfg(feature = "eval")]
+pub(crate) mod archive;
+#[cfg(feature = "eval")]
 pub(crate) mod eval;
+mod ini;
 pub(crate) mod parse;
-mod path;
-mod version;
-
+#[cfg(feature = "eval")]
+pub(crate) mod path;
+#[cfg(feature = "eval")]
+mod plugin_header;
+mod registry;
+mod timestamp;
+pub(crate) mod version;
+#[cfg(feature = "eval")]
+pub(crate) mod virtual_files;
+#[cfg(feature = "eval")]
+mod wine;
+// Patterns are only matched against strings when evaluating.
+#[cfg(any(not(feature = "regex"), test))]
+#[cfg_attr(not(any(feature = "eval", test)), expect(dead_code))]
+mod wildcard;
+
+// Functions and the types that they hold are only reachable from outside the
+// crate through the error type of State::clear_condition_cache.
+#[cfg_attr(not(feature = "eval"), expect(unreachable_pub))]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum ComparisonOperator {
     Equal,
@@ -34,83 +62,458 @@ impl fmt::Display for ComparisonOperator {
     }
 }
 
+/// An instruction set architecture that an executable can be built for.
+#[cfg_attr(not(feature = "eval"), expect(unreachable_pub))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Architecture {
+    X86,
+    X64,
+}
+
+impl fmt::Display for Architecture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::X86 => write!(f, "x86"),
+            Self::X64 => write!(f, "x64"),
+        }
+    }
+}
+
+/// An operating system that conditions can check for.
+#[cfg_attr(not(feature = "eval"), expect(unreachable_pub))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Platform {
+    Windows,
+    Linux,
+    MacOs,
+}
+
+impl Platform {
+    /// Check if the crate was built for the platform.
+    pub(crate) const fn is_current(self) -> bool {
+        match self {
+            Self::Windows => cfg!(windows),
+            Self::Linux => cfg!(target_os = "linux"),
+            Self::MacOs => cfg!(target_os = "macos"),
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Windows => write!(f, "windows"),
+            Self::Linux => write!(f, "linux"),
+            Self::MacOs => write!(f, "macos"),
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "eval"), expect(unreachable_pub))]
 #[derive(Clone, Debug)]
 pub enum Function {
     FilePath(PathBuf),
     FileRegex(PathBuf, Regex),
-    FileSize(PathBuf, u64),
+    /// The string is the glob that the regex was built from.
+    FileGlob(PathBuf, String, Regex),
+    FileSize(PathBuf, u64, ComparisonOperator),
+    FileModifiedTime(PathBuf, ComparisonOperator, Timestamp),
     Readable(PathBuf),
+    Writable(PathBuf),
+    /// The strings are the registry key path, including its root key, and
+    /// the name of the value to match against the regex.
+    RegistryValue(String, String, Regex),
+    IsPlatform(Platform),
+    IsWine,
     IsExecutable(PathBuf),
+    IsArchitecture(PathBuf, Architecture),
     ActivePath(PathBuf),
     ActiveRegex(Regex),
     IsMaster(PathBuf),
+    IsLightPlugin(PathBuf),
+    IsMediumPlugin(PathBuf),
+    HeaderVersion(PathBuf, ComparisonOperator, f32),
+    RecordCount(PathBuf, ComparisonOperator, u64),
+    /// The string is the filename of the master to look for.
+    MastersContain(PathBuf, String),
     Many(PathBuf, Regex),
+    /// The number is how many levels of subdirectories below the path are
+    /// also searched for entries matching the regex.
+    RecursiveFileRegex(PathBuf, Regex, u32),
+    RecursiveMany(PathBuf, Regex, u32),
+    /// The number of directory entries matching the regex is compared
+    /// against the given count.
+    Count(PathBuf, Regex, ComparisonOperator, u64),
     ManyActive(Regex),
-    Checksum(PathBuf, u32),
+    ActiveCount(Regex, ComparisonOperator, u64),
+    /// The CRCs are all the accepted CRCs of the file.
+    Checksum(PathBuf, Vec<u32>),
+    ChecksumSha256(PathBuf, [u8; 32]),
     Version(PathBuf, String, ComparisonOperator),
     ProductVersion(PathBuf, String, ComparisonOperator),
     FilenameVersion(PathBuf, Regex, String, ComparisonOperator),
     DescriptionContains(PathBuf, Regex),
+    /// The first regex is matched against plugin filenames in the directory,
+    /// and the second against the descriptions of matching plugins.
+    DescriptionRegex(PathBuf, Regex, Regex),
+    AuthorContains(PathBuf, Regex),
+    IsNewerThan(PathBuf, PathBuf),
+    /// The strings are the inclusive lower and upper bounds of the range.
+    VersionInRange(PathBuf, String, String),
+    ProductVersionInRange(PathBuf, String, String),
+    ChecksumManifest(PathBuf),
+    VersionCmp(PathBuf, PathBuf, ComparisonOperator),
+    DescriptionContainsText(PathBuf, String),
+    IsValidPlugin(PathBuf),
+    FileContentContains(PathBuf, Regex),
+    /// The strings are the section name, key and expected value.
+    IniValue(PathBuf, String, String, String),
+    /// The string is the path of a file in the archive.
+    ArchiveContains(PathBuf, String),
+    /// The regex is matched against the paths of the files in the archive,
+    /// which use forward slashes as separators.
+    ArchiveRegex(PathBuf, Regex),
+    ArchiveVersion(PathBuf, ComparisonOperator, u64),
 }
 
 impl fmt::Display for Function {
+    #[expect(clippy::too_many_lines)]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::FilePath(p) => write!(f, "file(\"{}\")", p.display()),
-            Self::FileRegex(p, r) => write!(f, "file(\"{}/{}\")", p.display(), r),
-            Self::FileSize(p, s) => write!(f, "file_size(\"{}\", {})", p.display(), s),
-            Self::Readable(p) => write!(f, "readable(\"{}\")", p.display()),
-            Self::IsExecutable(p) => write!(f, "is_executable(\"{}\")", p.display()),
-            Self::ActivePath(p) => write!(f, "active(\"{}\")", p.display()),
-            Self::ActiveRegex(r) => write!(f, "active(\"{r}\")"),
-            Self::IsMaster(p) => write!(f, "is_master(\"{}\")", p.display()),
-            Self::Many(p, r) => write!(f, "many(\"{}/{}\")", p.display(), r),
-            Self::ManyActive(r) => write!(f, "many_active(\"{r}\")"),
-            Self::Checksum(p, c) => write!(f, "checksum(\"{}\", {:02X})", p.display(), c),
-            Self::Version(p, v, c) => write!(f, "version(\"{}\", \"{}\", {})", p.display(), v, c),
+            Self::FilePath(p) => write!(f, "file(\"{}\")", Escaped(p.display())),
+            Self::FileRegex(p, r) => write!(f, "file(\"{}\")", RegexPath(p, r)),
+            Self::FileGlob(p, g, _) => {
+                write!(f, "file_glob(\"{}\", \"{}\")", Escaped(p.display()), g)
+            }
+            Self::FileSize(p, s, ComparisonOperator::Equal) => {
+                write!(f, "file_size(\"{}\", {})", Escaped(p.display()), s)
+            }
+            Self::FileSize(p, s, c) => {
+                write!(f, "file_size(\"{}\", {}, {})", Escaped(p.display()), s, c)
+            }
+            Self::FileModifiedTime(p, c, t) => {
+                write!(
+                    f,
+                    "file_mtime(\"{}\", {}, \"{}\")",
+                    Escaped(p.display()),
+                    c,
+                    t
+                )
+            }
+            Self::Readable(p) => write!(f, "readable(\"{}\")", Escaped(p.display())),
+            Self::Writable(p) => write!(f, "writable(\"{}\")", Escaped(p.display())),
+            Self::RegistryValue(key, name, regex) => {
+                write!(
+                    f,
+                    "registry_value(\"{}\", \"{}\", \"{regex}\")",
+                    Escaped(key),
+                    Escaped(name)
+                )
+            }
+            Self::IsPlatform(platform) => write!(f, "is_{platform}()"),
+            Self::IsWine => write!(f, "is_wine()"),
+            Self::IsExecutable(p) => write!(f, "is_executable(\"{}\")", Escaped(p.display())),
+            Self::IsArchitecture(p, a) => write!(f, "is_{}(\"{}\")", a, Escaped(p.display())),
+            Self::ActivePath(p) => write!(f, "active(\"{}\")", Escaped(p.display())),
+            Self::ActiveRegex(r) => write!(f, "active(\"{}\")", unanchored(r)),
+            Self::IsMaster(p) => write!(f, "is_master(\"{}\")", Escaped(p.display())),
+            Self::IsLightPlugin(p) => write!(f, "is_light_plugin(\"{}\")", Escaped(p.display())),
+            Self::IsMediumPlugin(p) => write!(f, "is_medium_plugin(\"{}\")", Escaped(p.display())),
+            Self::HeaderVersion(p, c, v) => {
+                write!(
+                    f,
+                    "header_version(\"{}\", {}, {})",
+                    Escaped(p.display()),
+                    c,
+                    v
+                )
+            }
+            Self::RecordCount(p, c, n) => {
+                write!(
+                    f,
+                    "record_count(\"{}\", {}, {})",
+                    Escaped(p.display()),
+                    c,
+                    n
+                )
+            }
+            Self::MastersContain(p, m) => {
+                write!(
+                    f,
+                    "masters_contain(\"{}\", \"{}\")",
+                    Escaped(p.display()),
+                    Escaped(m)
+                )
+            }
+            Self::IsValidPlugin(p) => write!(f, "is_valid_plugin(\"{}\")", Escaped(p.display())),
+            Self::ChecksumManifest(p) => {
+                write!(f, "checksum_manifest(\"{}\")", Escaped(p.display()))
+            }
+            Self::Many(p, r) => write!(f, "many(\"{}\")", RegexPath(p, r)),
+            Self::RecursiveFileRegex(p, r, d) => {
+                write!(f, "file(\"{}\", {})", RegexPath(p, r), d)
+            }
+            Self::RecursiveMany(p, r, d) => {
+                write!(f, "many(\"{}\", {})", RegexPath(p, r), d)
+            }
+            Self::Count(path, regex, comparator, count) => {
+                write!(
+                    f,
+                    "count(\"{}\", {}, {})",
+                    RegexPath(path, regex),
+                    comparator,
+                    count
+                )
+            }
+            Self::ManyActive(r) => write!(f, "many_active(\"{}\")", unanchored(r)),
+            Self::ActiveCount(r, c, n) => {
+                write!(f, "active_count(\"{}\", {}, {})", unanchored(r), c, n)
+            }
+            Self::Checksum(p, crcs) => {
+                write!(f, "checksum(\"{}\"", Escaped(p.display()))?;
+                for crc in crcs {
+                    write!(f, ", {crc:02X}")?;
+                }
+                write!(f, ")")
+            }
+            Self::ChecksumSha256(p, d) => {
+                write!(f, "checksum_sha256(\"{}\", \"", Escaped(p.display()))?;
+                for byte in d {
+                    write!(f, "{byte:02x}")?;
+                }
+                write!(f, "\")")
+            }
+            Self::Version(p, v, c) => write!(
+                f,
+                "version(\"{}\", \"{}\", {})",
+                Escaped(p.display()),
+                Escaped(v),
+                c
+            ),
             Self::ProductVersion(p, v, c) => {
-                write!(f, "product_version(\"{}\", \"{}\", {})", p.display(), v, c)
+                write!(
+                    f,
+                    "product_version(\"{}\", \"{}\", {})",
+                    Escaped(p.display()),
+                    Escaped(v),
+                    c
+                )
             }
             Self::FilenameVersion(path, regex, version, comparator) => {
                 write!(
                     f,
-                    "filename_version(\"{}/{}\", \"{}\", {})",
-                    path.display(),
-                    regex,
-                    version,
+                    "filename_version(\"{}\", \"{}\", {})",
+                    RegexPath(path, regex),
+                    Escaped(version),
                     comparator
                 )
             }
             Self::DescriptionContains(p, r) => {
-                write!(f, "description_contains(\"{}\", \"{}\")", p.display(), r)
+                write!(
+                    f,
+                    "description_contains(\"{}\", \"{}\")",
+                    Escaped(p.display()),
+                    r
+                )
             }
+            Self::DescriptionRegex(p, r, d) => {
+                write!(f, "description_regex(\"{}\", \"{}\")", RegexPath(p, r), d)
+            }
+            Self::AuthorContains(p, r) => {
+                write!(
+                    f,
+                    "author_contains(\"{}\", \"{}\")",
+                    Escaped(p.display()),
+                    r
+                )
+            }
+            Self::FileContentContains(p, r) => {
+                write!(
+                    f,
+                    "file_content_contains(\"{}\", \"{}\")",
+                    Escaped(p.display()),
+                    r
+                )
+            }
+            Self::ArchiveContains(p, e) => {
+                write!(
+                    f,
+                    "archive_contains(\"{}\", \"{}\")",
+                    Escaped(p.display()),
+                    Escaped(e)
+                )
+            }
+            Self::ArchiveVersion(p, c, v) => {
+                write!(
+                    f,
+                    "archive_version(\"{}\", {}, {})",
+                    Escaped(p.display()),
+                    c,
+                    v
+                )
+            }
+            Self::ArchiveRegex(p, r) => {
+                write!(
+                    f,
+                    "archive_regex(\"{}\", \"{}\")",
+                    Escaped(p.display()),
+                    unanchored(r)
+                )
+            }
+            Self::IniValue(p, section, key, value) => write!(
+                f,
+                "ini_value(\"{}\", \"{}\", \"{}\", \"{}\")",
+                Escaped(p.display()),
+                Escaped(section),
+                Escaped(key),
+                Escaped(value)
+            ),
+            Self::IsNewerThan(p1, p2) => {
+                write!(
+                    f,
+                    "is_newer_than(\"{}\", \"{}\")",
+                    Escaped(p1.display()),
+                    Escaped(p2.display())
+                )
+            }
+            Self::DescriptionContainsText(p, t) => {
+                write!(
+                    f,
+                    "description_contains_text(\"{}\", \"{}\")",
+                    Escaped(p.display()),
+                    Escaped(t)
+                )
+            }
+            Self::VersionCmp(p1, p2, c) => write!(
+                f,
+                "version_cmp(\"{}\", \"{}\", {})",
+                Escaped(p1.display()),
+                Escaped(p2.display()),
+                c
+            ),
+            Self::VersionInRange(p, l, u) => {
+                write!(
+                    f,
+                    "version(\"{}\", \"{} - {}\")",
+                    Escaped(p.display()),
+                    Escaped(l),
+                    Escaped(u)
+                )
+            }
+            Self::ProductVersionInRange(p, l, u) => {
+                write!(
+                    f,
+                    "product_version(\"{}\", \"{} - {}\")",
+                    Escaped(p.display()),
+                    Escaped(l),
+                    Escaped(u)
+                )
+            }
+        }
+    }
+}
+
+/// Text formatted as it's written in a quoted argument, escaping double quotes
+/// and any backslashes that would otherwise be read as the start of an escape
+/// sequence.
+struct Escaped<T>(T);
+
+impl<T: fmt::Display> fmt::Display for Escaped<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = self.0.to_string();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' if matches!(chars.peek(), None | Some('"' | '\\')) => f.write_str("\\\\")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A parent path and filename regex, formatted as they're written in a
+/// condition string. The parent path is left out if there isn't one, as it's
+/// optional when parsing.
+struct RegexPath<'a>(&'a Path, &'a Regex);
+
+impl fmt::Display for RegexPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 != Path::new(".") {
+            write!(f, "{}/", self.0.display())?;
         }
+        write!(f, "{}", unanchored(self.1))
     }
 }
 
+/// Get a regex as it was written in a condition string, without the anchors
+/// that were added to it when it was parsed.
+pub(crate) fn unanchored(regex: &Regex) -> &str {
+    let string = regex.as_str();
+    string
+        .strip_prefix('^')
+        .and_then(|s| s.strip_suffix('$'))
+        .unwrap_or(string)
+}
+
 impl PartialEq for Function {
+    #[expect(clippy::too_many_lines)]
     fn eq(&self, other: &Function) -> bool {
         match (self, other) {
             (Self::FilePath(p1), Self::FilePath(p2))
             | (Self::Readable(p1), Self::Readable(p2))
+            | (Self::Writable(p1), Self::Writable(p2))
             | (Self::IsExecutable(p1), Self::IsExecutable(p2))
             | (Self::ActivePath(p1), Self::ActivePath(p2))
-            | (Self::IsMaster(p1), Self::IsMaster(p2)) => {
+            | (Self::IsMaster(p1), Self::IsMaster(p2))
+            | (Self::IsLightPlugin(p1), Self::IsLightPlugin(p2))
+            | (Self::IsMediumPlugin(p1), Self::IsMediumPlugin(p2))
+            | (Self::IsValidPlugin(p1), Self::IsValidPlugin(p2))
+            | (Self::ChecksumManifest(p1), Self::ChecksumManifest(p2)) => {
                 eq(&p1.to_string_lossy(), &p2.to_string_lossy())
             }
             (Self::FileRegex(p1, r1), Self::FileRegex(p2, r2))
+            | (Self::FileGlob(p1, _, r1), Self::FileGlob(p2, _, r2))
             | (Self::Many(p1, r1), Self::Many(p2, r2))
-            | (Self::DescriptionContains(p1, r1), Self::DescriptionContains(p2, r2)) => {
+            | (Self::DescriptionContains(p1, r1), Self::DescriptionContains(p2, r2))
+            | (Self::AuthorContains(p1, r1), Self::AuthorContains(p2, r2))
+            | (Self::FileContentContains(p1, r1), Self::FileContentContains(p2, r2))
+            | (Self::ArchiveRegex(p1, r1), Self::ArchiveRegex(p2, r2)) => {
                 eq(r1.as_str(), r2.as_str()) && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
             }
-            (Self::FileSize(p1, s1), Self::FileSize(p2, s2)) => {
-                s1 == s2 && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+            (Self::RecursiveFileRegex(p1, r1, d1), Self::RecursiveFileRegex(p2, r2, d2))
+            | (Self::RecursiveMany(p1, r1, d1), Self::RecursiveMany(p2, r2, d2)) => {
+                d1 == d2
+                    && eq(r1.as_str(), r2.as_str())
+                    && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+            }
+            (Self::FileSize(p1, s1, c1), Self::FileSize(p2, s2, c2)) => {
+                s1 == s2 && c1 == c2 && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+            }
+            (Self::FileModifiedTime(p1, c1, t1), Self::FileModifiedTime(p2, c2, t2)) => {
+                c1 == c2 && t1 == t2 && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+            }
+            (Self::RecordCount(p1, c1, n1), Self::RecordCount(p2, c2, n2))
+            | (Self::ArchiveVersion(p1, c1, n1), Self::ArchiveVersion(p2, c2, n2)) => {
+                c1 == c2 && n1 == n2 && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+            }
+            (Self::HeaderVersion(p1, c1, v1), Self::HeaderVersion(p2, c2, v2)) => {
+                c1 == c2
+                    && v1.to_bits() == v2.to_bits()
+                    && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+            }
+            (Self::IsArchitecture(p1, a1), Self::IsArchitecture(p2, a2)) => {
+                a1 == a2 && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
             }
             (Self::ActiveRegex(r1), Self::ActiveRegex(r2))
             | (Self::ManyActive(r1), Self::ManyActive(r2)) => eq(r1.as_str(), r2.as_str()),
             (Self::Checksum(p1, c1), Self::Checksum(p2, c2)) => {
                 c1 == c2 && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
             }
+            (Self::ChecksumSha256(p1, d1), Self::ChecksumSha256(p2, d2)) => {
+                d1 == d2 && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+            }
             (Self::Version(p1, v1, c1), Self::Version(p2, v2, c2))
             | (Self::ProductVersion(p1, v1, c1), Self::ProductVersion(p2, v2, c2)) => {
                 c1 == c2 && eq(&v1, &v2) && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
@@ -121,6 +524,49 @@ impl PartialEq for Function {
                     && eq(r1.as_str(), r2.as_str())
                     && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
             }
+            (Self::ActiveCount(r1, c1, n1), Self::ActiveCount(r2, c2, n2)) => {
+                c1 == c2 && n1 == n2 && eq(r1.as_str(), r2.as_str())
+            }
+            (Self::RegistryValue(k1, n1, r1), Self::RegistryValue(k2, n2, r2)) => {
+                eq(&k1, &k2) && eq(&n1, &n2) && eq(r1.as_str(), r2.as_str())
+            }
+            (Self::Count(p1, r1, c1, n1), Self::Count(p2, r2, c2, n2)) => {
+                c1 == c2
+                    && n1 == n2
+                    && eq(r1.as_str(), r2.as_str())
+                    && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+            }
+            (Self::DescriptionRegex(p1, r1, d1), Self::DescriptionRegex(p2, r2, d2)) => {
+                eq(d1.as_str(), d2.as_str())
+                    && eq(r1.as_str(), r2.as_str())
+                    && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+            }
+            (Self::VersionInRange(p1, l1, u1), Self::VersionInRange(p2, l2, u2))
+            | (Self::ProductVersionInRange(p1, l1, u1), Self::ProductVersionInRange(p2, l2, u2)) => {
+                eq(&l1, &l2) && eq(&u1, &u2) && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+            }
+            (Self::IniValue(p1, s1, k1, v1), Self::IniValue(p2, s2, k2, v2)) => {
+                eq(&s1, &s2)
+                    && eq(&k1, &k2)
+                    && eq(&v1, &v2)
+                    && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+            }
+            (Self::DescriptionContainsText(p1, t1), Self::DescriptionContainsText(p2, t2))
+            | (Self::MastersContain(p1, t1), Self::MastersContain(p2, t2))
+            | (Self::ArchiveContains(p1, t1), Self::ArchiveContains(p2, t2)) => {
+                eq(&t1, &t2) && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+            }
+            (Self::VersionCmp(p1, q1, c1), Self::VersionCmp(p2, q2, c2)) => {
+                c1 == c2
+                    && eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+                    && eq(&q1.to_string_lossy(), &q2.to_string_lossy())
+            }
+            (Self::IsPlatform(p1), Self::IsPlatform(p2)) => p1 == p2,
+            (Self::IsWine, Self::IsWine) => true,
+            (Self::IsNewerThan(p1, q1), Self::IsNewerThan(p2, q2)) => {
+                eq(&p1.to_string_lossy(), &p2.to_string_lossy())
+                    && eq(&q1.to_string_lossy(), &q2.to_string_lossy())
+            }
             _ => false,
         }
     }
@@ -129,30 +575,78 @@ impl PartialEq for Function {
 impl Eq for Function {}
 
 impl Hash for Function {
+    #[expect(clippy::too_many_lines)]
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
             Self::FilePath(p)
             | Self::Readable(p)
+            | Self::Writable(p)
             | Self::IsExecutable(p)
             | Self::ActivePath(p)
-            | Self::IsMaster(p) => {
+            | Self::IsMaster(p)
+            | Self::IsLightPlugin(p)
+            | Self::IsMediumPlugin(p)
+            | Self::IsValidPlugin(p)
+            | Self::ChecksumManifest(p) => {
+                p.to_string_lossy().to_lowercase().hash(state);
+            }
+            Self::FileRegex(p, r)
+            | Self::FileGlob(p, _, r)
+            | Self::Many(p, r)
+            | Self::DescriptionContains(p, r)
+            | Self::AuthorContains(p, r)
+            | Self::FileContentContains(p, r)
+            | Self::ArchiveRegex(p, r) => {
                 p.to_string_lossy().to_lowercase().hash(state);
+                r.as_str().to_lowercase().hash(state);
             }
-            Self::FileRegex(p, r) | Self::Many(p, r) | Self::DescriptionContains(p, r) => {
+            Self::RecursiveFileRegex(p, r, d) | Self::RecursiveMany(p, r, d) => {
                 p.to_string_lossy().to_lowercase().hash(state);
                 r.as_str().to_lowercase().hash(state);
+                d.hash(state);
             }
-            Self::FileSize(p, s) => {
+            Self::IsPlatform(platform) => platform.hash(state),
+            Self::IsWine => {}
+            Self::FileSize(p, s, c) => {
                 p.to_string_lossy().to_lowercase().hash(state);
                 s.hash(state);
+                c.hash(state);
+            }
+            Self::FileModifiedTime(p, c, t) => {
+                p.to_string_lossy().to_lowercase().hash(state);
+                c.hash(state);
+                t.hash(state);
+            }
+            Self::RecordCount(p, c, n) | Self::ArchiveVersion(p, c, n) => {
+                p.to_string_lossy().to_lowercase().hash(state);
+                c.hash(state);
+                n.hash(state);
+            }
+            Self::HeaderVersion(p, c, v) => {
+                p.to_string_lossy().to_lowercase().hash(state);
+                c.hash(state);
+                v.to_bits().hash(state);
+            }
+            Self::IsArchitecture(p, a) => {
+                p.to_string_lossy().to_lowercase().hash(state);
+                a.hash(state);
             }
             Self::ActiveRegex(r) | Self::ManyActive(r) => {
                 r.as_str().to_lowercase().hash(state);
             }
+            Self::ActiveCount(r, c, n) => {
+                r.as_str().to_lowercase().hash(state);
+                c.hash(state);
+                n.hash(state);
+            }
             Self::Checksum(p, c) => {
                 p.to_string_lossy().to_lowercase().hash(state);
                 c.hash(state);
             }
+            Self::ChecksumSha256(p, d) => {
+                p.to_string_lossy().to_lowercase().hash(state);
+                d.hash(state);
+            }
             Self::Version(p, v, c) | Self::ProductVersion(p, v, c) => {
                 p.to_string_lossy().to_lowercase().hash(state);
                 v.to_lowercase().hash(state);
@@ -164,12 +658,269 @@ impl Hash for Function {
                 v.to_lowercase().hash(state);
                 c.hash(state);
             }
+            Self::RegistryValue(k, n, r) => {
+                k.to_lowercase().hash(state);
+                n.to_lowercase().hash(state);
+                r.as_str().to_lowercase().hash(state);
+            }
+            Self::Count(p, r, c, n) => {
+                p.to_string_lossy().to_lowercase().hash(state);
+                r.as_str().to_lowercase().hash(state);
+                c.hash(state);
+                n.hash(state);
+            }
+            Self::DescriptionRegex(p, r, d) => {
+                p.to_string_lossy().to_lowercase().hash(state);
+                r.as_str().to_lowercase().hash(state);
+                d.as_str().to_lowercase().hash(state);
+            }
+            Self::IsNewerThan(p1, p2) => {
+                p1.to_string_lossy().to_lowercase().hash(state);
+                p2.to_string_lossy().to_lowercase().hash(state);
+            }
+            Self::DescriptionContainsText(p, t)
+            | Self::MastersContain(p, t)
+            | Self::ArchiveContains(p, t) => {
+                p.to_string_lossy().to_lowercase().hash(state);
+                t.to_lowercase().hash(state);
+            }
+            Self::VersionCmp(p1, p2, c) => {
+                p1.to_string_lossy().to_lowercase().hash(state);
+                p2.to_string_lossy().to_lowercase().hash(state);
+                c.hash(state);
+            }
+            Self::VersionInRange(p, l, u) | Self::ProductVersionInRange(p, l, u) => {
+                p.to_string_lossy().to_lowercase().hash(state);
+                l.to_lowercase().hash(state);
+                u.to_lowercase().hash(state);
+            }
+            Self::IniValue(p, s, k, v) => {
+                p.to_string_lossy().to_lowercase().hash(state);
+                s.to_lowercase().hash(state);
+                k.to_lowercase().hash(state);
+                v.to_lowercase().hash(state);
+            }
         }
 
         discriminant(self).hash(state);
     }
 }
 
+impl Function {
+    /// The name that the function is called by in condition strings.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::FilePath(_) | Self::FileRegex(_, _) | Self::RecursiveFileRegex(_, _, _) => "file",
+            Self::FileGlob(_, _, _) => "file_glob",
+            Self::FileSize(_, _, _) => "file_size",
+            Self::FileModifiedTime(_, _, _) => "file_mtime",
+            Self::Readable(_) => "readable",
+            Self::Writable(_) => "writable",
+            Self::RegistryValue(_, _, _) => "registry_value",
+            Self::IsPlatform(Platform::Windows) => "is_windows",
+            Self::IsPlatform(Platform::Linux) => "is_linux",
+            Self::IsPlatform(Platform::MacOs) => "is_macos",
+            Self::IsWine => "is_wine",
+            Self::IsExecutable(_) => "is_executable",
+            Self::IsArchitecture(_, Architecture::X86) => "is_x86",
+            Self::IsArchitecture(_, Architecture::X64) => "is_x64",
+            Self::ActivePath(_) | Self::ActiveRegex(_) => "active",
+            Self::IsMaster(_) => "is_master",
+            Self::IsLightPlugin(_) => "is_light_plugin",
+            Self::IsMediumPlugin(_) => "is_medium_plugin",
+            Self::HeaderVersion(_, _, _) => "header_version",
+            Self::RecordCount(_, _, _) => "record_count",
+            Self::MastersContain(_, _) => "masters_contain",
+            Self::Many(_, _) | Self::RecursiveMany(_, _, _) => "many",
+            Self::Count(_, _, _, _) => "count",
+            Self::ManyActive(_) => "many_active",
+            Self::ActiveCount(_, _, _) => "active_count",
+            Self::Checksum(_, _) => "checksum",
+            Self::ChecksumSha256(_, _) => "checksum_sha256",
+            Self::Version(_, _, _) | Self::VersionInRange(_, _, _) => "version",
+            Self::ProductVersion(_, _, _) | Self::ProductVersionInRange(_, _, _) => {
+                "product_version"
+            }
+            Self::FilenameVersion(_, _, _, _) => "filename_version",
+            Self::DescriptionContains(_, _) => "description_contains",
+            Self::DescriptionRegex(_, _, _) => "description_regex",
+            Self::AuthorContains(_, _) => "author_contains",
+            Self::IsNewerThan(_, _) => "is_newer_than",
+            Self::ChecksumManifest(_) => "checksum_manifest",
+            Self::VersionCmp(_, _, _) => "version_cmp",
+            Self::DescriptionContainsText(_, _) => "description_contains_text",
+            Self::IsValidPlugin(_) => "is_valid_plugin",
+            Self::FileContentContains(_, _) => "file_content_contains",
+            Self::IniValue(_, _, _, _) => "ini_value",
+            Self::ArchiveContains(_, _) => "archive_contains",
+            Self::ArchiveRegex(_, _) => "archive_regex",
+            Self::ArchiveVersion(_, _, _) => "archive_version",
+        }
+    }
+
+    /// Get the filesystem paths that the function checks, distinguishing the
+    /// directories that it matches entries in against a regex from the paths
+    /// that it reads directly.
+    pub(crate) fn referenced_paths(&self) -> impl Iterator<Item = ReferencedPath<'_>> {
+        let pattern = match self {
+            Self::FileRegex(p, r)
+            | Self::FileGlob(p, _, r)
+            | Self::Many(p, r)
+            | Self::Count(p, r, _, _)
+            | Self::FilenameVersion(p, r, _, _)
+            | Self::DescriptionRegex(p, r, _) => Some((p, r, 0)),
+            Self::RecursiveFileRegex(p, r, d) | Self::RecursiveMany(p, r, d) => Some((p, r, *d)),
+            _ => None,
+        };
+
+        let literals = self
+            .paths()
+            .filter(move |_| pattern.is_none())
+            .map(ReferencedPath::Literal);
+
+        pattern
+            .map(|(directory, regex, depth)| ReferencedPath::Pattern {
+                directory,
+                regex: unanchored(regex),
+                depth,
+            })
+            .into_iter()
+            .chain(literals)
+    }
+
+    /// Get the filesystem paths that the function checks. Active plugin paths
+    /// are not included, as they're only compared against plugin names.
+    pub(crate) fn paths(&self) -> impl Iterator<Item = &Path> {
+        let (path, other_path) = match self {
+            Self::FilePath(p)
+            | Self::FileRegex(p, _)
+            | Self::FileGlob(p, _, _)
+            | Self::FileSize(p, _, _)
+            | Self::FileModifiedTime(p, _, _)
+            | Self::Readable(p)
+            | Self::Writable(p)
+            | Self::IsExecutable(p)
+            | Self::IsArchitecture(p, _)
+            | Self::IsMaster(p)
+            | Self::IsLightPlugin(p)
+            | Self::IsMediumPlugin(p)
+            | Self::HeaderVersion(p, _, _)
+            | Self::RecordCount(p, _, _)
+            | Self::MastersContain(p, _)
+            | Self::IsValidPlugin(p)
+            | Self::FileContentContains(p, _)
+            | Self::IniValue(p, _, _, _)
+            | Self::ArchiveContains(p, _)
+            | Self::ArchiveRegex(p, _)
+            | Self::ArchiveVersion(p, _, _)
+            | Self::Many(p, _)
+            | Self::RecursiveFileRegex(p, _, _)
+            | Self::RecursiveMany(p, _, _)
+            | Self::Count(p, _, _, _)
+            | Self::Checksum(p, _)
+            | Self::ChecksumSha256(p, _)
+            | Self::Version(p, _, _)
+            | Self::ProductVersion(p, _, _)
+            | Self::FilenameVersion(p, _, _, _)
+            | Self::DescriptionContains(p, _)
+            | Self::DescriptionRegex(p, _, _)
+            | Self::AuthorContains(p, _)
+            | Self::VersionInRange(p, _, _)
+            | Self::ProductVersionInRange(p, _, _)
+            | Self::ChecksumManifest(p)
+            | Self::DescriptionContainsText(p, _) => (Some(p.as_path()), None),
+            Self::IsNewerThan(p1, p2) | Self::VersionCmp(p1, p2, _) => {
+                (Some(p1.as_path()), Some(p2.as_path()))
+            }
+            Self::ActivePath(_)
+            | Self::ActiveRegex(_)
+            | Self::ManyActive(_)
+            | Self::ActiveCount(_, _, _)
+            | Self::RegistryValue(_, _, _)
+            | Self::IsPlatform(_)
+            | Self::IsWine => (None, None),
+        };
+
+        path.into_iter().chain(other_path)
+    }
+
+    /// Get the function's result if it's known without evaluating it, because
+    /// it only depends on the platform that the crate was built for.
+    pub(crate) fn constant_result(&self) -> Option<bool> {
+        match self {
+            Self::IsPlatform(platform) => Some(platform.is_current()),
+            _ => None,
+        }
+    }
+
+    /// Get the arguments that may reference variables: paths, including the
+    /// parent paths of regex paths, and version and text strings. Regexes,
+    /// numbers and timestamps can't reference variables.
+    pub(crate) fn variable_strings(&self) -> Vec<Cow<'_, str>> {
+        match self {
+            Self::FilePath(p)
+            | Self::FileRegex(p, _)
+            | Self::FileGlob(p, _, _)
+            | Self::FileSize(p, _, _)
+            | Self::FileModifiedTime(p, _, _)
+            | Self::Readable(p)
+            | Self::Writable(p)
+            | Self::IsExecutable(p)
+            | Self::IsArchitecture(p, _)
+            | Self::ActivePath(p)
+            | Self::IsMaster(p)
+            | Self::IsLightPlugin(p)
+            | Self::IsMediumPlugin(p)
+            | Self::HeaderVersion(p, _, _)
+            | Self::RecordCount(p, _, _)
+            | Self::ArchiveVersion(p, _, _)
+            | Self::Many(p, _)
+            | Self::RecursiveFileRegex(p, _, _)
+            | Self::RecursiveMany(p, _, _)
+            | Self::Count(p, _, _, _)
+            | Self::Checksum(p, _)
+            | Self::ChecksumSha256(p, _)
+            | Self::DescriptionContains(p, _)
+            | Self::DescriptionRegex(p, _, _)
+            | Self::AuthorContains(p, _)
+            | Self::FileContentContains(p, _)
+            | Self::ArchiveRegex(p, _)
+            | Self::ChecksumManifest(p)
+            | Self::IsValidPlugin(p) => vec![p.to_string_lossy()],
+            Self::ActiveRegex(_)
+            | Self::ManyActive(_)
+            | Self::ActiveCount(_, _, _)
+            | Self::IsPlatform(_)
+            | Self::IsWine => Vec::new(),
+            Self::Version(p, v, _)
+            | Self::ProductVersion(p, v, _)
+            | Self::FilenameVersion(p, _, v, _)
+            | Self::DescriptionContainsText(p, v)
+            | Self::MastersContain(p, v)
+            | Self::ArchiveContains(p, v) => {
+                vec![p.to_string_lossy(), Cow::Borrowed(v.as_str())]
+            }
+            Self::IsNewerThan(p1, p2) | Self::VersionCmp(p1, p2, _) => {
+                vec![p1.to_string_lossy(), p2.to_string_lossy()]
+            }
+            Self::RegistryValue(k, n, _) => {
+                vec![Cow::Borrowed(k.as_str()), Cow::Borrowed(n.as_str())]
+            }
+            Self::VersionInRange(p, l, u) | Self::ProductVersionInRange(p, l, u) => vec![
+                p.to_string_lossy(),
+                Cow::Borrowed(l.as_str()),
+                Cow::Borrowed(u.as_str()),
+            ],
+            Self::IniValue(p, s, k, v) => vec![
+                p.to_string_lossy(),
+                Cow::Borrowed(s.as_str()),
+                Cow::Borrowed(k.as_str()),
+                Cow::Borrowed(v.as_str()),
+            ],
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,6 +932,10 @@ mod tests {
         Regex::new(string).unwrap()
     }
 
+    fn timestamp(string: &str) -> Timestamp {
+        Timestamp::parse(string).unwrap()
+    }
+
     mod fmt {
         use super::*;
 
@@ -199,55 +954,324 @@ mod tests {
         }
 
         #[test]
-        fn function_fmt_for_file_size_should_format_correctly() {
-            let function = Function::FileSize("subdir/Blank.esm".into(), 12_345_678);
+        fn function_fmt_for_file_regex_should_omit_a_missing_parent_path() {
+            let function = Function::FileRegex(".".into(), regex("Blank.*"));
+
+            assert_eq!("file(\"Blank.*\")", &format!("{function}"));
+        }
+
+        #[test]
+        fn function_fmt_should_escape_double_quotes_in_text_arguments() {
+            let function =
+                Function::Version("a\"b.esp".into(), "1\"2".into(), ComparisonOperator::Equal);
 
             assert_eq!(
-                "file_size(\"subdir/Blank.esm\", 12345678)",
+                "version(\"a\\\"b.esp\", \"1\\\"2\", ==)",
                 &format!("{function}")
             );
         }
 
         #[test]
-        fn function_fmt_for_readable_should_format_correctly() {
-            let function = Function::Readable("subdir/Blank.esm".into());
+        fn function_fmt_should_only_escape_backslashes_that_would_start_an_escape_sequence() {
+            let function = Function::MastersContain("..\\a.esp".into(), "b\\\\c\\".into());
 
-            assert_eq!("readable(\"subdir/Blank.esm\")", &format!("{function}"));
+            assert_eq!(
+                "masters_contain(\"..\\a.esp\", \"b\\\\\\c\\\\\")",
+                &format!("{function}")
+            );
         }
 
         #[test]
-        fn function_fmt_for_is_executable_should_format_correctly() {
-            let function = Function::IsExecutable("subdir/Blank.esm".into());
+        fn function_fmt_for_file_size_should_format_correctly() {
+            let function = Function::FileSize(
+                "subdir/Blank.esm".into(),
+                12_345_678,
+                ComparisonOperator::Equal,
+            );
 
             assert_eq!(
-                "is_executable(\"subdir/Blank.esm\")",
+                "file_size(\"subdir/Blank.esm\", 12345678)",
                 &format!("{function}")
             );
         }
 
         #[test]
-        fn function_fmt_for_active_path_should_format_correctly() {
-            let function = Function::ActivePath("Blank.esm".into());
+        fn function_fmt_for_file_size_with_a_comparator_should_format_correctly() {
+            let function = Function::FileSize(
+                "subdir/Blank.esm".into(),
+                12_345_678,
+                ComparisonOperator::LessThan,
+            );
 
-            assert_eq!("active(\"Blank.esm\")", &format!("{function}"));
+            assert_eq!(
+                "file_size(\"subdir/Blank.esm\", 12345678, <)",
+                &format!("{function}")
+            );
         }
 
         #[test]
-        fn function_fmt_for_active_regex_should_format_correctly() {
-            let function = Function::ActiveRegex(regex("Blank.*"));
+        fn function_fmt_for_file_mtime_should_format_correctly() {
+            let function = Function::FileModifiedTime(
+                "subdir/Blank.esm".into(),
+                ComparisonOperator::GreaterThan,
+                timestamp("2024-01-01T12:00:00+01:00"),
+            );
 
-            assert_eq!("active(\"Blank.*\")", &format!("{function}"));
+            assert_eq!(
+                "file_mtime(\"subdir/Blank.esm\", >, \"2024-01-01T11:00:00Z\")",
+                &format!("{function}")
+            );
         }
 
         #[test]
-        fn function_fmt_for_is_master_should_format_correctly() {
-            let function = Function::IsMaster("Blank.esm".into());
+        fn function_fmt_for_writable_should_format_correctly() {
+            let function = Function::Writable("subdir/Blank.esm".into());
 
-            assert_eq!("is_master(\"Blank.esm\")", &format!("{function}"));
+            assert_eq!("writable(\"subdir/Blank.esm\")", &format!("{function}"));
         }
 
         #[test]
-        fn function_fmt_for_many_should_format_correctly() {
+        fn function_fmt_for_readable_should_format_correctly() {
+            let function = Function::Readable("subdir/Blank.esm".into());
+
+            assert_eq!("readable(\"subdir/Blank.esm\")", &format!("{function}"));
+        }
+
+        #[test]
+        fn function_fmt_for_is_executable_should_format_correctly() {
+            let function = Function::IsExecutable("subdir/Blank.esm".into());
+
+            assert_eq!(
+                "is_executable(\"subdir/Blank.esm\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_is_architecture_should_format_correctly() {
+            let function = Function::IsArchitecture("subdir/loot.dll".into(), Architecture::X64);
+
+            assert_eq!("is_x64(\"subdir/loot.dll\")", &format!("{function}"));
+
+            let function = Function::IsArchitecture("subdir/loot.dll".into(), Architecture::X86);
+
+            assert_eq!("is_x86(\"subdir/loot.dll\")", &format!("{function}"));
+        }
+
+        #[test]
+        fn function_fmt_for_is_platform_should_format_correctly() {
+            assert_eq!(
+                "is_windows()",
+                &format!("{}", Function::IsPlatform(Platform::Windows))
+            );
+            assert_eq!(
+                "is_linux()",
+                &format!("{}", Function::IsPlatform(Platform::Linux))
+            );
+            assert_eq!(
+                "is_macos()",
+                &format!("{}", Function::IsPlatform(Platform::MacOs))
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_is_wine_should_format_correctly() {
+            assert_eq!("is_wine()", &format!("{}", Function::IsWine));
+        }
+
+        #[test]
+        fn function_fmt_for_file_glob_should_format_correctly() {
+            let function =
+                Function::FileGlob("Data".into(), "Blank*.es?".into(), regex("^Blank.*\\.es.$"));
+
+            assert_eq!(
+                "file_glob(\"Data\", \"Blank*.es?\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_recursive_file_regex_and_many_should_format_correctly() {
+            let function = Function::RecursiveFileRegex("meshes".into(), regex(".*\\.nif"), 3);
+
+            assert_eq!("file(\"meshes/.*\\.nif\", 3)", &format!("{function}"));
+
+            let function = Function::RecursiveMany("meshes".into(), regex(".*\\.nif"), 3);
+
+            assert_eq!("many(\"meshes/.*\\.nif\", 3)", &format!("{function}"));
+        }
+
+        #[test]
+        fn function_fmt_for_active_path_should_format_correctly() {
+            let function = Function::ActivePath("Blank.esm".into());
+
+            assert_eq!("active(\"Blank.esm\")", &format!("{function}"));
+        }
+
+        #[test]
+        fn function_fmt_for_active_regex_should_format_correctly() {
+            let function = Function::ActiveRegex(regex("Blank.*"));
+
+            assert_eq!("active(\"Blank.*\")", &format!("{function}"));
+        }
+
+        #[test]
+        fn function_fmt_for_is_master_should_format_correctly() {
+            let function = Function::IsMaster("Blank.esm".into());
+
+            assert_eq!("is_master(\"Blank.esm\")", &format!("{function}"));
+        }
+
+        #[test]
+        fn function_fmt_for_is_light_plugin_should_format_correctly() {
+            let function = Function::IsLightPlugin("Blank.esl".into());
+
+            assert_eq!("is_light_plugin(\"Blank.esl\")", &format!("{function}"));
+        }
+
+        #[test]
+        fn function_fmt_for_is_medium_plugin_should_format_correctly() {
+            let function = Function::IsMediumPlugin("Blank.esm".into());
+
+            assert_eq!("is_medium_plugin(\"Blank.esm\")", &format!("{function}"));
+        }
+
+        #[test]
+        fn function_fmt_for_record_count_should_format_correctly() {
+            let function =
+                Function::RecordCount("Blank.esp".into(), ComparisonOperator::LessThanOrEqual, 1);
+
+            assert_eq!("record_count(\"Blank.esp\", <=, 1)", &format!("{function}"));
+        }
+
+        #[test]
+        fn function_fmt_for_masters_contain_should_format_correctly() {
+            let function = Function::MastersContain("Child.esp".into(), "Master.esm".into());
+
+            assert_eq!(
+                "masters_contain(\"Child.esp\", \"Master.esm\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_file_content_contains_should_format_correctly() {
+            let function = Function::FileContentContains("foo.ini".into(), regex("bPatch=1"));
+
+            assert_eq!(
+                "file_content_contains(\"foo.ini\", \"bPatch=1\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_archive_contains_should_format_correctly() {
+            let function =
+                Function::ArchiveContains("Textures.bsa".into(), "textures/a.dds".into());
+
+            assert_eq!(
+                "archive_contains(\"Textures.bsa\", \"textures/a.dds\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_archive_regex_should_format_correctly() {
+            let function =
+                Function::ArchiveRegex("Meshes - Main.ba2".into(), regex("^meshes/.*\\.nif$"));
+
+            assert_eq!(
+                "archive_regex(\"Meshes - Main.ba2\", \"meshes/.*\\.nif\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_archive_version_should_format_correctly() {
+            let function =
+                Function::ArchiveVersion("Archive.ba2".into(), ComparisonOperator::LessThan, 3);
+
+            assert_eq!(
+                "archive_version(\"Archive.ba2\", <, 3)",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_registry_value_should_format_correctly() {
+            let function = Function::RegistryValue(
+                "HKLM\\SOFTWARE\\Bethesda Softworks\\Oblivion".into(),
+                "Installed Path".into(),
+                regex("Steam"),
+            );
+
+            assert_eq!(
+                "registry_value(\"HKLM\\SOFTWARE\\Bethesda Softworks\\Oblivion\", \"Installed Path\", \"Steam\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_ini_value_should_format_correctly() {
+            let function = Function::IniValue(
+                "MyMod.ini".into(),
+                "General".into(),
+                "bEnabled".into(),
+                "1".into(),
+            );
+
+            assert_eq!(
+                "ini_value(\"MyMod.ini\", \"General\", \"bEnabled\", \"1\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_count_should_format_correctly() {
+            let function = Function::Count(
+                "Patches".into(),
+                regex(".* Patch\\.esp"),
+                ComparisonOperator::GreaterThanOrEqual,
+                3,
+            );
+
+            assert_eq!(
+                "count(\"Patches/.* Patch\\.esp\", >=, 3)",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_header_version_should_format_correctly() {
+            let function =
+                Function::HeaderVersion("Blank.esp".into(), ComparisonOperator::GreaterThan, 0.94);
+
+            assert_eq!(
+                "header_version(\"Blank.esp\", >, 0.94)",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_is_valid_plugin_should_format_correctly() {
+            let function = Function::IsValidPlugin("Blank.esm".into());
+
+            assert_eq!("is_valid_plugin(\"Blank.esm\")", &format!("{function}"));
+        }
+
+        #[test]
+        fn function_fmt_for_active_count_should_format_correctly() {
+            let function =
+                Function::ActiveCount(regex(".*Weather.*"), ComparisonOperator::GreaterThan, 4);
+
+            assert_eq!(
+                "active_count(\".*Weather.*\", >, 4)",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_many_should_format_correctly() {
             let function = Function::Many("subdir".into(), regex("Blank.*"));
 
             assert_eq!("many(\"subdir/Blank.*\")", &format!("{function}"));
@@ -260,9 +1284,19 @@ mod tests {
             assert_eq!("many_active(\"Blank.*\")", &format!("{function}"));
         }
 
+        #[test]
+        fn function_fmt_for_checksum_with_several_crcs_should_format_correctly() {
+            let function = Function::Checksum("Blank.esm".into(), vec![0xDEAD_BEEF, 0x0012_3456]);
+
+            assert_eq!(
+                "checksum(\"Blank.esm\", DEADBEEF, 123456)",
+                &format!("{function}")
+            );
+        }
+
         #[test]
         fn function_fmt_for_checksum_should_format_correctly() {
-            let function = Function::Checksum("subdir/Blank.esm".into(), 0xDEAD_BEEF);
+            let function = Function::Checksum("subdir/Blank.esm".into(), vec![0xDEAD_BEEF]);
 
             assert_eq!(
                 "checksum(\"subdir/Blank.esm\", DEADBEEF)",
@@ -270,6 +1304,19 @@ mod tests {
             );
         }
 
+        #[test]
+        fn function_fmt_for_checksum_sha256_should_format_correctly() {
+            let function = Function::ChecksumSha256("subdir/Blank.esm".into(), [0xAB; 32]);
+
+            assert_eq!(
+                format!(
+                    "checksum_sha256(\"subdir/Blank.esm\", \"{}\")",
+                    "ab".repeat(32)
+                ),
+                format!("{function}")
+            );
+        }
+
         #[test]
         fn function_fmt_for_version_should_format_correctly() {
             let function = Function::Version(
@@ -299,6 +1346,7 @@ mod tests {
         }
 
         #[test]
+        #[cfg(feature = "regex")]
         fn function_fmt_for_filename_version_should_format_correctly() {
             let function = Function::FilenameVersion(
                 "subdir".into(),
@@ -323,6 +1371,94 @@ mod tests {
                 &format!("{function}")
             );
         }
+
+        #[test]
+        fn function_fmt_for_version_in_range_should_format_correctly() {
+            let function = Function::VersionInRange("app.exe".into(), "1.2".into(), "1.4".into());
+
+            assert_eq!(
+                "version(\"app.exe\", \"1.2 - 1.4\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_product_version_in_range_should_format_correctly() {
+            let function =
+                Function::ProductVersionInRange("app.exe".into(), "1.2".into(), "1.4".into());
+
+            assert_eq!(
+                "product_version(\"app.exe\", \"1.2 - 1.4\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_checksum_manifest_should_format_correctly() {
+            let function = Function::ChecksumManifest("mod/checksums.sfv".into());
+
+            assert_eq!(
+                "checksum_manifest(\"mod/checksums.sfv\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_version_cmp_should_format_correctly() {
+            let function = Function::VersionCmp(
+                "skse64_loader.exe".into(),
+                "../SkyrimSE.exe".into(),
+                ComparisonOperator::GreaterThanOrEqual,
+            );
+
+            assert_eq!(
+                "version_cmp(\"skse64_loader.exe\", \"../SkyrimSE.exe\", >=)",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        #[cfg(feature = "regex")]
+        fn function_fmt_for_description_regex_should_format_correctly() {
+            let function =
+                Function::DescriptionRegex("Patches".into(), regex(".*\\.esp"), regex("a|b"));
+
+            assert_eq!(
+                "description_regex(\"Patches/.*\\.esp\", \"a|b\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        #[cfg(feature = "regex")]
+        fn function_fmt_for_author_contains_should_format_correctly() {
+            let function = Function::AuthorContains("Blank.esp".into(), regex("Some(one|body)"));
+
+            assert_eq!(
+                "author_contains(\"Blank.esp\", \"Some(one|body)\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_description_contains_text_should_format_correctly() {
+            let function = Function::DescriptionContainsText("Blank.esp".into(), "[Patch]".into());
+
+            assert_eq!(
+                "description_contains_text(\"Blank.esp\", \"[Patch]\")",
+                &format!("{function}")
+            );
+        }
+
+        #[test]
+        fn function_fmt_for_is_newer_than_should_format_correctly() {
+            let function = Function::IsNewerThan("Bashed Patch, 0.esp".into(), "Blank.esp".into());
+
+            assert_eq!(
+                "is_newer_than(\"Bashed Patch, 0.esp\", \"Blank.esp\")",
+                &format!("{function}")
+            );
+        }
     }
 
     mod eq {
@@ -377,25 +1513,50 @@ mod tests {
         #[test]
         fn function_eq_for_file_size_should_check_pathbuf_and_size() {
             assert_eq!(
-                Function::FileSize("subdir".into(), 1),
-                Function::FileSize("subdir".into(), 1)
+                Function::FileSize("subdir".into(), 1, ComparisonOperator::Equal),
+                Function::FileSize("subdir".into(), 1, ComparisonOperator::Equal)
             );
 
             assert_ne!(
-                Function::FileSize("subdir".into(), 1),
-                Function::FileSize("other".into(), 1)
+                Function::FileSize("subdir".into(), 1, ComparisonOperator::Equal),
+                Function::FileSize("other".into(), 1, ComparisonOperator::Equal)
             );
             assert_ne!(
-                Function::FileSize("subdir".into(), 1),
-                Function::FileSize("subdir".into(), 2)
+                Function::FileSize("subdir".into(), 1, ComparisonOperator::Equal),
+                Function::FileSize("subdir".into(), 2, ComparisonOperator::Equal)
             );
         }
 
         #[test]
         fn function_eq_for_file_size_should_be_case_insensitive_on_pathbuf() {
             assert_eq!(
-                Function::FileSize("subdir".into(), 1),
-                Function::FileSize("Subdir".into(), 1)
+                Function::FileSize("subdir".into(), 1, ComparisonOperator::Equal),
+                Function::FileSize("Subdir".into(), 1, ComparisonOperator::Equal)
+            );
+        }
+
+        #[test]
+        fn function_eq_for_file_mtime_should_check_pathbuf_comparator_and_timestamp() {
+            let function = |path: &str, comparator, time| {
+                Function::FileModifiedTime(path.into(), comparator, timestamp(time))
+            };
+
+            assert_eq!(
+                function("subdir", ComparisonOperator::LessThan, "2024-01-01"),
+                function("Subdir", ComparisonOperator::LessThan, "2024-01-01")
+            );
+
+            assert_ne!(
+                function("subdir", ComparisonOperator::LessThan, "2024-01-01"),
+                function("other", ComparisonOperator::LessThan, "2024-01-01")
+            );
+            assert_ne!(
+                function("subdir", ComparisonOperator::LessThan, "2024-01-01"),
+                function("subdir", ComparisonOperator::GreaterThan, "2024-01-01")
+            );
+            assert_ne!(
+                function("subdir", ComparisonOperator::LessThan, "2024-01-01"),
+                function("subdir", ComparisonOperator::LessThan, "2024-01-02")
             );
         }
 
@@ -421,7 +1582,19 @@ mod tests {
         }
 
         #[test]
-        fn function_eq_for_readable_should_not_be_equal_to_file_path_with_same_pathbuf() {
+        fn function_eq_for_writable_should_be_case_insensitive_and_not_equal_to_readable() {
+            assert_eq!(
+                Function::Writable("Blank.esm".into()),
+                Function::Writable("blank.esm".into())
+            );
+            assert_ne!(
+                Function::Writable("Blank.esm".into()),
+                Function::Readable("Blank.esm".into())
+            );
+        }
+
+        #[test]
+        fn function_eq_for_readable_should_not_be_equal_to_file_path_with_same_pathbuf() {
             assert_ne!(
                 Function::Readable("Blank.esm".into()),
                 Function::FilePath("Blank.esm".into())
@@ -565,6 +1738,115 @@ mod tests {
             );
         }
 
+        #[test]
+        fn function_eq_for_is_light_plugin_should_check_pathbuf_case_insensitively() {
+            assert_eq!(
+                Function::IsLightPlugin("Blank.esl".into()),
+                Function::IsLightPlugin("blank.ESL".into())
+            );
+
+            assert_ne!(
+                Function::IsLightPlugin("Blank.esp".into()),
+                Function::IsLightPlugin("Blank.esl".into())
+            );
+        }
+
+        #[test]
+        fn function_eq_for_is_light_plugin_should_not_be_equal_to_is_master_with_same_pathbuf() {
+            assert_ne!(
+                Function::IsLightPlugin("Blank.esm".into()),
+                Function::IsMaster("Blank.esm".into())
+            );
+        }
+
+        #[test]
+        fn function_eq_for_is_medium_plugin_should_check_pathbuf_case_insensitively() {
+            assert_eq!(
+                Function::IsMediumPlugin("Blank.esm".into()),
+                Function::IsMediumPlugin("blank.ESM".into())
+            );
+
+            assert_ne!(
+                Function::IsMediumPlugin("Blank.esp".into()),
+                Function::IsMediumPlugin("Blank.esm".into())
+            );
+            assert_ne!(
+                Function::IsMediumPlugin("Blank.esm".into()),
+                Function::IsLightPlugin("Blank.esm".into())
+            );
+        }
+
+        #[test]
+        fn function_eq_for_record_count_should_check_pathbuf_comparator_and_count() {
+            let function = |path: &str, comparator, count| {
+                Function::RecordCount(path.into(), comparator, count)
+            };
+
+            assert_eq!(
+                function("Blank.esp", ComparisonOperator::Equal, 1),
+                function("blank.ESP", ComparisonOperator::Equal, 1)
+            );
+
+            assert_ne!(
+                function("Blank.esp", ComparisonOperator::Equal, 1),
+                function("Blank.esm", ComparisonOperator::Equal, 1)
+            );
+            assert_ne!(
+                function("Blank.esp", ComparisonOperator::Equal, 1),
+                function("Blank.esp", ComparisonOperator::NotEqual, 1)
+            );
+            assert_ne!(
+                function("Blank.esp", ComparisonOperator::Equal, 1),
+                function("Blank.esp", ComparisonOperator::Equal, 2)
+            );
+        }
+
+        #[test]
+        fn function_eq_for_masters_contain_should_check_pathbuf_and_master_case_insensitively() {
+            assert_eq!(
+                Function::MastersContain("Child.esp".into(), "Master.esm".into()),
+                Function::MastersContain("child.ESP".into(), "master.ESM".into())
+            );
+
+            assert_ne!(
+                Function::MastersContain("Child.esp".into(), "Master.esm".into()),
+                Function::MastersContain("Other.esp".into(), "Master.esm".into())
+            );
+            assert_ne!(
+                Function::MastersContain("Child.esp".into(), "Master.esm".into()),
+                Function::MastersContain("Child.esp".into(), "Other.esm".into())
+            );
+            assert_ne!(
+                Function::MastersContain("Child.esp".into(), "Master.esm".into()),
+                Function::DescriptionContainsText("Child.esp".into(), "Master.esm".into())
+            );
+        }
+
+        #[test]
+        fn function_eq_for_header_version_should_check_pathbuf_comparator_and_version() {
+            let function = |path: &str, comparator, version| {
+                Function::HeaderVersion(path.into(), comparator, version)
+            };
+
+            assert_eq!(
+                function("Blank.esp", ComparisonOperator::Equal, 1.7),
+                function("blank.ESP", ComparisonOperator::Equal, 1.7)
+            );
+
+            assert_ne!(
+                function("Blank.esp", ComparisonOperator::Equal, 1.7),
+                function("Blank.esm", ComparisonOperator::Equal, 1.7)
+            );
+            assert_ne!(
+                function("Blank.esp", ComparisonOperator::Equal, 1.7),
+                function("Blank.esp", ComparisonOperator::NotEqual, 1.7)
+            );
+            assert_ne!(
+                function("Blank.esp", ComparisonOperator::Equal, 1.7),
+                function("Blank.esp", ComparisonOperator::Equal, 1.71)
+            );
+        }
+
         #[test]
         fn function_eq_for_many_should_check_pathbuf_and_regex() {
             assert_eq!(
@@ -598,6 +1880,54 @@ mod tests {
             );
         }
 
+        #[test]
+        fn function_eq_for_active_count_should_check_all_arguments() {
+            assert_eq!(
+                Function::ActiveCount(regex("blank.*"), ComparisonOperator::Equal, 2),
+                Function::ActiveCount(regex("Blank.*"), ComparisonOperator::Equal, 2)
+            );
+            assert_ne!(
+                Function::ActiveCount(regex("blank.*"), ComparisonOperator::Equal, 2),
+                Function::ActiveCount(regex("other.*"), ComparisonOperator::Equal, 2)
+            );
+            assert_ne!(
+                Function::ActiveCount(regex("blank.*"), ComparisonOperator::Equal, 2),
+                Function::ActiveCount(regex("blank.*"), ComparisonOperator::LessThan, 2)
+            );
+            assert_ne!(
+                Function::ActiveCount(regex("blank.*"), ComparisonOperator::Equal, 2),
+                Function::ActiveCount(regex("blank.*"), ComparisonOperator::Equal, 3)
+            );
+        }
+
+        #[test]
+        fn function_eq_for_count_should_check_all_arguments() {
+            let count = |p: &str, r: &str, c: ComparisonOperator, n: u64| {
+                Function::Count(p.into(), regex(r), c, n)
+            };
+
+            assert_eq!(
+                count("Patches", "a.*", ComparisonOperator::Equal, 2),
+                count("patches", "A.*", ComparisonOperator::Equal, 2)
+            );
+            assert_ne!(
+                count("Patches", "a.*", ComparisonOperator::Equal, 2),
+                count("Other", "a.*", ComparisonOperator::Equal, 2)
+            );
+            assert_ne!(
+                count("Patches", "a.*", ComparisonOperator::Equal, 2),
+                count("Patches", "b.*", ComparisonOperator::Equal, 2)
+            );
+            assert_ne!(
+                count("Patches", "a.*", ComparisonOperator::Equal, 2),
+                count("Patches", "a.*", ComparisonOperator::LessThan, 2)
+            );
+            assert_ne!(
+                count("Patches", "a.*", ComparisonOperator::Equal, 2),
+                count("Patches", "a.*", ComparisonOperator::Equal, 3)
+            );
+        }
+
         #[test]
         fn function_eq_for_many_active_should_check_regex() {
             assert_eq!(
@@ -630,25 +1960,25 @@ mod tests {
         #[test]
         fn function_eq_for_checksum_should_check_pathbuf_and_crc() {
             assert_eq!(
-                Function::Checksum("Blank.esm".into(), 1),
-                Function::Checksum("Blank.esm".into(), 1)
+                Function::Checksum("Blank.esm".into(), vec![1]),
+                Function::Checksum("Blank.esm".into(), vec![1])
             );
 
             assert_ne!(
-                Function::Checksum("Blank.esm".into(), 1),
-                Function::Checksum("Blank.esm".into(), 2)
+                Function::Checksum("Blank.esm".into(), vec![1]),
+                Function::Checksum("Blank.esm".into(), vec![2])
             );
             assert_ne!(
-                Function::Checksum("Blank.esm".into(), 1),
-                Function::Checksum("Blank.esp".into(), 1)
+                Function::Checksum("Blank.esm".into(), vec![1]),
+                Function::Checksum("Blank.esp".into(), vec![1])
             );
         }
 
         #[test]
         fn function_eq_for_checksum_should_be_case_insensitive_on_pathbuf() {
             assert_eq!(
-                Function::Checksum("Blank.esm".into(), 1),
-                Function::Checksum("blank.esm".into(), 1)
+                Function::Checksum("Blank.esm".into(), vec![1]),
+                Function::Checksum("blank.esm".into(), vec![1])
             );
         }
 
@@ -746,98 +2076,419 @@ mod tests {
                 )
             );
             assert_ne!(
-                Function::FilenameVersion(
-                    "subdir".into(),
-                    regex("Blank\\.esm"),
-                    "1".into(),
-                    ComparisonOperator::Equal
-                ),
-                Function::FilenameVersion(
-                    "subdir".into(),
-                    regex("Blank.esp"),
-                    "1".into(),
-                    ComparisonOperator::Equal
-                )
+                Function::FilenameVersion(
+                    "subdir".into(),
+                    regex("Blank\\.esm"),
+                    "1".into(),
+                    ComparisonOperator::Equal
+                ),
+                Function::FilenameVersion(
+                    "subdir".into(),
+                    regex("Blank.esp"),
+                    "1".into(),
+                    ComparisonOperator::Equal
+                )
+            );
+            assert_ne!(
+                Function::FilenameVersion(
+                    "subdir".into(),
+                    regex("Blank\\.esm"),
+                    "1".into(),
+                    ComparisonOperator::Equal
+                ),
+                Function::FilenameVersion(
+                    "subdir".into(),
+                    regex("Blank\\.esm"),
+                    "2".into(),
+                    ComparisonOperator::Equal
+                )
+            );
+            assert_ne!(
+                Function::FilenameVersion(
+                    "subdir".into(),
+                    regex("Blank\\.esm"),
+                    "1".into(),
+                    ComparisonOperator::Equal
+                ),
+                Function::FilenameVersion(
+                    "subdir".into(),
+                    regex("Blank\\.esm"),
+                    "1".into(),
+                    ComparisonOperator::NotEqual
+                )
+            );
+        }
+
+        #[test]
+        fn function_eq_for_filename_version_should_be_case_insensitive_on_pathbuf_and_version() {
+            assert_eq!(
+                Function::FilenameVersion(
+                    "subdir".into(),
+                    regex("Blank\\.esm"),
+                    "A".into(),
+                    ComparisonOperator::Equal
+                ),
+                Function::FilenameVersion(
+                    "Subdir".into(),
+                    regex("Blank\\.esm"),
+                    "a".into(),
+                    ComparisonOperator::Equal
+                )
+            );
+        }
+
+        #[test]
+        fn function_eq_for_description_contains_should_check_pathbuf_and_regex() {
+            assert_eq!(
+                Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII)),
+                Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII))
+            );
+
+            assert_ne!(
+                Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII)),
+                Function::DescriptionContains("Blank.esp".into(), regex(".*"))
+            );
+            assert_ne!(
+                Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII)),
+                Function::DescriptionContains("other".into(), regex(LOWERCASE_NON_ASCII))
+            );
+        }
+
+        #[test]
+        fn function_eq_for_description_contains_should_be_case_insensitive_on_pathbuf_and_regex() {
+            assert_eq!(
+                Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII)),
+                Function::DescriptionContains("blank.esp".into(), regex(UPPERCASE_NON_ASCII))
+            );
+        }
+
+        #[test]
+        fn function_eq_description_contains_should_not_be_equal_to_file_regex_with_same_pathbuf_and_regex(
+        ) {
+            assert_ne!(
+                Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII)),
+                Function::FileRegex("Blank.esp".into(), regex(LOWERCASE_NON_ASCII))
+            );
+        }
+
+        #[test]
+        fn function_eq_for_file_content_contains_should_check_pathbuf_and_regex_case_insensitively()
+        {
+            assert_eq!(
+                Function::FileContentContains("foo.ini".into(), regex("bPatch=1")),
+                Function::FileContentContains("FOO.ini".into(), regex("bpatch=1"))
+            );
+
+            assert_ne!(
+                Function::FileContentContains("foo.ini".into(), regex("bPatch=1")),
+                Function::FileContentContains("bar.ini".into(), regex("bPatch=1"))
+            );
+            assert_ne!(
+                Function::FileContentContains("foo.ini".into(), regex("bPatch=1")),
+                Function::FileContentContains("foo.ini".into(), regex("bPatch=0"))
+            );
+            assert_ne!(
+                Function::FileContentContains("foo.ini".into(), regex("bPatch=1")),
+                Function::DescriptionContains("foo.ini".into(), regex("bPatch=1"))
+            );
+        }
+
+        #[test]
+        fn function_eq_for_archive_contains_should_check_pathbuf_and_entry_case_insensitively() {
+            assert_eq!(
+                Function::ArchiveContains("Textures.bsa".into(), "textures/a.dds".into()),
+                Function::ArchiveContains("textures.BSA".into(), "TEXTURES/A.dds".into())
+            );
+
+            assert_ne!(
+                Function::ArchiveContains("Textures.bsa".into(), "textures/a.dds".into()),
+                Function::ArchiveContains("Other.bsa".into(), "textures/a.dds".into())
+            );
+            assert_ne!(
+                Function::ArchiveContains("Textures.bsa".into(), "textures/a.dds".into()),
+                Function::ArchiveContains("Textures.bsa".into(), "textures/b.dds".into())
+            );
+            assert_ne!(
+                Function::ArchiveContains("Textures.bsa".into(), "textures/a.dds".into()),
+                Function::MastersContain("Textures.bsa".into(), "textures/a.dds".into())
+            );
+        }
+
+        #[test]
+        fn function_eq_for_archive_regex_should_check_pathbuf_and_regex_case_insensitively() {
+            assert_eq!(
+                Function::ArchiveRegex("Main.ba2".into(), regex("meshes/.*")),
+                Function::ArchiveRegex("main.BA2".into(), regex("MESHES/.*"))
+            );
+
+            assert_ne!(
+                Function::ArchiveRegex("Main.ba2".into(), regex("meshes/.*")),
+                Function::ArchiveRegex("Other.ba2".into(), regex("meshes/.*"))
+            );
+            assert_ne!(
+                Function::ArchiveRegex("Main.ba2".into(), regex("meshes/.*")),
+                Function::ArchiveRegex("Main.ba2".into(), regex("textures/.*"))
+            );
+            assert_ne!(
+                Function::ArchiveRegex("Main.ba2".into(), regex("meshes/.*")),
+                Function::FileRegex("Main.ba2".into(), regex("meshes/.*"))
+            );
+        }
+
+        #[test]
+        fn function_eq_for_archive_version_should_check_pathbuf_comparator_and_version() {
+            let function = |path: &str, comparator, version| {
+                Function::ArchiveVersion(path.into(), comparator, version)
+            };
+
+            assert_eq!(
+                function("Archive.ba2", ComparisonOperator::Equal, 1),
+                function("archive.BA2", ComparisonOperator::Equal, 1)
+            );
+
+            assert_ne!(
+                function("Archive.ba2", ComparisonOperator::Equal, 1),
+                function("Other.ba2", ComparisonOperator::Equal, 1)
+            );
+            assert_ne!(
+                function("Archive.ba2", ComparisonOperator::Equal, 1),
+                function("Archive.ba2", ComparisonOperator::NotEqual, 1)
+            );
+            assert_ne!(
+                function("Archive.ba2", ComparisonOperator::Equal, 1),
+                function("Archive.ba2", ComparisonOperator::Equal, 2)
+            );
+            assert_ne!(
+                function("Archive.ba2", ComparisonOperator::Equal, 1),
+                Function::RecordCount("Archive.ba2".into(), ComparisonOperator::Equal, 1)
+            );
+        }
+
+        #[test]
+        fn function_eq_for_is_platform_should_check_the_platform() {
+            assert_eq!(
+                Function::IsPlatform(Platform::Windows),
+                Function::IsPlatform(Platform::Windows)
+            );
+            assert_ne!(
+                Function::IsPlatform(Platform::Windows),
+                Function::IsPlatform(Platform::Linux)
+            );
+        }
+
+        #[test]
+        fn function_eq_for_is_wine_should_be_true_for_another_is_wine() {
+            assert_eq!(Function::IsWine, Function::IsWine);
+            assert_ne!(Function::IsWine, Function::IsPlatform(Platform::Windows));
+        }
+
+        #[test]
+        fn function_eq_for_file_glob_should_check_the_path_and_glob_case_insensitively() {
+            let file_glob =
+                |p: &str, g: &str, r: &str| Function::FileGlob(p.into(), g.into(), regex(r));
+
+            assert_eq!(
+                file_glob("Data", "Blank*", "^Blank.*$"),
+                file_glob("data", "blank*", "^blank.*$")
+            );
+            assert_ne!(
+                file_glob("Data", "Blank*", "^Blank.*$"),
+                file_glob("Other", "Blank*", "^Blank.*$")
+            );
+            assert_ne!(
+                file_glob("Data", "Blank*", "^Blank.*$"),
+                file_glob("Data", "Blank?", "^Blank.$")
+            );
+        }
+
+        #[test]
+        fn function_eq_for_recursive_file_regex_should_check_the_depth() {
+            assert_eq!(
+                Function::RecursiveFileRegex("Meshes".into(), regex("A.*"), 2),
+                Function::RecursiveFileRegex("meshes".into(), regex("a.*"), 2)
+            );
+            assert_ne!(
+                Function::RecursiveFileRegex("meshes".into(), regex("a.*"), 2),
+                Function::RecursiveFileRegex("meshes".into(), regex("a.*"), 3)
+            );
+            assert_ne!(
+                Function::RecursiveFileRegex("meshes".into(), regex("a.*"), 2),
+                Function::RecursiveMany("meshes".into(), regex("a.*"), 2)
+            );
+        }
+
+        #[test]
+        fn function_eq_for_registry_value_should_check_all_arguments_case_insensitively() {
+            let registry_value =
+                |k: &str, n: &str, r: &str| Function::RegistryValue(k.into(), n.into(), regex(r));
+
+            assert_eq!(
+                registry_value("HKLM\\Software", "Path", "steam"),
+                registry_value("hklm\\SOFTWARE", "path", "Steam")
+            );
+            assert_ne!(
+                registry_value("HKLM\\Software", "Path", "steam"),
+                registry_value("HKCU\\Software", "Path", "steam")
+            );
+            assert_ne!(
+                registry_value("HKLM\\Software", "Path", "steam"),
+                registry_value("HKLM\\Software", "Other", "steam")
+            );
+            assert_ne!(
+                registry_value("HKLM\\Software", "Path", "steam"),
+                registry_value("HKLM\\Software", "Path", "gog")
+            );
+        }
+
+        #[test]
+        fn function_eq_for_ini_value_should_check_all_arguments_case_insensitively() {
+            let function = |path: &str, section: &str, key: &str, value: &str| {
+                Function::IniValue(path.into(), section.into(), key.into(), value.into())
+            };
+
+            assert_eq!(
+                function("MyMod.ini", "General", "sName", "Value"),
+                function("mymod.INI", "GENERAL", "sname", "VALUE")
+            );
+
+            assert_ne!(
+                function("MyMod.ini", "General", "sName", "Value"),
+                function("Other.ini", "General", "sName", "Value")
+            );
+            assert_ne!(
+                function("MyMod.ini", "General", "sName", "Value"),
+                function("MyMod.ini", "Display", "sName", "Value")
+            );
+            assert_ne!(
+                function("MyMod.ini", "General", "sName", "Value"),
+                function("MyMod.ini", "General", "sOther", "Value")
+            );
+            assert_ne!(
+                function("MyMod.ini", "General", "sName", "Value"),
+                function("MyMod.ini", "General", "sName", "Other")
+            );
+        }
+
+        #[test]
+        fn function_eq_for_version_in_range_should_check_pathbuf_and_bounds() {
+            assert_eq!(
+                Function::VersionInRange("App.exe".into(), "1.2A".into(), "1.4".into()),
+                Function::VersionInRange("app.exe".into(), "1.2a".into(), "1.4".into())
+            );
+
+            assert_ne!(
+                Function::VersionInRange("app.exe".into(), "1.2".into(), "1.4".into()),
+                Function::VersionInRange("app.exe".into(), "1.2".into(), "1.5".into())
+            );
+            assert_ne!(
+                Function::VersionInRange("app.exe".into(), "1.2".into(), "1.4".into()),
+                Function::VersionInRange("app.exe".into(), "1.1".into(), "1.4".into())
+            );
+            assert_ne!(
+                Function::VersionInRange("app.exe".into(), "1.2".into(), "1.4".into()),
+                Function::ProductVersionInRange("app.exe".into(), "1.2".into(), "1.4".into())
+            );
+        }
+
+        #[test]
+        fn function_eq_for_checksum_manifest_should_check_pathbuf_case_insensitively() {
+            assert_eq!(
+                Function::ChecksumManifest("Checksums.sfv".into()),
+                Function::ChecksumManifest("checksums.SFV".into())
+            );
+
+            assert_ne!(
+                Function::ChecksumManifest("checksums.sfv".into()),
+                Function::ChecksumManifest("other.sfv".into())
+            );
+            assert_ne!(
+                Function::ChecksumManifest("checksums.sfv".into()),
+                Function::FilePath("checksums.sfv".into())
+            );
+        }
+
+        #[test]
+        fn function_eq_for_version_cmp_should_check_pathbufs_and_comparator() {
+            assert_eq!(
+                Function::VersionCmp("A.exe".into(), "B.exe".into(), ComparisonOperator::Equal),
+                Function::VersionCmp("a.exe".into(), "b.exe".into(), ComparisonOperator::Equal)
+            );
+
+            assert_ne!(
+                Function::VersionCmp("a.exe".into(), "b.exe".into(), ComparisonOperator::Equal),
+                Function::VersionCmp("a.exe".into(), "b.exe".into(), ComparisonOperator::LessThan)
+            );
+            assert_ne!(
+                Function::VersionCmp("a.exe".into(), "b.exe".into(), ComparisonOperator::Equal),
+                Function::VersionCmp("b.exe".into(), "a.exe".into(), ComparisonOperator::Equal)
+            );
+        }
+
+        #[test]
+        fn function_eq_for_description_regex_should_check_all_arguments_case_insensitively() {
+            assert_eq!(
+                Function::DescriptionRegex("Patches".into(), regex("a.*"), regex("text")),
+                Function::DescriptionRegex("patches".into(), regex("A.*"), regex("TEXT"))
+            );
+
+            assert_ne!(
+                Function::DescriptionRegex("Patches".into(), regex("a.*"), regex("text")),
+                Function::DescriptionRegex("other".into(), regex("a.*"), regex("text"))
             );
             assert_ne!(
-                Function::FilenameVersion(
-                    "subdir".into(),
-                    regex("Blank\\.esm"),
-                    "1".into(),
-                    ComparisonOperator::Equal
-                ),
-                Function::FilenameVersion(
-                    "subdir".into(),
-                    regex("Blank\\.esm"),
-                    "2".into(),
-                    ComparisonOperator::Equal
-                )
+                Function::DescriptionRegex("Patches".into(), regex("a.*"), regex("text")),
+                Function::DescriptionRegex("Patches".into(), regex("b.*"), regex("text"))
             );
             assert_ne!(
-                Function::FilenameVersion(
-                    "subdir".into(),
-                    regex("Blank\\.esm"),
-                    "1".into(),
-                    ComparisonOperator::Equal
-                ),
-                Function::FilenameVersion(
-                    "subdir".into(),
-                    regex("Blank\\.esm"),
-                    "1".into(),
-                    ComparisonOperator::NotEqual
-                )
+                Function::DescriptionRegex("Patches".into(), regex("a.*"), regex("text")),
+                Function::DescriptionRegex("Patches".into(), regex("a.*"), regex("other"))
             );
         }
 
         #[test]
-        fn function_eq_for_filename_version_should_be_case_insensitive_on_pathbuf_and_version() {
+        fn function_eq_for_author_contains_should_not_be_equal_to_description_contains() {
             assert_eq!(
-                Function::FilenameVersion(
-                    "subdir".into(),
-                    regex("Blank\\.esm"),
-                    "A".into(),
-                    ComparisonOperator::Equal
-                ),
-                Function::FilenameVersion(
-                    "Subdir".into(),
-                    regex("Blank\\.esm"),
-                    "a".into(),
-                    ComparisonOperator::Equal
-                )
+                Function::AuthorContains("Blank.esp".into(), regex("someone")),
+                Function::AuthorContains("blank.esp".into(), regex("SOMEONE"))
+            );
+
+            assert_ne!(
+                Function::AuthorContains("Blank.esp".into(), regex("someone")),
+                Function::DescriptionContains("Blank.esp".into(), regex("someone"))
             );
         }
 
         #[test]
-        fn function_eq_for_description_contains_should_check_pathbuf_and_regex() {
+        fn function_eq_for_description_contains_text_should_be_case_insensitive() {
             assert_eq!(
-                Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII)),
-                Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII))
+                Function::DescriptionContainsText("Blank.esp".into(), LOWERCASE_NON_ASCII.into()),
+                Function::DescriptionContainsText("blank.esp".into(), UPPERCASE_NON_ASCII.into())
             );
 
             assert_ne!(
-                Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII)),
-                Function::DescriptionContains("Blank.esp".into(), regex(".*"))
+                Function::DescriptionContainsText("Blank.esp".into(), "text".into()),
+                Function::DescriptionContainsText("Blank.esp".into(), "other".into())
             );
             assert_ne!(
-                Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII)),
-                Function::DescriptionContains("other".into(), regex(LOWERCASE_NON_ASCII))
+                Function::DescriptionContainsText("Blank.esp".into(), "text".into()),
+                Function::DescriptionContains("Blank.esp".into(), regex("text"))
             );
         }
 
         #[test]
-        fn function_eq_for_description_contains_should_be_case_insensitive_on_pathbuf_and_regex() {
+        fn function_eq_for_is_newer_than_should_check_both_pathbufs_case_insensitively() {
             assert_eq!(
-                Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII)),
-                Function::DescriptionContains("blank.esp".into(), regex(UPPERCASE_NON_ASCII))
+                Function::IsNewerThan("Patch.esp".into(), "Blank.esp".into()),
+                Function::IsNewerThan("patch.esp".into(), "blank.esp".into())
             );
-        }
 
-        #[test]
-        fn function_eq_description_contains_should_not_be_equal_to_file_regex_with_same_pathbuf_and_regex(
-        ) {
             assert_ne!(
-                Function::DescriptionContains("Blank.esp".into(), regex(LOWERCASE_NON_ASCII)),
-                Function::FileRegex("Blank.esp".into(), regex(LOWERCASE_NON_ASCII))
+                Function::IsNewerThan("Patch.esp".into(), "Blank.esp".into()),
+                Function::IsNewerThan("Blank.esp".into(), "Patch.esp".into())
+            );
+            assert_ne!(
+                Function::IsNewerThan("Patch.esp".into(), "Blank.esp".into()),
+                Function::IsNewerThan("Patch.esp".into(), "other".into())
             );
         }
     }
@@ -902,30 +2553,70 @@ mod tests {
 
         #[test]
         fn function_hash_file_size_should_hash_pathbuf_and_size() {
-            let function1 = Function::FileSize("subdir".into(), 1);
-            let function2 = Function::FileSize("subdir".into(), 1);
+            let function1 = Function::FileSize("subdir".into(), 1, ComparisonOperator::Equal);
+            let function2 = Function::FileSize("subdir".into(), 1, ComparisonOperator::Equal);
 
             assert_eq!(hash(&function1), hash(&function2));
 
-            let function1 = Function::FileSize("subdir".into(), 1);
-            let function2 = Function::FileSize("other".into(), 1);
+            let function1 = Function::FileSize("subdir".into(), 1, ComparisonOperator::Equal);
+            let function2 = Function::FileSize("other".into(), 1, ComparisonOperator::Equal);
 
             assert_ne!(hash(&function1), hash(&function2));
 
-            let function1 = Function::FileSize("subdir".into(), 1);
-            let function2 = Function::FileSize("subdir".into(), 2);
+            let function1 = Function::FileSize("subdir".into(), 1, ComparisonOperator::Equal);
+            let function2 = Function::FileSize("subdir".into(), 2, ComparisonOperator::Equal);
 
             assert_ne!(hash(&function1), hash(&function2));
         }
 
         #[test]
         fn function_hash_file_size_should_be_case_insensitive() {
-            let function1 = Function::FileSize("Subdir".into(), 1);
-            let function2 = Function::FileSize("subdir".into(), 1);
+            let function1 = Function::FileSize("Subdir".into(), 1, ComparisonOperator::Equal);
+            let function2 = Function::FileSize("subdir".into(), 1, ComparisonOperator::Equal);
 
             assert_eq!(hash(&function1), hash(&function2));
         }
 
+        #[test]
+        fn function_hash_file_mtime_should_hash_pathbuf_comparator_and_timestamp() {
+            let function = |path: &str, comparator, time| {
+                Function::FileModifiedTime(path.into(), comparator, timestamp(time))
+            };
+
+            assert_eq!(
+                hash(&function(
+                    "Subdir",
+                    ComparisonOperator::LessThan,
+                    "2024-01-01"
+                )),
+                hash(&function(
+                    "subdir",
+                    ComparisonOperator::LessThan,
+                    "2024-01-01"
+                ))
+            );
+            assert_ne!(
+                hash(&function(
+                    "subdir",
+                    ComparisonOperator::LessThan,
+                    "2024-01-01"
+                )),
+                hash(&function("subdir", ComparisonOperator::Equal, "2024-01-01"))
+            );
+            assert_ne!(
+                hash(&function(
+                    "subdir",
+                    ComparisonOperator::LessThan,
+                    "2024-01-01"
+                )),
+                hash(&function(
+                    "subdir",
+                    ComparisonOperator::LessThan,
+                    "2024-01-02"
+                ))
+            );
+        }
+
         #[test]
         fn function_hash_readable_should_hash_pathbuf() {
             let function1 = Function::Readable("Blank.esm".into());
@@ -947,6 +2638,14 @@ mod tests {
             assert_eq!(hash(&function1), hash(&function2));
         }
 
+        #[test]
+        fn function_hash_readable_and_writable_should_not_have_equal_hashes() {
+            let function1 = Function::Readable("Blank.esm".into());
+            let function2 = Function::Writable("Blank.esm".into());
+
+            assert_ne!(hash(&function1), hash(&function2));
+        }
+
         #[test]
         fn function_hash_file_path_and_readable_should_not_have_equal_hashes() {
             let function1 = Function::FilePath("Blank.esm".into());
@@ -1066,6 +2765,68 @@ mod tests {
             assert_eq!(hash(&function1), hash(&function2));
         }
 
+        #[test]
+        fn function_hash_record_count_should_hash_pathbuf_comparator_and_count() {
+            let function = |path: &str, comparator, count| {
+                Function::RecordCount(path.into(), comparator, count)
+            };
+
+            assert_eq!(
+                hash(&function("Blank.esp", ComparisonOperator::Equal, 1)),
+                hash(&function("blank.ESP", ComparisonOperator::Equal, 1))
+            );
+            assert_ne!(
+                hash(&function("Blank.esp", ComparisonOperator::Equal, 1)),
+                hash(&function("Blank.esp", ComparisonOperator::LessThan, 1))
+            );
+            assert_ne!(
+                hash(&function("Blank.esp", ComparisonOperator::Equal, 1)),
+                hash(&function("Blank.esp", ComparisonOperator::Equal, 2))
+            );
+        }
+
+        #[test]
+        fn function_hash_masters_contain_should_be_case_insensitive() {
+            let function1 = Function::MastersContain("Child.esp".into(), "Master.esm".into());
+            let function2 = Function::MastersContain("child.ESP".into(), "master.ESM".into());
+
+            assert_eq!(hash(&function1), hash(&function2));
+
+            let function1 = Function::MastersContain("Child.esp".into(), "Master.esm".into());
+            let function2 =
+                Function::DescriptionContainsText("Child.esp".into(), "Master.esm".into());
+
+            assert_ne!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_header_version_should_hash_pathbuf_comparator_and_version() {
+            let function = |path: &str, comparator, version| {
+                Function::HeaderVersion(path.into(), comparator, version)
+            };
+
+            assert_eq!(
+                hash(&function("Blank.esp", ComparisonOperator::Equal, 1.7)),
+                hash(&function("blank.ESP", ComparisonOperator::Equal, 1.7))
+            );
+            assert_ne!(
+                hash(&function("Blank.esp", ComparisonOperator::Equal, 1.7)),
+                hash(&function("Blank.esp", ComparisonOperator::LessThan, 1.7))
+            );
+            assert_ne!(
+                hash(&function("Blank.esp", ComparisonOperator::Equal, 1.7)),
+                hash(&function("Blank.esp", ComparisonOperator::Equal, 0.94))
+            );
+        }
+
+        #[test]
+        fn function_hash_is_light_plugin_and_is_master_should_not_have_equal_hashes() {
+            let function1 = Function::IsLightPlugin("Blank.esm".into());
+            let function2 = Function::IsMaster("Blank.esm".into());
+
+            assert_ne!(hash(&function1), hash(&function2));
+        }
+
         #[test]
         fn function_hash_file_path_and_is_master_should_not_have_equal_hashes() {
             let function1 = Function::FilePath("Blank.esm".into());
@@ -1116,6 +2877,32 @@ mod tests {
             assert_eq!(hash(&function1), hash(&function2));
         }
 
+        #[test]
+        fn function_hash_active_count_should_be_case_insensitive() {
+            let function1 = Function::ActiveCount(regex("Blank.*"), ComparisonOperator::Equal, 2);
+            let function2 = Function::ActiveCount(regex("blank.*"), ComparisonOperator::Equal, 2);
+
+            assert_eq!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_count_should_be_case_insensitive() {
+            let function1 = Function::Count(
+                "Subdir".into(),
+                regex("Blank.*"),
+                ComparisonOperator::Equal,
+                2,
+            );
+            let function2 = Function::Count(
+                "subdir".into(),
+                regex("blank.*"),
+                ComparisonOperator::Equal,
+                2,
+            );
+
+            assert_eq!(hash(&function1), hash(&function2));
+        }
+
         #[test]
         fn function_hash_file_regex_and_many_should_not_have_equal_hashes() {
             let function1 = Function::FileRegex("subdir".into(), regex(".*"));
@@ -1155,26 +2942,26 @@ mod tests {
 
         #[test]
         fn function_hash_checksum_should_hash_pathbuf_and_regex() {
-            let function1 = Function::Checksum("subdir".into(), 1);
-            let function2 = Function::Checksum("subdir".into(), 1);
+            let function1 = Function::Checksum("subdir".into(), vec![1]);
+            let function2 = Function::Checksum("subdir".into(), vec![1]);
 
             assert_eq!(hash(&function1), hash(&function2));
 
-            let function1 = Function::Checksum("subdir".into(), 1);
-            let function2 = Function::Checksum("other".into(), 1);
+            let function1 = Function::Checksum("subdir".into(), vec![1]);
+            let function2 = Function::Checksum("other".into(), vec![1]);
 
             assert_ne!(hash(&function1), hash(&function2));
 
-            let function1 = Function::Checksum("subdir".into(), 1);
-            let function2 = Function::Checksum("subdir".into(), 2);
+            let function1 = Function::Checksum("subdir".into(), vec![1]);
+            let function2 = Function::Checksum("subdir".into(), vec![2]);
 
             assert_ne!(hash(&function1), hash(&function2));
         }
 
         #[test]
         fn function_hash_checksum_should_be_case_insensitive() {
-            let function1 = Function::Checksum("Blank.esm".into(), 1);
-            let function2 = Function::Checksum("Blank.esm".into(), 1);
+            let function1 = Function::Checksum("Blank.esm".into(), vec![1]);
+            let function2 = Function::Checksum("Blank.esm".into(), vec![1]);
 
             assert_eq!(hash(&function1), hash(&function2));
         }
@@ -1413,5 +3200,185 @@ mod tests {
 
             assert_ne!(hash(&function1), hash(&function2));
         }
+
+        #[test]
+        fn function_hash_file_content_contains_should_be_case_insensitive() {
+            let function1 = Function::FileContentContains("foo.ini".into(), regex("bPatch=1"));
+            let function2 = Function::FileContentContains("FOO.ini".into(), regex("bpatch=1"));
+
+            assert_eq!(hash(&function1), hash(&function2));
+
+            let function1 = Function::FileContentContains("foo.ini".into(), regex("bPatch=1"));
+            let function2 = Function::DescriptionContains("foo.ini".into(), regex("bPatch=1"));
+
+            assert_ne!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_archive_contains_should_be_case_insensitive() {
+            let function1 =
+                Function::ArchiveContains("Textures.bsa".into(), "textures/a.dds".into());
+            let function2 =
+                Function::ArchiveContains("textures.BSA".into(), "TEXTURES/A.dds".into());
+
+            assert_eq!(hash(&function1), hash(&function2));
+
+            let function2 =
+                Function::MastersContain("Textures.bsa".into(), "textures/a.dds".into());
+
+            assert_ne!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_archive_regex_should_be_case_insensitive() {
+            let function1 = Function::ArchiveRegex("Main.ba2".into(), regex("meshes/.*"));
+            let function2 = Function::ArchiveRegex("main.BA2".into(), regex("MESHES/.*"));
+
+            assert_eq!(hash(&function1), hash(&function2));
+
+            let function2 = Function::FileRegex("Main.ba2".into(), regex("meshes/.*"));
+
+            assert_ne!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_archive_version_should_be_case_insensitive() {
+            let function1 =
+                Function::ArchiveVersion("Archive.ba2".into(), ComparisonOperator::Equal, 1);
+            let function2 =
+                Function::ArchiveVersion("archive.BA2".into(), ComparisonOperator::Equal, 1);
+
+            assert_eq!(hash(&function1), hash(&function2));
+
+            let function2 =
+                Function::RecordCount("Archive.ba2".into(), ComparisonOperator::Equal, 1);
+
+            assert_ne!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_is_platform_should_hash_the_platform() {
+            assert_eq!(
+                hash(&Function::IsPlatform(Platform::MacOs)),
+                hash(&Function::IsPlatform(Platform::MacOs))
+            );
+            assert_ne!(
+                hash(&Function::IsPlatform(Platform::MacOs)),
+                hash(&Function::IsPlatform(Platform::Linux))
+            );
+        }
+
+        #[test]
+        fn function_hash_registry_value_should_be_case_insensitive() {
+            let function1 =
+                Function::RegistryValue("HKLM\\Software".into(), "Path".into(), regex("steam"));
+            let function2 =
+                Function::RegistryValue("hklm\\SOFTWARE".into(), "path".into(), regex("Steam"));
+
+            assert_eq!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_ini_value_should_hash_all_arguments_case_insensitively() {
+            let function = |path: &str, section: &str, key: &str, value: &str| {
+                Function::IniValue(path.into(), section.into(), key.into(), value.into())
+            };
+
+            assert_eq!(
+                hash(&function("MyMod.ini", "General", "sName", "Value")),
+                hash(&function("mymod.INI", "GENERAL", "sname", "VALUE"))
+            );
+            assert_ne!(
+                hash(&function("MyMod.ini", "General", "sName", "Value")),
+                hash(&function("MyMod.ini", "General", "sName", "Other"))
+            );
+        }
+
+        #[test]
+        fn function_hash_is_newer_than_should_hash_both_pathbufs_case_insensitively() {
+            let function1 = Function::IsNewerThan("Patch.esp".into(), "Blank.esp".into());
+            let function2 = Function::IsNewerThan("patch.esp".into(), "blank.esp".into());
+
+            assert_eq!(hash(&function1), hash(&function2));
+
+            let function1 = Function::IsNewerThan("Patch.esp".into(), "Blank.esp".into());
+            let function2 = Function::IsNewerThan("Patch.esp".into(), "other".into());
+
+            assert_ne!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_version_in_range_should_hash_pathbuf_and_bounds() {
+            let function1 = Function::VersionInRange("App.exe".into(), "1.2".into(), "1.4".into());
+            let function2 = Function::VersionInRange("app.exe".into(), "1.2".into(), "1.4".into());
+
+            assert_eq!(hash(&function1), hash(&function2));
+
+            let function1 = Function::VersionInRange("app.exe".into(), "1.2".into(), "1.4".into());
+            let function2 = Function::VersionInRange("app.exe".into(), "1.2".into(), "1.5".into());
+
+            assert_ne!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_checksum_manifest_should_be_case_insensitive() {
+            let function1 = Function::ChecksumManifest("Checksums.sfv".into());
+            let function2 = Function::ChecksumManifest("checksums.SFV".into());
+
+            assert_eq!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_file_path_and_checksum_manifest_should_not_have_equal_hashes() {
+            let function1 = Function::FilePath("checksums.sfv".into());
+            let function2 = Function::ChecksumManifest("checksums.sfv".into());
+
+            assert_ne!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_version_cmp_should_hash_pathbufs_and_comparator() {
+            let function1 =
+                Function::VersionCmp("A.exe".into(), "B.exe".into(), ComparisonOperator::Equal);
+            let function2 =
+                Function::VersionCmp("a.exe".into(), "b.exe".into(), ComparisonOperator::Equal);
+
+            assert_eq!(hash(&function1), hash(&function2));
+
+            let function1 =
+                Function::VersionCmp("a.exe".into(), "b.exe".into(), ComparisonOperator::Equal);
+            let function2 =
+                Function::VersionCmp("a.exe".into(), "b.exe".into(), ComparisonOperator::NotEqual);
+
+            assert_ne!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_description_regex_should_be_case_insensitive() {
+            let function1 =
+                Function::DescriptionRegex("Patches".into(), regex("a.*"), regex("text"));
+            let function2 =
+                Function::DescriptionRegex("patches".into(), regex("A.*"), regex("TEXT"));
+
+            assert_eq!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_author_contains_and_description_contains_should_not_have_equal_hashes() {
+            let function1 = Function::AuthorContains("Blank.esp".into(), regex("someone"));
+            let function2 = Function::DescriptionContains("Blank.esp".into(), regex("someone"));
+
+            assert_ne!(hash(&function1), hash(&function2));
+        }
+
+        #[test]
+        fn function_hash_description_contains_text_should_be_case_insensitive() {
+            let function1 =
+                Function::DescriptionContainsText("Blank.esp".into(), LOWERCASE_NON_ASCII.into());
+            let function2 =
+                Function::DescriptionContainsText("blank.esp".into(), UPPERCASE_NON_ASCII.into());
+
+            assert_eq!(hash(&function1), hash(&function2));
+        }
     }
 }
@@ -5,17 +5,26 @@ use nom::branch::alt;
 use nom::bytes::complete::{is_not, tag};
 use nom::character::complete::digit1;
 use nom::character::complete::hex_digit1;
-use nom::combinator::{map, map_parser, value};
-use nom::sequence::delimited;
+use nom::character::complete::{char, satisfy};
+use nom::combinator::{map, map_opt, map_parser, opt, recognize, value};
+use nom::error::ErrorKind;
+use nom::multi::many1;
+use nom::sequence::{delimited, pair, preceded};
 use nom::{Err, IResult, Parser};
-use regex::{Regex, RegexBuilder};
-
-use super::{ComparisonOperator, Function};
-use crate::error::ParsingErrorKind;
-use crate::{map_err, whitespace, ParsingResult};
+#[cfg(feature = "regex")]
+use regex::RegexBuilder;
+
+use super::{
+    ini, registry, Architecture, ComparisonOperator, Function, Platform, Regex, Timestamp,
+};
+use crate::error::{ParsingError, ParsingErrorKind};
+use crate::{
+    is_multispace, map_err, token, variables, whitespace, whitespace_token, ParseOptions,
+    ParsingResult,
+};
 
 impl ComparisonOperator {
-    pub fn parse(input: &str) -> IResult<&str, ComparisonOperator> {
+    pub(crate) fn parse(input: &str) -> IResult<&str, ComparisonOperator> {
         alt((
             value(ComparisonOperator::Equal, tag("==")),
             value(ComparisonOperator::NotEqual, tag("!=")),
@@ -32,26 +41,150 @@ const INVALID_PATH_CHARS: &str = "\":*?<>|";
 const INVALID_NON_REGEX_PATH_CHARS: &str = "\":*?<>|\\"; // \ is treated as invalid to distinguish regex strings.
 const INVALID_REGEX_PATH_CHARS: &str = "\"<>";
 
-fn build_regex(input: &str) -> Result<(&'static str, Regex), regex::Error> {
-    RegexBuilder::new(input)
-        .case_insensitive(true)
-        .build()
+/// Check the given regex string against the parse options' limits, and then
+/// build a case-insensitive regex from the pattern that `to_pattern` creates
+/// from it.
+fn build_regex<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+    to_pattern: impl FnOnce(&str) -> String,
+) -> ParsingResult<'a, Regex> {
+    options
+        .check_regex(input)
+        .and_then(|()| compile_regex(&to_pattern(input), options))
         .map(|r| ("", r))
+        .map_err(|e| Err::Failure(e.at(input)))
+}
+
+#[cfg(feature = "regex")]
+pub(super) fn compile_regex(
+    pattern: &str,
+    options: &ParseOptions,
+) -> Result<Regex, ParsingErrorKind> {
+    options.cached_regex(pattern, || {
+        let mut builder = RegexBuilder::new(pattern);
+        builder.case_insensitive(true);
+        if let Some(size_limit) = options.max_regex_size() {
+            builder.size_limit(size_limit);
+        }
+
+        builder.build().map_err(ParsingErrorKind::from)
+    })
 }
 
-fn parse_regex(input: &str) -> ParsingResult<Regex> {
-    build_regex(input).map_err(|e| Err::Failure(ParsingErrorKind::from(e).at(input)))
+/// Wildcard patterns are always case-insensitive, and are too simple for a
+/// size limit to be needed.
+#[cfg(not(feature = "regex"))]
+pub(super) fn compile_regex(
+    pattern: &str,
+    options: &ParseOptions,
+) -> Result<Regex, ParsingErrorKind> {
+    options.cached_regex(pattern, || Regex::new(pattern))
 }
 
-fn parse_anchored_regex(input: &str) -> ParsingResult<Regex> {
-    build_regex(&format!("^{input}$"))
-        .map_err(|e| Err::Failure(ParsingErrorKind::from(e).at(input)))
+fn parse_regex<'a>(input: &'a str, options: &ParseOptions) -> ParsingResult<'a, Regex> {
+    build_regex(input, options, str::to_owned)
+}
+
+fn parse_anchored_regex<'a>(input: &'a str, options: &ParseOptions) -> ParsingResult<'a, Regex> {
+    build_regex(input, options, |i| format!("^{i}$"))
+}
+
+/// Parse a drive letter prefix like `C:`, so that Windows-style absolute paths
+/// can be given.
+fn parse_drive_prefix(input: &str) -> IResult<&str, &str> {
+    recognize(pair(satisfy(|c| c.is_ascii_alphabetic()), char(':'))).parse(input)
+}
+
+/// Check if the given input starts with a backslash that is the last character
+/// of a quoted argument, rather than the start of a `\"` escape sequence. A
+/// closing quote is always followed by the `,` or `)` that ends its argument,
+/// so a backslash that is followed by a double quote and then one of those is
+/// read as the end of the argument, e.g. in `file("sub\dir\")`. This keeps
+/// arguments that end in a backslash parsing as they did before escape
+/// sequences were supported.
+pub(crate) fn starts_with_trailing_backslash(input: &str) -> bool {
+    input
+        .strip_prefix("\\\"")
+        .is_some_and(|rest| matches!(rest.trim_start().chars().next(), None | Some(',' | ')')))
+}
+
+/// Parse the contents of a quoted string, up to its closing quote or the first
+/// of the given invalid characters. The escape sequences `\"` and `\\` are
+/// decoded into a double quote and a backslash, and any other backslash is
+/// kept as it is. If backslashes are invalid, only `\"` is decoded. A
+/// backslash at the end of the string is kept as it is, as described by
+/// [starts_with_trailing_backslash].
+fn parse_escaped<'a>(input: &'a str, invalid_chars: &str) -> IResult<&'a str, String> {
+    let mut string = String::new();
+    let mut chars = input.char_indices().peekable();
+    let mut end = input.len();
+    while let Some((offset, c)) = chars.next() {
+        let escaped = match chars.peek() {
+            Some(&(_, next @ '"'))
+                if c == '\\'
+                    && !starts_with_trailing_backslash(input.get(offset..).unwrap_or_default()) =>
+            {
+                Some(next)
+            }
+            Some(&(_, next @ '\\')) if c == '\\' && !invalid_chars.contains('\\') => Some(next),
+            _ => None,
+        };
+
+        if let Some(escaped) = escaped {
+            chars.next();
+            string.push(escaped);
+        } else if c == '"' || invalid_chars.contains(c) {
+            end = offset;
+            break;
+        } else {
+            string.push(c);
+        }
+    }
+
+    if string.is_empty() {
+        return Err(Err::Error(nom::error::Error::new(input, ErrorKind::IsNot)));
+    }
+
+    Ok((input.get(end..).unwrap_or_default(), string))
+}
+
+/// Recognise the contents of a quoted regex, up to its closing quote or the
+/// first of the given invalid characters. A backslash escapes the character
+/// after it, so `\"` doesn't end the regex unless the backslash is the end of
+/// the argument, as described by [starts_with_trailing_backslash]. Escape
+/// sequences are left for the regex to interpret, as `\"` and `\\` mean the
+/// same in regex syntax as they do in other quoted strings.
+fn recognize_regex<'a>(input: &'a str, invalid_chars: &str) -> IResult<&'a str, &'a str> {
+    let mut chars = input.char_indices();
+    let mut end = input.len();
+    while let Some((offset, c)) = chars.next() {
+        if c == '\\' {
+            if !starts_with_trailing_backslash(input.get(offset..).unwrap_or_default()) {
+                chars.next();
+            }
+        } else if c == '"' || invalid_chars.contains(c) {
+            end = offset;
+            break;
+        }
+    }
+
+    match input.split_at_checked(end) {
+        Some((regex, remaining_input)) if !regex.is_empty() => Ok((remaining_input, regex)),
+        _ => Err(Err::Error(nom::error::Error::new(input, ErrorKind::IsNot))),
+    }
 }
 
 fn parse_path(input: &str) -> IResult<&str, PathBuf> {
     map(
-        delimited(tag("\""), is_not(INVALID_PATH_CHARS), tag("\"")),
-        PathBuf::from,
+        delimited(
+            tag("\""),
+            pair(opt(parse_drive_prefix), |i| {
+                parse_escaped(i, INVALID_PATH_CHARS)
+            }),
+            tag("\""),
+        ),
+        |(drive, path)| PathBuf::from(format!("{}{path}", drive.unwrap_or_default())),
     )
     .parse(input)
 }
@@ -62,24 +195,27 @@ fn parse_size(input: &str) -> ParsingResult<u64> {
         .map_err(|e| Err::Failure(ParsingErrorKind::from(e).at(input)))
 }
 
-fn parse_file_size_args(input: &str) -> ParsingResult<(PathBuf, u64)> {
+fn parse_file_size_args(input: &str) -> ParsingResult<(PathBuf, u64, ComparisonOperator)> {
     let mut parser = (
         map_err(parse_path),
-        map_err(whitespace(tag(","))),
+        whitespace_token(","),
         map_parser(digit1, parse_size),
+        map_err(opt(preceded(
+            whitespace(tag(",")),
+            ComparisonOperator::parse,
+        ))),
     );
 
-    let (remaining_input, (path, _, size)) = parser.parse(input)?;
+    let (remaining_input, (path, _, size, comparator)) = parser.parse(input)?;
 
-    Ok((remaining_input, (path, size)))
+    Ok((
+        remaining_input,
+        (path, size, comparator.unwrap_or(ComparisonOperator::Equal)),
+    ))
 }
 
 fn parse_version(input: &str) -> IResult<&str, String> {
-    map(
-        delimited(tag("\""), is_not("\""), tag("\"")),
-        |version: &str| version.to_owned(),
-    )
-    .parse(input)
+    delimited(tag("\""), |i| parse_escaped(i, ""), tag("\"")).parse(input)
 }
 
 fn parse_version_args(input: &str) -> ParsingResult<(PathBuf, String, ComparisonOperator)> {
@@ -96,14 +232,15 @@ fn parse_version_args(input: &str) -> ParsingResult<(PathBuf, String, Comparison
     Ok((remaining_input, (path, version, comparator)))
 }
 
-fn parse_filename_version_args(
-    input: &str,
-) -> ParsingResult<(PathBuf, Regex, String, ComparisonOperator)> {
+fn parse_filename_version_args<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> ParsingResult<'a, (PathBuf, Regex, String, ComparisonOperator)> {
     let mut parser = (
-        delimited(map_err(tag("\"")), parse_regex_path, map_err(tag("\""))),
-        map_err(whitespace(tag(","))),
+        delimited(token("\""), |i| parse_regex_path(i, options), token("\"")),
+        whitespace_token(","),
         map_err(parse_version),
-        map_err(whitespace(tag(","))),
+        whitespace_token(","),
         map_err(ComparisonOperator::parse),
     );
 
@@ -118,14 +255,125 @@ fn parse_filename_version_args(
     Ok((remaining_input, (path, regex, version, comparator)))
 }
 
-fn parse_description_contains_args(input: &str) -> ParsingResult<(PathBuf, Regex)> {
+fn parse_description_contains_args<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> ParsingResult<'a, (PathBuf, Regex)> {
+    let mut parser = (
+        map_err(parse_path),
+        whitespace_token(","),
+        delimited(
+            token("\""),
+            map_parser(map_err(|i| recognize_regex(i, "")), |i| {
+                parse_regex(i, options)
+            }),
+            token("\""),
+        ),
+    );
+
+    let (remaining_input, (path, _, regex)) = parser.parse(input)?;
+
+    Ok((remaining_input, (path, regex)))
+}
+
+/// Build a regex pattern that matches the same filenames as the given glob:
+/// `*` matches any sequence of characters, `?` matches any single character,
+/// and every other character matches itself.
+fn glob_to_pattern(glob: &str) -> String {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            '\\' | '.' | '+' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$' | '#' | '&'
+            | '-' | '~' => {
+                pattern.push('\\');
+                pattern.push(c);
+            }
+            c => pattern.push(c),
+        }
+    }
+    pattern.push('$');
+
+    pattern
+}
+
+/// Parse a glob that must match a whole filename, so can't contain directory
+/// separators.
+fn parse_glob<'a>(input: &'a str, options: &ParseOptions) -> ParsingResult<'a, Regex> {
+    if input.contains(['/', '\\']) {
+        return Err(Err::Failure(
+            ParsingErrorKind::InvalidGlob(input.into()).at(input),
+        ));
+    }
+
+    let pattern = glob_to_pattern(input);
+    options
+        .check_regex(&pattern)
+        .and_then(|()| compile_regex(&pattern, options))
+        .map(|r| ("", r))
+        .map_err(|e| Err::Failure(e.at(input)))
+}
+
+fn parse_file_glob_args<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> ParsingResult<'a, (PathBuf, String, Regex)> {
+    let mut parser = (
+        map_err(parse_path),
+        whitespace_token(","),
+        delimited(
+            token("\""),
+            map_parser(is_not(INVALID_REGEX_PATH_CHARS), |i: &'a str| {
+                parse_glob(i, options).map(|(r, regex)| (r, (i.to_owned(), regex)))
+            }),
+            token("\""),
+        ),
+    );
+
+    let (remaining_input, (path, _, (glob, regex))) = parser.parse(input)?;
+
+    Ok((remaining_input, (path, glob, regex)))
+}
+
+/// Parse a regex path followed by a quoted regex that only needs to match part
+/// of a plugin's description.
+fn parse_description_regex_args<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> ParsingResult<'a, (PathBuf, Regex, Regex)> {
+    let mut parser = (
+        delimited(token("\""), |i| parse_regex_path(i, options), token("\"")),
+        whitespace_token(","),
+        delimited(
+            token("\""),
+            map_parser(map_err(|i| recognize_regex(i, "")), |i| {
+                parse_regex(i, options)
+            }),
+            token("\""),
+        ),
+    );
+
+    let (remaining_input, ((path, regex), _, description_regex)) = parser.parse(input)?;
+
+    Ok((remaining_input, (path, regex, description_regex)))
+}
+
+/// Parse an archive path followed by a quoted regex that must match the whole
+/// path of a file in the archive.
+fn parse_archive_regex_args<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> ParsingResult<'a, (PathBuf, Regex)> {
     let mut parser = (
         map_err(parse_path),
-        map_err(whitespace(tag(","))),
+        whitespace_token(","),
         delimited(
-            map_err(tag("\"")),
-            map_parser(is_not("\""), parse_regex),
-            map_err(tag("\"")),
+            token("\""),
+            map_parser(map_err(|i| recognize_regex(i, "")), |i| {
+                parse_anchored_regex(i, options)
+            }),
+            token("\""),
         ),
     );
 
@@ -134,28 +382,314 @@ fn parse_description_contains_args(input: &str) -> ParsingResult<(PathBuf, Regex
     Ok((remaining_input, (path, regex)))
 }
 
+/// Parse a quoted inclusive version range like `"1.2 - 1.4"` into its lower
+/// and upper bounds.
+fn parse_version_range(input: &str) -> IResult<&str, (String, String)> {
+    map_opt(parse_version, |range| {
+        range
+            .split_once(" - ")
+            .map(|(lower, upper)| (lower.trim(), upper.trim()))
+            .filter(|(lower, upper)| !lower.is_empty() && !upper.is_empty())
+            .map(|(lower, upper)| (lower.to_owned(), upper.to_owned()))
+    })
+    .parse(input)
+}
+
+fn parse_version_range_args(input: &str) -> ParsingResult<'_, (PathBuf, String, String)> {
+    let parser = (parse_path, whitespace(tag(",")), parse_version_range);
+
+    let (remaining_input, (path, _, (lower, upper))) = map_err(parser).parse(input)?;
+
+    Ok((remaining_input, (path, lower, upper)))
+}
+
+fn parse_version_cmp_args(
+    input: &str,
+) -> ParsingResult<'_, (PathBuf, PathBuf, ComparisonOperator)> {
+    let parser = (
+        parse_path,
+        whitespace(tag(",")),
+        parse_path,
+        whitespace(tag(",")),
+        ComparisonOperator::parse,
+    );
+
+    let (remaining_input, (path1, _, path2, _, comparator)) = map_err(parser).parse(input)?;
+
+    Ok((remaining_input, (path1, path2, comparator)))
+}
+
+fn parse_is_newer_than_args(input: &str) -> ParsingResult<'_, (PathBuf, PathBuf)> {
+    let parser = (parse_path, whitespace(tag(",")), parse_path);
+
+    let (remaining_input, (path1, _, path2)) = map_err(parser).parse(input)?;
+
+    Ok((remaining_input, (path1, path2)))
+}
+
+/// The text is a quoted string, like a version string.
+fn parse_description_contains_text_args(input: &str) -> ParsingResult<'_, (PathBuf, String)> {
+    let parser = (parse_path, whitespace(tag(",")), parse_version);
+
+    let (remaining_input, (path, _, text)) = map_err(parser).parse(input)?;
+
+    Ok((remaining_input, (path, text)))
+}
+
+/// Parse a quoted `ini_value()` argument, failing if the given check finds
+/// that it can't match anything in an INI file.
+fn parse_ini_argument(input: &str, is_valid: fn(&str) -> bool) -> ParsingResult<'_, String> {
+    let (remaining_input, argument) = map_err(parse_version).parse(input)?;
+
+    if is_valid(&argument) {
+        Ok((remaining_input, argument))
+    } else {
+        Err(Err::Failure(
+            ParsingErrorKind::InvalidIniArgument(argument).at(input),
+        ))
+    }
+}
+
+/// Parse a quoted registry key path, a quoted value name and a quoted regex
+/// that only needs to match part of the value.
+fn parse_registry_value_args<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> ParsingResult<'a, (String, String, Regex)> {
+    let mut parser = (
+        map_err(parse_version),
+        whitespace_token(","),
+        map_err(parse_version),
+        whitespace_token(","),
+        delimited(
+            token("\""),
+            map_parser(map_err(|i| recognize_regex(i, "")), |i| {
+                parse_regex(i, options)
+            }),
+            token("\""),
+        ),
+    );
+
+    let (remaining_input, (key, _, value_name, _, regex)) = parser.parse(input)?;
+
+    if registry::split_key(&key).is_none() {
+        return Err(Err::Failure(
+            ParsingErrorKind::InvalidRegistryKey(key).at(input),
+        ));
+    }
+
+    Ok((remaining_input, (key, value_name, regex)))
+}
+
+fn parse_ini_value_args(input: &str) -> ParsingResult<'_, (PathBuf, String, String, String)> {
+    let mut parser = (
+        map_err(parse_path),
+        whitespace_token(","),
+        |i| parse_ini_argument(i, ini::is_valid_section),
+        whitespace_token(","),
+        |i| parse_ini_argument(i, ini::is_valid_key),
+        whitespace_token(","),
+        |i| parse_ini_argument(i, ini::is_valid_value),
+    );
+
+    let (remaining_input, (path, _, section, _, key, _, value)) = parser.parse(input)?;
+
+    Ok((remaining_input, (path, section, key, value)))
+}
+
 fn parse_crc(input: &str) -> ParsingResult<u32> {
     u32::from_str_radix(input, 16)
         .map(|c| ("", c))
         .map_err(|e| Err::Failure(ParsingErrorKind::from(e).at(input)))
 }
 
-fn parse_checksum_args(input: &str) -> ParsingResult<(PathBuf, u32)> {
+/// Parse a path followed by one or more CRCs that are accepted for it.
+fn parse_checksum_args(input: &str) -> ParsingResult<'_, (PathBuf, Vec<u32>)> {
+    let mut parser = (
+        map_err(parse_path),
+        many1(preceded(
+            whitespace_token(","),
+            map_parser(hex_digit1, parse_crc),
+        )),
+    );
+
+    let (remaining_input, (path, crcs)) = parser.parse(input)?;
+
+    Ok((remaining_input, (path, crcs)))
+}
+
+/// Parse a plugin header version, which is written as a decimal number like
+/// `1.7`.
+fn parse_header_version(input: &str) -> IResult<&str, f32> {
+    map_opt(
+        recognize(pair(digit1, opt(pair(char('.'), digit1)))),
+        |version: &str| version.parse().ok(),
+    )
+    .parse(input)
+}
+
+fn parse_header_version_args(input: &str) -> ParsingResult<'_, (PathBuf, ComparisonOperator, f32)> {
+    let parser = (
+        parse_path,
+        whitespace(tag(",")),
+        ComparisonOperator::parse,
+        whitespace(tag(",")),
+        parse_header_version,
+    );
+
+    let (remaining_input, (path, _, comparator, _, version)) = map_err(parser).parse(input)?;
+
+    Ok((remaining_input, (path, comparator, version)))
+}
+
+/// Parse a regex path, a comparison operator and a non-negative integer.
+fn parse_depth(input: &str) -> ParsingResult<'_, u32> {
+    str::parse(input)
+        .map(|d| ("", d))
+        .map_err(|e| Err::Failure(ParsingErrorKind::from(e).at(input)))
+}
+
+/// Parse a quoted regex path followed by the number of levels of
+/// subdirectories to also search.
+fn parse_recursive_regex_path_args<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> ParsingResult<'a, (PathBuf, Regex, u32)> {
+    let mut parser = (
+        delimited(token("\""), |i| parse_regex_path(i, options), token("\"")),
+        whitespace_token(","),
+        map_parser(digit1, parse_depth),
+    );
+
+    let (remaining_input, ((path, regex), _, depth)) = parser.parse(input)?;
+
+    Ok((remaining_input, (path, regex, depth)))
+}
+
+fn parse_count_args<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> ParsingResult<'a, (PathBuf, Regex, ComparisonOperator, u64)> {
+    let mut parser = (
+        delimited(token("\""), |i| parse_regex_path(i, options), token("\"")),
+        whitespace_token(","),
+        map_err(ComparisonOperator::parse),
+        whitespace_token(","),
+        map_parser(digit1, parse_size),
+    );
+
+    let (remaining_input, ((path, regex), _, comparator, _, count)) = parser.parse(input)?;
+
+    Ok((remaining_input, (path, regex, comparator, count)))
+}
+
+/// Parse a quoted plugin filename regex, a comparison operator and a
+/// non-negative integer.
+fn parse_active_count_args<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> ParsingResult<'a, (Regex, ComparisonOperator, u64)> {
+    let mut parser = (
+        delimited(
+            token("\""),
+            |i| parse_regex_filename(i, options),
+            token("\""),
+        ),
+        whitespace_token(","),
+        map_err(ComparisonOperator::parse),
+        whitespace_token(","),
+        map_parser(digit1, parse_size),
+    );
+
+    let (remaining_input, (regex, _, comparator, _, count)) = parser.parse(input)?;
+
+    Ok((remaining_input, (regex, comparator, count)))
+}
+
+/// Parse a path, a comparison operator and a non-negative integer, as used
+/// by functions that compare a number read from a file.
+fn parse_record_count_args(input: &str) -> ParsingResult<'_, (PathBuf, ComparisonOperator, u64)> {
+    let mut parser = (
+        map_err(parse_path),
+        whitespace_token(","),
+        map_err(ComparisonOperator::parse),
+        whitespace_token(","),
+        map_parser(digit1, parse_size),
+    );
+
+    let (remaining_input, (path, _, comparator, _, count)) = parser.parse(input)?;
+
+    Ok((remaining_input, (path, comparator, count)))
+}
+
+/// Parse a quoted SHA-256 digest written as 64 hexadecimal digits, in either
+/// case.
+fn parse_sha256(input: &str) -> ParsingResult<'_, [u8; 32]> {
+    let (remaining_input, digest) =
+        map_err(delimited(tag("\""), is_not("\""), tag("\""))).parse(input)?;
+
+    let bytes: Option<Vec<u8>> = digest
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            str::from_utf8(pair)
+                .ok()
+                .filter(|s| s.len() == 2 && s.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|s| u8::from_str_radix(s, 16).ok())
+        })
+        .collect();
+
+    match bytes.and_then(|b| b.try_into().ok()) {
+        Some(bytes) => Ok((remaining_input, bytes)),
+        None => Err(Err::Failure(
+            ParsingErrorKind::InvalidSha256(digest.to_owned()).at(input),
+        )),
+    }
+}
+
+fn parse_checksum_sha256_args(input: &str) -> ParsingResult<'_, (PathBuf, [u8; 32])> {
+    let mut parser = (map_err(parse_path), whitespace_token(","), parse_sha256);
+
+    let (remaining_input, (path, _, digest)) = parser.parse(input)?;
+
+    Ok((remaining_input, (path, digest)))
+}
+
+fn parse_timestamp(input: &str) -> ParsingResult<'_, Timestamp> {
+    let (remaining_input, timestamp) =
+        map_err(delimited(tag("\""), is_not("\""), tag("\""))).parse(input)?;
+
+    match Timestamp::parse(timestamp) {
+        Some(timestamp) => Ok((remaining_input, timestamp)),
+        None => Err(Err::Failure(
+            ParsingErrorKind::InvalidTimestamp(timestamp.to_owned()).at(input),
+        )),
+    }
+}
+
+fn parse_file_mtime_args(
+    input: &str,
+) -> ParsingResult<'_, (PathBuf, ComparisonOperator, Timestamp)> {
     let mut parser = (
         map_err(parse_path),
-        map_err(whitespace(tag(","))),
-        map_parser(hex_digit1, parse_crc),
+        whitespace_token(","),
+        map_err(ComparisonOperator::parse),
+        whitespace_token(","),
+        parse_timestamp,
     );
 
-    let (remaining_input, (path, _, crc)) = parser.parse(input)?;
+    let (remaining_input, (path, _, comparator, _, timestamp)) = parser.parse(input)?;
 
-    Ok((remaining_input, (path, crc)))
+    Ok((remaining_input, (path, comparator, timestamp)))
 }
 
 fn parse_non_regex_path(input: &str) -> ParsingResult<PathBuf> {
-    let (remaining_input, path) = map(is_not(INVALID_NON_REGEX_PATH_CHARS), |path: &str| {
-        PathBuf::from(path)
-    })
+    let (remaining_input, path) = map_err(map(
+        pair(opt(parse_drive_prefix), |i| {
+            parse_escaped(i, INVALID_NON_REGEX_PATH_CHARS)
+        }),
+        |(drive, path)| PathBuf::from(format!("{}{path}", drive.unwrap_or_default())),
+    ))
     .parse(input)?;
 
     Ok((remaining_input, path))
@@ -163,8 +697,12 @@ fn parse_non_regex_path(input: &str) -> ParsingResult<PathBuf> {
 
 /// Parse a string that is a path where the last component is a regex string
 /// that may contain characters that are invalid in paths but valid in regex.
-fn parse_regex_path(input: &str) -> ParsingResult<(PathBuf, Regex)> {
-    let (remaining_input, string) = is_not(INVALID_REGEX_PATH_CHARS)(input)?;
+fn parse_regex_path<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> ParsingResult<'a, (PathBuf, Regex)> {
+    let (remaining_input, string) =
+        map_err(|i| recognize_regex(i, INVALID_REGEX_PATH_CHARS)).parse(input)?;
 
     if string.ends_with('/') {
         return Err(Err::Failure(
@@ -176,120 +714,198 @@ fn parse_regex_path(input: &str) -> ParsingResult<(PathBuf, Regex)> {
 
     let parent_path = PathBuf::from(parent_path_slice);
 
-    let regex = parse_anchored_regex(regex_slice)?.1;
+    let regex = parse_anchored_regex(regex_slice, options)?.1;
 
     Ok((remaining_input, (parent_path, regex)))
 }
 
-fn parse_regex_filename(input: &str) -> ParsingResult<Regex> {
-    map_parser(is_not(INVALID_REGEX_PATH_CHARS), parse_anchored_regex).parse(input)
+fn parse_regex_filename<'a>(input: &'a str, options: &ParseOptions) -> ParsingResult<'a, Regex> {
+    map_parser(
+        map_err(|i| recognize_regex(i, INVALID_REGEX_PATH_CHARS)),
+        |i| parse_anchored_regex(i, options),
+    )
+    .parse(input)
+}
+
+/// Match part of a function call that surrounds its arguments, e.g. `file("`
+/// or `")`. If the options allow lenient syntax, the function's name is
+/// matched case-insensitively, and whitespace may appear before or after the
+/// opening parenthesis and before the closing parenthesis.
+fn function_token<'a>(
+    token: &'static str,
+    options: &ParseOptions,
+) -> impl FnMut(&'a str) -> ParsingResult<'a, &'a str> + use<'a> {
+    let mut strict = crate::token(token);
+    let lenient = options.lenient_syntax();
+    move |i| {
+        if !lenient {
+            return strict(i);
+        }
+
+        let mut rest = i;
+        for expected in token.chars() {
+            if matches!(expected, '(' | ')') {
+                rest = rest.trim_start_matches(is_multispace);
+            }
+            rest = rest
+                .strip_prefix(|c: char| c.eq_ignore_ascii_case(&expected))
+                .ok_or_else(|| Err::Error(ParsingError::missing(i, token)))?;
+            if expected == '(' {
+                rest = rest.trim_start_matches(is_multispace);
+            }
+        }
+
+        let matched = i
+            .get(..i.len().saturating_sub(rest.len()))
+            .unwrap_or_default();
+        Ok((rest, matched))
+    }
 }
 
 impl Function {
+    pub(crate) fn parse<'a>(input: &'a str, options: &ParseOptions) -> ParsingResult<'a, Function> {
+        let (remaining_input, function) = Function::parse_unchecked(input, options)?;
+
+        function
+            .check_variables(options)
+            .map_err(|e| Err::Failure(e.at(input)))?;
+
+        Ok((remaining_input, function))
+    }
+
+    /// Check that every variable that the function references is defined.
+    fn check_variables(&self, options: &ParseOptions) -> Result<(), ParsingErrorKind> {
+        for string in self.variable_strings() {
+            for name in variables::references(&string)? {
+                if !options.has_variable(name) {
+                    return Err(ParsingErrorKind::UndefinedVariable(name.to_owned()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     #[expect(clippy::too_many_lines)]
-    pub fn parse(input: &str) -> ParsingResult<Function> {
+    fn parse_unchecked<'a>(input: &'a str, options: &ParseOptions) -> ParsingResult<'a, Function> {
         alt((
             map(
                 delimited(
-                    map_err(tag("file(\"")),
+                    function_token("file(\"", options),
                     parse_non_regex_path,
-                    map_err(tag("\")")),
+                    function_token("\")", options),
                 ),
                 Function::FilePath,
             ),
             map(
                 delimited(
-                    map_err(tag("file(\"")),
-                    parse_regex_path,
-                    map_err(tag("\")")),
+                    function_token("file(\"", options),
+                    |i| parse_regex_path(i, options),
+                    function_token("\")", options),
                 ),
                 |(path, regex)| Function::FileRegex(path, regex),
             ),
             map(
                 delimited(
-                    map_err(tag("file_size(")),
+                    function_token("file_size(", options),
                     parse_file_size_args,
-                    map_err(tag(")")),
+                    function_token(")", options),
                 ),
-                |(path, size)| Function::FileSize(path, size),
+                |(path, size, comparator)| Function::FileSize(path, size, comparator),
             ),
             map(
                 delimited(
-                    map_err(tag("readable(\"")),
+                    function_token("readable(\"", options),
                     parse_non_regex_path,
-                    map_err(tag("\")")),
+                    function_token("\")", options),
                 ),
                 Function::Readable,
             ),
             map(
                 delimited(
-                    map_err(tag("is_executable(\"")),
+                    function_token("is_executable(\"", options),
                     parse_non_regex_path,
-                    map_err(tag("\")")),
+                    function_token("\")", options),
                 ),
                 Function::IsExecutable,
             ),
             map(
                 delimited(
-                    map_err(tag("active(\"")),
+                    function_token("active(\"", options),
                     parse_non_regex_path,
-                    map_err(tag("\")")),
+                    function_token("\")", options),
                 ),
                 Function::ActivePath,
             ),
             map(
                 delimited(
-                    map_err(tag("active(\"")),
-                    parse_regex_filename,
-                    map_err(tag("\")")),
+                    function_token("active(\"", options),
+                    |i| parse_regex_filename(i, options),
+                    function_token("\")", options),
                 ),
                 Function::ActiveRegex,
             ),
             map(
                 delimited(
-                    map_err(tag("is_master(\"")),
+                    function_token("is_master(\"", options),
                     parse_non_regex_path,
-                    map_err(tag("\")")),
+                    function_token("\")", options),
                 ),
                 Function::IsMaster,
             ),
             map(
                 delimited(
-                    map_err(tag("many(\"")),
-                    parse_regex_path,
-                    map_err(tag("\")")),
+                    function_token("many(\"", options),
+                    |i| parse_regex_path(i, options),
+                    function_token("\")", options),
                 ),
                 |(path, regex)| Function::Many(path, regex),
             ),
             map(
                 delimited(
-                    map_err(tag("many_active(\"")),
-                    parse_regex_filename,
-                    map_err(tag("\")")),
+                    function_token("many_active(\"", options),
+                    |i| parse_regex_filename(i, options),
+                    function_token("\")", options),
                 ),
                 Function::ManyActive,
             ),
             map(
                 delimited(
-                    map_err(tag("version(")),
+                    function_token("version(", options),
                     parse_version_args,
-                    map_err(tag(")")),
+                    function_token(")", options),
                 ),
                 |(path, version, comparator)| Function::Version(path, version, comparator),
             ),
             map(
                 delimited(
-                    map_err(tag("product_version(")),
+                    function_token("product_version(", options),
                     parse_version_args,
-                    map_err(tag(")")),
+                    function_token(")", options),
                 ),
                 |(path, version, comparator)| Function::ProductVersion(path, version, comparator),
             ),
             map(
                 delimited(
-                    map_err(tag("filename_version(")),
-                    parse_filename_version_args,
-                    map_err(tag(")")),
+                    function_token("version(", options),
+                    parse_version_range_args,
+                    function_token(")", options),
+                ),
+                |(path, lower, upper)| Function::VersionInRange(path, lower, upper),
+            ),
+            map(
+                delimited(
+                    function_token("product_version(", options),
+                    parse_version_range_args,
+                    function_token(")", options),
+                ),
+                |(path, lower, upper)| Function::ProductVersionInRange(path, lower, upper),
+            ),
+            map(
+                delimited(
+                    function_token("filename_version(", options),
+                    |i| parse_filename_version_args(i, options),
+                    function_token(")", options),
                 ),
                 |(path, regex, version, comparator)| {
                     Function::FilenameVersion(path, regex, version, comparator)
@@ -297,20 +913,270 @@ impl Function {
             ),
             map(
                 delimited(
-                    map_err(tag("checksum(")),
+                    function_token("checksum(", options),
                     parse_checksum_args,
-                    map_err(tag(")")),
+                    function_token(")", options),
                 ),
-                |(path, crc)| Function::Checksum(path, crc),
+                |(path, crcs)| Function::Checksum(path, crcs),
             ),
             map(
                 delimited(
-                    map_err(tag("description_contains(")),
-                    parse_description_contains_args,
-                    map_err(tag(")")),
+                    function_token("description_contains(", options),
+                    |i| parse_description_contains_args(i, options),
+                    function_token(")", options),
                 ),
                 |(path, regex)| Function::DescriptionContains(path, regex),
             ),
+            map(
+                delimited(
+                    function_token("description_contains_text(", options),
+                    parse_description_contains_text_args,
+                    function_token(")", options),
+                ),
+                |(path, text)| Function::DescriptionContainsText(path, text),
+            ),
+            map(
+                delimited(
+                    function_token("version_cmp(", options),
+                    parse_version_cmp_args,
+                    function_token(")", options),
+                ),
+                |(path1, path2, comparator)| Function::VersionCmp(path1, path2, comparator),
+            ),
+            map(
+                delimited(
+                    function_token("checksum_manifest(\"", options),
+                    parse_non_regex_path,
+                    function_token("\")", options),
+                ),
+                Function::ChecksumManifest,
+            ),
+            // alt() can only choose between up to 21 parsers.
+            alt((
+                map(
+                    delimited(
+                        function_token("is_newer_than(", options),
+                        parse_is_newer_than_args,
+                        function_token(")", options),
+                    ),
+                    |(path1, path2)| Function::IsNewerThan(path1, path2),
+                ),
+                map(
+                    delimited(
+                        function_token("is_valid_plugin(\"", options),
+                        parse_non_regex_path,
+                        function_token("\")", options),
+                    ),
+                    Function::IsValidPlugin,
+                ),
+                map(
+                    delimited(
+                        function_token("is_x64(\"", options),
+                        parse_non_regex_path,
+                        function_token("\")", options),
+                    ),
+                    |path| Function::IsArchitecture(path, Architecture::X64),
+                ),
+                map(
+                    delimited(
+                        function_token("is_x86(\"", options),
+                        parse_non_regex_path,
+                        function_token("\")", options),
+                    ),
+                    |path| Function::IsArchitecture(path, Architecture::X86),
+                ),
+                map(
+                    delimited(
+                        function_token("checksum_sha256(", options),
+                        parse_checksum_sha256_args,
+                        function_token(")", options),
+                    ),
+                    |(path, digest)| Function::ChecksumSha256(path, digest),
+                ),
+                map(
+                    delimited(
+                        function_token("file_mtime(", options),
+                        parse_file_mtime_args,
+                        function_token(")", options),
+                    ),
+                    |(path, comparator, timestamp)| {
+                        Function::FileModifiedTime(path, comparator, timestamp)
+                    },
+                ),
+                map(
+                    delimited(
+                        function_token("is_light_plugin(\"", options),
+                        parse_non_regex_path,
+                        function_token("\")", options),
+                    ),
+                    Function::IsLightPlugin,
+                ),
+                map(
+                    delimited(
+                        function_token("is_medium_plugin(\"", options),
+                        parse_non_regex_path,
+                        function_token("\")", options),
+                    ),
+                    Function::IsMediumPlugin,
+                ),
+                map(
+                    delimited(
+                        function_token("header_version(", options),
+                        parse_header_version_args,
+                        function_token(")", options),
+                    ),
+                    |(path, comparator, version)| {
+                        Function::HeaderVersion(path, comparator, version)
+                    },
+                ),
+                map(
+                    delimited(
+                        function_token("record_count(", options),
+                        parse_record_count_args,
+                        function_token(")", options),
+                    ),
+                    |(path, comparator, count)| Function::RecordCount(path, comparator, count),
+                ),
+                map(
+                    delimited(
+                        function_token("masters_contain(", options),
+                        parse_description_contains_text_args,
+                        function_token(")", options),
+                    ),
+                    |(path, master)| Function::MastersContain(path, master),
+                ),
+                alt((
+                    map(
+                        delimited(
+                            function_token("file_content_contains(", options),
+                            |i| parse_description_contains_args(i, options),
+                            function_token(")", options),
+                        ),
+                        |(path, regex)| Function::FileContentContains(path, regex),
+                    ),
+                    map(
+                        delimited(
+                            function_token("ini_value(", options),
+                            parse_ini_value_args,
+                            function_token(")", options),
+                        ),
+                        |(path, section, key, value)| Function::IniValue(path, section, key, value),
+                    ),
+                    map(
+                        delimited(
+                            function_token("archive_contains(", options),
+                            parse_description_contains_text_args,
+                            function_token(")", options),
+                        ),
+                        |(path, entry)| Function::ArchiveContains(path, entry),
+                    ),
+                    map(
+                        delimited(
+                            function_token("archive_regex(", options),
+                            |i| parse_archive_regex_args(i, options),
+                            function_token(")", options),
+                        ),
+                        |(path, regex)| Function::ArchiveRegex(path, regex),
+                    ),
+                    map(
+                        delimited(
+                            function_token("archive_version(", options),
+                            parse_record_count_args,
+                            function_token(")", options),
+                        ),
+                        |(path, comparator, version)| {
+                            Function::ArchiveVersion(path, comparator, version)
+                        },
+                    ),
+                    map(
+                        delimited(
+                            function_token("description_regex(", options),
+                            |i| parse_description_regex_args(i, options),
+                            function_token(")", options),
+                        ),
+                        |(path, regex, description_regex)| {
+                            Function::DescriptionRegex(path, regex, description_regex)
+                        },
+                    ),
+                    map(
+                        delimited(
+                            function_token("active_count(", options),
+                            |i| parse_active_count_args(i, options),
+                            function_token(")", options),
+                        ),
+                        |(regex, comparator, count)| {
+                            Function::ActiveCount(regex, comparator, count)
+                        },
+                    ),
+                    map(
+                        delimited(
+                            function_token("count(", options),
+                            |i| parse_count_args(i, options),
+                            function_token(")", options),
+                        ),
+                        |(path, regex, comparator, count)| {
+                            Function::Count(path, regex, comparator, count)
+                        },
+                    ),
+                    map(
+                        delimited(
+                            function_token("author_contains(", options),
+                            |i| parse_description_contains_args(i, options),
+                            function_token(")", options),
+                        ),
+                        |(path, regex)| Function::AuthorContains(path, regex),
+                    ),
+                    map(
+                        delimited(
+                            function_token("registry_value(", options),
+                            |i| parse_registry_value_args(i, options),
+                            function_token(")", options),
+                        ),
+                        |(key, value_name, regex)| Function::RegistryValue(key, value_name, regex),
+                    ),
+                    map(
+                        delimited(
+                            function_token("writable(\"", options),
+                            parse_non_regex_path,
+                            function_token("\")", options),
+                        ),
+                        Function::Writable,
+                    ),
+                    map(
+                        alt((
+                            value(Platform::Windows, function_token("is_windows()", options)),
+                            value(Platform::Linux, function_token("is_linux()", options)),
+                            value(Platform::MacOs, function_token("is_macos()", options)),
+                        )),
+                        Function::IsPlatform,
+                    ),
+                    value(Function::IsWine, function_token("is_wine()", options)),
+                    map(
+                        delimited(
+                            function_token("file_glob(", options),
+                            |i| parse_file_glob_args(i, options),
+                            function_token(")", options),
+                        ),
+                        |(path, glob, regex)| Function::FileGlob(path, glob, regex),
+                    ),
+                    map(
+                        delimited(
+                            function_token("file(", options),
+                            |i| parse_recursive_regex_path_args(i, options),
+                            function_token(")", options),
+                        ),
+                        |(path, regex, depth)| Function::RecursiveFileRegex(path, regex, depth),
+                    ),
+                    map(
+                        delimited(
+                            function_token("many(", options),
+                            |i| parse_recursive_regex_path_args(i, options),
+                            function_token(")", options),
+                        ),
+                        |(path, regex, depth)| Function::RecursiveMany(path, regex, depth),
+                    ),
+                )),
+            )),
         ))
         .parse(input)
     }
@@ -324,35 +1190,35 @@ mod tests {
 
     #[test]
     fn parse_regex_should_produce_case_insensitive_regex() {
-        let (_, regex) = parse_regex("cargo.*").unwrap();
+        let (_, regex) = parse_regex("cargo.*", &ParseOptions::default()).unwrap();
 
         assert!(regex.is_match("Cargo.toml"));
     }
 
     #[test]
     fn parse_regex_should_produce_a_regex_that_does_partially_match() {
-        let (_, regex) = parse_regex("argo.").unwrap();
+        let (_, regex) = parse_regex("argo.", &ParseOptions::default()).unwrap();
 
         assert!(regex.is_match("Cargo.toml"));
     }
 
     #[test]
     fn parse_anchored_regex_should_produce_case_insensitive_regex() {
-        let (_, regex) = parse_anchored_regex("cargo.*").unwrap();
+        let (_, regex) = parse_anchored_regex("cargo.*", &ParseOptions::default()).unwrap();
 
         assert!(regex.is_match("Cargo.toml"));
     }
 
     #[test]
     fn parse_anchored_regex_should_produce_a_regex_that_does_not_partially_match() {
-        let (_, regex) = parse_anchored_regex("cargo.").unwrap();
+        let (_, regex) = parse_anchored_regex("cargo.", &ParseOptions::default()).unwrap();
 
         assert!(!regex.is_match("Cargo.toml"));
     }
 
     #[test]
     fn function_parse_should_parse_a_file_path_function() {
-        let output = Function::parse("file(\"Cargo.toml\")").unwrap();
+        let output = Function::parse("file(\"Cargo.toml\")", &ParseOptions::default()).unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -363,7 +1229,7 @@ mod tests {
 
     #[test]
     fn function_parse_should_parse_a_file_regex_function_with_no_parent_path() {
-        let output = Function::parse("file(\"Cargo.*\")").unwrap();
+        let output = Function::parse("file(\"Cargo.*\")", &ParseOptions::default()).unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -377,7 +1243,7 @@ mod tests {
 
     #[test]
     fn function_parse_should_parse_a_file_regex_function_with_a_parent_path() {
-        let output = Function::parse("file(\"subdir/Cargo.*\")").unwrap();
+        let output = Function::parse("file(\"subdir/Cargo.*\")", &ParseOptions::default()).unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -391,26 +1257,138 @@ mod tests {
 
     #[test]
     fn function_parse_should_error_if_given_a_file_regex_function_ending_in_a_forward_slash() {
-        assert!(Function::parse("file(\"sub\\dir/\")").is_err());
+        assert!(Function::parse("file(\"sub\\dir/\")", &ParseOptions::default()).is_err());
     }
 
     #[test]
-    fn function_parse_should_parse_a_file_size_function() {
-        let output = Function::parse("file_size(\"Cargo.toml\", 1234)").unwrap();
+    fn function_parse_should_parse_a_file_glob_function() {
+        let output = Function::parse(
+            "file_glob(\"Data\", \"Blank*.es?\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
-            Function::FileSize(f, s) => {
-                assert_eq!(Path::new("Cargo.toml"), f);
-                assert_eq!(1234, s);
+            Function::FileGlob(p, g, r) => {
+                assert_eq!(Path::new("Data"), p);
+                assert_eq!("Blank*.es?", g);
+                assert_eq!("^Blank.*\\.es.$", r.as_str());
+            }
+            _ => panic!("Expected a file glob function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_escape_regex_syntax_in_a_file_glob_function() {
+        let output = Function::parse(
+            "file_glob(\"Data\", \"Blank (v1.0) [A+B].esp\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        match output.1 {
+            Function::FileGlob(_, _, r) => {
+                assert!(r.is_match("blank (v1.0) [a+b].ESP"));
+                assert!(!r.is_match("Blank (v1x0) [A+B].esp"));
+            }
+            _ => panic!("Expected a file glob function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_error_if_a_file_glob_contains_a_directory_separator() {
+        assert!(Function::parse(
+            "file_glob(\"Data\", \"meshes/*.nif\")",
+            &ParseOptions::default()
+        )
+        .is_err());
+        assert!(Function::parse(
+            "file_glob(\"Data\", \"meshes\\*.nif\")",
+            &ParseOptions::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_file_regex_function_with_a_depth() {
+        let output =
+            Function::parse("file(\"meshes/.*\\.nif\", 3)", &ParseOptions::default()).unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::RecursiveFileRegex(p, r, d) => {
+                assert_eq!(Path::new("meshes"), p);
+                assert_eq!("^.*\\.nif$", r.as_str());
+                assert_eq!(3, d);
+            }
+            _ => panic!("Expected a recursive file regex function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_many_function_with_a_depth() {
+        let output =
+            Function::parse("many(\"meshes/.*\\.nif\", 2)", &ParseOptions::default()).unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::RecursiveMany(p, r, d) => {
+                assert_eq!(Path::new("meshes"), p);
+                assert_eq!("^.*\\.nif$", r.as_str());
+                assert_eq!(2, d);
+            }
+            _ => panic!("Expected a recursive many function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_file_size_function() {
+        let output =
+            Function::parse("file_size(\"Cargo.toml\", 1234)", &ParseOptions::default()).unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::FileSize(f, s, c) => {
+                assert_eq!(Path::new("Cargo.toml"), f);
+                assert_eq!(1234, s);
+                assert_eq!(ComparisonOperator::Equal, c);
             }
             _ => panic!("Expected a file size function"),
         }
     }
 
+    #[test]
+    fn function_parse_should_parse_a_file_size_function_with_a_comparator() {
+        let output = Function::parse(
+            "file_size(\"Cargo.toml\", 1234, >=)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::FileSize(f, s, c) => {
+                assert_eq!(Path::new("Cargo.toml"), f);
+                assert_eq!(1234, s);
+                assert_eq!(ComparisonOperator::GreaterThanOrEqual, c);
+            }
+            _ => panic!("Expected a file size function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_error_if_a_file_size_function_has_an_invalid_comparator() {
+        assert!(Function::parse(
+            "file_size(\"Cargo.toml\", 1234, =>)",
+            &ParseOptions::default()
+        )
+        .is_err());
+    }
+
     #[test]
     fn function_parse_should_parse_a_readable_function() {
-        let output = Function::parse("readable(\"Cargo.toml\")").unwrap();
+        let output = Function::parse("readable(\"Cargo.toml\")", &ParseOptions::default()).unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -419,9 +1397,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn function_parse_should_parse_a_writable_function() {
+        let output = Function::parse("writable(\"Cargo.toml\")", &ParseOptions::default()).unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::Writable(f) => assert_eq!(Path::new("Cargo.toml"), f),
+            _ => panic!("Expected a writable function"),
+        }
+    }
+
     #[test]
     fn function_parse_should_parse_an_is_executable_function() {
-        let output = Function::parse("is_executable(\"Cargo.toml\")").unwrap();
+        let output =
+            Function::parse("is_executable(\"Cargo.toml\")", &ParseOptions::default()).unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -430,9 +1420,53 @@ mod tests {
         }
     }
 
+    #[test]
+    fn function_parse_should_parse_is_x64_and_is_x86_functions() {
+        for (string, architecture) in [
+            ("is_x64(\"loot.dll\")", Architecture::X64),
+            ("is_x86(\"loot.dll\")", Architecture::X86),
+        ] {
+            let output = Function::parse(string, &ParseOptions::default()).unwrap();
+
+            assert!(output.0.is_empty());
+            match output.1 {
+                Function::IsArchitecture(f, a) => {
+                    assert_eq!(Path::new("loot.dll"), f);
+                    assert_eq!(architecture, a);
+                }
+                _ => panic!("Expected an is_x64 or is_x86 function"),
+            }
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_is_windows_is_linux_and_is_macos_functions() {
+        for (string, platform) in [
+            ("is_windows()", Platform::Windows),
+            ("is_linux()", Platform::Linux),
+            ("is_macos()", Platform::MacOs),
+        ] {
+            let output = Function::parse(string, &ParseOptions::default()).unwrap();
+
+            assert!(output.0.is_empty());
+            match output.1 {
+                Function::IsPlatform(p) => assert_eq!(platform, p),
+                _ => panic!("Expected an is_windows, is_linux or is_macos function"),
+            }
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_an_is_wine_function() {
+        let output = Function::parse("is_wine()", &ParseOptions::default()).unwrap();
+
+        assert!(output.0.is_empty());
+        assert!(matches!(output.1, Function::IsWine));
+    }
+
     #[test]
     fn function_parse_should_parse_an_active_path_function() {
-        let output = Function::parse("active(\"Cargo.toml\")").unwrap();
+        let output = Function::parse("active(\"Cargo.toml\")", &ParseOptions::default()).unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -443,7 +1477,7 @@ mod tests {
 
     #[test]
     fn function_parse_should_parse_an_active_regex_function() {
-        let output = Function::parse("active(\"Cargo.*\")").unwrap();
+        let output = Function::parse("active(\"Cargo.*\")", &ParseOptions::default()).unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -456,7 +1490,7 @@ mod tests {
 
     #[test]
     fn function_parse_should_parse_an_is_master_function() {
-        let output = Function::parse("is_master(\"Blank.esm\")").unwrap();
+        let output = Function::parse("is_master(\"Blank.esm\")", &ParseOptions::default()).unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -465,9 +1499,273 @@ mod tests {
         }
     }
 
+    #[test]
+    fn function_parse_should_parse_an_is_light_plugin_function() {
+        let output =
+            Function::parse("is_light_plugin(\"Blank.esl\")", &ParseOptions::default()).unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::IsLightPlugin(f) => assert_eq!(Path::new("Blank.esl"), f),
+            _ => panic!("Expected an is light plugin function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_an_is_medium_plugin_function() {
+        let output =
+            Function::parse("is_medium_plugin(\"Blank.esm\")", &ParseOptions::default()).unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::IsMediumPlugin(f) => assert_eq!(Path::new("Blank.esm"), f),
+            _ => panic!("Expected an is medium plugin function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_header_version_function() {
+        let output = Function::parse(
+            "header_version(\"Blank.esp\", >=, 1.71)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::HeaderVersion(f, c, v) => {
+                assert_eq!(Path::new("Blank.esp"), f);
+                assert_eq!(ComparisonOperator::GreaterThanOrEqual, c);
+                assert_eq!(1.71_f32.to_bits(), v.to_bits());
+            }
+            _ => panic!("Expected a header version function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_header_version_without_a_fractional_part() {
+        let output = Function::parse(
+            "header_version(\"Blank.esm\", ==, 1)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::HeaderVersion(_, _, v) => assert_eq!(1.0_f32.to_bits(), v.to_bits()),
+            _ => panic!("Expected a header version function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_record_count_function() {
+        let output = Function::parse(
+            "record_count(\"Blank.esp\", >, 0)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::RecordCount(f, c, n) => {
+                assert_eq!(Path::new("Blank.esp"), f);
+                assert_eq!(ComparisonOperator::GreaterThan, c);
+                assert_eq!(0, n);
+            }
+            _ => panic!("Expected a record count function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_masters_contain_function() {
+        let output = Function::parse(
+            "masters_contain(\"Child.esp\", \"Master.esm\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::MastersContain(f, m) => {
+                assert_eq!(Path::new("Child.esp"), f);
+                assert_eq!("Master.esm", m);
+            }
+            _ => panic!("Expected a masters contain function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_file_content_contains_function() {
+        let output = Function::parse(
+            "file_content_contains(\"SKSE/Plugins/foo.ini\", \"^bEnabled=1$\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::FileContentContains(p, r) => {
+                assert_eq!(Path::new("SKSE/Plugins/foo.ini"), p);
+                assert_eq!("^bEnabled=1$", r.as_str());
+            }
+            _ => panic!("Expected a file content contains function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_an_archive_contains_function() {
+        let output = Function::parse(
+            "archive_contains(\"Textures.bsa\", \"textures/some/path.dds\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::ArchiveContains(p, e) => {
+                assert_eq!(Path::new("Textures.bsa"), p);
+                assert_eq!("textures/some/path.dds", e);
+            }
+            _ => panic!("Expected an archive contains function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_an_archive_regex_function() {
+        let output = Function::parse(
+            "archive_regex(\"Meshes - Main.ba2\", \"meshes/armor/.*\\.nif\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::ArchiveRegex(p, r) => {
+                assert_eq!(Path::new("Meshes - Main.ba2"), p);
+                assert_eq!(
+                    Regex::new("^meshes/armor/.*\\.nif$").unwrap().as_str(),
+                    r.as_str()
+                );
+            }
+            _ => panic!("Expected an archive regex function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_an_archive_version_function() {
+        let output = Function::parse(
+            "archive_version(\"Archive.ba2\", >=, 2)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::ArchiveVersion(p, c, v) => {
+                assert_eq!(Path::new("Archive.ba2"), p);
+                assert_eq!(ComparisonOperator::GreaterThanOrEqual, c);
+                assert_eq!(2, v);
+            }
+            _ => panic!("Expected an archive version function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_an_ini_value_function() {
+        let output = Function::parse(
+            "ini_value(\"MyMod.ini\", \"General\", \"bEnabled\", \"1\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::IniValue(p, s, k, v) => {
+                assert_eq!(Path::new("MyMod.ini"), p);
+                assert_eq!("General", s);
+                assert_eq!("bEnabled", k);
+                assert_eq!("1", v);
+            }
+            _ => panic!("Expected an INI value function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_registry_value_function() {
+        let output = Function::parse(
+            "registry_value(\"HKLM\\SOFTWARE\\Bethesda Softworks\\Oblivion\", \"Installed Path\", \"Steam\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::RegistryValue(k, n, r) => {
+                assert_eq!("HKLM\\SOFTWARE\\Bethesda Softworks\\Oblivion", k);
+                assert_eq!("Installed Path", n);
+                assert_eq!(Regex::new("Steam").unwrap().as_str(), r.as_str());
+            }
+            _ => panic!("Expected a registry value function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_error_if_a_registry_value_key_has_no_known_root() {
+        let result = Function::parse(
+            "registry_value(\"SOFTWARE\\Bethesda Softworks\", \"Installed Path\", \".*\")",
+            &ParseOptions::default(),
+        );
+
+        assert!(matches!(
+            result.map_err(crate::Error::from),
+            Err(crate::Error::ParsingError(
+                _,
+                ParsingErrorKind::InvalidRegistryKey(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn function_parse_should_error_if_an_ini_value_argument_cannot_match() {
+        for args in [
+            "\" General\", \"bEnabled\", \"1\"",
+            "\"Gen]eral\", \"bEnabled\", \"1\"",
+            "\"General\", \"b=Enabled\", \"1\"",
+            "\"General\", \";bEnabled\", \"1\"",
+            "\"General\", \"bEnabled\", \"1 \"",
+            "\"General\", \"bEnabled\", \"1\n2\"",
+        ] {
+            let condition = format!("ini_value(\"MyMod.ini\", {args})");
+            let result = Function::parse(&condition, &ParseOptions::default());
+
+            assert!(
+                matches!(
+                    result.map_err(crate::Error::from),
+                    Err(crate::Error::ParsingError(
+                        _,
+                        ParsingErrorKind::InvalidIniArgument(_)
+                    ))
+                ),
+                "{args}"
+            );
+        }
+    }
+
+    #[test]
+    fn function_parse_should_error_if_a_header_version_is_not_a_decimal_number() {
+        for version in ["-1.7", "1.", ".7", "\"1.7\"", "1e3"] {
+            let condition = format!("header_version(\"Blank.esp\", ==, {version})");
+
+            assert!(
+                Function::parse(&condition, &ParseOptions::default()).is_err(),
+                "{version}"
+            );
+        }
+    }
+
     #[test]
     fn function_parse_should_parse_a_many_function_with_no_parent_path() {
-        let output = Function::parse("many(\"Cargo.*\")").unwrap();
+        let output = Function::parse("many(\"Cargo.*\")", &ParseOptions::default()).unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -481,7 +1779,7 @@ mod tests {
 
     #[test]
     fn function_parse_should_parse_a_many_function_with_a_parent_path() {
-        let output = Function::parse("many(\"subdir/Cargo.*\")").unwrap();
+        let output = Function::parse("many(\"subdir/Cargo.*\")", &ParseOptions::default()).unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -493,14 +1791,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn function_parse_should_parse_a_count_function() {
+        let output = Function::parse(
+            "count(\"Patches/.* Patch\\.esp\", >=, 3)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::Count(p, r, c, n) => {
+                assert_eq!(PathBuf::from("Patches"), p);
+                assert_eq!(Regex::new("^.* Patch\\.esp$").unwrap().as_str(), r.as_str());
+                assert_eq!(ComparisonOperator::GreaterThanOrEqual, c);
+                assert_eq!(3, n);
+            }
+            _ => panic!("Expected a count function"),
+        }
+    }
+
     #[test]
     fn function_parse_should_error_if_given_a_many_function_ending_in_a_forward_slash() {
-        assert!(Function::parse("many(\"subdir/\")").is_err());
+        assert!(Function::parse("many(\"subdir/\")", &ParseOptions::default()).is_err());
     }
 
     #[test]
     fn function_parse_should_parse_a_many_active_function() {
-        let output = Function::parse("many_active(\"Cargo.*\")").unwrap();
+        let output = Function::parse("many_active(\"Cargo.*\")", &ParseOptions::default()).unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -511,23 +1829,155 @@ mod tests {
         }
     }
 
+    #[test]
+    fn function_parse_should_parse_an_active_count_function() {
+        let output = Function::parse(
+            "active_count(\".*Weather.*\", >, 4)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::ActiveCount(r, c, n) => {
+                assert_eq!(Regex::new("^.*Weather.*$").unwrap().as_str(), r.as_str());
+                assert_eq!(ComparisonOperator::GreaterThan, c);
+                assert_eq!(4, n);
+            }
+            _ => panic!("Expected an active count function"),
+        }
+    }
+
     #[test]
     fn function_parse_should_parse_a_checksum_function() {
-        let output = Function::parse("checksum(\"Cargo.toml\", DEADBEEF)").unwrap();
+        let output = Function::parse(
+            "checksum(\"Cargo.toml\", DEADBEEF)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
-            Function::Checksum(path, crc) => {
+            Function::Checksum(path, crcs) => {
                 assert_eq!(Path::new("Cargo.toml"), path);
-                assert_eq!(0xDEAD_BEEF, crc);
+                assert_eq!(vec![0xDEAD_BEEF], crcs);
             }
             _ => panic!("Expected a checksum function"),
         }
     }
 
+    #[test]
+    fn function_parse_should_parse_a_checksum_function_with_several_crcs() {
+        let output = Function::parse(
+            "checksum(\"Cargo.toml\", DEADBEEF,11223344 , aabbccdd)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::Checksum(path, crcs) => {
+                assert_eq!(Path::new("Cargo.toml"), path);
+                assert_eq!(vec![0xDEAD_BEEF, 0x1122_3344, 0xAABB_CCDD], crcs);
+            }
+            _ => panic!("Expected a checksum function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_error_if_a_checksum_function_has_a_trailing_comma() {
+        assert!(Function::parse(
+            "checksum(\"Cargo.toml\", DEADBEEF, )",
+            &ParseOptions::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_checksum_sha256_function() {
+        let output = Function::parse(
+            "checksum_sha256(\"Cargo.toml\", \"D7A8FBB307D7809469CA9ABCB0082E4F8D5651E46D3CDB762D02D0BF37C9e592\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::ChecksumSha256(path, digest) => {
+                assert_eq!(Path::new("Cargo.toml"), path);
+                assert_eq!(Some(&0xD7), digest.first());
+                assert_eq!(Some(&0x92), digest.last());
+            }
+            _ => panic!("Expected a checksum_sha256 function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_error_if_a_sha256_digest_is_not_64_hex_digits() {
+        for digest in [
+            "d7a8",
+            "+7a8fbb307d7809469ca9abcb0082e4f8d5651e46d3cdb762d02d0bf37c9e592",
+            "z".repeat(64).as_str(),
+            &"a".repeat(66),
+        ] {
+            let condition = format!("checksum_sha256(\"Cargo.toml\", \"{digest}\")");
+            let result = Function::parse(&condition, &ParseOptions::default());
+
+            assert!(
+                matches!(
+                    result.map_err(crate::Error::from),
+                    Err(crate::Error::ParsingError(
+                        _,
+                        ParsingErrorKind::InvalidSha256(_)
+                    ))
+                ),
+                "{digest}"
+            );
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_file_mtime_function() {
+        let output = Function::parse(
+            "file_mtime(\"Bashed Patch, 0.esp\", <, \"2024-01-01T12:00:00+01:00\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::FileModifiedTime(f, c, t) => {
+                assert_eq!(Path::new("Bashed Patch, 0.esp"), f);
+                assert_eq!(ComparisonOperator::LessThan, c);
+                assert_eq!(Timestamp::parse("2024-01-01T11:00:00Z"), Some(t));
+            }
+            _ => panic!("Expected a file mtime function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_error_if_a_file_mtime_timestamp_is_invalid() {
+        let result = Function::parse(
+            "file_mtime(\"Cargo.toml\", <, \"2024-02-30\")",
+            &ParseOptions::default(),
+        );
+
+        assert!(matches!(
+            result.map_err(crate::Error::from),
+            Err(crate::Error::ParsingError(
+                _,
+                ParsingErrorKind::InvalidTimestamp(_)
+            ))
+        ));
+    }
+
     #[test]
     fn function_parse_should_parse_a_version_equals_function() {
-        let output = Function::parse("version(\"Cargo.toml\", \"1.2\", ==)").unwrap();
+        let output = Function::parse(
+            "version(\"Cargo.toml\", \"1.2\", ==)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -542,7 +1992,11 @@ mod tests {
 
     #[test]
     fn function_parse_should_parse_a_version_not_equals_function() {
-        let output = Function::parse("version(\"Cargo.toml\", \"1.2\", !=)").unwrap();
+        let output = Function::parse(
+            "version(\"Cargo.toml\", \"1.2\", !=)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -557,7 +2011,11 @@ mod tests {
 
     #[test]
     fn function_parse_should_parse_a_version_less_than_function() {
-        let output = Function::parse("version(\"Cargo.toml\", \"1.2\", <)").unwrap();
+        let output = Function::parse(
+            "version(\"Cargo.toml\", \"1.2\", <)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -572,7 +2030,11 @@ mod tests {
 
     #[test]
     fn function_parse_should_parse_a_version_greater_than_function() {
-        let output = Function::parse("version(\"Cargo.toml\", \"1.2\", >)").unwrap();
+        let output = Function::parse(
+            "version(\"Cargo.toml\", \"1.2\", >)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -587,7 +2049,11 @@ mod tests {
 
     #[test]
     fn function_parse_should_parse_a_version_less_than_or_equal_to_function() {
-        let output = Function::parse("version(\"Cargo.toml\", \"1.2\", <=)").unwrap();
+        let output = Function::parse(
+            "version(\"Cargo.toml\", \"1.2\", <=)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -602,7 +2068,11 @@ mod tests {
 
     #[test]
     fn function_parse_should_parse_a_version_greater_than_or_equal_to_function() {
-        let output = Function::parse("version(\"Cargo.toml\", \"1.2\", >=)").unwrap();
+        let output = Function::parse(
+            "version(\"Cargo.toml\", \"1.2\", >=)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -617,7 +2087,11 @@ mod tests {
 
     #[test]
     fn function_parse_should_parse_a_version_with_a_path_containing_backslashes() {
-        let output = Function::parse("version(\"..\\Cargo.toml\", \"1.2\", ==)").unwrap();
+        let output = Function::parse(
+            "version(\"..\\Cargo.toml\", \"1.2\", ==)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -630,9 +2104,188 @@ mod tests {
         }
     }
 
+    #[test]
+    fn function_parse_should_parse_a_version_with_a_drive_letter_path() {
+        let output = Function::parse(
+            "version(\"C:\\Games\\skse_loader.exe\", \"1.2\", ==)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::Version(path, _, _) => {
+                assert_eq!(Path::new("C:\\Games\\skse_loader.exe"), path);
+            }
+            _ => panic!("Expected a version function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_decode_escape_sequences_in_text_arguments() {
+        let (_, function) = Function::parse(
+            "masters_contain(\"a\\\"b.esp\", \"c\\\\d\\e\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            Function::MastersContain("a\"b.esp".into(), "c\\d\\e".into()),
+            function
+        );
+    }
+
+    #[test]
+    fn function_parse_should_decode_an_escaped_double_quote_in_a_non_regex_path() {
+        let (_, function) =
+            Function::parse("file(\"a\\\"b.esp\")", &ParseOptions::default()).unwrap();
+
+        assert_eq!(Function::FilePath("a\"b.esp".into()), function);
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn function_parse_should_not_end_a_regex_at_an_escaped_double_quote() {
+        let (remaining, function) =
+            Function::parse("many(\"a\\\".*\\\\\") and", &ParseOptions::default()).unwrap();
+
+        assert_eq!(" and", remaining);
+        match function {
+            Function::Many(_, regex) => {
+                assert_eq!("^a\\\".*\\\\$", regex.as_str());
+                assert!(regex.is_match("A\"b\\"));
+            }
+            _ => panic!("Expected a many function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_round_trip_escape_sequences() {
+        let input = "ini_value(\"a\\\"b.ini\", \"c\\\\\", \"d\\e\", \"f\\\"\")";
+        let (_, function) = Function::parse(input, &ParseOptions::default()).unwrap();
+
+        assert_eq!(input, function.to_string());
+    }
+
+    #[test]
+    fn function_parse_should_not_read_a_trailing_backslash_in_a_path_as_an_escape() {
+        let (remaining, function) = Function::parse(
+            "checksum(\"sub\\dir\\\", DEADBEEF)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(remaining.is_empty());
+        assert_eq!(
+            Function::Checksum("sub\\dir\\".into(), vec![0xDEAD_BEEF]),
+            function
+        );
+
+        let (_, round_tripped) =
+            Function::parse(&function.to_string(), &ParseOptions::default()).unwrap();
+        assert_eq!(function, round_tripped);
+    }
+
+    #[test]
+    fn function_parse_should_not_read_a_trailing_backslash_in_a_version_path_as_an_escape() {
+        let (remaining, function) =
+            Function::parse("version(\"a\\b\\\", \"1\", ==)", &ParseOptions::default()).unwrap();
+
+        assert!(remaining.is_empty());
+        assert_eq!(
+            Function::Version("a\\b\\".into(), "1".into(), ComparisonOperator::Equal),
+            function
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn function_parse_should_not_read_a_trailing_backslash_in_a_regex_path_as_an_escape() {
+        let (remaining, function) =
+            Function::parse("file(\"sub\\dir\\\")", &ParseOptions::default()).unwrap();
+
+        assert!(remaining.is_empty());
+        match function {
+            Function::FileRegex(path, regex) => {
+                assert_eq!(Path::new("."), path);
+                assert_eq!("^sub\\dir\\$", regex.as_str());
+            }
+            _ => panic!("Expected a file regex function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_error_on_non_canonical_syntax_by_default() {
+        let options = ParseOptions::default();
+
+        assert!(Function::parse("File(\"a.esp\")", &options).is_err());
+        assert!(Function::parse("file (\"a.esp\")", &options).is_err());
+        assert!(Function::parse("file(\"a.esp\" )", &options).is_err());
+    }
+
+    #[test]
+    fn function_parse_should_accept_any_case_function_names_with_lenient_syntax() {
+        let options = ParseOptions::new().with_lenient_syntax(true);
+
+        let (_, function) = Function::parse("File(\"a.esp\")", &options).unwrap();
+        assert_eq!(Function::FilePath("a.esp".into()), function);
+
+        let (_, function) = Function::parse("IS_MASTER(\"a.esp\")", &options).unwrap();
+        assert_eq!(Function::IsMaster("a.esp".into()), function);
+
+        let (_, function) = Function::parse("Is_Windows()", &options).unwrap();
+        assert_eq!(Function::IsPlatform(Platform::Windows), function);
+    }
+
+    #[test]
+    fn function_parse_should_accept_whitespace_around_parentheses_with_lenient_syntax() {
+        let options = ParseOptions::new().with_lenient_syntax(true);
+
+        let (remaining, function) =
+            Function::parse("version ( \"a.esp\" , \"1.0\" , >= ) and", &options).unwrap();
+        assert_eq!(" and", remaining);
+        assert_eq!(
+            Function::Version(
+                "a.esp".into(),
+                "1.0".into(),
+                ComparisonOperator::GreaterThanOrEqual
+            ),
+            function
+        );
+
+        let (_, function) = Function::parse("is_wine(\n)", &options).unwrap();
+        assert_eq!(Function::IsWine, function);
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_file_path_function_with_a_drive_letter_path() {
+        let output =
+            Function::parse("file(\"Z:/home/deck/tool.exe\")", &ParseOptions::default()).unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::FilePath(f) => assert_eq!(Path::new("Z:/home/deck/tool.exe"), f),
+            _ => panic!("Expected a file path function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_error_if_a_non_regex_path_contains_a_colon_that_is_not_a_drive_prefix()
+    {
+        assert!(Function::parse(
+            "checksum(\"a:b:c.esp\", DEADBEEF)",
+            &ParseOptions::default()
+        )
+        .is_err());
+    }
+
     #[test]
     fn function_parse_should_parse_a_product_version_equals_function() {
-        let output = Function::parse("product_version(\"Cargo.toml\", \"1.2\", ==)").unwrap();
+        let output = Function::parse(
+            "product_version(\"Cargo.toml\", \"1.2\", ==)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -646,9 +2299,13 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "regex")]
     fn function_parse_should_parse_a_filename_version_equals_function() {
-        let output =
-            Function::parse("filename_version(\"subdir/Cargo (.+).toml\", \"1.2\", ==)").unwrap();
+        let output = Function::parse(
+            "filename_version(\"subdir/Cargo (.+).toml\", \"1.2\", ==)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -668,16 +2325,18 @@ mod tests {
     #[test]
     fn function_parse_should_error_if_the_filename_version_regex_does_not_contain_an_explicit_capture_group(
     ) {
-        assert!(
-            Function::parse("filename_version(\"subdir/Cargo .+.toml\", \"1.2\", ==)").is_err()
-        );
+        assert!(Function::parse(
+            "filename_version(\"subdir/Cargo .+.toml\", \"1.2\", ==)",
+            &ParseOptions::default()
+        )
+        .is_err());
     }
 
     #[test]
     fn function_parse_should_parse_a_description_contains_function() {
         let lowercase_non_ascii = "\u{20ac}\u{192}.";
         let function = format!("description_contains(\"Blank.esp\", \"{lowercase_non_ascii}\")");
-        let output = Function::parse(&function).unwrap();
+        let output = Function::parse(&function, &ParseOptions::default()).unwrap();
 
         assert!(output.0.is_empty());
         match output.1 {
@@ -691,4 +2350,209 @@ mod tests {
             _ => panic!("Expected a description_contains function"),
         }
     }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn function_parse_should_parse_an_author_contains_function() {
+        let output = Function::parse(
+            "author_contains(\"Blank.esp\", \"Some(one|body)\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::AuthorContains(p, r) => {
+                assert_eq!(PathBuf::from("Blank.esp"), p);
+                assert_eq!(Regex::new("Some(one|body)").unwrap().as_str(), r.as_str());
+            }
+            _ => panic!("Expected an author_contains function"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn function_parse_should_parse_a_description_regex_function() {
+        let output = Function::parse(
+            "description_regex(\"Patches/.*\\.esp\", \"requires \\w+\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::DescriptionRegex(p, r, d) => {
+                assert_eq!(PathBuf::from("Patches"), p);
+                assert_eq!(Regex::new("^.*\\.esp$").unwrap().as_str(), r.as_str());
+                assert_eq!(Regex::new("requires \\w+").unwrap().as_str(), d.as_str());
+            }
+            _ => panic!("Expected a description_regex function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_version_range_function() {
+        let output = Function::parse(
+            "version(\"Cargo.toml\", \"1.2 - 1.4.1\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::VersionInRange(p, l, u) => {
+                assert_eq!(Path::new("Cargo.toml"), p);
+                assert_eq!("1.2", l);
+                assert_eq!("1.4.1", u);
+            }
+            _ => panic!("Expected a version range function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_product_version_range_function() {
+        let output = Function::parse(
+            "product_version(\"Cargo.toml\", \"1.0-beta - 1.0\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::ProductVersionInRange(p, l, u) => {
+                assert_eq!(Path::new("Cargo.toml"), p);
+                assert_eq!("1.0-beta", l);
+                assert_eq!("1.0", u);
+            }
+            _ => panic!("Expected a product version range function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_error_if_a_version_has_no_comparator_and_is_not_a_range() {
+        assert!(
+            Function::parse("version(\"Cargo.toml\", \"1.2\")", &ParseOptions::default()).is_err()
+        );
+        assert!(Function::parse(
+            "version(\"Cargo.toml\", \"1.2 - \")",
+            &ParseOptions::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_checksum_manifest_function() {
+        let output = Function::parse(
+            "checksum_manifest(\"mod/checksums.sfv\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::ChecksumManifest(p) => assert_eq!(Path::new("mod/checksums.sfv"), p),
+            _ => panic!("Expected a checksum manifest function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_version_cmp_function() {
+        let output = Function::parse(
+            "version_cmp(\"skse64_loader.exe\", \"../SkyrimSE.exe\", ==)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::VersionCmp(p1, p2, c) => {
+                assert_eq!(Path::new("skse64_loader.exe"), p1);
+                assert_eq!(Path::new("../SkyrimSE.exe"), p2);
+                assert_eq!(ComparisonOperator::Equal, c);
+            }
+            _ => panic!("Expected a version_cmp function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_a_description_contains_text_function() {
+        let output = Function::parse(
+            "description_contains_text(\"Blank.esp\", \"Version: 1.0 (*)\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::DescriptionContainsText(p, t) => {
+                assert_eq!(Path::new("Blank.esp"), p);
+                assert_eq!("Version: 1.0 (*)", t);
+            }
+            _ => panic!("Expected a description_contains_text function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_an_is_valid_plugin_function() {
+        let output =
+            Function::parse("is_valid_plugin(\"Blank.esm\")", &ParseOptions::default()).unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::IsValidPlugin(f) => assert_eq!(Path::new("Blank.esm"), f),
+            _ => panic!("Expected an is_valid_plugin function"),
+        }
+    }
+
+    #[test]
+    fn function_parse_should_parse_an_is_newer_than_function() {
+        let output = Function::parse(
+            "is_newer_than(\"Bashed Patch, 0.esp\", \"Blank.esp\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output.0.is_empty());
+        match output.1 {
+            Function::IsNewerThan(p1, p2) => {
+                assert_eq!(Path::new("Bashed Patch, 0.esp"), p1);
+                assert_eq!(Path::new("Blank.esp"), p2);
+            }
+            _ => panic!("Expected an is_newer_than function"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn function_parse_should_error_if_a_compiled_regex_exceeds_the_size_limit() {
+        let options = ParseOptions::new().with_max_regex_size(Some(100));
+        let error = Function::parse("file(\"\\w{50}\\.esp\")", &options).unwrap_err();
+
+        match error {
+            Err::Failure(e) => assert_eq!(
+                "An error was encountered while parsing the expression \"\\w{50}\\.esp\": The compiled regex exceeds the size limit of 100 bytes",
+                e.to_string()
+            ),
+            _ => panic!("Expected a parsing failure"),
+        }
+
+        assert!(Function::parse("file(\"\\w{50}\\.esp\")", &ParseOptions::default()).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn function_parse_should_check_regex_features_in_all_regex_arguments() {
+        let options = ParseOptions::new()
+            .with_disallowed_regex_features(&[crate::RegexFeature::CountedRepetition]);
+
+        assert!(Function::parse("active(\"Blank.{2}\\.esp\")", &options).is_err());
+        assert!(Function::parse("many_active(\"Blank.{2}\\.esp\")", &options).is_err());
+        assert!(
+            Function::parse("filename_version(\"(\\d{2})\\.esp\", \"1\", ==)", &options).is_err()
+        );
+        assert!(
+            Function::parse("description_contains(\"Blank.esp\", \"a{2}\")", &options).is_err()
+        );
+        assert!(Function::parse("active(\"Blank.+\\.esp\")", &options).is_ok());
+    }
 }
@@ -1,9 +1,13 @@
 use std::{
     ffi::OsStr,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
 };
 
-use crate::{GameType, State};
+use unicase::UniCase;
+
+use super::eval::read_dir_entries;
+use crate::{DirEntry, Error, GameType, State};
 
 const GHOST_EXTENSION: &str = "ghost";
 const GHOST_EXTENSION_WITH_PERIOD: &str = ".ghost";
@@ -70,31 +74,371 @@ pub(super) fn normalise_file_name(game_type: GameType, name: &OsStr) -> &OsStr {
     name
 }
 
-pub(super) fn resolve_path_in_parent_paths<'a>(
+/// Fold the case of a plugin filename using full Unicode case folding, which
+/// doesn't depend on the locale and maps strings that differ only in case,
+/// such as `STRASSE.esp` and `straße.esp`, to the same string.
+pub(crate) fn fold_case(plugin_name: &str) -> String {
+    UniCase::new(plugin_name).to_folded_case()
+}
+
+/// Check if the given path's filename is the given plugin filename, ignoring
+/// case and any ghost extension.
+pub(crate) fn is_plugin_path(game_type: GameType, path: &Path, plugin_name: &str) -> bool {
+    path.file_name()
+        .map(|n| normalise_file_name(game_type, n))
+        .and_then(OsStr::to_str)
+        .is_some_and(|n| unicase::eq(n, plugin_name))
+}
+
+/// Split a path into its components, treating both forward and backward
+/// slashes as separators regardless of platform and skipping empty and `.`
+/// components.
+pub(super) fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\'])
+        .filter(|c| !c.is_empty() && *c != ".")
+}
+
+/// Check if the given path is absolute or contains a `..` component, and so
+/// could refer to something outside of the data paths it's resolved against.
+pub(super) fn is_path_traversal(path: &Path) -> bool {
+    if path.has_root() || path.is_absolute() {
+        return true;
+    }
+
+    let Some(path) = path.to_str() else {
+        return path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    };
+
+    has_drive_prefix(path) || path.starts_with(['/', '\\']) || split_path(path).any(|c| c == "..")
+}
+
+/// Check if the given path starts with a Windows drive letter, e.g. `C:`.
+fn has_drive_prefix(path: &str) -> bool {
+    let mut chars = path.chars();
+    matches!(
+        (chars.next(), chars.next()),
+        (Some(letter), Some(':')) if letter.is_ascii_alphabetic()
+    )
+}
+
+/// Check if the given path starts with the given allowlisted prefix, ignoring
+/// case, and doesn't contain any `..` components after the prefix that could
+/// take it back out of the allowlisted directory.
+fn is_allowed_by_prefix(path: &Path, prefix: &Path) -> bool {
+    let (Some(path_str), Some(prefix)) = (path.to_str(), prefix.to_str()) else {
+        return path.strip_prefix(prefix).is_ok_and(|rest| {
+            rest.components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        });
+    };
+
+    let mut components = split_path(path_str);
+    split_path(prefix).all(|p| components.next().is_some_and(|c| unicase::eq(c, p)))
+        && components.all(|c| c != "..")
+}
+
+/// Check that the given path is allowed by the state's path traversal
+/// allowlist, if it has one. Paths that start with a drive letter are only
+/// allowed if the state has a Wine path mapper to translate them, as they
+/// would otherwise be resolved against the data path.
+pub(super) fn check_path_is_allowed(state: &State, path: &Path) -> Result<(), Error> {
+    if state.wine_path_mapper.is_none() && path.to_str().is_some_and(has_drive_prefix) {
+        return Err(Error::UnmappedDrivePath(path.to_path_buf()));
+    }
+
+    let Some(allowlist) = &state.path_traversal_allowlist else {
+        return Ok(());
+    };
+
+    if is_path_traversal(path) && !allowlist.iter().any(|a| is_allowed_by_prefix(path, a)) {
+        Err(Error::PathTraversal(path.to_path_buf()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Find an existing path that is the given path joined onto the given parent
+/// path, ignoring the case of the given path's components and resolving any
+/// components that are DOS 8.3 short names to the long names that they
+/// abbreviate. This is only done on platforms with case-sensitive filesystems,
+/// as elsewhere the path would already have been found to exist. If more than
+/// one entry matches a component ignoring case and ordering should be
+/// deterministic, the entry with the smallest name is used, otherwise the
+/// first entry found is used.
+///
+/// Directory listings are read through the state's directory cache, and
+/// searching a directory with more entries than the state's limit is an error.
+fn find_path_ignoring_case(
+    state: &State,
+    parent_path: &Path,
+    path: &Path,
+) -> Result<Option<PathBuf>, Error> {
+    if cfg!(windows) {
+        return Ok(None);
+    }
+
+    let mut current_path = parent_path.to_path_buf();
+    for component in path.components() {
+        let Component::Normal(name) = component else {
+            current_path.push(component);
+            continue;
+        };
+
+        let joined_path = current_path.join(name);
+        if exists(state, &joined_path) {
+            current_path = joined_path;
+            continue;
+        }
+
+        let Some(name) = name.to_str() else {
+            return Ok(None);
+        };
+        let entries = match read_dir_entries(state, &current_path, state.max_directory_entries) {
+            Ok(Some(entries)) => entries,
+            // I/O errors are treated like the directory not existing, as they
+            // are when checking if a path exists.
+            Ok(None) | Err(Error::IoError(..)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if let Some(max_entries) = state.max_directory_entries {
+            if entries.len() > max_entries {
+                return Err(Error::DirectoryEntryLimitExceeded(
+                    current_path,
+                    max_entries,
+                ));
+            }
+        }
+
+        let Some(entry) = find_entry_ignoring_case(&entries, name, state.deterministic_ordering)
+            .or_else(|| find_entry_by_short_name(&entries, name))
+        else {
+            return Ok(None);
+        };
+        current_path.push(entry.file_name());
+    }
+
+    Ok(Some(current_path))
+}
+
+fn find_entry_ignoring_case<'a>(
+    entries: &'a [DirEntry],
+    name: &str,
+    deterministic_ordering: bool,
+) -> Option<&'a DirEntry> {
+    let mut entries = entries
+        .iter()
+        .filter(|e| e.file_name().to_str().is_some_and(|n| unicase::eq(n, name)));
+
+    if deterministic_ordering {
+        entries.min_by(|a, b| a.file_name().cmp(b.file_name()))
+    } else {
+        entries.next()
+    }
+}
+
+/// Find the entry in the given directory listing that the given DOS 8.3 short
+/// name abbreviates. Windows numbers the short names of long names that share
+/// a prefix in the order that they were created, which can't be known, so the
+/// short name is only resolved if it's numbered 1 and exactly one entry could
+/// have it, as anything else is ambiguous.
+fn find_entry_by_short_name<'a>(entries: &'a [DirEntry], name: &str) -> Option<&'a DirEntry> {
+    let short_name = ShortName::parse(name)?;
+    if short_name.number != 1 {
+        return None;
+    }
+
+    let mut candidates = entries.iter().filter(|e| {
+        e.file_name()
+            .to_str()
+            .is_some_and(|n| short_name.abbreviates(n))
+    });
+
+    let candidate = candidates.next()?;
+    if candidates.next().is_some() {
+        None
+    } else {
+        Some(candidate)
+    }
+}
+
+/// A DOS 8.3 short name like `PROGRA~1` or `BLANKP~2.ESP`, made of the start
+/// of a long name's base name, a tilde and a number, and the start of the long
+/// name's extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ShortName<'a> {
+    prefix: &'a str,
+    number: usize,
+    extension: &'a str,
+}
+
+impl<'a> ShortName<'a> {
+    const MAX_BASE_NAME_LENGTH: usize = 8;
+    const MAX_EXTENSION_LENGTH: usize = 3;
+
+    fn parse(name: &'a str) -> Option<Self> {
+        let (base_name, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+        let (prefix, number) = base_name.rsplit_once('~')?;
+
+        let is_valid = !prefix.is_empty()
+            && base_name.len() <= Self::MAX_BASE_NAME_LENGTH
+            && extension.len() <= Self::MAX_EXTENSION_LENGTH
+            && prefix.chars().all(is_short_name_char)
+            && extension.chars().all(is_short_name_char)
+            && !number.starts_with('0')
+            && number.chars().all(|c| c.is_ascii_digit());
+
+        if !is_valid {
+            return None;
+        }
+
+        Some(ShortName {
+            prefix,
+            number: number.parse().ok()?,
+            extension,
+        })
+    }
+
+    /// Check if this short name could have been generated for the given long
+    /// name. Windows generates short names by removing spaces and periods
+    /// from the base name, replacing characters that aren't allowed in short
+    /// names with underscores, and truncating the result.
+    fn abbreviates(&self, long_name: &str) -> bool {
+        let (base_name, extension) = match long_name.rsplit_once('.') {
+            Some((base_name, extension)) if !base_name.is_empty() => (base_name, extension),
+            _ => (long_name, ""),
+        };
+
+        let base_name: String = base_name
+            .chars()
+            .filter(|c| *c != ' ' && *c != '.')
+            .map(to_short_name_char)
+            .collect();
+        let extension: String = extension
+            .chars()
+            .filter(|c| *c != ' ')
+            .map(to_short_name_char)
+            .take(Self::MAX_EXTENSION_LENGTH)
+            .collect();
+
+        base_name
+            .get(..self.prefix.len())
+            .is_some_and(|p| p.eq_ignore_ascii_case(self.prefix))
+            && extension.eq_ignore_ascii_case(self.extension)
+    }
+}
+
+fn is_short_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'()-@^_`{}~".contains(c)
+}
+
+fn to_short_name_char(c: char) -> char {
+    if is_short_name_char(c) {
+        c
+    } else {
+        '_'
+    }
+}
+
+fn resolve_path_in_parent_path(
+    state: &State,
+    parent_path: &Path,
     path: &Path,
-    parent_paths: impl Iterator<Item = &'a PathBuf>,
     try_with_ghost_extension: bool,
-) -> Option<PathBuf> {
-    for parent_path in parent_paths {
-        let joined_path = parent_path.join(path);
+) -> Result<Option<PathBuf>, Error> {
+    let joined_path = parent_path.join(path);
+
+    if exists(state, &joined_path) {
+        return Ok(Some(joined_path));
+    }
 
-        if joined_path.exists() {
-            return Some(joined_path);
+    if try_with_ghost_extension {
+        let ghosted_path = add_ghost_extension(&joined_path);
+
+        if exists(state, &ghosted_path) {
+            return Ok(Some(ghosted_path));
         }
+    }
 
-        if try_with_ghost_extension {
-            let ghosted_path = add_ghost_extension(&joined_path);
+    if let Some(found_path) = find_path_ignoring_case(state, parent_path, path)? {
+        return Ok(Some(found_path));
+    }
 
-            if ghosted_path.exists() {
-                return Some(ghosted_path);
-            }
+    if try_with_ghost_extension {
+        find_path_ignoring_case(state, parent_path, &add_ghost_extension(path))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Errors are treated like the path not existing, as they are by
+/// [Path::exists]. Paths with cached metadata aren't checked again.
+fn exists(state: &State, path: &Path) -> bool {
+    if let Some(metadata) = state.metadata_cache.get(path) {
+        return metadata.is_some();
+    }
+
+    state.file_system.exists(path).unwrap_or(false)
+}
+
+fn resolve_path_in_parent_paths<'a>(
+    state: &State,
+    path: &Path,
+    parent_paths: impl Iterator<Item = &'a PathBuf>,
+    try_with_ghost_extension: bool,
+) -> Result<Option<PathBuf>, Error> {
+    for parent_path in parent_paths {
+        let result =
+            resolve_path_in_parent_path(state, parent_path, path, try_with_ghost_extension)?;
+        if result.is_some() {
+            return Ok(result);
         }
     }
 
-    None
+    Ok(None)
 }
 
-pub(super) fn resolve_path(state: &State, path: &Path) -> PathBuf {
+/// Resolve the given path relative to the game's data paths, taking ghosted
+/// plugins, case differences and DOS 8.3 short names into account. Results
+/// are cached, so the filesystem is only checked the first time a path is
+/// resolved, and paths that could not be found are also recorded in the
+/// state's missing path cache.
+pub(super) fn resolve_path(state: &State, path: &Path) -> Result<PathBuf, Error> {
+    if let Some(resolved_path) = state.resolved_path_cache.get(path) {
+        return Ok(resolved_path);
+    }
+
+    let (resolved_path, was_found) = resolve_path_uncached(state, path)?;
+
+    let path = path.to_path_buf();
+    if !was_found {
+        state.missing_path_cache.insert(&path, SystemTime::now());
+    }
+    state
+        .resolved_path_cache
+        .insert(&path, resolved_path.clone());
+
+    Ok(resolved_path)
+}
+
+/// Get when the given path was found to be missing, if it's in the state's
+/// missing path cache.
+pub(super) fn missing_since(state: &State, path: &Path) -> Option<SystemTime> {
+    state.missing_path_cache.get(path)
+}
+
+/// Resolve the path, also returning false if nothing was found at any of the
+/// paths that it could resolve to. Translated Wine paths are assumed to exist.
+fn resolve_path_uncached(state: &State, path: &Path) -> Result<(PathBuf, bool), Error> {
+    if let Some(path) = state
+        .wine_path_mapper
+        .as_ref()
+        .and_then(|m| m.translate(path))
+    {
+        return Ok((path, true));
+    }
+
     let try_with_ghost_extension = state.game_type.allows_ghosted_plugins()
         && has_unghosted_plugin_file_extension(state.game_type, path);
 
@@ -104,31 +448,92 @@ pub(super) fn resolve_path(state: &State, path: &Path) -> PathBuf {
     // main data path is checked.
     let result = match state.game_type {
         GameType::OpenMW => resolve_path_in_parent_paths(
+            state,
             path,
             state.additional_data_paths.iter().rev(),
             try_with_ghost_extension,
         ),
         _ => resolve_path_in_parent_paths(
+            state,
             path,
             state.additional_data_paths.iter(),
             try_with_ghost_extension,
         ),
-    };
+    }?;
 
     if let Some(path) = result {
-        return path;
+        return Ok((path, true));
     }
 
     // Now check the main data path.
+    if let Some(path) =
+        resolve_path_in_parent_path(state, &state.data_path, path, try_with_ghost_extension)?
+    {
+        return Ok((path, true));
+    }
+
     let joined_path = state.data_path.join(path);
 
-    if !joined_path.exists() && try_with_ghost_extension {
-        add_ghost_extension(&joined_path)
+    if try_with_ghost_extension {
+        Ok((add_ghost_extension(&joined_path), false))
     } else {
-        joined_path
+        Ok((joined_path, false))
     }
 }
 
+/// Get the data paths in the order that they're searched for a path, which is
+/// the order in which the first match is used.
+pub(crate) fn data_paths_in_search_order(state: &State) -> Vec<&Path> {
+    let additional_data_paths = state.additional_data_paths.iter().map(PathBuf::as_path);
+    match state.game_type {
+        GameType::OpenMW => additional_data_paths
+            .rev()
+            .chain(std::iter::once(state.data_path.as_path()))
+            .collect(),
+        _ => additional_data_paths
+            .chain(std::iter::once(state.data_path.as_path()))
+            .collect(),
+    }
+}
+
+/// Get the paths that resolving the given path would check the existence of,
+/// in order, without accessing the filesystem, and the path that it would
+/// resolve to if it was found in the main data path. Paths that have already
+/// been resolved or that are translated Wine paths don't need to be checked.
+///
+/// If none of the paths exist, resolution also lists the data paths'
+/// contents to find a path that only differs by case, which isn't included.
+pub(crate) fn plan_path_resolution(state: &State, path: &Path) -> (Vec<PathBuf>, PathBuf) {
+    if let Some(resolved_path) = state.resolved_path_cache.get(path) {
+        return (Vec::new(), resolved_path);
+    }
+
+    if let Some(translated_path) = state
+        .wine_path_mapper
+        .as_ref()
+        .and_then(|m| m.translate(path))
+    {
+        return (Vec::new(), translated_path);
+    }
+
+    let try_with_ghost_extension = state.game_type.allows_ghosted_plugins()
+        && has_unghosted_plugin_file_extension(state.game_type, path);
+
+    let mut checked_paths = Vec::new();
+    for data_path in data_paths_in_search_order(state) {
+        let joined_path = data_path.join(path);
+        if try_with_ghost_extension {
+            let ghosted_path = add_ghost_extension(&joined_path);
+            checked_paths.push(joined_path);
+            checked_paths.push(ghosted_path);
+        } else {
+            checked_paths.push(joined_path);
+        }
+    }
+
+    (checked_paths, state.data_path.join(path))
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::create_dir_all;
@@ -655,7 +1060,7 @@ mod tests {
         let data_path = PathBuf::from(".");
         let state = State::new(GameType::Skyrim, data_path.clone());
         let input_path = Path::new("README.md");
-        let resolved_path = resolve_path(&state, input_path);
+        let resolved_path = resolve_path(&state, input_path).unwrap();
 
         assert_eq!(data_path.join(input_path), resolved_path);
     }
@@ -666,12 +1071,12 @@ mod tests {
         let data_path = PathBuf::from(".");
         let state = State::new(GameType::Skyrim, data_path.clone());
         let input_path = Path::new("plugin.esp.ghost");
-        let resolved_path = resolve_path(&state, input_path);
+        let resolved_path = resolve_path(&state, input_path).unwrap();
 
         assert_eq!(data_path.join(input_path), resolved_path);
 
         let input_path = Path::new("file.txt");
-        let resolved_path = resolve_path(&state, input_path);
+        let resolved_path = resolve_path(&state, input_path).unwrap();
 
         assert_eq!(data_path.join(input_path), resolved_path);
     }
@@ -682,7 +1087,7 @@ mod tests {
         let data_path = PathBuf::from(".");
         let state = State::new(GameType::Skyrim, data_path.clone());
         let input_path = Path::new("plugin.esp");
-        let resolved_path = resolve_path(&state, input_path);
+        let resolved_path = resolve_path(&state, input_path).unwrap();
 
         assert_eq!(
             data_path.join(input_path.with_extension("esp.ghost")),
@@ -695,7 +1100,7 @@ mod tests {
         let data_path = PathBuf::from(".");
         let state = State::new(GameType::OpenMW, data_path.clone());
         let input_path = Path::new("plugin.esp");
-        let resolved_path = resolve_path(&state, input_path);
+        let resolved_path = resolve_path(&state, input_path).unwrap();
 
         assert_eq!(data_path.join(input_path), resolved_path);
     }
@@ -731,7 +1136,7 @@ mod tests {
         ]);
 
         let input_path = Path::new("Cargo.toml");
-        let resolved_path = resolve_path(&state, input_path);
+        let resolved_path = resolve_path(&state, input_path).unwrap();
 
         assert_eq!(external_data_path_1.join(input_path), resolved_path);
     }
@@ -768,8 +1173,318 @@ mod tests {
         ]);
 
         let input_path = Path::new("Cargo.toml");
-        let resolved_path = resolve_path(&state, input_path);
+        let resolved_path = resolve_path(&state, input_path).unwrap();
 
         assert_eq!(external_data_path_2.join(input_path), resolved_path);
     }
+
+    #[test]
+    fn resolve_path_should_translate_a_drive_letter_path_using_the_wine_path_mapper() {
+        let mut state = State::new(GameType::Skyrim, PathBuf::from("."));
+        state.set_wine_path_mapper(Some(
+            crate::WinePathMapper::new().with_drive('z', PathBuf::from("/")),
+        ));
+
+        let resolved_path = resolve_path(&state, Path::new("Z:\\games\\TESV.exe")).unwrap();
+
+        assert_eq!(PathBuf::from("/games/TESV.exe"), resolved_path);
+    }
+
+    #[test]
+    fn resolve_path_should_not_translate_a_drive_letter_path_if_there_is_no_wine_path_mapper() {
+        let state = State::new(GameType::Skyrim, PathBuf::from("."));
+        let input_path = Path::new("Z:\\games\\TESV.exe");
+
+        assert_eq!(
+            Path::new(".").join(input_path),
+            resolve_path(&state, input_path).unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn resolve_path_should_find_a_path_that_differs_only_in_case() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        create_dir_all(data_path.join("Textures")).unwrap();
+        std::fs::write(data_path.join("Textures").join("Sky.dds"), "").unwrap();
+
+        let state = State::new(GameType::Skyrim, data_path.clone());
+
+        assert_eq!(
+            data_path.join("Textures").join("Sky.dds"),
+            resolve_path(&state, Path::new("textures/sky.dds")).unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn resolve_path_should_cache_the_directories_listed_when_ignoring_case() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        create_dir_all(data_path.join("Textures")).unwrap();
+
+        let state = State::new(GameType::Skyrim, data_path.clone());
+
+        resolve_path(&state, Path::new("textures/sky.dds")).unwrap();
+
+        assert!(state.directory_cache.get(&data_path).is_some());
+        assert!(state
+            .directory_cache
+            .get(&data_path.join("Textures"))
+            .is_some());
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn resolve_path_should_error_if_a_directory_has_too_many_entries_to_search_ignoring_case() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        create_dir_all(&data_path).unwrap();
+        for name in ["a.dds", "b.dds", "Sky.dds"] {
+            std::fs::write(data_path.join(name), "").unwrap();
+        }
+
+        let mut state = State::new(GameType::Skyrim, data_path.clone());
+        state.set_max_directory_entries(Some(2));
+
+        assert!(matches!(
+            resolve_path(&state, Path::new("sky.dds")),
+            Err(Error::DirectoryEntryLimitExceeded(p, 2)) if p == data_path
+        ));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn resolve_path_should_use_the_smallest_matching_name_if_ordering_is_deterministic() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        create_dir_all(&data_path).unwrap();
+        for name in ["sky.dds", "Sky.DDS", "SKY.dds"] {
+            std::fs::write(data_path.join(name), "").unwrap();
+        }
+
+        let mut state = State::new(GameType::Skyrim, data_path.clone());
+        state.set_deterministic_ordering(true);
+
+        assert_eq!(
+            data_path.join("SKY.dds"),
+            resolve_path(&state, Path::new("Sky.dds")).unwrap()
+        );
+    }
+
+    #[test]
+    fn short_name_parse_should_only_accept_numbered_8_3_names() {
+        assert_eq!(
+            Some(ShortName {
+                prefix: "BLANK",
+                number: 12,
+                extension: "ESP"
+            }),
+            ShortName::parse("BLANK~12.ESP")
+        );
+        assert_eq!(
+            Some(ShortName {
+                prefix: "PROGRA",
+                number: 1,
+                extension: ""
+            }),
+            ShortName::parse("PROGRA~1")
+        );
+
+        for name in [
+            "Blank.esp",
+            "~1.esp",
+            "BLANKPL~12.esp",
+            "BLANKP~1.ghost",
+            "BLANK~01.esp",
+            "BLANK~X.esp",
+            "BL NK~1.esp",
+        ] {
+            assert_eq!(None, ShortName::parse(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn short_name_abbreviates_should_compare_with_the_start_of_the_normalised_long_name() {
+        let short_name = ShortName::parse("BLANKP~1.ESP").unwrap();
+
+        assert!(short_name.abbreviates("Blank Plugin.esp"));
+        assert!(short_name.abbreviates("blank.plugin.esp"));
+        assert!(!short_name.abbreviates("Blank.esp"));
+        assert!(!short_name.abbreviates("Blank Plugin.esm"));
+
+        let short_name = ShortName::parse("A_B~1.ES_").unwrap();
+        assert!(short_name.abbreviates("a+b plugin.es+"));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn resolve_path_should_resolve_short_names_to_long_names() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        create_dir_all(data_path.join("Long Directory")).unwrap();
+        std::fs::write(data_path.join("Long Directory/Blank Plugin.esp"), "").unwrap();
+
+        let state = State::new(GameType::Skyrim, data_path.clone());
+
+        assert_eq!(
+            data_path.join("Long Directory/Blank Plugin.esp"),
+            resolve_path(&state, Path::new("LONGDI~1/blankp~1.esp")).unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn resolve_path_should_not_resolve_short_names_that_are_ambiguous_or_not_numbered_1() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        create_dir_all(data_path.join("Long Directory")).unwrap();
+        for name in [
+            "Long Directory/Blank Plugin.esp",
+            "Long Directory/Blank Patch.esp",
+        ] {
+            std::fs::write(data_path.join(name), "").unwrap();
+        }
+
+        let state = State::new(GameType::Skyrim, data_path.clone());
+
+        for path in ["LONGDI~1/BLANKP~1.ESP", "LONGDI~3/Blank Patch.esp"] {
+            assert!(missing_since(&state, Path::new(path)).is_none());
+            resolve_path(&state, Path::new(path)).unwrap();
+            assert!(missing_since(&state, Path::new(path)).is_some());
+        }
+        assert_eq!(
+            data_path.join("Long Directory/Blank Patch.esp"),
+            resolve_path(&state, Path::new("LONGDI~1/Blank Patch.esp")).unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn resolve_path_should_find_a_ghosted_plugin_that_differs_only_in_case() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        create_dir_all(&data_path).unwrap();
+        std::fs::write(data_path.join("Blank.esp.ghost"), "").unwrap();
+
+        let state = State::new(GameType::Skyrim, data_path.clone());
+
+        assert_eq!(
+            data_path.join("Blank.esp.ghost"),
+            resolve_path(&state, Path::new("blank.esp")).unwrap()
+        );
+    }
+
+    #[test]
+    fn resolve_path_should_cache_resolved_paths_until_the_condition_cache_is_cleared() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let data_path = tmp_dir.path().join("Data");
+        create_dir_all(&data_path).unwrap();
+
+        let mut state = State::new(GameType::Skyrim, data_path.clone());
+        let input_path = Path::new("Blank.esp");
+
+        assert_eq!(
+            data_path.join("Blank.esp.ghost"),
+            resolve_path(&state, input_path).unwrap()
+        );
+
+        std::fs::write(data_path.join("Blank.esp"), "").unwrap();
+
+        assert_eq!(
+            data_path.join("Blank.esp.ghost"),
+            resolve_path(&state, input_path).unwrap()
+        );
+
+        state.clear_condition_cache().unwrap();
+
+        assert_eq!(
+            data_path.join("Blank.esp"),
+            resolve_path(&state, input_path).unwrap()
+        );
+    }
+
+    #[test]
+    fn is_path_traversal_should_be_true_for_absolute_paths_and_parent_components() {
+        assert!(is_path_traversal(Path::new("/etc/passwd")));
+        assert!(is_path_traversal(Path::new("\\Windows\\System32")));
+        assert!(is_path_traversal(Path::new("C:\\Windows")));
+        assert!(is_path_traversal(Path::new("c:/Windows")));
+        assert!(is_path_traversal(Path::new("../skse_loader.exe")));
+        assert!(is_path_traversal(Path::new("Data\\..\\..\\file.txt")));
+    }
+
+    #[test]
+    fn is_path_traversal_should_be_false_for_relative_paths_within_the_data_path() {
+        assert!(!is_path_traversal(Path::new("Blank.esp")));
+        assert!(!is_path_traversal(Path::new("textures/sky..dds")));
+        assert!(!is_path_traversal(Path::new("./Blank.esp")));
+    }
+
+    #[test]
+    fn check_path_is_allowed_should_allow_any_path_if_there_is_no_allowlist() {
+        let state = State::new(GameType::Skyrim, PathBuf::from("."));
+
+        assert!(check_path_is_allowed(&state, Path::new("../skse_loader.exe")).is_ok());
+    }
+
+    #[test]
+    fn check_path_is_allowed_should_not_allow_a_drive_letter_path_if_there_is_no_wine_path_mapper()
+    {
+        let state = State::new(GameType::Skyrim, PathBuf::from("."));
+
+        assert!(matches!(
+            check_path_is_allowed(&state, Path::new("C:\\Windows\\x.dll")),
+            Err(Error::UnmappedDrivePath(p)) if p == Path::new("C:\\Windows\\x.dll")
+        ));
+        assert!(matches!(
+            check_path_is_allowed(&state, Path::new("z:/games/TESV.exe")),
+            Err(Error::UnmappedDrivePath(_))
+        ));
+        assert!(check_path_is_allowed(&state, Path::new("C.esp")).is_ok());
+    }
+
+    #[test]
+    fn check_path_is_allowed_should_allow_a_drive_letter_path_if_there_is_a_wine_path_mapper() {
+        let mut state = State::new(GameType::Skyrim, PathBuf::from("."));
+        state.set_wine_path_mapper(Some(
+            crate::WinePathMapper::new().with_drive('z', PathBuf::from("/")),
+        ));
+
+        assert!(check_path_is_allowed(&state, Path::new("Z:\\games\\TESV.exe")).is_ok());
+    }
+
+    #[test]
+    fn check_path_is_allowed_should_allow_traversals_under_an_allowlisted_path() {
+        let mut state = State::new(GameType::Skyrim, PathBuf::from("."));
+        state.set_path_traversal_allowlist(Some(vec![PathBuf::from("../SKSE")]));
+
+        assert!(check_path_is_allowed(&state, Path::new("..\\skse\\Plugins\\a.dll")).is_ok());
+        assert!(check_path_is_allowed(&state, Path::new("Blank.esp")).is_ok());
+        assert!(matches!(
+            check_path_is_allowed(&state, Path::new("../skse_loader.exe")),
+            Err(Error::PathTraversal(_))
+        ));
+        assert!(matches!(
+            check_path_is_allowed(&state, Path::new("../SKSEx/a.dll")),
+            Err(Error::PathTraversal(_))
+        ));
+    }
+
+    #[test]
+    fn check_path_is_allowed_should_not_allow_traversals_out_of_an_allowlisted_path() {
+        let mut state = State::new(GameType::Skyrim, PathBuf::from("."));
+        state.set_path_traversal_allowlist(Some(vec![PathBuf::from("../SKSE")]));
+
+        assert!(matches!(
+            check_path_is_allowed(&state, Path::new("../SKSE/../../Windows/x.dll")),
+            Err(Error::PathTraversal(_))
+        ));
+        assert!(matches!(
+            check_path_is_allowed(&state, Path::new("..\\skse\\Plugins\\..\\..\\x.dll")),
+            Err(Error::PathTraversal(_))
+        ));
+        assert!(check_path_is_allowed(&state, Path::new("../SKSE/./Plugins/a.dll")).is_ok());
+    }
 }
@@ -0,0 +1,215 @@
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use encoding_rs::WINDOWS_1252;
+
+use crate::{FileSystem, GameType};
+
+const MORROWIND_HEADER_TYPE: &[u8] = b"TES3";
+const HEADER_TYPE: &[u8] = b"TES4";
+
+const MORROWIND_RECORD_HEADER_SIZE: u64 = 16;
+const OBLIVION_RECORD_HEADER_SIZE: u64 = 20;
+const RECORD_HEADER_SIZE: u64 = 24;
+
+/// Morrowind's HEDR subrecord holds the author in a fixed-size field after the
+/// version and file type.
+const MORROWIND_AUTHOR_OFFSET: usize = 8;
+const MORROWIND_AUTHOR_SIZE: usize = 32;
+
+/// Read the author from the header record of the plugin at the given path.
+/// Morrowind plugins store it in a fixed-size field of their HEDR subrecord,
+/// and plugins for later games in a CNAM subrecord. Files that aren't plugins
+/// for the given game have no author, as do plugins with an empty author.
+///
+/// esplugin doesn't expose the author, so the header record is read here.
+pub(super) fn read_author(
+    game_type: GameType,
+    file_system: &dyn FileSystem,
+    path: &Path,
+) -> io::Result<Option<String>> {
+    let mut reader = BufReader::new(file_system.open(path)?);
+
+    match read_author_from(game_type, &mut reader) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        result => result,
+    }
+}
+
+fn read_author_from<R: Read>(game_type: GameType, reader: &mut R) -> io::Result<Option<String>> {
+    let (expected_type, header_size) = match game_type {
+        GameType::Morrowind | GameType::OpenMW => {
+            (MORROWIND_HEADER_TYPE, MORROWIND_RECORD_HEADER_SIZE)
+        }
+        GameType::Oblivion => (HEADER_TYPE, OBLIVION_RECORD_HEADER_SIZE),
+        _ => (HEADER_TYPE, RECORD_HEADER_SIZE),
+    };
+
+    let mut record_type = [0; 4];
+    reader.read_exact(&mut record_type)?;
+    if record_type.as_slice() != expected_type {
+        return Ok(None);
+    }
+
+    let mut size = [0; 4];
+    reader.read_exact(&mut size)?;
+    let size = u64::from(u32::from_le_bytes(size));
+
+    // The rest of the record header is flags and IDs that aren't needed.
+    io::copy(
+        &mut reader.by_ref().take(header_size.saturating_sub(8)),
+        &mut io::sink(),
+    )?;
+
+    let mut data = Vec::new();
+    reader.by_ref().take(size).read_to_end(&mut data)?;
+
+    let author = match game_type {
+        GameType::Morrowind | GameType::OpenMW => find_morrowind_subrecord(&data, *b"HEDR")
+            .and_then(|hedr| {
+                hedr.get(MORROWIND_AUTHOR_OFFSET..)
+                    .map(|a| a.get(..MORROWIND_AUTHOR_SIZE).unwrap_or(a))
+            }),
+        _ => find_subrecord(&data, *b"CNAM"),
+    };
+
+    Ok(author
+        .map(until_first_null)
+        .filter(|a| !a.is_empty())
+        .map(|a| WINDOWS_1252.decode_without_bom_handling(a).0.into_owned()))
+}
+
+/// Morrowind subrecords have 4-byte sizes.
+fn find_morrowind_subrecord(mut data: &[u8], target_type: [u8; 4]) -> Option<&[u8]> {
+    while let Some((subrecord_type, rest)) = data.split_first_chunk::<4>() {
+        let (size, rest) = rest.split_first_chunk::<4>()?;
+        let size = usize::try_from(u32::from_le_bytes(*size)).ok()?;
+        let (subrecord_data, rest) = rest.split_at_checked(size)?;
+
+        if *subrecord_type == target_type {
+            return Some(subrecord_data);
+        }
+        data = rest;
+    }
+
+    None
+}
+
+/// Subrecords after Morrowind have 2-byte sizes, and an XXXX subrecord holds
+/// the 4-byte size of a following subrecord that is too large for that.
+fn find_subrecord(mut data: &[u8], target_type: [u8; 4]) -> Option<&[u8]> {
+    let mut large_size = None;
+
+    while let Some((subrecord_type, rest)) = data.split_first_chunk::<4>() {
+        let (size, rest) = rest.split_first_chunk::<2>()?;
+        let size = large_size
+            .take()
+            .unwrap_or_else(|| u32::from(u16::from_le_bytes(*size)));
+        let (subrecord_data, rest) = rest.split_at_checked(usize::try_from(size).ok()?)?;
+
+        if *subrecord_type == target_type {
+            return Some(subrecord_data);
+        } else if subrecord_type == b"XXXX" {
+            large_size = subrecord_data
+                .first_chunk::<4>()
+                .map(|s| u32::from_le_bytes(*s));
+        }
+        data = rest;
+    }
+
+    None
+}
+
+fn until_first_null(bytes: &[u8]) -> &[u8] {
+    bytes.split(|b| *b == 0).next().unwrap_or(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    fn subrecord(subrecord_type: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut bytes = subrecord_type.to_vec();
+        bytes.extend_from_slice(&u16::try_from(data.len()).unwrap().to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn record(record_type: &[u8], header_size: usize, subrecords: &[u8]) -> Vec<u8> {
+        let mut bytes = record_type.to_vec();
+        bytes.extend_from_slice(&u32::try_from(subrecords.len()).unwrap().to_le_bytes());
+        bytes.resize(header_size, 0);
+        bytes.extend_from_slice(subrecords);
+        bytes
+    }
+
+    fn author(game_type: GameType, bytes: &[u8]) -> Option<String> {
+        read_author_from(game_type, &mut Cursor::new(bytes)).unwrap()
+    }
+
+    #[test]
+    fn read_author_from_should_read_the_cnam_subrecord() {
+        let mut subrecords = subrecord(b"HEDR", &[0; 12]);
+        subrecords.extend(subrecord(b"CNAM", b"Someone \xe9\0"));
+        subrecords.extend(subrecord(b"SNAM", b"A description\0"));
+
+        assert_eq!(
+            Some("Someone \u{e9}".to_owned()),
+            author(GameType::SkyrimSE, &record(b"TES4", 24, &subrecords))
+        );
+        assert_eq!(
+            Some("Someone \u{e9}".to_owned()),
+            author(GameType::Oblivion, &record(b"TES4", 20, &subrecords))
+        );
+    }
+
+    #[test]
+    fn read_author_from_should_use_the_size_given_by_a_preceding_xxxx_subrecord() {
+        let mut subrecords = subrecord(b"XXXX", &6u32.to_le_bytes());
+        subrecords.extend(subrecord(b"ONAM", &[]));
+        subrecords.extend_from_slice(&[1; 6]);
+        subrecords.extend(subrecord(b"CNAM", b"Someone\0"));
+
+        assert_eq!(
+            Some("Someone".to_owned()),
+            author(GameType::Fallout4, &record(b"TES4", 24, &subrecords))
+        );
+    }
+
+    #[test]
+    fn read_author_from_should_read_the_author_field_of_a_morrowind_hedr_subrecord() {
+        let mut hedr = vec![0; 8];
+        hedr.extend_from_slice(b"Someone");
+        hedr.resize(300, 0);
+
+        let mut subrecords = b"HEDR".to_vec();
+        subrecords.extend_from_slice(&300u32.to_le_bytes());
+        subrecords.extend_from_slice(&hedr);
+
+        assert_eq!(
+            Some("Someone".to_owned()),
+            author(GameType::Morrowind, &record(b"TES3", 16, &subrecords))
+        );
+    }
+
+    #[test]
+    fn read_author_from_should_return_none_if_there_is_no_author() {
+        let subrecords = subrecord(b"HEDR", &[0; 12]);
+        let empty_author = [subrecords.clone(), subrecord(b"CNAM", b"\0")].concat();
+
+        assert_eq!(
+            None,
+            author(GameType::SkyrimSE, &record(b"TES4", 24, &subrecords))
+        );
+        assert_eq!(
+            None,
+            author(GameType::SkyrimSE, &record(b"TES4", 24, &empty_author))
+        );
+        assert_eq!(
+            None,
+            author(GameType::Morrowind, &record(b"TES4", 24, &subrecords))
+        );
+    }
+}
@@ -0,0 +1,186 @@
+/// The registry hives that `registry_value()` can read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RegistryRoot {
+    ClassesRoot,
+    CurrentUser,
+    LocalMachine,
+    Users,
+}
+
+/// Split a registry key path into its root hive and the path of the subkey
+/// within it. The hive may be given by its full name or its abbreviation, in
+/// any case, and must be followed by a backslash and a non-empty subkey path.
+pub(crate) fn split_key(key: &str) -> Option<(RegistryRoot, &str)> {
+    let (root, subkey) = key.split_once('\\')?;
+
+    let root = if root.eq_ignore_ascii_case("HKCR")
+        || root.eq_ignore_ascii_case("HKEY_CLASSES_ROOT")
+    {
+        RegistryRoot::ClassesRoot
+    } else if root.eq_ignore_ascii_case("HKCU") || root.eq_ignore_ascii_case("HKEY_CURRENT_USER") {
+        RegistryRoot::CurrentUser
+    } else if root.eq_ignore_ascii_case("HKLM") || root.eq_ignore_ascii_case("HKEY_LOCAL_MACHINE") {
+        RegistryRoot::LocalMachine
+    } else if root.eq_ignore_ascii_case("HKU") || root.eq_ignore_ascii_case("HKEY_USERS") {
+        RegistryRoot::Users
+    } else {
+        return None;
+    };
+
+    if subkey.is_empty() {
+        None
+    } else {
+        Some((root, subkey))
+    }
+}
+
+/// Read a string, DWORD or QWORD value from the registry. Numbers are read as
+/// their decimal representations, and environment variables in expandable
+/// strings are not expanded. Values that don't exist, can't be read or have
+/// another type are not read.
+#[cfg(all(windows, feature = "windows-registry"))]
+#[expect(unsafe_code)]
+pub(super) fn read_value(key: &str, value_name: &str) -> Option<String> {
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{
+        RegGetValueW, HKEY_CLASSES_ROOT, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, HKEY_USERS,
+        REG_DWORD, REG_EXPAND_SZ, REG_QWORD, REG_SZ, REG_VALUE_TYPE, RRF_NOEXPAND,
+        RRF_RT_REG_DWORD, RRF_RT_REG_EXPAND_SZ, RRF_RT_REG_QWORD, RRF_RT_REG_SZ,
+    };
+
+    let (root, subkey) = split_key(key)?;
+    let hkey = match root {
+        RegistryRoot::ClassesRoot => HKEY_CLASSES_ROOT,
+        RegistryRoot::CurrentUser => HKEY_CURRENT_USER,
+        RegistryRoot::LocalMachine => HKEY_LOCAL_MACHINE,
+        RegistryRoot::Users => HKEY_USERS,
+    };
+
+    let subkey = to_wide_string(subkey);
+    let value_name = to_wide_string(value_name);
+    let flags =
+        RRF_RT_REG_SZ | RRF_RT_REG_EXPAND_SZ | RRF_RT_REG_DWORD | RRF_RT_REG_QWORD | RRF_NOEXPAND;
+
+    let mut value_type: REG_VALUE_TYPE = 0;
+    let mut size: u32 = 0;
+    // SAFETY: The subkey and value name are null-terminated UTF-16 strings
+    // that outlive the call, and passing a null data pointer only queries the
+    // size of the value.
+    let status = unsafe {
+        RegGetValueW(
+            hkey,
+            subkey.as_ptr(),
+            value_name.as_ptr(),
+            flags,
+            &mut value_type,
+            std::ptr::null_mut(),
+            &mut size,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+
+    let mut data = vec![0_u8; usize::try_from(size).ok()?];
+    // SAFETY: The data buffer is size bytes long, which RegGetValueW won't
+    // write past, and the other pointers are valid as above.
+    let status = unsafe {
+        RegGetValueW(
+            hkey,
+            subkey.as_ptr(),
+            value_name.as_ptr(),
+            flags,
+            &mut value_type,
+            data.as_mut_ptr().cast(),
+            &mut size,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    data.truncate(usize::try_from(size).ok()?);
+
+    match value_type {
+        REG_SZ | REG_EXPAND_SZ => {
+            let wide: Vec<u16> = data
+                .chunks_exact(2)
+                .filter_map(|c| c.first_chunk::<2>())
+                .map(|c| u16::from_le_bytes(*c))
+                .collect();
+
+            Some(
+                String::from_utf16_lossy(&wide)
+                    .trim_end_matches('\0')
+                    .to_owned(),
+            )
+        }
+        REG_DWORD => data
+            .first_chunk::<4>()
+            .map(|d| u32::from_le_bytes(*d).to_string()),
+        REG_QWORD => data
+            .first_chunk::<8>()
+            .map(|d| u64::from_le_bytes(*d).to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(all(windows, feature = "windows-registry"))]
+fn to_wide_string(string: &str) -> Vec<u16> {
+    string.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// The registry can only be read on Windows, and only if the crate was built
+/// with the windows-registry feature.
+#[cfg(all(feature = "eval", not(all(windows, feature = "windows-registry"))))]
+pub(super) fn read_value(_key: &str, _value_name: &str) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_key_should_accept_full_and_abbreviated_root_names_in_any_case() {
+        assert_eq!(
+            Some((RegistryRoot::LocalMachine, "SOFTWARE\\Bethesda Softworks")),
+            split_key("HKLM\\SOFTWARE\\Bethesda Softworks")
+        );
+        assert_eq!(
+            Some((RegistryRoot::LocalMachine, "SOFTWARE")),
+            split_key("hkey_local_machine\\SOFTWARE")
+        );
+        assert_eq!(
+            Some((RegistryRoot::CurrentUser, "Software")),
+            split_key("HKCU\\Software")
+        );
+        assert_eq!(
+            Some((RegistryRoot::ClassesRoot, ".esp")),
+            split_key("HKEY_CLASSES_ROOT\\.esp")
+        );
+        assert_eq!(
+            Some((RegistryRoot::Users, ".DEFAULT")),
+            split_key("HKU\\.DEFAULT")
+        );
+    }
+
+    #[test]
+    fn split_key_should_reject_unknown_roots_and_missing_subkeys() {
+        assert_eq!(None, split_key("HKLM"));
+        assert_eq!(None, split_key("HKLM\\"));
+        assert_eq!(None, split_key("HKXX\\SOFTWARE"));
+        assert_eq!(None, split_key("SOFTWARE\\Bethesda Softworks"));
+    }
+
+    #[cfg(all(feature = "eval", not(all(windows, feature = "windows-registry"))))]
+    #[test]
+    fn read_value_should_read_nothing() {
+        assert_eq!(
+            None,
+            read_value(
+                "HKLM\\SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion",
+                "ProductName"
+            )
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::fmt;
+#[cfg(feature = "eval")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_MINUTE: i64 = 60;
+const SECONDS_PER_HOUR: i64 = 3600;
+const SECONDS_PER_DAY: i64 = 86_400;
+const DAYS_PER_ERA: i64 = 146_097;
+/// The number of days from 0000-03-01 to 1970-01-01.
+const UNIX_EPOCH_DAY: i64 = 719_468;
+
+/// A point in time with a precision of one second, stored as the number of
+/// seconds since the Unix epoch.
+#[cfg_attr(not(feature = "eval"), expect(unreachable_pub))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    /// Parse an ISO-8601 timestamp in the form `YYYY-MM-DD`,
+    /// `YYYY-MM-DDTHH:MM:SS`, or either of those followed by `Z` or a `+HH:MM`
+    /// or `-HH:MM` offset from UTC. A space may be used instead of the `T`.
+    /// Timestamps without an offset are in UTC.
+    pub(crate) fn parse(input: &str) -> Option<Self> {
+        let bytes = input.as_bytes();
+
+        let (date, rest) = bytes.split_at_checked(10)?;
+        let [y1, y2, y3, y4, b'-', m1, m2, b'-', d1, d2] = *date else {
+            return None;
+        };
+        let year = digits(&[y1, y2, y3, y4])?;
+        let month = digits(&[m1, m2])?;
+        let day = digits(&[d1, d2])?;
+
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return None;
+        }
+
+        let (time_of_day, offset) = match rest {
+            [] => (0, rest),
+            [b'T' | b' ', h1, h2, b':', mi1, mi2, b':', s1, s2, offset @ ..] => {
+                let hour = digits(&[*h1, *h2])?;
+                let minute = digits(&[*mi1, *mi2])?;
+                let second = digits(&[*s1, *s2])?;
+
+                if hour > 23 || minute > 59 || second > 59 {
+                    return None;
+                }
+
+                let seconds = hour * SECONDS_PER_HOUR + minute * SECONDS_PER_MINUTE + second;
+                (seconds, offset)
+            }
+            _ => return None,
+        };
+
+        let offset = match *offset {
+            [] | [b'Z'] => 0,
+            [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+                let hours = digits(&[h1, h2])?;
+                let minutes = digits(&[m1, m2])?;
+
+                if hours > 23 || minutes > 59 {
+                    return None;
+                }
+
+                let offset = hours * SECONDS_PER_HOUR + minutes * SECONDS_PER_MINUTE;
+                if sign == b'-' {
+                    -offset
+                } else {
+                    offset
+                }
+            }
+            _ => return None,
+        };
+
+        let days = days_from_civil(year, month, day);
+
+        Some(Timestamp(days * SECONDS_PER_DAY + time_of_day - offset))
+    }
+}
+
+/// System times are rounded down to the start of the second that they fall
+/// within.
+#[cfg(feature = "eval")]
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => Timestamp(i64::try_from(duration.as_secs()).unwrap_or(i64::MAX)),
+            Err(e) => {
+                let duration = e.duration();
+                let seconds = i64::try_from(duration.as_secs()).unwrap_or(i64::MAX);
+                if duration.subsec_nanos() > 0 {
+                    Timestamp(seconds.saturating_add(1).saturating_neg())
+                } else {
+                    Timestamp(seconds.saturating_neg())
+                }
+            }
+        }
+    }
+}
+
+/// Timestamps are written in UTC, in the form `YYYY-MM-DDTHH:MM:SSZ`.
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let days = self.0.div_euclid(SECONDS_PER_DAY);
+        let time_of_day = self.0.rem_euclid(SECONDS_PER_DAY);
+
+        let (year, month, day) = civil_from_days(days);
+        let hour = time_of_day.div_euclid(SECONDS_PER_HOUR);
+        let minute = time_of_day
+            .rem_euclid(SECONDS_PER_HOUR)
+            .div_euclid(SECONDS_PER_MINUTE);
+        let second = time_of_day.rem_euclid(SECONDS_PER_MINUTE);
+
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z"
+        )
+    }
+}
+
+fn digits(bytes: &[u8]) -> Option<i64> {
+    bytes.iter().try_fold(0, |value: i64, byte| {
+        char::from(*byte)
+            .to_digit(10)
+            .map(|digit| value * 10 + i64::from(digit))
+    })
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year.rem_euclid(4) == 0 && (year.rem_euclid(100) != 0 || year.rem_euclid(400) == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Get the number of days since the Unix epoch of the given date in the
+/// proleptic Gregorian calendar, using the algorithm described at
+/// <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (month + 9).rem_euclid(12) + 2).div_euclid(5) + day - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era.div_euclid(4) - year_of_era.div_euclid(100) + day_of_year;
+
+    era * DAYS_PER_ERA + day_of_era - UNIX_EPOCH_DAY
+}
+
+/// The inverse of [days_from_civil], using the algorithm described at
+/// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days.saturating_add(UNIX_EPOCH_DAY);
+    let era = days.div_euclid(DAYS_PER_ERA);
+    let day_of_era = days.rem_euclid(DAYS_PER_ERA);
+    let year_of_era = (day_of_era - day_of_era.div_euclid(1460) + day_of_era.div_euclid(36_524)
+        - day_of_era.div_euclid(DAYS_PER_ERA - 1))
+    .div_euclid(365);
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era.div_euclid(4) - year_of_era.div_euclid(100));
+    let month_index = (5 * day_of_year + 2).div_euclid(153);
+    let day = day_of_year - (153 * month_index + 2).div_euclid(5) + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era.saturating_mul(400).saturating_add(year_of_era);
+
+    if month <= 2 {
+        (year.saturating_add(1), month, day)
+    } else {
+        (year, month, day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_should_read_a_date_as_midnight_utc() {
+        assert_eq!(Some(Timestamp(0)), Timestamp::parse("1970-01-01"));
+        assert_eq!(
+            Some(Timestamp(1_704_067_200)),
+            Timestamp::parse("2024-01-01")
+        );
+    }
+
+    #[test]
+    fn parse_should_read_a_date_and_time() {
+        let expected = Some(Timestamp(1_709_210_096));
+
+        assert_eq!(expected, Timestamp::parse("2024-02-29T12:34:56"));
+        assert_eq!(expected, Timestamp::parse("2024-02-29 12:34:56"));
+        assert_eq!(expected, Timestamp::parse("2024-02-29T12:34:56Z"));
+    }
+
+    #[test]
+    fn parse_should_apply_an_offset_from_utc() {
+        let expected = Timestamp::parse("2024-02-29T12:34:56Z");
+
+        assert_eq!(expected, Timestamp::parse("2024-02-29T14:04:56+01:30"));
+        assert_eq!(expected, Timestamp::parse("2024-02-29T07:34:56-05:00"));
+    }
+
+    #[test]
+    fn parse_should_read_dates_before_the_unix_epoch() {
+        assert_eq!(
+            Some(Timestamp(-SECONDS_PER_DAY)),
+            Timestamp::parse("1969-12-31")
+        );
+    }
+
+    #[test]
+    fn parse_should_reject_invalid_timestamps() {
+        for input in [
+            "",
+            "2024",
+            "2024-1-01",
+            "2024/01/01",
+            "2024-00-01",
+            "2024-13-01",
+            "2024-01-00",
+            "2023-02-29",
+            "2024-04-31",
+            "2024-01-01T",
+            "2024-01-01T24:00:00",
+            "2024-01-01T12:60:00",
+            "2024-01-01T12:00:60",
+            "2024-01-01T12:00",
+            "2024-01-01T12:00:00.5Z",
+            "2024-01-01T12:00:00+0100",
+            "2024-01-01T12:00:00 ",
+            "2024-01-01Z",
+            "+024-01-01",
+        ] {
+            assert_eq!(None, Timestamp::parse(input), "{input}");
+        }
+    }
+
+    #[test]
+    fn display_should_write_the_timestamp_in_utc() {
+        for (input, output) in [
+            ("1970-01-01", "1970-01-01T00:00:00Z"),
+            ("1969-12-31T23:59:59", "1969-12-31T23:59:59Z"),
+            ("2000-02-29T01:02:03+04:00", "2000-02-28T21:02:03Z"),
+            ("9999-12-31T23:59:59Z", "9999-12-31T23:59:59Z"),
+            ("0000-01-01", "0000-01-01T00:00:00Z"),
+        ] {
+            let timestamp = Timestamp::parse(input).unwrap();
+
+            assert_eq!(output, timestamp.to_string());
+            assert_eq!(Some(timestamp), Timestamp::parse(output));
+        }
+    }
+
+    #[cfg(feature = "eval")]
+    #[test]
+    fn from_system_time_should_round_down_to_the_second() {
+        use std::time::Duration;
+
+        let after = UNIX_EPOCH + Duration::from_millis(1_500);
+        let before = UNIX_EPOCH - Duration::from_millis(1_500);
+
+        assert_eq!(Timestamp(1), Timestamp::from(after));
+        assert_eq!(Timestamp(-2), Timestamp::from(before));
+        assert_eq!(
+            Timestamp(-1),
+            Timestamp::from(UNIX_EPOCH - Duration::from_secs(1))
+        );
+    }
+}
@@ -1,10 +1,18 @@
 use std::cmp::Ordering;
+#[cfg(feature = "eval")]
 use std::path::Path;
 
+#[cfg(feature = "eval")]
 use pelite::resources::version_info::VersionInfo;
+#[cfg(feature = "eval")]
 use pelite::resources::{FindError, Resources};
 
+#[cfg(feature = "eval")]
 use crate::error::Error;
+#[cfg(feature = "eval")]
+use crate::retry::with_retries;
+#[cfg(feature = "eval")]
+use crate::State;
 
 #[derive(Clone, Debug)]
 enum ReleaseId {
@@ -24,7 +32,7 @@ impl<'a> From<&'a str> for ReleaseId {
 fn are_numeric_values_equal(n: u32, s: &str) -> bool {
     // The values can only be equal if the trimmed string can be wholly
     // converted to the same u32 value.
-    match s.trim().parse() {
+    match s.trim().parse::<u32>() {
         Ok(n2) => n == n2,
         Err(_) => false,
     }
@@ -102,15 +110,19 @@ impl<'a> From<&'a str> for PreReleaseId {
     }
 }
 
-#[derive(Debug)]
-pub(super) struct Version {
+#[derive(Clone, Debug)]
+pub(crate) struct Version {
     release_ids: Vec<ReleaseId>,
     pre_release_ids: Vec<PreReleaseId>,
 }
 
+#[cfg(feature = "eval")]
 impl Version {
-    pub(super) fn read_file_version(file_path: &Path) -> Result<Option<Self>, Error> {
-        Self::read_version(file_path, |v| {
+    pub(super) fn read_file_version(
+        state: &State,
+        file_path: &Path,
+    ) -> Result<Option<Self>, Error> {
+        Self::read_version(state, file_path, |v| {
             v.fixed().map(|f| {
                 format!(
                     "{}.{}.{}.{}",
@@ -123,39 +135,36 @@ impl Version {
         })
     }
 
-    pub(super) fn read_product_version(file_path: &Path) -> Result<Option<Self>, Error> {
-        Self::read_version(file_path, |v| {
+    pub(super) fn read_product_version(
+        state: &State,
+        file_path: &Path,
+    ) -> Result<Option<Self>, Error> {
+        Self::read_version(state, file_path, |v| {
             v.translation()
                 .first()
                 .and_then(|language| v.value(*language, "ProductVersion"))
         })
     }
 
-    pub(super) fn is_readable(file_path: &Path) -> bool {
-        Self::read_version(file_path, |_| None).is_ok()
+    /// A file is readable if its version fields can be read, but failing to
+    /// read it because of a transient error that outlasted the retry policy
+    /// is still an error.
+    pub(super) fn is_readable(state: &State, file_path: &Path) -> Result<bool, Error> {
+        match Self::read_version(state, file_path, |_| None) {
+            Err(e @ Error::RetriesExhausted(..)) => Err(e),
+            result => Ok(result.is_ok()),
+        }
     }
 
     fn read_version<F: Fn(VersionInfo) -> Option<String>>(
+        state: &State,
         file_path: &Path,
         formatter: F,
     ) -> Result<Option<Self>, Error> {
-        #[cfg(any(windows, unix))]
-        let result = {
-            let file_map = pelite::FileMap::open(file_path)
-                .map_err(|e| Error::IoError(file_path.to_path_buf(), e))?;
-
-            get_pe_version_info(file_map.as_ref()).map(formatter)
-        };
+        let bytes = with_retries(state, file_path, || state.file_system.read(file_path))?
+            .map_err(|e| Error::IoError(file_path.to_path_buf(), e))?;
 
-        #[cfg(not(any(windows, unix)))]
-        let result = {
-            let bytes =
-                std::fs::read(file_path).map_err(|e| Error::IoError(file_path.to_path_buf(), e))?;
-
-            get_pe_version_info(&bytes).map(formatter)
-        };
-
-        match result {
+        match get_pe_version_info((*bytes).as_ref()).map(formatter) {
             Ok(s) => Ok(s.map(Version::from)),
             Err(FindError::NotFound) => Ok(None),
             Err(e) => Err(Error::PeParsingError(file_path.to_path_buf(), Box::new(e))),
@@ -163,10 +172,12 @@ impl Version {
     }
 }
 
+#[cfg(feature = "eval")]
 fn get_pe_version_info(bytes: &[u8]) -> Result<VersionInfo, FindError> {
     get_pe_resources(bytes)?.version_info()
 }
 
+#[cfg(feature = "eval")]
 fn get_pe_resources(bytes: &[u8]) -> Result<Resources, pelite::Error> {
     use pelite::pe64;
     match pe64::PeFile::from_bytes(bytes) {
@@ -192,13 +203,30 @@ fn is_pre_release_separator(c: char) -> bool {
     c == '.' || is_separator(c)
 }
 
+/// Check if the string contains four runs of digits separated by `", "`.
+fn contains_comma_separated_version(string: &str) -> bool {
+    let parts: Vec<&str> = string.split(", ").collect();
+
+    parts.windows(4).any(|window| match window {
+        [first, second, third, fourth] => {
+            first.ends_with(char::is_numeric)
+                && is_all_numeric(second)
+                && is_all_numeric(third)
+                && fourth.starts_with(char::is_numeric)
+        }
+        _ => false,
+    })
+}
+
+fn is_all_numeric(string: &str) -> bool {
+    !string.is_empty() && string.chars().all(char::is_numeric)
+}
+
 fn split_version_string(string: &str) -> (&str, &str) {
     // Special case for strings of the form "0, 1, 2, 3", which are used in
     // OBSE and SKSE, and which should be interpreted as "0.1.2.3".
-    if let Ok(regex) = regex::Regex::new("\\d+, \\d+, \\d+, \\d+") {
-        if regex.is_match(string) {
-            return (string, "");
-        }
+    if contains_comma_separated_version(string) {
+        return (string, "");
     }
 
     string.split_once(is_separator).unwrap_or((string, ""))
@@ -258,6 +286,12 @@ impl PartialEq for Version {
     }
 }
 
+/// Compare two version strings in the same way as the functions in conditions
+/// that compare versions. Returns `None` if the versions can't be ordered.
+pub fn compare_versions(lhs: &str, rhs: &str) -> Option<Ordering> {
+    Version::from(lhs).partial_cmp(&Version::from(rhs))
+}
+
 fn pad_release_ids(ids1: &[ReleaseId], ids2: &[ReleaseId]) -> (Vec<ReleaseId>, Vec<ReleaseId>) {
     let mut ids1 = ids1.to_vec();
     let mut ids2 = ids2.to_vec();
@@ -390,14 +424,23 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "eval")]
     mod constructors {
+        use std::path::PathBuf;
+
         use super::super::*;
+        use crate::GameType;
+
+        fn state() -> State {
+            State::new(GameType::Oblivion, PathBuf::from("."))
+        }
 
         #[test]
         fn version_read_file_version_should_read_the_file_version_field_of_a_32_bit_executable() {
-            let version = Version::read_file_version(Path::new("tests/libloot_win32/loot.dll"))
-                .unwrap()
-                .unwrap();
+            let version =
+                Version::read_file_version(&state(), Path::new("tests/libloot_win32/loot.dll"))
+                    .unwrap()
+                    .unwrap();
 
             assert_eq!(
                 version.release_ids,
@@ -413,9 +456,10 @@ mod tests {
 
         #[test]
         fn version_read_file_version_should_read_the_file_version_field_of_a_64_bit_executable() {
-            let version = Version::read_file_version(Path::new("tests/libloot_win64/loot.dll"))
-                .unwrap()
-                .unwrap();
+            let version =
+                Version::read_file_version(&state(), Path::new("tests/libloot_win64/loot.dll"))
+                    .unwrap()
+                    .unwrap();
 
             assert_eq!(
                 version.release_ids,
@@ -431,7 +475,7 @@ mod tests {
 
         #[test]
         fn version_read_file_version_should_error_with_path_if_path_does_not_exist() {
-            let error = Version::read_file_version(Path::new("missing")).unwrap_err();
+            let error = Version::read_file_version(&state(), Path::new("missing")).unwrap_err();
 
             assert!(error
                 .to_string()
@@ -440,16 +484,18 @@ mod tests {
 
         #[test]
         fn version_read_file_version_should_error_with_path_if_the_file_is_not_an_executable() {
-            let error = Version::read_file_version(Path::new("Cargo.toml")).unwrap_err();
+            let error = Version::read_file_version(&state(), Path::new("Cargo.toml")).unwrap_err();
 
             assert_eq!("An error was encountered while reading the version fields of \"Cargo.toml\": unknown magic number", error.to_string());
         }
 
         #[test]
         fn version_read_file_version_should_return_none_if_there_is_no_version_info() {
-            let version =
-                Version::read_file_version(Path::new("tests/loot_api_python/loot_api.pyd"))
-                    .unwrap();
+            let version = Version::read_file_version(
+                &state(),
+                Path::new("tests/loot_api_python/loot_api.pyd"),
+            )
+            .unwrap();
 
             assert!(version.is_none());
         }
@@ -457,9 +503,10 @@ mod tests {
         #[test]
         fn version_read_product_version_should_read_the_file_version_field_of_a_32_bit_executable()
         {
-            let version = Version::read_product_version(Path::new("tests/libloot_win32/loot.dll"))
-                .unwrap()
-                .unwrap();
+            let version =
+                Version::read_product_version(&state(), Path::new("tests/libloot_win32/loot.dll"))
+                    .unwrap()
+                    .unwrap();
 
             assert_eq!(
                 version.release_ids,
@@ -475,9 +522,10 @@ mod tests {
         #[test]
         fn version_read_product_version_should_read_the_file_version_field_of_a_64_bit_executable()
         {
-            let version = Version::read_product_version(Path::new("tests/libloot_win64/loot.dll"))
-                .unwrap()
-                .unwrap();
+            let version =
+                Version::read_product_version(&state(), Path::new("tests/libloot_win64/loot.dll"))
+                    .unwrap()
+                    .unwrap();
 
             assert_eq!(
                 version.release_ids,
@@ -503,7 +551,9 @@ mod tests {
 
             std::fs::write(&dll_path, dll_bytes).unwrap();
 
-            let version = Version::read_product_version(&dll_path).unwrap().unwrap();
+            let version = Version::read_product_version(&state(), &dll_path)
+                .unwrap()
+                .unwrap();
 
             assert_eq!(
                 version.release_ids,
@@ -518,7 +568,7 @@ mod tests {
 
         #[test]
         fn version_read_product_version_should_error_with_path_if_path_does_not_exist() {
-            let error = Version::read_product_version(Path::new("missing")).unwrap_err();
+            let error = Version::read_product_version(&state(), Path::new("missing")).unwrap_err();
 
             assert!(error
                 .to_string()
@@ -527,16 +577,19 @@ mod tests {
 
         #[test]
         fn version_read_product_version_should_error_with_path_if_the_file_is_not_an_executable() {
-            let error = Version::read_product_version(Path::new("Cargo.toml")).unwrap_err();
+            let error =
+                Version::read_product_version(&state(), Path::new("Cargo.toml")).unwrap_err();
 
             assert_eq!("An error was encountered while reading the version fields of \"Cargo.toml\": unknown magic number", error.to_string());
         }
 
         #[test]
         fn version_read_product_version_should_return_none_if_there_is_no_version_info() {
-            let version =
-                Version::read_product_version(Path::new("tests/loot_api_python/loot_api.pyd"))
-                    .unwrap();
+            let version = Version::read_product_version(
+                &state(),
+                Path::new("tests/loot_api_python/loot_api.pyd"),
+            )
+            .unwrap();
 
             assert!(version.is_none());
         }
@@ -1075,4 +1128,22 @@ mod tests {
             );
         }
     }
+
+    mod comparison {
+        use super::super::*;
+
+        #[test]
+        fn compare_versions_should_compare_version_strings() {
+            assert_eq!(Some(Ordering::Less), compare_versions("1.2", "1.10"));
+            assert_eq!(Some(Ordering::Equal), compare_versions("1.0", "1.0.0"));
+            assert_eq!(
+                Some(Ordering::Greater),
+                compare_versions("1.0.0", "1.0.0-alpha")
+            );
+            assert_eq!(
+                Some(Ordering::Equal),
+                compare_versions("0, 1, 2, 3", "0.1.2.3")
+            );
+        }
+    }
 }
@@ -0,0 +1,168 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use super::path::{fold_case, split_path};
+
+/// Files that the host says will exist at the data path when the game runs,
+/// but which may not be on disk, e.g. because a mod manager will overlay them.
+/// Paths are relative to the data path, and are compared case-insensitively
+/// with forward and backward slashes treated as equivalent.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct VirtualFiles {
+    /// Case-folded directory paths mapped to the files in them, as
+    /// case-folded filenames mapped to the paths that were given, with `/` as
+    /// their separator.
+    directories: BTreeMap<String, HashMap<String, PathBuf>>,
+}
+
+impl VirtualFiles {
+    pub(crate) fn new(paths: Vec<PathBuf>) -> Self {
+        let mut directories: BTreeMap<String, HashMap<String, PathBuf>> = BTreeMap::new();
+
+        for path in paths {
+            let Some(path) = path.to_str() else {
+                continue;
+            };
+            let path = split_path(path).collect::<Vec<_>>().join("/");
+
+            if let Some((directory, file_name)) = split_key(Path::new(&path)) {
+                directories
+                    .entry(directory)
+                    .or_default()
+                    .insert(file_name, PathBuf::from(path));
+            }
+        }
+
+        VirtualFiles { directories }
+    }
+
+    pub(crate) fn contains(&self, path: &Path) -> bool {
+        split_key(path).is_some_and(|(directory, file_name)| {
+            self.directories
+                .get(&directory)
+                .is_some_and(|files| files.contains_key(&file_name))
+        })
+    }
+
+    /// Get the paths of the files in the given directory and its
+    /// subdirectories, down to the given max depth, where a depth of zero
+    /// only includes files directly in the given directory.
+    pub(crate) fn files_in(
+        &self,
+        directory: &Path,
+        max_depth: u32,
+    ) -> impl Iterator<Item = &Path> + use<'_> {
+        let prefix = directory_key(directory);
+        let start = prefix.clone().unwrap_or_default();
+
+        // Subdirectories' keys start with their parent's key, so they sort
+        // after it and before any directory that doesn't start with it.
+        self.directories
+            .range(start.clone()..)
+            .take_while(move |(d, _)| d.starts_with(start.as_str()))
+            .filter(move |(d, _)| {
+                depth_below(prefix.as_deref(), d).is_some_and(|depth| depth <= max_depth)
+            })
+            .flat_map(|(_, files)| files.values())
+            .map(PathBuf::as_path)
+    }
+}
+
+/// Get the depth of the given directory below the given parent, if it is the
+/// parent or one of its subdirectories.
+fn depth_below(parent: Option<&str>, directory: &str) -> Option<u32> {
+    let relative = match parent {
+        None => directory,
+        Some(parent) if directory == parent => return Some(0),
+        Some(parent) => directory.strip_prefix(parent)?.strip_prefix('/')?,
+    };
+
+    if relative.is_empty() {
+        return Some(0);
+    }
+
+    let separators = relative.matches('/').count().saturating_add(1);
+    Some(u32::try_from(separators).unwrap_or(u32::MAX))
+}
+
+/// Get the case-folded directory and filename of the given path, using `/` as
+/// the separator.
+fn split_key(path: &Path) -> Option<(String, String)> {
+    let path = path.to_str()?;
+    let mut components: Vec<_> = split_path(path).collect();
+    let file_name = fold_case(components.pop()?);
+
+    Some((fold_case(&components.join("/")), file_name))
+}
+
+/// Get the case-folded key of the given directory, or `None` if it's the data
+/// path itself.
+fn directory_key(directory: &Path) -> Option<String> {
+    let directory = directory.to_string_lossy();
+    let key = fold_case(&split_path(&directory).collect::<Vec<_>>().join("/"));
+
+    if key.is_empty() {
+        None
+    } else {
+        Some(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn virtual_files() -> VirtualFiles {
+        VirtualFiles::new(vec![
+            PathBuf::from("Blank.esp"),
+            PathBuf::from("textures\\a.dds"),
+            PathBuf::from("Textures/Sub/b.dds"),
+            PathBuf::from("textures/sub/deeper/c.dds"),
+        ])
+    }
+
+    fn files_in(directory: &str, max_depth: u32) -> Vec<PathBuf> {
+        let virtual_files = virtual_files();
+        let mut files: Vec<_> = virtual_files
+            .files_in(Path::new(directory), max_depth)
+            .map(Path::to_path_buf)
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn contains_should_ignore_case_and_separator_differences() {
+        let virtual_files = virtual_files();
+
+        assert!(virtual_files.contains(Path::new("blank.ESP")));
+        assert!(virtual_files.contains(Path::new("./textures/A.dds")));
+        assert!(virtual_files.contains(Path::new("textures\\sub\\B.dds")));
+        assert!(!virtual_files.contains(Path::new("textures")));
+        assert!(!virtual_files.contains(Path::new("a.dds")));
+    }
+
+    #[test]
+    fn files_in_should_only_include_files_down_to_the_max_depth() {
+        assert_eq!(vec![PathBuf::from("Blank.esp")], files_in("", 0));
+        assert_eq!(
+            vec![PathBuf::from("textures/a.dds")],
+            files_in("Textures", 0)
+        );
+        assert_eq!(
+            vec![
+                PathBuf::from("Textures/Sub/b.dds"),
+                PathBuf::from("textures/a.dds"),
+            ],
+            files_in("textures", 1)
+        );
+        assert_eq!(3, files_in(".", 2).len());
+    }
+
+    #[test]
+    fn files_in_should_not_include_directories_that_only_share_a_prefix() {
+        let virtual_files = VirtualFiles::new(vec![PathBuf::from("textures2/a.dds")]);
+
+        assert_eq!(0, virtual_files.files_in(Path::new("textures"), 1).count());
+    }
+}
@@ -0,0 +1,210 @@
+use std::fmt;
+
+use crate::error::ParsingErrorKind;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    AnyChar,
+    AnyString,
+}
+
+/// A case-insensitive pattern that supports the subset of regex syntax that
+/// can be matched without a regex engine: literal characters, punctuation
+/// escaped with a backslash, `.` to match any character, `.*` to match any
+/// sequence of characters, and the `^` and `$` anchors at the start and end of
+/// the pattern. Any other regex syntax is rejected, so a pattern that can be
+/// built matches the same strings as the equivalent case-insensitive regex.
+///
+/// This is used in place of a regex when the crate is built without its
+/// `regex` feature.
+#[cfg_attr(any(feature = "regex", not(feature = "eval")), expect(unreachable_pub))]
+#[derive(Clone, Debug)]
+pub struct WildcardPattern {
+    pattern: String,
+    tokens: Vec<Token>,
+}
+
+impl WildcardPattern {
+    pub(crate) fn new(pattern: &str) -> Result<Self, ParsingErrorKind> {
+        let (anchored_start, body) = match pattern.strip_prefix('^') {
+            Some(body) => (true, body),
+            None => (false, pattern),
+        };
+        let (anchored_end, body) = match body.strip_suffix('$') {
+            Some(body) if !body.ends_with('\\') => (true, body),
+            _ => (false, body),
+        };
+
+        let mut tokens = Vec::new();
+        if !anchored_start {
+            tokens.push(Token::AnyString);
+        }
+
+        let mut chars = body.chars().peekable();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '\\' => match chars.next() {
+                    Some(c) if c.is_ascii_punctuation() => Token::Literal(c),
+                    Some(c) => return Err(unsupported(&format!("\\{c}"))),
+                    None => return Err(unsupported("\\")),
+                },
+                '.' if chars.next_if_eq(&'*').is_some() => Token::AnyString,
+                '.' => Token::AnyChar,
+                '^' | '$' | '*' | '+' | '?' | '|' | '(' | ')' | '[' | ']' | '{' | '}' => {
+                    return Err(unsupported(&c.to_string()));
+                }
+                c => Token::Literal(c),
+            };
+            tokens.push(token);
+        }
+
+        if !anchored_end {
+            tokens.push(Token::AnyString);
+        }
+
+        Ok(WildcardPattern {
+            pattern: pattern.to_owned(),
+            tokens,
+        })
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Patterns can't contain groups, so only the implicit group for the
+    /// whole match is counted.
+    #[expect(clippy::unused_self)]
+    pub(crate) fn captures_len(&self) -> usize {
+        1
+    }
+
+    pub(crate) fn is_match(&self, haystack: &str) -> bool {
+        let haystack: Vec<char> = haystack.chars().collect();
+
+        // Match greedily, and when a mismatch is found, backtrack to let the
+        // most recent AnyString consume one more character.
+        let mut token_index: usize = 0;
+        let mut char_index: usize = 0;
+        let mut backtrack: Option<(usize, usize)> = None;
+
+        loop {
+            let token = self.tokens.get(token_index);
+            let current_char = haystack.get(char_index);
+
+            match (token, current_char) {
+                (None, None) => return true,
+                (Some(Token::AnyString), _) => {
+                    token_index = token_index.saturating_add(1);
+                    backtrack = Some((token_index, char_index));
+                    continue;
+                }
+                (Some(Token::AnyChar), Some(_)) => {
+                    token_index = token_index.saturating_add(1);
+                    char_index = char_index.saturating_add(1);
+                    continue;
+                }
+                (Some(Token::Literal(expected)), Some(actual))
+                    if eq_ignoring_case(*expected, *actual) =>
+                {
+                    token_index = token_index.saturating_add(1);
+                    char_index = char_index.saturating_add(1);
+                    continue;
+                }
+                _ => {}
+            }
+
+            match backtrack {
+                Some((resume_token_index, resume_char_index))
+                    if resume_char_index < haystack.len() =>
+                {
+                    token_index = resume_token_index;
+                    char_index = resume_char_index.saturating_add(1);
+                    backtrack = Some((resume_token_index, char_index));
+                }
+                _ => return false,
+            }
+        }
+    }
+}
+
+impl fmt::Display for WildcardPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+fn eq_ignoring_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+fn unsupported(syntax: &str) -> ParsingErrorKind {
+    ParsingErrorKind::InvalidRegexSyntax(format!(
+        "\"{syntax}\" is not supported, only literal characters, escaped punctuation, . and .* can be used when regex support is disabled"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(string: &str) -> WildcardPattern {
+        WildcardPattern::new(string).unwrap()
+    }
+
+    #[test]
+    fn is_match_should_match_literals_case_insensitively() {
+        let pattern = pattern("^Blank\\.esp$");
+
+        assert!(pattern.is_match("blank.ESP"));
+        assert!(!pattern.is_match("blankxesp"));
+        assert!(!pattern.is_match("Blank.esp.ghost"));
+        assert!(!pattern.is_match("a Blank.esp"));
+    }
+
+    #[test]
+    fn is_match_should_match_any_character_and_any_sequence() {
+        let pattern = pattern("^Blank.*\\.es.$");
+
+        assert!(pattern.is_match("Blank.esm"));
+        assert!(pattern.is_match("Blank - Different.esp"));
+        assert!(pattern.is_match("Blank.esp.esp"));
+        assert!(!pattern.is_match("Blank.es"));
+        assert!(!pattern.is_match("Other.esp"));
+    }
+
+    #[test]
+    fn is_match_should_match_anywhere_if_the_pattern_is_not_anchored() {
+        let pattern = pattern("nk\\.e");
+
+        assert!(pattern.is_match("Blank.esp"));
+        assert!(!pattern.is_match("Blank_esp"));
+        assert!(self::pattern("").is_match("Blank.esp"));
+    }
+
+    #[test]
+    fn new_should_reject_other_regex_syntax() {
+        for string in [
+            "a+", "a?", "a*", "a|b", "(a)", "[ab]", "a{2}", "\\d", "a^", "$a", "a\\",
+        ] {
+            assert!(
+                matches!(
+                    WildcardPattern::new(string),
+                    Err(ParsingErrorKind::InvalidRegexSyntax(_))
+                ),
+                "{string}"
+            );
+        }
+    }
+
+    #[test]
+    fn as_str_should_return_the_pattern() {
+        assert_eq!("^a.*$", pattern("^a.*$").as_str());
+    }
+
+    #[test]
+    fn captures_len_should_only_count_the_whole_match() {
+        assert_eq!(1, pattern("^a.*$").captures_len());
+    }
+}
@@ -0,0 +1,37 @@
+/// Check if the process is running under Wine, including Proton. Wine's
+/// ntdll exports functions that Windows' doesn't, so the check looks for one
+/// of them. The result can't change while the process is running, so it's
+/// only checked once.
+#[cfg(windows)]
+#[expect(unsafe_code)]
+pub(super) fn is_wine() -> bool {
+    use std::sync::OnceLock;
+
+    use windows_sys::Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress};
+
+    static IS_WINE: OnceLock<bool> = OnceLock::new();
+
+    *IS_WINE.get_or_init(|| {
+        let module_name: Vec<u16> = "ntdll.dll"
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+
+        // SAFETY: The module name is a null-terminated UTF-16 string that
+        // outlives the call.
+        let module = unsafe { GetModuleHandleW(module_name.as_ptr()) };
+        if module.is_null() {
+            return false;
+        }
+
+        // SAFETY: The module handle is for ntdll, which is never unloaded,
+        // and the function name is a null-terminated string.
+        unsafe { GetProcAddress(module, c"wine_get_version".as_ptr().cast()) }.is_some()
+    })
+}
+
+/// Wine only runs Windows executables, so nothing else runs under it.
+#[cfg(not(windows))]
+pub(super) fn is_wine() -> bool {
+    false
+}
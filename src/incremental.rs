@@ -0,0 +1,396 @@
+use std::ops::Range;
+
+use nom::bytes::complete::tag;
+use nom::character::complete::multispace0;
+use nom::multi::separated_list0;
+use nom::sequence::preceded;
+use nom::Parser;
+
+use crate::{
+    blank_comments, check_expression_length, is_multispace, map_err, whitespace, CompoundCondition,
+    Error, Expression, ParseOptions,
+};
+
+/// A condition string and its parsed expression, which can be updated as the
+/// string is edited without reparsing all of it.
+///
+/// When a string is edited, only the compound conditions (i.e. the top-level
+/// operands of `or`) that the edit touches are reparsed, and the rest of the
+/// previously parsed expression is reused. If the edited text can't be
+/// reparsed in isolation, the whole string is reparsed, so the result is
+/// always the same as parsing the edited string from scratch.
+#[derive(Clone, Debug)]
+pub struct IncrementalExpression {
+    input: String,
+    options: ParseOptions,
+    /// The parsed expression and the span in the input of each of its
+    /// compound conditions, or None if the input is not a valid condition.
+    parsed: Option<(Expression, Vec<Range<usize>>)>,
+}
+
+impl IncrementalExpression {
+    /// Create an incremental expression with an empty condition string, which
+    /// will be parsed using the given options.
+    pub fn new(options: ParseOptions) -> Self {
+        IncrementalExpression {
+            input: String::new(),
+            options,
+            parsed: Some((Expression::default(), Vec::new())),
+        }
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Get the parsed expression, or `None` if the current input is not a
+    /// valid condition.
+    pub fn expression(&self) -> Option<&Expression> {
+        self.parsed.as_ref().map(|(expression, _)| expression)
+    }
+
+    /// Replace the whole condition string and parse it.
+    pub fn set_input(&mut self, input: String) -> Result<&Expression, Error> {
+        self.input = input;
+        self.reparse_all()
+    }
+
+    /// Replace the given byte range of the condition string with the given
+    /// replacement text, reparsing as little of the string as possible.
+    ///
+    /// If the range is out of bounds or does not lie on character boundaries,
+    /// an error is returned and the input is left unchanged. Otherwise the
+    /// edit is applied even if the result is not a valid condition.
+    pub fn apply_edit(
+        &mut self,
+        range: Range<usize>,
+        replacement: &str,
+    ) -> Result<&Expression, Error> {
+        let (Some(before), Some(after)) =
+            (self.input.get(..range.start), self.input.get(range.end..))
+        else {
+            return Err(Error::InvalidEdit(range));
+        };
+        if range.start > range.end {
+            return Err(Error::InvalidEdit(range));
+        }
+
+        // A comment runs to the end of its line, so adding or removing one
+        // can change how text outside the edited compound conditions is read.
+        let affects_comments = self.input.contains('#') || replacement.contains('#');
+
+        self.input = [before, replacement, after].concat();
+
+        if affects_comments {
+            return self.reparse_all();
+        }
+
+        // Reparsing only part of the input would skip the length check.
+        if self.options.check_expression_length(&self.input).is_err() {
+            return self.reparse_all();
+        }
+
+        let Some((mut expression, spans)) = self.parsed.take() else {
+            return self.reparse_all();
+        };
+
+        // Get the compound conditions that contain or neighbour the start
+        // and end of the edit.
+        let first = spans.iter().rposition(|span| span.start <= range.start);
+        let last = spans.iter().position(|span| span.end >= range.end);
+        let (Some(first), Some(last)) = (first, last) else {
+            return self.reparse_all();
+        };
+        let (Some(first_span), Some(last_span)) = (spans.get(first), spans.get(last)) else {
+            return self.reparse_all();
+        };
+
+        // Offsets after the edit move by the change in length.
+        let shift = |offset: usize| {
+            offset
+                .saturating_sub(range.end)
+                .saturating_add(range.start)
+                .saturating_add(replacement.len())
+        };
+
+        let window = first_span.start..shift(last_span.end);
+        let Some(window_input) = self.input.get(window.clone()) else {
+            return self.reparse_all();
+        };
+
+        // Whitespace after an "or" belongs to the separator, so a window that
+        // now starts with whitespace can't be parsed in isolation.
+        if window.start > 0 && window_input.starts_with(char::is_whitespace) {
+            return self.reparse_all();
+        }
+
+        let Ok((mut window_expression, window_spans)) = parse_spanned(window_input, &self.options)
+        else {
+            return self.reparse_all();
+        };
+
+        // If the window is now empty, the text around it needs checking.
+        if window_expression.0.is_empty() {
+            return self.reparse_all();
+        }
+
+        let spliced_spans = spans
+            .get(..first)
+            .unwrap_or_default()
+            .iter()
+            .cloned()
+            .chain(window_spans.into_iter().map(|span| {
+                span.start.saturating_add(window.start)..span.end.saturating_add(window.start)
+            }))
+            .chain(
+                spans
+                    .get(last.saturating_add(1)..)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|span| shift(span.start)..shift(span.end)),
+            )
+            .collect();
+
+        window_expression.map_spans(&|offset| offset.saturating_add(window.start));
+        for compound_condition in expression.0.iter_mut().skip(last.saturating_add(1)) {
+            compound_condition.map_spans(&shift);
+        }
+
+        expression.0.splice(first..=last, window_expression.0);
+
+        Ok(&self.parsed.insert((expression, spliced_spans)).0)
+    }
+
+    fn reparse_all(&mut self) -> Result<&Expression, Error> {
+        self.parsed = None;
+        let parsed = parse_spanned(&self.input, &self.options)?;
+        Ok(&self.parsed.insert(parsed).0)
+    }
+}
+
+impl Default for IncrementalExpression {
+    fn default() -> Self {
+        IncrementalExpression::new(ParseOptions::default())
+    }
+}
+
+/// Parse the given input as an expression, also returning the span of each
+/// of its compound conditions.
+fn parse_spanned(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(Expression, Vec<Range<usize>>), Error> {
+    let input = &*blank_comments(input);
+    check_expression_length(input, options).map_err(nom::Err::Failure)?;
+
+    let offset = |remaining: &str| input.len().saturating_sub(remaining.len());
+
+    let (remaining_input, spanned) = preceded(
+        map_err(multispace0),
+        separated_list0(map_err(whitespace(tag("or"))), |i| {
+            CompoundCondition::parse(i, options).map(|(remaining, condition)| {
+                (remaining, (offset(i)..offset(remaining), condition))
+            })
+        }),
+    )
+    .parse(input)?;
+
+    if !remaining_input.trim_start_matches(is_multispace).is_empty() {
+        return Err(Error::UnconsumedInput(remaining_input.to_owned()));
+    }
+
+    let (spans, compound_conditions) = spanned.into_iter().unzip();
+
+    let mut expression = Expression(compound_conditions);
+    expression.resolve_spans(input);
+
+    Ok((expression, spans))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn incremental(input: &str) -> IncrementalExpression {
+        let mut incremental = IncrementalExpression::default();
+        incremental.set_input(input.to_owned()).unwrap();
+        incremental
+    }
+
+    fn assert_edit_matches_full_parse(input: &str, range: Range<usize>, replacement: &str) {
+        let mut incremental = incremental(input);
+
+        let result = incremental
+            .apply_edit(range, replacement)
+            .cloned()
+            .map_err(|e| e.to_string());
+        let expected = Expression::from_str(incremental.input()).map_err(|e| e.to_string());
+
+        assert_eq!(expected, result, "edited input: {}", incremental.input());
+        assert_eq!(expected.as_ref().ok(), incremental.expression());
+        // Spans are ignored when comparing expressions, but not when debug
+        // formatting them.
+        assert_eq!(
+            format!("{:?}", expected.ok()),
+            format!("{:?}", incremental.expression())
+        );
+
+        if let Some((_, spans)) = &incremental.parsed {
+            let (_, expected_spans) =
+                parse_spanned(incremental.input(), &ParseOptions::default()).unwrap();
+            assert_eq!(&expected_spans, spans);
+        }
+    }
+
+    #[test]
+    fn new_should_have_an_empty_valid_input() {
+        let incremental = IncrementalExpression::new(ParseOptions::default());
+
+        assert_eq!("", incremental.input());
+        assert_eq!(Some(&Expression::default()), incremental.expression());
+    }
+
+    #[test]
+    fn set_input_should_parse_the_whole_input() {
+        let mut incremental = IncrementalExpression::default();
+
+        assert_eq!(
+            Expression::from_str("file(\"a\") or active(\"b\")").unwrap(),
+            *incremental
+                .set_input("file(\"a\") or active(\"b\")".to_owned())
+                .unwrap()
+        );
+
+        assert!(incremental.set_input("foo".to_owned()).is_err());
+        assert!(incremental.expression().is_none());
+    }
+
+    #[test]
+    fn parse_spanned_should_record_the_span_of_each_compound_condition() {
+        let input = "file(\"a\") or  active(\"b\") and file(\"c\")";
+
+        let (_, spans) = parse_spanned(input, &ParseOptions::default()).unwrap();
+
+        assert_eq!(vec![0..9, 14..39], spans);
+    }
+
+    #[test]
+    fn apply_edit_should_reparse_an_edit_within_a_compound_condition() {
+        let input = "file(\"a\") or active(\"b\") or file(\"c\")";
+
+        assert_edit_matches_full_parse(input, 21..22, "Blank.esp");
+        assert_edit_matches_full_parse(input, 13..13, "not ");
+        assert_edit_matches_full_parse(input, 24..24, " and file(\"d\")");
+    }
+
+    #[test]
+    fn apply_edit_should_handle_edits_that_span_separators() {
+        let input = "file(\"a\") or active(\"b\") or file(\"c\")";
+
+        assert_edit_matches_full_parse(input, 9..13, " and ");
+        assert_edit_matches_full_parse(input, 3..35, "e(\"x");
+        assert_edit_matches_full_parse(input, 0..37, "many(\"a\")");
+    }
+
+    #[test]
+    fn apply_edit_should_handle_edits_that_add_separators() {
+        let input = "file(\"a\") or active(\"b\")";
+
+        assert_edit_matches_full_parse(input, 9..9, " or file(\"z\")");
+        assert_edit_matches_full_parse(input, 0..0, "file(\"z\") or ");
+    }
+
+    #[test]
+    fn apply_edit_should_handle_edits_that_remove_compound_conditions() {
+        let input = "file(\"a\") or active(\"b\") or file(\"c\")";
+
+        assert_edit_matches_full_parse(input, 13..28, "");
+        assert_edit_matches_full_parse(input, 9..24, "");
+        assert_edit_matches_full_parse(input, 0..13, "");
+    }
+
+    #[test]
+    fn apply_edit_should_handle_edits_outside_compound_conditions() {
+        assert_edit_matches_full_parse("file(\"a\")", 9..9, " ");
+        assert_edit_matches_full_parse("", 0..0, "file(\"a\")");
+        assert_edit_matches_full_parse(" not file(\"a\")", 0..1, "");
+    }
+
+    #[test]
+    fn apply_edit_should_match_a_full_parse_for_every_single_character_edit() {
+        let input =
+            "file(\"a\") or not active(\"b\") and (file(\"c\") or many(\"d\")) or file(\"e\")";
+
+        for start in 0..input.len() {
+            assert_edit_matches_full_parse(input, start..start + 1, "");
+            assert_edit_matches_full_parse(input, start..start, " ");
+            assert_edit_matches_full_parse(input, start..start, "r");
+            assert_edit_matches_full_parse(input, start..start, ")");
+            assert_edit_matches_full_parse(input, start..start, " or file(\"x\")");
+        }
+    }
+
+    #[test]
+    fn apply_edit_should_handle_edits_that_add_or_remove_comments() {
+        let input = "file(\"a\") # b\nor active(\"#c\")";
+
+        assert_edit_matches_full_parse(input, 10..10, "or file(\"d\")");
+        assert_edit_matches_full_parse(input, 13..14, "");
+        assert_edit_matches_full_parse(input, 0..0, "# x");
+        assert_edit_matches_full_parse("file(\"a\") or active(\"b\")", 9..9, " #");
+    }
+
+    #[test]
+    fn apply_edit_should_recover_after_an_invalid_edit() {
+        let mut incremental = incremental("file(\"a\") or active(\"b\")");
+
+        assert!(incremental.apply_edit(9..9, " an").is_err());
+        assert!(incremental.expression().is_none());
+
+        assert_eq!(
+            Expression::from_str("file(\"a\") and file(\"c\") or active(\"b\")").unwrap(),
+            *incremental.apply_edit(12..12, "d file(\"c\")").unwrap()
+        );
+    }
+
+    #[test]
+    fn apply_edit_should_check_the_length_limit_against_the_whole_input() {
+        let options = ParseOptions::new().with_max_expression_length(Some(30));
+        let mut incremental = IncrementalExpression::new(options);
+        incremental
+            .set_input("file(\"a\") or file(\"b\")".to_owned())
+            .unwrap();
+
+        assert!(matches!(
+            incremental.apply_edit(20..20, "cdefghijk"),
+            Err(Error::ParsingError(
+                _,
+                crate::ParsingErrorKind::ExpressionTooLong(30)
+            ))
+        ));
+        assert!(incremental.expression().is_none());
+    }
+
+    #[test]
+    #[expect(clippy::reversed_empty_ranges)]
+    fn apply_edit_should_error_if_the_range_is_invalid() {
+        let mut incremental = incremental("file(\"\u{e9}\")");
+
+        assert!(matches!(
+            incremental.apply_edit(7..20, ""),
+            Err(Error::InvalidEdit(_))
+        ));
+        assert!(matches!(
+            incremental.apply_edit(8..6, ""),
+            Err(Error::InvalidEdit(_))
+        ));
+        assert!(matches!(
+            incremental.apply_edit(7..7, ""),
+            Err(Error::InvalidEdit(_))
+        ));
+        assert_eq!("file(\"\u{e9}\")", incremental.input());
+        assert!(incremental.expression().is_some());
+    }
+}
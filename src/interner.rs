@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+
+use crate::error::ParsingErrorKind;
+use crate::function::Regex;
+
+/// A regex's pattern and the compiled size limit that it was compiled with,
+/// as the same pattern may fail to compile with a lower limit.
+type RegexKey = (String, Option<usize>);
+
+/// Compiled regexes that can be shared between expressions, so that a regex
+/// that is given in many condition strings is only compiled once and every
+/// function that uses it shares the same compiled program. Clones of an
+/// interner share the same regexes.
+///
+/// Give an interner to
+/// [ParseOptions::with_regex_interner](crate::ParseOptions::with_regex_interner)
+/// to use it when parsing. Regexes are kept until the interner is cleared or
+/// it and all its clones are dropped, including after the expressions that
+/// use them are dropped.
+#[derive(Clone, Debug, Default)]
+pub struct RegexInterner(Arc<Mutex<HashMap<RegexKey, Regex>>>);
+
+impl RegexInterner {
+    pub fn new() -> Self {
+        RegexInterner::default()
+    }
+
+    /// Get an interner that is shared by the whole process.
+    pub fn global() -> &'static RegexInterner {
+        static GLOBAL: OnceLock<RegexInterner> = OnceLock::new();
+        GLOBAL.get_or_init(RegexInterner::new)
+    }
+
+    /// The number of distinct regexes in the interner.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Remove all regexes from the interner. Expressions that have already
+    /// been parsed keep the regexes that they use.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Get the regex for the given pattern and size limit, or use the given
+    /// function to compile it, adding it to the interner if it's valid.
+    pub(crate) fn get_or_compile(
+        &self,
+        pattern: &str,
+        size_limit: Option<usize>,
+        compile: impl FnOnce() -> Result<Regex, ParsingErrorKind>,
+    ) -> Result<Regex, ParsingErrorKind> {
+        let key = (pattern.to_owned(), size_limit);
+        if let Some(regex) = self.lock().get(&key) {
+            return Ok(regex.clone());
+        }
+
+        // Compile without holding the lock, so that other threads aren't
+        // blocked.
+        let regex = compile()?;
+        Ok(self.lock().entry(key).or_insert(regex).clone())
+    }
+
+    /// Nothing panics while the lock is held, so a poisoned interner is still
+    /// valid.
+    fn lock(&self) -> MutexGuard<'_, HashMap<RegexKey, Regex>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regex(pattern: &str) -> Regex {
+        Regex::new(pattern).unwrap()
+    }
+
+    #[test]
+    fn get_or_compile_should_only_compile_each_pattern_and_size_limit_once() {
+        let interner = RegexInterner::new();
+        let clone = interner.clone();
+        let mut compilations = 0_u32;
+
+        for (interner, pattern, size_limit) in [
+            (&interner, "a.*", None),
+            (&clone, "a.*", None),
+            (&interner, "a.*", Some(100)),
+            (&interner, "b.*", None),
+        ] {
+            interner
+                .get_or_compile(pattern, size_limit, || {
+                    compilations += 1;
+                    Ok(regex(pattern))
+                })
+                .unwrap();
+        }
+
+        assert_eq!(3_u32, compilations);
+        assert_eq!(3, interner.len());
+    }
+
+    #[test]
+    fn get_or_compile_should_not_keep_errors() {
+        let interner = RegexInterner::new();
+
+        for _ in 0_u32..2 {
+            let result =
+                interner.get_or_compile("a", None, || Err(ParsingErrorKind::RegexTooLong(0)));
+            assert!(result.is_err());
+        }
+
+        assert!(interner.is_empty());
+    }
+
+    #[test]
+    fn clear_should_remove_all_regexes() {
+        let interner = RegexInterner::new();
+        interner
+            .get_or_compile("a", None, || Ok(regex("a")))
+            .unwrap();
+
+        interner.clone().clear();
+
+        assert!(interner.is_empty());
+    }
+
+    #[test]
+    fn global_should_always_return_the_same_interner() {
+        assert!(Arc::ptr_eq(
+            &RegexInterner::global().0,
+            &RegexInterner::global().0
+        ));
+    }
+}
@@ -104,27 +104,154 @@
         clippy::unwrap_used,
     )
 )]
+mod alias;
+mod ast;
+#[cfg(feature = "eval")]
+mod batch;
+mod bundle;
+#[cfg(feature = "eval")]
+mod cache;
+#[cfg(feature = "eval")]
+mod cancellation;
+#[cfg(feature = "eval")]
+mod checksum;
+mod cost;
+#[cfg(feature = "eval")]
+mod dry_run;
 mod error;
+#[cfg(feature = "eval")]
+mod explain;
+#[cfg(feature = "eval")]
+mod filesystem;
 mod function;
-
-use std::collections::{HashMap, HashSet};
+mod incremental;
+mod interner;
+mod lint;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod options;
+#[cfg(feature = "parallel")]
+mod parallel;
+#[cfg(feature = "eval")]
+mod plugin;
+#[cfg(feature = "eval")]
+mod prefetch;
+#[cfg(feature = "eval")]
+mod registry;
+#[cfg(feature = "eval")]
+mod report;
+#[cfg(feature = "eval")]
+mod retry;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "server")]
+mod server;
+mod simplify;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+#[cfg(feature = "eval")]
+mod stepwise;
+#[cfg(feature = "eval")]
+mod trace;
+#[cfg(feature = "eval")]
+mod truth;
+#[cfg(feature = "eval")]
+mod truth_table;
+mod variables;
+#[cfg(feature = "eval")]
+mod wine;
+
+use std::borrow::Cow;
+#[cfg(feature = "eval")]
+use std::collections::HashMap;
 use std::fmt;
-use std::ops::DerefMut;
-use std::path::PathBuf;
+use std::hash::{Hash, Hasher};
+use std::mem::discriminant;
+use std::ops::Range;
+#[cfg(feature = "eval")]
+use std::path::{Path, PathBuf};
 use std::str;
-use std::sync::{PoisonError, RwLock, RwLockWriteGuard};
+#[cfg(feature = "eval")]
+use std::sync::{Arc, PoisonError, RwLockWriteGuard};
+#[cfg(feature = "eval")]
+use std::time::{Instant, SystemTime};
 
 use nom::branch::alt;
-use nom::bytes::complete::tag;
+use nom::bytes::complete::{tag, take_while1};
 use nom::character::complete::multispace0;
 use nom::combinator::map;
 use nom::multi::separated_list0;
 use nom::sequence::{delimited, preceded};
 use nom::{IResult, Parser};
 
+pub use alias::ConditionAliases;
+pub use ast::{
+    walk_clause, walk_compound_condition, walk_expression, Clause, FunctionCall, ReferencedPath,
+    Visitor,
+};
+#[cfg(feature = "parallel")]
+pub use batch::eval_batch_parallel;
+#[cfg(feature = "eval")]
+pub use batch::{eval_batch, evaluate_all, BatchOutcome};
+pub use bundle::ConditionBundle;
+#[cfg(feature = "eval")]
+use cache::{Cache, CacheChange};
+#[cfg(feature = "eval")]
+pub use cache::{CacheEvent, CacheObserver, CacheStorage, MemoryCacheStorage};
+#[cfg(feature = "eval")]
+pub use cancellation::CancellationToken;
+#[cfg(feature = "eval")]
+pub use checksum::{crc32_from_reader, CachedCrc, Crc32Writer};
+pub use cost::EvaluationCost;
+#[cfg(feature = "eval")]
+pub use dry_run::FileSystemOperation;
 use error::ParsingError;
-pub use error::{Error, MoreDataNeeded, ParsingErrorKind};
+pub use error::{Error, MoreDataNeeded, ParseError, ParsingErrorKind};
+#[cfg(feature = "eval")]
+pub use filesystem::{DirEntry, FileSystem, Metadata, ReadSeek, StdFileSystem};
+#[cfg(feature = "eval")]
+use function::archive::ArchiveIndex;
+use function::parse::starts_with_trailing_backslash;
+#[cfg(feature = "eval")]
+use function::path::{fold_case, is_plugin_path};
+pub use function::version::compare_versions;
+#[cfg(feature = "eval")]
+use function::virtual_files::VirtualFiles;
 use function::Function;
+pub use incremental::IncrementalExpression;
+pub use interner::RegexInterner;
+pub use lint::{ExpressionMetrics, Lint, LintKind};
+#[cfg(feature = "metrics")]
+pub use metrics::Metrics;
+pub use options::{ParseOptions, RegexFeature};
+#[cfg(feature = "eval")]
+pub use plugin::ParsedPlugin;
+#[cfg(feature = "eval")]
+pub use prefetch::prefetch;
+#[cfg(feature = "eval")]
+use registry::SharedCaches;
+#[cfg(feature = "eval")]
+pub use registry::StateRegistry;
+#[cfg(feature = "eval")]
+use report::EvalObserver;
+#[cfg(feature = "eval")]
+pub use report::{EvaluationReport, FunctionEvaluation, ResultSource};
+#[cfg(feature = "eval")]
+pub use retry::RetryPolicy;
+#[cfg(feature = "server")]
+pub use server::Server;
+#[cfg(feature = "snapshot")]
+pub use snapshot::DataSnapshot;
+#[cfg(feature = "eval")]
+pub use stepwise::StepwiseEvaluation;
+#[cfg(feature = "eval")]
+pub use trace::{ClauseTrace, CompoundConditionTrace, ExpressionTrace, FunctionTrace};
+#[cfg(feature = "eval")]
+pub use truth::Truth;
+#[cfg(feature = "eval")]
+pub use truth_table::{Fact, TruthTable};
+#[cfg(feature = "eval")]
+pub use wine::{find_wine_installs, WineInstall, WineLauncher, WinePathMapper};
 
 type ParsingResult<'a, T> = IResult<&'a str, T, ParsingError<&'a str>>;
 
@@ -144,6 +271,7 @@ pub enum GameType {
     OpenMW,
 }
 
+#[cfg(feature = "eval")]
 impl GameType {
     fn supports_light_plugins(self) -> bool {
         matches!(
@@ -161,6 +289,26 @@ impl GameType {
     }
 }
 
+/// The default size in bytes above which files are memory-mapped to calculate
+/// their CRCs.
+#[cfg(feature = "eval")]
+const DEFAULT_MEMORY_MAP_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// The default size in bytes above which memory-mapped files have their CRCs
+/// calculated on multiple threads.
+#[cfg(feature = "parallel")]
+const DEFAULT_PARALLEL_CRC_THRESHOLD: u64 = 512 * 1024 * 1024;
+
+/// The game-specific data that conditions are evaluated against, and the
+/// caches of their results.
+///
+/// A state is [Send] and [Sync], so conditions can be evaluated from many
+/// threads at once using a shared reference to it. Its default caches split
+/// their entries between shards that are locked separately, so threads that
+/// cache results for different functions or files rarely wait for each other,
+/// and reading a cached result that has been published never waits for a
+/// lock. Changing the state's configuration requires a mutable reference.
+#[cfg(feature = "eval")]
 #[derive(Debug)]
 pub struct State {
     game_type: GameType,
@@ -169,26 +317,112 @@ pub struct State {
     /// Other directories that may contain plugins and other game files, used before data_path and
     /// in the order they're listed.
     additional_data_paths: Vec<PathBuf>,
-    /// Lowercased plugin filenames.
-    active_plugins: HashSet<String>,
-    /// Lowercased paths.
-    crc_cache: RwLock<HashMap<String, u32>>,
+    /// Case-folded plugin filenames and the filenames as they were given.
+    active_plugins: HashMap<String, String>,
+    /// Lowercased paths, their CRCs and when they were cached.
+    crc_cache: Cache<String, (u32, SystemTime)>,
+    /// Lowercased paths, their SHA-256 digests and when they were cached.
+    sha256_cache: Cache<String, ([u8; 32], SystemTime)>,
+    /// Lowercased plugin paths, their record counts and when they were cached.
+    record_count_cache: Cache<String, (u32, SystemTime)>,
+    /// Lowercased plugin paths and their parsed headers, or `None` if they
+    /// couldn't be parsed.
+    plugin_cache: Cache<String, Option<Arc<esplugin::Plugin>>>,
+    /// Lowercased archive paths, the paths of the files in them and when they
+    /// were cached.
+    archive_index_cache: Cache<String, (Arc<ArchiveIndex>, SystemTime)>,
     /// Lowercased plugin filenames and their versions as found in description fields.
     plugin_versions: HashMap<String, String>,
-    /// Conditions that have already been evaluated, and their results.
-    condition_cache: RwLock<HashMap<Function, bool>>,
+    /// Conditions that have already been evaluated, their results and when
+    /// they were evaluated.
+    condition_cache: Cache<Function, (bool, SystemTime)>,
+    /// Used to translate Windows-style absolute paths when running under Wine.
+    wine_path_mapper: Option<WinePathMapper>,
+    /// Paths given in conditions and the paths they were resolved to.
+    resolved_path_cache: Cache<PathBuf, PathBuf>,
+    /// The entries in directories that functions have read, by the
+    /// directories' paths.
+    directory_cache: Cache<PathBuf, Arc<[DirEntry]>>,
+    /// Resolved paths and their metadata, or `None` if there was nothing at
+    /// the path.
+    metadata_cache: Cache<PathBuf, Option<Metadata>>,
+    /// Paths given in conditions that could not be found, and when that was.
+    missing_path_cache: Cache<PathBuf, SystemTime>,
+    /// If set, condition paths that could refer to something outside of the
+    /// data paths are only allowed if they start with one of these paths.
+    path_traversal_allowlist: Option<Vec<PathBuf>>,
+    /// The maximum number of directory entries that a regex-based function
+    /// may examine.
+    max_directory_entries: Option<usize>,
+    /// The size in bytes above which files are memory-mapped to calculate
+    /// their CRCs, if the filesystem supports it.
+    memory_map_threshold: Option<u64>,
+    /// The size in bytes above which memory-mapped files have their CRCs
+    /// calculated on multiple threads.
+    #[cfg(feature = "parallel")]
+    parallel_crc_threshold: Option<u64>,
+    /// Whether work whose order depends on the filesystem or on thread
+    /// scheduling should be done in a fixed order instead.
+    deterministic_ordering: bool,
+    /// Whether functions may read from the filesystem.
+    filesystem_access: bool,
+    /// Lowercased plugin filenames and the host's parsed plugin objects.
+    parsed_plugins: HashMap<String, Arc<dyn ParsedPlugin>>,
+    /// Caches of data read from files that can be shared with other states.
+    shared_caches: Arc<SharedCaches>,
+    /// Values of the variables that conditions reference as `${NAME}`.
+    variables: HashMap<String, String>,
+    /// How to retry filesystem operations that fail with transient errors.
+    retry_policy: Option<RetryPolicy>,
+    /// The filesystem that functions read from.
+    file_system: Arc<dyn FileSystem>,
+    /// Files that will exist at the data path when the game runs.
+    virtual_files: VirtualFiles,
+    /// Whether unexpected IO errors are returned instead of being treated
+    /// like paths being missing or unreadable.
+    strict: bool,
+    /// A token that can be used to cancel evaluation from another thread.
+    cancellation_token: Option<CancellationToken>,
+    /// When evaluation should give up.
+    deadline: Option<Instant>,
 }
 
+#[cfg(feature = "eval")]
 impl State {
     pub fn new(game_type: GameType, data_path: PathBuf) -> Self {
         State {
             game_type,
             data_path,
             additional_data_paths: Vec::default(),
-            active_plugins: HashSet::default(),
-            crc_cache: RwLock::default(),
+            active_plugins: HashMap::default(),
+            crc_cache: Cache::default(),
+            sha256_cache: Cache::default(),
+            record_count_cache: Cache::default(),
+            plugin_cache: Cache::default(),
+            archive_index_cache: Cache::default(),
             plugin_versions: HashMap::default(),
-            condition_cache: RwLock::default(),
+            condition_cache: Cache::default(),
+            wine_path_mapper: None,
+            resolved_path_cache: Cache::default(),
+            directory_cache: Cache::default(),
+            metadata_cache: Cache::default(),
+            missing_path_cache: Cache::default(),
+            path_traversal_allowlist: None,
+            max_directory_entries: None,
+            memory_map_threshold: Some(DEFAULT_MEMORY_MAP_THRESHOLD),
+            #[cfg(feature = "parallel")]
+            parallel_crc_threshold: Some(DEFAULT_PARALLEL_CRC_THRESHOLD),
+            deterministic_ordering: false,
+            filesystem_access: true,
+            parsed_plugins: HashMap::default(),
+            shared_caches: Arc::default(),
+            variables: HashMap::default(),
+            retry_policy: None,
+            file_system: Arc::new(StdFileSystem),
+            virtual_files: VirtualFiles::default(),
+            strict: false,
+            cancellation_token: None,
+            deadline: None,
         }
     }
 
@@ -207,11 +441,15 @@ impl State {
         self
     }
 
+    /// Sets the active plugins, replacing any previously set active plugins.
+    /// The cached results of only those conditions that reference a plugin
+    /// that was active before but isn't now, or vice versa, are invalidated.
     pub fn set_active_plugins<T: AsRef<str>>(&mut self, active_plugins: &[T]) {
-        self.active_plugins = active_plugins
-            .iter()
-            .map(|s| s.as_ref().to_lowercase())
-            .collect();
+        let active_plugins = case_folded_map(active_plugins);
+
+        self.invalidate_plugins(&changed_plugins(&self.active_plugins, &active_plugins));
+
+        self.active_plugins = active_plugins;
     }
 
     pub fn set_plugin_versions<T: AsRef<str>, V: ToString>(&mut self, plugin_versions: &[(T, V)]) {
@@ -221,51 +459,625 @@ impl State {
             .collect();
     }
 
+    /// Sets the version of a single plugin, replacing any version that was
+    /// previously set for it, and invalidates the cached results of only
+    /// those conditions that read the plugin's version.
+    pub fn set_plugin_version(&mut self, plugin_name: &str, version: &str) {
+        let key = plugin_name.to_lowercase();
+
+        if self.plugin_versions.get(&key).map(String::as_str) != Some(version) {
+            self.plugin_versions.insert(key, version.to_owned());
+            self.condition_cache
+                .retain(|f, _| !f.references_plugin_version(plugin_name));
+        }
+    }
+
+    /// Sets the values of the variables that conditions reference as
+    /// `${NAME}`, replacing any previously set values. Variable names are
+    /// case-sensitive. Evaluating a function that references a variable
+    /// without a value is an error.
+    pub fn set_variables<T: AsRef<str>, V: ToString>(&mut self, variables: &[(T, V)]) {
+        self.variables = variables
+            .iter()
+            .map(|(n, v)| (n.as_ref().to_owned(), v.to_string()))
+            .collect();
+    }
+
+    /// Sets plugins that the host has already parsed, which are used instead
+    /// of parsing the plugin files with the same filenames when evaluating
+    /// `is_master()`, `is_light_plugin()`, `is_medium_plugin()`,
+    /// `header_version()`, `record_count()`, `masters_contain()`,
+    /// `description_contains()` and `description_contains_text()`.
+    pub fn set_parsed_plugins<T: AsRef<str>>(&mut self, plugins: &[(T, Arc<dyn ParsedPlugin>)]) {
+        self.parsed_plugins = plugins
+            .iter()
+            .map(|(p, plugin)| (p.as_ref().to_lowercase(), Arc::clone(plugin)))
+            .collect();
+    }
+
+    /// Replaces the state's cached CRCs with the given CRCs. This never fails:
+    /// the error type is kept for compatibility with earlier versions, which
+    /// guarded the cache with a lock that could be poisoned.
     pub fn set_cached_crcs<T: AsRef<str>>(
         &mut self,
         plugin_crcs: &[(T, u32)],
-    ) -> Result<(), PoisonError<RwLockWriteGuard<HashMap<String, u32>>>> {
-        let mut writer = self.crc_cache.write().unwrap_or_else(|mut e| {
-            **e.get_mut() = HashMap::new();
-            self.crc_cache.clear_poison();
-            e.into_inner()
-        });
+    ) -> Result<(), PoisonError<RwLockWriteGuard<'_, HashMap<String, u32>>>> {
+        let cached_at = SystemTime::now();
 
-        writer.deref_mut().clear();
-        writer.deref_mut().extend(
+        self.crc_cache.replace(
             plugin_crcs
                 .iter()
-                .map(|(p, v)| (p.as_ref().to_lowercase(), *v)),
+                .map(|(p, v)| (p.as_ref().to_lowercase(), (*v, cached_at))),
         );
 
         Ok(())
     }
 
+    /// Gets the CRCs in the state's CRC cache so that the host can save them,
+    /// e.g. to avoid recalculating the CRCs of large archives in later runs.
+    /// Each CRC is given with the size and modification time of its file,
+    /// and CRCs for files that are missing or that have been modified since
+    /// their CRCs were cached are not included. The CRCs of files outside
+    /// the data paths, which are shared with other states in the same
+    /// [StateRegistry], are not included either.
+    pub fn export_crc_cache(&self) -> Vec<CachedCrc> {
+        function::eval::export_crc_cache(self)
+    }
+
+    /// Adds CRCs that were previously exported using
+    /// [State::export_crc_cache] to the state's CRC cache. CRCs for files that
+    /// are now missing or that have a different size or modification time are
+    /// skipped, as the files may have changed. Returns the number of CRCs that
+    /// were added.
+    pub fn import_crc_cache(&mut self, cached_crcs: &[CachedCrc]) -> usize {
+        function::eval::import_crc_cache(self, cached_crcs)
+    }
+
+    /// Calculates the CRC-32 of the file at the given path, resolving the
+    /// path relative to the data paths in the same way as paths in
+    /// conditions, so ghosted plugins and case differences are taken into
+    /// account and the state's path traversal allowlist applies. The state's
+    /// CRC caches are not used or updated. Returns `None` if there is no file
+    /// at the path.
+    pub fn calculate_crc<P: AsRef<Path>>(&self, path: P) -> Result<Option<u32>, Error> {
+        function::eval::calculate_crc(self, path.as_ref())
+    }
+
+    /// Sets the active plugins to those in `new_load_order`, and invalidates
+    /// the cached results of only those conditions that reference a plugin
+    /// that is active in one of the given load orders but not the other.
+    /// Plugin order is ignored, as no condition depends on it.
+    pub fn apply_load_order_change<T: AsRef<str>>(
+        &mut self,
+        old_load_order: &[T],
+        new_load_order: &[T],
+    ) {
+        let old_active_plugins = case_folded_map(old_load_order);
+        let new_active_plugins = case_folded_map(new_load_order);
+
+        self.invalidate_plugins(&changed_plugins(&old_active_plugins, &new_active_plugins));
+
+        self.active_plugins = new_active_plugins;
+    }
+
+    /// Adds the given plugin to the active plugins, and invalidates the cached
+    /// results of only those conditions that reference it, if it wasn't
+    /// already active.
+    pub fn activate_plugin(&mut self, plugin_name: &str) {
+        let previous = self
+            .active_plugins
+            .insert(fold_case(plugin_name), plugin_name.to_owned());
+
+        if previous.is_none() {
+            self.invalidate_plugins(&[plugin_name.to_owned()]);
+        }
+    }
+
+    /// Removes the given plugin from the active plugins, and invalidates the
+    /// cached results of only those conditions that reference it, if it was
+    /// active.
+    pub fn deactivate_plugin(&mut self, plugin_name: &str) {
+        if let Some(removed) = self.active_plugins.remove(&fold_case(plugin_name)) {
+            self.invalidate_plugins(&[removed]);
+        }
+    }
+
+    /// Clears the cached results of evaluated conditions, the cached
+    /// resolutions of the paths that they reference (including which paths
+    /// are missing), the cached directory entries, file metadata and plugin
+    /// headers, and the cached executable versions that are shared with other
+    /// states in the same [StateRegistry]. Cached CRCs are kept, as they're
+    /// expensive to calculate: use [State::clear_crc_cache] to clear them.
+    ///
+    /// This never fails: the error type is kept for compatibility with earlier
+    /// versions, which guarded the cache with a lock that could be poisoned.
     pub fn clear_condition_cache(
         &mut self,
-    ) -> Result<(), PoisonError<RwLockWriteGuard<HashMap<Function, bool>>>> {
-        let mut writer = self.condition_cache.write().unwrap_or_else(|mut e| {
-            **e.get_mut() = HashMap::new();
-            self.crc_cache.clear_poison();
-            e.into_inner()
-        });
-        writer.clear();
+    ) -> Result<(), PoisonError<RwLockWriteGuard<'_, HashMap<Function, bool>>>> {
+        self.condition_cache.clear();
+        self.clear_path_caches();
+        self.shared_caches.clear_versions();
+
         Ok(())
     }
 
+    /// Clears the cached CRCs of files, including those given by
+    /// [State::set_cached_crcs] and those that are shared with other states
+    /// in the same [StateRegistry]. Cached condition results that were
+    /// calculated using them are kept.
+    pub fn clear_crc_cache(&mut self) {
+        self.crc_cache.clear();
+        self.shared_caches.crcs.clear();
+    }
+
+    /// Clears everything that the state has cached, including everything
+    /// that [State::clear_condition_cache] and [State::clear_crc_cache]
+    /// clear, and the cached SHA-256 digests, plugin record counts and archive
+    /// indexes.
+    pub fn clear_all_caches(&mut self) {
+        self.condition_cache.clear();
+        self.clear_path_caches();
+        self.crc_cache.clear();
+        self.sha256_cache.clear();
+        self.record_count_cache.clear();
+        self.archive_index_cache.clear();
+        self.shared_caches.clear();
+    }
+
+    /// Forgets that the given path was found to be missing, e.g. because a
+    /// file has been created at that path, so that conditions that reference
+    /// it are evaluated using the filesystem again. The path is compared with
+    /// the paths given in conditions case-insensitively. The cached results
+    /// of conditions that reference the path are also cleared, as is any
+    /// cached metadata that records nothing being at the path.
+    pub fn invalidate_missing_path<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref();
+        let is_other_path = |p: &Path| !p.as_os_str().eq_ignore_ascii_case(path.as_os_str());
+
+        self.missing_path_cache.retain(|p, _| is_other_path(p));
+        self.resolved_path_cache.retain(|p, _| is_other_path(p));
+        self.plugin_cache
+            .retain(|p, _| !p.as_str().eq_ignore_ascii_case(&path.to_string_lossy()));
+        self.condition_cache
+            .retain(|f, _| f.paths().all(is_other_path));
+        self.invalidate_metadata(path, true);
+    }
+
+    /// Forgets what is cached about the file or directory at the given path,
+    /// e.g. because a mod that provides it has been installed or removed, so
+    /// that functions that reference it use the filesystem again. The path is
+    /// relative to the data paths, like the paths given in conditions, and is
+    /// compared with them case-insensitively. As well as everything that
+    /// [State::invalidate_missing_path] forgets, this forgets the metadata
+    /// cached for the path in each data path, including for its ghosted form.
+    /// The entries of the directory that contains the path are not forgotten:
+    /// use [State::invalidate_directory] for that.
+    pub fn invalidate_path<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref();
+        self.invalidate_missing_path(path);
+        self.invalidate_metadata(path, false);
+    }
+
+    /// Forgets the cached entries of the given directory and its
+    /// subdirectories, e.g. because files have been added to or removed from
+    /// it, so that functions that read it use the filesystem again. The path
+    /// is relative to the data paths, like the paths given in conditions, and
+    /// is compared with them case-insensitively. The cached results of
+    /// conditions that reference the directory are also cleared.
+    pub fn invalidate_directory<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref();
+        let lowercased = |p: &Path| PathBuf::from(p.to_string_lossy().to_lowercase());
+        let directories: Vec<_> = self
+            .additional_data_paths
+            .iter()
+            .chain(std::iter::once(&self.data_path))
+            .map(|data_path| lowercased(&data_path.join(path)))
+            .collect();
+
+        self.directory_cache.retain(|directory, _| {
+            let directory = lowercased(directory);
+            !directories.iter().any(|d| directory.starts_with(d))
+        });
+        self.condition_cache.retain(|f, _| {
+            f.paths()
+                .all(|p| !p.as_os_str().eq_ignore_ascii_case(path.as_os_str()))
+        });
+    }
+
     pub fn set_additional_data_paths(&mut self, additional_data_paths: Vec<PathBuf>) {
         self.additional_data_paths = additional_data_paths;
+        self.clear_path_caches();
+    }
+
+    /// Sets the mapper used to translate Windows-style absolute paths in
+    /// conditions (e.g. `C:\Program Files\...`) into host paths, for when
+    /// the game is run under Wine or Proton. If no mapper is set, evaluating
+    /// a condition with a path that starts with a drive letter errors.
+    pub fn set_wine_path_mapper(&mut self, wine_path_mapper: Option<WinePathMapper>) {
+        self.wine_path_mapper = wine_path_mapper;
+        self.clear_path_caches();
+    }
+
+    /// Restricts the paths that conditions may reference. If an allowlist is
+    /// given, evaluating a condition with a path that is absolute or that
+    /// contains a `..` component will fail with [Error::PathTraversal] unless
+    /// the path starts with one of the allowlisted paths (compared
+    /// case-insensitively). An empty allowlist rejects all such paths, and
+    /// `None` removes the restriction, which is the default.
+    pub fn set_path_traversal_allowlist(&mut self, allowlist: Option<Vec<PathBuf>>) {
+        self.path_traversal_allowlist = allowlist;
+    }
+
+    /// Limits how many directory entries a function that matches filenames
+    /// against a regex may examine, across all data paths, before evaluation
+    /// fails with [Error::DirectoryEntryLimitExceeded]. This guards against
+    /// scanning enormous directories if the data path is set incorrectly.
+    /// `None` removes the limit, which is the default.
+    pub fn set_max_directory_entries(&mut self, max_directory_entries: Option<usize>) {
+        self.max_directory_entries = max_directory_entries;
+    }
+
+    /// Sets the size in bytes above which files are memory-mapped to calculate
+    /// their CRCs, which is much faster than reading them for multi-gigabyte
+    /// archives. Memory mapping is only used if the state's
+    /// [FileSystem] supports it, which [StdFileSystem] does on Windows and
+    /// Unix-like platforms. `None` disables memory mapping, and the default
+    /// threshold is 64 MiB.
+    pub fn set_memory_map_threshold(&mut self, memory_map_threshold: Option<u64>) {
+        self.memory_map_threshold = memory_map_threshold;
+    }
+
+    /// Sets the size in bytes above which files have their CRCs calculated by
+    /// hashing chunks of them on rayon's thread pool, which speeds up
+    /// `checksum()` conditions on very large archives. Only files that are
+    /// memory-mapped, as set by [State::set_memory_map_threshold], are hashed
+    /// on multiple threads. `None` disables multi-threaded hashing, and the
+    /// default threshold is 512 MiB.
+    #[cfg(feature = "parallel")]
+    pub fn set_parallel_crc_threshold(&mut self, parallel_crc_threshold: Option<u64>) {
+        self.parallel_crc_threshold = parallel_crc_threshold;
+    }
+
+    /// Makes results that would otherwise depend on the order in which the
+    /// filesystem lists directory entries or in which threads are scheduled
+    /// the same across runs and platforms, at some cost to performance:
+    ///
+    /// - If a path's case doesn't match the filesystem's and more than one
+    ///   entry matches it case-insensitively, the entry with the
+    ///   lexicographically smallest name is used instead of the first one
+    ///   listed, so the whole directory is read.
+    /// - [prefetch()](crate::prefetch()) evaluates functions one at a time in the
+    ///   order that they first appear, so caches and their listeners see the
+    ///   same sequence of changes on every run.
+    ///
+    /// Reports, batch results and bundles are always produced in the order of
+    /// their inputs, and cache evictions are always reported in order of their
+    /// keys. Deterministic ordering is disabled by default.
+    pub fn set_deterministic_ordering(&mut self, deterministic_ordering: bool) {
+        self.deterministic_ordering = deterministic_ordering;
+    }
+
+    /// Sets how filesystem operations that fail with errors that are likely
+    /// to be transient, like timeouts on network shares, are retried. If
+    /// there is no policy, which is the default, they're not retried and
+    /// functions treat them like other errors, e.g. `file()` is false. If
+    /// there is a policy, an operation that is still failing once its retries
+    /// are used up fails evaluation with [Error::RetriesExhausted].
+    pub fn set_retry_policy(&mut self, retry_policy: Option<RetryPolicy>) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Sets the filesystem that functions read from, which is the filesystem on
+    /// disk by default. Paths that were resolved and results that were cached
+    /// using the previous filesystem are forgotten, apart from those in caches
+    /// that are shared with other states.
+    pub fn set_file_system(&mut self, file_system: Arc<dyn FileSystem>) {
+        self.file_system = file_system;
+        self.clear_path_caches();
+        self.condition_cache.clear();
+        self.crc_cache.clear();
+        self.sha256_cache.clear();
+        self.record_count_cache.clear();
+        self.archive_index_cache.clear();
+    }
+
+    /// Sets files that will exist at the data path when the game runs but that
+    /// may not be on disk, e.g. because a mod manager will overlay them. Paths
+    /// are relative to the data path and are compared case-insensitively.
+    /// `file()`, `file_size()`, `file_regex()`, `many()` and the functions
+    /// related to them check these files before the filesystem: virtual
+    /// files exist, but their sizes aren't known, so `file_size()` is false for
+    /// them, and only their filenames are matched against regexes, not the
+    /// names of the directories that contain them. Any previously set virtual
+    /// files are replaced, and cached results are forgotten.
+    pub fn set_virtual_files(&mut self, virtual_files: Vec<PathBuf>) {
+        self.virtual_files = VirtualFiles::new(virtual_files);
+        self.condition_cache.clear();
+    }
+
+    /// Sets whether evaluation is strict, which it isn't by default. Functions
+    /// usually treat errors when accessing a path like the path being missing
+    /// or unreadable, e.g. `file_size()` is false, but in strict mode,
+    /// evaluation fails with [Error::IoError] for the path instead, unless the
+    /// error is that the path doesn't exist, or that the file's contents
+    /// aren't in the expected format. Permission errors are still expected
+    /// from `readable()` and `writable()`, as that's what they check for. This
+    /// can help to find why a condition has an unexpected result. Changing
+    /// whether evaluation is strict forgets cached results, as they may hide
+    /// errors.
+    pub fn set_strict(&mut self, strict: bool) {
+        if strict != self.strict {
+            self.condition_cache.clear();
+        }
+        self.strict = strict;
+    }
+
+    /// Sets a token that can be used to cancel evaluations that use this
+    /// state, e.g. from a UI thread. Once the token is cancelled, evaluation
+    /// fails with [Error::Cancelled] before the next function is evaluated or
+    /// the next directory entry is read.
+    pub fn set_cancellation_token(&mut self, cancellation_token: Option<CancellationToken>) {
+        self.cancellation_token = cancellation_token;
+    }
+
+    /// Sets a time after which evaluations that use this state give up,
+    /// failing with [Error::DeadlineExceeded] before the next function is
+    /// evaluated or the next directory entry is read. There is no deadline by
+    /// default. A single filesystem operation that is slow, e.g. on a network
+    /// share, is not interrupted, but evaluation stops as soon as it
+    /// finishes.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// Sets whether functions may read from the filesystem, which is allowed
+    /// by default. If it's not allowed, evaluating a function that would
+    /// need to read a path fails with [Error::FilesystemAccessDisabled],
+    /// unless its result is cached or can be calculated from the CRCs,
+    /// parsed plugins or active plugins given to the state.
+    /// [Expression::eval_three_valued] treats such functions as having an
+    /// unknown result.
+    pub fn set_filesystem_access(&mut self, filesystem_access: bool) {
+        self.filesystem_access = filesystem_access;
+    }
+
+    /// Sets the storage used to cache the results of evaluated conditions.
+    /// Keys are functions as they would be written in a condition string.
+    /// Results already cached in the previous storage are not copied over.
+    pub fn set_condition_cache_storage(
+        &mut self,
+        storage: Box<dyn CacheStorage<(bool, SystemTime)>>,
+    ) {
+        self.condition_cache.set_storage(storage);
+    }
+
+    /// Sets the storage used to cache the CRCs of files. Keys are lowercased
+    /// file paths as written in condition strings. CRCs already cached in the
+    /// previous storage are not copied over.
+    pub fn set_crc_cache_storage(&mut self, storage: Box<dyn CacheStorage<(u32, SystemTime)>>) {
+        self.crc_cache.set_storage(storage);
+    }
+
+    /// Registers an observer that is notified whenever a function result or
+    /// file CRC is cached, or cached values are invalidated. Observers can't
+    /// be removed, and are notified in the order they were added.
+    pub fn add_cache_observer(&mut self, observer: Arc<dyn CacheObserver>) {
+        let condition_observer = Arc::clone(&observer);
+        self.condition_cache.add_listener(Arc::new(move |change| {
+            let event = match change {
+                CacheChange::Inserted(function, (result, _)) => CacheEvent::ConditionCached {
+                    function: function.to_string(),
+                    result: *result,
+                },
+                CacheChange::Evicted(function) => CacheEvent::ConditionEvicted {
+                    function: function.to_string(),
+                },
+                CacheChange::Cleared => CacheEvent::ConditionCacheCleared,
+            };
+            condition_observer.cache_changed(&event);
+        }));
+
+        self.crc_cache.add_listener(Arc::new(move |change| {
+            let event = match change {
+                CacheChange::Inserted(file, (crc, _)) => CacheEvent::CrcCached {
+                    file: file.clone(),
+                    crc: *crc,
+                },
+                CacheChange::Evicted(file) => CacheEvent::CrcEvicted { file: file.clone() },
+                CacheChange::Cleared => CacheEvent::CrcCacheCleared,
+            };
+            observer.cache_changed(&event);
+        }));
+    }
+
+    /// Forgets the metadata cached for the given path in each data path,
+    /// including for its ghosted form, or only the metadata that records
+    /// nothing being there.
+    fn invalidate_metadata(&mut self, path: &Path, only_missing: bool) {
+        let lowercased = |p: &Path| p.to_string_lossy().to_lowercase();
+        let resolved_paths: Vec<_> = self
+            .additional_data_paths
+            .iter()
+            .chain(std::iter::once(&self.data_path))
+            .map(|data_path| lowercased(&data_path.join(path)))
+            .collect();
+
+        self.metadata_cache.retain(|resolved_path, metadata| {
+            if only_missing && metadata.is_some() {
+                return true;
+            }
+
+            let resolved_path = lowercased(resolved_path);
+            let unghosted_path = resolved_path
+                .strip_suffix(".ghost")
+                .unwrap_or(&resolved_path);
+            !resolved_paths
+                .iter()
+                .any(|p| *p == resolved_path || p == unghosted_path)
+        });
+    }
+
+    /// Forgets the cached results of conditions that reference any of the
+    /// given plugins, and the cached paths and metadata of those plugins, as
+    /// activating or deactivating a plugin may ghost or unghost it.
+    fn invalidate_plugins(&mut self, changed_plugins: &[String]) {
+        if changed_plugins.is_empty() {
+            return;
+        }
+
+        let game_type = self.game_type;
+        let is_unaffected = |references_plugin: &dyn Fn(&str) -> bool| {
+            !changed_plugins.iter().any(|p| references_plugin(p))
+        };
+
+        self.condition_cache
+            .retain(|f, _| is_unaffected(&|p| f.references_plugin(game_type, p)));
+        self.resolved_path_cache
+            .retain(|path, _| is_unaffected(&|p| is_plugin_path(game_type, path, p)));
+        self.missing_path_cache
+            .retain(|path, _| is_unaffected(&|p| is_plugin_path(game_type, path, p)));
+        self.metadata_cache
+            .retain(|path, _| is_unaffected(&|p| is_plugin_path(game_type, path, p)));
+    }
+
+    fn clear_path_caches(&mut self) {
+        self.resolved_path_cache.clear();
+        self.missing_path_cache.clear();
+        self.directory_cache.clear();
+        self.metadata_cache.clear();
+        self.plugin_cache.clear();
     }
 }
 
+/// Get the plugins that are in only one of the given maps of case-folded
+/// plugin filenames, as they were given.
+#[cfg(feature = "eval")]
+fn changed_plugins(
+    old_plugins: &HashMap<String, String>,
+    new_plugins: &HashMap<String, String>,
+) -> Vec<String> {
+    let only_in = |plugins: &HashMap<String, String>, other: &HashMap<String, String>| {
+        plugins
+            .iter()
+            .filter(|(key, _)| !other.contains_key(*key))
+            .map(|(_, name)| name.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let mut changed_plugins = only_in(old_plugins, new_plugins);
+    changed_plugins.extend(only_in(new_plugins, old_plugins));
+    changed_plugins
+}
+
+/// Map plugin filenames by their case-folded forms, so that they can be looked
+/// up without regard to case.
+#[cfg(feature = "eval")]
+fn case_folded_map<T: AsRef<str>>(plugin_names: &[T]) -> HashMap<String, String> {
+    plugin_names
+        .iter()
+        .map(|n| (fold_case(n.as_ref()), n.as_ref().to_owned()))
+        .collect()
+}
+
 /// Compound conditions joined by 'or'
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct Expression(Vec<CompoundCondition>);
 
+#[cfg(feature = "eval")]
 impl Expression {
     pub fn eval(&self, state: &State) -> Result<bool, Error> {
+        self.eval_with_observer(state, &mut ())
+    }
+
+    /// Evaluate the expression's top-level `or` and `and` clauses in parallel
+    /// on rayon's thread pool, which can be chosen by calling this within
+    /// [rayon::ThreadPool::install]. Clauses that serial evaluation would skip
+    /// due to short-circuiting are still evaluated, but the result and any
+    /// error returned are the same as for [Expression::eval].
+    #[cfg(feature = "parallel")]
+    pub fn eval_parallel(&self, state: &State) -> Result<bool, Error> {
+        parallel::eval_expression(self, state)
+    }
+
+    /// Evaluate the expression, recording the result and source of each
+    /// function that gets evaluated.
+    pub fn eval_with_report(&self, state: &State) -> Result<EvaluationReport, Error> {
+        let mut function_evaluations = Vec::new();
+        let result = self.eval_with_observer(state, &mut function_evaluations)?;
+
+        Ok(EvaluationReport::new(result, function_evaluations))
+    }
+
+    /// Evaluate the expression, recording a trace with the same structure as
+    /// the expression that gives the result of each part, and the arguments,
+    /// result source and resolved paths of each function. Parts that aren't
+    /// evaluated due to short-circuiting have no result.
+    pub fn eval_with_trace(&self, state: &State) -> Result<ExpressionTrace, Error> {
+        ExpressionTrace::new(self, state)
+    }
+
+    /// Evaluate the expression and explain its result as a Markdown list that
+    /// gives the result of each part of the expression, and the reason for
+    /// each function's result where there is a clearer one than its result,
+    /// e.g. that a file was not found in any data path.
+    pub fn explain(&self, state: &State) -> Result<String, Error> {
+        self.eval_with_trace(state)
+            .map(|trace| explain::explain(&trace, state.file_system.as_ref()))
+    }
+
+    /// Get the filesystem operations that evaluating the expression would
+    /// perform, in order, without accessing the filesystem. As the results of
+    /// functions aren't known, every function is included, even those that
+    /// evaluation would skip because an earlier function already decided the
+    /// result. Functions with results or data that the state has already
+    /// cached, and repeats of earlier functions, are left out.
+    ///
+    /// As it's not known which of the data paths contain a file, every path
+    /// that resolving its path would check is included, and then operations
+    /// on the file use the path that it would have in the main data path.
+    /// Subdirectories that recursive directory searches would also list, and
+    /// files listed by `checksum_manifest()` manifests, aren't included.
+    pub fn dry_run(&self, state: &State) -> Result<Vec<FileSystemOperation>, Error> {
+        dry_run::dry_run(self, state)
+    }
+
+    /// Start evaluating the expression one function at a time. See
+    /// [StepwiseEvaluation] for details.
+    pub fn eval_stepwise<'a>(&'a self, state: &'a State) -> StepwiseEvaluation<'a> {
+        StepwiseEvaluation::new(self, state)
+    }
+
+    /// Evaluate the expression using three-valued logic, in which functions
+    /// that can't be evaluated because their paths aren't allowed or because
+    /// the state doesn't allow filesystem access have an unknown result.
+    /// Unknown results are combined using Kleene logic, so the expression's
+    /// result is only unknown if it depends on them. Other errors are still
+    /// returned.
+    pub fn eval_three_valued(&self, state: &State) -> Result<Truth, Error> {
+        truth::eval_expression(self, &mut |function| truth::eval_function(function, state))
+    }
+
+    /// Build a truth table of the expression's results for every combination
+    /// of results of its functions that have unknown results in
+    /// [three-valued evaluation](Expression::eval_three_valued), which can be
+    /// used to find what would need to hold for the expression to be true.
+    ///
+    /// Every function in the expression is evaluated once, including those
+    /// that short-circuiting would skip, so errors that [Expression::eval]
+    /// wouldn't encounter may be returned. If more than `max_unknowns`
+    /// functions have unknown results, building the table fails with
+    /// [Error::TooManyUnknowns], as the table doubles in size for each one.
+    pub fn truth_table(&self, state: &State, max_unknowns: usize) -> Result<TruthTable, Error> {
+        TruthTable::new(self, state, max_unknowns)
+    }
+
+    fn eval_with_observer(
+        &self,
+        state: &State,
+        observer: &mut dyn EvalObserver,
+    ) -> Result<bool, Error> {
         for compound_condition in &self.0 {
-            if compound_condition.eval(state)? {
+            if compound_condition.eval(state, observer)? {
                 return Ok(true);
             }
         }
@@ -273,30 +1085,395 @@ impl Expression {
     }
 }
 
-impl str::FromStr for Expression {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        parse_expression(s)
+impl Expression {
+    /// Parse the given condition string, applying the limits set in the given
+    /// options. Parsing using [str::parse] is equivalent to using the default
+    /// options.
+    ///
+    /// Conditions may be split across lines, and a `#` that isn't inside a
+    /// string starts a comment that runs to the end of its line. Comments are
+    /// ignored, so they're not kept when the expression is displayed.
+    pub fn parse_with_options(input: &str, options: &ParseOptions) -> Result<Self, Error> {
+        let input = &*blank_comments(input);
+        parse_expression(input, options)
             .map_err(Error::from)
-            .and_then(|(remaining_input, expression)| {
+            .and_then(|(remaining_input, mut expression)| {
                 if remaining_input.is_empty() {
+                    expression.resolve_spans(input);
                     Ok(expression)
                 } else {
                     Err(Error::UnconsumedInput(remaining_input.to_owned()))
                 }
             })
     }
+
+    /// Parse each of the given condition strings like
+    /// [Expression::parse_with_options], returning a result for each in the
+    /// same order. Each distinct regex is only compiled once, however many of
+    /// the condition strings it's given in, which can save a lot of time when
+    /// parsing a whole masterlist. If the options don't have a
+    /// [RegexInterner], a new one is used for just these condition strings.
+    pub fn parse_all<I, S>(inputs: I, options: &ParseOptions) -> Vec<Result<Self, Error>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut options = options.clone();
+        if !options.has_regex_interner() {
+            options = options.with_regex_interner(RegexInterner::new());
+        }
+
+        inputs
+            .into_iter()
+            .map(|input| Expression::parse_with_options(input.as_ref(), &options))
+            .collect()
+    }
+
+    /// Parse the given condition string like [Expression::parse_with_options],
+    /// also finding any functions that aren't written in their canonical
+    /// form, which is only possible if the options allow
+    /// [lenient syntax](ParseOptions::with_lenient_syntax). Each is given as a
+    /// [LintKind::NonCanonicalSyntax] lint, in the order they appear.
+    pub fn parse_with_warnings(
+        input: &str,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<Lint>), Error> {
+        let expression = Expression::parse_with_options(input, options)?;
+        let warnings = lint::non_canonical_syntax(&expression, input);
+
+        Ok((expression, warnings))
+    }
+
+    /// Parse the given condition string like [Expression::parse_with_options],
+    /// but if it can't be parsed, describe where parsing failed and what was
+    /// expected there, so that the problem can be pointed out to the
+    /// condition's author.
+    pub fn parse_with_diagnostics(input: &str, options: &ParseOptions) -> Result<Self, ParseError> {
+        let input = &*blank_comments(input);
+        parse_to_end(input, options)
+            .map(|mut expression| {
+                expression.resolve_spans(input);
+                expression
+            })
+            .map_err(|error| ParseError::new(input, error))
+    }
+
+    /// Parse the given condition string like
+    /// [Expression::parse_with_diagnostics], but if it can't be parsed, carry
+    /// on after each error to find any others, and return all of them in the
+    /// order that they appear.
+    ///
+    /// Parsing resumes after the next `and` or `or` that isn't inside
+    /// parentheses or a string, so each condition at the top level of the
+    /// expression gets at most one error.
+    pub fn parse_with_recovery(
+        input: &str,
+        options: &ParseOptions,
+    ) -> Result<Self, Vec<ParseError>> {
+        let input = &*blank_comments(input);
+
+        // Parsing would resume after the first error, so check the limit on
+        // the whole input up front.
+        if let Err(error) = check_expression_length(input, options) {
+            return Err(vec![ParseError::new(input, error)]);
+        }
+
+        let mut errors = Vec::new();
+        let mut remaining_input = input;
+        loop {
+            match parse_to_end(remaining_input, options) {
+                Ok(mut expression) if errors.is_empty() => {
+                    expression.resolve_spans(input);
+                    return Ok(expression);
+                }
+                Ok(_) => break,
+                Err(error) => {
+                    let error = ParseError::new(input, error);
+                    let resume_at = next_top_level_separator_end(input, error.span().start);
+                    errors.push(error);
+
+                    match resume_at.and_then(|offset| input.get(offset..)) {
+                        Some(rest) => remaining_input = rest,
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        Err(errors)
+    }
+
+    /// Estimate how expensive the expression is to evaluate, without
+    /// accessing the filesystem. See [EvaluationCost] for details.
+    pub fn estimate_cost(&self) -> EvaluationCost {
+        EvaluationCost::of(self)
+    }
+
+    /// Measure how complex the expression is and look for problems in it
+    /// that are probably unintended, without accessing the filesystem. See
+    /// [ExpressionMetrics] for details.
+    pub fn metrics(&self) -> ExpressionMetrics {
+        ExpressionMetrics::of(self)
+    }
+
+    /// Get an equivalent expression that is cheaper to evaluate. Functions
+    /// with results that only depend on the platform that the crate was built
+    /// for are folded into the conditions that contain them, unnecessary
+    /// parentheses and double negations are removed, and repeated conditions
+    /// and compound conditions are removed. The functions that remain keep
+    /// their spans.
+    ///
+    /// As there are no literal `true` or `false` conditions, a condition that
+    /// is found to always have the same result is simplified to a single
+    /// platform function.
+    #[must_use]
+    pub fn simplify(&self) -> Expression {
+        simplify::simplify_expression(self)
+    }
+
+    /// Write the expression as a condition string in a canonical form, which
+    /// is the same as its [Display](fmt::Display) output. Parsing the string
+    /// gives an equal expression, and conditions that only differ in their
+    /// formatting give the same string: keywords and arguments are separated
+    /// by single spaces, CRCs are written in uppercase hexadecimal, SHA-256
+    /// digests are written in lowercase hexadecimal, and regexes are written
+    /// as they were given, without a parent path if they didn't have one.
+    pub fn to_condition_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// Get every filesystem path that the expression may read when it's
+    /// evaluated, in the order that they first appear and without
+    /// duplicates. The plugins that `active()` and similar functions check
+    /// are not included, as they're only compared against plugin names.
+    pub fn referenced_paths(&self) -> Vec<ReferencedPath<'_>> {
+        ast::referenced_paths(self)
+    }
+
+    /// Get the compound conditions that are joined by `or` in the
+    /// expression. Use a [Visitor] to traverse the whole expression.
+    pub fn compound_conditions(&self) -> &[CompoundCondition] {
+        &self.0
+    }
+}
+
+impl Expression {
+    /// Convert the function spans recorded while parsing the given input into
+    /// offsets from its start.
+    fn resolve_spans(&mut self, input: &str) {
+        self.map_spans(&|offset| input.len().saturating_sub(offset));
+    }
+
+    fn map_spans(&mut self, f: &dyn Fn(usize) -> usize) {
+        for compound_condition in &mut self.0 {
+            compound_condition.map_spans(f);
+        }
+    }
+
+    fn set_spans(&mut self, span: &Range<usize>) {
+        for compound_condition in &mut self.0 {
+            compound_condition.set_spans(span);
+        }
+    }
 }
 
-fn parse_expression(input: &str) -> ParsingResult<Expression> {
+impl str::FromStr for Expression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expression::parse_with_options(s, &ParseOptions::default())
+    }
+}
+
+fn parse_expression<'a>(input: &'a str, options: &ParseOptions) -> ParsingResult<'a, Expression> {
+    check_expression_length(input, options).map_err(nom::Err::Failure)?;
+
+    // The whole condition may be surrounded by whitespace, e.g. if it's split
+    // across lines.
+    let (input, _) = map_err(multispace0).parse(input)?;
+    let (remaining_input, expression) = parse_nested_expression(input, options, 0)?;
+    if remaining_input.trim_start_matches(is_multispace).is_empty() {
+        Ok(("", expression))
+    } else {
+        Ok((remaining_input, expression))
+    }
+}
+
+/// Parse an expression that is nested inside the given number of
+/// parenthesised expressions.
+fn parse_nested_expression<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+    depth: usize,
+) -> ParsingResult<'a, Expression> {
     map(
-        separated_list0(map_err(whitespace(tag("or"))), CompoundCondition::parse),
+        separated_list0(whitespace_token("or"), |i| {
+            CompoundCondition::parse_nested(i, options, depth)
+        }),
         Expression,
     )
     .parse(input)
 }
 
+/// Check the whole condition string against the options' length limit. The
+/// error is given at the end of the string, so that it doesn't hold a copy of
+/// the string.
+fn check_expression_length<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Result<(), ParsingError<&'a str>> {
+    options
+        .check_expression_length(input)
+        .map_err(|kind| kind.at(input.get(input.len()..).unwrap_or_default()))
+}
+
+/// Check if the given character is whitespace that may separate tokens.
+fn is_multispace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n')
+}
+
+/// Replace each comment in the given condition string with spaces, so that
+/// it's skipped like any other whitespace. A comment starts with a `#` that
+/// isn't inside a string and runs to the end of its line. Each byte of a
+/// comment becomes a space, so offsets into the condition string are the same
+/// with or without its comments.
+fn blank_comments(input: &str) -> Cow<'_, str> {
+    if !input.contains('#') {
+        return Cow::Borrowed(input);
+    }
+
+    let mut blanked = String::with_capacity(input.len());
+    let mut in_string = false;
+    let mut in_comment = false;
+    let mut escaped = false;
+    for (offset, c) in input.char_indices() {
+        match c {
+            '\n' => in_comment = false,
+            _ if in_comment => {}
+            _ if escaped => escaped = false,
+            '\\' if in_string => {
+                escaped = !starts_with_trailing_backslash(input.get(offset..).unwrap_or_default());
+            }
+            '"' => in_string = !in_string,
+            '#' if !in_string => in_comment = true,
+            _ => {}
+        }
+
+        if in_comment {
+            blanked.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        } else {
+            blanked.push(c);
+        }
+    }
+
+    Cow::Owned(blanked)
+}
+
+/// Parse a parenthesised expression's contents, unless that would nest it
+/// deeper than the options allow.
+fn parse_parenthesised_expression<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+    depth: usize,
+) -> ParsingResult<'a, Expression> {
+    let depth = depth.saturating_add(1);
+    if let Some(max_depth) = options.max_nesting_depth() {
+        if depth > max_depth {
+            return Err(nom::Err::Failure(
+                ParsingErrorKind::ExpressionTooDeep(max_depth).at(input),
+            ));
+        }
+    }
+
+    parse_nested_expression(input, options, depth)
+}
+
+/// Parse all of the given input as an expression, or get the error that
+/// stopped it from being parsed.
+fn parse_to_end<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Result<Expression, ParsingError<&'a str>> {
+    match parse_expression(input, options) {
+        Ok(("", expression)) => Ok(expression),
+        Ok((remaining_input, _)) => Err(unconsumed_input_error(input, remaining_input, options)),
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => Err(e),
+        Err(nom::Err::Incomplete(_)) => Err(ParsingErrorKind::GenericParserError(
+            "more input was needed".to_owned(),
+        )
+        .at(input.get(input.len()..).unwrap_or_default())),
+    }
+}
+
+/// Find the first `and` or `or` at or after the given offset that isn't
+/// inside parentheses or a string, and get the offset of the start of the
+/// condition that follows it.
+fn next_top_level_separator_end(input: &str, from: usize) -> Option<usize> {
+    let mut depth = 0_usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (offset, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => {
+                escaped = !starts_with_trailing_backslash(input.get(offset..).unwrap_or_default());
+            }
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth = depth.saturating_add(1),
+            ')' if !in_string => depth = depth.saturating_sub(1),
+            _ if in_string || depth > 0 || offset < from => {}
+            _ => {
+                let follows_condition = input
+                    .get(..offset)
+                    .and_then(|before| before.chars().next_back())
+                    .is_some_and(|c| c.is_whitespace() || c == ')');
+                let rest = input.get(offset..).unwrap_or_default();
+                let after = ["and", "or"]
+                    .into_iter()
+                    .find_map(|separator| rest.strip_prefix(separator))
+                    .filter(|after| after.starts_with(|c: char| c.is_whitespace() || c == '('));
+
+                if let Some(after) = after.filter(|_| follows_condition) {
+                    let condition = after.trim_start();
+                    return Some(input.len().saturating_sub(condition.len()));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Get the error that stopped an expression from being parsed past the
+/// remaining input. Lists of conditions end at the last condition that could
+/// be parsed, so the error comes from parsing the next separator and
+/// condition, or the first condition if none could be parsed.
+fn unconsumed_input_error<'a>(
+    input: &'a str,
+    remaining_input: &'a str,
+    options: &ParseOptions,
+) -> ParsingError<&'a str> {
+    let consumed = input.len().saturating_sub(remaining_input.len());
+    let result = if input
+        .get(..consumed)
+        .is_some_and(|c| c.trim_start_matches(is_multispace).is_empty())
+    {
+        Condition::parse(remaining_input, options)
+    } else {
+        preceded(
+            alt((whitespace_token("and"), whitespace_token("or"))),
+            |i| Condition::parse(i, options),
+        )
+        .parse(remaining_input)
+    };
+
+    match result {
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => e,
+        _ => {
+            ParsingErrorKind::GenericParserError("unexpected input".to_owned()).at(remaining_input)
+        }
+    }
+}
+
 impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let strings: Vec<String> = self.0.iter().map(CompoundCondition::to_string).collect();
@@ -306,27 +1483,60 @@ impl fmt::Display for Expression {
 
 /// Conditions joined by 'and'
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
-struct CompoundCondition(Vec<Condition>);
+pub struct CompoundCondition(Vec<Condition>);
 
 impl CompoundCondition {
-    fn eval(&self, state: &State) -> Result<bool, Error> {
+    /// Get the clauses that are joined by `and` in the compound condition,
+    /// in the order they were written.
+    pub fn clauses(&self) -> impl ExactSizeIterator<Item = Clause<'_>> {
+        self.0.iter().map(Clause::new)
+    }
+}
+
+impl CompoundCondition {
+    #[cfg(feature = "eval")]
+    fn eval(&self, state: &State, observer: &mut dyn EvalObserver) -> Result<bool, Error> {
         for condition in &self.0 {
-            if !condition.eval(state)? {
+            if !condition.eval(state, observer)? {
                 return Ok(false);
             }
         }
         Ok(true)
     }
 
-    fn parse(input: &str) -> ParsingResult<CompoundCondition> {
+    fn parse<'a>(input: &'a str, options: &ParseOptions) -> ParsingResult<'a, CompoundCondition> {
+        CompoundCondition::parse_nested(input, options, 0)
+    }
+
+    fn parse_nested<'a>(
+        input: &'a str,
+        options: &ParseOptions,
+        depth: usize,
+    ) -> ParsingResult<'a, CompoundCondition> {
         map(
-            separated_list0(map_err(whitespace(tag("and"))), Condition::parse),
+            separated_list0(whitespace_token("and"), |i| {
+                Condition::parse_nested(i, options, depth)
+            }),
             CompoundCondition,
         )
         .parse(input)
     }
 }
 
+impl CompoundCondition {
+    fn map_spans(&mut self, f: &dyn Fn(usize) -> usize) {
+        for condition in &mut self.0 {
+            condition.map_spans(f);
+        }
+    }
+
+    fn set_spans(&mut self, span: &Range<usize>) {
+        for condition in &mut self.0 {
+            condition.set_spans(span);
+        }
+    }
+}
+
 impl fmt::Display for CompoundCondition {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let strings: Vec<String> = self.0.iter().map(Condition::to_string).collect();
@@ -334,57 +1544,164 @@ impl fmt::Display for CompoundCondition {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// The ranges are the byte spans of the functions in the condition string
+/// that they were parsed from, and are ignored when comparing and hashing
+/// conditions.
+#[derive(Clone, Debug)]
 enum Condition {
-    Function(Function),
-    InvertedFunction(Function),
+    Function(Function, Range<usize>),
+    InvertedFunction(Function, Range<usize>),
     Expression(Expression),
     InvertedExpression(Expression),
 }
 
+impl PartialEq for Condition {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Condition::Function(a, _), Condition::Function(b, _))
+            | (Condition::InvertedFunction(a, _), Condition::InvertedFunction(b, _)) => a == b,
+            (Condition::Expression(a), Condition::Expression(b))
+            | (Condition::InvertedExpression(a), Condition::InvertedExpression(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Condition {}
+
+impl Hash for Condition {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        discriminant(self).hash(state);
+
+        match self {
+            Condition::Function(f, _) | Condition::InvertedFunction(f, _) => f.hash(state),
+            Condition::Expression(e) | Condition::InvertedExpression(e) => e.hash(state),
+        }
+    }
+}
+
 impl Condition {
-    fn eval(&self, state: &State) -> Result<bool, Error> {
+    #[cfg(feature = "eval")]
+    fn eval(&self, state: &State, observer: &mut dyn EvalObserver) -> Result<bool, Error> {
         match self {
-            Condition::Function(f) => f.eval(state),
-            Condition::InvertedFunction(f) => f.eval(state).map(|r| !r),
-            Condition::Expression(e) => e.eval(state),
-            Condition::InvertedExpression(e) => e.eval(state).map(|r| !r),
+            Condition::Function(f, span) => f.eval_with_observer(state, span, observer),
+            Condition::InvertedFunction(f, span) => {
+                f.eval_with_observer(state, span, observer).map(|r| !r)
+            }
+            Condition::Expression(e) => e.eval_with_observer(state, observer),
+            Condition::InvertedExpression(e) => e.eval_with_observer(state, observer).map(|r| !r),
         }
     }
 
-    fn parse(input: &str) -> ParsingResult<Condition> {
+    fn parse<'a>(input: &'a str, options: &ParseOptions) -> ParsingResult<'a, Condition> {
+        Condition::parse_nested(input, options, 0)
+    }
+
+    fn parse_nested<'a>(
+        input: &'a str,
+        options: &ParseOptions,
+        depth: usize,
+    ) -> ParsingResult<'a, Condition> {
         alt((
-            map(Function::parse, Condition::Function),
             map(
-                preceded(map_err(whitespace(tag("not"))), Function::parse),
-                Condition::InvertedFunction,
+                |i| parse_spanned_function(i, options),
+                |(f, span)| Condition::Function(f, span),
+            ),
+            map(
+                preceded(whitespace_token("not"), |i| {
+                    parse_spanned_function(i, options)
+                }),
+                |(f, span)| Condition::InvertedFunction(f, span),
             ),
             map(
                 delimited(
-                    map_err(whitespace(tag("("))),
-                    parse_expression,
-                    map_err(whitespace(tag(")"))),
+                    whitespace_token("("),
+                    |i| parse_parenthesised_expression(i, options, depth),
+                    whitespace_token(")"),
                 ),
                 Condition::Expression,
             ),
             map(
                 delimited(
-                    map_err(preceded(whitespace(tag("not")), whitespace(tag("(")))),
-                    parse_expression,
-                    map_err(whitespace(tag(")"))),
+                    preceded(whitespace_token("not"), whitespace_token("(")),
+                    |i| parse_parenthesised_expression(i, options, depth),
+                    whitespace_token(")"),
                 ),
                 Condition::InvertedExpression,
             ),
+            map(|i| parse_alias_reference(i, options), Condition::Expression),
+            map(
+                preceded(whitespace_token("not"), |i| {
+                    parse_alias_reference(i, options)
+                }),
+                Condition::InvertedExpression,
+            ),
         ))
         .parse(input)
     }
 }
 
+impl Condition {
+    fn map_spans(&mut self, f: &dyn Fn(usize) -> usize) {
+        match self {
+            Condition::Function(_, span) | Condition::InvertedFunction(_, span) => {
+                *span = f(span.start)..f(span.end);
+            }
+            Condition::Expression(e) | Condition::InvertedExpression(e) => e.map_spans(f),
+        }
+    }
+
+    fn set_spans(&mut self, new_span: &Range<usize>) {
+        match self {
+            Condition::Function(_, span) | Condition::InvertedFunction(_, span) => {
+                span.clone_from(new_span);
+            }
+            Condition::Expression(e) | Condition::InvertedExpression(e) => e.set_spans(new_span),
+        }
+    }
+}
+
+/// Parse a function, also returning its span. As the input is always a suffix
+/// of the whole condition string, the span's offsets are counted back from the
+/// end of the string, and get converted once the whole string has been parsed.
+fn parse_spanned_function<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> ParsingResult<'a, (Function, Range<usize>)> {
+    let (remaining_input, function) = Function::parse(input, options)?;
+
+    Ok((
+        remaining_input,
+        (function, input.len()..remaining_input.len()),
+    ))
+}
+
+/// Parse an `@name` alias reference, returning a copy of the alias's
+/// expression with all its spans set to the span of the reference.
+fn parse_alias_reference<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> ParsingResult<'a, Expression> {
+    let (remaining_input, name) =
+        preceded(token("@"), map_err(take_while1(alias::is_alias_name_char))).parse(input)?;
+
+    let Some(expression) = options.alias(name) else {
+        return Err(nom::Err::Failure(
+            ParsingErrorKind::UnknownAlias(name.to_owned()).at(input),
+        ));
+    };
+
+    let mut expression = expression.clone();
+    expression.set_spans(&(input.len()..remaining_input.len()));
+
+    Ok((remaining_input, expression))
+}
+
 impl fmt::Display for Condition {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Function(function) => write!(f, "{function}"),
-            Self::InvertedFunction(function) => write!(f, "not {function}"),
+            Self::Function(function, _) => write!(f, "{function}"),
+            Self::InvertedFunction(function, _) => write!(f, "not {function}"),
             Self::Expression(e) => write!(f, "({e})"),
             Self::InvertedExpression(e) => write!(f, "not ({e})"),
         }
@@ -397,6 +1714,27 @@ fn map_err<'a, O>(
     move |i| parser.parse(i).map_err(nom::Err::convert)
 }
 
+/// Match the given token, recording it as expected if the input doesn't start
+/// with it.
+fn token<'a>(token: &'static str) -> impl FnMut(&'a str) -> ParsingResult<'a, &'a str> {
+    move |i| {
+        tag(token)
+            .parse(i)
+            .map_err(|e: nom::Err<nom::error::Error<&'a str>>| {
+                e.map(|e| ParsingError::missing(e.input, token))
+            })
+    }
+}
+
+fn whitespace_token<'a>(token: &'static str) -> impl FnMut(&'a str) -> ParsingResult<'a, &'a str> {
+    let mut parser = delimited(
+        map_err(multispace0),
+        self::token(token),
+        map_err(multispace0),
+    );
+    move |i| parser.parse(i)
+}
+
 fn whitespace<'a, O>(
     parser: impl Fn(&'a str) -> IResult<&'a str, O>,
 ) -> impl Parser<&'a str, Output = O, Error = nom::error::Error<&'a str>> {
@@ -405,86 +1743,581 @@ fn whitespace<'a, O>(
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "eval")]
     use crate::function::ComparisonOperator;
 
-    use super::*;
+    use super::*;
+
+    #[cfg(feature = "eval")]
+    use std::fs::create_dir_all;
+    // PathBuf is only imported by the parent module for evaluation.
+    #[cfg(not(feature = "eval"))]
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    #[cfg(feature = "eval")]
+    fn state<T: Into<PathBuf>>(data_path: T) -> State {
+        let data_path = data_path.into();
+        if !data_path.exists() {
+            create_dir_all(&data_path).unwrap();
+        }
+
+        State {
+            game_type: GameType::Oblivion,
+            data_path,
+            additional_data_paths: Vec::default(),
+            active_plugins: HashMap::new(),
+            crc_cache: Cache::default(),
+            sha256_cache: Cache::default(),
+            record_count_cache: Cache::default(),
+            plugin_cache: Cache::default(),
+            archive_index_cache: Cache::default(),
+            plugin_versions: HashMap::default(),
+            condition_cache: Cache::default(),
+            wine_path_mapper: None,
+            resolved_path_cache: Cache::default(),
+            directory_cache: Cache::default(),
+            metadata_cache: Cache::default(),
+            missing_path_cache: Cache::default(),
+            path_traversal_allowlist: None,
+            max_directory_entries: None,
+            memory_map_threshold: Some(DEFAULT_MEMORY_MAP_THRESHOLD),
+            #[cfg(feature = "parallel")]
+            parallel_crc_threshold: Some(DEFAULT_PARALLEL_CRC_THRESHOLD),
+            deterministic_ordering: false,
+            filesystem_access: true,
+            parsed_plugins: HashMap::default(),
+            shared_caches: Arc::default(),
+            variables: HashMap::default(),
+            retry_policy: None,
+            file_system: Arc::new(StdFileSystem),
+            virtual_files: VirtualFiles::default(),
+            strict: false,
+            cancellation_token: None,
+            deadline: None,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn game_type_supports_light_plugins_should_be_true_for_tes5se_tes5vr_fo4_fo4vr_and_starfield() {
+        assert!(GameType::SkyrimSE.supports_light_plugins());
+        assert!(GameType::SkyrimVR.supports_light_plugins());
+        assert!(GameType::Fallout4.supports_light_plugins());
+        assert!(GameType::Fallout4VR.supports_light_plugins());
+        assert!(GameType::Starfield.supports_light_plugins());
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn game_type_supports_light_master_should_be_false_for_tes3_to_5_fo3_and_fonv() {
+        assert!(!GameType::OpenMW.supports_light_plugins());
+        assert!(!GameType::Morrowind.supports_light_plugins());
+        assert!(!GameType::Oblivion.supports_light_plugins());
+        assert!(!GameType::Skyrim.supports_light_plugins());
+        assert!(!GameType::Fallout3.supports_light_plugins());
+        assert!(!GameType::FalloutNV.supports_light_plugins());
+    }
+
+    #[test]
+    fn expression_from_str_should_error_with_input_on_incomplete_input() {
+        let error = Expression::from_str("file(\"Carg").unwrap_err();
+
+        assert_eq!(
+            "The parser did not consume the following input: \"file(\"Carg\"",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn expression_parse_all_should_return_a_result_for_each_input_in_order() {
+        let results = Expression::parse_all(
+            [
+                "many(\"a/.*\")",
+                "file(\"b\") xor",
+                "many(\"a/.*\") and file(\"c\")",
+            ],
+            &ParseOptions::default(),
+        );
+
+        assert_eq!(3, results.len());
+        assert_eq!("many(\"a/.*\")", results[0].as_ref().unwrap().to_string());
+        assert!(results[1].is_err());
+        assert_eq!(
+            "many(\"a/.*\") and file(\"c\")",
+            results[2].as_ref().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn expression_parse_with_options_should_add_regexes_to_a_given_interner() {
+        let interner = RegexInterner::new();
+        let options = ParseOptions::new().with_regex_interner(interner.clone());
+
+        Expression::parse_with_options("many(\"a/.*\") or file(\"b\")", &options).unwrap();
+        Expression::parse_with_options("many(\"c/.*\") and not many(\"d/.*\")", &options).unwrap();
+
+        assert_eq!(1, interner.len());
+    }
+
+    #[test]
+    fn expression_parse_all_should_apply_the_given_options() {
+        let results = Expression::parse_all(
+            vec!["file(\"a\")".to_owned(), "many(\"abc\")".to_owned()],
+            &ParseOptions::new().with_max_regex_length(Some(2)),
+        );
+
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            results[1],
+            Err(Error::ParsingError(_, ParsingErrorKind::RegexTooLong(2)))
+        ));
+    }
+
+    #[test]
+    fn expression_parse_with_warnings_should_find_non_canonical_functions() {
+        let (expression, warnings) = Expression::parse_with_warnings(
+            "File(\"a.esp\") or (is_master( \"b.esp\") and not active(\"c.esp\"))",
+            &ParseOptions::new().with_lenient_syntax(true),
+        )
+        .unwrap();
+
+        assert_eq!(
+            "file(\"a.esp\") or (is_master(\"b.esp\") and not active(\"c.esp\"))",
+            expression.to_string()
+        );
+        assert_eq!(
+            vec![("file(\"a.esp\")", 0..13), ("is_master(\"b.esp\")", 18..37)],
+            warnings
+                .iter()
+                .map(|w| (w.clause(), w.span()))
+                .collect::<Vec<_>>()
+        );
+        assert!(warnings
+            .iter()
+            .all(|w| w.kind() == LintKind::NonCanonicalSyntax));
+    }
+
+    #[test]
+    fn expression_parse_with_warnings_should_not_warn_about_canonical_functions() {
+        let (_, warnings) = Expression::parse_with_warnings(
+            "file(\"a.esp\") and version(\"b.esp\",\"1.0\",>=)",
+            &ParseOptions::new().with_lenient_syntax(true),
+        )
+        .unwrap();
+
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn expression_parse_with_diagnostics_should_parse_a_valid_condition() {
+        let expression = Expression::parse_with_diagnostics(
+            "file(\"a\") and not many(\"b/.*\")",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!("file(\"a\") and not many(\"b/.*\")", expression.to_string());
+    }
+
+    #[test]
+    fn expression_parse_with_diagnostics_should_locate_an_unclosed_function() {
+        let error = Expression::parse_with_diagnostics(
+            "file(\"a\") and file(\"Carg",
+            &ParseOptions::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(24..24, error.span());
+        assert_eq!("", error.fragment());
+        assert!(error.expected().contains(&"\")"));
+    }
+
+    #[test]
+    fn expression_parse_with_diagnostics_should_expect_a_separator_after_a_condition() {
+        let error = Expression::parse_with_diagnostics(
+            "file(\"a\") xor file(\"b\")",
+            &ParseOptions::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(10..13, error.span());
+        assert_eq!("xor", error.fragment());
+        assert_eq!(&["and", "or"], error.expected());
+        assert_eq!(
+            "Unexpected \"xor\" at bytes 10..13, expected one of \"and\", \"or\"",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn expression_parse_with_diagnostics_should_expect_a_function_at_the_start() {
+        let error = Expression::parse_with_diagnostics("flie(\"a\")", &ParseOptions::default())
+            .unwrap_err();
+
+        assert_eq!(0..9, error.span());
+        assert_eq!("flie(\"a\")", error.fragment());
+        assert!(error.expected().contains(&"file(\""));
+        assert!(error.expected().contains(&"not"));
+        assert!(error.expected().contains(&"("));
+    }
+
+    #[test]
+    fn expression_parse_with_diagnostics_should_locate_an_invalid_argument() {
+        let error = Expression::parse_with_diagnostics(
+            "file(\"a\") or checksum(\"Cargo.toml\", DEADBEEFDEAD)",
+            &ParseOptions::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(36..48, error.span());
+        assert_eq!("DEADBEEFDEAD", error.fragment());
+        assert!(error.expected().is_empty());
+        assert!(matches!(error.kind(), ParsingErrorKind::InvalidCrc(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn expression_to_condition_string_should_round_trip() {
+        let inputs = [
+            "file(\"Cargo.toml\")",
+            "file(\".*\\.esp\") or many(\"meshes/.*\\.nif\", 2)",
+            "not (checksum(\"a.esp\", DEADBEEF, 1234) and not version(\"a.esp\", \"1.0\", >=))",
+            "((file(\"a\") or readable(\"b\")) and active(\"c\\.esp\"))",
+            "filename_version(\"(\\d+)\\.esp\", \"1\", ==) and count(\"a/.*\", >, 1)",
+        ];
+
+        for input in inputs {
+            let expression = Expression::from_str(input).unwrap();
+            let string = expression.to_condition_string();
+
+            assert_eq!(input, string);
+            assert_eq!(expression, Expression::from_str(&string).unwrap());
+        }
+    }
+
+    #[test]
+    fn expression_to_condition_string_should_normalise_formatting() {
+        let expression =
+            Expression::from_str("file(\"./.*\\.esp\")  and\tnot   checksum(\"a.esp\",deadbeef)")
+                .unwrap();
+
+        assert_eq!(
+            "file(\".*\\.esp\") and not checksum(\"a.esp\", DEADBEEF)",
+            expression.to_condition_string()
+        );
+    }
+
+    #[test]
+    fn expression_parse_with_recovery_should_parse_a_valid_condition() {
+        let expression = Expression::parse_with_recovery(
+            "file(\"a\") or not (file(\"b\") and file(\"c\"))",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            "file(\"a\") or not (file(\"b\") and file(\"c\"))",
+            expression.to_string()
+        );
+    }
+
+    #[test]
+    fn expression_parse_with_recovery_should_return_an_error_for_each_invalid_condition() {
+        let input = "file(\"a\") xor file(\"b\") or flie(\"c\") and not (file(\"d\") and x)";
+        let errors = Expression::parse_with_recovery(input, &ParseOptions::default()).unwrap_err();
+
+        let fragments: Vec<_> = errors.iter().map(ParseError::fragment).collect();
+        assert_eq!(vec!["xor", "flie(\"c\")", "and"], fragments);
+        assert_eq!(10..13, errors[0].span());
+        assert_eq!(27..36, errors[1].span());
+        assert_eq!(56..59, errors[2].span());
+    }
+
+    #[test]
+    fn expression_parse_with_recovery_should_not_resume_inside_parentheses_or_strings() {
+        let input = "(flie(\"a\") or file(\"b\")) and file(\" or \") and file(\"(\")";
+        let errors = Expression::parse_with_recovery(input, &ParseOptions::default()).unwrap_err();
+
+        assert_eq!(1, errors.len());
+        assert_eq!(1..10, errors[0].span());
+    }
+
+    #[test]
+    fn expression_parse_with_recovery_should_stop_at_the_end_of_the_input() {
+        let errors = Expression::parse_with_recovery("file(\"a\") and", &ParseOptions::default())
+            .unwrap_err();
+
+        assert_eq!(1, errors.len());
+        assert_eq!(13..13, errors[0].span());
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn expression_from_str_should_error_with_input_on_invalid_regex() {
+        let error = Expression::from_str("file(\"Carg\\.*(\")").unwrap_err();
+
+        assert_eq!(
+            "An error was encountered while parsing the expression \"Carg\\.*(\": regex parse error:\n    ^Carg\\.*($\n            ^\nerror: unclosed group",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn expression_from_str_should_error_with_input_on_invalid_crc() {
+        let error = Expression::from_str("checksum(\"Cargo.toml\", DEADBEEFDEAD)").unwrap_err();
+
+        assert_eq!(
+            "An error was encountered while parsing the expression \"DEADBEEFDEAD\": number too large to fit in target type",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn expression_from_str_should_error_with_input_on_directory_regex() {
+        let error = Expression::from_str("file(\"targ.*et/\")").unwrap_err();
+
+        assert_eq!(
+            "An error was encountered while parsing the expression \"targ.*et/\\\")\": \"targ.*et/\" ends in a directory separator",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn expression_parse_with_options_should_error_if_a_regex_is_too_long() {
+        let options = ParseOptions::new().with_max_regex_length(Some(5));
+        let error = Expression::parse_with_options(
+            "file(\"Cargo.toml\") or active(\"Blank\\.esm\")",
+            &options,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            "An error was encountered while parsing the expression \"Blank\\.esm\": The regex is longer than the limit of 5 bytes",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn expression_parse_with_options_should_error_if_the_condition_is_too_long() {
+        let options = ParseOptions::new().with_max_expression_length(Some(18));
+
+        assert!(Expression::parse_with_options("file(\"Cargo.toml\")", &options).is_ok());
+
+        let error = Expression::parse_with_options("file(\"Cargo.toml\") ", &options).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::ParsingError(_, ParsingErrorKind::ExpressionTooLong(18))
+        ));
+        assert_eq!(
+            "An error was encountered while parsing the expression \"\": The condition is longer than the limit of 18 bytes",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn expression_parse_with_options_should_error_if_expressions_are_nested_too_deeply() {
+        let options = ParseOptions::new().with_max_nesting_depth(Some(2));
+
+        assert!(Expression::parse_with_options(
+            "(file(\"a\") or not (file(\"b\"))) and (file(\"c\"))",
+            &options
+        )
+        .is_ok());
+
+        let error = Expression::parse_with_options(
+            "file(\"a\") or (file(\"b\") and not ((file(\"c\"))))",
+            &options,
+        )
+        .unwrap_err();
+        assert_eq!(
+            "An error was encountered while parsing the expression \"file(\\\"c\\\"))))\": The condition nests parenthesised expressions deeper than the limit of 2",
+            error.to_string()
+        );
+    }
 
-    use std::fs::create_dir_all;
-    use std::str::FromStr;
+    #[test]
+    fn expression_parse_with_options_should_stop_recursing_at_the_nesting_limit() {
+        let input = format!("{}file(\"a\"){}", "(".repeat(100_000), ")".repeat(100_000));
+        let options = ParseOptions::new().with_max_nesting_depth(Some(10));
 
-    fn state<T: Into<PathBuf>>(data_path: T) -> State {
-        let data_path = data_path.into();
-        if !data_path.exists() {
-            create_dir_all(&data_path).unwrap();
-        }
+        let error = Expression::parse_with_options(&input, &options).unwrap_err();
 
-        State {
-            game_type: GameType::Oblivion,
-            data_path,
-            additional_data_paths: Vec::default(),
-            active_plugins: HashSet::new(),
-            crc_cache: RwLock::default(),
-            plugin_versions: HashMap::default(),
-            condition_cache: RwLock::default(),
-        }
+        assert!(matches!(
+            error,
+            Error::ParsingError(_, ParsingErrorKind::ExpressionTooDeep(10))
+        ));
     }
 
     #[test]
-    fn game_type_supports_light_plugins_should_be_true_for_tes5se_tes5vr_fo4_fo4vr_and_starfield() {
-        assert!(GameType::SkyrimSE.supports_light_plugins());
-        assert!(GameType::SkyrimVR.supports_light_plugins());
-        assert!(GameType::Fallout4.supports_light_plugins());
-        assert!(GameType::Fallout4VR.supports_light_plugins());
-        assert!(GameType::Starfield.supports_light_plugins());
+    fn expression_parse_with_diagnostics_should_locate_the_nesting_limit() {
+        let options = ParseOptions::new().with_max_nesting_depth(Some(0));
+        let error = Expression::parse_with_diagnostics("file(\"a\") or (file(\"b\"))", &options)
+            .unwrap_err();
+
+        assert_eq!(14..24, error.span());
+        assert!(matches!(
+            error.kind(),
+            ParsingErrorKind::ExpressionTooDeep(0)
+        ));
     }
 
     #[test]
-    fn game_type_supports_light_master_should_be_false_for_tes3_to_5_fo3_and_fonv() {
-        assert!(!GameType::OpenMW.supports_light_plugins());
-        assert!(!GameType::Morrowind.supports_light_plugins());
-        assert!(!GameType::Oblivion.supports_light_plugins());
-        assert!(!GameType::Skyrim.supports_light_plugins());
-        assert!(!GameType::Fallout3.supports_light_plugins());
-        assert!(!GameType::FalloutNV.supports_light_plugins());
+    fn expression_parse_with_recovery_should_only_report_a_condition_that_is_too_long() {
+        let options = ParseOptions::new().with_max_expression_length(Some(10));
+        let errors =
+            Expression::parse_with_recovery("file(\"a\") or flie(\"b\")", &options).unwrap_err();
+
+        assert_eq!(1, errors.len());
+        assert!(matches!(
+            errors[0].kind(),
+            ParsingErrorKind::ExpressionTooLong(10)
+        ));
     }
 
     #[test]
-    fn expression_from_str_should_error_with_input_on_incomplete_input() {
-        let error = Expression::from_str("file(\"Carg").unwrap_err();
+    #[cfg(feature = "regex")]
+    fn expression_parse_with_options_should_error_if_a_regex_uses_a_disallowed_feature() {
+        let options =
+            ParseOptions::new().with_disallowed_regex_features(&[RegexFeature::NestedRepetition]);
+        let error =
+            Expression::parse_with_options("many(\"(Blank.*)+\\.esp\")", &options).unwrap_err();
 
         assert_eq!(
-            "The parser did not consume the following input: \"file(\"Carg\"",
+            "An error was encountered while parsing the expression \"(Blank.*)+\\.esp\": The regex uses nested repetitions, which are not allowed",
             error.to_string()
         );
     }
 
+    fn alias_options() -> ParseOptions {
+        let mut aliases = ConditionAliases::new();
+        aliases
+            .define(
+                "has_cargo",
+                "file(\"Cargo.toml\") and file(\"src/lib.rs\")",
+                &ParseOptions::default(),
+            )
+            .unwrap();
+
+        ParseOptions::new().with_aliases(aliases)
+    }
+
     #[test]
-    fn expression_from_str_should_error_with_input_on_invalid_regex() {
-        let error = Expression::from_str("file(\"Carg\\.*(\")").unwrap_err();
+    #[cfg(feature = "eval")]
+    fn expression_parse_with_options_should_expand_alias_references() {
+        let expression = Expression::parse_with_options(
+            "@has_cargo or not @has_cargo and file(\"a\")",
+            &alias_options(),
+        )
+        .unwrap();
 
         assert_eq!(
-            "An error was encountered while parsing the expression \"Carg\\.*(\": regex parse error:\n    ^Carg\\.*($\n            ^\nerror: unclosed group",
-            error.to_string()
+            Expression::from_str(
+                "(file(\"Cargo.toml\") and file(\"src/lib.rs\")) or not (file(\"Cargo.toml\") and file(\"src/lib.rs\")) and file(\"a\")"
+            )
+            .unwrap(),
+            expression
         );
+        assert!(expression.eval(&state(".")).unwrap());
     }
 
     #[test]
-    fn expression_from_str_should_error_with_input_on_invalid_crc() {
-        let error = Expression::from_str("checksum(\"Cargo.toml\", DEADBEEFDEAD)").unwrap_err();
+    fn expression_parse_with_options_should_error_if_an_alias_is_not_defined() {
+        let error = Expression::parse_with_options("file(\"a\") or @missing", &alias_options())
+            .unwrap_err();
 
         assert_eq!(
-            "An error was encountered while parsing the expression \"DEADBEEFDEAD\": number too large to fit in target type",
+            "An error was encountered while parsing the expression \"@missing\": The alias \"@missing\" is not defined",
             error.to_string()
         );
     }
 
     #[test]
-    fn expression_from_str_should_error_with_input_on_directory_regex() {
-        let error = Expression::from_str("file(\"targ.*et/\")").unwrap_err();
+    #[cfg(feature = "eval")]
+    fn expression_eval_with_report_should_use_the_alias_reference_span_for_its_functions() {
+        let input = "file(\"missing\") or  @has_cargo";
+        let expression = Expression::parse_with_options(input, &alias_options()).unwrap();
+
+        let report = expression.eval_with_report(&state(".")).unwrap();
+
+        let spans: Vec<&str> = report
+            .function_evaluations()
+            .iter()
+            .map(|e| input.get(e.span()).unwrap())
+            .collect();
+        assert_eq!(vec!["file(\"missing\")", "@has_cargo", "@has_cargo"], spans);
+    }
+
+    fn variable_options() -> ParseOptions {
+        ParseOptions::new().with_variables(&["PROFILE", "GAME_VERSION"])
+    }
+
+    #[test]
+    fn expression_parse_with_options_should_error_if_a_variable_is_not_defined() {
+        let error = Expression::parse_with_options(
+            "file(\"profiles/${PROFILE}/${OTHER}.txt\")",
+            &variable_options(),
+        )
+        .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .ends_with(": The variable \"${OTHER}\" is not defined"));
+        assert!(Expression::from_str("file(\"${PROFILE}\")").is_err());
+        assert!(
+            Expression::parse_with_options("file(\"${PROFILE\")", &variable_options()).is_err()
+        );
+    }
+
+    #[test]
+    fn expression_parse_with_options_should_keep_variable_references() {
+        let input = "version(\"${PROFILE}/a.exe\", \"${GAME_VERSION}\", >=)";
+        let expression = Expression::parse_with_options(input, &variable_options()).unwrap();
+
+        assert_eq!(input, expression.to_string());
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn expression_eval_should_substitute_variable_values() {
+        let expression =
+            Expression::parse_with_options("file(\"${PROFILE}/lib.rs\")", &variable_options())
+                .unwrap();
+        let mut state = state(".");
+
+        state.set_variables(&[("PROFILE", "src")]);
+        assert!(expression.eval(&state).unwrap());
+
+        state.set_variables(&[("PROFILE", "missing")]);
+        assert!(!expression.eval(&state).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn expression_eval_should_check_substituted_paths_against_the_allowlist() {
+        let expression =
+            Expression::parse_with_options("file(\"${PROFILE}/Cargo.toml\")", &variable_options())
+                .unwrap();
+        let mut state = state(".");
+        state.set_path_traversal_allowlist(Some(Vec::new()));
+        state.set_variables(&[("PROFILE", "..")]);
+
+        assert!(matches!(
+            expression.eval(&state),
+            Err(Error::PathTraversal(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn expression_eval_should_error_if_a_variable_has_no_value() {
+        let expression =
+            Expression::parse_with_options("file(\"${PROFILE}/lib.rs\")", &variable_options())
+                .unwrap();
 
         assert_eq!(
-            "An error was encountered while parsing the expression \"targ.*et/\\\")\": \"targ.*et/\" ends in a directory separator",
-            error.to_string()
+            "The variable \"${PROFILE}\" has no value",
+            expression.eval(&state(".")).unwrap_err().to_string()
         );
     }
 
@@ -519,6 +2352,70 @@ mod tests {
     }
 
     #[test]
+    fn expression_parse_should_ignore_newlines_and_comments() {
+        let expression = Expression::from_str(
+            "# Either plugin will do.\nfile(\"a.esp\") # The original.\n    or (\n        file(\"b.esp\")\n        and not active(\"c.esp\") # Fixed upstream.\n    )\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            "file(\"a.esp\") or (file(\"b.esp\") and not active(\"c.esp\"))",
+            expression.to_string()
+        );
+    }
+
+    #[test]
+    fn expression_parse_should_not_treat_a_hash_in_a_string_as_a_comment() {
+        let expression = Expression::from_str("file(\"#a.esp\") # or file(\"b\")").unwrap();
+
+        assert_eq!("file(\"#a.esp\")", expression.to_string());
+    }
+
+    #[test]
+    fn expression_parse_should_not_end_a_string_at_an_escaped_double_quote() {
+        let expression = Expression::from_str("file(\"a\\\" # b\") # or file(\"c\")").unwrap();
+
+        assert_eq!("file(\"a\\\" # b\")", expression.to_string());
+    }
+
+    #[test]
+    fn expression_parse_should_end_a_string_at_a_quote_after_a_trailing_backslash() {
+        let expression =
+            Expression::from_str("checksum(\"a\\b\\\", DEADBEEF) # or file(\"c\")").unwrap();
+
+        assert_eq!("checksum(\"a\\b\\\\\", DEADBEEF)", expression.to_string());
+    }
+
+    #[test]
+    fn expression_parse_with_diagnostics_should_give_spans_that_include_comments() {
+        let error = Expression::parse_with_diagnostics(
+            "file(\"a\") # caf\u{e9}\nxor file(\"b\")",
+            &ParseOptions::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(18..21, error.span());
+        assert_eq!("xor", error.fragment());
+    }
+
+    #[test]
+    fn expression_parse_should_give_function_spans_that_include_comments() {
+        let expression =
+            Expression::from_str("# \u{e9}\nfile(\"a\") and # b\n active(\"c\")").unwrap();
+
+        let spans: Vec<_> = expression.compound_conditions()[0]
+            .clauses()
+            .filter_map(|c| match c {
+                Clause::Function(f) => Some(f.span()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(vec![5..14, 24..35], spans);
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
     fn expression_parsing_should_ignore_whitespace_between_function_arguments() {
         let is_ok = |s: &str| Expression::from_str(s).is_ok();
 
@@ -552,8 +2449,8 @@ mod tests {
             [CompoundCondition(c1), CompoundCondition(c2)] => {
                 match (c1.as_slice(), c2.as_slice()) {
                     (
-                        [Condition::Function(_)],
-                        [Condition::Function(_), Condition::Function(_)],
+                        [Condition::Function(_, _)],
+                        [Condition::Function(_, _), Condition::Function(_, _)],
                     ) => {}
                     v => panic!("Expected an expression with two compound conditions, got {v:?}",),
                 }
@@ -564,10 +2461,12 @@ mod tests {
 
     #[test]
     fn compound_condition_parse_should_handle_a_single_condition() {
-        let result = CompoundCondition::parse("file(\"Cargo.toml\")").unwrap().1;
+        let result = CompoundCondition::parse("file(\"Cargo.toml\")", &ParseOptions::default())
+            .unwrap()
+            .1;
 
         match result.0.as_slice() {
-            [Condition::Function(Function::FilePath(f))] => {
+            [Condition::Function(Function::FilePath(f), _)] => {
                 assert_eq!(&PathBuf::from("Cargo.toml"), f);
             }
             v => panic!("Expected an expression with two compound conditions, got {v:?}",),
@@ -576,12 +2475,15 @@ mod tests {
 
     #[test]
     fn compound_condition_parse_should_handle_multiple_conditions() {
-        let result = CompoundCondition::parse("file(\"Cargo.toml\") and file(\"README.md\")")
-            .unwrap()
-            .1;
+        let result = CompoundCondition::parse(
+            "file(\"Cargo.toml\") and file(\"README.md\")",
+            &ParseOptions::default(),
+        )
+        .unwrap()
+        .1;
 
         match result.0.as_slice() {
-            [Condition::Function(Function::FilePath(f1)), Condition::Function(Function::FilePath(f2))] =>
+            [Condition::Function(Function::FilePath(f1), _), Condition::Function(Function::FilePath(f2), _)] =>
             {
                 assert_eq!(&PathBuf::from("Cargo.toml"), f1);
                 assert_eq!(&PathBuf::from("README.md"), f2);
@@ -592,10 +2494,12 @@ mod tests {
 
     #[test]
     fn condition_parse_should_handle_a_function() {
-        let result = Condition::parse("file(\"Cargo.toml\")").unwrap().1;
+        let result = Condition::parse("file(\"Cargo.toml\")", &ParseOptions::default())
+            .unwrap()
+            .1;
 
         match result {
-            Condition::Function(Function::FilePath(f)) => {
+            Condition::Function(Function::FilePath(f), _) => {
                 assert_eq!(PathBuf::from("Cargo.toml"), f);
             }
             v => panic!("Expected an expression with two compound conditions, got {v:?}",),
@@ -604,10 +2508,12 @@ mod tests {
 
     #[test]
     fn condition_parse_should_handle_an_inverted_function() {
-        let result = Condition::parse("not file(\"Cargo.toml\")").unwrap().1;
+        let result = Condition::parse("not file(\"Cargo.toml\")", &ParseOptions::default())
+            .unwrap()
+            .1;
 
         match result {
-            Condition::InvertedFunction(Function::FilePath(f)) => {
+            Condition::InvertedFunction(Function::FilePath(f), _) => {
                 assert_eq!(PathBuf::from("Cargo.toml"), f);
             }
             v => panic!("Expected an expression with two compound conditions, got {v:?}",),
@@ -616,7 +2522,9 @@ mod tests {
 
     #[test]
     fn condition_parse_should_handle_an_expression_in_parentheses() {
-        let result = Condition::parse("(not file(\"Cargo.toml\"))").unwrap().1;
+        let result = Condition::parse("(not file(\"Cargo.toml\"))", &ParseOptions::default())
+            .unwrap()
+            .1;
 
         match result {
             Condition::Expression(_) => {}
@@ -626,7 +2534,9 @@ mod tests {
 
     #[test]
     fn condition_parse_should_handle_an_expression_in_parentheses_with_whitespace() {
-        let result = Condition::parse("( not file(\"Cargo.toml\") )").unwrap().1;
+        let result = Condition::parse("( not file(\"Cargo.toml\") )", &ParseOptions::default())
+            .unwrap()
+            .1;
 
         match result {
             Condition::Expression(_) => {}
@@ -636,7 +2546,9 @@ mod tests {
 
     #[test]
     fn condition_parse_should_handle_an_inverted_expression_in_parentheses() {
-        let result = Condition::parse("not(not file(\"Cargo.toml\"))").unwrap().1;
+        let result = Condition::parse("not(not file(\"Cargo.toml\"))", &ParseOptions::default())
+            .unwrap()
+            .1;
 
         match result {
             Condition::InvertedExpression(_) => {}
@@ -646,7 +2558,7 @@ mod tests {
 
     #[test]
     fn condition_parse_should_handle_an_inverted_expression_in_parentheses_with_whitespace() {
-        let result = Condition::parse("not ( not file(\"Cargo.toml\") )")
+        let result = Condition::parse("not ( not file(\"Cargo.toml\") )", &ParseOptions::default())
             .unwrap()
             .1;
 
@@ -657,57 +2569,62 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "eval")]
     fn condition_eval_should_return_function_eval_for_a_function_condition() {
         let state = state(".");
 
-        let condition = Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml")));
+        let condition = Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml")), 0..0);
 
-        assert!(condition.eval(&state).unwrap());
+        assert!(condition.eval(&state, &mut ()).unwrap());
 
-        let condition = Condition::Function(Function::FilePath(PathBuf::from("missing")));
+        let condition = Condition::Function(Function::FilePath(PathBuf::from("missing")), 0..0);
 
-        assert!(!condition.eval(&state).unwrap());
+        assert!(!condition.eval(&state, &mut ()).unwrap());
     }
 
     #[test]
+    #[cfg(feature = "eval")]
     fn condition_eval_should_return_expression_eval_for_an_expression_condition() {
         let state = state(".");
 
         let condition = Condition::Expression(Expression(vec![CompoundCondition(vec![
-            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml"))),
+            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml")), 0..0),
         ])]));
 
-        assert!(condition.eval(&state).unwrap());
+        assert!(condition.eval(&state, &mut ()).unwrap());
     }
 
     #[test]
+    #[cfg(feature = "eval")]
     fn condition_eval_should_return_inverse_of_function_eval_for_a_not_function_condition() {
         let state = state(".");
 
         let condition =
-            Condition::InvertedFunction(Function::FilePath(PathBuf::from("Cargo.toml")));
+            Condition::InvertedFunction(Function::FilePath(PathBuf::from("Cargo.toml")), 0..0);
 
-        assert!(!condition.eval(&state).unwrap());
+        assert!(!condition.eval(&state, &mut ()).unwrap());
 
-        let condition = Condition::InvertedFunction(Function::FilePath(PathBuf::from("missing")));
+        let condition =
+            Condition::InvertedFunction(Function::FilePath(PathBuf::from("missing")), 0..0);
 
-        assert!(condition.eval(&state).unwrap());
+        assert!(condition.eval(&state, &mut ()).unwrap());
     }
 
     #[test]
+    #[cfg(feature = "eval")]
     fn condition_eval_should_return_inverse_of_expression_eval_for_a_not_expression_condition() {
         let state = state(".");
 
         let condition = Condition::InvertedExpression(Expression(vec![CompoundCondition(vec![
-            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml"))),
+            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml")), 0..0),
         ])]));
 
-        assert!(!condition.eval(&state).unwrap());
+        assert!(!condition.eval(&state, &mut ()).unwrap());
     }
 
     #[test]
     fn condition_fmt_should_format_function_correctly() {
-        let condition = Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml")));
+        let condition = Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml")), 0..0);
 
         assert_eq!("file(\"Cargo.toml\")", &format!("{condition}"));
     }
@@ -715,7 +2632,7 @@ mod tests {
     #[test]
     fn condition_fmt_should_format_inverted_function_correctly() {
         let condition =
-            Condition::InvertedFunction(Function::FilePath(PathBuf::from("Cargo.toml")));
+            Condition::InvertedFunction(Function::FilePath(PathBuf::from("Cargo.toml")), 0..0);
 
         assert_eq!("not file(\"Cargo.toml\")", &format!("{condition}"));
     }
@@ -723,7 +2640,7 @@ mod tests {
     #[test]
     fn condition_fmt_should_format_expression_correctly() {
         let condition = Condition::Expression(Expression(vec![CompoundCondition(vec![
-            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml"))),
+            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml")), 0..0),
         ])]));
 
         assert_eq!("(file(\"Cargo.toml\"))", &format!("{condition}"));
@@ -732,59 +2649,65 @@ mod tests {
     #[test]
     fn condition_fmt_should_format_inverted_expression_correctly() {
         let condition = Condition::InvertedExpression(Expression(vec![CompoundCondition(vec![
-            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml"))),
+            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml")), 0..0),
         ])]));
 
         assert_eq!("not (file(\"Cargo.toml\"))", &format!("{condition}"));
     }
 
     #[test]
+    #[cfg(feature = "eval")]
     fn compound_condition_eval_should_be_true_if_all_conditions_are_true() {
         let state = state(".");
 
         let compound_condition = CompoundCondition(vec![
-            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml"))),
-            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml"))),
+            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml")), 0..0),
+            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml")), 0..0),
         ]);
 
-        assert!(compound_condition.eval(&state).unwrap());
+        assert!(compound_condition.eval(&state, &mut ()).unwrap());
     }
 
     #[test]
+    #[cfg(feature = "eval")]
     fn compound_condition_eval_should_be_false_if_any_condition_is_false() {
         let state = state(".");
 
         let compound_condition = CompoundCondition(vec![
-            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml"))),
-            Condition::Function(Function::FilePath(PathBuf::from("missing"))),
+            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml")), 0..0),
+            Condition::Function(Function::FilePath(PathBuf::from("missing")), 0..0),
         ]);
 
-        assert!(!compound_condition.eval(&state).unwrap());
+        assert!(!compound_condition.eval(&state, &mut ()).unwrap());
     }
 
     #[test]
+    #[cfg(feature = "eval")]
     fn compound_condition_eval_should_return_false_on_first_false_condition() {
         let state = state(".");
         let path = "Cargo.toml";
 
         // If the second function is evaluated, it will result in an error.
         let compound_condition = CompoundCondition(vec![
-            Condition::InvertedFunction(Function::Readable(PathBuf::from(path))),
-            Condition::Function(Function::ProductVersion(
-                PathBuf::from(path),
-                "1.0.0".into(),
-                ComparisonOperator::Equal,
-            )),
+            Condition::InvertedFunction(Function::Readable(PathBuf::from(path)), 0..0),
+            Condition::Function(
+                Function::ProductVersion(
+                    PathBuf::from(path),
+                    "1.0.0".into(),
+                    ComparisonOperator::Equal,
+                ),
+                0..0,
+            ),
         ]);
 
-        assert!(!compound_condition.eval(&state).unwrap());
+        assert!(!compound_condition.eval(&state, &mut ()).unwrap());
     }
 
     #[test]
     fn compound_condition_fmt_should_format_correctly() {
         let compound_condition = CompoundCondition(vec![
-            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml"))),
-            Condition::Function(Function::FilePath(PathBuf::from("missing"))),
+            Condition::Function(Function::FilePath(PathBuf::from("Cargo.toml")), 0..0),
+            Condition::Function(Function::FilePath(PathBuf::from("missing")), 0..0),
         ]);
 
         assert_eq!(
@@ -792,52 +2715,538 @@ mod tests {
             &format!("{compound_condition}")
         );
 
-        let compound_condition = CompoundCondition(vec![Condition::Function(Function::FilePath(
-            PathBuf::from("Cargo.toml"),
-        ))]);
+        let compound_condition = CompoundCondition(vec![Condition::Function(
+            Function::FilePath(PathBuf::from("Cargo.toml")),
+            0..0,
+        )]);
 
         assert_eq!("file(\"Cargo.toml\")", &format!("{compound_condition}"));
     }
 
     #[test]
+    #[cfg(feature = "eval")]
     fn expression_eval_should_be_true_if_any_compound_condition_is_true() {
         let state = state(".");
 
         let expression = Expression(vec![
-            CompoundCondition(vec![Condition::Function(Function::FilePath(
-                PathBuf::from("Cargo.toml"),
-            ))]),
-            CompoundCondition(vec![Condition::Function(Function::FilePath(
-                PathBuf::from("missing"),
-            ))]),
+            CompoundCondition(vec![Condition::Function(
+                Function::FilePath(PathBuf::from("Cargo.toml")),
+                0..0,
+            )]),
+            CompoundCondition(vec![Condition::Function(
+                Function::FilePath(PathBuf::from("missing")),
+                0..0,
+            )]),
         ]);
         assert!(expression.eval(&state).unwrap());
     }
 
     #[test]
+    #[cfg(feature = "eval")]
     fn expression_eval_should_be_false_if_all_compound_conditions_are_false() {
         let state = state(".");
 
         let expression = Expression(vec![
-            CompoundCondition(vec![Condition::Function(Function::FilePath(
-                PathBuf::from("missing"),
-            ))]),
-            CompoundCondition(vec![Condition::Function(Function::FilePath(
-                PathBuf::from("missing"),
-            ))]),
+            CompoundCondition(vec![Condition::Function(
+                Function::FilePath(PathBuf::from("missing")),
+                0..0,
+            )]),
+            CompoundCondition(vec![Condition::Function(
+                Function::FilePath(PathBuf::from("missing")),
+                0..0,
+            )]),
         ]);
         assert!(!expression.eval(&state).unwrap());
     }
 
+    #[test]
+    #[cfg(feature = "eval")]
+    fn expression_eval_should_share_cached_results_between_threads() {
+        let state = state(".");
+        let paths = ["Cargo.toml", "README.md", "src", "missing"];
+
+        std::thread::scope(|s| {
+            for _ in 0..4_u32 {
+                s.spawn(|| {
+                    for path in paths {
+                        let expression = Expression::from_str(&format!("file(\"{path}\")"));
+                        let result = expression.unwrap().eval(&state).unwrap();
+                        assert_eq!(path != "missing", result);
+                    }
+                });
+            }
+        });
+
+        for path in paths {
+            let function = Function::FilePath(PathBuf::from(path));
+            let cached = state
+                .condition_cache
+                .get(&function)
+                .map(|(result, _)| result);
+            assert_eq!(Some(path != "missing"), cached);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn expression_eval_with_report_should_record_evaluated_functions_in_order() {
+        let state = state(".").with_active_plugins(&["Blank.esp"]);
+        let expression = Expression::from_str(
+            "not file(\"missing\") and active(\"Blank.esp\") or file(\"Cargo.toml\")",
+        )
+        .unwrap();
+
+        let report = expression.eval_with_report(&state).unwrap();
+
+        assert!(report.result());
+        let evaluations = report.function_evaluations();
+        assert_eq!(2, evaluations.len());
+        assert_eq!("file(\"missing\")", evaluations[0].function());
+        assert!(!evaluations[0].result());
+        assert_eq!(ResultSource::FreshIo, evaluations[0].source());
+        assert_eq!("active(\"Blank.esp\")", evaluations[1].function());
+        assert!(evaluations[1].result());
+        assert_eq!(ResultSource::Preloaded, evaluations[1].source());
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn expression_eval_with_report_should_record_the_span_of_each_function() {
+        let state = state(".");
+        let input =
+            "file(\"missing\") or\n(not  file(\"README.md\") or file(\"src/lib.rs\")) and file(\"Cargo.toml\")";
+        let expression = Expression::from_str(input).unwrap();
+
+        let report = expression.eval_with_report(&state).unwrap();
+
+        let spans: Vec<&str> = report
+            .function_evaluations()
+            .iter()
+            .map(|e| input.get(e.span()).unwrap())
+            .collect();
+        assert_eq!(
+            vec![
+                "file(\"missing\")",
+                "file(\"README.md\")",
+                "file(\"src/lib.rs\")",
+                "file(\"Cargo.toml\")"
+            ],
+            spans
+        );
+    }
+
+    fn hash(expression: &Expression) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        expression.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn expressions_that_differ_only_in_whitespace_should_be_equal() {
+        let expression1 = Expression::from_str("file(\"a\") or not active(\"b\")").unwrap();
+        let expression2 = Expression::from_str("file(\"a\")  or\nnot   active(\"b\")").unwrap();
+
+        assert_eq!(expression1, expression2);
+        assert_eq!(hash(&expression1), hash(&expression2));
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn expression_eval_with_report_should_record_cached_results_and_when_they_were_cached() {
+        let state = state(".");
+        let expression = Expression::from_str("file(\"Cargo.toml\")").unwrap();
+
+        let first_report = expression.eval_with_report(&state).unwrap();
+        let second_report = expression.eval_with_report(&state).unwrap();
+
+        let first = &first_report.function_evaluations()[0];
+        let second = &second_report.function_evaluations()[0];
+        assert_eq!(ResultSource::FreshIo, first.source());
+        assert_eq!(ResultSource::ConditionCache, second.source());
+        assert_eq!(first.computed_at(), second.computed_at());
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn expression_eval_with_report_should_record_checksums_that_used_the_crc_cache() {
+        let mut state = state(".");
+        state
+            .set_cached_crcs(&[("Blank.esm", 0x374E_2A6F)])
+            .unwrap();
+        let expression = Expression::from_str("checksum(\"Blank.esm\", 374E2A6F)").unwrap();
+
+        let report = expression.eval_with_report(&state).unwrap();
+
+        assert!(report.result());
+        assert_eq!(
+            ResultSource::CrcCache,
+            report.function_evaluations()[0].source()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn clear_condition_cache_should_keep_cached_crcs() {
+        let mut state = state(".");
+        state
+            .set_cached_crcs(&[("Blank.esm", 0x374E_2A6F)])
+            .unwrap();
+        let expression = Expression::from_str("checksum(\"Blank.esm\", 374E2A6F)").unwrap();
+        let source = |state: &State| {
+            let report = expression.eval_with_report(state).unwrap();
+            (report.result(), report.function_evaluations()[0].source())
+        };
+
+        assert_eq!((true, ResultSource::CrcCache), source(&state));
+
+        state.clear_condition_cache().unwrap();
+
+        assert_eq!((true, ResultSource::CrcCache), source(&state));
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn clear_crc_cache_should_keep_cached_condition_results() {
+        let mut state = state(".");
+        state
+            .set_cached_crcs(&[("Blank.esm", 0x374E_2A6F)])
+            .unwrap();
+        let checksum = Expression::from_str("checksum(\"Blank.esm\", 374E2A6F)").unwrap();
+        let file = Expression::from_str("file(\"Cargo.toml\")").unwrap();
+        let source = |expression: &Expression, state: &State| {
+            let report = expression.eval_with_report(state).unwrap();
+            (report.result(), report.function_evaluations()[0].source())
+        };
+
+        assert_eq!((true, ResultSource::CrcCache), source(&checksum, &state));
+        assert_eq!((true, ResultSource::FreshIo), source(&file, &state));
+
+        state.clear_crc_cache();
+
+        assert_eq!((false, ResultSource::FreshIo), source(&checksum, &state));
+        assert_eq!((true, ResultSource::ConditionCache), source(&file, &state));
+
+        state.clear_all_caches();
+
+        assert_eq!((true, ResultSource::FreshIo), source(&file, &state));
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn calculate_crc_should_match_the_crc_used_by_checksum_conditions() {
+        let state = state(".");
+        let crc = state.calculate_crc("Cargo.toml").unwrap().unwrap();
+
+        let expression =
+            Expression::from_str(&format!("checksum(\"Cargo.toml\", {crc:X})")).unwrap();
+
+        assert!(expression.eval(&state).unwrap());
+        assert_eq!(
+            crc,
+            crc32_from_reader(std::fs::File::open("Cargo.toml").unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn calculate_crc_should_resolve_ghosted_plugins_and_return_none_for_missing_files() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(tmp_dir.path().join("Blank.esp.ghost"), b"data").unwrap();
+        let mut state = state(tmp_dir.path());
+        state.game_type = GameType::Skyrim;
+
+        let mut writer = Crc32Writer::new();
+        writer.update(b"data");
+
+        assert_eq!(
+            Some(writer.crc()),
+            state.calculate_crc("blank.esp").unwrap()
+        );
+        assert_eq!(None, state.calculate_crc("missing.esp").unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn calculate_crc_should_apply_the_path_traversal_allowlist() {
+        let mut state = state(".");
+        state.set_path_traversal_allowlist(Some(Vec::new()));
+
+        assert!(matches!(
+            state.calculate_crc("../Cargo.toml"),
+            Err(Error::PathTraversal(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn import_crc_cache_should_add_exported_crcs_for_unchanged_files() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(tmp_dir.path().join("Blank.ba2"), b"data").unwrap();
+        let old_state = state(tmp_dir.path());
+        let crc = old_state.calculate_crc("Blank.ba2").unwrap().unwrap();
+        let expression =
+            Expression::from_str(&format!("checksum(\"Blank.ba2\", {crc:X})")).unwrap();
+        expression.eval(&old_state).unwrap();
+
+        let exported = old_state.export_crc_cache();
+
+        assert_eq!(1, exported.len());
+        assert_eq!("blank.ba2", exported[0].path());
+        assert_eq!(4, exported[0].size());
+        assert_eq!(crc, exported[0].crc());
+
+        let mut new_state = state(tmp_dir.path());
+        assert_eq!(1, new_state.import_crc_cache(&exported));
+
+        let report = expression.eval_with_report(&new_state).unwrap();
+        assert!(report.result());
+        assert_eq!(
+            ResultSource::CrcCache,
+            report.function_evaluations()[0].source()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn import_crc_cache_should_skip_crcs_for_changed_or_missing_files() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(tmp_dir.path().join("a.ba2"), b"data").unwrap();
+        std::fs::write(tmp_dir.path().join("b.ba2"), b"data").unwrap();
+        let mut old_state = state(tmp_dir.path());
+        old_state
+            .set_cached_crcs(&[("a.ba2", 1), ("b.ba2", 2), ("missing.ba2", 3)])
+            .unwrap();
+
+        let exported = old_state.export_crc_cache();
+        assert_eq!(2, exported.len());
+
+        std::fs::write(tmp_dir.path().join("b.ba2"), b"changed").unwrap();
+
+        let mut new_state = state(tmp_dir.path());
+        assert_eq!(1, new_state.import_crc_cache(&exported));
+        assert_eq!(
+            vec![CachedCrc::new("a.ba2".into(), 4, exported[0].modified(), 1)],
+            new_state.export_crc_cache()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn apply_load_order_change_should_only_invalidate_conditions_referencing_changed_plugins() {
+        let mut state = state(".");
+        state.set_active_plugins(&["Blank.esp", "Other.esp"]);
+        let blank = Expression::from_str("file(\"Blank.esp\")").unwrap();
+        let other = Expression::from_str("file(\"Other.esp\")").unwrap();
+        let regex = Expression::from_str("many(\"Bla.*\\.esp\")").unwrap();
+
+        for expression in [&blank, &other, &regex] {
+            expression.eval(&state).unwrap();
+        }
+
+        state.apply_load_order_change(&["Blank.esp", "Other.esp"], &["Other.esp"]);
+        assert!(!state.active_plugins.contains_key("blank.esp"));
+        assert!(state.active_plugins.contains_key("other.esp"));
+
+        let source =
+            |e: &Expression| e.eval_with_report(&state).unwrap().function_evaluations()[0].source();
+        assert_eq!(ResultSource::FreshIo, source(&blank));
+        assert_eq!(ResultSource::ConditionCache, source(&other));
+        assert_eq!(ResultSource::FreshIo, source(&regex));
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn set_active_plugins_should_only_invalidate_conditions_referencing_changed_plugins() {
+        let mut state = state(".");
+        state.set_active_plugins(&["Blank.esp", "Other.esp"]);
+        let blank = Expression::from_str("file(\"Blank.esp\")").unwrap();
+        let other = Expression::from_str("file(\"Other.esp\")").unwrap();
+
+        for expression in [&blank, &other] {
+            expression.eval(&state).unwrap();
+        }
+
+        state.set_active_plugins(&["other.esp"]);
+        assert!(!state.active_plugins.contains_key("blank.esp"));
+
+        let source =
+            |e: &Expression| e.eval_with_report(&state).unwrap().function_evaluations()[0].source();
+        assert_eq!(ResultSource::FreshIo, source(&blank));
+        assert_eq!(ResultSource::ConditionCache, source(&other));
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn activate_and_deactivate_plugin_should_only_invalidate_conditions_referencing_the_plugin() {
+        let mut state = state(".");
+        state.set_active_plugins(&["Blank.esp"]);
+        let blank = Expression::from_str("file(\"Blank.esp\")").unwrap();
+        let other = Expression::from_str("file(\"Other.esp\")").unwrap();
+        let active = Expression::from_str("active(\"Other.esp\")").unwrap();
+        let eval_all = |state: &State| {
+            for expression in [&blank, &other] {
+                expression.eval(state).unwrap();
+            }
+        };
+        let source = |state: &State, e: &Expression| {
+            e.eval_with_report(state).unwrap().function_evaluations()[0].source()
+        };
+
+        eval_all(&state);
+        state.activate_plugin("Other.esp");
+        assert!(active.eval(&state).unwrap());
+        assert_eq!(ResultSource::ConditionCache, source(&state, &blank));
+        assert_eq!(ResultSource::FreshIo, source(&state, &other));
+
+        eval_all(&state);
+        state.activate_plugin("other.ESP");
+        assert_eq!(ResultSource::ConditionCache, source(&state, &other));
+
+        state.deactivate_plugin("OTHER.esp");
+        assert!(!active.eval(&state).unwrap());
+        assert_eq!(ResultSource::ConditionCache, source(&state, &blank));
+        assert_eq!(ResultSource::FreshIo, source(&state, &other));
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn set_plugin_version_should_only_invalidate_conditions_reading_that_plugins_version() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(tmp_dir.path().join("Blank.esp"), b"").unwrap();
+        std::fs::write(tmp_dir.path().join("Other.esp"), b"").unwrap();
+        let mut state = state(tmp_dir.path());
+        state.set_plugin_versions(&[("Blank.esp", "1.0"), ("Other.esp", "1.0")]);
+        let blank = Expression::from_str("version(\"Blank.esp\", \"1.0\", ==)").unwrap();
+        let other = Expression::from_str("version(\"Other.esp\", \"1.0\", ==)").unwrap();
+        let file = Expression::from_str("file(\"Blank.esp\")").unwrap();
+
+        for expression in [&blank, &other, &file] {
+            assert!(expression.eval(&state).unwrap());
+        }
+
+        state.set_plugin_version("blank.ESP", "2.0");
+
+        let report = |e: &Expression| {
+            let report = e.eval_with_report(&state).unwrap();
+            (report.result(), report.function_evaluations()[0].source())
+        };
+        assert_eq!((false, ResultSource::Preloaded), report(&blank));
+        assert_eq!((true, ResultSource::ConditionCache), report(&other));
+        assert_eq!((true, ResultSource::ConditionCache), report(&file));
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn apply_load_order_change_should_not_invalidate_anything_if_only_the_order_changed() {
+        let mut state = state(".");
+        let blank = Expression::from_str("file(\"Blank.esp\")").unwrap();
+        blank.eval(&state).unwrap();
+
+        state.apply_load_order_change(&["Blank.esp", "Other.esp"], &["Other.esp", "Blank.esp"]);
+
+        assert_eq!(
+            ResultSource::ConditionCache,
+            blank
+                .eval_with_report(&state)
+                .unwrap()
+                .function_evaluations()[0]
+                .source()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn apply_load_order_change_should_not_invalidate_anything_if_only_the_case_changed() {
+        let mut state = state(".");
+        let plugin = Expression::from_str("file(\"Stra\u{df}e.esp\")").unwrap();
+        plugin.eval(&state).unwrap();
+
+        state.apply_load_order_change(&["Stra\u{df}e.esp"], &["STRASSE.ESP"]);
+
+        assert_eq!(
+            ResultSource::ConditionCache,
+            plugin
+                .eval_with_report(&state)
+                .unwrap()
+                .function_evaluations()[0]
+                .source()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn add_cache_observer_should_notify_the_observer_of_cache_changes() {
+        let mut state = state(".");
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let events_clone = Arc::clone(&events);
+        state.add_cache_observer(Arc::new(move |event: &CacheEvent| {
+            events_clone.lock().unwrap().push(event.clone());
+        }));
+
+        state
+            .set_cached_crcs(&[("Blank.esp", 0xDEAD_BEEF)])
+            .unwrap();
+        Expression::from_str("file(\"Blank.esp\")")
+            .unwrap()
+            .eval(&state)
+            .unwrap();
+        state.apply_load_order_change(&[], &["Blank.esp"]);
+        state.clear_condition_cache().unwrap();
+
+        assert_eq!(
+            vec![
+                CacheEvent::CrcCacheCleared,
+                CacheEvent::CrcCached {
+                    file: "blank.esp".to_owned(),
+                    crc: 0xDEAD_BEEF,
+                },
+                CacheEvent::ConditionCached {
+                    function: "file(\"Blank.esp\")".to_owned(),
+                    result: false,
+                },
+                CacheEvent::ConditionEvicted {
+                    function: "file(\"Blank.esp\")".to_owned(),
+                },
+                CacheEvent::ConditionCacheCleared,
+            ],
+            *events.lock().unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "eval")]
+    fn set_condition_cache_storage_should_allow_states_to_share_cached_results() {
+        let storage = Arc::new(MemoryCacheStorage::new());
+        let mut state1 = state(".");
+        let mut state2 = state(".");
+        state1.set_condition_cache_storage(Box::new(Arc::clone(&storage)));
+        state2.set_condition_cache_storage(Box::new(Arc::clone(&storage)));
+        let expression = Expression::from_str("file(\"Cargo.toml\")").unwrap();
+
+        expression.eval(&state1).unwrap();
+
+        assert!(storage.get("file(\"Cargo.toml\")").is_some());
+        assert_eq!(
+            ResultSource::ConditionCache,
+            expression
+                .eval_with_report(&state2)
+                .unwrap()
+                .function_evaluations()[0]
+                .source()
+        );
+    }
+
     #[test]
     fn expression_fmt_should_format_correctly() {
         let expression = Expression(vec![
-            CompoundCondition(vec![Condition::Function(Function::FilePath(
-                PathBuf::from("Cargo.toml"),
-            ))]),
-            CompoundCondition(vec![Condition::Function(Function::FilePath(
-                PathBuf::from("missing"),
-            ))]),
+            CompoundCondition(vec![Condition::Function(
+                Function::FilePath(PathBuf::from("Cargo.toml")),
+                0..0,
+            )]),
+            CompoundCondition(vec![Condition::Function(
+                Function::FilePath(PathBuf::from("missing")),
+                0..0,
+            )]),
         ]);
 
         assert_eq!(
@@ -847,6 +3256,7 @@ mod tests {
 
         let expression = Expression(vec![CompoundCondition(vec![Condition::Function(
             Function::FilePath(PathBuf::from("Cargo.toml")),
+            0..0,
         )])]);
 
         assert_eq!("file(\"Cargo.toml\")", &format!("{expression}"));
@@ -0,0 +1,415 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::ast::{FunctionCall, Visitor};
+use crate::cost::is_expensive;
+use crate::function::{Function, Regex};
+use crate::{is_multispace, Condition, Expression};
+
+/// Measures of how complex an expression is, along with problems found in it
+/// that don't stop it from being evaluated but probably aren't intended. They
+/// are calculated without accessing the filesystem.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExpressionMetrics {
+    function_count: usize,
+    nesting_depth: usize,
+    slow_function_count: usize,
+    duplicated_clause_count: usize,
+    lints: Vec<Lint>,
+}
+
+impl ExpressionMetrics {
+    pub(crate) fn of(expression: &Expression) -> Self {
+        let mut metrics = ExpressionMetrics::default();
+        metrics.add_expression(expression, 0, &mut Vec::new());
+        metrics
+    }
+
+    /// The number of functions in the expression, including any that are
+    /// duplicated.
+    pub fn function_count(&self) -> usize {
+        self.function_count
+    }
+
+    /// The greatest number of parenthesised expressions that are nested
+    /// inside each other, which is 0 if the expression has no parentheses.
+    pub fn nesting_depth(&self) -> usize {
+        self.nesting_depth
+    }
+
+    /// The number of functions that need more than file metadata or the
+    /// state's active plugins to evaluate, i.e. that scan directories or
+    /// read plugins, executables, archives or whole files. See
+    /// [EvaluationCost](crate::EvaluationCost) for details.
+    pub fn slow_function_count(&self) -> usize {
+        self.slow_function_count
+    }
+
+    /// The number of clauses that are the same as an earlier clause that
+    /// they're joined to by `and`, which makes them redundant.
+    pub fn duplicated_clause_count(&self) -> usize {
+        self.duplicated_clause_count
+    }
+
+    /// The problems found in the expression, in the order that they appear.
+    pub fn lints(&self) -> &[Lint] {
+        &self.lints
+    }
+
+    fn add_expression(
+        &mut self,
+        expression: &Expression,
+        depth: usize,
+        checksums: &mut Vec<(String, u32)>,
+    ) {
+        self.nesting_depth = self.nesting_depth.max(depth);
+
+        for compound_condition in &expression.0 {
+            for (index, condition) in compound_condition.0.iter().enumerate() {
+                let is_duplicate = compound_condition
+                    .0
+                    .get(..index)
+                    .is_some_and(|earlier| earlier.contains(condition));
+                if is_duplicate {
+                    self.duplicated_clause_count = self.duplicated_clause_count.saturating_add(1);
+                    self.lints.push(Lint::new(
+                        LintKind::DuplicatedClause,
+                        condition,
+                        condition_span(condition),
+                    ));
+                }
+
+                match condition {
+                    Condition::Function(f, span) | Condition::InvertedFunction(f, span) => {
+                        self.add_function(f, span, checksums);
+                    }
+                    Condition::Expression(e) | Condition::InvertedExpression(e) => {
+                        self.add_expression(e, depth.saturating_add(1), checksums);
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_function(
+        &mut self,
+        function: &Function,
+        span: &Range<usize>,
+        checksums: &mut Vec<(String, u32)>,
+    ) {
+        self.function_count = self.function_count.saturating_add(1);
+        if is_expensive(function) {
+            self.slow_function_count = self.slow_function_count.saturating_add(1);
+        }
+
+        if regexes(function).any(|(regex, matches_names)| matches_nothing(regex, matches_names)) {
+            self.lints.push(Lint::new(
+                LintKind::RegexMatchesNothing,
+                function,
+                span.clone(),
+            ));
+        }
+
+        if let Function::Checksum(path, crcs) = function {
+            let path = path.to_string_lossy().to_lowercase();
+            let mut is_duplicate = false;
+            for crc in crcs {
+                let key = (path.clone(), *crc);
+                if checksums.contains(&key) {
+                    is_duplicate = true;
+                } else {
+                    checksums.push(key);
+                }
+            }
+
+            if is_duplicate {
+                self.lints.push(Lint::new(
+                    LintKind::DuplicatedChecksum,
+                    function,
+                    span.clone(),
+                ));
+            }
+        }
+    }
+}
+
+/// A problem found in an expression.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Lint {
+    kind: LintKind,
+    clause: String,
+    span: Range<usize>,
+}
+
+impl Lint {
+    fn new(kind: LintKind, clause: &dyn fmt::Display, span: Range<usize>) -> Self {
+        Lint {
+            kind,
+            clause: clause.to_string(),
+            span,
+        }
+    }
+
+    pub fn kind(&self) -> LintKind {
+        self.kind
+    }
+
+    /// The function or clause that has the problem, as it would be written
+    /// in a condition string.
+    pub fn clause(&self) -> &str {
+        &self.clause
+    }
+
+    /// The byte range in the condition string that the expression was parsed
+    /// from that covers the function or the functions in the clause that has
+    /// the problem.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} in \"{}\" at bytes {}..{}",
+            self.kind, self.clause, self.span.start, self.span.end
+        )
+    }
+}
+
+/// The kinds of problem that can be found in an expression.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[non_exhaustive]
+pub enum LintKind {
+    /// A function has a regex that can't match any string, or a regex that
+    /// is matched against names that can only match an empty name.
+    RegexMatchesNothing,
+    /// A `checksum()` function gives a CRC that was already given for the
+    /// same file, in the same function or earlier in the expression.
+    DuplicatedChecksum,
+    /// A clause is the same as an earlier clause that it's joined to by
+    /// `and`.
+    DuplicatedClause,
+    /// A function's name isn't written in lowercase, or there is whitespace
+    /// after its name or inside its parentheses. This is only accepted when
+    /// parsing with [lenient syntax](crate::ParseOptions::with_lenient_syntax).
+    NonCanonicalSyntax,
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintKind::RegexMatchesNothing => write!(f, "regex matches nothing"),
+            LintKind::DuplicatedChecksum => write!(f, "same checksum tested twice"),
+            LintKind::DuplicatedClause => write!(f, "duplicated clause"),
+            LintKind::NonCanonicalSyntax => write!(f, "non-canonical syntax"),
+        }
+    }
+}
+
+/// Find the functions that aren't written in their canonical form in the
+/// given condition string, which the expression was parsed from.
+pub(crate) fn non_canonical_syntax(expression: &Expression, input: &str) -> Vec<Lint> {
+    struct Finder<'a> {
+        input: &'a str,
+        lints: Vec<Lint>,
+    }
+
+    impl Visitor for Finder<'_> {
+        fn visit_function(&mut self, function: FunctionCall<'_>) {
+            let is_canonical = self
+                .input
+                .get(function.span())
+                .and_then(|call| call.strip_prefix(function.name()))
+                .and_then(|call| call.strip_prefix('('))
+                .and_then(|call| call.strip_suffix(')'))
+                .is_some_and(|arguments| {
+                    !arguments.starts_with(is_multispace) && !arguments.ends_with(is_multispace)
+                });
+
+            if !is_canonical {
+                self.lints.push(Lint::new(
+                    LintKind::NonCanonicalSyntax,
+                    &function,
+                    function.span(),
+                ));
+            }
+        }
+    }
+
+    let mut finder = Finder {
+        input,
+        lints: Vec::new(),
+    };
+    finder.visit_expression(expression);
+    finder.lints
+}
+
+/// Get the byte range that covers the functions in the given condition.
+fn condition_span(condition: &Condition) -> Range<usize> {
+    match condition {
+        Condition::Function(_, span) | Condition::InvertedFunction(_, span) => span.clone(),
+        Condition::Expression(e) | Condition::InvertedExpression(e) => {
+            e.0.iter()
+                .flat_map(|compound_condition| &compound_condition.0)
+                .map(condition_span)
+                .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+                .unwrap_or_default()
+        }
+    }
+}
+
+/// Get the function's regexes, and whether each is matched against the names
+/// of files, plugins or archive entries (as opposed to their contents).
+fn regexes(function: &Function) -> impl Iterator<Item = (&Regex, bool)> {
+    let (name_regex, other_regex) = match function {
+        Function::FileRegex(_, r)
+        | Function::FileGlob(_, _, r)
+        | Function::Many(_, r)
+        | Function::RecursiveFileRegex(_, r, _)
+        | Function::RecursiveMany(_, r, _)
+        | Function::Count(_, r, _, _)
+        | Function::FilenameVersion(_, r, _, _)
+        | Function::ActiveRegex(r)
+        | Function::ManyActive(r)
+        | Function::ActiveCount(r, _, _)
+        | Function::ArchiveRegex(_, r) => (Some(r), None),
+        Function::DescriptionRegex(_, r, d) => (Some(r), Some(d)),
+        Function::DescriptionContains(_, r)
+        | Function::AuthorContains(_, r)
+        | Function::FileContentContains(_, r)
+        | Function::RegistryValue(_, _, r) => (None, Some(r)),
+        _ => (None, None),
+    };
+
+    name_regex
+        .map(|r| (r, true))
+        .into_iter()
+        .chain(other_regex.map(|r| (r, false)))
+}
+
+#[cfg(feature = "regex")]
+fn matches_nothing(regex: &Regex, matches_names: bool) -> bool {
+    let Ok(hir) = regex_syntax::ParserBuilder::new()
+        .case_insensitive(true)
+        .build()
+        .parse(regex.as_str())
+    else {
+        return false;
+    };
+
+    let properties = hir.properties();
+    match properties.minimum_len() {
+        None => true,
+        Some(_) => matches_names && properties.maximum_len() == Some(0),
+    }
+}
+
+/// Wildcard patterns always match at least one name.
+#[cfg(not(feature = "regex"))]
+fn matches_nothing(_regex: &Regex, _matches_names: bool) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn metrics(expression: &str) -> ExpressionMetrics {
+        Expression::from_str(expression).unwrap().metrics()
+    }
+
+    #[test]
+    fn metrics_should_count_functions_and_slow_functions() {
+        let metrics = metrics(
+            "file(\"a.esp\") and active(\"a.esp\") or (is_master(\"b.esp\") and not checksum(\"c.esp\", DEADBEEF))",
+        );
+
+        assert_eq!(4, metrics.function_count());
+        assert_eq!(2, metrics.slow_function_count());
+    }
+
+    #[test]
+    fn metrics_should_measure_the_deepest_nesting() {
+        assert_eq!(0, metrics("file(\"a\")").nesting_depth());
+        assert_eq!(
+            3,
+            metrics("(file(\"a\")) or not ((file(\"b\") and (file(\"c\"))))").nesting_depth()
+        );
+    }
+
+    #[test]
+    fn metrics_should_lint_clauses_that_duplicate_earlier_clauses_joined_by_and() {
+        let metrics = metrics(
+            "file(\"a\") and not (file(\"b\")) and file(\"A\") and not (file(\"b\")) or file(\"a\")",
+        );
+
+        assert_eq!(2, metrics.duplicated_clause_count());
+        assert_eq!(
+            vec![
+                Lint {
+                    kind: LintKind::DuplicatedClause,
+                    clause: "file(\"A\")".into(),
+                    span: 34..43,
+                },
+                Lint {
+                    kind: LintKind::DuplicatedClause,
+                    clause: "not (file(\"b\"))".into(),
+                    span: 53..62,
+                },
+            ],
+            metrics.lints()
+        );
+    }
+
+    #[test]
+    fn metrics_should_lint_a_crc_that_is_tested_twice_for_the_same_file() {
+        let metrics = metrics(
+            "checksum(\"a.esp\", DEADBEEF, 12345678) or checksum(\"b.esp\", DEADBEEF) or checksum(\"A.esp\", 12345678)",
+        );
+
+        assert_eq!(1, metrics.lints().len());
+        assert_eq!(LintKind::DuplicatedChecksum, metrics.lints()[0].kind());
+        assert_eq!(72..99, metrics.lints()[0].span());
+    }
+
+    #[test]
+    fn metrics_should_lint_a_crc_that_is_repeated_in_one_function() {
+        let metrics = metrics("checksum(\"a.esp\", DEADBEEF, DEADBEEF)");
+
+        assert_eq!(
+            vec![LintKind::DuplicatedChecksum],
+            metrics.lints().iter().map(Lint::kind).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn metrics_should_lint_regexes_that_match_nothing() {
+        let metrics = metrics(
+            "file(\"a/[^\\s\\S]\\.esp\") or file(\"b/.*\") or many_active(\"[^\\s\\S]?\") or description_contains(\"c.esp\", \"x?\")",
+        );
+
+        assert_eq!(
+            vec!["file(\"a/[^\\s\\S]\\.esp\")", "many_active(\"[^\\s\\S]?\")"],
+            metrics.lints().iter().map(Lint::clause).collect::<Vec<_>>()
+        );
+        assert!(metrics
+            .lints()
+            .iter()
+            .all(|l| l.kind() == LintKind::RegexMatchesNothing));
+    }
+
+    #[test]
+    fn lint_display_should_describe_the_problem_and_its_location() {
+        let metrics = metrics("checksum(\"a.esp\", DEADBEEF, DEADBEEF)");
+
+        assert_eq!(
+            "same checksum tested twice in \"checksum(\"a.esp\", DEADBEEF, DEADBEEF)\" at bytes 0..37",
+            metrics.lints()[0].to_string()
+        );
+    }
+}
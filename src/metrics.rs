@@ -0,0 +1,239 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{CacheEvent, CacheObserver, EvaluationReport, ResultSource};
+
+const PREFIX: &str = "loot_condition_interpreter";
+
+/// Counts of expression evaluations and cache changes, which can be rendered
+/// in the Prometheus text exposition format.
+///
+/// Evaluations are counted by passing their reports to
+/// [Metrics::record_report], and cache changes are counted by registering the
+/// metrics with [State::add_cache_observer](crate::State::add_cache_observer).
+/// All counts can be updated concurrently.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    expressions_evaluated: AtomicU64,
+    true_results: AtomicU64,
+    condition_cache_results: AtomicU64,
+    crc_cache_results: AtomicU64,
+    sha256_cache_results: AtomicU64,
+    record_count_cache_results: AtomicU64,
+    plugin_cache_results: AtomicU64,
+    metadata_cache_results: AtomicU64,
+    archive_index_cache_results: AtomicU64,
+    version_cache_results: AtomicU64,
+    missing_file_cache_results: AtomicU64,
+    preloaded_results: AtomicU64,
+    constant_results: AtomicU64,
+    fresh_io_results: AtomicU64,
+    condition_cache_insertions: AtomicU64,
+    condition_cache_evictions: AtomicU64,
+    condition_cache_clears: AtomicU64,
+    crc_cache_insertions: AtomicU64,
+    crc_cache_evictions: AtomicU64,
+    crc_cache_clears: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics::default()
+    }
+
+    /// Count an expression's evaluation and the functions that were evaluated
+    /// as part of it.
+    pub fn record_report(&self, report: &EvaluationReport) {
+        increment(&self.expressions_evaluated);
+        if report.result() {
+            increment(&self.true_results);
+        }
+
+        for evaluation in report.function_evaluations() {
+            increment(match evaluation.source() {
+                ResultSource::ConditionCache => &self.condition_cache_results,
+                ResultSource::CrcCache => &self.crc_cache_results,
+                ResultSource::Sha256Cache => &self.sha256_cache_results,
+                ResultSource::RecordCountCache => &self.record_count_cache_results,
+                ResultSource::PluginCache => &self.plugin_cache_results,
+                ResultSource::MetadataCache => &self.metadata_cache_results,
+                ResultSource::ArchiveIndexCache => &self.archive_index_cache_results,
+                ResultSource::VersionCache => &self.version_cache_results,
+                ResultSource::MissingFileCache => &self.missing_file_cache_results,
+                ResultSource::Preloaded => &self.preloaded_results,
+                ResultSource::Constant => &self.constant_results,
+                ResultSource::FreshIo => &self.fresh_io_results,
+            });
+        }
+    }
+
+    /// Write the metrics in the Prometheus text exposition format. All
+    /// metrics are counters, and their names are prefixed with
+    /// `loot_condition_interpreter_`.
+    pub fn write_prometheus_text<W: fmt::Write>(&self, writer: &mut W) -> fmt::Result {
+        write_counter(
+            writer,
+            "expressions_evaluated_total",
+            "The number of expressions evaluated.",
+            &[("", &self.expressions_evaluated)],
+        )?;
+        write_counter(
+            writer,
+            "expressions_true_total",
+            "The number of expression evaluations that returned true.",
+            &[("", &self.true_results)],
+        )?;
+        write_counter(
+            writer,
+            "function_evaluations_total",
+            "The number of functions evaluated, by where their result came from.",
+            &[
+                ("source=\"condition_cache\"", &self.condition_cache_results),
+                ("source=\"crc_cache\"", &self.crc_cache_results),
+                ("source=\"sha256_cache\"", &self.sha256_cache_results),
+                (
+                    "source=\"record_count_cache\"",
+                    &self.record_count_cache_results,
+                ),
+                ("source=\"plugin_cache\"", &self.plugin_cache_results),
+                ("source=\"metadata_cache\"", &self.metadata_cache_results),
+                (
+                    "source=\"archive_index_cache\"",
+                    &self.archive_index_cache_results,
+                ),
+                ("source=\"version_cache\"", &self.version_cache_results),
+                (
+                    "source=\"missing_file_cache\"",
+                    &self.missing_file_cache_results,
+                ),
+                ("source=\"preloaded\"", &self.preloaded_results),
+                ("source=\"constant\"", &self.constant_results),
+                ("source=\"fresh_io\"", &self.fresh_io_results),
+            ],
+        )?;
+        write_counter(
+            writer,
+            "cache_insertions_total",
+            "The number of values added to each cache.",
+            &[
+                ("cache=\"condition\"", &self.condition_cache_insertions),
+                ("cache=\"crc\"", &self.crc_cache_insertions),
+            ],
+        )?;
+        write_counter(
+            writer,
+            "cache_evictions_total",
+            "The number of values individually invalidated in each cache.",
+            &[
+                ("cache=\"condition\"", &self.condition_cache_evictions),
+                ("cache=\"crc\"", &self.crc_cache_evictions),
+            ],
+        )?;
+        write_counter(
+            writer,
+            "cache_clears_total",
+            "The number of times each cache was cleared.",
+            &[
+                ("cache=\"condition\"", &self.condition_cache_clears),
+                ("cache=\"crc\"", &self.crc_cache_clears),
+            ],
+        )
+    }
+}
+
+impl CacheObserver for Metrics {
+    fn cache_changed(&self, event: &CacheEvent) {
+        increment(match event {
+            CacheEvent::ConditionCached { .. } => &self.condition_cache_insertions,
+            CacheEvent::ConditionEvicted { .. } => &self.condition_cache_evictions,
+            CacheEvent::ConditionCacheCleared => &self.condition_cache_clears,
+            CacheEvent::CrcCached { .. } => &self.crc_cache_insertions,
+            CacheEvent::CrcEvicted { .. } => &self.crc_cache_evictions,
+            CacheEvent::CrcCacheCleared => &self.crc_cache_clears,
+        });
+    }
+}
+
+fn increment(counter: &AtomicU64) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Write a counter with the given name, help text and samples, where each
+/// sample is its labels (which may be empty) and value.
+fn write_counter<W: fmt::Write>(
+    writer: &mut W,
+    name: &str,
+    help: &str,
+    samples: &[(&str, &AtomicU64)],
+) -> fmt::Result {
+    writeln!(writer, "# HELP {PREFIX}_{name} {help}")?;
+    writeln!(writer, "# TYPE {PREFIX}_{name} counter")?;
+    for (labels, value) in samples {
+        let value = value.load(Ordering::Relaxed);
+        if labels.is_empty() {
+            writeln!(writer, "{PREFIX}_{name} {value}")?;
+        } else {
+            writeln!(writer, "{PREFIX}_{name}{{{labels}}} {value}")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use crate::{Expression, GameType, State};
+
+    use super::*;
+
+    #[test]
+    fn write_prometheus_text_should_write_all_counters() {
+        let metrics = Metrics::new();
+        let mut text = String::new();
+
+        metrics.write_prometheus_text(&mut text).unwrap();
+
+        assert!(text.starts_with(concat!(
+            "# HELP loot_condition_interpreter_expressions_evaluated_total The number of expressions evaluated.\n",
+            "# TYPE loot_condition_interpreter_expressions_evaluated_total counter\n",
+            "loot_condition_interpreter_expressions_evaluated_total 0\n",
+        )));
+        assert!(text.contains(
+            "loot_condition_interpreter_function_evaluations_total{source=\"fresh_io\"} 0\n"
+        ));
+        assert!(text.ends_with("loot_condition_interpreter_cache_clears_total{cache=\"crc\"} 0\n"));
+    }
+
+    #[test]
+    fn metrics_should_count_evaluations_and_cache_changes() {
+        let metrics = Arc::new(Metrics::new());
+        let mut state =
+            State::new(GameType::Oblivion, PathBuf::from(".")).with_active_plugins(&["Blank.esp"]);
+        state.add_cache_observer(Arc::<Metrics>::clone(&metrics));
+
+        let expression =
+            Expression::from_str("file(\"Cargo.toml\") and active(\"Blank.esp\")").unwrap();
+        metrics.record_report(&expression.eval_with_report(&state).unwrap());
+        metrics.record_report(&expression.eval_with_report(&state).unwrap());
+        state.clear_condition_cache().unwrap();
+
+        let mut text = String::new();
+        metrics.write_prometheus_text(&mut text).unwrap();
+
+        for line in [
+            "loot_condition_interpreter_expressions_evaluated_total 2\n",
+            "loot_condition_interpreter_expressions_true_total 2\n",
+            "loot_condition_interpreter_function_evaluations_total{source=\"condition_cache\"} 1\n",
+            "loot_condition_interpreter_function_evaluations_total{source=\"preloaded\"} 2\n",
+            "loot_condition_interpreter_function_evaluations_total{source=\"fresh_io\"} 1\n",
+            "loot_condition_interpreter_cache_insertions_total{cache=\"condition\"} 1\n",
+            "loot_condition_interpreter_cache_clears_total{cache=\"condition\"} 1\n",
+            "loot_condition_interpreter_cache_clears_total{cache=\"crc\"} 0\n",
+        ] {
+            assert!(text.contains(line), "{line}");
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use std::fmt;
+
+#[cfg(feature = "regex")]
+use regex_syntax::ast::{self, Ast, ClassSetItem, RepetitionKind};
+
+use crate::error::ParsingErrorKind;
+use crate::function::Regex;
+use crate::{ConditionAliases, Expression, RegexInterner};
+
+/// Regex syntax features that can be disallowed when parsing conditions.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[non_exhaustive]
+pub enum RegexFeature {
+    /// Unicode character classes, e.g. `\pL` or `\p{Greek}`, which can
+    /// compile into very large programs.
+    UnicodeClass,
+    /// Counted repetitions, e.g. `a{2,5}`.
+    CountedRepetition,
+    /// Repetitions of expressions that themselves contain repetitions, e.g.
+    /// `(a+)*`.
+    NestedRepetition,
+}
+
+impl fmt::Display for RegexFeature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegexFeature::UnicodeClass => write!(f, "Unicode character classes"),
+            RegexFeature::CountedRepetition => write!(f, "counted repetitions"),
+            RegexFeature::NestedRepetition => write!(f, "nested repetitions"),
+        }
+    }
+}
+
+/// The interner that regexes are compiled using, if any.
+#[derive(Clone, Debug, Default)]
+struct RegexCache(Option<RegexInterner>);
+
+/// An interner doesn't change what is parsed, so it doesn't affect whether
+/// options are equal.
+impl PartialEq for RegexCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for RegexCache {}
+
+/// Options that control how condition strings are parsed. The default options
+/// impose no limits beyond those of the regex library itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    max_expression_length: Option<usize>,
+    max_nesting_depth: Option<usize>,
+    max_regex_length: Option<usize>,
+    max_regex_size: Option<usize>,
+    disallowed_regex_features: Vec<RegexFeature>,
+    aliases: ConditionAliases,
+    variables: Vec<String>,
+    lenient_syntax: bool,
+    regex_cache: RegexCache,
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        ParseOptions::default()
+    }
+
+    /// Sets the maximum length in bytes of a condition string, which is
+    /// checked before any of it is parsed.
+    #[must_use]
+    pub fn with_max_expression_length(mut self, max_expression_length: Option<usize>) -> Self {
+        self.max_expression_length = max_expression_length;
+        self
+    }
+
+    /// Sets the maximum number of parenthesised expressions that may be
+    /// nested inside each other in a condition string, e.g. `(file("a"))` has
+    /// a depth of 1. Parsing stops as soon as the limit is exceeded, so this
+    /// also limits how deeply the parser recurses.
+    #[must_use]
+    pub fn with_max_nesting_depth(mut self, max_nesting_depth: Option<usize>) -> Self {
+        self.max_nesting_depth = max_nesting_depth;
+        self
+    }
+
+    /// Sets the maximum length in bytes of a regex string given in a condition.
+    #[must_use]
+    pub fn with_max_regex_length(mut self, max_regex_length: Option<usize>) -> Self {
+        self.max_regex_length = max_regex_length;
+        self
+    }
+
+    /// Sets the maximum size in bytes of a regex once it has been compiled.
+    #[must_use]
+    pub fn with_max_regex_size(mut self, max_regex_size: Option<usize>) -> Self {
+        self.max_regex_size = max_regex_size;
+        self
+    }
+
+    #[must_use]
+    pub fn with_disallowed_regex_features(mut self, features: &[RegexFeature]) -> Self {
+        self.disallowed_regex_features = features.to_vec();
+        self
+    }
+
+    /// Sets the aliases that can be referenced as `@name` in conditions.
+    #[must_use]
+    pub fn with_aliases(mut self, aliases: ConditionAliases) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Sets the names of the variables that can be referenced as `${NAME}` in
+    /// path, version and text arguments. Referencing any other variable is a
+    /// parsing error. Values are given when evaluating, using
+    /// [State::set_variables](crate::State::set_variables).
+    #[must_use]
+    pub fn with_variables<T: AsRef<str>>(mut self, names: &[T]) -> Self {
+        self.variables = names.iter().map(|n| n.as_ref().to_owned()).collect();
+        self
+    }
+
+    /// Sets whether function names may be written in any case, e.g.
+    /// `File("a.esp")` or `IS_MASTER("a.esp")`, and whether whitespace may
+    /// follow a function's name and surround its arguments inside its
+    /// parentheses, e.g. `file ( "a.esp" )`. Neither is allowed by default.
+    /// [Expression::parse_with_warnings] can be used to find where they were
+    /// used.
+    #[must_use]
+    pub fn with_lenient_syntax(mut self, lenient_syntax: bool) -> Self {
+        self.lenient_syntax = lenient_syntax;
+        self
+    }
+
+    /// Sets the interner that regexes are compiled using, so that they can
+    /// be shared with other expressions that are parsed using the same
+    /// interner. By default, each regex is compiled separately.
+    #[must_use]
+    pub fn with_regex_interner(mut self, interner: RegexInterner) -> Self {
+        self.regex_cache = RegexCache(Some(interner));
+        self
+    }
+
+    pub(crate) fn has_regex_interner(&self) -> bool {
+        self.regex_cache.0.is_some()
+    }
+
+    /// Get the regex for the given pattern from the interner, or use the given
+    /// function to compile it if there is no interner.
+    pub(crate) fn cached_regex(
+        &self,
+        pattern: &str,
+        compile: impl FnOnce() -> Result<Regex, ParsingErrorKind>,
+    ) -> Result<Regex, ParsingErrorKind> {
+        match &self.regex_cache.0 {
+            Some(interner) => interner.get_or_compile(pattern, self.max_regex_size, compile),
+            None => compile(),
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    pub(crate) fn max_regex_size(&self) -> Option<usize> {
+        self.max_regex_size
+    }
+
+    pub(crate) fn lenient_syntax(&self) -> bool {
+        self.lenient_syntax
+    }
+
+    pub(crate) fn max_nesting_depth(&self) -> Option<usize> {
+        self.max_nesting_depth
+    }
+
+    pub(crate) fn check_expression_length(&self, input: &str) -> Result<(), ParsingErrorKind> {
+        match self.max_expression_length {
+            Some(max_length) if input.len() > max_length => {
+                Err(ParsingErrorKind::ExpressionTooLong(max_length))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn alias(&self, name: &str) -> Option<&Expression> {
+        self.aliases.get(name)
+    }
+
+    pub(crate) fn has_variable(&self, name: &str) -> bool {
+        self.variables.iter().any(|v| v == name)
+    }
+
+    /// Check the given regex string against the length and feature limits.
+    /// Syntax errors are left for the regex library to report.
+    pub(crate) fn check_regex(&self, regex: &str) -> Result<(), ParsingErrorKind> {
+        if let Some(max_length) = self.max_regex_length {
+            if regex.len() > max_length {
+                return Err(ParsingErrorKind::RegexTooLong(max_length));
+            }
+        }
+
+        // Wildcard patterns can't use any of the features that can be
+        // disallowed.
+        #[cfg(feature = "regex")]
+        if !self.disallowed_regex_features.is_empty() {
+            return check_regex_features(regex, &self.disallowed_regex_features);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "regex")]
+fn check_regex_features(
+    regex: &str,
+    disallowed_features: &[RegexFeature],
+) -> Result<(), ParsingErrorKind> {
+    let Ok(ast) = ast::parse::Parser::new().parse(regex) else {
+        return Ok(());
+    };
+
+    let visitor = FeatureVisitor {
+        disallowed_features,
+        repetition_depth: 0,
+    };
+
+    ast::visit(&ast, visitor).map_err(ParsingErrorKind::DisallowedRegexFeature)
+}
+
+#[cfg(feature = "regex")]
+struct FeatureVisitor<'a> {
+    disallowed_features: &'a [RegexFeature],
+    repetition_depth: usize,
+}
+
+#[cfg(feature = "regex")]
+impl FeatureVisitor<'_> {
+    fn check(&self, feature: RegexFeature) -> Result<(), RegexFeature> {
+        if self.disallowed_features.contains(&feature) {
+            Err(feature)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "regex")]
+impl ast::Visitor for FeatureVisitor<'_> {
+    type Output = ();
+    type Err = RegexFeature;
+
+    fn finish(self) -> Result<Self::Output, Self::Err> {
+        Ok(())
+    }
+
+    fn visit_pre(&mut self, ast: &Ast) -> Result<(), Self::Err> {
+        match ast {
+            Ast::ClassUnicode(_) => self.check(RegexFeature::UnicodeClass),
+            Ast::Repetition(repetition) => {
+                if matches!(repetition.op.kind, RepetitionKind::Range(_)) {
+                    self.check(RegexFeature::CountedRepetition)?;
+                }
+
+                self.repetition_depth = self.repetition_depth.saturating_add(1);
+                if self.repetition_depth > 1 {
+                    self.check(RegexFeature::NestedRepetition)?;
+                }
+
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn visit_post(&mut self, ast: &Ast) -> Result<(), Self::Err> {
+        if let Ast::Repetition(_) = ast {
+            self.repetition_depth = self.repetition_depth.saturating_sub(1);
+        }
+
+        Ok(())
+    }
+
+    fn visit_class_set_item_pre(&mut self, item: &ClassSetItem) -> Result<(), Self::Err> {
+        match item {
+            ClassSetItem::Unicode(_) => self.check(RegexFeature::UnicodeClass),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_with_a_regex_interner_should_equal_options_without_one() {
+        assert_eq!(
+            ParseOptions::new(),
+            ParseOptions::new().with_regex_interner(RegexInterner::new())
+        );
+    }
+
+    #[test]
+    fn check_regex_should_accept_anything_with_default_options() {
+        let options = ParseOptions::default();
+
+        assert!(options.check_regex("(a+)*\\p{Greek}{2,5}").is_ok());
+    }
+
+    #[test]
+    fn check_regex_should_reject_a_regex_longer_than_the_max_length() {
+        let options = ParseOptions::new().with_max_regex_length(Some(4));
+
+        assert!(options.check_regex("abcd").is_ok());
+        assert_eq!(
+            Err(ParsingErrorKind::RegexTooLong(4)),
+            options.check_regex("abcde")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn check_regex_should_reject_unicode_classes_if_disallowed() {
+        let options =
+            ParseOptions::new().with_disallowed_regex_features(&[RegexFeature::UnicodeClass]);
+
+        assert!(options.check_regex("[a-z]+\\.esp").is_ok());
+        assert_eq!(
+            Err(ParsingErrorKind::DisallowedRegexFeature(
+                RegexFeature::UnicodeClass
+            )),
+            options.check_regex("\\pL")
+        );
+        assert_eq!(
+            Err(ParsingErrorKind::DisallowedRegexFeature(
+                RegexFeature::UnicodeClass
+            )),
+            options.check_regex("[a\\p{Greek}]")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn check_regex_should_reject_counted_repetitions_if_disallowed() {
+        let options =
+            ParseOptions::new().with_disallowed_regex_features(&[RegexFeature::CountedRepetition]);
+
+        assert!(options.check_regex("a*b+c?").is_ok());
+        assert_eq!(
+            Err(ParsingErrorKind::DisallowedRegexFeature(
+                RegexFeature::CountedRepetition
+            )),
+            options.check_regex("a{1000}")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn check_regex_should_reject_nested_repetitions_if_disallowed() {
+        let options =
+            ParseOptions::new().with_disallowed_regex_features(&[RegexFeature::NestedRepetition]);
+
+        assert!(options.check_regex("a*(bc)+d*").is_ok());
+        assert_eq!(
+            Err(ParsingErrorKind::DisallowedRegexFeature(
+                RegexFeature::NestedRepetition
+            )),
+            options.check_regex("(a(bc)+)*")
+        );
+    }
+}
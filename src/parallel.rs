@@ -0,0 +1,95 @@
+use rayon::prelude::*;
+
+use crate::{CompoundCondition, Error, Expression, State};
+
+/// Evaluate the compound conditions of the given expression, and the
+/// conditions in each of them, on rayon's thread pool. Nested expressions are
+/// evaluated serially. Every condition is evaluated, as it's not known in
+/// advance which results will short-circuit evaluation, but the results are
+/// combined in order so that the result, or the error returned, is the same
+/// as for serial evaluation.
+pub(crate) fn eval_expression(expression: &Expression, state: &State) -> Result<bool, Error> {
+    let results: Vec<_> = expression
+        .0
+        .par_iter()
+        .map(|compound_condition| eval_compound_condition(compound_condition, state))
+        .collect();
+
+    combine(results, true)
+}
+
+fn eval_compound_condition(
+    compound_condition: &CompoundCondition,
+    state: &State,
+) -> Result<bool, Error> {
+    let results: Vec<_> = compound_condition
+        .0
+        .par_iter()
+        .map(|condition| condition.eval(state, &mut ()))
+        .collect();
+
+    combine(results, false)
+}
+
+/// Combine results in the same way as short-circuiting evaluation would, so
+/// that the first error or the first result that is equal to `decisive`
+/// decides the combined result.
+fn combine(results: Vec<Result<bool, Error>>, decisive: bool) -> Result<bool, Error> {
+    for result in results {
+        if result? == decisive {
+            return Ok(decisive);
+        }
+    }
+
+    Ok(!decisive)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use crate::GameType;
+
+    use super::*;
+
+    fn eval(expression: &str, state: &State) -> Result<bool, Error> {
+        eval_expression(&Expression::from_str(expression)?, state)
+    }
+
+    #[test]
+    fn eval_expression_should_give_the_same_results_as_serial_evaluation() {
+        let state = State::new(GameType::Oblivion, PathBuf::from("."));
+
+        for expression in [
+            "file(\"Cargo.toml\")",
+            "file(\"missing\")",
+            "file(\"Cargo.toml\") and file(\"missing\")",
+            "file(\"missing\") or file(\"Cargo.toml\")",
+            "file(\"missing\") or (file(\"Cargo.toml\") and not file(\"missing\"))",
+            "not file(\"missing\") and (file(\"missing\") or file(\"src\"))",
+        ] {
+            assert_eq!(
+                Expression::from_str(expression)
+                    .unwrap()
+                    .eval(&state)
+                    .unwrap(),
+                eval(expression, &state).unwrap(),
+                "{expression}"
+            );
+        }
+    }
+
+    #[test]
+    fn eval_expression_should_only_return_errors_that_serial_evaluation_would() {
+        let mut state = State::new(GameType::Oblivion, PathBuf::from("."));
+        state.set_path_traversal_allowlist(Some(Vec::new()));
+
+        assert!(eval("file(\"Cargo.toml\") or file(\"../Cargo.toml\")", &state).unwrap());
+        assert!(!eval("file(\"missing\") and file(\"../Cargo.toml\")", &state).unwrap());
+        assert!(matches!(
+            eval("file(\"Cargo.toml\") and file(\"../Cargo.toml\")", &state),
+            Err(Error::PathTraversal(_))
+        ));
+    }
+}
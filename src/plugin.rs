@@ -0,0 +1,67 @@
+use std::fmt;
+
+/// A plugin that has already been parsed by the host, which can be given to a
+/// [State](crate::State) using
+/// [State::set_parsed_plugins](crate::State::set_parsed_plugins) so that
+/// functions that read data from the plugin don't need to parse it again.
+///
+/// This is implemented for [esplugin::Plugin], so a host that uses esplugin
+/// can share its plugin objects directly.
+pub trait ParsedPlugin: fmt::Debug + Send + Sync {
+    /// Whether the plugin is a master file, as checked by `is_master()`.
+    fn is_master_file(&self) -> bool;
+
+    /// Whether the plugin is a light plugin, as checked by `is_light_plugin()`.
+    fn is_light_plugin(&self) -> bool;
+
+    /// Whether the plugin is a medium plugin, as checked by
+    /// `is_medium_plugin()`.
+    fn is_medium_plugin(&self) -> bool;
+
+    /// The version in the plugin's HEDR subrecord, as checked by
+    /// `header_version()`, or `None` if it could not be read.
+    fn header_version(&self) -> Option<f32>;
+
+    /// The number of records and groups in the plugin as given in its header,
+    /// as checked by `record_count()`, or `None` if it could not be read.
+    fn record_count(&self) -> Option<u32>;
+
+    /// The filenames of the plugin's masters, as checked by
+    /// `masters_contain()`, or an empty list if they could not be read.
+    fn masters(&self) -> Vec<String>;
+
+    /// The plugin's description, as checked by `description_contains()` and
+    /// `description_contains_text()`, or `None` if it has no description or
+    /// the description could not be read.
+    fn description(&self) -> Option<String>;
+}
+
+impl ParsedPlugin for esplugin::Plugin {
+    fn is_master_file(&self) -> bool {
+        esplugin::Plugin::is_master_file(self)
+    }
+
+    fn is_light_plugin(&self) -> bool {
+        esplugin::Plugin::is_light_plugin(self)
+    }
+
+    fn is_medium_plugin(&self) -> bool {
+        esplugin::Plugin::is_medium_plugin(self)
+    }
+
+    fn header_version(&self) -> Option<f32> {
+        esplugin::Plugin::header_version(self)
+    }
+
+    fn record_count(&self) -> Option<u32> {
+        esplugin::Plugin::record_and_group_count(self)
+    }
+
+    fn masters(&self) -> Vec<String> {
+        esplugin::Plugin::masters(self).unwrap_or_default()
+    }
+
+    fn description(&self) -> Option<String> {
+        esplugin::Plugin::description(self).unwrap_or(None)
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::function::Function;
+use crate::{Condition, Expression, State};
+
+/// Evaluate all the functions in the given expressions that read from the
+/// filesystem, using up to the given number of threads, so that their results
+/// are cached before the expressions are evaluated. Evaluating the expressions
+/// afterwards then only needs to combine the cached results, instead of doing
+/// each function's I/O in turn, which can be slow on hard drives and network
+/// filesystems.
+///
+/// Every such function is evaluated, including those that evaluating the
+/// expressions would skip because an earlier function already decided the
+/// result. Errors are not cached, so any error that a function encounters
+/// will be returned again when the expression that contains it is evaluated.
+///
+/// The threads can share the work without blocking each other, so using more
+/// threads than there are CPU cores can help when the I/O is slow. If the
+/// state has deterministic ordering enabled, only one thread is used.
+// Functions are hashed using their regexes' strings, which can't change.
+#[cfg_attr(feature = "regex", expect(clippy::mutable_key_type))]
+pub fn prefetch<'a>(
+    expressions: impl IntoIterator<Item = &'a Expression>,
+    state: &State,
+    max_threads: NonZeroUsize,
+) {
+    let mut seen = HashSet::new();
+    let mut functions = Vec::new();
+    for expression in expressions {
+        collect_functions(expression, &mut seen, &mut functions);
+    }
+
+    let next_index = AtomicUsize::new(0);
+    let thread_count = if state.deterministic_ordering {
+        1
+    } else {
+        max_threads.get().min(functions.len())
+    };
+
+    thread::scope(|scope| {
+        for _ in 0..thread_count {
+            scope.spawn(|| {
+                while let Some(function) = functions.get(next_index.fetch_add(1, Ordering::Relaxed))
+                {
+                    // The result is cached as a side effect of evaluation.
+                    let _result = function.eval_with_source(state);
+                }
+            });
+        }
+    });
+}
+
+/// Collect the distinct functions in the expression that read from the
+/// filesystem, in the order that they first appear.
+#[cfg_attr(feature = "regex", expect(clippy::mutable_key_type))]
+pub(crate) fn collect_functions<'a>(
+    expression: &'a Expression,
+    seen: &mut HashSet<&'a Function>,
+    functions: &mut Vec<&'a Function>,
+) {
+    for condition in expression.0.iter().flat_map(|c| &c.0) {
+        match condition {
+            Condition::Function(f, _) | Condition::InvertedFunction(f, _) => {
+                if f.paths().next().is_some() && seen.insert(f) {
+                    functions.push(f);
+                }
+            }
+            Condition::Expression(e) | Condition::InvertedExpression(e) => {
+                collect_functions(e, seen, functions);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+
+    use crate::{CacheEvent, GameType, ResultSource};
+
+    use super::*;
+
+    fn threads(count: usize) -> NonZeroUsize {
+        NonZeroUsize::new(count).unwrap()
+    }
+
+    #[test]
+    fn collect_functions_should_only_collect_distinct_functions_that_read_from_the_filesystem() {
+        let expression = Expression::from_str(
+            "file(\"a\") or (active(\"b.esp\") and not (file(\"a\") or checksum(\"c\", DEADBEEF)))",
+        )
+        .unwrap();
+        let mut functions = Vec::new();
+
+        collect_functions(&expression, &mut HashSet::new(), &mut functions);
+
+        let functions: Vec<String> = functions.iter().map(ToString::to_string).collect();
+        assert_eq!(vec!["file(\"a\")", "checksum(\"c\", DEADBEEF)"], functions);
+    }
+
+    #[test]
+    fn prefetch_should_cache_the_results_of_all_functions_that_read_from_the_filesystem() {
+        let state = State::new(GameType::Oblivion, PathBuf::from("."));
+        let expressions = [
+            Expression::from_str("file(\"Cargo.toml\") or file(\"missing\")").unwrap(),
+            Expression::from_str("checksum(\"Cargo.toml\", DEADBEEF) and readable(\"src\")")
+                .unwrap(),
+        ];
+
+        prefetch(&expressions, &state, threads(4));
+
+        for expression in &expressions {
+            let report = expression.eval_with_report(&state).unwrap();
+            for evaluation in report.function_evaluations() {
+                assert!(
+                    matches!(
+                        evaluation.source(),
+                        ResultSource::ConditionCache | ResultSource::CrcCache
+                    ),
+                    "{}",
+                    evaluation.function()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn prefetch_should_not_stop_if_a_function_errors() {
+        let mut state = State::new(GameType::Oblivion, PathBuf::from("."));
+        state.set_path_traversal_allowlist(Some(Vec::new()));
+        let expression =
+            Expression::from_str("file(\"../Cargo.toml\") or file(\"Cargo.toml\")").unwrap();
+
+        prefetch([&expression], &state, threads(1));
+
+        assert!(expression.eval(&state).is_err());
+        let report = Expression::from_str("file(\"Cargo.toml\")")
+            .unwrap()
+            .eval_with_report(&state)
+            .unwrap();
+        assert_eq!(
+            ResultSource::ConditionCache,
+            report.function_evaluations()[0].source()
+        );
+    }
+
+    #[test]
+    fn prefetch_should_cache_results_in_order_if_ordering_is_deterministic() {
+        let mut state = State::new(GameType::Oblivion, PathBuf::from("."));
+        state.set_deterministic_ordering(true);
+        let cached = Arc::new(Mutex::new(Vec::new()));
+        let cached_clone = Arc::clone(&cached);
+        state.add_cache_observer(Arc::new(move |event: &CacheEvent| {
+            if let CacheEvent::ConditionCached { function, .. } = event {
+                cached_clone.lock().unwrap().push(function.clone());
+            }
+        }));
+        let expression = Expression::from_str(
+            "file(\"Cargo.toml\") or readable(\"src\") or file(\"missing\") or file(\"README.md\")",
+        )
+        .unwrap();
+
+        prefetch([&expression], &state, threads(4));
+
+        assert_eq!(
+            vec![
+                "file(\"Cargo.toml\")",
+                "readable(\"src\")",
+                "file(\"missing\")",
+                "file(\"README.md\")",
+            ],
+            *cached.lock().unwrap()
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::cache::Cache;
+use crate::function::version::Version;
+use crate::State;
+
+/// Caches of data read from files that don't depend on the game being
+/// evaluated for, keyed by the paths that the files were resolved to.
+#[derive(Debug, Default)]
+pub(crate) struct SharedCaches {
+    /// The CRCs of files outside of the data paths and when they were cached.
+    pub(crate) crcs: Cache<PathBuf, (u32, SystemTime)>,
+    /// The file versions of executables, or None if they have no version.
+    pub(crate) file_versions: Cache<PathBuf, Option<Version>>,
+    /// The product versions of executables, or None if they have no version.
+    pub(crate) product_versions: Cache<PathBuf, Option<Version>>,
+}
+
+impl SharedCaches {
+    pub(crate) fn clear(&self) {
+        self.crcs.clear();
+        self.clear_versions();
+    }
+
+    pub(crate) fn clear_versions(&self) {
+        self.file_versions.clear();
+        self.product_versions.clear();
+    }
+}
+
+/// A collection of states for different games or profiles, each identified by
+/// a string, that share caches of data that doesn't depend on the game: the
+/// versions of executables and the CRCs of files outside of the data paths.
+///
+/// This is useful for hosts that manage several games in one process, where
+/// e.g. script extender executables are often checked by conditions for more
+/// than one game.
+#[derive(Debug, Default)]
+pub struct StateRegistry {
+    shared_caches: Arc<SharedCaches>,
+    states: BTreeMap<String, State>,
+}
+
+impl StateRegistry {
+    pub fn new() -> Self {
+        StateRegistry::default()
+    }
+
+    /// Adds a state with the given ID, returning the state that previously
+    /// had that ID, if any. The state's shared caches are replaced by the
+    /// registry's, so any data already cached in them is discarded.
+    pub fn insert<T: Into<String>>(&mut self, id: T, mut state: State) -> Option<State> {
+        state.shared_caches = Arc::clone(&self.shared_caches);
+        self.states.insert(id.into(), state)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&State> {
+        self.states.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut State> {
+        self.states.get_mut(id)
+    }
+
+    /// Removes and returns the state with the given ID, if there is one. The
+    /// removed state no longer shares caches with the registry's states.
+    pub fn remove(&mut self, id: &str) -> Option<State> {
+        let mut state = self.states.remove(id)?;
+        state.shared_caches = Arc::default();
+        Some(state)
+    }
+
+    /// Gets the IDs of the registry's states, in lexicographical order.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.states.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Clears the caches that are shared between the registry's states,
+    /// without clearing any of the states' other caches.
+    pub fn clear_shared_caches(&self) {
+        self.shared_caches.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{Expression, GameType, ResultSource};
+
+    use super::*;
+
+    fn state(data_path: PathBuf) -> State {
+        State::new(GameType::Oblivion, data_path)
+    }
+
+    #[test]
+    fn insert_should_replace_and_return_a_state_with_the_same_id() {
+        let mut registry = StateRegistry::new();
+
+        assert!(registry.insert("b", state(PathBuf::from("b"))).is_none());
+        assert!(registry.insert("a", state(PathBuf::from("a"))).is_none());
+        let previous = registry.insert("a", state(PathBuf::from("c"))).unwrap();
+
+        assert_eq!(PathBuf::from("a"), previous.data_path);
+        assert_eq!(PathBuf::from("c"), registry.get("a").unwrap().data_path);
+        assert_eq!(2, registry.len());
+
+        assert_eq!(vec!["a", "b"], registry.ids().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn remove_should_stop_the_state_sharing_caches() {
+        let mut registry = StateRegistry::new();
+        registry.insert("a", state(PathBuf::from("a")));
+
+        let removed = registry.remove("a").unwrap();
+
+        assert!(registry.is_empty());
+        assert!(!Arc::ptr_eq(
+            &registry.shared_caches,
+            &removed.shared_caches
+        ));
+        assert!(registry.remove("a").is_none());
+    }
+
+    #[test]
+    fn states_should_share_the_crcs_of_files_outside_their_data_paths() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let file_path = tmp_dir.path().join("shared.exe");
+        std::fs::write(&file_path, b"content").unwrap();
+        let crc = crc32fast::hash(b"content");
+        let expression =
+            Expression::from_str(&format!("checksum(\"{}\", {crc:X})", file_path.display()))
+                .unwrap();
+
+        let mut registry = StateRegistry::new();
+        registry.insert("a", state(tmp_dir.path().join("a")));
+        registry.insert("b", state(tmp_dir.path().join("b")));
+
+        let source = |registry: &StateRegistry, id: &str| {
+            let report = expression
+                .eval_with_report(registry.get(id).unwrap())
+                .unwrap();
+            assert!(report.result());
+            report.function_evaluations()[0].source()
+        };
+
+        assert_eq!(ResultSource::FreshIo, source(&registry, "a"));
+        assert_eq!(ResultSource::CrcCache, source(&registry, "b"));
+
+        registry.get_mut("a").unwrap().clear_crc_cache();
+
+        assert_eq!(ResultSource::FreshIo, source(&registry, "b"));
+    }
+}
@@ -0,0 +1,164 @@
+use std::ops::Range;
+use std::time::SystemTime;
+
+use crate::function::Function;
+
+/// Where the result of a function's evaluation came from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ResultSource {
+    /// The result was cached when the same function was previously evaluated.
+    ConditionCache,
+    /// The result was calculated using a CRC that was cached, either during a
+    /// previous evaluation or by [State::set_cached_crcs](crate::State::set_cached_crcs).
+    CrcCache,
+    /// The result was calculated using a SHA-256 digest that was cached during
+    /// a previous evaluation.
+    Sha256Cache,
+    /// The result was calculated using a plugin record count that was cached
+    /// during a previous evaluation.
+    RecordCountCache,
+    /// The result was calculated using a plugin header that was parsed and
+    /// cached while evaluating another function.
+    PluginCache,
+    /// The result was calculated using a file or directory's metadata that was
+    /// cached during a previous evaluation.
+    MetadataCache,
+    /// The result was calculated using an archive's index of files that was
+    /// cached during a previous evaluation.
+    ArchiveIndexCache,
+    /// The result was calculated using a file version that was cached, either
+    /// during a previous evaluation or by another state in the same
+    /// [StateRegistry](crate::StateRegistry).
+    VersionCache,
+    /// The result was known because a path that the function reads from was
+    /// already found to be missing while evaluating another function.
+    MissingFileCache,
+    /// The result was calculated using data that was given to the state, i.e.
+    /// its active plugins or plugin versions.
+    Preloaded,
+    /// The result doesn't depend on the state or the filesystem, e.g. because
+    /// it only depends on the platform that the crate was built for.
+    Constant,
+    /// The result was calculated by accessing the filesystem.
+    FreshIo,
+}
+
+impl ResultSource {
+    /// Check if the result was calculated using data that was cached during a
+    /// previous evaluation, or that was given to the state to cache.
+    pub fn is_cached(self) -> bool {
+        !matches!(
+            self,
+            ResultSource::Preloaded | ResultSource::Constant | ResultSource::FreshIo
+        )
+    }
+}
+
+/// The result of evaluating a single function in a condition.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionEvaluation {
+    function: String,
+    span: Range<usize>,
+    result: bool,
+    source: ResultSource,
+    computed_at: SystemTime,
+}
+
+impl FunctionEvaluation {
+    /// The function as it would be written in a condition string.
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    /// The byte range of the function in the condition string that the
+    /// expression was parsed from.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The result of the function, ignoring any `not` applied to it.
+    pub fn result(&self) -> bool {
+        self.result
+    }
+
+    pub fn source(&self) -> ResultSource {
+        self.source
+    }
+
+    /// When the result was computed. For results that came from the condition
+    /// cache this is when the result was cached, and for results that came
+    /// from the CRC cache it's when the CRC was cached.
+    pub fn computed_at(&self) -> SystemTime {
+        self.computed_at
+    }
+}
+
+/// The result of evaluating an expression, along with the results of the
+/// functions that were evaluated in the order they were evaluated. Functions
+/// that were skipped due to short-circuiting are not included.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EvaluationReport {
+    result: bool,
+    function_evaluations: Vec<FunctionEvaluation>,
+}
+
+impl EvaluationReport {
+    pub(crate) fn new(result: bool, function_evaluations: Vec<FunctionEvaluation>) -> Self {
+        EvaluationReport {
+            result,
+            function_evaluations,
+        }
+    }
+
+    pub fn result(&self) -> bool {
+        self.result
+    }
+
+    pub fn function_evaluations(&self) -> &[FunctionEvaluation] {
+        &self.function_evaluations
+    }
+}
+
+/// Receives the results of functions as they are evaluated.
+pub(crate) trait EvalObserver {
+    fn function_evaluated(
+        &mut self,
+        function: &Function,
+        span: Range<usize>,
+        result: bool,
+        source: ResultSource,
+        computed_at: SystemTime,
+    );
+}
+
+impl EvalObserver for () {
+    fn function_evaluated(
+        &mut self,
+        _: &Function,
+        _: Range<usize>,
+        _: bool,
+        _: ResultSource,
+        _: SystemTime,
+    ) {
+    }
+}
+
+impl EvalObserver for Vec<FunctionEvaluation> {
+    fn function_evaluated(
+        &mut self,
+        function: &Function,
+        span: Range<usize>,
+        result: bool,
+        source: ResultSource,
+        computed_at: SystemTime,
+    ) {
+        self.push(FunctionEvaluation {
+            function: function.to_string(),
+            span,
+            result,
+            source,
+            computed_at,
+        });
+    }
+}
@@ -0,0 +1,252 @@
+use std::io;
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use crate::function::eval::check_cancellation;
+use crate::{Error, State};
+
+/// How to retry filesystem operations that fail with errors that are likely
+/// to be transient, such as timeouts and dropped connections when the game is
+/// installed on a network share or in a folder synced by a cloud storage
+/// client. The delay before each retry is double the delay before the
+/// previous retry, up to the maximum delay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// The maximum delay before a retry unless another is set.
+    pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(5);
+
+    /// Create a policy that retries a failed operation up to `max_retries`
+    /// times, waiting `initial_delay` before the first retry. The delay is
+    /// capped at [RetryPolicy::DEFAULT_MAX_DELAY], or at `initial_delay` if
+    /// that is longer.
+    pub fn new(max_retries: u32, initial_delay: Duration) -> Self {
+        RetryPolicy {
+            max_retries,
+            initial_delay,
+            max_delay: initial_delay.max(Self::DEFAULT_MAX_DELAY),
+        }
+    }
+
+    /// Sets the maximum delay before a retry.
+    #[must_use]
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Get the delay before the given retry, counting from zero.
+    pub fn delay(&self, retry: u32) -> Duration {
+        1_u32
+            .checked_shl(retry)
+            .and_then(|factor| self.initial_delay.checked_mul(factor))
+            .unwrap_or(Duration::MAX)
+            .min(self.max_delay)
+    }
+}
+
+/// Check if the error is likely to go away if the operation is retried.
+fn is_transient(error: &io::Error) -> bool {
+    // ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION, ERROR_UNEXP_NET_ERR,
+    // ERROR_NETNAME_DELETED and ERROR_SEM_TIMEOUT.
+    #[cfg(windows)]
+    if matches!(error.raw_os_error(), Some(32 | 33 | 59 | 64 | 121)) {
+        return true;
+    }
+
+    matches!(
+        error.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ResourceBusy
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::NetworkDown
+            | io::ErrorKind::NetworkUnreachable
+            | io::ErrorKind::HostUnreachable
+            | io::ErrorKind::StaleNetworkFileHandle
+    )
+}
+
+/// Run the operation on the given path, retrying it according to the state's
+/// policy while it fails with a transient error. If it still fails with a
+/// transient error once the retries have been used up,
+/// [Error::RetriesExhausted] is returned so that the failure isn't mistaken
+/// for the path being missing or unreadable. Other errors are left for the
+/// caller to handle, and without a policy the operation is only run once.
+///
+/// Cancellation and the state's deadline are checked before each retry, and
+/// the delay before a retry is cut short if the deadline would pass first.
+pub(crate) fn with_retries<T>(
+    state: &State,
+    path: &Path,
+    mut operation: impl FnMut() -> io::Result<T>,
+) -> Result<io::Result<T>, Error> {
+    let Some(policy) = state.retry_policy.as_ref() else {
+        return Ok(operation());
+    };
+
+    let mut retry = 0;
+    loop {
+        match operation() {
+            Err(e) if is_transient(&e) => {
+                if retry >= policy.max_retries {
+                    return Err(Error::RetriesExhausted(
+                        path.to_path_buf(),
+                        retry.saturating_add(1),
+                        e,
+                    ));
+                }
+
+                check_cancellation(state)?;
+                sleep(delay_until_deadline(state, policy.delay(retry)));
+                check_cancellation(state)?;
+
+                retry = retry.saturating_add(1);
+            }
+            result => return Ok(result),
+        }
+    }
+}
+
+fn delay_until_deadline(state: &State, delay: Duration) -> Duration {
+    match state.deadline {
+        Some(deadline) => delay.min(deadline.saturating_duration_since(Instant::now())),
+        None => delay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{CancellationToken, GameType};
+
+    fn state(policy: Option<RetryPolicy>) -> State {
+        let mut state = State::new(GameType::Oblivion, PathBuf::from("."));
+        state.set_retry_policy(policy);
+        state
+    }
+
+    fn failing_operation(
+        failures: &mut Vec<io::ErrorKind>,
+    ) -> impl FnMut() -> io::Result<u32> + '_ {
+        failures.reverse();
+        move || match failures.pop() {
+            Some(kind) => Err(io::Error::from(kind)),
+            None => Ok(1),
+        }
+    }
+
+    #[test]
+    fn delay_should_double_for_each_retry_up_to_the_maximum() {
+        let policy = RetryPolicy::new(5, Duration::from_millis(10))
+            .with_max_delay(Duration::from_millis(50));
+
+        let delays: Vec<_> = (0..5).map(|retry| policy.delay(retry)).collect();
+
+        assert_eq!(
+            vec![10, 20, 40, 50, 50],
+            delays.iter().map(Duration::as_millis).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            RetryPolicy::DEFAULT_MAX_DELAY,
+            RetryPolicy::new(1, Duration::from_millis(10)).delay(64)
+        );
+    }
+
+    #[test]
+    fn with_retries_should_retry_transient_errors() {
+        let policy = RetryPolicy::new(2, Duration::ZERO);
+        let mut failures = vec![io::ErrorKind::TimedOut, io::ErrorKind::ConnectionReset];
+
+        let result = with_retries(
+            &state(Some(policy)),
+            Path::new("a"),
+            failing_operation(&mut failures),
+        );
+
+        assert_eq!(1, result.unwrap().unwrap());
+    }
+
+    #[test]
+    fn with_retries_should_error_if_transient_errors_outlast_the_retries() {
+        let policy = RetryPolicy::new(1, Duration::ZERO);
+        let mut failures = vec![io::ErrorKind::TimedOut, io::ErrorKind::TimedOut];
+
+        let result = with_retries(
+            &state(Some(policy)),
+            Path::new("a"),
+            failing_operation(&mut failures),
+        );
+
+        assert!(matches!(result, Err(Error::RetriesExhausted(p, 2, _)) if p == Path::new("a")));
+    }
+
+    #[test]
+    fn with_retries_should_not_retry_other_errors() {
+        let policy = RetryPolicy::new(2, Duration::ZERO);
+        let mut failures = vec![io::ErrorKind::NotFound, io::ErrorKind::TimedOut];
+
+        let result = with_retries(
+            &state(Some(policy)),
+            Path::new("a"),
+            failing_operation(&mut failures),
+        );
+
+        assert_eq!(io::ErrorKind::NotFound, result.unwrap().unwrap_err().kind());
+    }
+
+    #[test]
+    fn with_retries_should_run_the_operation_once_without_a_policy() {
+        let mut failures = vec![io::ErrorKind::TimedOut];
+
+        let result = with_retries(
+            &state(None),
+            Path::new("a"),
+            failing_operation(&mut failures),
+        );
+
+        assert_eq!(io::ErrorKind::TimedOut, result.unwrap().unwrap_err().kind());
+    }
+
+    #[test]
+    fn with_retries_should_not_retry_once_cancelled() {
+        let mut state = state(Some(RetryPolicy::new(2, Duration::ZERO)));
+        let token = CancellationToken::new();
+        token.cancel();
+        state.set_cancellation_token(Some(token));
+        let mut failures = vec![io::ErrorKind::TimedOut, io::ErrorKind::ConnectionReset];
+
+        let result = with_retries(&state, Path::new("a"), failing_operation(&mut failures));
+
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert_eq!(vec![io::ErrorKind::ConnectionReset], failures);
+    }
+
+    #[test]
+    fn with_retries_should_not_wait_past_the_deadline() {
+        let mut state = state(Some(RetryPolicy::new(2, Duration::from_secs(30))));
+        let start = Instant::now();
+        state.set_deadline(Some(start + Duration::from_millis(10)));
+        let mut failures = vec![io::ErrorKind::TimedOut];
+
+        let result = with_retries(&state, Path::new("a"), failing_operation(&mut failures));
+
+        assert!(matches!(result, Err(Error::DeadlineExceeded)));
+        assert!(start.elapsed() < Duration::from_secs(30));
+    }
+}
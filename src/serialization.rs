@@ -0,0 +1,163 @@
+//! Expressions, functions and comparison operators are serialized as they
+//! would be written in a condition string, and deserialized by parsing that
+//! string using the default parse options. This keeps the serialized form
+//! stable as the crate's internal representation changes, and means that
+//! regexes are validated and compiled as they're deserialized.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::function::{ComparisonOperator, Function};
+use crate::{Error, Expression, ParseOptions};
+
+/// Deserializes a string by parsing it with the given function, which
+/// returns a description of the problem if the string can't be parsed.
+struct ParsingVisitor<T> {
+    expecting: &'static str,
+    parse: fn(&str) -> Result<T, String>,
+}
+
+impl<T> Visitor<'_> for ParsingVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.expecting)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        (self.parse)(v).map_err(E::custom)
+    }
+}
+
+impl Serialize for Expression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(ParsingVisitor {
+            expecting: "a condition string",
+            parse: |s| Expression::from_str(s).map_err(|e| e.to_string()),
+        })
+    }
+}
+
+impl Serialize for Function {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Function {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(ParsingVisitor {
+            expecting: "a condition function",
+            parse: |s| match Function::parse(s, &ParseOptions::default()) {
+                Ok(("", function)) => Ok(function),
+                Ok((remaining_input, _)) => {
+                    Err(Error::UnconsumedInput(remaining_input.to_owned()).to_string())
+                }
+                Err(e) => Err(Error::from(e).to_string()),
+            },
+        })
+    }
+}
+
+impl Serialize for ComparisonOperator {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ComparisonOperator {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(ParsingVisitor {
+            expecting: "a comparison operator",
+            parse: |s| match ComparisonOperator::parse(s) {
+                Ok(("", operator)) => Ok(operator),
+                _ => Err(format!("\"{s}\" is not a comparison operator")),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expression_should_be_serialized_as_a_condition_string() {
+        let expression =
+            Expression::from_str("file(\"a.esp\")   and not checksum(\"b.esp\", deadbeef)")
+                .unwrap();
+
+        let json = serde_json::to_string(&expression).unwrap();
+
+        assert_eq!(
+            "\"file(\\\"a.esp\\\") and not checksum(\\\"b.esp\\\", DEADBEEF)\"",
+            json
+        );
+    }
+
+    #[test]
+    fn expression_should_round_trip() {
+        let expression = Expression::from_str(
+            "(many(\"meshes/.*\\\\.nif\") or not is_master(\"a.esp\")) and version(\"a.esp\", \"1.0\", >=)",
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&expression).unwrap();
+        let deserialized: Expression = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(expression, deserialized);
+    }
+
+    #[test]
+    fn expression_deserialization_should_error_if_the_string_cannot_be_parsed() {
+        let error = serde_json::from_str::<Expression>("\"file(\\\"a.esp\\\") xor\"").unwrap_err();
+
+        assert!(error.to_string().contains("xor"));
+    }
+
+    #[test]
+    fn function_should_round_trip() {
+        let (_, function) = Function::parse(
+            "checksum(\"a.esp\", DEADBEEF, 1234)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&function).unwrap();
+        let deserialized: Function = serde_json::from_str(&json).unwrap();
+
+        assert_eq!("\"checksum(\\\"a.esp\\\", DEADBEEF, 1234)\"", json);
+        assert_eq!(function, deserialized);
+    }
+
+    #[test]
+    fn function_deserialization_should_error_if_there_is_more_than_one_function() {
+        let result = serde_json::from_str::<Function>("\"file(\\\"a\\\") and file(\\\"b\\\")\"");
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn comparison_operator_should_round_trip() {
+        let json = serde_json::to_string(&ComparisonOperator::LessThanOrEqual).unwrap();
+        let deserialized: ComparisonOperator = serde_json::from_str(&json).unwrap();
+
+        assert_eq!("\"<=\"", json);
+        assert_eq!(ComparisonOperator::LessThanOrEqual, deserialized);
+    }
+
+    #[test]
+    fn comparison_operator_deserialization_should_error_if_the_string_is_not_an_operator() {
+        assert!(serde_json::from_str::<ComparisonOperator>("\"=\"").is_err());
+        assert!(serde_json::from_str::<ComparisonOperator>("\"<=>\"").is_err());
+    }
+}
@@ -0,0 +1,496 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use serde_json::{json, Map, Value};
+
+use crate::{Error, Expression, ParseOptions, State};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const CONDITION_ERROR: i64 = -32000;
+
+/// The number of parsed expressions that are cached before the cache is
+/// cleared to make room for more.
+const MAX_CACHED_EXPRESSIONS: usize = 10_000;
+
+/// A JSON-RPC 2.0 server that owns a [State] and answers requests to parse and
+/// evaluate conditions against it. Because the state is kept between requests,
+/// its caches stay warm, and parsed expressions are also cached by their
+/// condition strings, up to a limit.
+///
+/// Requests and responses are each a single line of JSON. The supported
+/// methods are:
+///
+/// - `parse`, with a `condition` string param, which returns the condition
+///   in its normalised form.
+/// - `eval`, with a `condition` string param, which returns the condition's
+///   result as a boolean.
+/// - `set_active_plugins`, with a `plugins` array of strings.
+/// - `set_plugin_versions`, with a `versions` object mapping plugin names to
+///   version strings.
+/// - `set_additional_data_paths`, with a `paths` array of strings.
+/// - `clear_condition_cache`, which takes no params and also clears the
+///   cached parsed expressions.
+///
+/// The setter methods return null. Batch requests are not supported.
+#[derive(Debug)]
+pub struct Server {
+    state: State,
+    parse_options: ParseOptions,
+    expressions: HashMap<String, Expression>,
+}
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<Error> for RpcError {
+    fn from(error: Error) -> Self {
+        RpcError::new(CONDITION_ERROR, error.to_string())
+    }
+}
+
+impl Server {
+    pub fn new(state: State) -> Self {
+        Server {
+            state,
+            parse_options: ParseOptions::default(),
+            expressions: HashMap::new(),
+        }
+    }
+
+    /// Sets the options used to parse conditions given in requests.
+    #[must_use]
+    pub fn with_parse_options(mut self, parse_options: ParseOptions) -> Self {
+        self.parse_options = parse_options;
+        self.expressions.clear();
+        self
+    }
+
+    /// Read requests from the given reader line by line until it is exhausted,
+    /// writing each response to the given writer. Blank lines are ignored, and
+    /// notifications (requests without an ID) get no response.
+    pub fn serve<R: BufRead, W: Write>(&mut self, reader: R, mut writer: W) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if let Some(response) = self.handle_request(&line) {
+                writeln!(writer, "{response}")?;
+                writer.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle a single JSON-RPC request, returning the serialised response, or
+    /// `None` if the request was a notification.
+    pub fn handle_request(&mut self, request: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(request) {
+            Ok(request) => request,
+            Err(e) => {
+                return Some(error_response(
+                    &Value::Null,
+                    &RpcError::new(PARSE_ERROR, e.to_string()),
+                ))
+            }
+        };
+
+        let id = request.get("id").cloned();
+
+        let result = self.dispatch(&request);
+
+        match (id, result) {
+            (Some(id), Ok(result)) => Some(
+                json!({
+                    "jsonrpc": "2.0",
+                    "result": result,
+                    "id": id,
+                })
+                .to_string(),
+            ),
+            (Some(id), Err(e)) => Some(error_response(&id, &e)),
+            (None, Err(e)) if e.code == INVALID_REQUEST => Some(error_response(&Value::Null, &e)),
+            (None, _) => None,
+        }
+    }
+
+    fn dispatch(&mut self, request: &Value) -> Result<Value, RpcError> {
+        if request.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+            return Err(RpcError::new(
+                INVALID_REQUEST,
+                "Expected jsonrpc to be \"2.0\"",
+            ));
+        }
+
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            return Err(RpcError::new(
+                INVALID_REQUEST,
+                "Expected method to be a string",
+            ));
+        };
+
+        let empty_params = Map::new();
+        let params = match request.get("params") {
+            None => &empty_params,
+            Some(Value::Object(params)) => params,
+            Some(_) => {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    "Expected params to be an object",
+                ))
+            }
+        };
+
+        match method {
+            "parse" => {
+                let expression = cached_expression(
+                    &mut self.expressions,
+                    &self.parse_options,
+                    string_param(params, "condition")?,
+                )?;
+                Ok(Value::String(expression.to_string()))
+            }
+            "eval" => {
+                let expression = cached_expression(
+                    &mut self.expressions,
+                    &self.parse_options,
+                    string_param(params, "condition")?,
+                )?;
+                Ok(Value::Bool(expression.eval(&self.state)?))
+            }
+            "set_active_plugins" => {
+                let plugins = string_array_param(params, "plugins")?;
+                self.state.set_active_plugins(&plugins);
+                Ok(Value::Null)
+            }
+            "set_plugin_versions" => {
+                let versions = string_map_param(params, "versions")?;
+                self.state.set_plugin_versions(&versions);
+                Ok(Value::Null)
+            }
+            "set_additional_data_paths" => {
+                let paths = string_array_param(params, "paths")?
+                    .into_iter()
+                    .map(PathBuf::from)
+                    .collect();
+                self.state.set_additional_data_paths(paths);
+                Ok(Value::Null)
+            }
+            "clear_condition_cache" => {
+                self.state
+                    .clear_condition_cache()
+                    .map_err(|e| RpcError::new(CONDITION_ERROR, e.to_string()))?;
+                self.expressions.clear();
+                Ok(Value::Null)
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method \"{method}\""),
+            )),
+        }
+    }
+}
+
+fn cached_expression<'a>(
+    expressions: &'a mut HashMap<String, Expression>,
+    parse_options: &ParseOptions,
+    condition: &str,
+) -> Result<&'a Expression, Error> {
+    if expressions.len() >= MAX_CACHED_EXPRESSIONS && !expressions.contains_key(condition) {
+        // Clearing the whole cache is simpler than tracking which expressions
+        // were used least recently, and expressions are cheap to parse again.
+        expressions.clear();
+    }
+
+    match expressions.entry(condition.to_owned()) {
+        Entry::Occupied(entry) => Ok(entry.into_mut()),
+        Entry::Vacant(entry) => {
+            let expression = Expression::parse_with_options(condition, parse_options)?;
+            Ok(entry.insert(expression))
+        }
+    }
+}
+
+fn string_param<'a>(params: &'a Map<String, Value>, name: &str) -> Result<&'a str, RpcError> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_param(name, "a string"))
+}
+
+fn string_array_param<'a>(
+    params: &'a Map<String, Value>,
+    name: &str,
+) -> Result<Vec<&'a str>, RpcError> {
+    params
+        .get(name)
+        .and_then(Value::as_array)
+        .and_then(|values| values.iter().map(Value::as_str).collect())
+        .ok_or_else(|| invalid_param(name, "an array of strings"))
+}
+
+fn string_map_param<'a>(
+    params: &'a Map<String, Value>,
+    name: &str,
+) -> Result<Vec<(&'a str, &'a str)>, RpcError> {
+    params
+        .get(name)
+        .and_then(Value::as_object)
+        .and_then(|values| {
+            values
+                .iter()
+                .map(|(key, value)| value.as_str().map(|value| (key.as_str(), value)))
+                .collect()
+        })
+        .ok_or_else(|| invalid_param(name, "an object with string values"))
+}
+
+fn invalid_param(name: &str, expected: &str) -> RpcError {
+    RpcError::new(
+        INVALID_PARAMS,
+        format!("Expected param \"{name}\" to be {expected}"),
+    )
+}
+
+fn error_response(id: &Value, error: &RpcError) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "error": {
+            "code": error.code,
+            "message": error.message,
+        },
+        "id": id,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GameType;
+
+    use super::*;
+
+    fn server() -> Server {
+        Server::new(State::new(GameType::Oblivion, PathBuf::from(".")))
+    }
+
+    fn request(server: &mut Server, request: &Value) -> Value {
+        let response = server.handle_request(&request.to_string()).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn handle_request_should_return_the_normalised_condition_for_parse() {
+        let mut server = server();
+
+        let response = request(
+            &mut server,
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "parse",
+                "params": { "condition": "file(\"Cargo.toml\")  or  not active(\"a.esp\")" },
+                "id": 1_u32,
+            }),
+        );
+
+        assert_eq!(
+            json!({
+                "jsonrpc": "2.0",
+                "result": "file(\"Cargo.toml\") or not active(\"a.esp\")",
+                "id": 1_u32,
+            }),
+            response
+        );
+    }
+
+    #[test]
+    fn handle_request_should_evaluate_conditions_against_the_state() {
+        let mut server = server();
+        let eval = json!({
+            "jsonrpc": "2.0",
+            "method": "eval",
+            "params": { "condition": "active(\"Blank.esp\")" },
+            "id": "a",
+        });
+
+        assert_eq!(Value::Bool(false), request(&mut server, &eval)["result"]);
+
+        let set = json!({
+            "jsonrpc": "2.0",
+            "method": "set_active_plugins",
+            "params": { "plugins": ["Blank.esp"] },
+            "id": "b",
+        });
+        assert_eq!(Value::Null, request(&mut server, &set)["result"]);
+
+        assert_eq!(Value::Bool(true), request(&mut server, &eval)["result"]);
+    }
+
+    #[test]
+    fn handle_request_should_set_plugin_versions() {
+        let mut server = server();
+
+        request(
+            &mut server,
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "set_plugin_versions",
+                "params": { "versions": { "Cargo.toml": "5" } },
+                "id": 1_u32,
+            }),
+        );
+        let response = request(
+            &mut server,
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "eval",
+                "params": { "condition": "version(\"Cargo.toml\", \"5\", ==)" },
+                "id": 2_u32,
+            }),
+        );
+
+        assert_eq!(Value::Bool(true), response["result"]);
+    }
+
+    #[test]
+    fn handle_request_should_return_a_condition_error_for_an_invalid_condition() {
+        let mut server = server();
+
+        let response = request(
+            &mut server,
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "eval",
+                "params": { "condition": "foo(\"bar\")" },
+                "id": 1_u32,
+            }),
+        );
+
+        assert_eq!(json!(CONDITION_ERROR), response["error"]["code"]);
+        assert_eq!(json!(1_u32), response["id"]);
+    }
+
+    #[test]
+    fn handle_request_should_return_an_error_for_invalid_params() {
+        let mut server = server();
+
+        let response = request(
+            &mut server,
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "set_active_plugins",
+                "params": { "plugins": [1_u32] },
+                "id": 1_u32,
+            }),
+        );
+
+        assert_eq!(json!(INVALID_PARAMS), response["error"]["code"]);
+    }
+
+    #[test]
+    fn handle_request_should_return_an_error_for_an_unknown_method() {
+        let mut server = server();
+
+        let response = request(
+            &mut server,
+            &json!({ "jsonrpc": "2.0", "method": "foo", "id": 1_u32 }),
+        );
+
+        assert_eq!(json!(METHOD_NOT_FOUND), response["error"]["code"]);
+    }
+
+    #[test]
+    fn handle_request_should_return_an_error_for_invalid_json() {
+        let mut server = server();
+
+        let response: Value = serde_json::from_str(&server.handle_request("{").unwrap()).unwrap();
+
+        assert_eq!(json!(PARSE_ERROR), response["error"]["code"]);
+        assert_eq!(Value::Null, response["id"]);
+    }
+
+    #[test]
+    fn handle_request_should_not_respond_to_notifications() {
+        let mut server = server();
+
+        let response = server.handle_request(
+            &json!({ "jsonrpc": "2.0", "method": "clear_condition_cache" }).to_string(),
+        );
+
+        assert!(response.is_none());
+    }
+
+    #[test]
+    fn handle_request_should_clear_cached_expressions_for_clear_condition_cache() {
+        let mut server = server();
+        request(
+            &mut server,
+            &json!({ "jsonrpc": "2.0", "method": "parse", "params": { "condition": "file(\"a\")" }, "id": 1 }),
+        );
+        assert_eq!(1, server.expressions.len());
+
+        request(
+            &mut server,
+            &json!({ "jsonrpc": "2.0", "method": "clear_condition_cache", "id": 2 }),
+        );
+
+        assert!(server.expressions.is_empty());
+    }
+
+    #[test]
+    fn cached_expression_should_clear_the_cache_once_it_is_full() {
+        let mut expressions = HashMap::new();
+        let parse_options = ParseOptions::default();
+        for i in 0..MAX_CACHED_EXPRESSIONS {
+            cached_expression(&mut expressions, &parse_options, &format!("file(\"{i}\")")).unwrap();
+        }
+
+        cached_expression(&mut expressions, &parse_options, "file(\"0\")").unwrap();
+        assert_eq!(MAX_CACHED_EXPRESSIONS, expressions.len());
+
+        cached_expression(&mut expressions, &parse_options, "file(\"new\")").unwrap();
+        assert_eq!(1, expressions.len());
+    }
+
+    #[test]
+    fn serve_should_write_one_response_line_per_request() {
+        let mut server = server();
+        let input = concat!(
+            "{\"jsonrpc\": \"2.0\", \"method\": \"eval\", \"params\": {\"condition\": \"file(\\\"Cargo.toml\\\")\"}, \"id\": 1}\n",
+            "\n",
+            "{\"jsonrpc\": \"2.0\", \"method\": \"clear_condition_cache\"}\n",
+            "{\"jsonrpc\": \"2.0\", \"method\": \"eval\", \"params\": {\"condition\": \"file(\\\"missing\\\")\"}, \"id\": 2}\n",
+        );
+        let mut output = Vec::new();
+
+        server.serve(input.as_bytes(), &mut output).unwrap();
+
+        let lines: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(2, lines.len());
+        assert_eq!(Value::Bool(true), lines[0]["result"]);
+        assert_eq!(Value::Bool(false), lines[1]["result"]);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{CompoundCondition, Condition, Expression};
+
+/// Simplify the expression bottom-up. Constant conditions can't be written as
+/// literals, so a part of the expression that is found to be constant is
+/// reduced to one of the constant conditions that it contains.
+pub(crate) fn simplify_expression(expression: &Expression) -> Expression {
+    let mut compound_conditions = Vec::new();
+    let mut false_compound_condition = None;
+
+    for compound_condition in &expression.0 {
+        let compound_condition = simplify_compound_condition(compound_condition);
+
+        match constant_value(&compound_condition) {
+            Some(true) => return Expression(vec![compound_condition]),
+            Some(false) => {
+                false_compound_condition.get_or_insert(compound_condition);
+                continue;
+            }
+            None => {}
+        }
+
+        // A compound condition that is only a parenthesised expression can be
+        // replaced by that expression's compound conditions.
+        if let [Condition::Expression(e)] = compound_condition.0.as_slice() {
+            for compound_condition in &e.0 {
+                push_unique(&mut compound_conditions, compound_condition.clone());
+            }
+        } else {
+            push_unique(&mut compound_conditions, compound_condition);
+        }
+    }
+
+    if compound_conditions.is_empty() {
+        compound_conditions.extend(false_compound_condition);
+    }
+
+    Expression(compound_conditions)
+}
+
+fn simplify_compound_condition(compound_condition: &CompoundCondition) -> CompoundCondition {
+    let mut conditions = Vec::new();
+    let mut true_condition = None;
+
+    for condition in &compound_condition.0 {
+        let condition = simplify_condition(condition);
+
+        match condition_constant_value(&condition) {
+            Some(false) => return CompoundCondition(vec![condition]),
+            Some(true) => {
+                true_condition.get_or_insert(condition);
+                continue;
+            }
+            None => {}
+        }
+
+        // A parenthesised expression with only one compound condition can be
+        // replaced by that compound condition's conditions.
+        match condition {
+            Condition::Expression(Expression(mut compound_conditions))
+                if compound_conditions.len() == 1 =>
+            {
+                for condition in compound_conditions.pop().into_iter().flat_map(|c| c.0) {
+                    push_unique(&mut conditions, condition);
+                }
+            }
+            condition => push_unique(&mut conditions, condition),
+        }
+    }
+
+    if conditions.is_empty() {
+        conditions.extend(true_condition);
+    }
+
+    CompoundCondition(conditions)
+}
+
+/// Parenthesised expressions that contain a single condition are replaced by
+/// that condition, which removes double negations.
+fn simplify_condition(condition: &Condition) -> Condition {
+    match condition {
+        Condition::Function(_, _) | Condition::InvertedFunction(_, _) => condition.clone(),
+        Condition::Expression(e) => {
+            into_single_condition(simplify_expression(e)).unwrap_or_else(Condition::Expression)
+        }
+        Condition::InvertedExpression(e) => match into_single_condition(simplify_expression(e)) {
+            Ok(condition) => negate(condition),
+            Err(e) => Condition::InvertedExpression(e),
+        },
+    }
+}
+
+fn into_single_condition(mut expression: Expression) -> Result<Condition, Expression> {
+    match expression.0.as_mut_slice() {
+        [CompoundCondition(conditions)] if conditions.len() == 1 => {
+            conditions.pop().ok_or(expression)
+        }
+        _ => Err(expression),
+    }
+}
+
+fn negate(condition: Condition) -> Condition {
+    match condition {
+        Condition::Function(f, span) => Condition::InvertedFunction(f, span),
+        Condition::InvertedFunction(f, span) => Condition::Function(f, span),
+        Condition::Expression(e) => Condition::InvertedExpression(e),
+        Condition::InvertedExpression(e) => Condition::Expression(e),
+    }
+}
+
+/// Only a compound condition that has been simplified to a single condition
+/// can have a constant value.
+fn constant_value(compound_condition: &CompoundCondition) -> Option<bool> {
+    match compound_condition.0.as_slice() {
+        [condition] => condition_constant_value(condition),
+        _ => None,
+    }
+}
+
+fn condition_constant_value(condition: &Condition) -> Option<bool> {
+    match condition {
+        Condition::Function(f, _) => f.constant_result(),
+        Condition::InvertedFunction(f, _) => f.constant_result().map(|r| !r),
+        Condition::Expression(_) | Condition::InvertedExpression(_) => None,
+    }
+}
+
+/// Repeating a condition that's joined by 'and' or 'or' doesn't change the
+/// result, so only its first occurrence is kept.
+fn push_unique<T: PartialEq>(items: &mut Vec<T>, item: T) {
+    if !items.contains(&item) {
+        items.push(item);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    /// A platform function that is false on every platform that the tests
+    /// run on.
+    const FALSE: &str = if cfg!(windows) {
+        "is_linux()"
+    } else {
+        "is_windows()"
+    };
+
+    fn simplify(condition: &str) -> String {
+        Expression::from_str(condition)
+            .unwrap()
+            .simplify()
+            .to_string()
+    }
+
+    #[test]
+    fn simplify_should_not_change_an_expression_with_nothing_to_simplify() {
+        let condition = "file(\"a\") and not many(\"b/.*\") or (active(\"c\") or not file(\"d\")) and file(\"e\")";
+
+        assert_eq!(condition, simplify(condition));
+    }
+
+    #[test]
+    fn simplify_should_remove_double_negations() {
+        assert_eq!("file(\"a\")", simplify("not (not file(\"a\"))"));
+        assert_eq!(
+            "file(\"c\") and (file(\"a\") or file(\"b\"))",
+            simplify("file(\"c\") and not (not (file(\"a\") or file(\"b\")))")
+        );
+    }
+
+    #[test]
+    fn simplify_should_remove_unnecessary_parentheses() {
+        assert_eq!(
+            "file(\"a\") and file(\"b\") and file(\"c\")",
+            simplify("file(\"a\") and (file(\"b\") and (file(\"c\")))")
+        );
+        assert_eq!(
+            "file(\"a\") or file(\"b\") or file(\"c\")",
+            simplify("file(\"a\") or (file(\"b\") or file(\"c\"))")
+        );
+        assert_eq!(
+            "not (file(\"a\") and file(\"b\"))",
+            simplify("not ((file(\"a\") and file(\"b\")))")
+        );
+    }
+
+    #[test]
+    fn simplify_should_deduplicate_repeated_conditions() {
+        assert_eq!(
+            "file(\"a\") or file(\"b\")",
+            simplify("file(\"a\") or file(\"b\") or file(\"A\")")
+        );
+        assert_eq!(
+            "file(\"a\") and file(\"b\")",
+            simplify("file(\"a\") and file(\"b\") and file(\"a\")")
+        );
+        assert_eq!(
+            "file(\"a\") and file(\"b\")",
+            simplify("(file(\"a\") and file(\"b\")) or (file(\"a\") and file(\"b\"))")
+        );
+    }
+
+    #[test]
+    fn simplify_should_fold_false_constants() {
+        assert_eq!(
+            "file(\"b\")",
+            simplify(&format!("{FALSE} and file(\"a\") or file(\"b\")"))
+        );
+        assert_eq!(FALSE, simplify(&format!("file(\"a\") and {FALSE}")));
+        assert_eq!(
+            FALSE,
+            simplify(&format!("file(\"a\") and ({FALSE} or not (not {FALSE}))"))
+        );
+    }
+
+    #[test]
+    fn simplify_should_fold_true_constants() {
+        assert_eq!(
+            "file(\"a\")",
+            simplify(&format!("not {FALSE} and file(\"a\")"))
+        );
+        assert_eq!(
+            format!("not {FALSE}"),
+            simplify(&format!("file(\"a\") or not {FALSE} or file(\"b\")"))
+        );
+        assert_eq!(
+            format!("not {FALSE}"),
+            simplify(&format!("not ({FALSE} and file(\"a\"))"))
+        );
+    }
+
+    #[test]
+    fn simplify_should_keep_function_spans() {
+        let expression = Expression::from_str("not (not file(\"a\"))").unwrap();
+
+        match expression.simplify().0.as_slice() {
+            [CompoundCondition(conditions)] => match conditions.as_slice() {
+                [Condition::Function(_, span)] => assert_eq!(&(9..18), span),
+                _ => panic!("Expected a single function"),
+            },
+            _ => panic!("Expected a single compound condition"),
+        }
+    }
+}
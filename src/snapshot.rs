@@ -0,0 +1,563 @@
+use std::fs::{create_dir_all, read_dir, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sevenz_rust::{Password, SevenZReader};
+use tempfile::TempDir;
+use time::OffsetDateTime;
+use zip::extra_fields::ExtraField;
+use zip::ZipArchive;
+
+use crate::{Error, GameType, State};
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const EMPTY_ZIP_MAGIC: &[u8] = b"PK\x05\x06";
+const SEVEN_ZIP_MAGIC: &[u8] = b"7z\xBC\xAF\x27\x1C";
+
+/// The number of seconds between the start of 1601, which NT timestamps count
+/// from, and the Unix epoch.
+const NT_EPOCH_OFFSET_SECS: u64 = 11_644_473_600;
+const NT_TICK_NANOS: u64 = 100;
+
+const TEMP_DIR_PREFIX: &str = "loot-condition-interpreter-snapshot-";
+
+/// A copy of a game's data directory extracted from a zip or 7z archive, so
+/// that conditions can be evaluated against a snapshot of a user's install,
+/// e.g. one uploaded to a support channel, without reconstructing it on disk.
+///
+/// The archive is extracted into a new temporary directory that is deleted
+/// when the snapshot is dropped. The archive's root is treated as the data
+/// directory, unless it holds nothing but a single directory, as it does when
+/// a data directory is compressed as a whole, in which case that directory is
+/// treated as the data directory. Modification times are kept so that
+/// `is_newer_than()` gives the same results as it would have for the
+/// original files.
+#[derive(Debug)]
+pub struct DataSnapshot {
+    // This is only held so that the directory is deleted when the snapshot is
+    // dropped.
+    _directory: TempDir,
+    data_path: PathBuf,
+}
+
+impl DataSnapshot {
+    /// The largest size that an archive entry can have once extracted.
+    pub const MAX_ENTRY_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+    /// The largest total size that an archive's entries can have once
+    /// extracted.
+    pub const MAX_TOTAL_SIZE: u64 = 16 * 1024 * 1024 * 1024;
+
+    /// Extract the archive at the given path. The archive's format is
+    /// detected from its content, not its file extension. Only stored and
+    /// deflated zip entries and unencrypted 7z archives are supported.
+    ///
+    /// As archives can hold entries that decompress to far more than their
+    /// own size, extraction fails if an entry would be larger than
+    /// [DataSnapshot::MAX_ENTRY_SIZE] or the entries would be larger than
+    /// [DataSnapshot::MAX_TOTAL_SIZE] in total.
+    pub fn extract(archive_path: &Path) -> Result<Self, Error> {
+        Self::extract_with_limits(
+            archive_path,
+            SizeLimits::new(archive_path, Self::MAX_ENTRY_SIZE, Self::MAX_TOTAL_SIZE),
+        )
+    }
+
+    fn extract_with_limits(archive_path: &Path, mut limits: SizeLimits) -> Result<Self, Error> {
+        // The directory is deleted when it's dropped, including if extraction
+        // fails.
+        let directory = tempfile::Builder::new()
+            .prefix(TEMP_DIR_PREFIX)
+            .tempdir()
+            .map_err(|e| Error::IoError(std::env::temp_dir(), e))?;
+
+        let mut magic = Vec::new();
+        File::open(archive_path)
+            .and_then(|f| f.take(6).read_to_end(&mut magic))
+            .map_err(|e| Error::IoError(archive_path.to_path_buf(), e))?;
+
+        if magic.starts_with(ZIP_MAGIC) || magic.starts_with(EMPTY_ZIP_MAGIC) {
+            extract_zip(archive_path, directory.path(), &mut limits)?;
+        } else if magic.starts_with(SEVEN_ZIP_MAGIC) {
+            extract_7z(archive_path, directory.path(), &mut limits)?;
+        } else {
+            return Err(Error::InvalidArchive(
+                archive_path.to_path_buf(),
+                "the file is not a zip or 7z archive".into(),
+            ));
+        }
+
+        let data_path = single_subdirectory(directory.path())
+            .map_err(|e| Error::IoError(directory.path().to_path_buf(), e))?
+            .unwrap_or_else(|| directory.path().to_path_buf());
+
+        Ok(DataSnapshot {
+            _directory: directory,
+            data_path,
+        })
+    }
+
+    /// The path of the extracted data directory.
+    pub fn data_path(&self) -> &Path {
+        &self.data_path
+    }
+
+    /// Create a state for evaluating conditions against the snapshot. The
+    /// snapshot must not be dropped while the state is in use.
+    pub fn state(&self, game_type: GameType) -> State {
+        State::new(game_type, self.data_path.clone())
+    }
+}
+
+/// Keeps track of how much has been extracted from an archive, to stop
+/// extraction once it goes over the size limits.
+#[derive(Debug)]
+struct SizeLimits<'a> {
+    archive_path: &'a Path,
+    max_entry_size: u64,
+    max_total_size: u64,
+    total_size: u64,
+}
+
+impl<'a> SizeLimits<'a> {
+    fn new(archive_path: &'a Path, max_entry_size: u64, max_total_size: u64) -> Self {
+        SizeLimits {
+            archive_path,
+            max_entry_size,
+            max_total_size,
+            total_size: 0,
+        }
+    }
+
+    /// Get the number of bytes that the next entry can have without going
+    /// over either limit.
+    fn entry_limit(&self) -> u64 {
+        self.max_entry_size
+            .min(self.max_total_size.saturating_sub(self.total_size))
+    }
+
+    /// Add an extracted entry's size to the total, checking it against the
+    /// limits.
+    fn add(&mut self, name: &str, size: u64) -> Result<(), Error> {
+        if size > self.max_entry_size {
+            return Err(self.error(format!(
+                "the entry \"{name}\" is larger than the limit of {} bytes",
+                self.max_entry_size
+            )));
+        }
+
+        self.total_size = self.total_size.saturating_add(size);
+        if self.total_size > self.max_total_size {
+            return Err(self.error(format!(
+                "the entries are larger than the limit of {} bytes in total",
+                self.max_total_size
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn error(&self, message: String) -> Error {
+        Error::InvalidArchive(self.archive_path.to_path_buf(), message.into())
+    }
+}
+
+fn single_subdirectory(directory: &Path) -> io::Result<Option<PathBuf>> {
+    let mut entries = read_dir(directory)?;
+
+    let (Some(entry), None) = (entries.next().transpose()?, entries.next()) else {
+        return Ok(None);
+    };
+
+    Ok(entry.file_type()?.is_dir().then(|| entry.path()))
+}
+
+/// Get the path that an entry should be extracted to, relative to the
+/// extraction directory, or None if its name could refer to something outside
+/// of it on any platform. Both forward and back slashes are treated as
+/// separators.
+fn entry_path(name: &str) -> Option<PathBuf> {
+    if name.starts_with(['/', '\\']) {
+        return None;
+    }
+
+    let mut path = PathBuf::new();
+    for part in name.split(['/', '\\']) {
+        match Path::new(part).components().next() {
+            None | Some(Component::CurDir) => {}
+            Some(Component::Normal(p)) if p == part && !part.contains(':') => path.push(p),
+            _ => return None,
+        }
+    }
+
+    Some(path)
+}
+
+fn invalid_entry(archive_path: &Path, name: &str) -> Error {
+    Error::InvalidArchive(
+        archive_path.to_path_buf(),
+        format!("the entry \"{name}\" could refer to a path outside of the archive").into(),
+    )
+}
+
+/// Extract an entry's content to the given path, reading no more than is
+/// needed to tell if it goes over the size limits.
+fn extract_file(
+    reader: &mut dyn Read,
+    name: &str,
+    path: &Path,
+    modified: Option<SystemTime>,
+    limits: &mut SizeLimits,
+) -> Result<(), Error> {
+    let to_error = |e| Error::IoError(path.to_path_buf(), e);
+
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent).map_err(to_error)?;
+    }
+
+    let mut file = File::create(path).map_err(to_error)?;
+    let size = io::copy(
+        &mut reader.take(limits.entry_limit().saturating_add(1)),
+        &mut file,
+    )
+    .map_err(to_error)?;
+    limits.add(name, size)?;
+
+    if let Some(modified) = modified {
+        file.set_modified(modified).map_err(to_error)?;
+    }
+
+    Ok(())
+}
+
+fn extract_zip(
+    archive_path: &Path,
+    directory: &Path,
+    limits: &mut SizeLimits,
+) -> Result<(), Error> {
+    let to_error =
+        |e: zip::result::ZipError| Error::InvalidArchive(archive_path.to_path_buf(), Box::new(e));
+
+    let file =
+        File::open(archive_path).map_err(|e| Error::IoError(archive_path.to_path_buf(), e))?;
+    let mut archive = ZipArchive::new(file).map_err(to_error)?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(to_error)?;
+
+        let Some(relative_path) = entry_path(entry.name()) else {
+            return Err(invalid_entry(archive_path, entry.name()));
+        };
+        let path = directory.join(relative_path);
+
+        if entry.is_dir() {
+            create_dir_all(&path).map_err(|e| Error::IoError(path, e))?;
+        } else if !entry.is_symlink() {
+            let name = entry.name().to_owned();
+            let modified = zip_modified_time(entry.extra_data_fields(), entry.last_modified());
+            extract_file(&mut entry, &name, &path, modified, limits)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Get an entry's modification time, preferring the precise timestamps that
+/// some archivers add to the DOS timestamp, which has a two-second resolution
+/// and no time zone, so it's interpreted as UTC.
+fn zip_modified_time<'a>(
+    mut extra_fields: impl Iterator<Item = &'a ExtraField>,
+    last_modified: Option<zip::DateTime>,
+) -> Option<SystemTime> {
+    let precise_time = extra_fields.find_map(|field| match field {
+        ExtraField::ExtendedTimestamp(t) => t
+            .mod_time()
+            .and_then(|s| UNIX_EPOCH.checked_add(Duration::from_secs(s.into()))),
+        ExtraField::Ntfs(t) => system_time_from_nt(t.mtime()),
+    });
+
+    precise_time.or_else(|| {
+        last_modified
+            .and_then(|t| OffsetDateTime::try_from(t).ok())
+            .map(SystemTime::from)
+    })
+}
+
+fn extract_7z(archive_path: &Path, directory: &Path, limits: &mut SizeLimits) -> Result<(), Error> {
+    let to_error =
+        |e: sevenz_rust::Error| Error::InvalidArchive(archive_path.to_path_buf(), Box::new(e));
+
+    let mut archive = SevenZReader::open(archive_path, Password::empty()).map_err(to_error)?;
+
+    // Errors that occur while extracting an entry are stored so that they can
+    // be returned as they are, instead of being converted to 7z errors.
+    let mut extraction_error = None;
+    archive
+        .for_each_entries(|entry, reader| {
+            let Some(relative_path) = entry_path(entry.name()) else {
+                extraction_error = Some(invalid_entry(archive_path, entry.name()));
+                return Ok(false);
+            };
+            let path = directory.join(relative_path);
+
+            let result = if entry.is_directory() {
+                create_dir_all(&path).map_err(|e| Error::IoError(path, e))
+            } else {
+                let modified = if entry.has_last_modified_date {
+                    system_time_from_nt(entry.last_modified_date.to_raw())
+                } else {
+                    None
+                };
+                extract_file(reader, entry.name(), &path, modified, limits)
+            };
+
+            match result {
+                Ok(()) => Ok(true),
+                Err(e) => {
+                    extraction_error = Some(e);
+                    Ok(false)
+                }
+            }
+        })
+        .map_err(to_error)?;
+
+    extraction_error.map_or(Ok(()), Err)
+}
+
+/// Convert an NT timestamp, which counts 100 nanosecond ticks since the start
+/// of 1601, to a system time.
+fn system_time_from_nt(ticks: u64) -> Option<SystemTime> {
+    UNIX_EPOCH
+        .checked_sub(Duration::from_secs(NT_EPOCH_OFFSET_SECS))?
+        .checked_add(Duration::from_nanos(ticks.checked_mul(NT_TICK_NANOS)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use sevenz_rust::{SevenZArchiveEntry, SevenZWriter};
+    use tempfile::tempdir;
+    use zip::write::SimpleFileOptions;
+    use zip::{DateTime, ZipWriter};
+
+    use super::*;
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut writer = ZipWriter::new(File::create(path).unwrap());
+        let options = SimpleFileOptions::default()
+            .last_modified_time(DateTime::from_date_and_time(2020, 1, 2, 3, 4, 6).unwrap());
+
+        for (name, content) in entries {
+            if name.ends_with('/') {
+                writer.add_directory(*name, options).unwrap();
+            } else {
+                writer.start_file(*name, options).unwrap();
+                writer.write_all(content).unwrap();
+            }
+        }
+
+        writer.finish().unwrap();
+    }
+
+    fn write_7z(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut writer = SevenZWriter::create(path).unwrap();
+
+        for (name, content) in entries {
+            let mut entry = SevenZArchiveEntry::new();
+            entry.name = (*name).to_owned();
+            entry.has_stream = !content.is_empty();
+            entry.has_last_modified_date = true;
+            entry.last_modified_date = 132_224_078_460_000_000.into();
+
+            writer.push_archive_entry(entry, Some(*content)).unwrap();
+        }
+
+        writer.finish().unwrap();
+    }
+
+    fn expected_modified_time() -> SystemTime {
+        // 2020-01-02T03:04:06Z
+        UNIX_EPOCH + Duration::from_secs(1_577_934_246)
+    }
+
+    #[test]
+    fn extract_should_extract_a_zip_archive_as_the_data_directory() {
+        let tmp_dir = tempdir().unwrap();
+        let archive_path = tmp_dir.path().join("snapshot.zip");
+        write_zip(
+            &archive_path,
+            &[
+                ("Blank.esp", b"plugin"),
+                ("meshes/", b""),
+                ("Docs\\readme.txt", b"text"),
+            ],
+        );
+
+        let snapshot = DataSnapshot::extract(&archive_path).unwrap();
+
+        let data_path = snapshot.data_path();
+        assert_eq!(
+            b"plugin",
+            std::fs::read(data_path.join("Blank.esp"))
+                .unwrap()
+                .as_slice()
+        );
+        assert_eq!(
+            b"text",
+            std::fs::read(data_path.join("Docs/readme.txt"))
+                .unwrap()
+                .as_slice()
+        );
+        assert!(data_path.join("meshes").is_dir());
+        assert_eq!(
+            expected_modified_time(),
+            std::fs::metadata(data_path.join("Blank.esp"))
+                .unwrap()
+                .modified()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn extract_should_extract_a_7z_archive_as_the_data_directory() {
+        let tmp_dir = tempdir().unwrap();
+        let archive_path = tmp_dir.path().join("snapshot.7z");
+        write_7z(
+            &archive_path,
+            &[("Blank.esp", b"plugin"), ("Empty.esp", b"")],
+        );
+
+        let snapshot = DataSnapshot::extract(&archive_path).unwrap();
+
+        let data_path = snapshot.data_path();
+        assert_eq!(
+            b"plugin",
+            std::fs::read(data_path.join("Blank.esp"))
+                .unwrap()
+                .as_slice()
+        );
+        assert!(std::fs::read(data_path.join("Empty.esp"))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            expected_modified_time(),
+            std::fs::metadata(data_path.join("Blank.esp"))
+                .unwrap()
+                .modified()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn extract_should_use_a_lone_top_level_directory_as_the_data_directory() {
+        let tmp_dir = tempdir().unwrap();
+        let archive_path = tmp_dir.path().join("snapshot.zip");
+        write_zip(&archive_path, &[("Data/Blank.esp", b"plugin")]);
+
+        let snapshot = DataSnapshot::extract(&archive_path).unwrap();
+
+        assert!(snapshot.data_path().ends_with("Data"));
+        assert!(snapshot.data_path().join("Blank.esp").is_file());
+    }
+
+    #[test]
+    fn extract_should_error_if_an_entry_could_be_outside_the_archive() {
+        let tmp_dir = tempdir().unwrap();
+        let archive_path = tmp_dir.path().join("snapshot.zip");
+        write_zip(&archive_path, &[("Data/../../evil.txt", b"evil")]);
+
+        assert!(matches!(
+            DataSnapshot::extract(&archive_path),
+            Err(Error::InvalidArchive(p, _)) if p == archive_path
+        ));
+        assert!(!tmp_dir.path().join("evil.txt").exists());
+    }
+
+    #[test]
+    fn extract_should_error_if_an_entry_is_larger_than_the_entry_size_limit() {
+        let tmp_dir = tempdir().unwrap();
+        let archive_path = tmp_dir.path().join("snapshot.zip");
+        write_zip(&archive_path, &[("a.txt", b"1234"), ("b.txt", b"12345")]);
+
+        let limits = SizeLimits::new(&archive_path, 4, 100);
+
+        assert!(matches!(
+            DataSnapshot::extract_with_limits(&archive_path, limits),
+            Err(Error::InvalidArchive(p, e)) if p == archive_path && e.to_string().contains("b.txt")
+        ));
+    }
+
+    #[test]
+    fn extract_should_error_if_the_entries_are_larger_than_the_total_size_limit() {
+        let tmp_dir = tempdir().unwrap();
+        let archive_path = tmp_dir.path().join("snapshot.7z");
+        write_7z(&archive_path, &[("a.txt", b"1234"), ("b.txt", b"1234")]);
+
+        let limits = SizeLimits::new(&archive_path, 4, 6);
+
+        assert!(matches!(
+            DataSnapshot::extract_with_limits(&archive_path, limits),
+            Err(Error::InvalidArchive(p, e)) if p == archive_path && e.to_string().contains("in total")
+        ));
+    }
+
+    #[test]
+    fn extract_should_error_if_the_file_is_not_an_archive() {
+        assert!(matches!(
+            DataSnapshot::extract(Path::new("Cargo.toml")),
+            Err(Error::InvalidArchive(_, _))
+        ));
+    }
+
+    #[test]
+    fn dropping_a_snapshot_should_delete_the_extracted_files() {
+        let tmp_dir = tempdir().unwrap();
+        let archive_path = tmp_dir.path().join("snapshot.zip");
+        write_zip(&archive_path, &[("Blank.esp", b"plugin")]);
+
+        let snapshot = DataSnapshot::extract(&archive_path).unwrap();
+        let data_path = snapshot.data_path().to_path_buf();
+        assert!(data_path.exists());
+
+        drop(snapshot);
+
+        assert!(!data_path.exists());
+    }
+
+    #[test]
+    fn state_should_evaluate_conditions_against_the_snapshot() {
+        let tmp_dir = tempdir().unwrap();
+        let archive_path = tmp_dir.path().join("snapshot.zip");
+        write_zip(&archive_path, &[("Data/Blank.esp", b"plugin")]);
+
+        let snapshot = DataSnapshot::extract(&archive_path).unwrap();
+        let state = snapshot.state(GameType::Oblivion);
+
+        let eval = |condition: &str| {
+            condition
+                .parse::<crate::Expression>()
+                .unwrap()
+                .eval(&state)
+                .unwrap()
+        };
+
+        assert!(eval("file(\"Blank.esp\")"));
+        assert!(!eval("file(\"Blank.esm\")"));
+    }
+
+    #[test]
+    fn entry_path_should_reject_names_that_could_be_outside_the_archive() {
+        assert_eq!(Some(PathBuf::from("a/b.txt")), entry_path("./a\\b.txt"));
+        for name in [
+            "../a.txt",
+            "a/../../b.txt",
+            "/a.txt",
+            "C:\\a.txt",
+            "C:a.txt",
+        ] {
+            assert_eq!(None, entry_path(name), "{name}");
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{CompoundCondition, Condition, Error, Expression, State};
+
+/// An in-progress evaluation of an expression that evaluates one function at a
+/// time, so that single-threaded callers can interleave evaluation with other
+/// work. Functions are evaluated in the same order and with the same
+/// short-circuiting as [Expression::eval], so the final result is the same.
+#[derive(Debug)]
+pub struct StepwiseEvaluation<'a> {
+    state: &'a State,
+    stack: Vec<Frame<'a>>,
+    /// The result of the most recently completed condition, function or
+    /// expression, which has not yet been used by the frame that is now at
+    /// the top of the stack.
+    pending: Option<bool>,
+}
+
+#[derive(Debug)]
+enum Frame<'a> {
+    /// The compound conditions of an expression that have yet to be evaluated.
+    Or {
+        remaining: &'a [CompoundCondition],
+        invert: bool,
+    },
+    /// The conditions of a compound condition that have yet to be evaluated.
+    And { remaining: &'a [Condition] },
+}
+
+impl<'a> StepwiseEvaluation<'a> {
+    pub(crate) fn new(expression: &'a Expression, state: &'a State) -> Self {
+        StepwiseEvaluation {
+            state,
+            stack: vec![Frame::Or {
+                remaining: &expression.0,
+                invert: false,
+            }],
+            pending: None,
+        }
+    }
+
+    /// Evaluate the next function in the expression, returning the
+    /// expression's result if it is now known, or `None` if more steps are
+    /// needed. Once the result is known, further steps return it again
+    /// without doing any more work.
+    ///
+    /// If the function's evaluation fails, the error is returned and the
+    /// evaluation does not advance, so the next step will retry it.
+    pub fn step(&mut self) -> Result<Option<bool>, Error> {
+        let mut evaluated_function = false;
+
+        loop {
+            let Some(frame) = self.stack.last_mut() else {
+                return Ok(self.pending);
+            };
+
+            match frame {
+                Frame::Or { remaining, invert } => {
+                    let invert = *invert;
+                    match (self.pending.take(), remaining.split_first()) {
+                        (Some(true), _) => self.pop_with_result(!invert),
+                        (_, None) => self.pop_with_result(invert),
+                        (_, Some((compound_condition, rest))) => {
+                            *remaining = rest;
+                            self.stack.push(Frame::And {
+                                remaining: &compound_condition.0,
+                            });
+                        }
+                    }
+                }
+                Frame::And { remaining } => match (self.pending.take(), remaining.split_first()) {
+                    (Some(false), _) => self.pop_with_result(false),
+                    (_, None) => self.pop_with_result(true),
+                    (_, Some((condition, rest))) => match condition {
+                        Condition::Function(f, span) | Condition::InvertedFunction(f, span) => {
+                            // Stop before evaluating a second function.
+                            if evaluated_function {
+                                return Ok(None);
+                            }
+
+                            let result = f.eval_with_observer(self.state, span, &mut ())?;
+                            let inverted = matches!(condition, Condition::InvertedFunction(..));
+
+                            *remaining = rest;
+                            self.pending = Some(result != inverted);
+                            evaluated_function = true;
+                        }
+                        Condition::Expression(e) | Condition::InvertedExpression(e) => {
+                            let invert = matches!(condition, Condition::InvertedExpression(_));
+
+                            *remaining = rest;
+                            self.stack.push(Frame::Or {
+                                remaining: &e.0,
+                                invert,
+                            });
+                        }
+                    },
+                },
+            }
+        }
+    }
+
+    /// Take up to the given number of steps, stopping early if the result
+    /// becomes known or a step fails.
+    pub fn run(&mut self, max_steps: usize) -> Result<Option<bool>, Error> {
+        for _ in 0..max_steps {
+            if let Some(result) = self.step()? {
+                return Ok(Some(result));
+            }
+        }
+
+        Ok(self.result())
+    }
+
+    /// Get the expression's result, if it is known yet.
+    pub fn result(&self) -> Option<bool> {
+        if self.stack.is_empty() {
+            self.pending
+        } else {
+            None
+        }
+    }
+
+    fn pop_with_result(&mut self, result: bool) {
+        self.stack.pop();
+        self.pending = Some(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use crate::GameType;
+
+    use super::*;
+
+    fn state() -> State {
+        State::new(GameType::Oblivion, PathBuf::from(".")).with_active_plugins(&["Blank.esp"])
+    }
+
+    fn step_count(expression: &Expression, state: &State) -> (bool, usize) {
+        let mut evaluation = expression.eval_stepwise(state);
+        let mut steps = 0_usize;
+        loop {
+            steps += 1;
+            if let Some(result) = evaluation.step().unwrap() {
+                return (result, steps);
+            }
+        }
+    }
+
+    #[test]
+    fn step_should_give_the_same_result_as_eval() {
+        let state = state();
+        let expressions = [
+            "",
+            "file(\"Cargo.toml\")",
+            "not file(\"Cargo.toml\")",
+            "file(\"missing\") or active(\"Blank.esp\")",
+            "file(\"Cargo.toml\") and file(\"missing\")",
+            "not (file(\"missing\") or active(\"missing.esp\")) and file(\"Cargo.toml\")",
+            "(file(\"missing\") or (active(\"Blank.esp\") and not file(\"missing\"))) and not (file(\"Cargo.toml\"))",
+            "not (not (file(\"Cargo.toml\")))",
+        ];
+
+        for string in expressions {
+            let expression = Expression::from_str(string).unwrap();
+            let (result, _) = step_count(&expression, &state);
+
+            assert_eq!(expression.eval(&state).unwrap(), result, "{string}");
+        }
+    }
+
+    #[test]
+    fn step_should_evaluate_one_function_at_a_time() {
+        let state = state();
+        let expression = Expression::from_str(
+            "file(\"missing\") or (active(\"Blank.esp\") and file(\"Cargo.toml\")) or file(\"README.md\")",
+        )
+        .unwrap();
+        let mut evaluation = expression.eval_stepwise(&state);
+
+        assert_eq!(None, evaluation.step().unwrap());
+        assert_eq!(None, evaluation.step().unwrap());
+        assert_eq!(None, evaluation.result());
+        assert_eq!(Some(true), evaluation.step().unwrap());
+        assert_eq!(Some(true), evaluation.result());
+        assert_eq!(Some(true), evaluation.step().unwrap());
+    }
+
+    #[test]
+    fn step_should_short_circuit_like_eval() {
+        let state = state();
+
+        let expression =
+            Expression::from_str("file(\"Cargo.toml\") or file(\"a\") or file(\"b\")").unwrap();
+        assert_eq!((true, 1), step_count(&expression, &state));
+
+        let expression =
+            Expression::from_str("file(\"missing\") and file(\"a\") and file(\"b\")").unwrap();
+        assert_eq!((false, 1), step_count(&expression, &state));
+    }
+
+    #[test]
+    fn step_should_retry_a_function_that_failed() {
+        let mut state = state();
+        state.set_path_traversal_allowlist(Some(Vec::new()));
+        let expression = Expression::from_str("file(\"../Cargo.toml\")").unwrap();
+        let mut evaluation = expression.eval_stepwise(&state);
+
+        assert!(matches!(evaluation.step(), Err(Error::PathTraversal(_))));
+        assert!(matches!(evaluation.step(), Err(Error::PathTraversal(_))));
+        assert_eq!(None, evaluation.result());
+    }
+
+    #[test]
+    fn run_should_stop_after_the_given_number_of_steps() {
+        let state = state();
+        let expression = Expression::from_str(
+            "file(\"missing\") or file(\"missing2\") or file(\"missing3\") or file(\"Cargo.toml\")",
+        )
+        .unwrap();
+        let mut evaluation = expression.eval_stepwise(&state);
+
+        assert_eq!(None, evaluation.run(3).unwrap());
+        assert_eq!(Some(true), evaluation.run(3).unwrap());
+    }
+}
@@ -0,0 +1,411 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::function::Function;
+use crate::{CompoundCondition, Condition, Error, Expression, ResultSource, State};
+
+/// A record of how an expression was evaluated, with the same structure as
+/// the expression. Parts of the expression that weren't evaluated because the
+/// result was already known have no result.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExpressionTrace {
+    expression: String,
+    result: Option<bool>,
+    compound_conditions: Vec<CompoundConditionTrace>,
+}
+
+impl ExpressionTrace {
+    pub(crate) fn new(expression: &Expression, state: &State) -> Result<Self, Error> {
+        trace_expression(expression, Some(state))
+    }
+
+    /// The expression as it would be written in a condition string.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// The expression's result, or `None` if it wasn't evaluated.
+    pub fn result(&self) -> Option<bool> {
+        self.result
+    }
+
+    /// The traces of the compound conditions joined by `or`.
+    pub fn compound_conditions(&self) -> &[CompoundConditionTrace] {
+        &self.compound_conditions
+    }
+
+    /// Get the traces of all the functions in the expression, including
+    /// those in nested expressions, in the order that they were written.
+    pub fn functions(&self) -> Vec<&FunctionTrace> {
+        let mut functions = Vec::new();
+        collect_functions(self, &mut functions);
+        functions
+    }
+}
+
+/// A record of how the conditions joined by `and` in a compound condition were
+/// evaluated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompoundConditionTrace {
+    result: Option<bool>,
+    clauses: Vec<ClauseTrace>,
+}
+
+impl CompoundConditionTrace {
+    /// The compound condition's result, or `None` if it wasn't evaluated.
+    pub fn result(&self) -> Option<bool> {
+        self.result
+    }
+
+    pub fn clauses(&self) -> &[ClauseTrace] {
+        &self.clauses
+    }
+}
+
+/// A record of how a function or parenthesised expression was evaluated,
+/// and whether its result was negated using `not`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ClauseTrace {
+    Function(FunctionTrace),
+    NotFunction(FunctionTrace),
+    Expression(ExpressionTrace),
+    NotExpression(ExpressionTrace),
+}
+
+impl ClauseTrace {
+    /// The clause's result, including any negation, or `None` if it wasn't
+    /// evaluated.
+    pub fn result(&self) -> Option<bool> {
+        match self {
+            ClauseTrace::Function(f) => f.result(),
+            ClauseTrace::NotFunction(f) => f.result().map(|r| !r),
+            ClauseTrace::Expression(e) => e.result(),
+            ClauseTrace::NotExpression(e) => e.result().map(|r| !r),
+        }
+    }
+
+    pub fn is_negated(&self) -> bool {
+        matches!(
+            self,
+            ClauseTrace::NotFunction(_) | ClauseTrace::NotExpression(_)
+        )
+    }
+}
+
+/// A record of how a function was evaluated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionTrace {
+    name: &'static str,
+    function: String,
+    bound: Function,
+    span: Range<usize>,
+    arguments: Vec<String>,
+    evaluation: Option<(bool, ResultSource)>,
+    resolved_paths: Vec<PathBuf>,
+}
+
+impl FunctionTrace {
+    /// The name that the function is called by, e.g. `file` or `is_master`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The function as it would be written in a condition string.
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    /// The byte range of the function in the condition string that the
+    /// expression was parsed from.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The function's arguments as they would be written in a condition
+    /// string. If the function was evaluated, any variables in them have
+    /// been replaced by their values.
+    pub fn arguments(&self) -> &[String] {
+        &self.arguments
+    }
+
+    /// The function's result, ignoring any `not` applied to it, or `None` if
+    /// it wasn't evaluated.
+    pub fn result(&self) -> Option<bool> {
+        self.evaluation.map(|(result, _)| result)
+    }
+
+    /// Where the function's result came from, or `None` if it wasn't
+    /// evaluated.
+    pub fn source(&self) -> Option<ResultSource> {
+        self.evaluation.map(|(_, source)| source)
+    }
+
+    /// Check if the function's result came from a cache instead of being
+    /// calculated from the state or the filesystem.
+    pub fn was_cached(&self) -> bool {
+        self.source().is_some_and(ResultSource::is_cached)
+    }
+
+    /// The function with any variable references replaced by their values
+    /// if it was evaluated, or as it was parsed otherwise.
+    pub(crate) fn bound_function(&self) -> &Function {
+        &self.bound
+    }
+
+    /// The paths that the function checked, after resolving them against the
+    /// state's data paths, in the order that they were written. Functions
+    /// that only check active plugins or that weren't evaluated have no
+    /// resolved paths.
+    pub fn resolved_paths(&self) -> impl ExactSizeIterator<Item = &Path> {
+        self.resolved_paths.iter().map(PathBuf::as_path)
+    }
+}
+
+/// Trace the expression, evaluating it using the given state, or only
+/// recording its structure if there is no state.
+fn trace_expression(
+    expression: &Expression,
+    state: Option<&State>,
+) -> Result<ExpressionTrace, Error> {
+    let mut result = state.map(|_| false);
+    let mut compound_conditions = Vec::new();
+    for compound_condition in &expression.0 {
+        let state = state.filter(|_| result == Some(false));
+        let trace = trace_compound_condition(compound_condition, state)?;
+        if trace.result == Some(true) {
+            result = Some(true);
+        }
+        compound_conditions.push(trace);
+    }
+
+    Ok(ExpressionTrace {
+        expression: expression.to_string(),
+        result,
+        compound_conditions,
+    })
+}
+
+fn trace_compound_condition(
+    compound_condition: &CompoundCondition,
+    state: Option<&State>,
+) -> Result<CompoundConditionTrace, Error> {
+    let mut result = state.map(|_| true);
+    let mut clauses = Vec::new();
+    for condition in &compound_condition.0 {
+        let state = state.filter(|_| result == Some(true));
+        let trace = match condition {
+            Condition::Function(f, span) => ClauseTrace::Function(trace_function(f, span, state)?),
+            Condition::InvertedFunction(f, span) => {
+                ClauseTrace::NotFunction(trace_function(f, span, state)?)
+            }
+            Condition::Expression(e) => ClauseTrace::Expression(trace_expression(e, state)?),
+            Condition::InvertedExpression(e) => {
+                ClauseTrace::NotExpression(trace_expression(e, state)?)
+            }
+        };
+        if trace.result() == Some(false) {
+            result = Some(false);
+        }
+        clauses.push(trace);
+    }
+
+    Ok(CompoundConditionTrace { result, clauses })
+}
+
+fn trace_function(
+    function: &Function,
+    span: &Range<usize>,
+    state: Option<&State>,
+) -> Result<FunctionTrace, Error> {
+    let (bound, evaluation, resolved_paths) = match state {
+        Some(state) => {
+            let (result, source, _) = function.eval_with_source(state)?;
+            let bound = function.bind_variables(state)?.into_owned();
+            let resolved_paths = bound.resolved_paths(state)?;
+            (bound, Some((result, source)), resolved_paths)
+        }
+        None => (function.clone(), None, Vec::new()),
+    };
+
+    Ok(FunctionTrace {
+        name: function.name(),
+        function: function.to_string(),
+        span: span.clone(),
+        arguments: split_arguments(&bound.to_string()),
+        bound,
+        evaluation,
+        resolved_paths,
+    })
+}
+
+/// Split a function call written in its canonical form into its arguments,
+/// which are separated by commas that aren't inside quoted strings.
+fn split_arguments(call: &str) -> Vec<String> {
+    let Some(arguments) = call
+        .split_once('(')
+        .and_then(|(_, rest)| rest.strip_suffix(')'))
+        .filter(|arguments| !arguments.is_empty())
+    else {
+        return Vec::new();
+    };
+
+    let mut split = Vec::new();
+    let mut current = String::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in arguments.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                split.push(current.trim().to_owned());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    split.push(current.trim().to_owned());
+
+    split
+}
+
+fn collect_functions<'a>(trace: &'a ExpressionTrace, functions: &mut Vec<&'a FunctionTrace>) {
+    for clause in trace
+        .compound_conditions
+        .iter()
+        .flat_map(CompoundConditionTrace::clauses)
+    {
+        match clause {
+            ClauseTrace::Function(f) | ClauseTrace::NotFunction(f) => functions.push(f),
+            ClauseTrace::Expression(e) | ClauseTrace::NotExpression(e) => {
+                collect_functions(e, functions);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::GameType;
+
+    use super::*;
+
+    fn state() -> State {
+        State::new(GameType::Oblivion, PathBuf::from(".")).with_active_plugins(&["Blank.esp"])
+    }
+
+    fn trace(expression: &str, state: &State) -> ExpressionTrace {
+        Expression::from_str(expression)
+            .unwrap()
+            .eval_with_trace(state)
+            .unwrap()
+    }
+
+    #[test]
+    fn eval_with_trace_should_mirror_the_expression() {
+        let trace = trace(
+            "file(\"missing\") and active(\"Blank.esp\") or not (file(\"Cargo.toml\"))",
+            &state(),
+        );
+
+        assert_eq!(Some(false), trace.result());
+        assert_eq!(2, trace.compound_conditions().len());
+
+        let first = &trace.compound_conditions()[0];
+        assert_eq!(Some(false), first.result());
+        assert!(matches!(
+            first.clauses(),
+            [ClauseTrace::Function(_), ClauseTrace::Function(_)]
+        ));
+
+        let second = &trace.compound_conditions()[1];
+        assert_eq!(Some(false), second.result());
+        match second.clauses() {
+            [clause @ ClauseTrace::NotExpression(e)] => {
+                assert!(clause.is_negated());
+                assert_eq!(Some(false), clause.result());
+                assert_eq!(Some(true), e.result());
+            }
+            _ => panic!("Expected a negated expression"),
+        }
+    }
+
+    #[test]
+    fn eval_with_trace_should_not_evaluate_functions_that_are_short_circuited() {
+        let trace = trace(
+            "file(\"missing\") and active(\"Blank.esp\") or file(\"Cargo.toml\") or file(\"a\")",
+            &state(),
+        );
+
+        assert_eq!(Some(true), trace.result());
+        assert_eq!(
+            vec![Some(false), None, Some(true), None],
+            trace
+                .functions()
+                .iter()
+                .map(|f| f.result())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(None, trace.compound_conditions()[2].result());
+    }
+
+    #[test]
+    fn eval_with_trace_should_record_function_details() {
+        let state = state();
+        let trace = trace("checksum(\"Cargo.toml\", DEADBEEF)", &state);
+
+        let function = trace.functions()[0];
+        assert_eq!("checksum", function.name());
+        assert_eq!("checksum(\"Cargo.toml\", DEADBEEF)", function.function());
+        assert_eq!(0..32, function.span());
+        assert_eq!(&["\"Cargo.toml\"", "DEADBEEF"], function.arguments());
+        assert_eq!(Some(false), function.result());
+        assert_eq!(Some(ResultSource::FreshIo), function.source());
+        assert!(!function.was_cached());
+        assert_eq!(
+            vec![Path::new("./Cargo.toml")],
+            function.resolved_paths().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn eval_with_trace_should_record_cached_results() {
+        let state = state();
+        let expression = Expression::from_str("many(\"src/.*\\.rs\")").unwrap();
+        expression.eval(&state).unwrap();
+
+        let trace = expression.eval_with_trace(&state).unwrap();
+
+        assert!(trace.functions()[0].was_cached());
+    }
+
+    #[test]
+    fn eval_with_trace_should_record_arguments_with_variables_substituted() {
+        let options = crate::ParseOptions::new().with_variables(&["DIR"]);
+        let expression =
+            Expression::parse_with_options("file(\"${DIR}/Cargo.toml\")", &options).unwrap();
+        let mut state = state();
+        state.set_variables(&[("DIR", ".")]);
+
+        let trace = expression.eval_with_trace(&state).unwrap();
+
+        let function = trace.functions()[0];
+        assert_eq!("file(\"${DIR}/Cargo.toml\")", function.function());
+        assert_eq!(&["\"./Cargo.toml\""], function.arguments());
+        assert_eq!(Some(true), function.result());
+    }
+
+    #[test]
+    fn split_arguments_should_not_split_inside_strings() {
+        assert_eq!(
+            vec!["\"a, \\\"b\"", "\"c\"", "=="],
+            split_arguments("version(\"a, \\\"b\", \"c\", ==)")
+        );
+        assert!(split_arguments("is_wine()").is_empty());
+    }
+}
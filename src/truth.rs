@@ -0,0 +1,255 @@
+use std::ops::Not;
+
+use crate::function::Function;
+use crate::{CompoundCondition, Condition, Error, Expression, State};
+
+/// The result of evaluating a condition when some of its functions may have
+/// results that can't be determined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Truth {
+    False,
+    True,
+    Unknown,
+}
+
+impl Truth {
+    /// Combine the values using Kleene logic: the result is false if either
+    /// value is false, unknown if either value is unknown, and otherwise true.
+    #[must_use]
+    pub fn and(self, other: Truth) -> Truth {
+        match (self, other) {
+            (Truth::False, _) | (_, Truth::False) => Truth::False,
+            (Truth::Unknown, _) | (_, Truth::Unknown) => Truth::Unknown,
+            (Truth::True, Truth::True) => Truth::True,
+        }
+    }
+
+    /// Combine the values using Kleene logic: the result is true if either
+    /// value is true, unknown if either value is unknown, and otherwise false.
+    #[must_use]
+    pub fn or(self, other: Truth) -> Truth {
+        match (self, other) {
+            (Truth::True, _) | (_, Truth::True) => Truth::True,
+            (Truth::Unknown, _) | (_, Truth::Unknown) => Truth::Unknown,
+            (Truth::False, Truth::False) => Truth::False,
+        }
+    }
+
+    /// Get the value as a bool, or `None` if it's unknown.
+    pub fn known(self) -> Option<bool> {
+        match self {
+            Truth::False => Some(false),
+            Truth::True => Some(true),
+            Truth::Unknown => None,
+        }
+    }
+}
+
+impl Not for Truth {
+    type Output = Truth;
+
+    fn not(self) -> Truth {
+        match self {
+            Truth::False => Truth::True,
+            Truth::True => Truth::False,
+            Truth::Unknown => Truth::Unknown,
+        }
+    }
+}
+
+impl From<bool> for Truth {
+    fn from(value: bool) -> Self {
+        if value {
+            Truth::True
+        } else {
+            Truth::False
+        }
+    }
+}
+
+/// Evaluate the function, treating it as unknown if it can't be evaluated
+/// because its path is not allowed, because filesystem access is disabled or
+/// because reading it kept failing with transient errors.
+pub(crate) fn eval_function(function: &Function, state: &State) -> Result<Truth, Error> {
+    match function.eval_with_source(state) {
+        Ok((result, _, _)) => Ok(result.into()),
+        Err(
+            Error::PathTraversal(_)
+            | Error::UnmappedDrivePath(_)
+            | Error::FilesystemAccessDisabled(_)
+            | Error::RetriesExhausted(..),
+        ) => Ok(Truth::Unknown),
+        Err(e) => Err(e),
+    }
+}
+
+/// Evaluate the expression using Kleene logic, getting the values of its
+/// functions from the given closure. Evaluation short-circuits like
+/// [Expression::eval], but an unknown value doesn't decide an `and` or an
+/// `or`, so the remaining conditions are evaluated in case they do.
+pub(crate) fn eval_expression(
+    expression: &Expression,
+    eval_function: &mut dyn FnMut(&Function) -> Result<Truth, Error>,
+) -> Result<Truth, Error> {
+    let mut result = Truth::False;
+    for compound_condition in &expression.0 {
+        result = result.or(eval_compound_condition(compound_condition, eval_function)?);
+        if result == Truth::True {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+fn eval_compound_condition(
+    compound_condition: &CompoundCondition,
+    eval_function: &mut dyn FnMut(&Function) -> Result<Truth, Error>,
+) -> Result<Truth, Error> {
+    let mut result = Truth::True;
+    for condition in &compound_condition.0 {
+        result = result.and(eval_condition(condition, eval_function)?);
+        if result == Truth::False {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+fn eval_condition(
+    condition: &Condition,
+    eval_function: &mut dyn FnMut(&Function) -> Result<Truth, Error>,
+) -> Result<Truth, Error> {
+    match condition {
+        Condition::Function(f, _) => eval_function(f),
+        Condition::InvertedFunction(f, _) => eval_function(f).map(Not::not),
+        Condition::Expression(e) => eval_expression(e, eval_function),
+        Condition::InvertedExpression(e) => eval_expression(e, eval_function).map(Not::not),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use crate::GameType;
+
+    use super::*;
+
+    const VALUES: [Truth; 3] = [Truth::False, Truth::True, Truth::Unknown];
+
+    fn eval(expression: &str, state: &State) -> Truth {
+        Expression::from_str(expression)
+            .unwrap()
+            .eval_three_valued(state)
+            .unwrap()
+    }
+
+    fn offline_state() -> State {
+        let mut state = State::new(GameType::Oblivion, PathBuf::from("."));
+        state.set_filesystem_access(false);
+        state.set_active_plugins(&["Blank.esp"]);
+        state
+    }
+
+    #[test]
+    fn and_or_and_not_should_follow_kleene_logic() {
+        for a in VALUES {
+            for b in VALUES {
+                let expected_and = match (a.known(), b.known()) {
+                    (Some(false), _) | (_, Some(false)) => Truth::False,
+                    (Some(true), Some(true)) => Truth::True,
+                    _ => Truth::Unknown,
+                };
+                let expected_or = match (a.known(), b.known()) {
+                    (Some(true), _) | (_, Some(true)) => Truth::True,
+                    (Some(false), Some(false)) => Truth::False,
+                    _ => Truth::Unknown,
+                };
+
+                assert_eq!(expected_and, a.and(b), "{a:?} and {b:?}");
+                assert_eq!(expected_or, a.or(b), "{a:?} or {b:?}");
+                assert_eq!(!a.or(b), (!a).and(!b), "{a:?} or {b:?}");
+            }
+        }
+
+        assert_eq!(Truth::Unknown, !Truth::Unknown);
+    }
+
+    #[test]
+    fn eval_three_valued_should_match_eval_when_everything_can_be_read() {
+        let state = State::new(GameType::Oblivion, PathBuf::from("."));
+
+        assert_eq!(Truth::True, eval("file(\"Cargo.toml\")", &state));
+        assert_eq!(Truth::False, eval("file(\"missing\")", &state));
+        assert_eq!(
+            Truth::True,
+            eval("not file(\"missing\") and readable(\"src\")", &state)
+        );
+    }
+
+    #[test]
+    fn eval_three_valued_should_be_unknown_for_functions_that_need_the_filesystem_if_access_is_disabled(
+    ) {
+        let state = offline_state();
+
+        assert_eq!(Truth::Unknown, eval("file(\"Cargo.toml\")", &state));
+        assert_eq!(Truth::Unknown, eval("not readable(\"src\")", &state));
+        assert_eq!(Truth::True, eval("active(\"Blank.esp\")", &state));
+        assert!(Expression::from_str("file(\"Cargo.toml\")")
+            .unwrap()
+            .eval(&state)
+            .is_err());
+    }
+
+    #[test]
+    fn eval_three_valued_should_use_crcs_given_to_the_state_if_access_is_disabled() {
+        let mut state = offline_state();
+        state
+            .set_cached_crcs(&[("Blank.esm", 0xDEAD_BEEF)])
+            .unwrap();
+
+        assert_eq!(
+            Truth::True,
+            eval("checksum(\"Blank.esm\", DEADBEEF)", &state)
+        );
+        assert_eq!(
+            Truth::Unknown,
+            eval("checksum(\"Blank.esp\", DEADBEEF)", &state)
+        );
+    }
+
+    #[test]
+    fn eval_three_valued_should_be_unknown_for_paths_that_are_not_allowed() {
+        let mut state = State::new(GameType::Oblivion, PathBuf::from("."));
+        state.set_path_traversal_allowlist(Some(Vec::new()));
+
+        assert_eq!(Truth::Unknown, eval("file(\"../Cargo.toml\")", &state));
+        assert_eq!(Truth::True, eval("file(\"Cargo.toml\")", &state));
+    }
+
+    #[test]
+    fn eval_three_valued_should_only_be_unknown_if_the_unknown_functions_could_decide_the_result() {
+        let state = offline_state();
+
+        assert_eq!(
+            Truth::True,
+            eval("file(\"Cargo.toml\") or active(\"Blank.esp\")", &state)
+        );
+        assert_eq!(
+            Truth::False,
+            eval("file(\"Cargo.toml\") and active(\"Other.esp\")", &state)
+        );
+        assert_eq!(
+            Truth::Unknown,
+            eval("file(\"Cargo.toml\") and active(\"Blank.esp\")", &state)
+        );
+        assert_eq!(
+            Truth::Unknown,
+            eval(
+                "not (file(\"Cargo.toml\") or active(\"Other.esp\"))",
+                &state
+            )
+        );
+    }
+}
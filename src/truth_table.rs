@@ -0,0 +1,329 @@
+use std::fmt;
+
+use crate::function::Function;
+use crate::truth::{eval_expression, eval_function, Truth};
+use crate::{Condition, Error, Expression, State};
+
+/// A fact about a function whose result is unknown: that its result is the
+/// given value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Fact {
+    function: String,
+    value: bool,
+}
+
+impl Fact {
+    /// The function, as it would be written in a condition string.
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    pub fn value(&self) -> bool {
+        self.value
+    }
+}
+
+impl fmt::Display for Fact {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.value {
+            write!(f, "{}", self.function)
+        } else {
+            write!(f, "not {}", self.function)
+        }
+    }
+}
+
+/// The results of an expression for every combination of results of the
+/// functions in it that have unknown results when evaluated using
+/// three-valued logic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TruthTable {
+    /// The functions with unknown results, as they would be written in a
+    /// condition string.
+    unknowns: Vec<String>,
+    /// The expression's results, indexed by a bit mask in which bit `i` is
+    /// the result of unknown function `i`.
+    results: Vec<bool>,
+}
+
+impl TruthTable {
+    pub(crate) fn new(
+        expression: &Expression,
+        state: &State,
+        max_unknowns: usize,
+    ) -> Result<Self, Error> {
+        let mut functions = Vec::new();
+        collect_functions(expression, &mut functions);
+
+        let mut known = Vec::new();
+        let mut unknowns = Vec::new();
+        for function in functions {
+            match eval_function(function, state)? {
+                Truth::Unknown => unknowns.push(function),
+                truth => known.push((function, truth)),
+            }
+        }
+
+        let row_count = u32::try_from(unknowns.len())
+            .ok()
+            .filter(|_| unknowns.len() <= max_unknowns)
+            .and_then(|count| 1_usize.checked_shl(count))
+            .ok_or(Error::TooManyUnknowns(max_unknowns))?;
+
+        let results = (0..row_count)
+            .map(|mask| {
+                let truth = eval_expression(expression, &mut |function| {
+                    if let Some(index) = unknowns.iter().position(|u| *u == function) {
+                        Ok(is_bit_set(mask, index).into())
+                    } else {
+                        Ok(known
+                            .iter()
+                            .find(|(k, _)| *k == function)
+                            .map_or(Truth::Unknown, |(_, truth)| *truth))
+                    }
+                })?;
+
+                Ok(truth == Truth::True)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(TruthTable {
+            unknowns: unknowns.iter().map(ToString::to_string).collect(),
+            results,
+        })
+    }
+
+    /// The functions with unknown results, as they would be written in a
+    /// condition string, in the order that they first appear in the
+    /// expression.
+    pub fn unknowns(&self) -> &[String] {
+        &self.unknowns
+    }
+
+    /// Iterate over the table's rows. Each row gives the results of the
+    /// unknown functions, in the same order as [TruthTable::unknowns], and
+    /// the expression's result for them.
+    pub fn rows(&self) -> impl Iterator<Item = (Vec<bool>, bool)> + '_ {
+        self.results
+            .iter()
+            .enumerate()
+            .map(|(mask, result)| (self.assignment(mask), *result))
+    }
+
+    /// Check if there is any combination of results for the unknown
+    /// functions that makes the expression true.
+    pub fn is_satisfiable(&self) -> bool {
+        self.results.contains(&true)
+    }
+
+    /// Get the minimal sets of facts that would each make the expression true
+    /// if they all held, whatever the results of the unknown functions that
+    /// they don't mention. Sets with fewer facts are listed first. If the
+    /// expression is true without any of the unknown functions, the only set
+    /// is empty, and if it can't be true, there are no sets.
+    pub fn requirements(&self) -> Vec<Vec<Fact>> {
+        let minterms = self
+            .results
+            .iter()
+            .enumerate()
+            .filter(|(_, result)| **result)
+            .map(|(mask, _)| mask);
+
+        let mut implicants = prime_implicants(minterms, self.unknowns.len());
+        implicants.sort_by_key(|(value, care)| (care.count_ones(), *care, *value));
+
+        implicants
+            .into_iter()
+            .map(|(value, care)| {
+                self.unknowns
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| is_bit_set(care, *index))
+                    .map(|(index, function)| Fact {
+                        function: function.clone(),
+                        value: is_bit_set(value, index),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn assignment(&self, mask: usize) -> Vec<bool> {
+        (0..self.unknowns.len())
+            .map(|index| is_bit_set(mask, index))
+            .collect()
+    }
+}
+
+fn is_bit_set(mask: usize, index: usize) -> bool {
+    (mask >> index) & 1 == 1
+}
+
+/// Collect the distinct functions in the expression, in the order that they
+/// first appear.
+fn collect_functions<'a>(expression: &'a Expression, functions: &mut Vec<&'a Function>) {
+    for condition in expression.0.iter().flat_map(|c| &c.0) {
+        match condition {
+            Condition::Function(f, _) | Condition::InvertedFunction(f, _) => {
+                if !functions.contains(&f) {
+                    functions.push(f);
+                }
+            }
+            Condition::Expression(e) | Condition::InvertedExpression(e) => {
+                collect_functions(e, functions);
+            }
+        }
+    }
+}
+
+/// Find the prime implicants of the given minterms using the Quine-McCluskey
+/// method. Each implicant is a pair of bit masks: the values of the variables
+/// that it depends on, and which variables those are.
+fn prime_implicants(
+    minterms: impl Iterator<Item = usize>,
+    variable_count: usize,
+) -> Vec<(usize, usize)> {
+    let all_variables = u32::try_from(variable_count)
+        .ok()
+        .and_then(|count| 1_usize.checked_shl(count))
+        .map_or(usize::MAX, |mask| mask.wrapping_sub(1));
+
+    let mut current: Vec<(usize, usize)> = minterms.map(|m| (m, all_variables)).collect();
+    let mut primes = Vec::new();
+
+    while !current.is_empty() {
+        let mut next = Vec::new();
+        let mut combined = vec![false; current.len()];
+
+        for (i, (value_a, care_a)) in current.iter().enumerate() {
+            for (j, (value_b, care_b)) in current.iter().enumerate().skip(i.saturating_add(1)) {
+                let difference = (value_a ^ value_b) & care_a;
+                if care_a != care_b || difference.count_ones() != 1 {
+                    continue;
+                }
+
+                for index in [i, j] {
+                    if let Some(flag) = combined.get_mut(index) {
+                        *flag = true;
+                    }
+                }
+
+                let implicant = (value_a & !difference, care_a & !difference);
+                if !next.contains(&implicant) {
+                    next.push(implicant);
+                }
+            }
+        }
+
+        for (implicant, was_combined) in current.iter().zip(&combined) {
+            if !was_combined && !primes.contains(implicant) {
+                primes.push(*implicant);
+            }
+        }
+
+        current = next;
+    }
+
+    primes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use crate::GameType;
+
+    use super::*;
+
+    fn truth_table(expression: &str, max_unknowns: usize) -> Result<TruthTable, Error> {
+        let mut state = State::new(GameType::Oblivion, PathBuf::from("."));
+        state.set_filesystem_access(false);
+        state.set_active_plugins(&["Blank.esp"]);
+
+        parse(expression).truth_table(&state, max_unknowns)
+    }
+
+    fn parse(expression: &str) -> Expression {
+        Expression::from_str(expression).unwrap()
+    }
+
+    fn requirement_strings(table: &TruthTable) -> Vec<Vec<String>> {
+        table
+            .requirements()
+            .iter()
+            .map(|facts| facts.iter().map(ToString::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn truth_table_should_have_a_row_for_each_combination_of_unknown_results() {
+        let table = truth_table(
+            "file(\"A.esp\") and (file(\"B.esp\") or active(\"Other.esp\")) and not file(\"A.esp\")",
+            4,
+        )
+        .unwrap();
+
+        assert_eq!(["file(\"A.esp\")", "file(\"B.esp\")"], table.unknowns());
+        assert_eq!(
+            vec![
+                (vec![false, false], false),
+                (vec![true, false], false),
+                (vec![false, true], false),
+                (vec![true, true], false),
+            ],
+            table.rows().collect::<Vec<_>>()
+        );
+        assert!(!table.is_satisfiable());
+        assert!(table.requirements().is_empty());
+    }
+
+    #[test]
+    fn truth_table_should_use_the_known_results_of_other_functions() {
+        let table = truth_table("file(\"A.esp\") and active(\"Blank.esp\")", 4).unwrap();
+
+        assert_eq!(
+            vec![(vec![false], false), (vec![true], true)],
+            table.rows().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn truth_table_should_error_if_there_are_too_many_unknowns() {
+        assert!(matches!(
+            truth_table("file(\"A.esp\") or file(\"B.esp\")", 1),
+            Err(Error::TooManyUnknowns(1))
+        ));
+    }
+
+    #[test]
+    fn requirements_should_be_the_minimal_sets_of_facts_that_make_the_expression_true() {
+        let table = truth_table(
+            "(file(\"A.esp\") and not file(\"B.esp\")) or (file(\"A.esp\") and file(\"B.esp\")) or file(\"C.esp\")",
+            4,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![vec!["file(\"A.esp\")"], vec!["file(\"C.esp\")"]],
+            requirement_strings(&table)
+        );
+    }
+
+    #[test]
+    fn requirements_should_include_negated_facts() {
+        let table = truth_table("not file(\"A.esp\") and file(\"B.esp\")", 4).unwrap();
+
+        assert_eq!(
+            vec![vec!["not file(\"A.esp\")", "file(\"B.esp\")"]],
+            requirement_strings(&table)
+        );
+    }
+
+    #[test]
+    fn requirements_should_be_one_empty_set_if_the_expression_is_already_true() {
+        let table = truth_table("active(\"Blank.esp\") or file(\"A.esp\")", 4).unwrap();
+
+        assert_eq!(vec![Vec::<String>::new()], requirement_strings(&table));
+    }
+}
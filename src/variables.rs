@@ -0,0 +1,162 @@
+#[cfg(feature = "eval")]
+use std::borrow::Cow;
+#[cfg(feature = "eval")]
+use std::collections::HashMap;
+
+use crate::alias::is_alias_name_char;
+use crate::error::ParsingErrorKind;
+
+const REFERENCE_START: &str = "${";
+const REFERENCE_END: char = '}';
+
+/// A part of a string argument that may reference variables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Segment<'a> {
+    Text(&'a str),
+    /// The name of a variable referenced as `${NAME}`.
+    Variable(&'a str),
+}
+
+/// Split the string into literal text and variable references. Variable
+/// names follow the same rules as alias names.
+fn segments(string: &str) -> Result<Vec<Segment<'_>>, ParsingErrorKind> {
+    let mut segments = Vec::new();
+    let mut remaining = string;
+
+    while let Some((text, reference)) = remaining.split_once(REFERENCE_START) {
+        let Some((name, rest)) = reference
+            .split_once(REFERENCE_END)
+            .filter(|(name, _)| is_valid_name(name))
+        else {
+            return Err(ParsingErrorKind::InvalidVariableReference(
+                remaining.to_owned(),
+            ));
+        };
+
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        segments.push(Segment::Variable(name));
+        remaining = rest;
+    }
+
+    if !remaining.is_empty() {
+        segments.push(Segment::Text(remaining));
+    }
+
+    Ok(segments)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(is_alias_name_char)
+}
+
+#[cfg(feature = "eval")]
+pub(crate) fn has_references(string: &str) -> bool {
+    string.contains(REFERENCE_START)
+}
+
+/// Get the names of the variables that the string references.
+pub(crate) fn references(string: &str) -> Result<Vec<&str>, ParsingErrorKind> {
+    Ok(segments(string)?
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Variable(name) => Some(name),
+            Segment::Text(_) => None,
+        })
+        .collect())
+}
+
+/// Replace the variable references in the string with the variables' values.
+#[cfg(feature = "eval")]
+pub(crate) fn substitute<'a>(
+    string: &'a str,
+    variables: &HashMap<String, String>,
+) -> Result<Cow<'a, str>, VariableError> {
+    if !has_references(string) {
+        return Ok(Cow::Borrowed(string));
+    }
+
+    let mut substituted = String::new();
+    for segment in segments(string).map_err(VariableError::Invalid)? {
+        match segment {
+            Segment::Text(text) => substituted.push_str(text),
+            Segment::Variable(name) => match variables.get(name) {
+                Some(value) => substituted.push_str(value),
+                None => return Err(VariableError::Unbound(name.to_owned())),
+            },
+        }
+    }
+
+    Ok(Cow::Owned(substituted))
+}
+
+#[cfg(feature = "eval")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum VariableError {
+    Invalid(ParsingErrorKind),
+    /// The string is the name of the first variable that has no value.
+    Unbound(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "eval")]
+    fn variables() -> HashMap<String, String> {
+        HashMap::from([
+            ("GAME_VERSION".to_owned(), "1.6.1170".to_owned()),
+            ("PROFILE".to_owned(), "Default".to_owned()),
+        ])
+    }
+
+    #[test]
+    fn references_should_return_the_referenced_variable_names_in_order() {
+        assert_eq!(
+            vec!["PROFILE", "GAME_VERSION", "PROFILE"],
+            references("a/${PROFILE}/${GAME_VERSION}${PROFILE}.txt").unwrap()
+        );
+        assert!(references("a/$PROFILE/{b}.txt").unwrap().is_empty());
+    }
+
+    #[test]
+    fn references_should_error_if_a_reference_is_unterminated_or_has_an_invalid_name() {
+        for string in ["a/${PROFILE", "${}", "${PRO FILE}", "${a/b}"] {
+            assert!(
+                matches!(
+                    references(string),
+                    Err(ParsingErrorKind::InvalidVariableReference(_))
+                ),
+                "{string}"
+            );
+        }
+    }
+
+    #[cfg(feature = "eval")]
+    #[test]
+    fn substitute_should_replace_references_with_values() {
+        assert_eq!(
+            "profiles/Default/1.6.1170.txt",
+            substitute("profiles/${PROFILE}/${GAME_VERSION}.txt", &variables()).unwrap()
+        );
+    }
+
+    #[cfg(feature = "eval")]
+    #[test]
+    fn substitute_should_borrow_strings_without_references() {
+        assert!(matches!(
+            substitute("Blank.esp", &variables()),
+            Ok(Cow::Borrowed("Blank.esp"))
+        ));
+    }
+
+    #[cfg(feature = "eval")]
+    #[test]
+    fn substitute_should_error_if_a_variable_has_no_value() {
+        assert_eq!(
+            Err(VariableError::Unbound("OTHER".to_owned())),
+            substitute("${PROFILE}/${OTHER}", &variables())
+        );
+    }
+}
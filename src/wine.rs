@@ -0,0 +1,696 @@
+use std::collections::BTreeMap;
+use std::fs::{read_dir, read_link, read_to_string};
+use std::path::{Path, PathBuf};
+
+use crate::GameType;
+
+const STEAM_ROOTS: &[&str] = &[
+    ".local/share/Steam",
+    ".steam/steam",
+    ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+];
+
+const HEROIC_ROOTS: &[&str] = &[
+    "Games/Heroic",
+    ".var/app/com.heroicgameslauncher.hgl/Games/Heroic",
+];
+
+const PROTON_USER_NAME: &str = "steamuser";
+
+/// A DLL that the GOG releases of games ship with.
+const GOG_DLL_NAME: &str = "Galaxy64.dll";
+
+/// DLLs that the Epic Games Store releases of games ship with.
+const EPIC_DLL_NAMES: &[&str] = &["EOSSDK-Win64-Shipping.dll", "EOSSDK-Win32-Shipping.dll"];
+
+/// The launcher that created a Wine prefix.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[non_exhaustive]
+pub enum WineLauncher {
+    Steam,
+    Heroic,
+}
+
+/// The store that a game was bought from, which can affect the names of the
+/// game's folders in the user's profile.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Store {
+    Steam,
+    Gog,
+    Epic,
+}
+
+/// A game installed under a Wine prefix, such as one created by Steam's Proton
+/// or by the Heroic Games Launcher.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WineInstall {
+    game_type: GameType,
+    launcher: WineLauncher,
+    /// The prefix directory, i.e. the directory that contains drive_c.
+    prefix_path: PathBuf,
+    /// The game's install directory, if it could be found.
+    install_path: Option<PathBuf>,
+}
+
+impl WineInstall {
+    pub fn game_type(&self) -> GameType {
+        self.game_type
+    }
+
+    pub fn launcher(&self) -> WineLauncher {
+        self.launcher
+    }
+
+    pub fn prefix_path(&self) -> &Path {
+        &self.prefix_path
+    }
+
+    pub fn install_path(&self) -> Option<&Path> {
+        self.install_path.as_deref()
+    }
+
+    /// The game's data path, which is the path that should be given to
+    /// [State::new](crate::State::new).
+    pub fn data_path(&self) -> Option<PathBuf> {
+        self.install_path
+            .as_ref()
+            .map(|p| p.join(data_folder_name(self.game_type)))
+    }
+
+    /// The path of the Windows user profile directory inside the prefix.
+    pub fn user_profile_path(&self) -> PathBuf {
+        let users_path = self.prefix_path.join("drive_c").join("users");
+
+        let user_name = if self.launcher == WineLauncher::Steam {
+            Some(PROTON_USER_NAME.to_owned())
+        } else {
+            find_user_name(&users_path)
+        };
+
+        users_path.join(user_name.as_deref().unwrap_or(PROTON_USER_NAME))
+    }
+
+    /// The directory that holds the game's plugins.txt, if the game has one.
+    pub fn local_app_data_path(&self) -> Option<PathBuf> {
+        game_folder_names(self.game_type, self.store()).map(|(local_folder, _)| {
+            self.user_profile_path()
+                .join("AppData")
+                .join("Local")
+                .join(local_folder)
+        })
+    }
+
+    /// The directory that holds the game's INI files, if the game keeps them in
+    /// the user's documents.
+    pub fn my_games_path(&self) -> Option<PathBuf> {
+        game_folder_names(self.game_type, self.store()).map(|(_, my_games_folder)| {
+            self.user_profile_path()
+                .join("Documents")
+                .join("My Games")
+                .join(my_games_folder)
+        })
+    }
+
+    /// Heroic installs games from GOG and the Epic Games Store, which can be
+    /// told apart by the DLLs in the game's install directory. If the store
+    /// can't be identified, the game is treated as if it were from Steam.
+    fn store(&self) -> Store {
+        let Some(install_path) = self.install_path.as_deref() else {
+            return Store::Steam;
+        };
+
+        if self.launcher == WineLauncher::Steam {
+            Store::Steam
+        } else if install_path.join(GOG_DLL_NAME).is_file() {
+            Store::Gog
+        } else if EPIC_DLL_NAMES
+            .iter()
+            .any(|name| install_path.join(name).is_file())
+        {
+            Store::Epic
+        } else {
+            Store::Steam
+        }
+    }
+
+    /// Create a path mapper for the install's prefix.
+    pub fn path_mapper(&self) -> WinePathMapper {
+        WinePathMapper::from_prefix(&self.prefix_path)
+    }
+}
+
+/// Translates Windows-style absolute paths (e.g. `C:\Program Files\...` or
+/// `Z:\home\...`) into the host paths that Wine maps their drives to.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct WinePathMapper {
+    /// Lowercase drive letters and the host paths they map to.
+    drives: BTreeMap<char, PathBuf>,
+}
+
+impl WinePathMapper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a mapper using the drives that are configured in the given
+    /// prefix's dosdevices directory. If the prefix has no drive configuration,
+    /// Wine's defaults of `C:` mapping to drive_c and `Z:` mapping to the root
+    /// directory are used.
+    pub fn from_prefix(prefix_path: &Path) -> Self {
+        let dosdevices_path = prefix_path.join("dosdevices");
+        let mut mapper = Self::new();
+
+        if let Ok(entries) = read_dir(&dosdevices_path) {
+            for entry in entries.filter_map(Result::ok) {
+                let Some(letter) = entry.file_name().to_str().and_then(parse_drive_name) else {
+                    continue;
+                };
+
+                if let Ok(target) = read_link(entry.path()) {
+                    mapper.set_drive(letter, dosdevices_path.join(target));
+                }
+            }
+        }
+
+        if mapper.drives.is_empty() {
+            mapper.set_drive('c', prefix_path.join("drive_c"));
+            mapper.set_drive('z', PathBuf::from("/"));
+        }
+
+        mapper
+    }
+
+    #[must_use]
+    pub fn with_drive(mut self, letter: char, host_path: PathBuf) -> Self {
+        self.set_drive(letter, host_path);
+        self
+    }
+
+    pub fn set_drive(&mut self, letter: char, host_path: PathBuf) {
+        self.drives.insert(letter.to_ascii_lowercase(), host_path);
+    }
+
+    /// Translate the given path if it is a Windows-style absolute path on a
+    /// mapped drive, returning None otherwise.
+    pub fn translate(&self, path: &Path) -> Option<PathBuf> {
+        let path = path.to_str()?;
+        let letter = path.chars().next()?;
+        let remainder = path.get(1..)?.strip_prefix(':')?;
+
+        if !remainder.starts_with(['\\', '/']) {
+            return None;
+        }
+
+        let host_path = self.drives.get(&letter.to_ascii_lowercase())?;
+
+        Some(
+            remainder
+                .split(['\\', '/'])
+                .filter(|c| !c.is_empty())
+                .fold(host_path.clone(), |path, component| path.join(component)),
+        )
+    }
+}
+
+/// Parse a dosdevices entry name like `c:` into its drive letter.
+fn parse_drive_name(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(letter), Some(':'), None) if letter.is_ascii_alphabetic() => Some(letter),
+        _ => None,
+    }
+}
+
+/// Find the Wine prefixes that the given game is installed under, looking in
+/// the default Steam (including Flatpak Steam) and Heroic locations relative to
+/// the given home directory.
+///
+/// Steam installs are listed before Heroic installs. OpenMW runs natively and
+/// so never has any Wine installs.
+pub fn find_wine_installs(game_type: GameType, home_path: &Path) -> Vec<WineInstall> {
+    let mut installs = Vec::new();
+
+    // Steam roots and libraries are often symlinks to one another (e.g.
+    // ~/.steam/steam usually links to ~/.local/share/Steam), so compare their
+    // canonical paths to avoid finding the same installs more than once.
+    let mut seen_library_paths = Vec::new();
+    for steam_root in STEAM_ROOTS {
+        for library_path in steam_library_paths(&home_path.join(steam_root)) {
+            let canonical_path = library_path
+                .canonicalize()
+                .ok()
+                .unwrap_or_else(|| library_path.clone());
+            if seen_library_paths.contains(&canonical_path) {
+                continue;
+            }
+            seen_library_paths.push(canonical_path);
+
+            installs.extend(find_steam_installs(game_type, &library_path));
+        }
+    }
+
+    for heroic_root in HEROIC_ROOTS {
+        installs.extend(find_heroic_installs(
+            game_type,
+            &home_path.join(heroic_root),
+        ));
+    }
+
+    installs
+}
+
+fn steam_app_ids(game_type: GameType) -> &'static [u32] {
+    match game_type {
+        GameType::Morrowind => &[22320],
+        GameType::Oblivion => &[22330],
+        GameType::Skyrim => &[72850],
+        GameType::SkyrimSE => &[489_830],
+        GameType::SkyrimVR => &[611_670],
+        GameType::Fallout3 => &[22300, 22370],
+        GameType::FalloutNV => &[22380],
+        GameType::Fallout4 => &[377_160],
+        GameType::Fallout4VR => &[611_660],
+        GameType::Starfield => &[1_716_740],
+        GameType::OpenMW => &[],
+    }
+}
+
+/// The names of the directories that the game's files are installed in by
+/// Steam and Heroic respectively.
+fn install_folder_names(game_type: GameType) -> &'static [&'static str] {
+    match game_type {
+        GameType::Morrowind => &["Morrowind", "The Elder Scrolls III Morrowind"],
+        GameType::Oblivion => &["Oblivion", "The Elder Scrolls IV Oblivion"],
+        GameType::Skyrim => &["Skyrim"],
+        GameType::SkyrimSE => &[
+            "Skyrim Special Edition",
+            "The Elder Scrolls V Skyrim Special Edition",
+        ],
+        GameType::SkyrimVR => &["SkyrimVR"],
+        GameType::Fallout3 => &["Fallout 3", "Fallout 3 goty", "Fallout 3 GOTY"],
+        GameType::FalloutNV => &["Fallout New Vegas"],
+        GameType::Fallout4 => &["Fallout 4"],
+        GameType::Fallout4VR => &["Fallout 4 VR"],
+        GameType::Starfield => &["Starfield"],
+        GameType::OpenMW => &[],
+    }
+}
+
+fn data_folder_name(game_type: GameType) -> &'static str {
+    match game_type {
+        GameType::Morrowind => "Data Files",
+        _ => "Data",
+    }
+}
+
+/// The names of the game's folders in the user's local application data and
+/// in "My Games", which differ between stores for some games.
+fn game_folder_names(game_type: GameType, store: Store) -> Option<(&'static str, &'static str)> {
+    match (game_type, store) {
+        (GameType::Morrowind | GameType::OpenMW, _) => None,
+        (GameType::SkyrimSE, Store::Gog) => {
+            Some(("Skyrim Special Edition GOG", "Skyrim Special Edition GOG"))
+        }
+        (GameType::SkyrimSE, Store::Epic) => {
+            Some(("Skyrim Special Edition EPIC", "Skyrim Special Edition EPIC"))
+        }
+        (GameType::FalloutNV, Store::Epic) => Some(("FalloutNV_Epic", "FalloutNV_Epic")),
+        (GameType::Oblivion, _) => Some(("Oblivion", "Oblivion")),
+        (GameType::Skyrim, _) => Some(("Skyrim", "Skyrim")),
+        (GameType::SkyrimSE, _) => Some(("Skyrim Special Edition", "Skyrim Special Edition")),
+        (GameType::SkyrimVR, _) => Some(("Skyrim VR", "Skyrim VR")),
+        (GameType::Fallout3, _) => Some(("Fallout3", "Fallout3")),
+        (GameType::FalloutNV, _) => Some(("FalloutNV", "FalloutNV")),
+        (GameType::Fallout4, _) => Some(("Fallout4", "Fallout4")),
+        (GameType::Fallout4VR, _) => Some(("Fallout4VR", "Fallout4VR")),
+        (GameType::Starfield, _) => Some(("Starfield", "Starfield")),
+    }
+}
+
+/// Get the Steam library directories, which are the Steam root directory and
+/// any listed in its libraryfolders.vdf.
+fn steam_library_paths(steam_root: &Path) -> Vec<PathBuf> {
+    if !steam_root.is_dir() {
+        return Vec::new();
+    }
+
+    let mut paths = vec![steam_root.to_path_buf()];
+
+    let vdf_path = steam_root.join("steamapps").join("libraryfolders.vdf");
+    if let Ok(content) = read_to_string(vdf_path) {
+        for line in content.lines() {
+            if let Some(path) = parse_vdf_path_line(line) {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+    }
+
+    paths
+}
+
+/// Parse a line of the form `"path" "/some/path"`, ignoring whitespace.
+fn parse_vdf_path_line(line: &str) -> Option<PathBuf> {
+    let value = line.trim().strip_prefix("\"path\"")?.trim();
+
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .map(|v| PathBuf::from(v.replace("\\\\", "\\")))
+}
+
+fn find_steam_installs(game_type: GameType, library_path: &Path) -> Vec<WineInstall> {
+    let steamapps_path = library_path.join("steamapps");
+
+    steam_app_ids(game_type)
+        .iter()
+        .map(|app_id| {
+            steamapps_path
+                .join("compatdata")
+                .join(app_id.to_string())
+                .join("pfx")
+        })
+        .filter(|prefix_path| prefix_path.is_dir())
+        .map(|prefix_path| WineInstall {
+            game_type,
+            launcher: WineLauncher::Steam,
+            prefix_path,
+            install_path: find_install_path(game_type, &steamapps_path.join("common")),
+        })
+        .collect()
+}
+
+fn find_heroic_installs(game_type: GameType, heroic_root: &Path) -> Vec<WineInstall> {
+    let prefixes_path = heroic_root.join("Prefixes").join("default");
+
+    install_folder_names(game_type)
+        .iter()
+        .map(|folder| prefixes_path.join(folder))
+        .filter(|prefix_path| prefix_path.join("drive_c").is_dir())
+        .map(|prefix_path| WineInstall {
+            game_type,
+            launcher: WineLauncher::Heroic,
+            prefix_path,
+            install_path: find_install_path(game_type, heroic_root),
+        })
+        .collect()
+}
+
+fn find_install_path(game_type: GameType, parent_path: &Path) -> Option<PathBuf> {
+    install_folder_names(game_type)
+        .iter()
+        .map(|folder| parent_path.join(folder))
+        .find(|path| path.join(data_folder_name(game_type)).is_dir())
+}
+
+/// Heroic prefixes use the host's user name, so use the first user directory
+/// that isn't shared by all users.
+fn find_user_name(users_path: &Path) -> Option<String> {
+    let mut names: Vec<String> = read_dir(users_path)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|n| !n.eq_ignore_ascii_case("Public"))
+        .collect();
+
+    names.sort();
+    names.into_iter().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, write};
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn create_steam_game(library_path: &Path, app_id: u32, folder: &str) {
+        let steamapps_path = library_path.join("steamapps");
+        create_dir_all(
+            steamapps_path
+                .join("compatdata")
+                .join(app_id.to_string())
+                .join("pfx/drive_c/users/steamuser"),
+        )
+        .unwrap();
+        create_dir_all(steamapps_path.join("common").join(folder).join("Data")).unwrap();
+    }
+
+    #[test]
+    fn parse_vdf_path_line_should_return_the_quoted_path_value() {
+        assert_eq!(
+            Some(PathBuf::from("/mnt/games/SteamLibrary")),
+            parse_vdf_path_line("\t\t\"path\"\t\t\"/mnt/games/SteamLibrary\"")
+        );
+    }
+
+    #[test]
+    fn parse_vdf_path_line_should_return_none_for_other_keys() {
+        assert!(parse_vdf_path_line("\t\t\"label\"\t\t\"\"").is_none());
+        assert!(parse_vdf_path_line("\t\t\"contentid\"\t\t\"123\"").is_none());
+    }
+
+    #[test]
+    fn find_wine_installs_should_find_a_proton_prefix_in_the_default_steam_library() {
+        let tmp_dir = tempdir().unwrap();
+        let library_path = tmp_dir.path().join(".local/share/Steam");
+        create_steam_game(&library_path, 489_830, "Skyrim Special Edition");
+
+        let installs = find_wine_installs(GameType::SkyrimSE, tmp_dir.path());
+
+        assert_eq!(1, installs.len());
+        let install = &installs[0];
+        assert_eq!(WineLauncher::Steam, install.launcher());
+        assert_eq!(
+            library_path.join("steamapps/compatdata/489830/pfx"),
+            install.prefix_path()
+        );
+        assert_eq!(
+            Some(library_path.join("steamapps/common/Skyrim Special Edition/Data")),
+            install.data_path()
+        );
+        assert_eq!(
+            Some(library_path.join(
+                "steamapps/compatdata/489830/pfx/drive_c/users/steamuser/AppData/Local/Skyrim Special Edition"
+            )),
+            install.local_app_data_path()
+        );
+        assert_eq!(
+            Some(library_path.join(
+                "steamapps/compatdata/489830/pfx/drive_c/users/steamuser/Documents/My Games/Skyrim Special Edition"
+            )),
+            install.my_games_path()
+        );
+    }
+
+    #[test]
+    fn find_wine_installs_should_find_a_proton_prefix_in_an_additional_steam_library() {
+        let tmp_dir = tempdir().unwrap();
+        let steam_root = tmp_dir.path().join(".local/share/Steam");
+        let library_path = tmp_dir.path().join("SteamLibrary");
+        create_dir_all(steam_root.join("steamapps")).unwrap();
+        write(
+            steam_root.join("steamapps/libraryfolders.vdf"),
+            format!(
+                "\"libraryfolders\"\n{{\n\t\"1\"\n\t{{\n\t\t\"path\"\t\t\"{}\"\n\t}}\n}}\n",
+                library_path.display()
+            ),
+        )
+        .unwrap();
+        create_steam_game(&library_path, 22330, "Oblivion");
+
+        let installs = find_wine_installs(GameType::Oblivion, tmp_dir.path());
+
+        assert_eq!(1, installs.len());
+        assert_eq!(
+            Some(library_path.join("steamapps/common/Oblivion/Data")),
+            installs[0].data_path()
+        );
+    }
+
+    #[test]
+    fn find_wine_installs_should_use_data_files_as_the_morrowind_data_path() {
+        let tmp_dir = tempdir().unwrap();
+        let library_path = tmp_dir.path().join(".steam/steam");
+        let steamapps_path = library_path.join("steamapps");
+        create_dir_all(steamapps_path.join("compatdata/22320/pfx")).unwrap();
+        create_dir_all(steamapps_path.join("common/Morrowind/Data Files")).unwrap();
+
+        let installs = find_wine_installs(GameType::Morrowind, tmp_dir.path());
+
+        assert_eq!(1, installs.len());
+        assert_eq!(
+            Some(steamapps_path.join("common/Morrowind/Data Files")),
+            installs[0].data_path()
+        );
+        assert!(installs[0].local_app_data_path().is_none());
+        assert!(installs[0].my_games_path().is_none());
+    }
+
+    #[test]
+    fn find_wine_installs_should_find_a_heroic_prefix_and_its_user_directory() {
+        let tmp_dir = tempdir().unwrap();
+        let heroic_root = tmp_dir.path().join("Games/Heroic");
+        let prefix_path = heroic_root.join("Prefixes/default/Fallout New Vegas");
+        create_dir_all(prefix_path.join("drive_c/users/Public")).unwrap();
+        create_dir_all(prefix_path.join("drive_c/users/deck")).unwrap();
+        create_dir_all(heroic_root.join("Fallout New Vegas/Data")).unwrap();
+
+        let installs = find_wine_installs(GameType::FalloutNV, tmp_dir.path());
+
+        assert_eq!(1, installs.len());
+        let install = &installs[0];
+        assert_eq!(WineLauncher::Heroic, install.launcher());
+        assert_eq!(
+            Some(heroic_root.join("Fallout New Vegas/Data")),
+            install.data_path()
+        );
+        assert_eq!(
+            Some(prefix_path.join("drive_c/users/deck/AppData/Local/FalloutNV")),
+            install.local_app_data_path()
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn find_wine_installs_should_not_find_the_same_install_through_a_symlinked_steam_root() {
+        let tmp_dir = tempdir().unwrap();
+        let library_path = tmp_dir.path().join(".local/share/Steam");
+        create_steam_game(&library_path, 377_160, "Fallout 4");
+        create_dir_all(tmp_dir.path().join(".steam")).unwrap();
+        std::os::unix::fs::symlink(&library_path, tmp_dir.path().join(".steam/steam")).unwrap();
+
+        let installs = find_wine_installs(GameType::Fallout4, tmp_dir.path());
+
+        assert_eq!(1, installs.len());
+        assert_eq!(
+            library_path.join("steamapps/compatdata/377160/pfx"),
+            installs[0].prefix_path()
+        );
+    }
+
+    #[test]
+    fn find_wine_installs_should_use_gog_folder_names_for_a_heroic_gog_install() {
+        let tmp_dir = tempdir().unwrap();
+        let heroic_root = tmp_dir.path().join("Games/Heroic");
+        let prefix_path = heroic_root.join("Prefixes/default/Skyrim Special Edition");
+        let install_path = heroic_root.join("Skyrim Special Edition");
+        create_dir_all(prefix_path.join("drive_c/users/deck")).unwrap();
+        create_dir_all(install_path.join("Data")).unwrap();
+        write(install_path.join("Galaxy64.dll"), "").unwrap();
+
+        let installs = find_wine_installs(GameType::SkyrimSE, tmp_dir.path());
+
+        assert_eq!(1, installs.len());
+        assert_eq!(
+            Some(prefix_path.join("drive_c/users/deck/AppData/Local/Skyrim Special Edition GOG")),
+            installs[0].local_app_data_path()
+        );
+        assert_eq!(
+            Some(
+                prefix_path
+                    .join("drive_c/users/deck/Documents/My Games/Skyrim Special Edition GOG")
+            ),
+            installs[0].my_games_path()
+        );
+    }
+
+    #[test]
+    fn find_wine_installs_should_use_epic_folder_names_for_a_heroic_epic_install() {
+        let tmp_dir = tempdir().unwrap();
+        let heroic_root = tmp_dir.path().join("Games/Heroic");
+        let prefix_path = heroic_root.join("Prefixes/default/Fallout New Vegas");
+        let install_path = heroic_root.join("Fallout New Vegas");
+        create_dir_all(prefix_path.join("drive_c/users/deck")).unwrap();
+        create_dir_all(install_path.join("Data")).unwrap();
+        write(install_path.join("EOSSDK-Win32-Shipping.dll"), "").unwrap();
+
+        let installs = find_wine_installs(GameType::FalloutNV, tmp_dir.path());
+
+        assert_eq!(1, installs.len());
+        assert_eq!(
+            Some(prefix_path.join("drive_c/users/deck/AppData/Local/FalloutNV_Epic")),
+            installs[0].local_app_data_path()
+        );
+        assert_eq!(
+            Some(prefix_path.join("drive_c/users/deck/Documents/My Games/FalloutNV_Epic")),
+            installs[0].my_games_path()
+        );
+    }
+
+    #[test]
+    fn find_wine_installs_should_find_nothing_for_openmw() {
+        let tmp_dir = tempdir().unwrap();
+        let library_path = tmp_dir.path().join(".local/share/Steam");
+        create_steam_game(&library_path, 22320, "Morrowind");
+
+        assert!(find_wine_installs(GameType::OpenMW, tmp_dir.path()).is_empty());
+    }
+
+    #[test]
+    fn wine_path_mapper_translate_should_map_drive_letters_case_insensitively() {
+        let mapper = WinePathMapper::new().with_drive('Z', PathBuf::from("/"));
+
+        assert_eq!(
+            Some(PathBuf::from("/home/deck/tool.exe")),
+            mapper.translate(Path::new("z:\\home\\deck\\tool.exe"))
+        );
+        assert_eq!(
+            Some(PathBuf::from("/home/deck/tool.exe")),
+            mapper.translate(Path::new("Z:/home/deck/tool.exe"))
+        );
+    }
+
+    #[test]
+    fn wine_path_mapper_translate_should_return_none_for_unmapped_drives_and_relative_paths() {
+        let mapper = WinePathMapper::new().with_drive('c', PathBuf::from("/prefix/drive_c"));
+
+        assert!(mapper.translate(Path::new("D:\\Games\\tool.exe")).is_none());
+        assert!(mapper.translate(Path::new("..\\TESV.exe")).is_none());
+        assert!(mapper.translate(Path::new("c:relative.exe")).is_none());
+    }
+
+    #[test]
+    fn wine_path_mapper_from_prefix_should_use_wine_defaults_if_there_are_no_dosdevices() {
+        let mapper = WinePathMapper::from_prefix(Path::new("/prefix"));
+
+        assert_eq!(
+            Some(PathBuf::from("/prefix/drive_c/Program Files/tool.exe")),
+            mapper.translate(Path::new("C:\\Program Files\\tool.exe"))
+        );
+        assert_eq!(
+            Some(PathBuf::from("/tmp/tool.exe")),
+            mapper.translate(Path::new("Z:\\tmp\\tool.exe"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn wine_path_mapper_from_prefix_should_read_drive_symlinks_from_dosdevices() {
+        let tmp_dir = tempdir().unwrap();
+        let prefix_path = tmp_dir.path();
+        create_dir_all(prefix_path.join("dosdevices")).unwrap();
+        create_dir_all(prefix_path.join("games")).unwrap();
+        std::os::unix::fs::symlink("../games", prefix_path.join("dosdevices/d:")).unwrap();
+
+        let mapper = WinePathMapper::from_prefix(prefix_path);
+
+        assert_eq!(
+            Some(prefix_path.join("dosdevices/../games/tool.exe")),
+            mapper.translate(Path::new("D:\\tool.exe"))
+        );
+        assert!(mapper.translate(Path::new("C:\\tool.exe")).is_none());
+    }
+
+    #[test]
+    fn find_wine_installs_should_find_nothing_if_the_game_has_no_prefix() {
+        let tmp_dir = tempdir().unwrap();
+        let library_path = tmp_dir.path().join(".local/share/Steam");
+        create_dir_all(library_path.join("steamapps/common/Fallout 4/Data")).unwrap();
+
+        assert!(find_wine_installs(GameType::Fallout4, tmp_dir.path()).is_empty());
+    }
+}